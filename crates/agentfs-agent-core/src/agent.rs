@@ -0,0 +1,1020 @@
+use std::io::IsTerminal;
+use std::sync::Arc;
+
+use crossterm::event::KeyCode;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+use agentfs_core::analytics::TokenRecord;
+
+use crate::api::{self, LlmClient, Message};
+use crate::auth::AuthProvider;
+use crate::checkpoints::{self, Applied, Recorder};
+use crate::config::TurnLimits;
+use crate::display;
+use crate::error::{AgentError, Result};
+use crate::executor::ToolExecutor;
+use crate::memory::MemoryManager;
+use crate::output;
+use crate::permissions;
+use crate::skills::{self, Skill};
+use crate::streaming::{ContentAccumulator, StreamEvent};
+use crate::tools;
+
+/// KV key prefix for persisted conversation messages.
+pub(crate) const MESSAGES_KEY_PREFIX: &str = "session:messages:";
+
+/// `agent_name` recorded for interactive sessions.
+pub const AGENT_NAME: &str = "infinity-agent";
+
+/// `agent_name` recorded for subagent sessions started by the `task` tool.
+/// Their metadata names the parent session.
+pub const TASK_AGENT_NAME: &str = "infinity-agent:task";
+
+const TASK_SYSTEM_PROMPT: &str = "You are a subagent of Infinity Agent, an AI coding assistant. \
+    You were given one task by the main agent; complete it using your tools, then reply with a \
+    concise final report. The report is the only thing the main agent will see, so include the \
+    concrete findings it needs (paths, line numbers, names, URLs) and nothing else.\n\n\
+    The workspace is AgentFS, a persistent virtual filesystem stored in a database: paths like \
+    /src/main.rs exist only there. You can read the workspace and the web but cannot write \
+    files or run commands.";
+
+/// Work done by a turn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TurnStats {
+    pub steps: u32,
+    pub tool_calls: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_microcents: i64,
+}
+
+/// The agentic loop: prompt -> API -> stream -> tool_use -> execute -> loop.
+pub struct Agent {
+    client: LlmClient,
+    executor: ToolExecutor,
+    messages: Vec<Message>,
+    tool_defs: Vec<Value>,
+    system: Option<String>,
+    session_id: String,
+    model: String,
+    total_input_tokens: u64,
+    total_output_tokens: u64,
+    /// Context size at the last model request (its input and reply), or 0
+    /// if the conversation changed without one since.
+    context_tokens: u64,
+    memory: Option<Arc<MemoryManager>>,
+    limits: TurnLimits,
+    /// Whether to ask before going past a limit, rather than stopping.
+    interactive: bool,
+    /// Emit `stream-json` events on stdout as the turn runs.
+    stream_json: bool,
+    /// Where `stream-json` events go instead of stdout.
+    events: Option<UnboundedSender<Value>>,
+    /// What the most recent turn did, so far if it failed.
+    last_turn: TurnStats,
+    /// Workspace files changed by the current turn, for `/undo`.
+    recorder: Option<Recorder>,
+}
+
+impl Agent {
+    pub fn new(
+        client: LlmClient,
+        executor: ToolExecutor,
+        system: Option<String>,
+        session_id: String,
+        model: String,
+        extra_tools: Vec<Value>,
+    ) -> Self {
+        let mut tool_defs = tools::merge_tools(tools::tool_definitions(), extra_tools);
+        // Tools outside the run's scope are never offered.
+        tool_defs.retain(|t| t["name"].as_str().is_some_and(|n| executor.permissions.in_scope(n)));
+        Self {
+            client,
+            executor,
+            messages: Vec::new(),
+            tool_defs,
+            system,
+            session_id,
+            model,
+            total_input_tokens: 0,
+            total_output_tokens: 0,
+            context_tokens: 0,
+            memory: None,
+            limits: TurnLimits::default(),
+            interactive: std::io::stdin().is_terminal(),
+            stream_json: false,
+            events: None,
+            last_turn: TurnStats::default(),
+            recorder: None,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: TurnLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Stop at turn limits instead of asking to continue.
+    pub fn non_interactive(mut self) -> Self {
+        self.interactive = false;
+        self
+    }
+
+    /// Emit text deltas, tool calls and tool results as `stream-json` events.
+    pub fn with_stream_json(mut self) -> Self {
+        self.stream_json = true;
+        self
+    }
+
+    /// Send `stream-json` events to `events` instead of stdout, e.g. to
+    /// relay turns to a browser.
+    pub fn with_events(mut self, events: UnboundedSender<Value>) -> Self {
+        self.stream_json = true;
+        self.events = Some(events);
+        self
+    }
+
+    /// Emit a `stream-json` event, if enabled.
+    fn emit(&self, event: Value) {
+        if let Some(events) = &self.events {
+            let _ = events.send(event);
+        } else if self.stream_json {
+            output::emit(&event);
+        }
+    }
+
+    /// Replace the system prompt for the following turns.
+    pub fn set_system(&mut self, system: String) {
+        self.system = Some(system);
+    }
+
+    /// Attach a memory manager to this agent.
+    pub fn with_memory(mut self, memory: Arc<MemoryManager>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Load persisted messages from a previous session.
+    pub async fn load_messages(&mut self) -> Result<usize> {
+        let key = format!("{MESSAGES_KEY_PREFIX}{}", self.session_id);
+        match self.executor.db.kv.get(&key).await {
+            Ok(entry) => {
+                let msgs: Vec<Message> = serde_json::from_str(&entry.value)
+                    .map_err(|e| AgentError::Other(format!("Failed to parse saved messages: {e}")))?;
+                let count = msgs.len();
+                self.messages = msgs;
+                Ok(count)
+            }
+            Err(agentfs_core::error::AgentFSError::KeyNotFound { .. }) => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Persist current messages to KV store. A dry run keeps them in memory.
+    async fn save_messages(&self) {
+        if self.executor.is_dry_run() {
+            return;
+        }
+        let key = format!("{MESSAGES_KEY_PREFIX}{}", self.session_id);
+        if let Ok(json) = serde_json::to_string(&self.messages) {
+            let _ = self.executor.db.kv.set(&key, &json).await;
+        }
+    }
+
+    /// Build the effective system prompt with memory context injected.
+    async fn effective_system_prompt(&self, user_input: &str) -> Option<String> {
+        let base = self.system.as_deref()?;
+
+        if let Some(memory) = &self.memory {
+            let memory_ctx = memory.context_for_prompt(user_input).await;
+            if memory_ctx.is_empty() {
+                Some(base.to_string())
+            } else {
+                Some(format!("{base}{memory_ctx}"))
+            }
+        } else {
+            Some(base.to_string())
+        }
+    }
+
+    /// Run a single turn: user message -> (possibly multiple) API calls until end_turn.
+    pub async fn run_turn(&mut self, auth: &mut AuthProvider, user_input: &str) -> Result<String> {
+        self.run_turn_with_images(auth, user_input, Vec::new()).await
+    }
+
+    /// Run a turn whose user message also carries image content blocks,
+    /// placed before the text.
+    pub async fn run_turn_with_images(
+        &mut self,
+        auth: &mut AuthProvider,
+        user_input: &str,
+        images: Vec<Value>,
+    ) -> Result<String> {
+        self.run_turn_inner(auth, user_input, images, None, None).await
+    }
+
+    /// Run a turn in which the model may only use the tools matching
+    /// `allowed` (tool name globs).
+    pub async fn run_turn_with_tools(
+        &mut self,
+        auth: &mut AuthProvider,
+        user_input: &str,
+        allowed: &[String],
+    ) -> Result<String> {
+        self.run_turn_inner(auth, user_input, Vec::new(), Some(allowed), None).await
+    }
+
+    async fn run_turn_inner(
+        &mut self,
+        auth: &mut AuthProvider,
+        user_input: &str,
+        images: Vec<Value>,
+        allowed: Option<&[String]>,
+        reads: Option<String>,
+    ) -> Result<String> {
+        // Held for the whole turn: a cancelled turn's scope is replaced by the next
+        self.executor.set_turn_tools(allowed.map(<[String]>::to_vec), reads);
+        let tool_defs: Vec<Value> = self
+            .tool_defs
+            .iter()
+            .filter(|t| t["name"].as_str().is_some_and(|n| self.executor.turn_allows(n)))
+            .cloned()
+            .collect();
+
+        // A cancelled or failed turn's changes are recorded before this one starts.
+        self.finish_checkpoint().await;
+        self.recorder = Some(Recorder::new(user_input));
+
+        let text = json!({ "type": "text", "text": user_input });
+        let messages_before = self.messages.len();
+        match self.messages.last_mut() {
+            // A turn stopped at a limit ends on tool results; keep roles alternating.
+            Some(Message { role, content: Value::Array(blocks) }) if role == "user" => {
+                blocks.extend(images);
+                blocks.push(text);
+            }
+            _ if !images.is_empty() => self.messages.push(Message {
+                role: "user".to_string(),
+                content: Value::Array(images.into_iter().chain([text]).collect()),
+            }),
+            _ => self.messages.push(Message {
+                role: "user".to_string(),
+                content: Value::String(user_input.to_string()),
+            }),
+        }
+
+        let mut full_response = String::new();
+        let mut all_tool_results: Vec<Value> = Vec::new();
+
+        // Get effective system prompt with memory context
+        let effective_system = self.effective_system_prompt(user_input).await;
+
+        // Work done this turn, and how much of it was already approved past a limit.
+        let turn_start = (self.total_input_tokens, self.total_output_tokens);
+        let mut step: u32 = 0;
+        let mut tool_calls: u32 = 0;
+        let mut approved = (0u32, 0u32, 0i64);
+        // Restarts of the current step after its response stream failed.
+        let mut stream_retries: u32 = 0;
+        self.last_turn = TurnStats::default();
+        loop {
+            step += 1;
+
+            // Ask before sending a request whose context alone is expensive.
+            let call_tokens =
+                estimate_request_tokens(&self.messages, &tool_defs, effective_system.as_deref());
+            let call_cost = estimate_cost(&self.model, call_tokens, 0);
+            if self.limits.call_needs_confirmation(call_cost)
+                && !self.confirm_call(call_tokens, call_cost).await?
+            {
+                let details = json!({
+                    "model": self.model,
+                    "step": step,
+                    "input_tokens": call_tokens,
+                    "cost_microcents": call_cost,
+                });
+                let _ = self
+                    .executor
+                    .db
+                    .events
+                    .log(Some(&self.session_id), "api_call_declined", None, Some(&details.to_string()))
+                    .await;
+                if step == 1 {
+                    // Nothing was sent; the message isn't part of the conversation.
+                    self.messages.truncate(messages_before);
+                }
+                if !self.interactive {
+                    return Err(AgentError::Other(format!(
+                        "request (~{}) not sent: it is over --confirm-call-cost and there is no one to confirm it",
+                        display::format_cost(call_cost)
+                    )));
+                }
+                display::print_call_declined(call_cost);
+                break;
+            }
+
+            // Show thinking spinner (context-aware: different messages after tool execution)
+            let spinner = if step == 1 {
+                display::Spinner::thinking()
+            } else {
+                display::print_agentic_continue(step);
+                display::Spinner::thinking_follow_up()
+            };
+
+            // Start streaming — returns a channel
+            let rx_result = self
+                .client
+                .stream_message(
+                    auth,
+                    &self.messages,
+                    &tool_defs,
+                    effective_system.as_deref(),
+                )
+                .await;
+
+            // If the request itself failed, stop spinner and return error
+            let mut rx = match rx_result {
+                Ok(rx) => rx,
+                Err(e) => {
+                    spinner.stop().await;
+                    if api::provider_failed(&e) && self.fall_back(&e.to_string()).await {
+                        step -= 1;
+                        continue;
+                    }
+                    return Err(e);
+                }
+            };
+
+            // Process events one-by-one from the channel
+            let mut accumulator = ContentAccumulator::new();
+            let mut renderer = display::StreamRenderer::new();
+            let mut input_tokens = 0u64;
+            let mut output_tokens = 0u64;
+            let mut stop_reason = String::from("end_turn");
+            let mut spinner_active = true;
+            let mut spinner = Some(spinner);
+            let mut tool_prep_spinner: Option<display::Spinner> = None;
+            // Why the response broke off, and whether to request it again.
+            let mut failed: Option<(String, bool)> = None;
+
+            while let Some(event) = rx.recv().await {
+                // Stop thinking spinner on first content event
+                if spinner_active {
+                    match &event {
+                        StreamEvent::TextDelta { .. }
+                        | StreamEvent::ContentBlockStart { .. }
+                        | StreamEvent::InputJsonDelta { .. } => {
+                            if let Some(s) = spinner.take() {
+                                s.stop().await;
+                            }
+                            spinner_active = false;
+                        }
+                        _ => {}
+                    }
+                }
+
+                // Render live — rich rendering for text, standard for other events
+                if let StreamEvent::TextDelta { text, .. } = &event {
+                    self.emit(json!({ "type": "text_delta", "text": text }));
+                    renderer.push(text);
+                } else {
+                    display::print_stream_event(&event);
+                }
+
+                // Show "preparing actions" spinner when tool calls start generating
+                // (this fills the dead zone between text output and tool execution)
+                if let StreamEvent::ContentBlockStart {
+                    block_type: crate::streaming::ContentBlockType::ToolUse { .. },
+                    ..
+                } = &event
+                {
+                    if tool_prep_spinner.is_none() {
+                        renderer.finish();
+                        tool_prep_spinner =
+                            Some(display::Spinner::start("Preparing actions"));
+                    }
+                }
+
+                // Accumulate
+                accumulator.process(&event);
+
+                match &event {
+                    StreamEvent::MessageStart {
+                        input_tokens: it, ..
+                    } => {
+                        input_tokens = *it;
+                    }
+                    StreamEvent::MessageDelta {
+                        stop_reason: sr,
+                        output_tokens: ot,
+                    } => {
+                        stop_reason = sr.clone();
+                        output_tokens = *ot;
+                    }
+                    StreamEvent::Error { message, retryable } => {
+                        if let Some(s) = spinner.take() {
+                            s.stop().await;
+                        }
+                        if let Some(s) = tool_prep_spinner.take() {
+                            s.stop().await;
+                        }
+                        renderer.finish();
+                        // A retry would show its output again after what's
+                        // already on screen, so only retry while the thinking
+                        // spinner still stands in for the response.
+                        failed = Some((message.clone(), *retryable && spinner_active));
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+
+            // Finish stream rendering and stop all spinners
+            renderer.finish();
+            if let Some(s) = tool_prep_spinner {
+                s.stop().await;
+            }
+            if let Some(s) = spinner.take() {
+                s.stop().await;
+            }
+
+            // Track tokens, also of a response that broke off: what arrived
+            // was still billed.
+            self.total_input_tokens += input_tokens;
+            self.total_output_tokens += output_tokens;
+            let turn_input = self.total_input_tokens - turn_start.0;
+            let turn_output = self.total_output_tokens - turn_start.1;
+            self.last_turn = TurnStats {
+                steps: step,
+                tool_calls,
+                input_tokens: turn_input,
+                output_tokens: turn_output,
+                cost_microcents: estimate_cost(&self.model, turn_input, turn_output),
+            };
+
+            // Record token usage
+            let _ = self
+                .executor
+                .db
+                .analytics
+                .record_usage(TokenRecord {
+                    id: None,
+                    session_id: Some(self.session_id.clone()),
+                    tool_call_id: None,
+                    model: self.model.clone(),
+                    input_tokens: input_tokens as i64,
+                    output_tokens: output_tokens as i64,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
+                    cost_microcents: estimate_cost(&self.model, input_tokens, output_tokens),
+                    recorded_at: None,
+                    purpose: None,
+                })
+                .await;
+
+            // The response broke off (overload, dropped connection): if none
+            // of it was shown, discard what arrived and request this step again.
+            if let Some((reason, retry)) = failed {
+                if !retry {
+                    return Err(AgentError::Stream(reason));
+                }
+                if stream_retries >= api::MAX_RETRIES {
+                    if !self.fall_back(&reason).await {
+                        return Err(AgentError::Stream(reason));
+                    }
+                    stream_retries = 0;
+                    step -= 1;
+                    continue;
+                }
+                let delay = api::backoff_delay(stream_retries);
+                stream_retries += 1;
+                display::print_retry(&reason, delay, stream_retries, api::MAX_RETRIES);
+                tokio::time::sleep(delay).await;
+                step -= 1;
+                continue;
+            }
+            stream_retries = 0;
+            self.context_tokens = input_tokens + output_tokens;
+
+            // Build assistant message content
+            let text = accumulator.full_text();
+            full_response.push_str(&text);
+
+            let mut content_blocks: Vec<Value> = Vec::new();
+
+            if !text.is_empty() {
+                content_blocks.push(json!({
+                    "type": "text",
+                    "text": text,
+                }));
+            }
+
+            for tool in &accumulator.tool_use_blocks {
+                content_blocks.push(json!({
+                    "type": "tool_use",
+                    "id": tool.id,
+                    "name": tool.name,
+                    "input": tool.input,
+                }));
+            }
+
+            self.messages.push(Message {
+                role: "assistant".to_string(),
+                content: Value::Array(content_blocks),
+            });
+
+            // If tool_use, execute tools and loop
+            if stop_reason == "tool_use" && !accumulator.tool_use_blocks.is_empty() {
+                let mut tool_results: Vec<Value> = Vec::new();
+
+                let tool_count = accumulator.tool_use_blocks.len();
+                tool_calls += tool_count as u32;
+                self.last_turn.tool_calls = tool_calls;
+                for (tool_idx, tool) in accumulator.tool_use_blocks.iter().enumerate() {
+                    display::print_tool_call(tool, tool_idx, tool_count);
+                    self.emit(json!({
+                        "type": "tool_use",
+                        "id": tool.id,
+                        "name": tool.name,
+                        "input": tool.input,
+                    }));
+
+                    let tool_start = std::time::Instant::now();
+                    let result = if !self.executor.turn_permits(&tool.name, &tool.input) {
+                        Err(AgentError::PermissionDenied(format!(
+                            "{} is not available in this turn",
+                            tool.name
+                        )))
+                    } else if let Err(e) = self.executor.authorize(&tool.name, &tool.input).await {
+                        Err(e)
+                    } else if tool.name == tools::TASK_TOOL {
+                        self.run_task(auth, &tool.input).await
+                    } else {
+                        if let Some(recorder) = &mut self.recorder {
+                            if let Err(e) = recorder.before_tool(&self.executor.db, &tool.name, &tool.input).await {
+                                tracing::warn!("Failed to record checkpoint: {e}");
+                            }
+                        }
+                        let tool_spinner = display::tool_spinner(&tool.name, &tool.input);
+                        let result = self.executor.execute(&tool.name, &tool.input).await;
+                        tool_spinner.stop().await;
+                        result
+                    };
+                    let tool_elapsed = tool_start.elapsed();
+                    let (content, is_error) = match &result {
+                        Ok(output) => (output.clone(), false),
+                        Err(e) => (e.to_string(), true),
+                    };
+                    // Calls that never ran (refused, blocked or dry run) don't reach post hooks.
+                    if !matches!(result, Err(AgentError::PermissionDenied(_))) && !self.executor.is_dry_run() {
+                        self.executor
+                            .hooks
+                            .post_tool_call(&self.session_id, &tool.name, &tool.input, &content, is_error, tool_elapsed)
+                            .await;
+                    }
+                    self.emit(json!({
+                        "type": "tool_result",
+                        "tool_use_id": tool.id,
+                        "name": tool.name,
+                        "content": content,
+                        "is_error": is_error,
+                        "duration_ms": tool_elapsed.as_millis() as u64,
+                    }));
+
+                    match result {
+                        Ok(output) => {
+                            display::print_tool_result(&tool.name, &output, false);
+                            display::print_tool_done(tool_elapsed, false);
+                            tool_results.push(json!({
+                                "type": "tool_result",
+                                "tool_use_id": tool.id,
+                                "content": output,
+                            }));
+                        }
+                        Err(e) => {
+                            let error_msg = e.to_string();
+                            display::print_tool_result(&tool.name, &error_msg, true);
+                            display::print_tool_done(tool_elapsed, true);
+                            tool_results.push(json!({
+                                "type": "tool_result",
+                                "tool_use_id": tool.id,
+                                "content": error_msg,
+                                "is_error": true,
+                            }));
+                        }
+                    }
+                }
+
+                // Collect tool results for reflection
+                all_tool_results.extend(tool_results.iter().cloned());
+
+                self.messages.push(Message {
+                    role: "user".to_string(),
+                    content: Value::Array(tool_results),
+                });
+
+                if !display::quiet() {
+                    println!();
+                }
+
+                let turn_cost = estimate_cost(
+                    &self.model,
+                    self.total_input_tokens - turn_start.0,
+                    self.total_output_tokens - turn_start.1,
+                );
+                let reached = self.limits.exceeded(
+                    step - approved.0,
+                    tool_calls - approved.1,
+                    turn_cost - approved.2,
+                );
+                if let Some(reason) = reached {
+                    if !self.ask_to_continue(&reason).await? {
+                        display::print_turn_stopped(&reason);
+                        break;
+                    }
+                    approved = (step, tool_calls, turn_cost);
+                }
+                continue;
+            }
+
+            // End of turn — show cost and session totals
+            let turn_cost = estimate_cost(&self.model, input_tokens, output_tokens);
+            let session_cost = estimate_cost(
+                &self.model,
+                self.total_input_tokens,
+                self.total_output_tokens,
+            );
+            display::print_token_usage(
+                input_tokens,
+                output_tokens,
+                turn_cost,
+                self.total_input_tokens + self.total_output_tokens,
+                session_cost,
+            );
+            break;
+        }
+
+        // Persist after each turn
+        self.save_messages().await;
+        self.finish_checkpoint().await;
+
+        // Trigger reflection (inline, uses cheap model)
+        if let Some(memory) = &self.memory {
+            let memory = Arc::clone(memory);
+            let messages = self.messages.clone();
+            let tool_results = all_tool_results;
+            let session_id = self.session_id.clone();
+            // Run reflection — pass auth for API call
+            memory
+                .reflect(auth, &messages, &tool_results, &session_id)
+                .await;
+        }
+
+        Ok(full_response)
+    }
+
+    /// Save the current turn's checkpoint, if it changed any files.
+    async fn finish_checkpoint(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            if let Err(e) = recorder.finish(&self.executor.db, &self.session_id).await {
+                tracing::warn!("Failed to save checkpoint: {e}");
+            }
+        }
+    }
+
+    /// Revert the workspace files changed by the last turn not yet undone.
+    pub async fn undo(&mut self) -> Result<Option<Applied>> {
+        self.finish_checkpoint().await;
+        checkpoints::undo(&self.executor.db, &self.session_id).await
+    }
+
+    /// Reapply the most recently undone turn.
+    pub async fn redo(&mut self) -> Result<Option<Applied>> {
+        self.finish_checkpoint().await;
+        checkpoints::redo(&self.executor.db, &self.session_id).await
+    }
+
+    /// Workspace files changed during this session.
+    pub async fn session_changes(&mut self) -> Result<Vec<checkpoints::FileChange>> {
+        self.finish_checkpoint().await;
+        checkpoints::session_changes(&self.executor.db, &self.session_id).await
+    }
+
+    /// Turns that can be undone and redone.
+    pub async fn checkpoints(&mut self) -> Result<checkpoints::History> {
+        self.finish_checkpoint().await;
+        checkpoints::History::load(&self.executor.db, &self.session_id).await
+    }
+
+    /// Switch to the next provider in the fallback chain after the current
+    /// one failed with `reason`. Returns false at the end of the chain.
+    async fn fall_back(&mut self, reason: &str) -> bool {
+        let from = self.client.provider().name().to_string();
+        let Some(next) = self.client.fall_back() else {
+            return false;
+        };
+        let (to, model) = (next.name().to_string(), next.model().to_string());
+        display::print_provider_fallback(&from, &to, &model, reason);
+        let details = json!({ "from": from, "to": to, "model": model, "reason": reason });
+        let _ = self
+            .executor
+            .db
+            .events
+            .log(Some(&self.session_id), "provider_fallback", None, Some(&details.to_string()))
+            .await;
+        self.model = model;
+        true
+    }
+
+    /// Ask whether to send a request estimated to cost `cost` microcents;
+    /// always no when nobody is watching.
+    async fn confirm_call(&self, tokens: u64, cost: i64) -> Result<bool> {
+        if !self.interactive {
+            return Ok(false);
+        }
+        display::print_call_cost_prompt(&self.model, tokens, cost);
+        let go = permissions::read_key(
+            |code| match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Some(true),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(false),
+                _ => None,
+            },
+            false,
+        )
+        .await?;
+        println!("{}", if go { "yes" } else { "no" });
+        Ok(go)
+    }
+
+    /// Ask whether to go past a turn limit; always no when nobody is watching.
+    async fn ask_to_continue(&self, reason: &str) -> Result<bool> {
+        if !self.interactive {
+            return Ok(false);
+        }
+        display::print_limit_prompt(reason);
+        let go = permissions::read_key(
+            |code| match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Some(true),
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(false),
+                _ => None,
+            },
+            false,
+        )
+        .await?;
+        println!("{}", if go { "yes" } else { "no" });
+        Ok(go)
+    }
+
+    /// Run the `task` tool: a child agent with its own session, the
+    /// read-only [`tools::TASK_TOOLS`] and an empty history works on the
+    /// prompt, and only its final report is returned. Its tokens count
+    /// toward this session's totals.
+    async fn run_task(&mut self, auth: &mut AuthProvider, input: &Value) -> Result<String> {
+        let prompt = input
+            .get("prompt")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| AgentError::Tool("task: missing 'prompt' parameter".to_string()))?;
+        let description = input
+            .get("description")
+            .and_then(|d| d.as_str())
+            .unwrap_or("subtask");
+
+        let db = Arc::clone(&self.executor.db);
+        let tc_id = db
+            .tools
+            .start(Some(&self.session_id), tools::TASK_TOOL, Some(&input.to_string()))
+            .await
+            .ok();
+        let child_id = Uuid::new_v4().to_string();
+        let metadata = json!({
+            "parent_session_id": self.session_id,
+            "description": description,
+        });
+        db.sessions
+            .start(
+                &child_id,
+                Some(TASK_AGENT_NAME),
+                Some(self.client.provider().name()),
+                Some(&metadata.to_string()),
+            )
+            .await?;
+
+        let executor = self.executor.child(child_id.clone(), tools::TASK_TOOLS);
+        let mut child = Agent::new(
+            self.client.clone(),
+            executor,
+            Some(TASK_SYSTEM_PROMPT.to_string()),
+            child_id.clone(),
+            self.model.clone(),
+            Vec::new(),
+        )
+        .with_limits(self.limits)
+        .non_interactive();
+        child
+            .tool_defs
+            .retain(|t| t["name"].as_str().is_some_and(|n| tools::TASK_TOOLS.contains(&n)));
+
+        display::print_subagent(description, false);
+        let result = Box::pin(child.run_turn(auth, prompt)).await;
+        display::print_subagent(description, true);
+
+        let (input_tokens, output_tokens) = child.token_counts();
+        self.total_input_tokens += input_tokens;
+        self.total_output_tokens += output_tokens;
+
+        let status = if result.is_ok() { "completed" } else { "failed" };
+        let _ = db.sessions.end(&child_id, status).await;
+        let report = result.map(|_| child.final_text());
+        if let Some(id) = tc_id {
+            let _ = match &report {
+                Ok(report) => db.tools.success(id, Some(report)).await,
+                Err(e) => db.tools.error(id, &e.to_string()).await,
+            };
+        }
+
+        let report = report?;
+        if report.trim().is_empty() {
+            Ok(format!("(subagent session {child_id} finished without a report)"))
+        } else {
+            Ok(report)
+        }
+    }
+
+    /// Text of the last assistant message.
+    fn final_text(&self) -> String {
+        let Some(last) = self.messages.iter().rev().find(|m| m.role == "assistant") else {
+            return String::new();
+        };
+        last.content
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|b| b["type"] == "text")
+            .filter_map(|b| b["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Run a skill turn: inject skill body + user args as a single user message.
+    /// A skill with `allowed_tools` may only use the tools matching them.
+    /// Files bundled under `/skills/<name>/` are listed for the model and
+    /// stay readable even when `allowed_tools` leaves out `read_file`, but
+    /// then only under that directory.
+    pub async fn run_skill_turn(
+        &mut self,
+        auth: &mut AuthProvider,
+        skill: &Skill,
+        user_args: &str,
+    ) -> Result<String> {
+        let resources = skills::resources(&self.executor.db, &skill.name).await;
+        let prompt = skills::prompt(&skill.name, &skill.body, &resources, "read_file", user_args);
+
+        let allowed = (!skill.allowed_tools.is_empty()).then_some(skill.allowed_tools.as_slice());
+        let reads = (!resources.is_empty()).then(|| skills::resource_dir(&skill.name));
+        self.run_turn_inner(auth, &prompt, Vec::new(), allowed, reads).await
+    }
+
+    /// Clear conversation history.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.context_tokens = 0;
+    }
+
+    /// Get current message count (for rollback on cancel).
+    pub fn message_count(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Rollback messages to a previous count (used on Ctrl+C cancel).
+    pub fn rollback_to(&mut self, count: usize) {
+        self.messages.truncate(count);
+        self.context_tokens = 0;
+    }
+
+    /// What the most recent turn did, so far if it failed.
+    pub fn last_turn(&self) -> TurnStats {
+        self.last_turn
+    }
+
+    /// Model of the provider in use.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn provider_name(&self) -> &str {
+        self.client.provider().name()
+    }
+
+    /// Tokens in the conversation's context, and the model's context window.
+    pub fn context_usage(&self) -> (u64, u64) {
+        let used = if self.context_tokens > 0 {
+            self.context_tokens
+        } else if self.messages.is_empty() {
+            0
+        } else {
+            estimate_request_tokens(&self.messages, &self.tool_defs, self.system.as_deref())
+        };
+        (used, context_window(&self.model))
+    }
+
+    /// Estimated spend so far this session, in microcents.
+    pub fn session_cost(&self) -> i64 {
+        estimate_cost(&self.model, self.total_input_tokens, self.total_output_tokens)
+    }
+
+    /// Names of the tools offered to the model.
+    pub fn tool_names(&self) -> Vec<&str> {
+        self.tool_defs
+            .iter()
+            .filter_map(|t| t.get("name").and_then(|n| n.as_str()))
+            .collect()
+    }
+
+    /// Get total token counts for the session.
+    pub fn token_counts(&self) -> (u64, u64) {
+        (self.total_input_tokens, self.total_output_tokens)
+    }
+
+    /// Get a reference to the tool executor.
+    #[allow(dead_code)]
+    pub fn executor(&self) -> &ToolExecutor {
+        &self.executor
+    }
+
+    /// Consume the agent and return the inner executor (for shutdown).
+    pub fn into_executor(self) -> ToolExecutor {
+        self.executor
+    }
+}
+
+/// Rough input token count of a request, at about four characters a token.
+/// Images count as a typical image's tokens rather than by their data.
+fn estimate_request_tokens(messages: &[Message], tool_defs: &[Value], system: Option<&str>) -> u64 {
+    const IMAGE_TOKENS: u64 = 1600;
+    let mut chars = system.map_or(0, str::len) as u64;
+    chars += tool_defs.iter().map(|t| t.to_string().len() as u64).sum::<u64>();
+    let mut images = 0;
+    for message in messages {
+        match &message.content {
+            Value::String(text) => chars += text.len() as u64,
+            Value::Array(blocks) => {
+                for block in blocks {
+                    if block["type"] == "image" {
+                        images += 1;
+                    } else {
+                        chars += block.to_string().len() as u64;
+                    }
+                }
+            }
+            other => chars += other.to_string().len() as u64,
+        }
+    }
+    chars / 4 + images * IMAGE_TOKENS
+}
+
+/// Context window of `model`, in tokens.
+fn context_window(model: &str) -> u64 {
+    if model.starts_with("claude") {
+        200_000
+    } else {
+        128_000
+    }
+}
+
+/// Microcents in one dollar.
+const MICROCENTS_PER_DOLLAR: i64 = 100_000_000;
+
+/// Rough cost estimation in microcents, from list prices in dollars per
+/// million tokens.
+pub(crate) fn estimate_cost(model: &str, input_tokens: u64, output_tokens: u64) -> i64 {
+    let (input_price, output_price) = if model.contains("opus") {
+        (15, 75)
+    } else if model.contains("haiku") {
+        (1, 5)
+    } else {
+        (3, 15)
+    };
+
+    let cost = |tokens: u64, dollars: i64| {
+        i64::try_from(tokens).unwrap_or(i64::MAX).saturating_mul(dollars * MICROCENTS_PER_DOLLAR) / 1_000_000
+    };
+    cost(input_tokens, input_price).saturating_add(cost(output_tokens, output_price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost_list_prices() {
+        // $3 per million input tokens, $15 per million output tokens.
+        assert_eq!(estimate_cost("claude-sonnet-4-5", 1_000_000, 0), 3 * MICROCENTS_PER_DOLLAR);
+        assert_eq!(estimate_cost("claude-sonnet-4-5", 0, 1_000_000), 15 * MICROCENTS_PER_DOLLAR);
+        assert_eq!(estimate_cost("claude-opus-4-1", 1_000, 1_000), 9_000_000);
+        assert_eq!(estimate_cost("claude-haiku-4-5", 2_000_000, 0), 2 * MICROCENTS_PER_DOLLAR);
+        assert!(estimate_cost("claude-opus-4-1", u64::MAX, u64::MAX) > 0);
+    }
+}
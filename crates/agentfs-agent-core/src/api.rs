@@ -0,0 +1,369 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::auth::AuthProvider;
+use crate::display;
+use crate::error::{AgentError, Result};
+use crate::providers::OpenAiClient;
+use crate::streaming::{self, StreamEvent};
+
+const API_URL: &str = "https://api.anthropic.com/v1/messages";
+const API_VERSION: &str = "2023-06-01";
+
+/// Retries after a rate limit, overload, server error or dropped connection.
+pub const MAX_RETRIES: u32 = 6;
+
+/// First backoff delay; doubled on each retry up to `MAX_DELAY`.
+const BASE_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Longest `retry-after` we wait out; anything longer fails the request.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// A message in the conversation.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Message {
+    pub role: String,
+    pub content: Value,
+}
+
+/// A model API the agent can stream from.
+#[derive(Clone)]
+pub enum Provider {
+    Anthropic(AnthropicClient),
+    /// An OpenAI-compatible chat completions API (OpenRouter, Ollama, ...).
+    OpenAi(OpenAiClient),
+}
+
+impl Provider {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Anthropic(_) => "anthropic",
+            Self::OpenAi(client) => client.name(),
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        match self {
+            Self::Anthropic(client) => &client.model,
+            Self::OpenAi(client) => client.model(),
+        }
+    }
+}
+
+/// The agent's model client: an ordered chain of providers, of which the
+/// current one serves requests until it fails persistently and the agent
+/// falls back to the next.
+#[derive(Clone)]
+pub struct LlmClient {
+    providers: Vec<Provider>,
+    current: usize,
+}
+
+impl LlmClient {
+    pub fn new(primary: Provider) -> Self {
+        Self {
+            providers: vec![primary],
+            current: 0,
+        }
+    }
+
+    /// Use `chain`, in order, instead; an empty chain is ignored.
+    pub fn with_chain(mut self, chain: Vec<Provider>) -> Self {
+        if !chain.is_empty() {
+            self.providers = chain;
+            self.current = 0;
+        }
+        self
+    }
+
+    pub fn provider(&self) -> &Provider {
+        &self.providers[self.current]
+    }
+
+    /// Switch to the next provider in the chain, if there is one.
+    pub fn fall_back(&mut self) -> Option<&Provider> {
+        if self.current + 1 < self.providers.len() {
+            self.current += 1;
+            Some(&self.providers[self.current])
+        } else {
+            None
+        }
+    }
+
+    /// Send a streaming message request to the current provider and return
+    /// a channel of events.
+    pub async fn stream_message(
+        &self,
+        auth: &mut AuthProvider,
+        messages: &[Message],
+        tools: &[Value],
+        system: Option<&str>,
+    ) -> Result<mpsc::Receiver<StreamEvent>> {
+        match self.provider() {
+            Provider::Anthropic(client) => client.stream_message(auth, messages, tools, system).await,
+            Provider::OpenAi(client) => client.stream_message(messages, tools, system).await,
+        }
+    }
+}
+
+/// Whether `e` means the provider is unusable for now (still failing after
+/// retries, or refusing our credentials), so falling back may help.
+pub fn provider_failed(e: &AgentError) -> bool {
+    match e {
+        AgentError::Api { status, .. } => retryable_status(*status) || matches!(status, 401 | 403),
+        AgentError::Http(e) => transient(e),
+        AgentError::Auth(_) => true,
+        _ => false,
+    }
+}
+
+/// Anthropic API client with streaming support.
+#[derive(Clone)]
+pub struct AnthropicClient {
+    client: reqwest::Client,
+    model: String,
+    max_tokens: u32,
+}
+
+impl AnthropicClient {
+    pub fn new(model: String, max_tokens: u32) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            model,
+            max_tokens,
+        }
+    }
+
+    /// Send a streaming message request and return a channel of events.
+    pub async fn stream_message(
+        &self,
+        auth: &mut AuthProvider,
+        messages: &[Message],
+        tools: &[Value],
+        system: Option<&str>,
+    ) -> Result<mpsc::Receiver<StreamEvent>> {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "stream": true,
+            "messages": messages,
+        });
+
+        if !tools.is_empty() {
+            body["tools"] = Value::Array(tools.to_vec());
+        }
+        if let Some(sys) = system {
+            body["system"] = Value::String(sys.to_string());
+        }
+
+        // Retry once on 401: the credentials are refreshed when headers are built.
+        let resp = send_with_retries(true, async || self.send(auth, &body).await).await?;
+        Ok(stream_events(resp))
+    }
+
+    /// Post the request; the response may be an error status.
+    async fn send(&self, auth: &mut AuthProvider, body: &Value) -> Result<reqwest::Response> {
+        let auth_headers = auth.get_auth_headers().await?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("anthropic-version"),
+            HeaderValue::from_static(API_VERSION),
+        );
+        headers.insert(
+            HeaderName::from_static("content-type"),
+            HeaderValue::from_static("application/json"),
+        );
+
+        for (key, value) in &auth_headers {
+            if let (Ok(name), Ok(val)) = (
+                HeaderName::from_bytes(key.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.insert(name, val);
+            }
+        }
+
+        Ok(self
+            .client
+            .post(API_URL)
+            .headers(headers)
+            .json(body)
+            .send()
+            .await?)
+    }
+}
+
+/// Parse the SSE body of a successful response into a channel of events.
+/// A connection that drops before `message_stop` ends with a retryable
+/// [`StreamEvent::Error`].
+fn stream_events(resp: reqwest::Response) -> mpsc::Receiver<StreamEvent> {
+    // Spawn a task to parse SSE stream and send events through channel
+    let (tx, rx) = mpsc::channel(64);
+
+    tokio::spawn(async move {
+        let mut stream = resp.bytes_stream();
+        let mut buffer = String::new();
+        let mut finished = false;
+        let mut dropped = None;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(c) => c,
+                Err(e) => {
+                    dropped = Some(e.to_string());
+                    break;
+                }
+            };
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(pos) = buffer.find("\n\n") {
+                let event_text = buffer[..pos].to_string();
+                buffer = buffer[pos + 2..].to_string();
+
+                if let Some(event) = streaming::parse_sse_event(&event_text) {
+                    finished |= matches!(event, StreamEvent::MessageStop | StreamEvent::Error { .. });
+                    if tx.send(event).await.is_err() {
+                        return; // receiver dropped
+                    }
+                }
+            }
+        }
+
+        // Process remaining
+        if !buffer.trim().is_empty() {
+            if let Some(event) = streaming::parse_sse_event(buffer.trim()) {
+                finished |= matches!(event, StreamEvent::MessageStop | StreamEvent::Error { .. });
+                let _ = tx.send(event).await;
+            }
+        }
+
+        if !finished {
+            let message = match dropped {
+                Some(e) => format!("connection lost mid-response: {e}"),
+                None => "connection closed mid-response".to_string(),
+            };
+            let _ = tx.send(StreamEvent::Error { message, retryable: true }).await;
+        }
+    });
+
+    rx
+}
+
+/// Send a request until it succeeds, backing off on rate limits, overload,
+/// server errors and connection failures. With `reauth`, a first 401 is
+/// retried immediately.
+pub(crate) async fn send_with_retries(
+    reauth: bool,
+    mut send: impl AsyncFnMut() -> Result<reqwest::Response>,
+) -> Result<reqwest::Response> {
+    let mut attempt = 0;
+    let mut reauthenticated = !reauth;
+    loop {
+        let (reason, delay) = match send().await {
+            Ok(resp) if resp.status().is_success() => return Ok(resp),
+            Ok(resp) => {
+                let status = resp.status().as_u16();
+                if status == 401 && !reauthenticated {
+                    tracing::info!("Got 401, attempting to re-authenticate");
+                    reauthenticated = true;
+                    continue;
+                }
+                let wait = retry_after(resp.headers());
+                let message = resp.text().await.unwrap_or_default();
+                let retry = retryable_status(status)
+                    && attempt < MAX_RETRIES
+                    && wait.is_none_or(|w| w <= MAX_RETRY_AFTER);
+                if !retry {
+                    return Err(AgentError::Api { status, message });
+                }
+                (status_reason(status), wait.unwrap_or_else(|| backoff_delay(attempt)))
+            }
+            Err(AgentError::Http(e)) if transient(&e) && attempt < MAX_RETRIES => {
+                ("connection failed".to_string(), backoff_delay(attempt))
+            }
+            Err(e) => return Err(e),
+        };
+        attempt += 1;
+        display::print_retry(&reason, delay, attempt, MAX_RETRIES);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Statuses worth retrying: timeouts, rate limits, overload and server errors.
+fn retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429 | 500 | 502 | 503 | 504 | 529)
+}
+
+fn status_reason(status: u16) -> String {
+    match status {
+        429 => "rate limited (429)".to_string(),
+        529 => "API overloaded (529)".to_string(),
+        _ => format!("API error ({status})"),
+    }
+}
+
+/// Errors from failing to connect or losing the connection mid-request.
+fn transient(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request()
+}
+
+/// Exponential backoff with jitter for the given (zero-based) retry: a
+/// random delay between half and all of `BASE_DELAY * 2^attempt`, capped.
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let ceiling = BASE_DELAY
+        .saturating_mul(1u32 << attempt.min(16))
+        .min(MAX_DELAY);
+    ceiling.mul_f64(rand::rng().random_range(0.5..=1.0))
+}
+
+/// Wait requested by the server, from `retry-after-ms` or `retry-after`
+/// (seconds or an HTTP date).
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok().map(str::trim);
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Duration::try_from_secs_f64(ms / 1000.0).ok();
+    }
+    let value = header("retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert("retry-after-ms", HeaderValue::from_static("1500"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(1500)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        for attempt in 0..10 {
+            let ceiling = (BASE_DELAY * 2u32.pow(attempt)).min(MAX_DELAY);
+            let delay = backoff_delay(attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "attempt {attempt}: {delay:?}");
+        }
+        assert!(backoff_delay(100) <= MAX_DELAY);
+    }
+}
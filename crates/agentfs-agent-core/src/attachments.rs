@@ -0,0 +1,219 @@
+//! Image attachments for user messages.
+//!
+//! Images come from `/attach <path>` or from host paths dropped into the
+//! prompt (terminals paste them quoted or with escaped spaces). Each is
+//! copied into the workspace under `/attachments/` and sent to the model
+//! as an image content block ahead of the message text.
+
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use agentfs_core::AgentFS;
+
+use crate::error::{AgentError, Result};
+
+/// Workspace directory attachments are stored under.
+pub const ATTACHMENTS_DIR: &str = "/attachments";
+
+/// Largest image accepted (the Anthropic API's per-image limit).
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// An image read from the host, ready to send.
+pub struct Attachment {
+    /// File name on the host.
+    pub name: String,
+    pub media_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Read an image file from the host.
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "image".to_string());
+        let media_type = media_type(path).ok_or_else(|| {
+            AgentError::Other(format!(
+                "{}: not a supported image (png, jpeg, gif or webp)",
+                path.display()
+            ))
+        })?;
+        let size = std::fs::metadata(path)?.len() as usize;
+        if size > MAX_IMAGE_BYTES {
+            return Err(AgentError::Other(format!(
+                "{}: image is {} KB; the limit is {} KB",
+                path.display(),
+                size / 1024,
+                MAX_IMAGE_BYTES / 1024
+            )));
+        }
+        Ok(Self {
+            name,
+            media_type,
+            data: std::fs::read(path)?,
+        })
+    }
+
+    /// Copy into the workspace under `/attachments/<session>/` and return
+    /// the workspace path.
+    pub async fn store(&self, db: &AgentFS, session_id: &str) -> Result<String> {
+        let session = session_id.get(..8).unwrap_or(session_id);
+        let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+        let path = format!("{ATTACHMENTS_DIR}/{session}/{stamp}-{}", self.name);
+        db.fs.write_file(&path, &self.data).await?;
+        Ok(path)
+    }
+
+    /// The Anthropic image content block for this attachment.
+    pub fn content_block(&self) -> Value {
+        json!({
+            "type": "image",
+            "source": {
+                "type": "base64",
+                "media_type": self.media_type,
+                "data": STANDARD.encode(&self.data),
+            }
+        })
+    }
+}
+
+/// Store `attachments` in the workspace and build the user message: the
+/// image blocks, and `text` preceded by a line naming each stored copy so
+/// the model can refer to it.
+pub async fn prepare(
+    db: &AgentFS,
+    session_id: &str,
+    attachments: &[Attachment],
+    text: &str,
+) -> Result<(String, Vec<Value>)> {
+    let mut notes = Vec::new();
+    for attachment in attachments {
+        let path = attachment.store(db, session_id).await?;
+        notes.push(format!("[Attached image {} saved at {path}]", attachment.name));
+    }
+    let text = if text.trim().is_empty() {
+        "Please look at the attached image.".to_string()
+    } else {
+        text.to_string()
+    };
+    let message = format!("{}\n\n{text}", notes.join("\n"));
+    Ok((message, attachments.iter().map(Attachment::content_block).collect()))
+}
+
+fn media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Resolve a path typed or pasted by the user: `~/` is expanded and a
+/// `file://` prefix dropped.
+pub fn host_path(raw: &str) -> PathBuf {
+    let raw = raw.strip_prefix("file://").unwrap_or(raw);
+    match raw.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(raw),
+    }
+}
+
+/// Split image files dropped into `input` out of it. Returns the remaining
+/// text and the image paths; words that aren't existing image files stay
+/// in the text untouched.
+pub fn detect_dropped(input: &str) -> (String, Vec<PathBuf>) {
+    let mut text = String::new();
+    let mut images = Vec::new();
+    let mut last = 0;
+    for (start, end, word) in words(input) {
+        let path = host_path(&word);
+        if media_type(&path).is_some() && path.is_file() {
+            text.push_str(&input[last..start]);
+            last = end;
+            images.push(path);
+        }
+    }
+    text.push_str(&input[last..]);
+    (text.split_whitespace().collect::<Vec<_>>().join(" "), images)
+}
+
+/// Whitespace-separated words with shell-style quoting and backslash
+/// escapes removed, as (start, end, word).
+fn words(input: &str) -> Vec<(usize, usize, String)> {
+    let mut out = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        let mut quote = None;
+        let mut end = input.len();
+        while let Some(&(i, c)) = chars.peek() {
+            match (quote, c) {
+                (None, c) if c.is_whitespace() => {
+                    end = i;
+                    break;
+                }
+                (None, '\'' | '"') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (None, '\\') => {
+                    chars.next();
+                    if let Some(&(_, escaped)) = chars.peek() {
+                        word.push(escaped);
+                    }
+                }
+                (_, c) => word.push(c),
+            }
+            chars.next();
+        }
+        out.push((start, end, word));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let shot = dir.path().join("my shot.png");
+        std::fs::write(&shot, b"\x89PNG").unwrap();
+        let escaped = shot.display().to_string().replace(' ', "\\ ");
+
+        let (text, images) = detect_dropped(&format!("why is this broken? {escaped}"));
+        assert_eq!(text, "why is this broken?");
+        assert_eq!(images, vec![shot.clone()]);
+
+        let (text, images) = detect_dropped(&format!("'{}' compare", shot.display()));
+        assert_eq!(text, "compare");
+        assert_eq!(images.len(), 1);
+
+        let (text, images) = detect_dropped("see /missing/file.png and notes.txt");
+        assert_eq!(text, "see /missing/file.png and notes.txt");
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn test_load_rejects_non_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "hi").unwrap();
+        assert!(Attachment::load(&notes).is_err());
+
+        let gif = dir.path().join("a.GIF");
+        std::fs::write(&gif, b"GIF89a").unwrap();
+        let attachment = Attachment::load(&gif).unwrap();
+        assert_eq!(attachment.content_block()["source"]["media_type"], "image/gif");
+    }
+}
@@ -0,0 +1,206 @@
+//! [`AgentBuilder`]: assemble an [`Agent`] against an AgentFS database.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use agentfs_core::AgentFS;
+
+use crate::agent::Agent;
+use crate::api::{AnthropicClient, LlmClient, Provider};
+use crate::config::{TurnLimits, DEFAULT_MAX_TOKENS, DEFAULT_MODEL};
+use crate::executor::ToolExecutor;
+use crate::hooks::Hooks;
+use crate::mcp_client::McpManager;
+use crate::memory::MemoryManager;
+use crate::permissions::Permissions;
+use crate::web::WebClient;
+
+/// Builds an [`Agent`]. Everything but the database is optional: by default
+/// the agent talks to Anthropic with [`DEFAULT_MODEL`], runs in a new
+/// session, has only the built-in tools, default permissions and no hooks,
+/// memory or MCP servers.
+pub struct AgentBuilder {
+    db: AgentFS,
+    session_id: Option<String>,
+    model: String,
+    max_tokens: u32,
+    client: Option<LlmClient>,
+    system: Option<String>,
+    mcp: Option<(Arc<Mutex<McpManager>>, Vec<Value>)>,
+    web: Option<WebClient>,
+    permissions: Option<Permissions>,
+    hooks: Option<Hooks>,
+    memory: Option<Arc<MemoryManager>>,
+    limits: TurnLimits,
+    interactive: bool,
+    stream_json: bool,
+    events: Option<UnboundedSender<Value>>,
+    dry_run: bool,
+}
+
+impl AgentBuilder {
+    /// Start building an agent whose tools work on `db`.
+    pub fn new(db: AgentFS) -> Self {
+        Self {
+            db,
+            session_id: None,
+            model: DEFAULT_MODEL.to_string(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            client: None,
+            system: None,
+            mcp: None,
+            web: None,
+            permissions: None,
+            hooks: None,
+            memory: None,
+            limits: TurnLimits::default(),
+            interactive: true,
+            stream_json: false,
+            events: None,
+            dry_run: false,
+        }
+    }
+
+    /// Session the conversation, tool calls and events are recorded under;
+    /// a new one by default.
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Anthropic model to use. Ignored if [`client`](Self::client) is set.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Output token limit per response. Ignored if
+    /// [`client`](Self::client) is set.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Model client to use instead of Anthropic with `model`, e.g. one
+    /// with a provider fallback chain.
+    pub fn client(mut self, client: LlmClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    /// Offer the tools of connected MCP servers. `tools` are the manager's
+    /// definitions, from [`McpManager::all_tool_definitions`].
+    pub fn mcp(mut self, manager: Arc<Mutex<McpManager>>, tools: Vec<Value>) -> Self {
+        self.mcp = Some((manager, tools));
+        self
+    }
+
+    /// Client for `web_search` and `fetch_url`.
+    pub fn web(mut self, web: WebClient) -> Self {
+        self.web = Some(web);
+        self
+    }
+
+    pub fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    pub fn hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// Reflect on turns and recall past experience.
+    pub fn memory(mut self, memory: Arc<MemoryManager>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    pub fn limits(mut self, limits: TurnLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Stop at turn limits and expensive requests instead of asking.
+    pub fn non_interactive(mut self) -> Self {
+        self.interactive = false;
+        self
+    }
+
+    /// Emit `stream-json` events on stdout as turns run.
+    pub fn stream_json(mut self) -> Self {
+        self.stream_json = true;
+        self
+    }
+
+    /// Send `stream-json` events to `events` instead of stdout.
+    pub fn events(mut self, events: UnboundedSender<Value>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Run read-only tools but only record the others, for previewing what
+    /// a turn would do. Messages and tool calls are not saved.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    pub fn build(self) -> Agent {
+        let session_id = self
+            .session_id
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let client = self.client.unwrap_or_else(|| {
+            LlmClient::new(Provider::Anthropic(AnthropicClient::new(
+                self.model,
+                self.max_tokens,
+            )))
+        });
+        let model = client.provider().model().to_string();
+
+        let mut executor = ToolExecutor::new(self.db, session_id.clone());
+        let mut extra_tools = Vec::new();
+        if let Some((manager, tools)) = self.mcp {
+            executor = executor.with_mcp(manager);
+            extra_tools = tools;
+        }
+        if let Some(web) = self.web {
+            executor = executor.with_web(web);
+        }
+        if let Some(permissions) = self.permissions {
+            executor = executor.with_permissions(permissions);
+        }
+        if let Some(hooks) = self.hooks {
+            executor = executor.with_hooks(hooks);
+        }
+        if self.dry_run {
+            executor = executor.with_dry_run();
+        }
+
+        let mut agent = Agent::new(client, executor, self.system, session_id, model, extra_tools)
+            .with_limits(self.limits);
+        if !self.interactive {
+            agent = agent.non_interactive();
+        }
+        if self.stream_json {
+            agent = agent.with_stream_json();
+        }
+        if let Some(events) = self.events {
+            agent = agent.with_events(events);
+        }
+        if let Some(memory) = self.memory {
+            agent = agent.with_memory(memory);
+        }
+        agent
+    }
+}
@@ -0,0 +1,349 @@
+//! Per-turn workspace checkpoints for `/undo` and `/redo`.
+//!
+//! Before a turn first changes a workspace file, the file's contents are
+//! recorded; when the turn ends its new contents are recorded beside them.
+//! Undo writes the old contents back and redo the new ones. Each session's
+//! checkpoints are kept in KV, along with each changed file's contents
+//! before the session first touched it, which `/diff` compares against.
+//! Host files changed through `bash` are not covered.
+
+use std::collections::BTreeMap;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use agentfs_core::error::AgentFSError;
+use agentfs_core::filesystem::FileEdit;
+use agentfs_core::AgentFS;
+
+use crate::error::{AgentError, Result};
+
+/// KV key prefix for a session's checkpoints.
+pub const CHECKPOINTS_KEY_PREFIX: &str = "session:checkpoints:";
+
+/// Checkpoints kept per session; older ones can no longer be undone.
+pub const MAX_CHECKPOINTS: usize = 20;
+
+/// One file before and after a turn. Contents are base64; `None` means
+/// the file didn't exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileState {
+    pub path: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// The workspace files one turn changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Start of the user message that started the turn.
+    pub prompt: String,
+    pub created_at: String,
+    pub files: Vec<FileState>,
+}
+
+/// A session's checkpoints: turns that can be undone, newest last, and
+/// undone turns that can be redone.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    pub done: Vec<Checkpoint>,
+    pub undone: Vec<Checkpoint>,
+    /// Each changed file as it was before the session first changed it.
+    #[serde(default)]
+    baseline: BTreeMap<String, Option<String>>,
+}
+
+impl History {
+    pub async fn load(db: &AgentFS, session_id: &str) -> Result<Self> {
+        match db.kv.get(&format!("{CHECKPOINTS_KEY_PREFIX}{session_id}")).await {
+            Ok(entry) => serde_json::from_str(&entry.value).map_err(|e| {
+                AgentError::Other(format!("Failed to parse saved checkpoints: {e}"))
+            }),
+            Err(AgentFSError::KeyNotFound { .. }) => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, db: &AgentFS, session_id: &str) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        db.kv
+            .set(&format!("{CHECKPOINTS_KEY_PREFIX}{session_id}"), &json)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Workspace files a tool call is about to change.
+pub fn touched_paths(tool: &str, input: &Value) -> Vec<String> {
+    match tool {
+        "write_file" => input
+            .get("path")
+            .and_then(|p| p.as_str())
+            .map(|p| vec![p.to_string()])
+            .unwrap_or_default(),
+        "multi_edit" => input
+            .get("edits")
+            .cloned()
+            .and_then(|edits| serde_json::from_value::<Vec<FileEdit>>(edits).ok())
+            .map(|edits| edits.into_iter().map(|e| e.path).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Collects the files a turn changes until the turn ends.
+#[derive(Debug)]
+pub struct Recorder {
+    prompt: String,
+    files: Vec<FileState>,
+}
+
+impl Recorder {
+    pub fn new(prompt: &str) -> Self {
+        let prompt = match prompt.char_indices().nth(80) {
+            Some((cut, _)) => format!("{}…", &prompt[..cut]),
+            None => prompt.to_string(),
+        };
+        Self {
+            prompt: prompt.replace('\n', " "),
+            files: Vec::new(),
+        }
+    }
+
+    /// Record the current contents of the files `tool` is about to change,
+    /// unless this turn already did.
+    pub async fn before_tool(&mut self, db: &AgentFS, tool: &str, input: &Value) -> Result<()> {
+        for path in touched_paths(tool, input) {
+            if self.files.iter().any(|f| f.path == path) {
+                continue;
+            }
+            let before = read(db, &path).await?.map(|data| STANDARD.encode(data));
+            self.files.push(FileState {
+                path,
+                before,
+                after: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Record where the files ended up and save the checkpoint. A turn that
+    /// changed nothing leaves no checkpoint, and keeps what can be redone.
+    pub async fn finish(mut self, db: &AgentFS, session_id: &str) -> Result<()> {
+        for file in &mut self.files {
+            file.after = read(db, &file.path).await?.map(|data| STANDARD.encode(data));
+        }
+        self.files.retain(|f| f.before != f.after);
+        if self.files.is_empty() {
+            return Ok(());
+        }
+        let mut history = History::load(db, session_id).await?;
+        for file in &self.files {
+            history
+                .baseline
+                .entry(file.path.clone())
+                .or_insert_with(|| file.before.clone());
+        }
+        history.done.push(Checkpoint {
+            prompt: self.prompt,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            files: self.files,
+        });
+        let excess = history.done.len().saturating_sub(MAX_CHECKPOINTS);
+        history.done.drain(..excess);
+        history.undone.clear();
+        history.save(db, session_id).await
+    }
+}
+
+/// What undo or redo did to one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileOutcome {
+    Written,
+    Removed,
+    /// Changed since the checkpoint; left alone.
+    Conflict,
+}
+
+/// The result of `/undo` or `/redo`.
+#[derive(Debug)]
+pub struct Applied {
+    pub prompt: String,
+    pub files: Vec<(String, FileOutcome)>,
+}
+
+/// Restore the files changed by the last turn that hasn't been undone.
+pub async fn undo(db: &AgentFS, session_id: &str) -> Result<Option<Applied>> {
+    let mut history = History::load(db, session_id).await?;
+    let Some(checkpoint) = history.done.pop() else {
+        return Ok(None);
+    };
+    let applied = apply(db, &checkpoint, false).await?;
+    history.undone.push(checkpoint);
+    history.save(db, session_id).await?;
+    Ok(Some(applied))
+}
+
+/// Reapply the most recently undone turn.
+pub async fn redo(db: &AgentFS, session_id: &str) -> Result<Option<Applied>> {
+    let mut history = History::load(db, session_id).await?;
+    let Some(checkpoint) = history.undone.pop() else {
+        return Ok(None);
+    };
+    let applied = apply(db, &checkpoint, true).await?;
+    history.done.push(checkpoint);
+    history.save(db, session_id).await?;
+    Ok(Some(applied))
+}
+
+/// Move each file from one side of the checkpoint to the other, skipping
+/// files that no longer match the side being left.
+async fn apply(db: &AgentFS, checkpoint: &Checkpoint, forward: bool) -> Result<Applied> {
+    let mut files = Vec::new();
+    for file in &checkpoint.files {
+        let (from, to) = if forward {
+            (&file.before, &file.after)
+        } else {
+            (&file.after, &file.before)
+        };
+        let current = read(db, &file.path).await?.map(|data| STANDARD.encode(data));
+        let outcome = if current != *from {
+            FileOutcome::Conflict
+        } else if let Some(to) = to {
+            let data = STANDARD
+                .decode(to)
+                .map_err(|e| AgentError::Other(format!("Corrupt checkpoint for {}: {e}", file.path)))?;
+            db.fs.write_file(&file.path, &data).await?;
+            FileOutcome::Written
+        } else {
+            db.fs.remove_file(&file.path).await?;
+            FileOutcome::Removed
+        };
+        files.push((file.path.clone(), outcome));
+    }
+    Ok(Applied {
+        prompt: checkpoint.prompt.clone(),
+        files,
+    })
+}
+
+/// A file changed during the session: its contents before the session
+/// first changed it and now. `None` means no such file.
+#[derive(Debug)]
+pub struct FileChange {
+    pub path: String,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+}
+
+impl FileChange {
+    /// Unified diff of the change, or `None` for binary files.
+    pub fn unified_diff(&self) -> Option<String> {
+        let text = |data: &Option<Vec<u8>>| match data {
+            Some(data) if data.iter().take(8192).any(|&b| b == 0) => None,
+            Some(data) => Some(String::from_utf8_lossy(data).into_owned()),
+            None => Some(String::new()),
+        };
+        let (old, new) = (text(&self.before)?, text(&self.after)?);
+        let name = |side: &str, data: &Option<Vec<u8>>| match data {
+            Some(_) => format!("{side}{}", self.path),
+            None => "/dev/null".to_string(),
+        };
+        let (old_name, new_name) = (name("a", &self.before), name("b", &self.after));
+        Some(
+            similar::TextDiff::from_lines(&old, &new)
+                .unified_diff()
+                .context_radius(3)
+                .header(&old_name, &new_name)
+                .to_string(),
+        )
+    }
+}
+
+/// Workspace files that differ from how they were before the session
+/// changed them, sorted by path.
+pub async fn session_changes(db: &AgentFS, session_id: &str) -> Result<Vec<FileChange>> {
+    let history = History::load(db, session_id).await?;
+    let mut changes = Vec::new();
+    for (path, before) in history.baseline {
+        let before = before
+            .map(|data| STANDARD.decode(data))
+            .transpose()
+            .map_err(|e| AgentError::Other(format!("Corrupt checkpoint for {path}: {e}")))?;
+        let after = read(db, &path).await?;
+        if before != after {
+            changes.push(FileChange { path, before, after });
+        }
+    }
+    Ok(changes)
+}
+
+/// A workspace file's contents, or `None` if there is no such file.
+async fn read(db: &AgentFS, path: &str) -> Result<Option<Vec<u8>>> {
+    match db.fs.read_file(path).await {
+        Ok(data) => Ok(Some(data)),
+        Err(AgentFSError::FileNotFound { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::config::AgentFSConfig;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_undo_redo() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentFSConfig::builder(dir.path().join("test.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        let db = AgentFS::create(config).await.unwrap();
+        db.fs.write_file("/a.txt", b"one").await.unwrap();
+
+        let mut recorder = Recorder::new("change things");
+        let edit = json!({ "edits": [{ "path": "/a.txt", "old_string": "one", "new_string": "two" }] });
+        recorder.before_tool(&db, "multi_edit", &edit).await.unwrap();
+        db.fs.write_file("/a.txt", b"two").await.unwrap();
+        let write = json!({ "path": "/b.txt", "content": "new" });
+        recorder.before_tool(&db, "write_file", &write).await.unwrap();
+        db.fs.write_file("/b.txt", b"new").await.unwrap();
+        recorder.finish(&db, "s1").await.unwrap();
+
+        let applied = undo(&db, "s1").await.unwrap().unwrap();
+        assert_eq!(applied.prompt, "change things");
+        assert_eq!(db.fs.read_file("/a.txt").await.unwrap(), b"one");
+        assert!(!db.fs.exists("/b.txt").await.unwrap());
+        assert!(undo(&db, "s1").await.unwrap().is_none());
+
+        // A file changed since the undo is left alone by redo.
+        db.fs.write_file("/b.txt", b"mine").await.unwrap();
+        let applied = redo(&db, "s1").await.unwrap().unwrap();
+        assert_eq!(
+            applied.files,
+            vec![
+                ("/a.txt".to_string(), FileOutcome::Written),
+                ("/b.txt".to_string(), FileOutcome::Conflict),
+            ]
+        );
+        assert_eq!(db.fs.read_file("/a.txt").await.unwrap(), b"two");
+        assert_eq!(db.fs.read_file("/b.txt").await.unwrap(), b"mine");
+
+        // The session's changes: /a.txt edited, /b.txt created.
+        let changes = session_changes(&db, "s1").await.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changes[0].unified_diff().unwrap(),
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-one\n\\ No newline at end of file\n+two\n\\ No newline at end of file\n"
+        );
+        assert!(changes[1].unified_diff().unwrap().starts_with("--- /dev/null\n+++ b/b.txt\n"));
+
+        // A turn that changed nothing records no checkpoint.
+        Recorder::new("look around").finish(&db, "s1").await.unwrap();
+        assert_eq!(History::load(&db, "s1").await.unwrap().done.len(), 1);
+        db.close().await.unwrap();
+    }
+}
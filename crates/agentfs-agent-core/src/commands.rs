@@ -0,0 +1,136 @@
+//! User-defined slash commands: prompt macros in `~/.infinity/commands/*.md`
+//! and `.infinity/commands/*.md` (project commands win on name clashes).
+//!
+//! `review.md` defines `/review`. Optional frontmatter gives a
+//! `description` and `allowed-tools`, a comma-separated list of tool name
+//! globs the turn may use. The body is the prompt; `$ARGUMENTS` is replaced
+//! by whatever follows the command.
+//!
+//! ```markdown
+//! ---
+//! description: Review a file for bugs
+//! allowed-tools: read_file, grep, glob
+//! ---
+//! Review $ARGUMENTS for bugs and risky changes. Report findings by severity.
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::skills::{allowed_tools, split_frontmatter};
+
+/// A command loaded from a file.
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub name: String,
+    pub description: String,
+    /// Tool name globs the command's turn may use; empty allows all.
+    pub allowed_tools: Vec<String>,
+    pub template: String,
+}
+
+impl Command {
+    /// The prompt for an invocation with `args`. A template without
+    /// `$ARGUMENTS` gets the arguments appended.
+    pub fn expand(&self, args: &str) -> String {
+        if self.template.contains("$ARGUMENTS") {
+            self.template.replace("$ARGUMENTS", args)
+        } else if args.is_empty() {
+            self.template.clone()
+        } else {
+            format!("{}\n\n{args}", self.template)
+        }
+    }
+}
+
+/// The commands available in this session.
+#[derive(Debug, Default)]
+pub struct CommandRegistry {
+    commands: HashMap<String, Command>,
+}
+
+impl CommandRegistry {
+    /// Load global commands, then project commands over them.
+    pub fn load() -> Self {
+        let mut commands = HashMap::new();
+        if let Some(home) = dirs::home_dir() {
+            load_dir(&home.join(".infinity").join("commands"), &mut commands);
+        }
+        load_dir(&PathBuf::from(".infinity").join("commands"), &mut commands);
+        Self { commands }
+    }
+
+    /// The command `input` invokes, and its arguments.
+    pub fn matches<'a>(&self, input: &'a str) -> Option<(&Command, &'a str)> {
+        let rest = input.strip_prefix('/')?;
+        let (name, args) = match rest.find(char::is_whitespace) {
+            Some(idx) => (&rest[..idx], rest[idx..].trim()),
+            None => (rest, ""),
+        };
+        self.commands.get(name).map(|command| (command, args))
+    }
+
+    /// All commands, sorted by name.
+    pub fn list(&self) -> Vec<&Command> {
+        let mut items: Vec<&Command> = self.commands.values().collect();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        items
+    }
+}
+
+/// Insert each `*.md` in `dir` into `commands`.
+fn load_dir(dir: &Path, commands: &mut HashMap<String, Command>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().is_none_or(|e| e != "md") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            commands.insert(name.to_string(), parse(name, &content));
+        }
+    }
+}
+
+fn parse(name: &str, content: &str) -> Command {
+    let (mut fields, template) = split_frontmatter(content)
+        .unwrap_or_else(|| (HashMap::new(), content.trim().to_string()));
+    Command {
+        name: name.to_string(),
+        description: fields.remove("description").unwrap_or_default(),
+        allowed_tools: allowed_tools(&mut fields),
+        template,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_expand() {
+        let review = parse(
+            "review",
+            "---\ndescription: Review a file\nallowed-tools: read_file, grep\n---\nReview $ARGUMENTS for bugs.\n",
+        );
+        assert_eq!(review.description, "Review a file");
+        assert_eq!(review.allowed_tools, vec!["read_file", "grep"]);
+        assert_eq!(review.expand("/src/main.rs"), "Review /src/main.rs for bugs.");
+
+        let summarize = parse("summarize", "Summarize the conversation so far.");
+        assert!(summarize.allowed_tools.is_empty());
+        assert_eq!(summarize.expand(""), "Summarize the conversation so far.");
+        assert_eq!(summarize.expand("briefly"), "Summarize the conversation so far.\n\nbriefly");
+
+        let registry = CommandRegistry {
+            commands: HashMap::from([("review".to_string(), review)]),
+        };
+        let (command, args) = registry.matches("/review  /a.rs").unwrap();
+        assert_eq!((command.name.as_str(), args), ("review", "/a.rs"));
+        assert!(registry.matches("/reviewer").is_none());
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use crate::auth::AuthProvider;
+use crate::error::Result;
+
+/// Model used when neither `--model` nor the project config names one.
+pub const DEFAULT_MODEL: &str = "claude-sonnet-4-6";
+
+/// Output token limit used when neither `--max-tokens` nor the project config sets one.
+pub const DEFAULT_MAX_TOKENS: u32 = 8192;
+
+/// Agent configuration assembled from CLI args and environment.
+#[allow(dead_code)]
+pub struct AgentConfig {
+    pub auth: AuthProvider,
+    pub model: String,
+    pub max_tokens: u32,
+    pub db_path: PathBuf,
+    pub system_prompt: Option<String>,
+}
+
+impl AgentConfig {
+    /// Build config from CLI arguments.
+    pub fn from_args(
+        db_path: PathBuf,
+        model: String,
+        max_tokens: u32,
+        system_prompt: Option<String>,
+    ) -> Result<Self> {
+        let auth = AuthProvider::load()?;
+        Ok(Self {
+            auth,
+            model,
+            max_tokens,
+            db_path,
+            system_prompt,
+        })
+    }
+}
+
+/// Bounds on one turn's agentic loop, so a confused model can't spin (and
+/// spend) indefinitely. Zero disables a limit.
+#[derive(Debug, Clone, Copy)]
+pub struct TurnLimits {
+    /// API calls (model responses) per turn.
+    pub max_steps: u32,
+    /// Tool calls per turn, including subagent tasks.
+    pub max_tool_calls: u32,
+    /// Estimated spend per turn, in microcents.
+    pub max_cost_microcents: i64,
+    /// Estimated input cost of one API call, in microcents, above which
+    /// the user is asked before it's sent.
+    pub confirm_call_cost_microcents: i64,
+}
+
+impl Default for TurnLimits {
+    fn default() -> Self {
+        Self {
+            max_steps: 50,
+            max_tool_calls: 100,
+            max_cost_microcents: 500_000_000, // $5
+            confirm_call_cost_microcents: 100_000_000, // $1
+        }
+    }
+}
+
+impl TurnLimits {
+    /// Limits from CLI values; `max_cost` and `confirm_call_cost` are in
+    /// dollars.
+    pub fn new(max_steps: u32, max_tool_calls: u32, max_cost: f64, confirm_call_cost: f64) -> Self {
+        Self {
+            max_steps,
+            max_tool_calls,
+            max_cost_microcents: (max_cost.max(0.0) * 1e8) as i64,
+            confirm_call_cost_microcents: (confirm_call_cost.max(0.0) * 1e8) as i64,
+        }
+    }
+
+    /// Whether an API call estimated to cost this much needs confirming.
+    pub fn call_needs_confirmation(&self, cost_microcents: i64) -> bool {
+        self.confirm_call_cost_microcents > 0 && cost_microcents >= self.confirm_call_cost_microcents
+    }
+
+    /// The first limit reached by this much work, described for the user.
+    pub fn exceeded(&self, steps: u32, tool_calls: u32, cost_microcents: i64) -> Option<String> {
+        if self.max_steps > 0 && steps >= self.max_steps {
+            Some(format!("{steps} steps"))
+        } else if self.max_tool_calls > 0 && tool_calls >= self.max_tool_calls {
+            Some(format!("{tool_calls} tool calls"))
+        } else if self.max_cost_microcents > 0 && cost_microcents >= self.max_cost_microcents {
+            Some(format!("${:.2} spent", cost_microcents as f64 / 1e8))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_limits_exceeded() {
+        let limits = TurnLimits::new(10, 20, 0.5, 1.0);
+        assert_eq!(limits.exceeded(3, 5, 1_000_000), None);
+        assert_eq!(limits.exceeded(10, 5, 0).as_deref(), Some("10 steps"));
+        assert_eq!(limits.exceeded(3, 25, 0).as_deref(), Some("25 tool calls"));
+        assert_eq!(limits.exceeded(3, 5, 60_000_000).as_deref(), Some("$0.60 spent"));
+
+        let off = TurnLimits::new(0, 0, 0.0, 0.0);
+        assert_eq!(off.exceeded(1000, 1000, i64::MAX), None);
+        assert!(!off.call_needs_confirmation(i64::MAX));
+        assert!(!limits.call_needs_confirmation(99_999_999));
+        assert!(limits.call_needs_confirmation(100_000_000));
+    }
+}
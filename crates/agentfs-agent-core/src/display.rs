@@ -8,6 +8,7 @@ use crossterm::style::{Attribute, Color, SetAttribute, SetForegroundColor, Reset
 use crossterm::terminal;
 use crossterm::ExecutableCommand;
 
+use crate::highlight::CodeHighlighter;
 use crate::streaming::{ContentBlockType, StreamEvent, ToolUseBlock};
 
 // ── Spinner ─────────────────────────────────────────────────────────
@@ -38,6 +39,29 @@ const FOLLOW_UP_MESSAGES: &[&str] = &[
 
 const SPINNER_COLORS: &[Color] = &[Color::Magenta, Color::Blue, Color::Cyan, Color::Blue];
 
+/// Set while stdout carries machine-readable output (`--output-format`).
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Silence the turn's terminal output (spinners, streamed text, tool
+/// calls and results) so stdout is left to structured output.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+thread_local! {
+    static THREAD_QUIET: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Like [`set_quiet`], for output from the current thread only: for an
+/// agent that runs on a thread of its own, next to others that don't.
+pub fn set_thread_quiet(quiet: bool) {
+    THREAD_QUIET.with(|q| q.set(quiet));
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed) || THREAD_QUIET.with(|q| q.get())
+}
+
 /// A terminal spinner that runs in a background task.
 pub struct Spinner {
     running: Arc<AtomicBool>,
@@ -48,6 +72,9 @@ impl Spinner {
     /// Start a spinner with a message.
     pub fn start(message: &str) -> Self {
         let running = Arc::new(AtomicBool::new(true));
+        if quiet() {
+            return Self { running, handle: None };
+        }
         let running_clone = running.clone();
         let message = message.to_string();
 
@@ -123,7 +150,9 @@ impl Drop for Spinner {
     fn drop(&mut self) {
         self.running.store(false, Ordering::Relaxed);
         // Best-effort show cursor
-        let _ = std::io::stdout().execute(cursor::Show);
+        if !quiet() {
+            let _ = std::io::stdout().execute(cursor::Show);
+        }
     }
 }
 
@@ -143,7 +172,8 @@ pub fn print_stream_event(event: &StreamEvent) {
             // Handled by print_tool_call after accumulation
         }
         StreamEvent::ContentBlockStop { .. } | StreamEvent::InputJsonDelta { .. } => {}
-        StreamEvent::Error { message } => {
+        // Retryable errors are reported by the retry notice instead.
+        StreamEvent::Error { message, retryable: false } => {
             eprintln!(
                 "\n{}{}  \u{2716} Error: {message}{}",
                 SetForegroundColor(Color::Red),
@@ -157,11 +187,19 @@ pub fn print_stream_event(event: &StreamEvent) {
 
 // ── Rich Stream Renderer ────────────────────────────────────────────
 
-/// Rich stream renderer: code blocks with borders and diff coloring,
-/// prose streams character-by-character for a live feel.
+/// Rich stream renderer: code blocks with borders, syntax highlighting
+/// and diff coloring, prose streams character-by-character for a live feel.
 pub struct StreamRenderer {
     in_code_block: bool,
     line_buffer: String,
+    /// Highlighter for the open code block, if its language is known.
+    highlighter: Option<CodeHighlighter>,
+}
+
+impl Default for StreamRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StreamRenderer {
@@ -169,11 +207,15 @@ impl StreamRenderer {
         Self {
             in_code_block: false,
             line_buffer: String::new(),
+            highlighter: None,
         }
     }
 
     /// Push a text delta chunk. Handles line buffering and rich rendering.
     pub fn push(&mut self, text: &str) {
+        if quiet() {
+            return;
+        }
         let mut stdout = std::io::stdout();
 
         for ch in text.chars() {
@@ -189,6 +231,7 @@ impl StreamRenderer {
                     print!("\r{}\r", " ".repeat(clear_len));
                     self.in_code_block = true;
                     let lang = line.trim_start().trim_start_matches('`').trim();
+                    self.highlighter = CodeHighlighter::for_lang(lang);
                     print!(
                         "{}  \u{250C}\u{2500}",
                         SetForegroundColor(Color::DarkGrey),
@@ -205,14 +248,15 @@ impl StreamRenderer {
                 } else if is_fence && self.in_code_block {
                     // Closing code fence
                     self.in_code_block = false;
+                    self.highlighter = None;
                     println!(
                         "{}  \u{2514}\u{2500}{}",
                         SetForegroundColor(Color::DarkGrey),
                         ResetColor,
                     );
                 } else if self.in_code_block {
-                    // Code block line: render with diff awareness
-                    Self::render_code_line(&line);
+                    // Code block line: highlight, or render with diff awareness
+                    self.render_code_line(&line);
                     println!();
                 } else {
                     // Prose: chars already streamed, just emit newline
@@ -230,8 +274,19 @@ impl StreamRenderer {
         }
     }
 
-    /// Render a code line with diff coloring and subtle border.
-    fn render_code_line(line: &str) {
+    /// Render a code line with syntax highlighting (or diff coloring) and
+    /// subtle border.
+    fn render_code_line(&mut self, line: &str) {
+        if let Some(highlighted) = self.highlighter.as_mut().and_then(|h| h.highlight(line)) {
+            print!(
+                "{}  \u{2502}{} {}{}",
+                SetForegroundColor(Color::DarkGrey),
+                ResetColor,
+                highlighted,
+                ResetColor,
+            );
+            return;
+        }
         let trimmed = line.trim_start();
 
         if trimmed.starts_with('+') && !trimmed.starts_with("+++") {
@@ -275,15 +330,20 @@ impl StreamRenderer {
 
     /// Flush any remaining buffered content.
     pub fn finish(&mut self) {
+        if quiet() {
+            return;
+        }
         if !self.line_buffer.is_empty() {
             if self.in_code_block {
-                Self::render_code_line(&self.line_buffer);
+                let line = std::mem::take(&mut self.line_buffer);
+                self.render_code_line(&line);
                 println!();
             }
             // Prose partial was already printed char by char
             self.line_buffer.clear();
         }
         self.in_code_block = false;
+        self.highlighter = None;
     }
 }
 
@@ -307,6 +367,9 @@ pub fn print_cancelled() {
 
 /// Print a continuation indicator when the agentic loop goes back for another round.
 pub fn print_agentic_continue(step: u32) {
+    if quiet() {
+        return;
+    }
     println!(
         "\n{}{}  \u{21bb} continuing (step {step}){}",
         SetForegroundColor(Color::Cyan),
@@ -323,10 +386,12 @@ fn tool_color(name: &str) -> Color {
         return Color::Magenta;
     }
     match name {
-        "read_file" | "list_dir" | "tree" => Color::Cyan,
-        "write_file" => Color::Green,
-        "search" => Color::Magenta,
-        "bash" => Color::Yellow,
+        "read_file" | "list_dir" | "tree" | "glob" => Color::Cyan,
+        "write_file" | "multi_edit" => Color::Green,
+        "search" | "grep" => Color::Magenta,
+        "bash" | "restart_shell" => Color::Yellow,
+        "fetch_url" | "web_search" => Color::DarkCyan,
+        "task" => Color::Magenta,
         "kv_get" | "kv_set" => Color::Blue,
         _ => Color::Cyan,
     }
@@ -334,6 +399,9 @@ fn tool_color(name: &str) -> Color {
 
 /// Print a tool execution header with progress indicator and color per tool type.
 pub fn print_tool_call(tool: &ToolUseBlock, index: usize, total: usize) {
+    if quiet() {
+        return;
+    }
     let mut stdout = std::io::stdout();
     let color = tool_color(&tool.name);
     let name = tool_display_name(&tool.name);
@@ -366,8 +434,134 @@ pub fn print_tool_call(tool: &ToolUseBlock, index: usize, total: usize) {
     let _ = stdout.flush();
 }
 
+/// Ask whether a tool call may run; the answer is read by the caller.
+pub fn print_permission_prompt(tool: &str, summary: &str, always: &str) {
+    let name = tool_display_name(tool);
+    println!(
+        "{}{}  ? Allow {name}{}  {summary}",
+        SetForegroundColor(Color::Yellow),
+        SetAttribute(Attribute::Bold),
+        SetAttribute(Attribute::Reset),
+    );
+    print!(
+        "{}    [y]es  [n]o  [a] {always}: {}",
+        SetForegroundColor(Color::DarkGrey),
+        ResetColor,
+    );
+    let _ = std::io::stdout().flush();
+}
+
+/// Note a failed API request that will be retried after `delay`.
+pub fn print_retry(reason: &str, delay: std::time::Duration, attempt: u32, max: u32) {
+    if quiet() {
+        return;
+    }
+    let width = terminal::size().map(|(w, _)| w as usize).unwrap_or(120);
+    println!(
+        "\r{}\r{}  \u{21BB} {reason}; retrying in {:.1}s ({attempt}/{max}){}",
+        " ".repeat(width),
+        SetForegroundColor(Color::DarkYellow),
+        delay.as_secs_f32(),
+        ResetColor,
+    );
+}
+
+/// Note a switch to the next provider in the fallback chain.
+pub fn print_provider_fallback(from: &str, to: &str, model: &str, reason: &str) {
+    if quiet() {
+        return;
+    }
+    let reason: String = reason.chars().take(160).collect();
+    println!(
+        "{}{}  \u{21AA} {from} unavailable ({reason}); switching to {to} ({model}){}",
+        SetForegroundColor(Color::DarkYellow),
+        SetAttribute(Attribute::Bold),
+        SetAttribute(Attribute::Reset),
+    );
+}
+
+/// Ask whether to keep going after a turn limit is reached.
+pub fn print_limit_prompt(reason: &str) {
+    println!(
+        "{}{}  ? Turn limit reached ({reason}). Continue?{}",
+        SetForegroundColor(Color::Yellow),
+        SetAttribute(Attribute::Bold),
+        SetAttribute(Attribute::Reset),
+    );
+    print!(
+        "{}    [y]es  [n]o: {}",
+        SetForegroundColor(Color::DarkGrey),
+        ResetColor,
+    );
+    let _ = std::io::stdout().flush();
+}
+
+/// Ask whether to send one expensive request.
+pub fn print_call_cost_prompt(model: &str, tokens: u64, cost_microcents: i64) {
+    println!(
+        "{}{}  ? Next request to {model} is ~{} input tokens (~{}). Send it?{}",
+        SetForegroundColor(Color::Yellow),
+        SetAttribute(Attribute::Bold),
+        fmt_tokens(tokens),
+        format_cost(cost_microcents),
+        SetAttribute(Attribute::Reset),
+    );
+    print!(
+        "{}    [y]es  [n]o: {}",
+        SetForegroundColor(Color::DarkGrey),
+        ResetColor,
+    );
+    let _ = std::io::stdout().flush();
+}
+
+/// Note that a turn stopped because an expensive request was declined.
+pub fn print_call_declined(cost_microcents: i64) {
+    if quiet() {
+        return;
+    }
+    println!(
+        "{}  \u{25A0} Stopped: request (~{}) not sent. Send a message to continue.{}",
+        SetForegroundColor(Color::Yellow),
+        format_cost(cost_microcents),
+        ResetColor,
+    );
+}
+
+/// Note that a turn was cut short by a limit.
+pub fn print_turn_stopped(reason: &str) {
+    if quiet() {
+        return;
+    }
+    println!(
+        "{}  \u{25A0} Stopped: turn limit reached ({reason}). Send a message to continue.{}",
+        SetForegroundColor(Color::Yellow),
+        ResetColor,
+    );
+}
+
+/// Mark where a subagent's output starts and ends.
+pub fn print_subagent(description: &str, finished: bool) {
+    if quiet() {
+        return;
+    }
+    let label = if finished {
+        format!("\u{2514} subagent done: {description}")
+    } else {
+        format!("\u{250C} subagent: {description}")
+    };
+    println!(
+        "{}{}  {label}{}",
+        SetForegroundColor(Color::Magenta),
+        SetAttribute(Attribute::Dim),
+        SetAttribute(Attribute::Reset),
+    );
+}
+
 /// Print tool execution result with a color-coded left border.
 pub fn print_tool_result(_tool_name: &str, result: &str, is_error: bool) {
+    if quiet() {
+        return;
+    }
     let color = if is_error { Color::Red } else { Color::DarkGrey };
 
     let display = if result.len() > 2000 {
@@ -387,6 +581,9 @@ pub fn print_tool_result(_tool_name: &str, result: &str, is_error: bool) {
 
 /// Print tool completion status with duration.
 pub fn print_tool_done(duration: std::time::Duration, is_error: bool) {
+    if quiet() {
+        return;
+    }
     let secs = duration.as_secs_f32();
     let (color, icon) = if is_error {
         (Color::Red, "\u{2717}")
@@ -416,6 +613,10 @@ pub fn tool_spinner(tool_name: &str, input: &serde_json::Value) -> Spinner {
             .get("path")
             .and_then(|p| p.as_str())
             .map(|p| format!("Writing {p}")),
+        "multi_edit" => input
+            .get("edits")
+            .and_then(|e| e.as_array())
+            .map(|e| format!("Applying {} edits", e.len())),
         "list_dir" => input
             .get("path")
             .and_then(|p| p.as_str())
@@ -424,6 +625,22 @@ pub fn tool_spinner(tool_name: &str, input: &serde_json::Value) -> Spinner {
             .get("pattern")
             .and_then(|p| p.as_str())
             .map(|p| format!("Searching \"{p}\"")),
+        "web_search" => input
+            .get("query")
+            .and_then(|q| q.as_str())
+            .map(|q| format!("Searching the web for \"{q}\"")),
+        "fetch_url" => input
+            .get("url")
+            .and_then(|u| u.as_str())
+            .map(|u| format!("Fetching {u}")),
+        "glob" => input
+            .get("pattern")
+            .and_then(|p| p.as_str())
+            .map(|p| format!("Globbing {p}")),
+        "grep" => input
+            .get("pattern")
+            .and_then(|p| p.as_str())
+            .map(|p| format!("Grepping \"{p}\"")),
         "tree" => input
             .get("path")
             .and_then(|p| p.as_str())
@@ -440,6 +657,7 @@ pub fn tool_spinner(tool_name: &str, input: &serde_json::Value) -> Spinner {
             };
             Some(format!("$ {short}"))
         }
+        "restart_shell" => Some("Restarting shell".to_string()),
         "kv_get" => input
             .get("key")
             .and_then(|k| k.as_str())
@@ -464,6 +682,9 @@ pub fn print_token_usage(
     session_tokens: u64,
     session_cost: i64,
 ) {
+    if quiet() {
+        return;
+    }
     let cost = format_cost(cost_microcents);
     let stotal = format_cost(session_cost);
     println!(
@@ -479,7 +700,8 @@ pub fn print_token_usage(
     );
 }
 
-fn format_cost(microcents: i64) -> String {
+/// Format microcents as dollars, e.g. `$0.0123`.
+pub fn format_cost(microcents: i64) -> String {
     let dollars = microcents as f64 / 1e8;
     format!("${:.4}", dollars)
 }
@@ -494,14 +716,37 @@ fn fmt_tokens(n: u64) -> String {
     }
 }
 
-/// Print the separator line above the prompt.
-pub fn print_separator() {
+/// Print the separator above the prompt with the session's status: model,
+/// provider, context window use, session tokens and cost.
+pub fn print_status_line(
+    model: &str,
+    provider: &str,
+    context: (u64, u64),
+    session_tokens: u64,
+    cost_microcents: i64,
+) {
+    let (used, window) = context;
+    let percent = (used * 100).checked_div(window).unwrap_or(0);
+    let context_color = match percent {
+        0..=59 => Color::DarkGrey,
+        60..=84 => Color::Yellow,
+        _ => Color::Red,
+    };
+    let context = format!("ctx {percent}%");
+    let before = format!(" {model} \u{00B7} {provider} \u{00B7} ");
+    let after = format!(
+        " \u{00B7} {} tok \u{00B7} {} ",
+        fmt_tokens(session_tokens),
+        format_cost(cost_microcents),
+    );
     let width = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
-    let line: String = "\u{2500}".repeat(width);
+    let used_width = 2 + before.chars().count() + context.len() + after.chars().count();
     println!(
-        "\n{}{}{}",
+        "\n{}\u{2500}\u{2500}{before}{}{context}{}{after}{}{}",
+        SetForegroundColor(Color::DarkGrey),
+        SetForegroundColor(context_color),
         SetForegroundColor(Color::DarkGrey),
-        line,
+        "\u{2500}".repeat(width.saturating_sub(used_width)),
         ResetColor,
     );
 }
@@ -557,10 +802,258 @@ pub fn print_banner(model: &str, db_path: &str) {
     );
 }
 
+// ── Attachments ─────────────────────────────────────────────────────
+
+/// List images queued for the next message.
+pub fn print_attachments(pending: &[crate::attachments::Attachment]) {
+    if pending.is_empty() {
+        println!("No images attached. Use /attach <path> or drop an image path into your message.");
+        return;
+    }
+    println!("Attached to your next message:");
+    for attachment in pending {
+        println!(
+            "  {}{}{} {}({} KB, {}){}",
+            SetForegroundColor(Color::Cyan),
+            attachment.name,
+            ResetColor,
+            SetForegroundColor(Color::DarkGrey),
+            attachment.data.len().div_ceil(1024),
+            attachment.media_type,
+            ResetColor,
+        );
+    }
+}
+
+/// Note the images sent with a message.
+pub fn print_attachments_sent(images: &[crate::attachments::Attachment]) {
+    if quiet() {
+        return;
+    }
+    let names: Vec<&str> = images.iter().map(|a| a.name.as_str()).collect();
+    println!(
+        "{}  \u{1F4CE} {}{}",
+        SetForegroundColor(Color::DarkGrey),
+        names.join(", "),
+        ResetColor,
+    );
+}
+
+/// Note the files included from `@path` mentions.
+pub fn print_mentions(mentions: &[crate::mentions::Mention]) {
+    if quiet() {
+        return;
+    }
+    use crate::mentions::Source;
+    for mention in mentions {
+        let (color, detail) = match (&mention.error, mention.source) {
+            (Some(error), _) => (Color::Red, format!("not included: {error}")),
+            (None, source) => {
+                let place = if source == Some(Source::Workspace) { "workspace" } else { "host" };
+                let truncated = if mention.truncated { ", truncated" } else { "" };
+                (Color::DarkGrey, format!("{place}, {:.1} KB{truncated}", mention.bytes as f64 / 1024.0))
+            }
+        };
+        println!(
+            "{}  @ {}{} {}({detail}){}",
+            SetForegroundColor(Color::Cyan),
+            mention.path,
+            ResetColor,
+            SetForegroundColor(color),
+            ResetColor,
+        );
+    }
+}
+
+// ── Help ────────────────────────────────────────────────────────────
+
+/// Built-in REPL commands and what they do.
+const BUILTIN_COMMANDS: &[(&str, &str)] = &[
+    ("/help", "Show this list"),
+    ("/attach [path]", "Attach an image to the next message, or list attached images"),
+    ("/profile [name|none]", "List prompt profiles, or switch to one"),
+    ("/theme [name]", "List code highlighting themes, or switch to one"),
+    ("/undo", "Revert workspace files changed by the last turn"),
+    ("/redo", "Reapply the last undone turn"),
+    ("/checkpoints", "List turns that can be undone or redone"),
+    ("/diff [path]", "Show workspace changes made in this session"),
+    ("/clear", "Clear the conversation"),
+    ("/new", "Start a fresh conversation"),
+    ("/tokens", "Show session token usage"),
+    ("/session", "Show the session ID"),
+    ("/permissions [reset]", "Show tool permissions, or forget remembered approvals"),
+    ("/skills", "List skills"),
+    ("/mcp", "List connected MCP servers"),
+    ("/memory", "Show memory statistics"),
+    ("/remember <text>", "Save a strategy to the playbook (`--ttl 3d <text>` to expire it, `preference: <text>` for a preference)"),
+    ("/quit", "End the session"),
+];
+
+/// Print built-in commands, skills and user-defined commands.
+pub fn print_help(skills: &[(&str, &str)], commands: &[&crate::commands::Command]) {
+    let mut sections: Vec<(&str, Vec<(String, &str)>)> = vec![(
+        "Commands",
+        BUILTIN_COMMANDS.iter().map(|(name, desc)| (name.to_string(), *desc)).collect(),
+    )];
+    if !commands.is_empty() {
+        sections.push((
+            "Custom commands",
+            commands
+                .iter()
+                .map(|c| (format!("/{}", c.name), c.description.as_str()))
+                .collect(),
+        ));
+    }
+    if !skills.is_empty() {
+        sections.push((
+            "Skills",
+            skills.iter().map(|(name, desc)| (format!("/{name}"), *desc)).collect(),
+        ));
+    }
+    for (title, entries) in sections {
+        println!("{}{title}:{}", SetAttribute(Attribute::Bold), SetAttribute(Attribute::Reset));
+        let width = entries.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, desc) in entries {
+            println!(
+                "  {}{name:<width$}{}  {}{desc}{}",
+                SetForegroundColor(Color::Cyan),
+                ResetColor,
+                SetForegroundColor(Color::DarkGrey),
+                ResetColor,
+            );
+        }
+    }
+}
+
+// ── Profiles ────────────────────────────────────────────────────────
+
+/// List prompt profiles, marking the active one.
+pub fn print_profiles(profiles: &[crate::profiles::Profile], active: Option<&str>) {
+    if profiles.is_empty() {
+        println!(
+            "No profiles. Add prompts as {}/<name>.md.",
+            crate::profiles::prompts_dir().display()
+        );
+        return;
+    }
+    println!("Profiles (switch with /profile <name>, clear with /profile none):");
+    for profile in profiles {
+        let marker = if active == Some(profile.name.as_str()) { "*" } else { " " };
+        println!(
+            "{marker} {}{}{} {}{}{}",
+            SetForegroundColor(Color::Cyan),
+            profile.name,
+            ResetColor,
+            SetForegroundColor(Color::DarkGrey),
+            profile.description,
+            ResetColor,
+        );
+    }
+}
+
+/// List the code highlighting themes, marking the one in use.
+pub fn print_themes(themes: &[&str], active: &str) {
+    println!("Themes (switch with /theme <name>):");
+    for theme in themes {
+        let marker = if *theme == active { "*" } else { " " };
+        println!("{marker} {}{theme}{}", SetForegroundColor(Color::Cyan), ResetColor);
+    }
+}
+
+// ── Checkpoints ─────────────────────────────────────────────────────
+
+/// Report the files restored by `/undo` or `/redo`.
+pub fn print_checkpoint_applied(action: &str, applied: &crate::checkpoints::Applied) {
+    use crate::checkpoints::FileOutcome;
+    println!(
+        "{action} \"{}\":",
+        applied.prompt,
+    );
+    for (path, outcome) in &applied.files {
+        let (color, detail) = match outcome {
+            FileOutcome::Written => (Color::Green, "restored"),
+            FileOutcome::Removed => (Color::Green, "removed"),
+            FileOutcome::Conflict => (Color::Yellow, "changed since; left alone"),
+        };
+        println!(
+            "  {}{path}{} {}({detail}){}",
+            SetForegroundColor(Color::Cyan),
+            ResetColor,
+            SetForegroundColor(color),
+            ResetColor,
+        );
+    }
+}
+
+/// Print unified diffs of the files changed during the session.
+pub fn print_diff(changes: &[crate::checkpoints::FileChange]) {
+    if changes.is_empty() {
+        println!("No workspace files changed in this session.");
+        return;
+    }
+    for change in changes {
+        let Some(diff) = change.unified_diff() else {
+            println!(
+                "{}{}{} {}(binary file changed){}",
+                SetAttribute(Attribute::Bold),
+                change.path,
+                SetAttribute(Attribute::Reset),
+                SetForegroundColor(Color::DarkGrey),
+                ResetColor,
+            );
+            continue;
+        };
+        for line in diff.lines() {
+            let color = if line.starts_with("+++") || line.starts_with("---") {
+                Color::White
+            } else if line.starts_with('+') {
+                Color::Green
+            } else if line.starts_with('-') {
+                Color::Red
+            } else if line.starts_with("@@") {
+                Color::Cyan
+            } else {
+                Color::DarkGrey
+            };
+            println!("{}{line}{}", SetForegroundColor(color), ResetColor);
+        }
+    }
+}
+
+/// List the turns `/undo` and `/redo` can step through.
+pub fn print_checkpoints(history: &crate::checkpoints::History) {
+    if history.done.is_empty() && history.undone.is_empty() {
+        println!("No checkpoints yet. Turns that change workspace files can be undone with /undo.");
+        return;
+    }
+    for (label, checkpoints) in [("Undo", &history.done), ("Redo", &history.undone)] {
+        if checkpoints.is_empty() {
+            continue;
+        }
+        println!("{label} (most recent first):");
+        for checkpoint in checkpoints.iter().rev() {
+            let time = checkpoint.created_at.get(11..19).unwrap_or(&checkpoint.created_at);
+            println!(
+                "  {}{time}{} {} {}({} files){}",
+                SetForegroundColor(Color::DarkGrey),
+                ResetColor,
+                checkpoint.prompt,
+                SetForegroundColor(Color::DarkGrey),
+                checkpoint.files.len(),
+                ResetColor,
+            );
+        }
+    }
+}
+
 // ── MCP & Skills ─────────────────────────────────────────────────────
 
 /// Print MCP server status at startup.
+#[allow(dead_code)]
 pub fn print_mcp_status(name: &str, tool_count: usize) {
+    if quiet() {
+        return;
+    }
     println!(
         "  {}mcp: {name} ({tool_count} tools){}",
         SetForegroundColor(Color::DarkGrey),
@@ -568,6 +1061,16 @@ pub fn print_mcp_status(name: &str, tool_count: usize) {
     );
 }
 
+/// Report a hook that failed to run.
+pub fn print_hook_error(event: &str, error: &str) {
+    eprintln!(
+        "  {}{}hook ({event}): {error}{}",
+        SetForegroundColor(Color::Red),
+        SetAttribute(Attribute::Dim),
+        SetAttribute(Attribute::Reset),
+    );
+}
+
 /// Print MCP server error at startup.
 pub fn print_mcp_error(name: &str, error: &str) {
     eprintln!(
@@ -578,8 +1081,8 @@ pub fn print_mcp_error(name: &str, error: &str) {
     );
 }
 
-/// Print the list of available skills.
-pub fn print_skills_list(skills: &[(&str, &str)]) {
+/// Print the list of available skills with their scope.
+pub fn print_skills_list(skills: &[&crate::skills::Skill]) {
     if skills.is_empty() {
         println!("No skills loaded.");
         return;
@@ -590,15 +1093,84 @@ pub fn print_skills_list(skills: &[(&str, &str)]) {
         SetAttribute(Attribute::Bold),
         SetAttribute(Attribute::Reset),
     );
-    for (name, desc) in skills {
+    for skill in skills {
+        println!(
+            "  {}  /{}{}  {}[{}]{}  — {}",
+            SetForegroundColor(Color::Cyan),
+            skill.name,
+            ResetColor,
+            SetForegroundColor(Color::DarkGrey),
+            skill.scope.as_str(),
+            ResetColor,
+            skill.description,
+        );
+    }
+}
+
+/// Print a skill's stored versions (for `skills history`).
+pub fn print_skill_history(name: &str, versions: &[crate::skills::SkillVersion]) {
+    if versions.is_empty() {
+        println!("Skill '{name}' not found in DB.");
+        return;
+    }
+    println!(
+        "{}{}History of /{name}:{}",
+        SetForegroundColor(Color::Cyan),
+        SetAttribute(Attribute::Bold),
+        SetAttribute(Attribute::Reset),
+    );
+    for v in versions {
+        let marker = if v.current { " (current)" } else { "" };
+        let updated = if v.updated.is_empty() { "-" } else { &v.updated };
+        let first_line = v.body.lines().next().unwrap_or("");
+        println!(
+            "  {}v{}{}{marker}  {updated}  {} chars  — {first_line}",
+            SetForegroundColor(Color::Cyan),
+            v.version,
+            ResetColor,
+            v.body.len(),
+        );
+    }
+}
+
+/// Print the tool calls a skill dry run would have made (for `skills test`).
+pub fn print_planned_calls(calls: &[crate::executor::PlannedCall]) {
+    println!();
+    if calls.is_empty() {
+        println!("No changes planned: the skill only used read-only tools.");
+        return;
+    }
+    println!(
+        "{}{}Tool calls it would make:{}",
+        SetForegroundColor(Color::Cyan),
+        SetAttribute(Attribute::Bold),
+        SetAttribute(Attribute::Reset),
+    );
+    for (i, call) in calls.iter().enumerate() {
+        let mut input = call.input.to_string();
+        if input.len() > 200 {
+            let cut = (0..=200).rev().find(|&i| input.is_char_boundary(i)).unwrap_or(0);
+            input = format!("{}…", &input[..cut]);
+        }
         println!(
-            "  {}  /{name}{}  — {desc}",
+            "  {}{}. {}{}  {input}",
             SetForegroundColor(Color::Cyan),
+            i + 1,
+            call.tool,
             ResetColor,
         );
     }
 }
 
+/// Note that edited skill files were picked up.
+pub fn print_skills_reloaded(count: usize) {
+    println!(
+        "{}Skills reloaded ({count} available).{}",
+        SetForegroundColor(Color::DarkGrey),
+        ResetColor,
+    );
+}
+
 /// Print configured MCP server list (for `mcp list` command).
 pub fn print_mcp_server_list(servers: &[(String, crate::mcp_client::McpServerEntry)]) {
     if servers.is_empty() {
@@ -621,11 +1193,11 @@ pub fn print_mcp_server_list(servers: &[(String, crate::mcp_client::McpServerEnt
             format!(" {}", entry.args.join(" "))
         };
         println!(
-            "  {}  {name}{} — {}{}{}",
+            "  {}  {name}{} — {}{}{args}{}",
             SetForegroundColor(Color::Cyan),
             ResetColor,
             SetForegroundColor(Color::DarkGrey),
-            format!("{}{args}", entry.command),
+            entry.command,
             ResetColor,
         );
     }
@@ -634,7 +1206,11 @@ pub fn print_mcp_server_list(servers: &[(String, crate::mcp_client::McpServerEnt
 // ── Memory ──────────────────────────────────────────────────────────
 
 /// Print memory system status at startup.
+#[allow(dead_code)]
 pub fn print_memory_status(provider_count: usize, reflection: bool) {
+    if quiet() {
+        return;
+    }
     let reflect_str = if reflection { " + reflection" } else { "" };
     println!(
         "  {}memory: {provider_count} providers{reflect_str}{}",
@@ -651,6 +1227,7 @@ pub fn print_startup_status(
     memory_info: Option<&str>,
     tool_names: &[&str],
     mcp_info: &[(String, usize)],
+    project_files: &[&str],
 ) {
     println!();
 
@@ -680,6 +1257,16 @@ pub fn print_startup_status(
         );
     }
 
+    // Project line
+    if !project_files.is_empty() {
+        println!(
+            "  {}\u{2713}{} Project: {}",
+            SetForegroundColor(Color::Green),
+            ResetColor,
+            project_files.join(", "),
+        );
+    }
+
     // Memory line
     if let Some(info) = memory_info {
         println!(
@@ -759,6 +1346,76 @@ pub fn print_playbook_entries(entries: &[(String, String, i32, i32)]) {
     }
 }
 
+/// Print memory entries with their ids for `memory show --ids`.
+pub fn print_memory_ids(entries: &[crate::memory::EntryId]) {
+    if entries.is_empty() {
+        println!("No memory entries yet.");
+        return;
+    }
+    let width = entries.iter().map(|e| e.id.len()).max().unwrap_or(0);
+    for entry in entries {
+        println!(
+            "  {}{:<width$}{}  {}[{}]{} {}",
+            SetForegroundColor(Color::Cyan),
+            entry.id,
+            ResetColor,
+            SetForegroundColor(Color::DarkGrey),
+            entry.provider,
+            ResetColor,
+            entry.summary,
+        );
+    }
+}
+
+/// Print the episode timeline for `memory timeline`, oldest first.
+pub fn print_timeline(entries: &[crate::memory::timeline::TimelineEntry]) {
+    if entries.is_empty() {
+        println!("No episodes yet. Episodes are recorded at the end of each session.");
+        return;
+    }
+    for entry in entries {
+        let date = entry.created.get(..16).unwrap_or(&entry.created).replace('T', " ");
+        let outcome_color = match entry.outcome.as_str() {
+            "completed" | "success" => Color::Green,
+            "mixed" => Color::Yellow,
+            _ => Color::Red,
+        };
+        let agent = entry.agent_name.as_deref().map(|a| format!(" {a}")).unwrap_or_default();
+        println!(
+            "{}{date}{}  {}{}{}  {}{}{agent}{}",
+            SetForegroundColor(Color::Cyan),
+            ResetColor,
+            SetForegroundColor(outcome_color),
+            entry.outcome,
+            ResetColor,
+            SetForegroundColor(Color::DarkGrey),
+            crate::memory::short_id(&entry.session_id),
+            ResetColor,
+        );
+        println!("  {}", entry.summary);
+        if !entry.summarizes.is_empty() {
+            println!(
+                "  {}summarizes {} archived sessions{}",
+                SetForegroundColor(Color::DarkGrey),
+                entry.summarizes.len(),
+                ResetColor,
+            );
+        }
+        for decision in &entry.key_decisions {
+            println!("  {}•{} {decision}", SetForegroundColor(Color::DarkGrey), ResetColor);
+        }
+        if !entry.files.is_empty() {
+            println!(
+                "  {}files:{} {}",
+                SetForegroundColor(Color::DarkGrey),
+                ResetColor,
+                entry.files.join(", "),
+            );
+        }
+        println!();
+    }
+}
+
 // ── Search & Compaction ─────────────────────────────────────────────
 
 /// Print BM25 search results.
@@ -804,6 +1461,12 @@ pub fn print_compaction_report(report: &crate::memory::compaction::CompactionRep
         SetAttribute(Attribute::Bold),
         SetAttribute(Attribute::Reset),
     );
+    println!(
+        "  {}  expired removed{}: {}",
+        SetForegroundColor(Color::Cyan),
+        ResetColor,
+        report.expired_removed,
+    );
     println!(
         "  {}  duplicates removed{}: {}",
         SetForegroundColor(Color::Cyan),
@@ -811,10 +1474,11 @@ pub fn print_compaction_report(report: &crate::memory::compaction::CompactionRep
         report.duplicates_removed,
     );
     println!(
-        "  {}  episodes compressed{}: {}",
+        "  {}  episodes summarized{}: {} into {} summaries",
         SetForegroundColor(Color::Cyan),
         ResetColor,
         report.episodes_compressed,
+        report.summaries_created,
     );
     println!(
         "  {}  tier changes{}: {}",
@@ -837,10 +1501,17 @@ fn tool_display_name(name: &str) -> String {
     match name {
         "read_file" => "Read".to_string(),
         "write_file" => "Write".to_string(),
+        "multi_edit" => "Edit".to_string(),
         "list_dir" => "List".to_string(),
         "search" => "Search".to_string(),
+        "grep" => "Grep".to_string(),
+        "fetch_url" => "Fetch".to_string(),
+        "web_search" => "Web Search".to_string(),
+        "task" => "Task".to_string(),
+        "glob" => "Glob".to_string(),
         "tree" => "Tree".to_string(),
         "bash" => "Bash".to_string(),
+        "restart_shell" => "Restart Shell".to_string(),
         "kv_get" => "KV Get".to_string(),
         "kv_set" => "KV Set".to_string(),
         _ => name.to_string(),
@@ -863,11 +1534,51 @@ fn tool_param_summary(tool_name: &str, params: &serde_json::Map<String, serde_js
                 .unwrap_or(0);
             format!("{path} ({len} bytes)")
         }
+        "multi_edit" => {
+            let edits = params
+                .get("edits")
+                .and_then(|v| v.as_array())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut paths: Vec<&str> = edits
+                .iter()
+                .filter_map(|e| e.get("path").and_then(|p| p.as_str()))
+                .collect();
+            paths.dedup();
+            format!("{} ({} edits)", paths.join(", "), edits.len())
+        }
         "search" => params
             .get("pattern")
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string(),
+        "task" => params
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        "web_search" => params
+            .get("query")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        "fetch_url" => params
+            .get("url")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        "glob" => params
+            .get("pattern")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        "grep" => {
+            let pattern = params.get("pattern").and_then(|v| v.as_str()).unwrap_or("");
+            match params.get("path").and_then(|v| v.as_str()) {
+                Some(path) => format!("{pattern} in {path}"),
+                None => pattern.to_string(),
+            }
+        }
         "bash" => {
             let cmd = params.get("command").and_then(|v| v.as_str()).unwrap_or("");
             if cmd.len() > 80 {
@@ -18,6 +18,8 @@ pub enum AgentError {
     Api { status: u16, message: String },
     /// Tool execution errors.
     Tool(String),
+    /// A tool call refused by permission policy or by the user.
+    PermissionDenied(String),
     /// Stream parsing errors.
     Stream(String),
     /// Configuration errors.
@@ -40,6 +42,7 @@ impl fmt::Display for AgentError {
             Self::Auth(msg) => write!(f, "Auth error: {msg}"),
             Self::Api { status, message } => write!(f, "API error ({status}): {message}"),
             Self::Tool(msg) => write!(f, "Tool error: {msg}"),
+            Self::PermissionDenied(msg) => write!(f, "Permission denied: {msg}"),
             Self::Stream(msg) => write!(f, "Stream error: {msg}"),
             Self::Config(msg) => write!(f, "Config error: {msg}"),
             Self::Mcp(msg) => write!(f, "MCP error: {msg}"),
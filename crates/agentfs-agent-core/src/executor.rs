@@ -0,0 +1,840 @@
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use agentfs_core::filesystem::{EntryKind, FileEdit, GrepOptions, GrepResult, SearchFilter};
+use agentfs_core::AgentFS;
+
+use crate::error::{AgentError, Result};
+use crate::hooks::{Hooks, HooksConfig};
+use crate::mcp_client::McpManager;
+use crate::permissions::{self, PermissionConfig, Permissions};
+use crate::shell::Shell;
+use crate::tools;
+use crate::web::{WebClient, WebConfig};
+
+/// A tool call a dry run recorded instead of running.
+#[derive(Debug, Clone)]
+pub struct PlannedCall {
+    pub tool: String,
+    pub input: Value,
+}
+
+/// Executes tool calls against AgentFS and the host shell.
+pub struct ToolExecutor {
+    pub db: Arc<AgentFS>,
+    pub session_id: String,
+    pub mcp: Option<Arc<Mutex<McpManager>>>,
+    pub web: Arc<WebClient>,
+    /// Built-in tools this executor may run; `None` allows all.
+    allowed_tools: Option<&'static [&'static str]>,
+    /// Tool name globs the current turn may use; `None` allows all.
+    turn_tools: Option<Vec<String>>,
+    /// Directory the current turn may `read_file` under even when
+    /// `turn_tools` leaves it out (a skill's bundled resources).
+    turn_reads: Option<String>,
+    pub permissions: Arc<Permissions>,
+    pub hooks: Arc<Hooks>,
+    /// Shell behind `bash`, started on first use so state persists.
+    shell: Mutex<Option<Shell>>,
+    /// Run only read-only tools; record the rest in `planned`. Nothing is
+    /// logged to the database.
+    dry_run: bool,
+    planned: std::sync::Mutex<Vec<PlannedCall>>,
+}
+
+/// Default and longest `bash` timeout, in seconds.
+const BASH_TIMEOUT_SECS: u64 = 30;
+const BASH_MAX_TIMEOUT_SECS: u64 = 600;
+
+impl ToolExecutor {
+    pub fn new(db: AgentFS, session_id: String) -> Self {
+        Self {
+            db: Arc::new(db),
+            session_id,
+            mcp: None,
+            web: Arc::new(WebClient::new(WebConfig::default())),
+            allowed_tools: None,
+            turn_tools: None,
+            turn_reads: None,
+            permissions: Arc::new(Permissions::new(PermissionConfig::default())),
+            hooks: Arc::new(Hooks::new(HooksConfig::default())),
+            shell: Mutex::new(None),
+            dry_run: false,
+            planned: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// An executor for a subagent session: same database and web client,
+    /// no MCP servers, its own shell, and only the built-in tools in `tools`.
+    pub fn child(&self, session_id: String, tools: &'static [&'static str]) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            session_id,
+            mcp: None,
+            web: Arc::clone(&self.web),
+            allowed_tools: Some(tools),
+            turn_tools: None,
+            turn_reads: None,
+            permissions: Arc::clone(&self.permissions),
+            hooks: Arc::clone(&self.hooks),
+            shell: Mutex::new(None),
+            dry_run: self.dry_run,
+            planned: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_mcp(mut self, mcp: Arc<Mutex<McpManager>>) -> Self {
+        self.mcp = Some(mcp);
+        self
+    }
+
+    pub fn with_web(mut self, web: WebClient) -> Self {
+        self.web = Arc::new(web);
+        self
+    }
+
+    pub fn with_permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = Arc::new(permissions);
+        self
+    }
+
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = Arc::new(hooks);
+        self
+    }
+
+    /// Run read-only tools as usual but only record the others (see
+    /// [`ToolExecutor::planned_calls`]), without logging anything.
+    pub fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Tool calls a dry run recorded instead of running, in order.
+    pub fn planned_calls(&self) -> Vec<PlannedCall> {
+        self.planned.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    /// Whether a dry run records `tool_name` rather than running it.
+    fn dry_run_skips(&self, tool_name: &str) -> bool {
+        self.dry_run && tool_name != tools::TASK_TOOL && !tools::TASK_TOOLS.contains(&tool_name)
+    }
+
+    /// Restrict the current turn to the tools matching `globs`, or lift
+    /// the restriction with `None`. Calls to other tools fail unrun, except
+    /// `read_file` of paths under `reads`.
+    pub fn set_turn_tools(&mut self, globs: Option<Vec<String>>, reads: Option<String>) {
+        self.turn_tools = globs;
+        self.turn_reads = reads;
+    }
+
+    /// Whether the current turn may use `tool_name` at all.
+    pub fn turn_allows(&self, tool_name: &str) -> bool {
+        self.turn_globs_allow(tool_name) || (tool_name == "read_file" && self.turn_reads.is_some())
+    }
+
+    /// Whether the current turn may make this call: `read_file` outside
+    /// the turn's globs only reaches paths under its read directory.
+    pub fn turn_permits(&self, tool_name: &str, input: &Value) -> bool {
+        if self.turn_globs_allow(tool_name) {
+            return true;
+        }
+        let path = input.get("path").and_then(|p| p.as_str());
+        tool_name == "read_file"
+            && self
+                .turn_reads
+                .as_deref()
+                .zip(path)
+                .is_some_and(|(dir, path)| path_under(path, dir))
+    }
+
+    fn turn_globs_allow(&self, tool_name: &str) -> bool {
+        self.turn_tools
+            .as_ref()
+            .is_none_or(|globs| globs.iter().any(|g| permissions::glob_match(g, tool_name)))
+    }
+
+    /// Check a tool call against the permission policy, asking the user if
+    /// needed, then run the `pre_tool_call` hooks. Call before `execute`,
+    /// while nothing else draws on the terminal.
+    pub async fn authorize(&self, tool_name: &str, input: &Value) -> Result<()> {
+        // A call a dry run won't make needs no permission.
+        if self.dry_run_skips(tool_name) {
+            return Ok(());
+        }
+        let mut result = self.permissions.authorize(&self.db.kv, tool_name, input).await;
+        if result.is_ok() {
+            if let Err(reason) = self.hooks.pre_tool_call(&self.session_id, tool_name, input).await {
+                result = Err(AgentError::PermissionDenied(format!("blocked by hook: {reason}")));
+            }
+        }
+        if let Err(AgentError::PermissionDenied(reason)) = &result {
+            let _ = self
+                .db
+                .events
+                .log(
+                    Some(&self.session_id),
+                    &format!("permission_denied:{tool_name}"),
+                    input.get("path").and_then(|p| p.as_str()),
+                    Some(reason),
+                )
+                .await;
+        }
+        result
+    }
+
+    /// Execute a tool call and return the result as a string.
+    pub async fn execute(&self, tool_name: &str, input: &Value) -> Result<String> {
+        if self.turn_permits(tool_name, input) && self.dry_run_skips(tool_name) {
+            if let Ok(mut planned) = self.planned.lock() {
+                planned.push(PlannedCall {
+                    tool: tool_name.to_string(),
+                    input: input.clone(),
+                });
+            }
+            return Ok(format!(
+                "Dry run: {tool_name} was not run. Assume it succeeded and carry on with the plan."
+            ));
+        }
+
+        // Log tool start
+        let tc_id = if self.dry_run {
+            None
+        } else {
+            self.db
+                .tools
+                .start(Some(&self.session_id), tool_name, Some(&input.to_string()))
+                .await
+                .ok()
+        };
+
+        let result = if !self.turn_permits(tool_name, input) {
+            Err(AgentError::PermissionDenied(format!(
+                "{tool_name} is not available in this turn"
+            )))
+        } else if McpManager::is_mcp_tool(tool_name) {
+            // Route to MCP server
+            match &self.mcp {
+                Some(mcp) => {
+                    let mut manager = mcp.lock().await;
+                    manager.call_tool(tool_name, input).await
+                }
+                None => Err(AgentError::Mcp(format!(
+                    "MCP tool '{tool_name}' called but no MCP manager available"
+                ))),
+            }
+        } else if self.allowed_tools.is_some_and(|allowed| !allowed.contains(&tool_name)) {
+            Err(AgentError::Tool(format!(
+                "{tool_name} is not available in this session"
+            )))
+        } else {
+            match tool_name {
+                "read_file" => self.exec_read_file(input).await,
+                "write_file" => self.exec_write_file(input).await,
+                "multi_edit" => self.exec_multi_edit(input).await,
+                "list_dir" => self.exec_list_dir(input).await,
+                "search" => self.exec_search(input).await,
+                "grep" => self.exec_grep(input).await,
+                "glob" => self.exec_glob(input).await,
+                "tree" => self.exec_tree(input).await,
+                "bash" => self.exec_bash(input).await,
+                "restart_shell" => self.exec_restart_shell().await,
+                "fetch_url" => self.exec_fetch_url(input).await,
+                "web_search" => self.exec_web_search(input).await,
+                "kv_get" => self.exec_kv_get(input).await,
+                "kv_set" => self.exec_kv_set(input).await,
+                _ => Err(AgentError::Tool(format!("Unknown tool: {tool_name}"))),
+            }
+        };
+
+        if self.dry_run {
+            return result;
+        }
+
+        // Log result
+        match &result {
+            Ok(output) => {
+                if let Some(id) = tc_id {
+                    let truncated = if output.len() > 1000 {
+                        format!("{}...", &output[..1000])
+                    } else {
+                        output.clone()
+                    };
+                    let _ = self.db.tools.success(id, Some(&truncated)).await;
+                }
+                let _ = self
+                    .db
+                    .events
+                    .log(
+                        Some(&self.session_id),
+                        &format!("tool:{tool_name}"),
+                        input.get("path").and_then(|p| p.as_str()),
+                        None,
+                    )
+                    .await;
+            }
+            Err(e) => {
+                if let Some(id) = tc_id {
+                    let _ = self.db.tools.error(id, &e.to_string()).await;
+                }
+                let _ = self
+                    .db
+                    .events
+                    .log(
+                        Some(&self.session_id),
+                        &format!("tool_error:{tool_name}"),
+                        None,
+                        Some(&e.to_string()),
+                    )
+                    .await;
+            }
+        }
+
+        result
+    }
+
+    async fn exec_read_file(&self, input: &Value) -> Result<String> {
+        let path = input
+            .get("path")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| AgentError::Tool("read_file: missing 'path' parameter".to_string()))?;
+
+        let data = self.db.fs.read_file(path).await?;
+        Ok(String::from_utf8_lossy(&data).to_string())
+    }
+
+    async fn exec_write_file(&self, input: &Value) -> Result<String> {
+        let path = input
+            .get("path")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| AgentError::Tool("write_file: missing 'path' parameter".to_string()))?;
+        let content = input
+            .get("content")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| {
+                AgentError::Tool("write_file: missing 'content' parameter".to_string())
+            })?;
+
+        self.db.fs.write_file(path, content.as_bytes()).await?;
+        Ok(format!("Written {} bytes to {path}", content.len()))
+    }
+
+    async fn exec_multi_edit(&self, input: &Value) -> Result<String> {
+        let edits = input
+            .get("edits")
+            .cloned()
+            .ok_or_else(|| AgentError::Tool("multi_edit: missing 'edits' parameter".to_string()))?;
+        let edits: Vec<FileEdit> = serde_json::from_value(edits)
+            .map_err(|e| AgentError::Tool(format!("multi_edit: invalid 'edits': {e}")))?;
+        if edits.is_empty() {
+            return Err(AgentError::Tool("multi_edit: 'edits' is empty".to_string()));
+        }
+
+        let count = edits.len();
+        let summaries = self.db.fs.edit_files(edits).await?;
+        let mut output = format!("Applied {count} edits to {} files:\n", summaries.len());
+        for s in &summaries {
+            output.push_str(&format!(
+                "  {} ({} edits, {} replacements, {} bytes)\n",
+                s.path, s.edits, s.replacements, s.size
+            ));
+        }
+        Ok(output)
+    }
+
+    async fn exec_list_dir(&self, input: &Value) -> Result<String> {
+        let path = input
+            .get("path")
+            .and_then(|p| p.as_str())
+            .unwrap_or("/");
+
+        let entries = self.db.fs.readdir(path).await?;
+        let mut output = String::new();
+        for entry in &entries {
+            let kind = if (entry.mode & 0o170000) == 0o040000 {
+                "dir"
+            } else {
+                "file"
+            };
+            output.push_str(&format!("[{kind}] {}\n", entry.name));
+        }
+        if output.is_empty() {
+            output = "(empty directory)\n".to_string();
+        }
+        Ok(output)
+    }
+
+    async fn exec_search(&self, input: &Value) -> Result<String> {
+        let pattern = input
+            .get("pattern")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| AgentError::Tool("search: missing 'pattern' parameter".to_string()))?;
+
+        let results = self.db.fs.search(pattern).await?;
+        let mut output = String::new();
+        for result in &results {
+            let kind = if result.is_dir { "dir" } else { "file" };
+            output.push_str(&format!("[{kind}] {} ({} bytes)\n", result.path, result.size));
+        }
+        if output.is_empty() {
+            output = "(no matches)\n".to_string();
+        }
+        Ok(output)
+    }
+
+    async fn exec_glob(&self, input: &Value) -> Result<String> {
+        let pattern = input
+            .get("pattern")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| AgentError::Tool("glob: missing 'pattern' parameter".to_string()))?;
+        let limit = input
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(100)
+            .clamp(1, 1000) as usize;
+        let dir = input
+            .get("path")
+            .and_then(|p| p.as_str())
+            .unwrap_or("")
+            .trim_end_matches('/');
+        let pattern = if dir.is_empty() {
+            pattern.to_string()
+        } else {
+            format!("{dir}/{}", pattern.trim_start_matches('/'))
+        };
+
+        let filter = SearchFilter {
+            path: Some(pattern),
+            kind: Some(EntryKind::File),
+            ..Default::default()
+        };
+        let mut files = self.db.fs.find(&filter).await?;
+        // Newest first; `find` already sorted by path, which breaks ties.
+        files.sort_by(|a, b| b.mtime.cmp(&a.mtime));
+
+        if files.is_empty() {
+            return Ok("(no matches)\n".to_string());
+        }
+        let mut output = String::new();
+        for file in files.iter().take(limit) {
+            output.push_str(&file.path);
+            output.push('\n');
+        }
+        if files.len() > limit {
+            output.push_str(&format!(
+                "(showing {limit} of {} matches; narrow the pattern to see more)\n",
+                files.len()
+            ));
+        }
+        Ok(output)
+    }
+
+    async fn exec_grep(&self, input: &Value) -> Result<String> {
+        let pattern = input
+            .get("pattern")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| AgentError::Tool("grep: missing 'pattern' parameter".to_string()))?;
+        let int = |key: &str, default: u64, max: u64| {
+            input
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .unwrap_or(default)
+                .min(max) as usize
+        };
+
+        let opts = GrepOptions {
+            pattern: pattern.to_string(),
+            path: input.get("path").and_then(|p| p.as_str()).map(str::to_string),
+            ignore_case: input
+                .get("ignore_case")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            fixed_strings: false,
+            max_matches: int("max_results", 50, 500).max(1),
+            context: int("context", 0, 10),
+        };
+        let result = self.db.fs.grep(&opts).await?;
+        Ok(render_grep(&result, opts.max_matches))
+    }
+
+    async fn exec_tree(&self, input: &Value) -> Result<String> {
+        let path = input
+            .get("path")
+            .and_then(|p| p.as_str())
+            .unwrap_or("/");
+
+        let tree = self.db.fs.tree(path).await?;
+        let mut output = String::new();
+        render_tree_node(&tree, "", true, &mut output);
+        Ok(output)
+    }
+
+    async fn exec_bash(&self, input: &Value) -> Result<String> {
+        let command = input
+            .get("command")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| AgentError::Tool("bash: missing 'command' parameter".to_string()))?;
+        let timeout = input
+            .get("timeout")
+            .and_then(|t| t.as_u64())
+            .unwrap_or(BASH_TIMEOUT_SECS)
+            .clamp(1, BASH_MAX_TIMEOUT_SECS);
+
+        let mut slot = self.shell.lock().await;
+        let shell = match slot.as_mut() {
+            Some(shell) => shell,
+            None => slot.insert(Shell::spawn().await?),
+        };
+        let output = shell
+            .run(command, std::time::Duration::from_secs(timeout))
+            .await?;
+
+        let mut notes = Vec::new();
+        if output.timed_out {
+            notes.push(format!("[timed out after {timeout} seconds; the command was interrupted]"));
+        } else if let Some(code) = output.exit_code.filter(|&c| c != 0) {
+            notes.push(format!("[exit code: {code}]"));
+        }
+        if output.exited {
+            if let Some(shell) = slot.take() {
+                shell.kill().await;
+            }
+            notes.push("[shell exited; the next command starts a new shell]".to_string());
+        }
+
+        let mut result = output.output;
+        for note in notes {
+            if !result.is_empty() {
+                result.push('\n');
+            }
+            result.push_str(&note);
+        }
+        if result.is_empty() {
+            result = "(no output)".to_string();
+        }
+        Ok(result)
+    }
+
+    async fn exec_restart_shell(&self) -> Result<String> {
+        match self.shell.lock().await.take() {
+            Some(shell) => {
+                shell.kill().await;
+                Ok("Shell stopped; the next bash command starts a fresh shell in the original directory.".to_string())
+            }
+            None => Ok("No shell was running; the next bash command starts a fresh shell.".to_string()),
+        }
+    }
+
+    async fn exec_fetch_url(&self, input: &Value) -> Result<String> {
+        let url = input
+            .get("url")
+            .and_then(|u| u.as_str())
+            .ok_or_else(|| AgentError::Tool("fetch_url: missing 'url' parameter".to_string()))?;
+        self.web.fetch(url).await
+    }
+
+    async fn exec_web_search(&self, input: &Value) -> Result<String> {
+        let query = input
+            .get("query")
+            .and_then(|q| q.as_str())
+            .ok_or_else(|| AgentError::Tool("web_search: missing 'query' parameter".to_string()))?;
+        let count = input
+            .get("max_results")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize);
+        self.web.search(query, count).await
+    }
+
+    async fn exec_kv_get(&self, input: &Value) -> Result<String> {
+        let key = input
+            .get("key")
+            .and_then(|k| k.as_str())
+            .ok_or_else(|| AgentError::Tool("kv_get: missing 'key' parameter".to_string()))?;
+
+        match self.db.kv.get(key).await {
+            Ok(entry) => Ok(entry.value),
+            Err(agentfs_core::error::AgentFSError::KeyNotFound { key }) => {
+                Ok(format!("(key not found: {key})"))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn exec_kv_set(&self, input: &Value) -> Result<String> {
+        let key = input
+            .get("key")
+            .and_then(|k| k.as_str())
+            .ok_or_else(|| AgentError::Tool("kv_set: missing 'key' parameter".to_string()))?;
+        let value = input
+            .get("value")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| AgentError::Tool("kv_set: missing 'value' parameter".to_string()))?;
+        if agentfs_core::kvstore::is_protected(key) {
+            return Err(AgentError::Tool(format!(
+                "kv_set: '{key}' holds the user's tool approvals or the agent's configuration and can't be written by tools"
+            )));
+        }
+
+        self.db.kv.set(key, value).await?;
+        Ok(format!("Set key '{key}'"))
+    }
+}
+
+/// Whether AgentFS `path` names `dir` or something inside it. Paths with
+/// `.` or `..` components never match.
+fn path_under(path: &str, dir: &str) -> bool {
+    let parts: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    if parts.iter().any(|c| matches!(*c, "." | "..")) {
+        return false;
+    }
+    let prefix: Vec<&str> = dir.split('/').filter(|c| !c.is_empty()).collect();
+    parts.starts_with(&prefix)
+}
+
+/// Render grep matches grouped by file, ripgrep style: `N:` marks a
+/// matching line, `N-` a context line, and `--` a gap between hunks.
+fn render_grep(result: &GrepResult, limit: usize) -> String {
+    if result.matches.is_empty() {
+        return format!("(no matches in {} files)\n", result.files_searched);
+    }
+
+    let mut output = String::new();
+    let mut files = 0;
+    // Last line number printed for the current file.
+    let mut printed = 0;
+    for (i, m) in result.matches.iter().enumerate() {
+        let same_file = i > 0 && result.matches[i - 1].path == m.path;
+        let first = m.line_number - m.before.len();
+        if !same_file {
+            if i > 0 {
+                output.push('\n');
+            }
+            output.push_str(&m.path);
+            output.push('\n');
+            files += 1;
+            printed = 0;
+        } else if first > printed + 1 {
+            output.push_str("--\n");
+        }
+
+        for (n, line) in (first..).zip(&m.before) {
+            if n > printed {
+                output.push_str(&format!("{n}-{line}\n"));
+            }
+        }
+        output.push_str(&format!("{}:{}\n", m.line_number, m.line));
+        printed = m.line_number;
+
+        // Stop context short of the next match; it prints its own line.
+        let next = result
+            .matches
+            .get(i + 1)
+            .filter(|next| next.path == m.path)
+            .map_or(usize::MAX, |next| next.line_number);
+        for (n, line) in (m.line_number + 1..next).zip(&m.after) {
+            output.push_str(&format!("{n}-{line}\n"));
+            printed = n;
+        }
+    }
+
+    output.push_str(&format!(
+        "\n{} matches in {files} files ({} files searched)\n",
+        result.matches.len(),
+        result.files_searched
+    ));
+    if result.truncated {
+        output.push_str(&format!(
+            "(stopped at {limit} matches; narrow the pattern or path to see more)\n"
+        ));
+    }
+    output
+}
+
+/// Render a tree node with indentation.
+fn render_tree_node(
+    node: &agentfs_core::filesystem::TreeNode,
+    prefix: &str,
+    is_last: bool,
+    output: &mut String,
+) {
+    let connector = if prefix.is_empty() {
+        ""
+    } else if is_last {
+        "└── "
+    } else {
+        "├── "
+    };
+
+    let kind = if node.stat.is_dir() { "/" } else { "" };
+    output.push_str(&format!("{prefix}{connector}{}{kind}\n", node.name));
+
+    let child_prefix = if prefix.is_empty() {
+        String::new()
+    } else if is_last {
+        format!("{prefix}    ")
+    } else {
+        format!("{prefix}│   ")
+    };
+
+    let len = node.children.len();
+    for (i, child) in node.children.iter().enumerate() {
+        render_tree_node(child, &child_prefix, i == len - 1, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::filesystem::GrepMatch;
+
+    fn m(path: &str, line_number: usize, before: &[&str], after: &[&str]) -> GrepMatch {
+        GrepMatch {
+            path: path.to_string(),
+            line_number,
+            line: format!("match {line_number}"),
+            before: before.iter().map(|s| s.to_string()).collect(),
+            after: after.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_render_grep_merges_context() {
+        let result = GrepResult {
+            matches: vec![
+                m("/a.rs", 2, &["one"], &["match 3"]),
+                m("/a.rs", 3, &["match 2"], &["four"]),
+                m("/a.rs", 9, &["eight"], &[]),
+                m("/b.rs", 1, &[], &["two"]),
+            ],
+            files_searched: 5,
+            truncated: true,
+        };
+        let output = render_grep(&result, 4);
+        assert_eq!(
+            output,
+            "/a.rs\n1-one\n2:match 2\n3:match 3\n4-four\n--\n8-eight\n9:match 9\n\n\
+             /b.rs\n1:match 1\n2-two\n\n\
+             4 matches in 2 files (5 files searched)\n\
+             (stopped at 4 matches; narrow the pattern or path to see more)\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn turn_tools_restrict_execution() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        let mut executor = ToolExecutor::new(db, "s1".into());
+        let input = serde_json::json!({ "key": "k", "value": "v" });
+        assert!(executor.turn_allows("kv_set"));
+
+        executor.set_turn_tools(Some(vec!["read_file".into(), "kv_g*".into()]), None);
+        assert!(executor.turn_allows("kv_get"));
+        assert!(!executor.turn_allows("bash"));
+        let err = executor.execute("kv_set", &input).await.unwrap_err();
+        assert!(matches!(err, AgentError::PermissionDenied(_)));
+        assert!(executor.db.kv.get("k").await.is_err());
+
+        executor.set_turn_tools(None, None);
+        executor.execute("kv_set", &input).await.unwrap();
+        assert_eq!(executor.db.kv.get("k").await.unwrap().value, "v");
+    }
+
+    #[tokio::test]
+    async fn turn_reads_scope_read_file() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        db.fs.write_file("/skills/release/checklist.md", b"- [ ] tag").await.unwrap();
+        db.fs.write_file("/secrets.env", b"TOKEN=1").await.unwrap();
+        let mut executor = ToolExecutor::new(db, "s1".into());
+        executor.set_turn_tools(Some(vec!["grep".into()]), Some("/skills/release".into()));
+
+        assert!(executor.turn_allows("read_file"));
+        assert!(!executor.turn_allows("bash"));
+        let read = |path: &str| serde_json::json!({ "path": path });
+        assert_eq!(executor.execute("read_file", &read("/skills/release/checklist.md")).await.unwrap(), "- [ ] tag");
+        for path in ["/secrets.env", "/skills/release/../../secrets.env", "/skills/releases/x.md"] {
+            let err = executor.execute("read_file", &read(path)).await.unwrap_err();
+            assert!(matches!(err, AgentError::PermissionDenied(_)), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn kv_set_refuses_protected_keys() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        let executor = ToolExecutor::new(db, "s1".into());
+        let key = format!("{}bash:sh", crate::permissions::ALWAYS_KEY_PREFIX);
+        let input = serde_json::json!({ "key": key, "value": "1" });
+        let err = executor.execute("kv_set", &input).await.unwrap_err();
+        assert!(matches!(err, AgentError::Tool(_)));
+        assert!(executor.db.kv.get(&key).await.is_err());
+
+        for key in ["config:mcp:evil", "config:skill:review"] {
+            let input = serde_json::json!({ "key": key, "value": "{}" });
+            assert!(executor.execute("kv_set", &input).await.is_err());
+            assert!(executor.db.kv.get(key).await.is_err());
+        }
+        let input = serde_json::json!({ "key": "notes:todo", "value": "1" });
+        assert!(executor.execute("kv_set", &input).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn dry_run_records_mutating_calls() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        db.kv.set("k", "before").await.unwrap();
+        let executor = ToolExecutor::new(db, "s1".into()).with_dry_run();
+
+        let output = executor.execute("kv_get", &serde_json::json!({ "key": "k" })).await.unwrap();
+        assert!(output.contains("before"));
+        let set = serde_json::json!({ "key": "k", "value": "after" });
+        executor.authorize("kv_set", &set).await.unwrap();
+        executor.execute("kv_set", &set).await.unwrap();
+        executor.execute("write_file", &serde_json::json!({ "path": "/a.md", "content": "x" })).await.unwrap();
+
+        let planned = executor.planned_calls();
+        assert_eq!(planned.iter().map(|c| c.tool.as_str()).collect::<Vec<_>>(), vec!["kv_set", "write_file"]);
+        assert_eq!(planned[0].input["value"], "after");
+        assert_eq!(executor.db.kv.get("k").await.unwrap().value, "before");
+        assert!(!executor.db.fs.exists("/a.md").await.unwrap());
+        assert!(executor.db.tools.by_session("s1").await.unwrap().is_empty());
+    }
+}
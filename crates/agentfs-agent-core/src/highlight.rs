@@ -0,0 +1,117 @@
+//! Syntax highlighting for code blocks in streamed responses.
+//!
+//! Fences are highlighted by their declared language with one of syntect's
+//! bundled themes. The theme comes from `theme` in `.infinity/config.toml`
+//! and can be switched with `/theme <name>`.
+
+use std::sync::{OnceLock, RwLock};
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+
+/// Theme used unless another is configured.
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+static THEME: RwLock<Option<String>> = RwLock::new(None);
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn themes() -> &'static ThemeSet {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Names of the available themes, sorted.
+pub fn theme_names() -> Vec<&'static str> {
+    themes().themes.keys().map(String::as_str).collect()
+}
+
+/// The theme code blocks are highlighted with.
+pub fn theme_name() -> String {
+    THEME
+        .read()
+        .ok()
+        .and_then(|theme| theme.clone())
+        .unwrap_or_else(|| DEFAULT_THEME.to_string())
+}
+
+/// Switch the highlighting theme.
+pub fn set_theme(name: &str) -> Result<(), String> {
+    if !themes().themes.contains_key(name) {
+        return Err(format!(
+            "Unknown theme {name} (available: {})",
+            theme_names().join(", ")
+        ));
+    }
+    if let Ok(mut theme) = THEME.write() {
+        *theme = Some(name.to_string());
+    }
+    Ok(())
+}
+
+fn theme() -> &'static Theme {
+    let themes = &themes().themes;
+    themes
+        .get(&theme_name())
+        .unwrap_or_else(|| &themes[DEFAULT_THEME])
+}
+
+/// Highlights one code block, line by line, keeping parser state between
+/// lines.
+pub struct CodeHighlighter {
+    lines: HighlightLines<'static>,
+}
+
+impl CodeHighlighter {
+    /// A highlighter for a fence's language token (`rust`, `py`, `sh`...),
+    /// or `None` if the language is unknown. Diffs are left to the
+    /// renderer's own +/- coloring.
+    pub fn for_lang(lang: &str) -> Option<Self> {
+        let token = lang.split([' ', ',', '{']).next().unwrap_or("").trim();
+        if token.is_empty() || matches!(token, "diff" | "patch") {
+            return None;
+        }
+        let syntax = syntaxes()
+            .find_syntax_by_token(token)
+            .or_else(|| syntaxes().find_syntax_by_name(token))?;
+        Some(Self {
+            lines: HighlightLines::new(syntax, theme()),
+        })
+    }
+
+    /// `line` with terminal color escapes, or `None` if it couldn't be
+    /// parsed.
+    pub fn highlight(&mut self, line: &str) -> Option<String> {
+        let line = format!("{line}\n");
+        let ranges = self.lines.highlight_line(&line, syntaxes()).ok()?;
+        let escaped = as_24_bit_terminal_escaped(&ranges, false);
+        Some(escaped.trim_end_matches('\n').to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        assert!(CodeHighlighter::for_lang("").is_none());
+        assert!(CodeHighlighter::for_lang("diff").is_none());
+        assert!(CodeHighlighter::for_lang("no-such-language").is_none());
+
+        let mut rust = CodeHighlighter::for_lang("rust").unwrap();
+        let line = rust.highlight("fn main() {}").unwrap();
+        assert!(line.contains("\x1b[38;2;"));
+        assert!(line.contains("main"));
+        assert!(!line.ends_with('\n'));
+        assert!(CodeHighlighter::for_lang("py").is_some());
+
+        assert!(set_theme("no-such-theme").is_err());
+        assert!(theme_names().contains(&DEFAULT_THEME));
+    }
+}
@@ -0,0 +1,325 @@
+//! User hooks: shell commands or HTTP calls run at points in the tool and
+//! session lifecycle.
+//!
+//! Configured by `~/.infinity/hooks.toml`:
+//!
+//! ```toml
+//! [[pre_tool_call]]
+//! tools = ["bash"]
+//! command = "~/.infinity/check-command.sh"
+//!
+//! [[post_tool_call]]
+//! tools = ["write_file", "multi_edit"]
+//! command = "cargo fmt --all"
+//!
+//! [[session_end]]
+//! url = "https://hooks.example.com/infinity"
+//! timeout = 5
+//! ```
+//!
+//! Each hook has a `command` (run with `sh -c`, the payload on stdin) or a
+//! `url` (the payload POSTed as JSON), and optionally `tools`, globs of the
+//! tool names it applies to, and `timeout` in seconds. The payload names
+//! the event, the session and, for tool events, the tool call.
+//!
+//! A `pre_tool_call` hook blocks the call by exiting with status 2 (stderr
+//! is the reason) or by answering `{"decision": "deny", "reason": "..."}`
+//! on stdout or in the HTTP response. A pre hook that fails blocks the call
+//! too; other hooks' failures are only reported.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+
+use crate::display;
+use crate::error::{AgentError, Result};
+use crate::permissions::glob_match;
+
+/// Default hook timeout, in seconds.
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Exit status with which a command hook blocks a tool call.
+const BLOCK_EXIT_CODE: i32 = 2;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hook {
+    /// Tool name globs; empty matches every tool.
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub pre_tool_call: Vec<Hook>,
+    #[serde(default)]
+    pub post_tool_call: Vec<Hook>,
+    #[serde(default)]
+    pub session_start: Vec<Hook>,
+    #[serde(default)]
+    pub session_end: Vec<Hook>,
+}
+
+/// Load hooks from ~/.infinity/hooks.toml (none if missing). An invalid
+/// file is an error rather than no hooks, since its pre hooks may be what
+/// keeps tool calls in check.
+pub fn load_hooks_config() -> Result<HooksConfig> {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".infinity");
+    path.push("hooks.toml");
+    match std::fs::read_to_string(&path) {
+        Ok(content) => parse_hooks_config(&path, &content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HooksConfig::default()),
+        Err(e) => Err(AgentError::Config(format!("Cannot read {}: {e}", path.display()))),
+    }
+}
+
+fn parse_hooks_config(path: &std::path::Path, content: &str) -> Result<HooksConfig> {
+    toml::from_str(content).map_err(|e| AgentError::Config(format!("Invalid {}: {e}", path.display())))
+}
+
+/// Runs the configured hooks.
+pub struct Hooks {
+    config: HooksConfig,
+    http: reqwest::Client,
+}
+
+impl Hooks {
+    pub fn new(config: HooksConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Run the `pre_tool_call` hooks for a call. `Err` carries the reason
+    /// a hook blocked it.
+    pub async fn pre_tool_call(
+        &self,
+        session_id: &str,
+        tool: &str,
+        input: &Value,
+    ) -> std::result::Result<(), String> {
+        let payload = json!({
+            "event": "pre_tool_call",
+            "session_id": session_id,
+            "tool": tool,
+            "input": input,
+        });
+        for hook in matching(&self.config.pre_tool_call, Some(tool)) {
+            match self.run(hook, &payload).await {
+                Ok(None) => {}
+                Ok(Some(reason)) => return Err(reason),
+                Err(e) => return Err(format!("hook failed: {e}")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the `post_tool_call` hooks for a finished call.
+    pub async fn post_tool_call(
+        &self,
+        session_id: &str,
+        tool: &str,
+        input: &Value,
+        output: &str,
+        is_error: bool,
+        duration: Duration,
+    ) {
+        let payload = json!({
+            "event": "post_tool_call",
+            "session_id": session_id,
+            "tool": tool,
+            "input": input,
+            "output": output,
+            "is_error": is_error,
+            "duration_ms": duration.as_millis() as u64,
+        });
+        self.notify(&self.config.post_tool_call, Some(tool), &payload).await;
+    }
+
+    pub async fn session_start(&self, session_id: &str, model: &str, resumed: bool) {
+        let payload = json!({
+            "event": "session_start",
+            "session_id": session_id,
+            "model": model,
+            "resumed": resumed,
+        });
+        self.notify(&self.config.session_start, None, &payload).await;
+    }
+
+    pub async fn session_end(&self, session_id: &str, status: &str) {
+        let payload = json!({
+            "event": "session_end",
+            "session_id": session_id,
+            "status": status,
+        });
+        self.notify(&self.config.session_end, None, &payload).await;
+    }
+
+    /// Run hooks whose answer doesn't matter, reporting failures.
+    async fn notify(&self, hooks: &[Hook], tool: Option<&str>, payload: &Value) {
+        for hook in matching(hooks, tool) {
+            if let Err(e) = self.run(hook, payload).await {
+                display::print_hook_error(payload["event"].as_str().unwrap_or("hook"), &e);
+            }
+        }
+    }
+
+    /// Run one hook. `Ok(Some(reason))` if it asked to block.
+    async fn run(&self, hook: &Hook, payload: &Value) -> std::result::Result<Option<String>, String> {
+        let timeout = Duration::from_secs(hook.timeout);
+        match (&hook.command, &hook.url) {
+            (Some(command), None) => run_command(command, payload, timeout).await,
+            (None, Some(url)) => {
+                let resp = self
+                    .http
+                    .post(url)
+                    .json(payload)
+                    .timeout(timeout)
+                    .send()
+                    .await
+                    .map_err(|e| format!("{url}: {e}"))?;
+                let status = resp.status();
+                if !status.is_success() {
+                    return Err(format!("{url}: HTTP {status}"));
+                }
+                Ok(decision(&resp.text().await.unwrap_or_default()))
+            }
+            _ => Err("a hook needs exactly one of `command` or `url`".to_string()),
+        }
+    }
+}
+
+fn matching<'a>(hooks: &'a [Hook], tool: Option<&'a str>) -> impl Iterator<Item = &'a Hook> {
+    hooks.iter().filter(move |hook| match tool {
+        Some(tool) if !hook.tools.is_empty() => hook.tools.iter().any(|t| glob_match(t, tool)),
+        _ => true,
+    })
+}
+
+async fn run_command(
+    command: &str,
+    payload: &Value,
+    timeout: Duration,
+) -> std::result::Result<Option<String>, String> {
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("INFINITY_HOOK_EVENT", payload["event"].as_str().unwrap_or(""))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("`{command}`: {e}"))?;
+    let stdin = child.stdin.take();
+    let input = payload.to_string();
+    // Writing counts against the timeout too: a hook that neither reads its
+    // input nor exits would otherwise block on a full pipe.
+    let run = async move {
+        if let Some(mut stdin) = stdin {
+            // A hook that doesn't read its input closes the pipe early; that's fine.
+            let _ = stdin.write_all(input.as_bytes()).await;
+        }
+        child.wait_with_output().await
+    };
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(output) => output.map_err(|e| format!("`{command}`: {e}"))?,
+        Err(_) => return Err(format!("`{command}` timed out after {}s", timeout.as_secs())),
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    match output.status.code() {
+        Some(0) => Ok(decision(&String::from_utf8_lossy(&output.stdout))),
+        Some(BLOCK_EXIT_CODE) if stderr.is_empty() => Ok(Some(format!("blocked by `{command}`"))),
+        Some(BLOCK_EXIT_CODE) => Ok(Some(stderr)),
+        code => Err(format!(
+            "`{command}` exited with {}{}",
+            code.map_or("a signal".to_string(), |c| format!("status {c}")),
+            if stderr.is_empty() { String::new() } else { format!(": {stderr}") }
+        )),
+    }
+}
+
+/// The reason in a `{"decision": "deny"}` (or `"block"`) answer.
+fn decision(body: &str) -> Option<String> {
+    let answer: Value = serde_json::from_str(body.trim()).ok()?;
+    match answer.get("decision")?.as_str()? {
+        "deny" | "block" => Some(
+            answer
+                .get("reason")
+                .and_then(|r| r.as_str())
+                .unwrap_or("blocked by hook")
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hooks(toml: &str) -> Hooks {
+        Hooks::new(toml::from_str(toml).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pre_tool_call_blocks() {
+        let hooks = hooks(
+            r#"
+            [[pre_tool_call]]
+            tools = ["bash"]
+            command = "grep -q 'rm -rf' && { echo 'no rm -rf' >&2; exit 2; } || exit 0"
+
+            [[pre_tool_call]]
+            tools = ["write_*"]
+            command = "echo '{\"decision\": \"deny\", \"reason\": \"read-only\"}'"
+            "#,
+        );
+        let rm = json!({ "command": "rm -rf /tmp/x" });
+        assert_eq!(hooks.pre_tool_call("s", "bash", &rm).await, Err("no rm -rf".to_string()));
+        let ls = json!({ "command": "ls" });
+        assert_eq!(hooks.pre_tool_call("s", "bash", &ls).await, Ok(()));
+        let write = json!({ "path": "/a" });
+        assert_eq!(hooks.pre_tool_call("s", "write_file", &write).await, Err("read-only".to_string()));
+        assert_eq!(hooks.pre_tool_call("s", "read_file", &write).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_failing_pre_hook_blocks() {
+        let hooks = hooks("[[pre_tool_call]]\ncommand = \"exit 1\"\ntimeout = 5\n");
+        let result = hooks.pre_tool_call("s", "read_file", &json!({})).await;
+        assert!(result.unwrap_err().starts_with("hook failed"));
+
+        let path = std::path::Path::new("hooks.toml");
+        let err = parse_hooks_config(path, "[[pre_tool_call]]\ncomand = \"x\"\n").unwrap_err();
+        assert!(err.to_string().contains("Invalid hooks.toml"));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_covers_payload_write() {
+        // Bigger than a pipe buffer, to a hook that never reads it.
+        let payload = json!({ "event": "pre_tool_call", "input": "x".repeat(1 << 20) });
+        let started = std::time::Instant::now();
+        let result = run_command("sleep 30", &payload, Duration::from_secs(1)).await;
+        assert!(result.unwrap_err().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
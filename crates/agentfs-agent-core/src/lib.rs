@@ -0,0 +1,61 @@
+//! The agentic loop behind `infinity-agent`, as a library.
+//!
+//! An [`Agent`] sends the conversation to a model, streams the reply to the
+//! terminal, runs the tool calls it makes against an AgentFS database (and
+//! the host shell) through a [`ToolExecutor`], and loops until the model is
+//! done. [`AgentBuilder`] puts one together:
+//!
+//! ```no_run
+//! use agentfs_agent_core::auth::AuthProvider;
+//! use agentfs_agent_core::AgentBuilder;
+//! use agentfs_core::config::AgentFSConfig;
+//! use agentfs_core::AgentFS;
+//!
+//! # async fn run() -> agentfs_agent_core::error::Result<()> {
+//! let db = AgentFS::open(AgentFSConfig::builder("project.db").build()).await?;
+//! let mut auth = AuthProvider::load()?;
+//! let mut agent = AgentBuilder::new(db)
+//!     .model("claude-sonnet-4-6")
+//!     .system("You are a careful code reviewer.")
+//!     .build();
+//! let reply = agent.run_turn(&mut auth, "Review /src/main.rs").await?;
+//! println!("{reply}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Permissions, hooks, MCP servers, web tools and memory are optional and
+//! configured on the builder. The `infinity-agent` binary is a CLI over
+//! this crate.
+
+pub mod agent;
+pub mod api;
+pub mod attachments;
+pub mod auth;
+pub mod builder;
+pub mod checkpoints;
+pub mod commands;
+pub mod config;
+pub mod display;
+pub mod error;
+pub mod executor;
+pub mod highlight;
+pub mod hooks;
+pub mod mcp_client;
+pub mod memory;
+pub mod mentions;
+pub mod output;
+pub mod permissions;
+pub mod profiles;
+pub mod replay;
+pub mod providers;
+pub mod shell;
+pub mod skills;
+pub mod streaming;
+pub mod tools;
+pub mod web;
+
+pub use agent::{Agent, TurnStats};
+pub use api::LlmClient;
+pub use builder::AgentBuilder;
+pub use executor::ToolExecutor;
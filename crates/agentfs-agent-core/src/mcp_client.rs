@@ -336,6 +336,7 @@ pub struct McpManager {
 
 impl McpManager {
     /// Load MCP config and spawn all servers. Warns on failure, continues.
+    #[allow(dead_code)]
     pub async fn from_config() -> Self {
         let mut servers = HashMap::new();
         let config = match load_mcp_config() {
@@ -459,11 +460,13 @@ fn load_mcp_config() -> Option<McpConfigFile> {
 }
 
 /// Get path to global MCP config file.
+#[allow(dead_code)]
 fn global_config_path() -> Option<PathBuf> {
     dirs::home_dir().map(|h| h.join(".infinity").join("mcp.json"))
 }
 
 /// Read or create the global MCP config file.
+#[allow(dead_code)]
 fn read_or_create_global_config() -> Result<(PathBuf, McpConfigFile)> {
     let path = global_config_path()
         .ok_or_else(|| AgentError::Config("Cannot determine home directory".to_string()))?;
@@ -484,6 +487,7 @@ fn read_or_create_global_config() -> Result<(PathBuf, McpConfigFile)> {
 }
 
 /// Add an MCP server entry to the global config.
+#[allow(dead_code)]
 pub fn add_server_to_config(
     name: &str,
     command: &str,
@@ -505,6 +509,7 @@ pub fn add_server_to_config(
 }
 
 /// Remove an MCP server entry from the global config.
+#[allow(dead_code)]
 pub fn remove_server_from_config(name: &str) -> Result<bool> {
     let (path, mut config) = read_or_create_global_config()?;
     let removed = config.mcp_servers.remove(name).is_some();
@@ -515,11 +520,13 @@ pub fn remove_server_from_config(name: &str) -> Result<bool> {
 }
 
 /// List all configured servers (from global config).
+#[allow(dead_code)]
 pub fn list_configured_servers() -> Result<Vec<(String, McpServerEntry)>> {
     let (_path, config) = read_or_create_global_config()?;
     Ok(config.mcp_servers.into_iter().collect())
 }
 
+#[allow(dead_code)]
 fn save_config(path: &PathBuf, config: &McpConfigFile) -> Result<()> {
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
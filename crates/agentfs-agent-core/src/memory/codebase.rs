@@ -0,0 +1,492 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use agentfs_core::filesystem::TreeNode;
+use agentfs_core::AgentFS;
+
+use crate::error::Result;
+use crate::memory::{citation, CodebaseConfig, MemoryEntry, MemoryProvider, Reflection};
+
+use super::compaction::content_hash;
+use super::search::MemorySearchEngine;
+use super::tiers::TierManager;
+
+const KV_KEY: &str = "memory:codebase:brief";
+const ENTRY_ID: &str = "codebase-brief";
+
+/// Directories that are build output or dependencies, not project structure.
+const IGNORED_DIRS: &[&str] = &[
+    "target", "node_modules", "dist", "build", "vendor", "__pycache__", "venv",
+];
+
+/// Files that say how a project is built or where it starts.
+const KEY_FILES: &[&str] = &[
+    "Cargo.toml", "package.json", "pyproject.toml", "setup.py", "requirements.txt",
+    "go.mod", "Makefile", "justfile", "CMakeLists.txt", "pom.xml", "build.gradle",
+    "Dockerfile", "README.md", "README", "main.rs", "lib.rs", "main.py", "main.go",
+    "index.ts", "index.js",
+];
+
+/// Commands that build, test, lint or run a project.
+const BUILD_PREFIXES: &[&str] = &[
+    "cargo ", "npm ", "npx ", "pnpm ", "yarn ", "bun ", "make ", "just ", "pytest ",
+    "python -m pytest ", "uv run ", "poetry run ", "tox ", "go build ", "go test ", "go run ",
+    "go vet ", "mvn ", "gradle ", "./gradlew ", "cmake ", "ctest ",
+];
+
+/// How often a build command was run successfully.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildCommand {
+    pub command: String,
+    pub uses: u32,
+    pub last_used: String,
+}
+
+/// A compact orientation to the workspace, rebuilt at the end of each
+/// session.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectBrief {
+    /// Directories near the root with their file counts, e.g. `/src (12 files)`.
+    pub modules: Vec<String>,
+    pub key_files: Vec<String>,
+    /// Build and test commands seen to work, most used first.
+    pub build_commands: Vec<BuildCommand>,
+    /// Session whose end last refreshed the brief.
+    pub session_id: String,
+    pub refreshed: String,
+}
+
+/// Codebase knowledge provider: remembers how the workspace is laid out
+/// and built so a new session doesn't have to rediscover it.
+pub struct CodebaseProvider {
+    db: Arc<AgentFS>,
+    config: CodebaseConfig,
+    /// The brief, loaded at session start.
+    brief: RwLock<Option<ProjectBrief>>,
+    /// Optional tier manager for access tracking and metadata.
+    tier_manager: Option<Arc<TierManager>>,
+    /// Optional search engine for FTS indexing.
+    search_engine: Option<Arc<MemorySearchEngine>>,
+}
+
+impl CodebaseProvider {
+    pub fn new(db: Arc<AgentFS>, config: CodebaseConfig) -> Self {
+        Self {
+            db,
+            config,
+            brief: RwLock::new(None),
+            tier_manager: None,
+            search_engine: None,
+        }
+    }
+
+    /// Attach a tier manager and search engine.
+    pub fn with_tier_and_search(
+        mut self,
+        tier_manager: Arc<TierManager>,
+        search_engine: Arc<MemorySearchEngine>,
+    ) -> Self {
+        self.tier_manager = Some(tier_manager);
+        self.search_engine = Some(search_engine);
+        self
+    }
+
+    async fn load_brief(&self) -> Result<Option<ProjectBrief>> {
+        match self.db.kv.get(KV_KEY).await {
+            Ok(entry) => match serde_json::from_str(&entry.value) {
+                Ok(brief) => Ok(Some(brief)),
+                Err(e) => {
+                    tracing::warn!("Failed to parse project brief: {e}");
+                    Ok(None)
+                }
+            },
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Save the brief to KV, and update metadata + FTS index.
+    async fn save_brief(&self, brief: &ProjectBrief) -> Result<()> {
+        let value = serde_json::to_string(brief)
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        self.db
+            .kv
+            .set(KV_KEY, &value)
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        if let Some(ref tm) = self.tier_manager {
+            let hash = content_hash(&value);
+            let _ = tm.ensure_metadata(KV_KEY, "codebase", Some(&hash), value.len() as i64).await;
+        }
+        if let Some(ref se) = self.search_engine {
+            let searchable = Self::format_for_prompt(brief, usize::MAX);
+            let _ = se.index_entry(KV_KEY, "codebase", &searchable).await;
+        }
+
+        Ok(())
+    }
+
+    /// Rebuild the brief from the workspace and the session's tool calls,
+    /// keeping the build commands earlier sessions found.
+    async fn refresh(&self, session_id: &str, previous: Option<&ProjectBrief>) -> Result<ProjectBrief> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let (modules, key_files) = match self.db.fs.tree("/").await {
+            Ok(root) => (
+                module_map(&root, self.config.max_modules),
+                key_files(&root, self.config.max_key_files),
+            ),
+            Err(_) => (Vec::new(), Vec::new()),
+        };
+
+        let mut build_commands = previous
+            .map(|b| b.build_commands.clone())
+            .unwrap_or_default();
+        let calls = self
+            .db
+            .tools
+            .by_session(session_id)
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+        for call in calls {
+            if call.tool_name != "bash" || call.status != "success" {
+                continue;
+            }
+            let Some(command) = call
+                .input
+                .as_deref()
+                .and_then(|input| serde_json::from_str::<serde_json::Value>(input).ok())
+                .and_then(|input| input["command"].as_str().and_then(build_command))
+            else {
+                continue;
+            };
+            match build_commands.iter_mut().find(|c| c.command == command) {
+                Some(known) => {
+                    known.uses += 1;
+                    known.last_used = now.clone();
+                }
+                None => build_commands.push(BuildCommand {
+                    command,
+                    uses: 1,
+                    last_used: now.clone(),
+                }),
+            }
+        }
+        build_commands.sort_by(|a, b| b.uses.cmp(&a.uses).then(b.last_used.cmp(&a.last_used)));
+        build_commands.truncate(self.config.max_commands);
+
+        Ok(ProjectBrief {
+            modules,
+            key_files,
+            build_commands,
+            session_id: session_id.to_string(),
+            refreshed: now,
+        })
+    }
+
+    /// Format the brief for system prompt, dropping whole sections that
+    /// don't fit the budget.
+    fn format_for_prompt(brief: &ProjectBrief, budget: usize) -> String {
+        let mut sections = Vec::new();
+        if !brief.modules.is_empty() {
+            sections.push(format!("Layout: {}", brief.modules.join(", ")));
+        }
+        if !brief.key_files.is_empty() {
+            sections.push(format!("Key files: {}", brief.key_files.join(", ")));
+        }
+        if !brief.build_commands.is_empty() {
+            let commands: Vec<String> = brief
+                .build_commands
+                .iter()
+                .map(|c| format!("`{}`", c.command))
+                .collect();
+            sections.push(format!("Build/test: {}", commands.join(", ")));
+        }
+
+        let mut lines = vec![format!("Workspace as of {}", citation("brief", &brief.refreshed))];
+        let mut total_len = lines[0].len();
+        for section in sections {
+            if total_len + section.len() > budget {
+                continue;
+            }
+            total_len += section.len();
+            lines.push(section);
+        }
+        if lines.len() == 1 {
+            return String::new();
+        }
+
+        format!("<project_brief>\n{}\n</project_brief>", lines.join("\n"))
+    }
+}
+
+/// Directories up to two levels down with how many files they hold,
+/// skipping hidden and build output directories.
+fn module_map(root: &TreeNode, max: usize) -> Vec<String> {
+    fn walk(node: &TreeNode, path: &str, depth: usize, out: &mut Vec<String>) {
+        for child in node.children.iter().filter(|c| c.stat.is_dir() && !ignored(&c.name)) {
+            let child_path = format!("{path}/{}", child.name);
+            let files = count_files(child);
+            if files > 0 {
+                let plural = if files == 1 { "" } else { "s" };
+                out.push(format!("{child_path} ({files} file{plural})"));
+            }
+            if depth < 2 {
+                walk(child, &child_path, depth + 1, out);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(root, "", 1, &mut out);
+    out.truncate(max);
+    out
+}
+
+/// Paths of build manifests, READMEs and entry points, shallowest first.
+fn key_files(root: &TreeNode, max: usize) -> Vec<String> {
+    fn walk(node: &TreeNode, path: &str, depth: usize, out: &mut Vec<(usize, String)>) {
+        for child in &node.children {
+            let child_path = format!("{path}/{}", child.name);
+            if child.stat.is_dir() {
+                if depth < 3 && !ignored(&child.name) {
+                    walk(child, &child_path, depth + 1, out);
+                }
+            } else if KEY_FILES.contains(&child.name.as_str()) {
+                out.push((depth, child_path));
+            }
+        }
+    }
+    let mut found = Vec::new();
+    walk(root, "", 0, &mut found);
+    found.sort();
+    found.into_iter().take(max).map(|(_, path)| path).collect()
+}
+
+fn count_files(node: &TreeNode) -> usize {
+    node.children
+        .iter()
+        .map(|c| {
+            if c.stat.is_dir() {
+                if ignored(&c.name) { 0 } else { count_files(c) }
+            } else {
+                1
+            }
+        })
+        .sum()
+}
+
+fn ignored(dir: &str) -> bool {
+    dir.starts_with('.') || IGNORED_DIRS.contains(&dir)
+}
+
+/// The build, test or lint step of a shell command, if it has one: the
+/// first `&&`/`;`-separated part that starts like one, e.g. `cargo test`
+/// from `cd app && cargo test`.
+fn build_command(command: &str) -> Option<String> {
+    command
+        .lines()
+        .flat_map(|line| line.split("&&"))
+        .flat_map(|part| part.split(';'))
+        .map(str::trim)
+        .find(|part| {
+            part.len() <= 120
+                && BUILD_PREFIXES
+                    .iter()
+                    .any(|prefix| part.starts_with(prefix) || *part == prefix.trim_end())
+        })
+        .map(String::from)
+}
+
+#[async_trait]
+impl MemoryProvider for CodebaseProvider {
+    fn name(&self) -> &str {
+        "codebase"
+    }
+
+    async fn context_for_prompt(&self, _query: &str) -> Result<Option<String>> {
+        let brief = self.brief.read().await;
+        let Some(brief) = brief.as_ref() else {
+            return Ok(None);
+        };
+
+        if let Some(ref tm) = self.tier_manager {
+            let _ = tm.record_access(KV_KEY).await;
+        }
+
+        let formatted = Self::format_for_prompt(brief, self.config.prompt_budget_chars);
+        if formatted.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(formatted))
+        }
+    }
+
+    async fn store(&self, _entry: MemoryEntry) -> Result<()> {
+        // The brief is rebuilt from the workspace at session end.
+        Ok(())
+    }
+
+    async fn on_reflection(&self, _reflection: &Reflection) -> Result<()> {
+        Ok(())
+    }
+
+    async fn on_session_start(&self, _session_id: &str) -> Result<()> {
+        let loaded = self.load_brief().await?;
+        *self.brief.write().await = loaded;
+        Ok(())
+    }
+
+    async fn on_session_end(&self, session_id: &str) -> Result<()> {
+        let mut brief = self.brief.write().await;
+        let refreshed = self.refresh(session_id, brief.as_ref()).await?;
+        let empty = refreshed.modules.is_empty()
+            && refreshed.key_files.is_empty()
+            && refreshed.build_commands.is_empty();
+        if empty && brief.is_none() {
+            return Ok(());
+        }
+        self.save_brief(&refreshed).await?;
+        *brief = Some(refreshed);
+        Ok(())
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, String)>> {
+        let brief = self.brief.read().await;
+        Ok(brief
+            .iter()
+            .map(|b| {
+                (
+                    ENTRY_ID.to_string(),
+                    format!(
+                        "project brief: {} directories, {} key files, {} build commands",
+                        b.modules.len(),
+                        b.key_files.len(),
+                        b.build_commands.len()
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<String>> {
+        if id != ENTRY_ID {
+            return Ok(None);
+        }
+        let brief = self.brief.read().await;
+        brief
+            .as_ref()
+            .map(|b| {
+                serde_json::to_string_pretty(b)
+                    .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn update(&self, id: &str, json: &str) -> Result<()> {
+        if id != ENTRY_ID {
+            return Err(crate::error::AgentError::Memory(format!("no memory entry {id}")));
+        }
+        let edited: ProjectBrief = serde_json::from_str(json)
+            .map_err(|e| crate::error::AgentError::Memory(format!("invalid project brief: {e}")))?;
+        let mut brief = self.brief.write().await;
+        self.save_brief(&edited).await?;
+        *brief = Some(edited);
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let mut brief = self.brief.write().await;
+        if id != ENTRY_ID || brief.is_none() {
+            return Err(crate::error::AgentError::Memory(format!("no memory entry {id}")));
+        }
+        super::forget(&self.db.kv, self.tier_manager.as_deref(), self.search_engine.as_deref(), KV_KEY).await?;
+        *brief = None;
+        Ok(())
+    }
+
+    fn owns(&self, key: &str) -> bool {
+        key == KV_KEY
+    }
+
+    async fn evict(&self, _key: &str) {
+        *self.brief.write().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_commands_from_shell() {
+        assert_eq!(build_command("cargo test --workspace").as_deref(), Some("cargo test --workspace"));
+        assert_eq!(build_command("cd app && npm run build").as_deref(), Some("npm run build"));
+        assert_eq!(build_command("make").as_deref(), Some("make"));
+        assert_eq!(build_command("ls -la; cat Cargo.toml"), None);
+        assert_eq!(build_command("makeself x"), None);
+    }
+
+    #[test]
+    fn format_drops_what_does_not_fit() {
+        let brief = ProjectBrief {
+            modules: vec!["/src (3 files)".into()],
+            key_files: vec!["/Cargo.toml".into()],
+            build_commands: vec![BuildCommand {
+                command: "cargo test".into(),
+                uses: 2,
+                last_used: String::new(),
+            }],
+            session_id: "s1".into(),
+            refreshed: "2026-03-01T09:00:00Z".into(),
+        };
+        let full = CodebaseProvider::format_for_prompt(&brief, 800);
+        assert!(full.contains("Workspace as of [brief, 2026-03-01]"));
+        assert!(full.contains("Layout: /src (3 files)"));
+        assert!(full.contains("Build/test: `cargo test`"));
+
+        let short = CodebaseProvider::format_for_prompt(&brief, 60);
+        assert!(short.contains("Layout"));
+        assert!(!short.contains("Build/test"));
+        assert!(CodebaseProvider::format_for_prompt(&ProjectBrief::default(), 800).is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_end_builds_brief() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentFSConfig::builder(dir.path().join("test.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        let db = Arc::new(AgentFS::create(config).await.unwrap());
+        db.fs.write_file("/Cargo.toml", b"[package]").await.unwrap();
+        db.fs.mkdir("/src").await.unwrap();
+        db.fs.write_file("/src/main.rs", b"fn main() {}").await.unwrap();
+        db.fs.mkdir("/target").await.unwrap();
+        db.fs.write_file("/target/out", b"").await.unwrap();
+
+        db.sessions.start("s1", None, None, None).await.unwrap();
+        let bash = |cmd: &str| serde_json::json!({ "command": cmd }).to_string();
+        db.tools.record(Some("s1"), "bash", Some(&bash("cargo test")), Some("ok"), None).await.unwrap();
+        db.tools.record(Some("s1"), "bash", Some(&bash("cargo test")), Some("ok"), None).await.unwrap();
+        db.tools.record(Some("s1"), "bash", Some(&bash("cargo bench")), None, Some("exit 101")).await.unwrap();
+        db.tools.record(Some("s1"), "bash", Some(&bash("ls")), Some("ok"), None).await.unwrap();
+
+        let provider = CodebaseProvider::new(Arc::clone(&db), CodebaseConfig::default());
+        provider.on_session_start("s1").await.unwrap();
+        assert!(provider.context_for_prompt("").await.unwrap().is_none());
+        provider.on_session_end("s1").await.unwrap();
+
+        let brief = provider.load_brief().await.unwrap().unwrap();
+        assert_eq!(brief.modules, vec!["/src (1 file)"]);
+        assert_eq!(brief.key_files, vec!["/Cargo.toml", "/src/main.rs"]);
+        assert_eq!(brief.build_commands.len(), 1);
+        assert_eq!(brief.build_commands[0].uses, 2);
+
+        let context = provider.context_for_prompt("").await.unwrap().unwrap();
+        assert!(context.contains("`cargo test`"));
+    }
+}
@@ -0,0 +1,465 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use agentfs_core::connection::pool::{ReaderPool, WriterHandle};
+use agentfs_core::kvstore::KvStore;
+
+use super::episodes::Episode;
+use super::is_expired;
+use super::search::MemorySearchEngine;
+use super::tiers::TierManager;
+
+// ── Config ─────────────────────────────────────────────────────────
+
+/// Configuration for the compaction engine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactionConfig {
+    /// Most cold episodes one summary replaces.
+    #[serde(default = "default_5")]
+    pub cold_batch_size: usize,
+    /// Whether to enable content-hash dedup.
+    #[serde(default = "default_true")]
+    pub dedup_enabled: bool,
+    /// Fewest similar cold episodes worth replacing with a summary.
+    #[serde(default = "default_2")]
+    pub min_cluster_size: usize,
+    /// Length a summary episode is kept to.
+    #[serde(default = "default_400")]
+    pub summary_max_chars: usize,
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            cold_batch_size: 5,
+            dedup_enabled: true,
+            min_cluster_size: 2,
+            summary_max_chars: 400,
+        }
+    }
+}
+
+/// Where compaction keeps episodes it replaced with a summary.
+pub const ARCHIVE_PREFIX: &str = "memory:archive:episode:";
+
+fn default_2() -> usize { 2 }
+fn default_5() -> usize { 5 }
+fn default_400() -> usize { 400 }
+fn default_true() -> bool { true }
+
+// ── Report ─────────────────────────────────────────────────────────
+
+/// Report from a compaction cycle.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompactionReport {
+    pub expired_removed: usize,
+    pub duplicates_removed: usize,
+    pub episodes_compressed: usize,
+    pub summaries_created: usize,
+    pub tiers_rebalanced: usize,
+    /// Keys removed as expired or duplicate, for providers to drop from
+    /// their caches.
+    #[serde(skip)]
+    pub removed_keys: Vec<String>,
+}
+
+impl std::fmt::Display for CompactionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Compaction: {} expired removed, {} duplicates removed, {} episodes compressed into {} summaries, {} tier changes",
+            self.expired_removed,
+            self.duplicates_removed,
+            self.episodes_compressed,
+            self.summaries_created,
+            self.tiers_rebalanced,
+        )
+    }
+}
+
+// ── CompactionEngine ───────────────────────────────────────────────
+
+/// Engine for deduplication, compression, and tier management.
+pub struct CompactionEngine {
+    #[allow(dead_code)]
+    writer: Arc<WriterHandle>,
+    readers: Arc<ReaderPool>,
+    kv: KvStore,
+    tier_manager: Arc<TierManager>,
+    search_engine: Arc<MemorySearchEngine>,
+    config: CompactionConfig,
+}
+
+impl CompactionEngine {
+    pub fn new(
+        writer: Arc<WriterHandle>,
+        readers: Arc<ReaderPool>,
+        kv: KvStore,
+        tier_manager: Arc<TierManager>,
+        search_engine: Arc<MemorySearchEngine>,
+        config: CompactionConfig,
+    ) -> Self {
+        Self {
+            writer,
+            readers,
+            kv,
+            tier_manager,
+            search_engine,
+            config,
+        }
+    }
+
+    /// Run a full compaction cycle:
+    /// 1. Remove expired entries
+    /// 2. Content-hash dedup scan
+    /// 3. Summarize clusters of cold episodes
+    /// 4. Rebalance tiers
+    pub async fn run_cycle(&self) -> crate::error::Result<CompactionReport> {
+        // Step 1: Expiry scan
+        let expired = self.expire_scan().await?;
+        let mut report = CompactionReport {
+            expired_removed: expired.len(),
+            removed_keys: expired,
+            ..CompactionReport::default()
+        };
+
+        // Step 2: Dedup scan
+        if self.config.dedup_enabled {
+            let duplicates = self.dedup_scan().await?;
+            report.duplicates_removed = duplicates.len();
+            report.removed_keys.extend(duplicates);
+        }
+
+        // Step 3: Summarize cold episodes
+        (report.episodes_compressed, report.summaries_created) =
+            self.summarize_cold_episodes().await?;
+
+        // Step 4: Rebalance tiers
+        report.tiers_rebalanced = self.tier_manager.rebalance().await?;
+
+        Ok(report)
+    }
+
+    /// Remove entries whose `expires` timestamp has passed. Returns their keys.
+    async fn expire_scan(&self) -> crate::error::Result<Vec<String>> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let mut stmt = reader.conn().prepare(
+            "SELECT m.key, json_extract(kv.value, '$.expires')
+             FROM memory_metadata m
+             JOIN kv_store kv ON kv.key = m.key
+             WHERE json_valid(kv.value) AND json_extract(kv.value, '$.expires') IS NOT NULL",
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let now = chrono::Utc::now();
+        let expired: Vec<String> = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .filter(|(_, expires)| is_expired(expires, &now))
+            .map(|(key, _)| key)
+            .collect();
+
+        drop(stmt);
+        drop(reader);
+
+        for key in &expired {
+            super::forget(&self.kv, Some(&self.tier_manager), Some(&self.search_engine), key).await?;
+        }
+
+        Ok(expired)
+    }
+
+    /// Scan for duplicate content using content hashes.
+    /// Removes newer duplicates, keeping the original. Returns their keys.
+    async fn dedup_scan(&self) -> crate::error::Result<Vec<String>> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        // Find all entries grouped by content_hash where there are duplicates
+        let mut stmt = reader.conn().prepare(
+            "SELECT content_hash, GROUP_CONCAT(key, '|') as keys
+             FROM memory_metadata
+             WHERE content_hash IS NOT NULL
+             GROUP BY content_hash
+             HAVING COUNT(*) > 1",
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let dups: Vec<(String, String)> = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        drop(stmt);
+        drop(reader);
+
+        let mut removed = Vec::new();
+        for (_hash, keys_str) in dups {
+            let keys: Vec<&str> = keys_str.split('|').collect();
+            if keys.len() < 2 {
+                continue;
+            }
+
+            // Keep the first key (oldest), remove the rest
+            for key in &keys[1..] {
+                super::forget(&self.kv, Some(&self.tier_manager), Some(&self.search_engine), key).await?;
+                removed.push(key.to_string());
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Summarize cold-tier episodes: each cluster of similar ones is
+    /// replaced by one summary episode, and the originals are archived
+    /// under [`ARCHIVE_PREFIX`]. Returns (episodes archived, summaries
+    /// created).
+    async fn summarize_cold_episodes(&self) -> crate::error::Result<(usize, usize)> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        // Find cold episodes
+        let mut stmt = reader.conn().prepare(
+            "SELECT m.key FROM memory_metadata m
+             WHERE m.tier = 'cold' AND m.provider = 'episodes'
+             ORDER BY m.created ASC",
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let cold_keys: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        drop(stmt);
+        drop(reader);
+
+        let mut cold = Vec::new();
+        for key in cold_keys {
+            if let Ok(entry) = self.kv.get(&key).await {
+                if let Ok(episode) = serde_json::from_str::<Episode>(&entry.value) {
+                    cold.push((key, entry.value, episode));
+                }
+            }
+        }
+        cold.sort_by(|a, b| a.2.created.cmp(&b.2.created));
+
+        let episodes: Vec<&Episode> = cold.iter().map(|(_, _, ep)| ep).collect();
+        let mut archived = 0;
+        let mut summaries = 0;
+        for cluster in cluster_episodes(&episodes, self.config.cold_batch_size) {
+            if cluster.len() < self.config.min_cluster_size.max(2) {
+                continue;
+            }
+            let members: Vec<&Episode> = cluster.iter().map(|&i| &cold[i].2).collect();
+            let summary = summarize_episodes(&members, self.config.summary_max_chars);
+
+            let key = format!("memory:episode:{}", summary.session_id);
+            let value = serde_json::to_string(&summary)
+                .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+            self.kv.set(&key, &value).await
+                .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+            let hash = content_hash(&value);
+            self.tier_manager.ensure_metadata(&key, "episodes", Some(&hash), value.len() as i64).await?;
+            let searchable = format!("{} {}", summary.summary, summary.key_decisions.join(" "));
+            self.search_engine.index_entry(&key, "episodes", &searchable).await?;
+            summaries += 1;
+
+            // Archive originals
+            for &i in &cluster {
+                let (key, value, episode) = &cold[i];
+                let archive_key = format!("{ARCHIVE_PREFIX}{}", episode.session_id);
+                self.kv.set(&archive_key, value).await
+                    .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+                super::forget(&self.kv, Some(&self.tier_manager), Some(&self.search_engine), key).await?;
+                archived += 1;
+            }
+        }
+
+        Ok((archived, summaries))
+    }
+}
+
+/// Group episodes (oldest first) into clusters of at most `max_size` that
+/// used mostly the same tools. Returns indexes into `episodes`.
+fn cluster_episodes(episodes: &[&Episode], max_size: usize) -> Vec<Vec<usize>> {
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    for (i, episode) in episodes.iter().enumerate() {
+        let similar = clusters.iter_mut().find(|cluster| {
+            cluster.len() < max_size
+                && tool_similarity(&episodes[cluster[0]].tools_used, &episode.tools_used) >= 0.5
+        });
+        match similar {
+            Some(cluster) => cluster.push(i),
+            None => clusters.push(vec![i]),
+        }
+    }
+    clusters
+}
+
+/// Jaccard similarity of two tool sets; two empty sets are alike.
+fn tool_similarity(a: &[String], b: &[String]) -> f64 {
+    let union = a.iter().chain(b.iter().filter(|t| !a.contains(t))).count();
+    if union == 0 {
+        return 1.0;
+    }
+    let shared = a.iter().filter(|t| b.contains(t)).count();
+    shared as f64 / union as f64
+}
+
+/// One episode standing in for `members` (oldest first), with a summary
+/// of at most about `max_chars`.
+fn summarize_episodes(members: &[&Episode], max_chars: usize) -> Episode {
+    let first = members.first().map(|e| e.created.as_str()).unwrap_or("");
+    let last = members.last().map(|e| e.created.as_str()).unwrap_or("");
+    let date = |ts: &str| ts.get(..10).unwrap_or(ts).to_string();
+    let header = format!("{} sessions {}..{}: ", members.len(), date(first), date(last));
+
+    let per_episode = max_chars.saturating_sub(header.len()) / members.len().max(1);
+    let gists: Vec<String> = members
+        .iter()
+        .map(|e| truncate_chars(&e.summary, per_episode.max(20)))
+        .collect();
+
+    let mut tools: Vec<String> = Vec::new();
+    let mut decisions: Vec<String> = Vec::new();
+    let mut tool_calls: Vec<String> = Vec::new();
+    let mut summarizes: Vec<String> = Vec::new();
+    for episode in members {
+        for t in &episode.tools_used {
+            if !tools.contains(t) {
+                tools.push(t.clone());
+            }
+        }
+        for d in &episode.key_decisions {
+            if !decisions.contains(d) && decisions.len() < 5 {
+                decisions.push(d.clone());
+            }
+        }
+        tool_calls.extend(episode.tool_calls.iter().cloned());
+        summarizes.push(episode.session_id.clone());
+    }
+    let outcome = match members.first() {
+        Some(e) if members.iter().all(|m| m.outcome == e.outcome) => e.outcome.clone(),
+        _ => "mixed".to_string(),
+    };
+
+    // Hash prefix first, so the short id in citations is distinct
+    let id = content_hash(&summarizes.join(","));
+    Episode {
+        session_id: format!("{}-summary", &id[..8]),
+        summary: format!("{header}{}", gists.join("; ")),
+        key_decisions: decisions,
+        tools_used: tools,
+        tool_calls,
+        outcome,
+        // Dated like its newest member, so it sorts where they were
+        created: last.to_string(),
+        summarizes,
+    }
+}
+
+fn truncate_chars(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let cut: String = s.chars().take(max.saturating_sub(1)).collect();
+    format!("{cut}…")
+}
+
+/// Compute a content hash using xxh3_64.
+pub fn content_hash(content: &str) -> String {
+    let hash = xxhash_rust::xxh3::xxh3_64(content.as_bytes());
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_deterministic() {
+        let h1 = content_hash("hello world");
+        let h2 = content_hash("hello world");
+        assert_eq!(h1, h2);
+    }
+
+    #[test]
+    fn content_hash_differs() {
+        let h1 = content_hash("hello");
+        let h2 = content_hash("world");
+        assert_ne!(h1, h2);
+    }
+
+    #[test]
+    fn default_config() {
+        let cfg = CompactionConfig::default();
+        assert_eq!(cfg.cold_batch_size, 5);
+        assert!(cfg.dedup_enabled);
+    }
+
+    fn episode(id: &str, tools: &[&str], outcome: &str) -> Episode {
+        Episode {
+            session_id: id.into(),
+            summary: format!("{id} summary"),
+            key_decisions: Vec::new(),
+            tools_used: tools.iter().map(|t| t.to_string()).collect(),
+            tool_calls: Vec::new(),
+            outcome: outcome.into(),
+            created: "2026-01-01T00:00:00Z".into(),
+            summarizes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn clusters_by_tools() {
+        let eps = [
+            episode("a", &["bash", "read_file"], "completed"),
+            episode("b", &["web_fetch"], "completed"),
+            episode("c", &["bash", "read_file", "grep"], "completed"),
+            episode("d", &["bash", "read_file"], "completed"),
+        ];
+        let refs: Vec<&Episode> = eps.iter().collect();
+        assert_eq!(cluster_episodes(&refs, 5), vec![vec![0, 2, 3], vec![1]]);
+        assert_eq!(cluster_episodes(&refs, 2), vec![vec![0, 2], vec![1], vec![3]]);
+    }
+
+    #[test]
+    fn summary_merges_members() {
+        let a = episode("a", &["bash"], "completed");
+        let b = episode("b", &["bash", "grep"], "failed");
+        let summary = summarize_episodes(&[&a, &b], 400);
+        assert!(summary.session_id.ends_with("-summary"));
+        assert_eq!(summary.summarizes, vec!["a", "b"]);
+        assert_eq!(summary.tools_used, vec!["bash", "grep"]);
+        assert_eq!(summary.outcome, "mixed");
+        assert!(summary.summary.contains("a summary; b summary"));
+
+        let long = Episode { summary: "x".repeat(1000), ..a.clone() };
+        assert!(summarize_episodes(&[&long, &b], 200).summary.chars().count() <= 200);
+    }
+
+    #[test]
+    fn report_display() {
+        let report = CompactionReport {
+            expired_removed: 1,
+            duplicates_removed: 3,
+            episodes_compressed: 10,
+            summaries_created: 2,
+            tiers_rebalanced: 5,
+            removed_keys: Vec::new(),
+        };
+        let s = format!("{report}");
+        assert!(s.contains("1 expired"));
+        assert!(s.contains("3 duplicates"));
+        assert!(s.contains("10 episodes"));
+        assert!(s.contains("5 tier changes"));
+    }
+}
@@ -7,7 +7,7 @@ use tokio::sync::RwLock;
 use agentfs_core::AgentFS;
 
 use crate::error::Result;
-use crate::memory::{EpisodesConfig, MemoryEntry, MemoryProvider, Reflection};
+use crate::memory::{citation, short_id, EpisodesConfig, MemoryEntry, MemoryProvider, Reflection};
 
 use super::compaction::content_hash;
 use super::search::MemorySearchEngine;
@@ -22,8 +22,14 @@ pub struct Episode {
     pub summary: String,
     pub key_decisions: Vec<String>,
     pub tools_used: Vec<String>,
+    /// `tool_use` ids of the session's reflected-on tool calls.
+    #[serde(default)]
+    pub tool_calls: Vec<String>,
     pub outcome: String,
     pub created: String,
+    /// Session ids of the archived episodes this one summarizes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summarizes: Vec<String>,
 }
 
 /// DeepAgent-style episodic memory provider.
@@ -34,6 +40,8 @@ pub struct EpisodeProvider {
     episodes: RwLock<Vec<Episode>>,
     /// Tools used in the current session (tracked for summary).
     session_tools: RwLock<Vec<String>>,
+    /// Ids of the current session's tool calls, for provenance.
+    session_tool_calls: RwLock<Vec<String>>,
     /// Optional tier manager for access tracking and metadata.
     tier_manager: Option<Arc<TierManager>>,
     /// Optional search engine for FTS indexing.
@@ -47,6 +55,7 @@ impl EpisodeProvider {
             config,
             episodes: RwLock::new(Vec::new()),
             session_tools: RwLock::new(Vec::new()),
+            session_tool_calls: RwLock::new(Vec::new()),
             tier_manager: None,
             search_engine: None,
         }
@@ -119,20 +128,18 @@ impl EpisodeProvider {
         Ok(())
     }
 
+    /// Remove an episode from KV, metadata and FTS.
+    async fn forget(&self, session_id: &str) -> Result<()> {
+        let key = format!("{KV_PREFIX}{session_id}");
+        super::forget(&self.db.kv, self.tier_manager.as_deref(), self.search_engine.as_deref(), &key).await
+    }
+
     /// Prune old episodes beyond max_episodes.
     async fn prune(&self) -> Result<()> {
         let mut episodes = self.episodes.write().await;
         while episodes.len() > self.config.max_episodes {
             if let Some(oldest) = episodes.pop() {
-                let key = format!("{KV_PREFIX}{}", oldest.session_id);
-                let _ = self.db.kv.delete(&key).await;
-                // Clean up metadata and FTS
-                if let Some(ref tm) = self.tier_manager {
-                    let _ = tm.remove_metadata(&key).await;
-                }
-                if let Some(ref se) = self.search_engine {
-                    let _ = se.remove_entry(&key).await;
-                }
+                let _ = self.forget(&oldest.session_id).await;
             }
         }
         Ok(())
@@ -149,11 +156,11 @@ impl EpisodeProvider {
 
         for ep in episodes.iter().take(5) {
             let line = format!(
-                "- [{}] {} (tools: {}, outcome: {})",
-                &ep.created[..10.min(ep.created.len())],
+                "- {} (tools: {}, outcome: {}) {}",
                 ep.summary,
                 ep.tools_used.join(", "),
                 ep.outcome,
+                citation(&format!("ep-{}", short_id(&ep.session_id)), &ep.created),
             );
             if total_len + line.len() > budget {
                 break;
@@ -218,6 +225,10 @@ impl MemoryProvider for EpisodeProvider {
                 tools.push(obs.tool.clone());
             }
         }
+        self.session_tool_calls
+            .write()
+            .await
+            .extend(reflection.tool_call_ids());
         Ok(())
     }
 
@@ -243,6 +254,7 @@ impl MemoryProvider for EpisodeProvider {
 
         *self.episodes.write().await = loaded;
         *self.session_tools.write().await = Vec::new();
+        *self.session_tool_calls.write().await = Vec::new();
         Ok(())
     }
 
@@ -260,8 +272,10 @@ impl MemoryProvider for EpisodeProvider {
             summary: format!("Session used {} tools", tools.len()),
             key_decisions: Vec::new(),
             tools_used: tools.clone(),
+            tool_calls: self.session_tool_calls.read().await.clone(),
             outcome: "completed".to_string(),
             created: chrono::Utc::now().to_rfc3339(),
+            summarizes: Vec::new(),
         };
 
         self.save_episode(&episode).await?;
@@ -270,6 +284,64 @@ impl MemoryProvider for EpisodeProvider {
 
         Ok(())
     }
+
+    async fn entries(&self) -> Result<Vec<(String, String)>> {
+        let episodes = self.episodes.read().await;
+        Ok(episodes
+            .iter()
+            .map(|ep| (format!("ep-{}", ep.session_id), format!("{} ({})", ep.summary, ep.outcome)))
+            .collect())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<String>> {
+        let episodes = self.episodes.read().await;
+        episodes
+            .iter()
+            .find(|ep| Some(ep.session_id.as_str()) == id.strip_prefix("ep-"))
+            .map(|ep| {
+                serde_json::to_string_pretty(ep)
+                    .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn update(&self, id: &str, json: &str) -> Result<()> {
+        let mut edited: Episode = serde_json::from_str(json)
+            .map_err(|e| crate::error::AgentError::Memory(format!("invalid episode: {e}")))?;
+        let session_id = id.strip_prefix("ep-").unwrap_or(id);
+        let mut episodes = self.episodes.write().await;
+        let episode = episodes
+            .iter_mut()
+            .find(|ep| ep.session_id == session_id)
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        // The session id is the storage key; keep it whatever the edit says.
+        edited.session_id = session_id.to_string();
+        self.save_episode(&edited).await?;
+        *episode = edited;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let session_id = id.strip_prefix("ep-").unwrap_or(id);
+        let mut episodes = self.episodes.write().await;
+        let idx = episodes
+            .iter()
+            .position(|ep| ep.session_id == session_id)
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        self.forget(session_id).await?;
+        episodes.remove(idx);
+        Ok(())
+    }
+
+    fn owns(&self, key: &str) -> bool {
+        key.starts_with(KV_PREFIX)
+    }
+
+    async fn evict(&self, key: &str) {
+        if let Some(session_id) = key.strip_prefix(KV_PREFIX) {
+            self.episodes.write().await.retain(|ep| ep.session_id != session_id);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -289,14 +361,17 @@ mod tests {
             summary: "Built a REST API".into(),
             key_decisions: vec!["chose tower".into()],
             tools_used: vec!["bash".into(), "write_file".into()],
+            tool_calls: vec!["toolu_01".into()],
             outcome: "success".into(),
             created: "2026-02-19T12:00:00Z".into(),
+            summarizes: Vec::new(),
         }];
 
         let result = EpisodeProvider::format_for_prompt(&episodes, 1000);
         assert!(result.contains("<past_sessions>"));
         assert!(result.contains("Built a REST API"));
         assert!(result.contains("bash, write_file"));
+        assert!(result.contains("[ep-abc-123, 2026-02-19]"));
     }
 
     #[test]
@@ -307,8 +382,10 @@ mod tests {
                 summary: format!("Did something important #{i} that takes up space in the prompt"),
                 key_decisions: vec![],
                 tools_used: vec!["bash".into()],
+                tool_calls: Vec::new(),
                 outcome: "success".into(),
                 created: format!("2026-02-{:02}T12:00:00Z", (i % 28) + 1),
+                summarizes: Vec::new(),
             })
             .collect();
 
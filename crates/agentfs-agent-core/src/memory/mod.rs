@@ -0,0 +1,1483 @@
+pub mod codebase;
+pub mod compaction;
+pub mod episodes;
+pub mod playbook;
+pub mod preferences;
+pub mod reflector;
+pub mod search;
+pub mod snapshot;
+pub mod tiers;
+pub mod timeline;
+pub mod tool_patterns;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use agentfs_core::AgentFS;
+use agentfs_core::analytics::{TokenRecord, UsageSummary};
+use agentfs_core::kvstore::KvStore;
+
+use crate::error::Result;
+
+use self::compaction::{CompactionConfig, CompactionEngine, CompactionReport};
+use self::search::{MemorySearchEngine, SearchFilter, SearchResult};
+use self::tiers::{EntryStats, MemoryPressure, TierConfig, TierManager};
+
+// ── Data types ──────────────────────────────────────────────────────
+
+/// A generic memory entry for storage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
+pub struct MemoryEntry {
+    pub id: String,
+    pub provider: String,
+    pub content: String,
+    pub metadata: serde_json::Value,
+    pub created: String,
+}
+
+/// Categories for playbook-style learnings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Strategy,
+    Mistake,
+    Pattern,
+}
+
+impl std::str::FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "strategy" => Ok(Category::Strategy),
+            "mistake" => Ok(Category::Mistake),
+            "pattern" => Ok(Category::Pattern),
+            other => Err(format!(
+                "unknown category {other} (expected strategy, mistake or pattern)"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Category::Strategy => write!(f, "strategy"),
+            Category::Mistake => write!(f, "mistake"),
+            Category::Pattern => write!(f, "pattern"),
+        }
+    }
+}
+
+/// A single learning extracted from reflection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Learning {
+    pub category: Category,
+    pub content: String,
+    pub confidence: f32,
+    /// Days the learning stays true, for temporary facts like an outage.
+    #[serde(default)]
+    pub ttl_days: Option<f64>,
+}
+
+/// An observation about tool usage in a turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolObs {
+    pub tool: String,
+    pub success: bool,
+    pub pattern: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A tool call made during a reflected-on turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCallRef {
+    /// The `tool_use` id, as recorded in the session's tool calls.
+    pub id: String,
+    pub tool: String,
+}
+
+/// The output of the reflector after analyzing a turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reflection {
+    pub learnings: Vec<Learning>,
+    pub helpful_ids: Vec<String>,
+    pub harmful_ids: Vec<String>,
+    pub tool_observations: Vec<ToolObs>,
+    pub session_id: String,
+    /// Tool calls the turn made, which the learnings came from.
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCallRef>,
+    /// Stable user preferences the turn revealed.
+    #[serde(default)]
+    pub preferences: Vec<String>,
+}
+
+impl Reflection {
+    /// Ids of all the turn's tool calls.
+    pub fn tool_call_ids(&self) -> Vec<String> {
+        self.tool_calls.iter().map(|c| c.id.clone()).collect()
+    }
+
+    /// Ids of the turn's calls to `tool`.
+    pub fn tool_call_ids_for(&self, tool: &str) -> Vec<String> {
+        self.tool_calls
+            .iter()
+            .filter(|c| c.tool == tool)
+            .map(|c| c.id.clone())
+            .collect()
+    }
+}
+
+/// Where a memory came from, for entries that don't carry these fields
+/// themselves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Provenance {
+    #[serde(default)]
+    pub session_id: String,
+    /// When the memory was recorded (RFC 3339).
+    #[serde(default)]
+    pub recorded: String,
+    /// `tool_use` ids of the calls it was learned from.
+    #[serde(default)]
+    pub tool_calls: Vec<String>,
+}
+
+impl Provenance {
+    pub fn new(session_id: &str, tool_calls: Vec<String>) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            recorded: chrono::Utc::now().to_rfc3339(),
+            tool_calls,
+        }
+    }
+
+    /// Citation for prompt context, naming the source session; empty for
+    /// memories recorded before provenance was kept.
+    pub fn citation(&self) -> String {
+        if self.session_id.is_empty() {
+            return String::new();
+        }
+        citation(&short_id(&self.session_id), &self.recorded)
+    }
+}
+
+/// Compact citation for a recalled memory, e.g. `[str-00003, 2025-12-01]`,
+/// so the model and the user can tell where it came from and how old it is.
+pub fn citation(id: &str, timestamp: &str) -> String {
+    let date = timestamp.get(..10).unwrap_or(timestamp);
+    if date.is_empty() {
+        format!("[{id}]")
+    } else {
+        format!("[{id}, {date}]")
+    }
+}
+
+/// Longest time-to-live, in days (a century).
+pub const MAX_TTL_DAYS: f64 = 36_500.0;
+
+/// Parse a time-to-live such as `3d`, `12h` or `2w` into days. A bare
+/// number is days, and at most [`MAX_TTL_DAYS`].
+pub fn parse_ttl(s: &str) -> std::result::Result<f64, String> {
+    let s = s.trim();
+    let (number, days_per_unit) = match s.char_indices().last() {
+        Some((i, 'h')) => (&s[..i], 1.0 / 24.0),
+        Some((i, 'd')) => (&s[..i], 1.0),
+        Some((i, 'w')) => (&s[..i], 7.0),
+        _ => (s, 1.0),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n > 0.0 && n * days_per_unit <= MAX_TTL_DAYS => Ok(n * days_per_unit),
+        Ok(n) if n > 0.0 => Err(format!("ttl {s} is too long (at most {MAX_TTL_DAYS} days)")),
+        _ => Err(format!("invalid ttl {s} (expected e.g. 12h, 3d or 2w)")),
+    }
+}
+
+/// When an entry recorded at `from` with a TTL of `ttl_days` expires
+/// (RFC 3339). TTLs from a model aren't checked by [`parse_ttl`], so
+/// longer ones are cut to [`MAX_TTL_DAYS`].
+pub fn expiry(from: &chrono::DateTime<chrono::Utc>, ttl_days: f64) -> String {
+    let seconds = (ttl_days.min(MAX_TTL_DAYS) * 86400.0) as i64;
+    chrono::Duration::try_seconds(seconds)
+        .and_then(|ttl| from.checked_add_signed(ttl))
+        .unwrap_or(*from)
+        .to_rfc3339()
+}
+
+/// Whether an entry that expires at `expires` has expired by `now`.
+/// Unparseable timestamps never expire, so a bad edit doesn't lose data.
+pub fn is_expired(expires: &str, now: &chrono::DateTime<chrono::Utc>) -> bool {
+    chrono::DateTime::parse_from_rfc3339(expires)
+        .map(|at| at.with_timezone(&chrono::Utc) <= *now)
+        .unwrap_or(false)
+}
+
+/// Parse a search cutoff: a date (`2026-01-31`) or an age such as `7d`
+/// or `12h`. Returns a timestamp comparable with `memory_metadata.created`.
+pub fn parse_since(s: &str) -> std::result::Result<String, String> {
+    let s = s.trim();
+    if chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok() {
+        return Ok(s.to_string());
+    }
+    let days = parse_ttl(s)
+        .map_err(|_| format!("invalid time {s} (expected a date like 2026-01-31 or an age like 7d)"))?;
+    let cutoff = chrono::Duration::try_seconds((days * 86400.0) as i64)
+        .and_then(|age| chrono::Utc::now().checked_sub_signed(age))
+        .ok_or_else(|| format!("time {s} is too far back"))?;
+    Ok(cutoff.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+/// Remove the entry stored at `key` from KV, tier metadata and FTS.
+/// Already gone from KV is fine; metadata and FTS are still cleared.
+pub(crate) async fn forget(
+    kv: &KvStore,
+    tier_manager: Option<&TierManager>,
+    search_engine: Option<&MemorySearchEngine>,
+    key: &str,
+) -> Result<()> {
+    let _ = kv.delete(key).await;
+    if let Some(tm) = tier_manager {
+        tm.remove_metadata(key).await?;
+    }
+    if let Some(se) = search_engine {
+        se.remove_entry(key).await?;
+    }
+    Ok(())
+}
+
+/// First eight characters of a session id, enough to find it in `sessions`.
+pub(crate) fn short_id(session_id: &str) -> String {
+    session_id.chars().take(8).collect()
+}
+
+// ── Trait ────────────────────────────────────────────────────────────
+
+#[async_trait]
+pub trait MemoryProvider: Send + Sync {
+    /// Unique name (e.g. "playbook", "episodes", "tool_patterns").
+    fn name(&self) -> &str;
+
+    /// Return context to inject into system prompt (called each turn).
+    async fn context_for_prompt(&self, query: &str) -> Result<Option<String>>;
+
+    /// Store a new memory entry.
+    async fn store(&self, entry: MemoryEntry) -> Result<()>;
+
+    /// Called after reflection extracts learnings from a turn.
+    async fn on_reflection(&self, reflection: &Reflection) -> Result<()>;
+
+    /// Called at session start (load/warm up).
+    async fn on_session_start(&self, session_id: &str) -> Result<()>;
+
+    /// Called at session end (compact/summarize).
+    async fn on_session_end(&self, session_id: &str) -> Result<()>;
+
+    /// Down-weight entries that have gone stale under `rules`. Returns the
+    /// number of entries changed.
+    async fn decay(&self, _rules: &TierConfig) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Entries as (id, one-line summary), for `memory show --ids`.
+    async fn entries(&self) -> Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    /// The entry with `id` as pretty-printed JSON, for editing.
+    async fn get(&self, _id: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Replace the entry with `id` by edited JSON, keeping KV, metadata
+    /// and the search index in step.
+    async fn update(&self, id: &str, _json: &str) -> Result<()> {
+        Err(crate::error::AgentError::Memory(format!("{} entries can't be edited ({id})", self.name())))
+    }
+
+    /// Delete the entry with `id` from KV, metadata and the search index.
+    async fn delete(&self, id: &str) -> Result<()> {
+        Err(crate::error::AgentError::Memory(format!("{} entries can't be deleted ({id})", self.name())))
+    }
+
+    /// Whether `key` is where this provider keeps an entry.
+    fn owns(&self, _key: &str) -> bool {
+        false
+    }
+
+    /// Drop the loaded copy of the entry at `key`, which has been deleted
+    /// from storage.
+    async fn evict(&self, _key: &str) {}
+}
+
+/// A memory entry listed by [`MemoryManager::entry_ids`].
+#[derive(Debug, Clone)]
+pub struct EntryId {
+    pub provider: String,
+    pub id: String,
+    pub summary: String,
+}
+
+/// Split the keys recalled into a turn into those the turn went well and
+/// badly for. The reflector's helpful/harmful votes decide for the entries
+/// it named; the rest follow the turn's tool error rate: no errors is a
+/// success, half or more failing a failure, anything between neither.
+fn turn_outcomes(
+    recalled: &[String],
+    tool_results: &[serde_json::Value],
+    reflection: Option<&Reflection>,
+) -> (Vec<String>, Vec<String>) {
+    let errors = tool_results
+        .iter()
+        .filter(|r| r.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false))
+        .count();
+    let turn_outcome = match tool_results.len() {
+        0 => None,
+        _ if errors == 0 => Some(true),
+        total if errors * 2 >= total => Some(false),
+        _ => None,
+    };
+
+    let mut succeeded = Vec::new();
+    let mut failed = Vec::new();
+    for key in recalled {
+        let id = key.rsplit(':').next().unwrap_or(key);
+        let voted = reflection.and_then(|r| {
+            if r.harmful_ids.iter().any(|h| h == id) {
+                Some(false)
+            } else if r.helpful_ids.iter().any(|h| h == id) {
+                Some(true)
+            } else {
+                None
+            }
+        });
+        match voted.or(turn_outcome) {
+            Some(true) => succeeded.push(key.clone()),
+            Some(false) => failed.push(key.clone()),
+            None => {}
+        }
+    }
+    (succeeded, failed)
+}
+
+/// Find the entry `wanted` names: an exact id, or a prefix of exactly one
+/// (so the short ids in citations work).
+fn match_id<'a>(wanted: &str, entries: &'a [EntryId]) -> Result<&'a EntryId> {
+    if let Some(exact) = entries.iter().find(|e| e.id == wanted) {
+        return Ok(exact);
+    }
+    let matches: Vec<&EntryId> = entries.iter().filter(|e| e.id.starts_with(wanted)).collect();
+    match matches.as_slice() {
+        [only] => Ok(only),
+        [] => Err(crate::error::AgentError::Memory(format!("no memory entry {wanted}"))),
+        several => Err(crate::error::AgentError::Memory(format!(
+            "{wanted} matches several entries: {}",
+            several.iter().map(|e| e.id.as_str()).collect::<Vec<_>>().join(", ")
+        ))),
+    }
+}
+
+// ── Config ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub reflect: bool,
+    #[serde(default = "default_reflect_model")]
+    pub reflect_model: String,
+    /// Estimated reflection spend per session, in microcents, after which
+    /// reflection is skipped for the rest of the session. 0 means no cap.
+    #[serde(default = "default_max_reflection_cost")]
+    pub max_reflection_cost_microcents: i64,
+    #[serde(default = "default_providers")]
+    pub providers: Vec<String>,
+    #[serde(default)]
+    pub playbook: PlaybookConfig,
+    #[serde(default)]
+    pub preferences: PreferencesConfig,
+    #[serde(default)]
+    pub codebase: CodebaseConfig,
+    #[serde(default)]
+    pub episodes: EpisodesConfig,
+    #[serde(default)]
+    pub tool_patterns: ToolPatternsConfig,
+    #[serde(default)]
+    pub tiers: TierConfig,
+    #[serde(default)]
+    pub compaction: CompactionConfig,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            reflect: true,
+            reflect_model: default_reflect_model(),
+            max_reflection_cost_microcents: default_max_reflection_cost(),
+            providers: default_providers(),
+            playbook: PlaybookConfig::default(),
+            preferences: PreferencesConfig::default(),
+            codebase: CodebaseConfig::default(),
+            episodes: EpisodesConfig::default(),
+            tool_patterns: ToolPatternsConfig::default(),
+            tiers: TierConfig::default(),
+            compaction: CompactionConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybookConfig {
+    #[serde(default = "default_100")]
+    pub max_entries: usize,
+    #[serde(default = "default_2000")]
+    pub prompt_budget_chars: usize,
+}
+
+impl Default for PlaybookConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 100,
+            prompt_budget_chars: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreferencesConfig {
+    #[serde(default = "default_50")]
+    pub max_entries: usize,
+    #[serde(default = "default_800")]
+    pub prompt_budget_chars: usize,
+}
+
+impl Default for PreferencesConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 50,
+            prompt_budget_chars: 800,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodebaseConfig {
+    #[serde(default = "default_800")]
+    pub prompt_budget_chars: usize,
+    /// Directories listed in the layout.
+    #[serde(default = "default_20")]
+    pub max_modules: usize,
+    #[serde(default = "default_15")]
+    pub max_key_files: usize,
+    /// Build and test commands kept, most used first.
+    #[serde(default = "default_8")]
+    pub max_commands: usize,
+}
+
+impl Default for CodebaseConfig {
+    fn default() -> Self {
+        Self {
+            prompt_budget_chars: 800,
+            max_modules: 20,
+            max_key_files: 15,
+            max_commands: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodesConfig {
+    #[serde(default = "default_20")]
+    pub max_episodes: usize,
+    #[serde(default = "default_1000")]
+    pub prompt_budget_chars: usize,
+}
+
+impl Default for EpisodesConfig {
+    fn default() -> Self {
+        Self {
+            max_episodes: 20,
+            prompt_budget_chars: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolPatternsConfig {
+    #[serde(default = "default_500")]
+    pub prompt_budget_chars: usize,
+}
+
+impl Default for ToolPatternsConfig {
+    fn default() -> Self {
+        Self {
+            prompt_budget_chars: 500,
+        }
+    }
+}
+
+fn default_true() -> bool { true }
+fn default_reflect_model() -> String { "claude-haiku-4-5-20251001".to_string() }
+fn default_max_reflection_cost() -> i64 { 5_000_000 } // $0.05
+fn default_providers() -> Vec<String> {
+    vec![
+        "preferences".into(),
+        "codebase".into(),
+        "playbook".into(),
+        "episodes".into(),
+        "tool_patterns".into(),
+    ]
+}
+fn default_100() -> usize { 100 }
+fn default_2000() -> usize { 2000 }
+fn default_50() -> usize { 50 }
+fn default_800() -> usize { 800 }
+fn default_20() -> usize { 20 }
+fn default_15() -> usize { 15 }
+fn default_8() -> usize { 8 }
+fn default_1000() -> usize { 1000 }
+fn default_500() -> usize { 500 }
+
+/// Load memory config from ~/.infinity/memory.json (creates default if missing).
+pub fn load_memory_config() -> MemoryConfig {
+    let path = memory_config_path();
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(_) => MemoryConfig::default(),
+    }
+}
+
+fn memory_config_path() -> PathBuf {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".infinity");
+    path.push("memory.json");
+    path
+}
+
+// ── MemoryManager ───────────────────────────────────────────────────
+
+/// Token usage purpose recorded for reflection calls.
+pub const REFLECTION_PURPOSE: &str = "reflection";
+
+/// Orchestrates all memory providers, tier management, search, and compaction.
+pub struct MemoryManager {
+    db: Arc<AgentFS>,
+    providers: Vec<Box<dyn MemoryProvider>>,
+    reflector: Option<reflector::Reflector>,
+    tier_manager: Arc<TierManager>,
+    search_engine: Arc<MemorySearchEngine>,
+    compaction: Arc<CompactionEngine>,
+    config: MemoryConfig,
+}
+
+impl MemoryManager {
+    /// Create a new memory manager from config, using the given DB for KV storage.
+    pub async fn from_config(config: MemoryConfig, db: Arc<AgentFS>) -> Result<Self> {
+        // Create tier manager and search engine from DB's writer/reader pool
+        let writer = db.writer().clone();
+        let readers = db.readers().clone();
+
+        let tier_manager = Arc::new(TierManager::new(
+            writer.clone(),
+            readers.clone(),
+            config.tiers.clone(),
+        ));
+
+        let search_engine = Arc::new(MemorySearchEngine::new(
+            writer.clone(),
+            readers.clone(),
+        ));
+
+        // Create KV store for compaction engine
+        let kv = KvStore::new(writer.clone(), readers.clone());
+
+        let compaction = Arc::new(CompactionEngine::new(
+            writer,
+            readers,
+            kv,
+            Arc::clone(&tier_manager),
+            Arc::clone(&search_engine),
+            config.compaction.clone(),
+        ));
+
+        let mut providers: Vec<Box<dyn MemoryProvider>> = Vec::new();
+
+        for name in &config.providers {
+            match name.as_str() {
+                "playbook" => {
+                    let provider = playbook::PlaybookProvider::new(
+                        Arc::clone(&db),
+                        config.playbook.clone(),
+                    ).with_tier_and_search(
+                        Arc::clone(&tier_manager),
+                        Arc::clone(&search_engine),
+                    );
+                    providers.push(Box::new(provider));
+                }
+                "preferences" => {
+                    let provider = preferences::PreferenceProvider::new(
+                        Arc::clone(&db),
+                        config.preferences.clone(),
+                    ).with_tier_and_search(
+                        Arc::clone(&tier_manager),
+                        Arc::clone(&search_engine),
+                    );
+                    // Preferences come first in the prompt, whatever the
+                    // configured order.
+                    providers.insert(0, Box::new(provider));
+                }
+                "codebase" => {
+                    let provider = codebase::CodebaseProvider::new(
+                        Arc::clone(&db),
+                        config.codebase.clone(),
+                    ).with_tier_and_search(
+                        Arc::clone(&tier_manager),
+                        Arc::clone(&search_engine),
+                    );
+                    providers.push(Box::new(provider));
+                }
+                "episodes" => {
+                    let provider = episodes::EpisodeProvider::new(
+                        Arc::clone(&db),
+                        config.episodes.clone(),
+                    ).with_tier_and_search(
+                        Arc::clone(&tier_manager),
+                        Arc::clone(&search_engine),
+                    );
+                    providers.push(Box::new(provider));
+                }
+                "tool_patterns" => {
+                    let provider = tool_patterns::ToolPatternProvider::new(
+                        Arc::clone(&db),
+                        config.tool_patterns.clone(),
+                    ).with_tier_and_search(
+                        Arc::clone(&tier_manager),
+                        Arc::clone(&search_engine),
+                    );
+                    providers.push(Box::new(provider));
+                }
+                other => {
+                    tracing::warn!("Unknown memory provider: {other}, skipping");
+                }
+            }
+        }
+
+        let reflector = if config.reflect {
+            Some(reflector::Reflector::new(config.reflect_model.clone()))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            db,
+            providers,
+            reflector,
+            tier_manager,
+            search_engine,
+            compaction,
+            config,
+        })
+    }
+
+    /// Get combined context from all providers to inject into system prompt.
+    pub async fn context_for_prompt(&self, query: &str) -> String {
+        let mut sections = Vec::new();
+
+        for provider in &self.providers {
+            match provider.context_for_prompt(query).await {
+                Ok(Some(ctx)) if !ctx.is_empty() => sections.push(ctx),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("Memory provider {} error: {e}", provider.name());
+                }
+            }
+        }
+
+        if sections.is_empty() {
+            return String::new();
+        }
+
+        format!("\n\n<memory>\n{}\n</memory>", sections.join("\n\n"))
+    }
+
+    /// Notify all providers of session start, then rebalance tiers.
+    pub async fn on_session_start(&self, session_id: &str) {
+        for provider in &self.providers {
+            if let Err(e) = provider.on_session_start(session_id).await {
+                tracing::warn!("Memory provider {} session_start error: {e}", provider.name());
+            }
+        }
+
+        // Repair drift between entries, metadata and the search index,
+        // which otherwise silently degrades recall
+        match self.search_engine.repair_index().await {
+            Ok(repaired) if repaired.total() > 0 => {
+                tracing::info!("Repaired memory index: {repaired}");
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Memory index repair error at session start: {e}"),
+        }
+
+        // Rebalance tiers at session start
+        if let Err(e) = self.tier_manager.rebalance().await {
+            tracing::warn!("Tier rebalance error at session start: {e}");
+        }
+    }
+
+    /// Notify all providers of session end, then run compaction cycle.
+    pub async fn on_session_end(&self, session_id: &str) {
+        for provider in &self.providers {
+            if let Err(e) = provider.on_session_end(session_id).await {
+                tracing::warn!("Memory provider {} session_end error: {e}", provider.name());
+            }
+        }
+
+        // Run compaction cycle at session end
+        match self.compact().await {
+            Ok(report) => {
+                if report.expired_removed > 0
+                    || report.duplicates_removed > 0
+                    || report.episodes_compressed > 0
+                {
+                    tracing::info!("{report}");
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Compaction error at session end: {e}");
+            }
+        }
+    }
+
+    /// Run reflection on a turn and feed results to providers, then credit
+    /// the memories recalled into the turn with how it went.
+    /// Returns true if reflection was triggered.
+    pub async fn reflect(
+        &self,
+        auth: &mut crate::auth::AuthProvider,
+        messages: &[crate::api::Message],
+        tool_results: &[serde_json::Value],
+        session_id: &str,
+    ) -> bool {
+        let recalled = self.tier_manager.take_recalled();
+        let reflection = self.run_reflection(auth, messages, tool_results, session_id).await;
+
+        let (succeeded, failed) = turn_outcomes(&recalled, tool_results, reflection.as_ref());
+        if !succeeded.is_empty() || !failed.is_empty() {
+            if let Err(e) = self.tier_manager.record_outcomes(&succeeded, &failed).await {
+                tracing::warn!("Failed to record memory outcomes: {e}");
+            }
+        }
+        reflection.is_some()
+    }
+
+    /// Reflect on a turn if it's worth it and the session's reflection
+    /// budget allows, feeding the result to providers.
+    async fn run_reflection(
+        &self,
+        auth: &mut crate::auth::AuthProvider,
+        messages: &[crate::api::Message],
+        tool_results: &[serde_json::Value],
+        session_id: &str,
+    ) -> Option<Reflection> {
+        let reflector = self.reflector.as_ref()?;
+
+        // Check triggers: only reflect when worthwhile
+        if !reflector.should_reflect(messages, tool_results) {
+            return None;
+        }
+
+        let cap = self.config.max_reflection_cost_microcents;
+        if cap > 0 {
+            match self.reflection_usage(session_id).await {
+                Ok(spent) if spent.total_cost_microcents >= cap => {
+                    tracing::debug!("Reflection budget for session {session_id} is spent, skipping");
+                    return None;
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to read reflection spend: {e}"),
+            }
+        }
+
+        match reflector.reflect_on_turn(auth, messages, tool_results, session_id).await {
+            Ok((reflection, usage)) => {
+                let record = TokenRecord {
+                    id: None,
+                    session_id: Some(session_id.to_string()),
+                    tool_call_id: None,
+                    model: reflector.model().to_string(),
+                    input_tokens: usage.input_tokens as i64,
+                    output_tokens: usage.output_tokens as i64,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
+                    cost_microcents: crate::agent::estimate_cost(
+                        reflector.model(),
+                        usage.input_tokens,
+                        usage.output_tokens,
+                    ),
+                    recorded_at: None,
+                    purpose: Some(REFLECTION_PURPOSE.to_string()),
+                };
+                if let Err(e) = self.db.analytics.record_usage(record).await {
+                    tracing::warn!("Failed to record reflection usage: {e}");
+                }
+
+                for provider in &self.providers {
+                    if let Err(e) = provider.on_reflection(&reflection).await {
+                        tracing::warn!(
+                            "Memory provider {} reflection error: {e}",
+                            provider.name()
+                        );
+                    }
+                }
+                Some(reflection)
+            }
+            Err(e) => {
+                tracing::warn!("Reflection failed: {e}");
+                None
+            }
+        }
+    }
+
+    /// Tokens and estimated cost reflection has used in a session.
+    pub async fn reflection_usage(&self, session_id: &str) -> Result<UsageSummary> {
+        self.db
+            .analytics
+            .session_usage(session_id, REFLECTION_PURPOSE)
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+    }
+
+    /// Add a playbook entry written by the user rather than learned by
+    /// reflection, optionally expiring after `ttl_days`.
+    pub async fn remember(
+        &self,
+        category: Category,
+        content: &str,
+        session_id: &str,
+        ttl_days: Option<f64>,
+    ) -> Result<()> {
+        let now = chrono::Utc::now();
+        self.provider("playbook")?
+            .store(MemoryEntry {
+                id: String::new(),
+                provider: "playbook".to_string(),
+                content: content.trim().to_string(),
+                metadata: serde_json::json!({
+                    "category": category,
+                    "session_id": session_id,
+                    "expires": ttl_days.map(|days| expiry(&now, days)),
+                }),
+                created: now.to_rfc3339(),
+            })
+            .await
+    }
+
+    /// Add a user preference stated explicitly, e.g. with
+    /// `/remember preference: ...`.
+    pub async fn remember_preference(&self, content: &str, session_id: &str) -> Result<()> {
+        self.provider("preferences")?
+            .store(MemoryEntry {
+                id: String::new(),
+                provider: "preferences".to_string(),
+                content: content.trim().to_string(),
+                metadata: serde_json::json!({ "session_id": session_id }),
+                created: chrono::Utc::now().to_rfc3339(),
+            })
+            .await
+    }
+
+    /// The enabled provider called `name`.
+    fn provider(&self, name: &str) -> Result<&dyn MemoryProvider> {
+        self.providers
+            .iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("the {name} provider is not enabled")))
+    }
+
+    /// Every provider's entries with their ids.
+    pub async fn entry_ids(&self) -> Result<Vec<EntryId>> {
+        let mut ids = Vec::new();
+        for provider in &self.providers {
+            for (id, summary) in provider.entries().await? {
+                ids.push(EntryId {
+                    provider: provider.name().to_string(),
+                    id,
+                    summary,
+                });
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Resolve `id` to its provider and full id.
+    async fn find_entry(&self, id: &str) -> Result<(&dyn MemoryProvider, String)> {
+        let ids = self.entry_ids().await?;
+        let found = match_id(id, &ids)?;
+        let provider = self
+            .providers
+            .iter()
+            .find(|p| p.name() == found.provider)
+            .expect("entry ids come from the providers");
+        Ok((provider.as_ref(), found.id.clone()))
+    }
+
+    /// An entry as JSON, with its full id.
+    pub async fn get_entry(&self, id: &str) -> Result<(String, String)> {
+        let (provider, id) = self.find_entry(id).await?;
+        let json = provider
+            .get(&id)
+            .await?
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        Ok((id, json))
+    }
+
+    /// Replace an entry with edited JSON.
+    pub async fn update_entry(&self, id: &str, json: &str) -> Result<()> {
+        let (provider, id) = self.find_entry(id).await?;
+        provider.update(&id, json).await
+    }
+
+    /// Delete an entry. Returns its full id.
+    pub async fn delete_entry(&self, id: &str) -> Result<String> {
+        let (provider, id) = self.find_entry(id).await?;
+        provider.delete(&id).await?;
+        Ok(id)
+    }
+
+    /// Delete the entry stored at `key` (e.g. `memory:playbook:str-00003`),
+    /// including one this manager hasn't loaded because another process
+    /// added it since.
+    pub async fn delete_key(&self, key: &str) -> Result<()> {
+        let Some(provider) = self.providers.iter().find(|p| p.owns(key)) else {
+            return Err(crate::error::AgentError::Memory(format!("{key} can't be deleted")));
+        };
+        match self.db.kv.get(key).await {
+            Ok(_) => {}
+            Err(agentfs_core::error::AgentFSError::KeyNotFound { .. }) => {
+                return Err(crate::error::AgentError::Memory(format!("no memory entry {key}")));
+            }
+            Err(e) => return Err(e.into()),
+        }
+        self.db.kv.delete(key).await?;
+        self.tier_manager.remove_metadata(key).await?;
+        self.search_engine.remove_entry(key).await?;
+        provider.evict(key).await;
+        Ok(())
+    }
+
+    /// Search memory using BM25, ranking entries that helped past turns
+    /// above those that didn't.
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        self.search_filtered(query, &SearchFilter::default(), limit).await
+    }
+
+    /// [`search`](Self::search), restricted to entries matching `filter`.
+    pub async fn search_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let mut results = self.search_engine.search_bm25(query, filter, limit).await?;
+        let usefulness = self.tier_manager.usefulness_scores().await?;
+        MemorySearchEngine::apply_combined_scoring(&mut results, &usefulness);
+        Ok(results)
+    }
+
+    /// Run a manual compaction cycle. Removed entries are evicted from
+    /// their providers' caches too.
+    pub async fn compact(&self) -> Result<CompactionReport> {
+        let report = self.compaction.run_cycle().await?;
+        for key in &report.removed_keys {
+            if let Some(provider) = self.providers.iter().find(|p| p.owns(key)) {
+                provider.evict(key).await;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Run a decay pass over all providers, then rebalance tiers.
+    /// Returns (entries decayed, tier changes).
+    pub async fn decay(&self) -> Result<(usize, usize)> {
+        let mut decayed = 0;
+        for provider in &self.providers {
+            decayed += provider.decay(&self.config.tiers).await?;
+        }
+        let rebalanced = self.tier_manager.rebalance().await?;
+        Ok((decayed, rebalanced))
+    }
+
+    /// Get tier distribution counts (hot, warm, cold).
+    pub async fn tier_counts(&self) -> Result<(usize, usize, usize)> {
+        self.tier_manager.tier_counts().await
+    }
+
+    /// Tier, access and outcome stats of every entry.
+    pub async fn entry_stats(&self) -> Result<Vec<EntryStats>> {
+        self.tier_manager.entry_stats().await
+    }
+
+    /// Get memory pressure level.
+    pub async fn memory_pressure(&self) -> Result<MemoryPressure> {
+        self.tier_manager.memory_pressure().await
+    }
+
+    /// Repair drift between memory entries, their metadata and the search
+    /// index. Returns what was repaired.
+    pub async fn repair_search_index(&self) -> Result<agentfs_core::memory_index::MemoryIndexReport> {
+        self.search_engine.repair_index().await
+    }
+
+    /// Get stats for display.
+    #[allow(dead_code)]
+    pub async fn stats(&self) -> Vec<(String, usize)> {
+        let mut stats = Vec::new();
+        for provider in &self.providers {
+            stats.push((provider.name().to_string(), 0));
+        }
+        stats
+    }
+
+    /// Whether memory is enabled.
+    #[allow(dead_code)]
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Provider count.
+    #[allow(dead_code)]
+    pub fn provider_count(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Get a reference to providers for stats queries.
+    pub fn providers(&self) -> &[Box<dyn MemoryProvider>] {
+        &self.providers
+    }
+
+    /// Check if reflection is configured.
+    pub fn has_reflector(&self) -> bool {
+        self.reflector.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config() {
+        let cfg = MemoryConfig::default();
+        assert!(cfg.enabled);
+        assert!(cfg.reflect);
+        assert_eq!(cfg.providers.len(), 5);
+        assert_eq!(cfg.max_reflection_cost_microcents, 5_000_000);
+        assert_eq!(cfg.codebase.max_commands, 8);
+        assert_eq!(cfg.preferences.prompt_budget_chars, 800);
+        assert_eq!(cfg.playbook.max_entries, 100);
+        assert_eq!(cfg.episodes.max_episodes, 20);
+        assert_eq!(cfg.tiers.hot_budget, 30);
+        assert_eq!(cfg.tiers.total_budget, 200);
+        assert_eq!(cfg.compaction.cold_batch_size, 5);
+    }
+
+    #[test]
+    fn deserialize_config() {
+        let json = r#"{"enabled": true, "reflect": false, "providers": ["playbook"]}"#;
+        let cfg: MemoryConfig = serde_json::from_str(json).unwrap();
+        assert!(cfg.enabled);
+        assert!(!cfg.reflect);
+        assert_eq!(cfg.providers, vec!["playbook"]);
+        // Defaults for tiers and compaction
+        assert_eq!(cfg.tiers.hot_budget, 30);
+        assert!(cfg.compaction.dedup_enabled);
+    }
+
+    #[test]
+    fn deserialize_config_with_tiers() {
+        let json = r#"{"enabled": true, "tiers": {"hot_budget": 50, "total_budget": 300}}"#;
+        let cfg: MemoryConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.tiers.hot_budget, 50);
+        assert_eq!(cfg.tiers.total_budget, 300);
+    }
+
+    #[test]
+    fn match_ids() {
+        let entry = |id: &str| EntryId {
+            provider: "episodes".into(),
+            id: id.into(),
+            summary: String::new(),
+        };
+        let entries = vec![entry("ep-1a2b3c4d-aaaa"), entry("ep-1a2b9999-bbbb"), entry("str-00001")];
+        assert_eq!(match_id("str-00001", &entries).unwrap().id, "str-00001");
+        assert_eq!(match_id("ep-1a2b3c4d", &entries).unwrap().id, "ep-1a2b3c4d-aaaa");
+        assert!(match_id("ep-1a2b", &entries).unwrap_err().to_string().contains("several"));
+        assert!(match_id("str-00002", &entries).is_err());
+    }
+
+    #[tokio::test]
+    async fn edit_and_delete_entries() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(
+            AgentFS::create(
+                AgentFSConfig::builder(dir.path().join("test.db"))
+                    .checkpoint_interval_secs(0)
+                    .build(),
+            )
+            .await
+            .unwrap(),
+        );
+        let manager = MemoryManager::from_config(MemoryConfig::default(), Arc::clone(&db))
+            .await
+            .unwrap();
+        manager.on_session_start("s1").await;
+        manager.remember(Category::Strategy, "Run the linter", "s1", None).await.unwrap();
+        manager.remember(Category::Mistake, "Force-push to main", "s1", None).await.unwrap();
+
+        let ids = manager.entry_ids().await.unwrap();
+        assert_eq!(ids.len(), 2);
+
+        let (id, json) = manager.get_entry("str-00001").await.unwrap();
+        let edited = json.replace("Run the linter", "Run cargo clippy");
+        manager.update_entry(&id, &edited).await.unwrap();
+        assert_eq!(manager.search("clippy", 5).await.unwrap().len(), 1);
+        assert!(manager.search("linter", 5).await.unwrap().is_empty());
+        assert!(manager.update_entry(&id, "{not json").await.is_err());
+
+        assert_eq!(manager.delete_entry("str-00002").await.unwrap(), "str-00002");
+        assert!(db.kv.get("memory:playbook:str-00002").await.is_err());
+        assert!(manager.search("force", 5).await.unwrap().is_empty());
+        assert_eq!(manager.entry_ids().await.unwrap().len(), 1);
+        assert!(manager.delete_entry("str-00002").await.is_err());
+
+        // An entry another process added after this manager loaded
+        let other = MemoryManager::from_config(MemoryConfig::default(), Arc::clone(&db))
+            .await
+            .unwrap();
+        other.on_session_start("s2").await;
+        other.remember(Category::Strategy, "Pin the toolchain", "s2", None).await.unwrap();
+        manager.delete_key("memory:playbook:str-00002").await.unwrap();
+        assert!(db.kv.get("memory:playbook:str-00002").await.is_err());
+        assert!(manager.search("toolchain", 5).await.unwrap().is_empty());
+        manager.delete_key("memory:playbook:str-00001").await.unwrap();
+        assert!(manager.entry_ids().await.unwrap().is_empty());
+        assert!(manager.delete_key("memory:playbook:str-00001").await.is_err());
+        assert!(manager.delete_key("session:messages:s1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn search_filters() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(
+            AgentFS::create(
+                AgentFSConfig::builder(dir.path().join("test.db"))
+                    .checkpoint_interval_secs(0)
+                    .build(),
+            )
+            .await
+            .unwrap(),
+        );
+        let manager = MemoryManager::from_config(MemoryConfig::default(), Arc::clone(&db))
+            .await
+            .unwrap();
+        manager.on_session_start("s1").await;
+        manager.remember(Category::Strategy, "Run tests before pushing", "s1", None).await.unwrap();
+        manager.remember(Category::Mistake, "Pushed without running tests", "s1", None).await.unwrap();
+
+        let count = |filter: SearchFilter| {
+            let manager = &manager;
+            async move { manager.search_filtered("tests", &filter, 10).await.unwrap().len() }
+        };
+        assert_eq!(count(SearchFilter::default()).await, 2);
+        assert_eq!(count(SearchFilter { category: Some(Category::Mistake), ..Default::default() }).await, 1);
+        assert_eq!(count(SearchFilter { provider: Some("playbook".into()), ..Default::default() }).await, 2);
+        assert_eq!(count(SearchFilter { provider: Some("episodes".into()), ..Default::default() }).await, 0);
+        assert_eq!(count(SearchFilter { tier: Some(tiers::MemoryTier::Warm), ..Default::default() }).await, 2);
+        assert_eq!(count(SearchFilter { tier: Some(tiers::MemoryTier::Hot), ..Default::default() }).await, 0);
+        assert_eq!(count(SearchFilter { since: Some(parse_since("1d").unwrap()), ..Default::default() }).await, 2);
+        assert_eq!(count(SearchFilter { since: Some("2999-01-01".into()), ..Default::default() }).await, 0);
+    }
+
+    #[tokio::test]
+    async fn reflection_usage_is_per_session() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(
+            AgentFS::create(
+                AgentFSConfig::builder(dir.path().join("test.db"))
+                    .checkpoint_interval_secs(0)
+                    .build(),
+            )
+            .await
+            .unwrap(),
+        );
+        let manager = MemoryManager::from_config(MemoryConfig::default(), Arc::clone(&db))
+            .await
+            .unwrap();
+        db.sessions.start("s1", None, None, None).await.unwrap();
+        for purpose in [Some(REFLECTION_PURPOSE.to_string()), None] {
+            db.analytics
+                .record_usage(TokenRecord {
+                    id: None,
+                    session_id: Some("s1".into()),
+                    tool_call_id: None,
+                    model: "claude-haiku-4-5".into(),
+                    input_tokens: 900,
+                    output_tokens: 100,
+                    cache_read_tokens: 0,
+                    cache_write_tokens: 0,
+                    cost_microcents: 350,
+                    recorded_at: None,
+                    purpose,
+                })
+                .await
+                .unwrap();
+        }
+
+        let usage = manager.reflection_usage("s1").await.unwrap();
+        assert_eq!(usage.record_count, 1);
+        assert_eq!(usage.total_cost_microcents, 350);
+        assert_eq!(manager.reflection_usage("s2").await.unwrap().record_count, 0);
+    }
+
+    #[tokio::test]
+    async fn compaction_removes_expired_entries() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(
+            AgentFS::create(
+                AgentFSConfig::builder(dir.path().join("test.db"))
+                    .checkpoint_interval_secs(0)
+                    .build(),
+            )
+            .await
+            .unwrap(),
+        );
+        let manager = MemoryManager::from_config(MemoryConfig::default(), Arc::clone(&db))
+            .await
+            .unwrap();
+        manager.on_session_start("s1").await;
+        manager.remember(Category::Pattern, "Staging is down", "s1", Some(3.0)).await.unwrap();
+        manager.remember(Category::Strategy, "Run the linter", "s1", None).await.unwrap();
+        let ids = manager.entry_ids().await.unwrap();
+        assert!(ids[0].summary.contains("(expires "));
+
+        let report = manager.compact().await.unwrap();
+        assert_eq!(report.expired_removed, 0);
+
+        let (id, json) = manager.get_entry("str-00001").await.unwrap();
+        let mut entry: serde_json::Value = serde_json::from_str(&json).unwrap();
+        entry["expires"] = "2020-01-01T00:00:00+00:00".into();
+        manager.update_entry(&id, &entry.to_string()).await.unwrap();
+
+        let report = manager.compact().await.unwrap();
+        assert_eq!(report.expired_removed, 1);
+        assert!(db.kv.get("memory:playbook:str-00001").await.is_err());
+        assert!(manager.get_entry("str-00001").await.is_err());
+        assert!(db.kv.get("memory:playbook:str-00002").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn compaction_summarizes_cold_episodes() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(
+            AgentFS::create(
+                AgentFSConfig::builder(dir.path().join("test.db"))
+                    .checkpoint_interval_secs(0)
+                    .build(),
+            )
+            .await
+            .unwrap(),
+        );
+        let manager = MemoryManager::from_config(MemoryConfig::default(), Arc::clone(&db))
+            .await
+            .unwrap();
+        for (i, tools) in [["bash", "read_file"], ["bash", "read_file"], ["bash", "read_file"], ["web_fetch", "write_file"]]
+            .iter()
+            .enumerate()
+        {
+            let episode = episodes::Episode {
+                session_id: format!("s{i}"),
+                summary: format!("Session {i} fixed the build"),
+                key_decisions: vec!["Pin the toolchain".into()],
+                tools_used: tools.iter().map(|t| t.to_string()).collect(),
+                tool_calls: Vec::new(),
+                outcome: "completed".into(),
+                created: format!("2026-01-0{}T12:00:00Z", i + 1),
+                summarizes: Vec::new(),
+            };
+            let key = format!("memory:episode:s{i}");
+            let value = serde_json::to_string(&episode).unwrap();
+            db.kv.set(&key, &value).await.unwrap();
+            manager.tier_manager.ensure_metadata(&key, "episodes", None, value.len() as i64).await.unwrap();
+        }
+        db.writer()
+            .with_conn(|conn| {
+                conn.execute("UPDATE memory_metadata SET tier = 'cold'", [])?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let report = manager.compact().await.unwrap();
+        assert_eq!(report.episodes_compressed, 3);
+        assert_eq!(report.summaries_created, 1);
+
+        let remaining = db.kv.list_prefix("memory:episode:").await.unwrap();
+        assert_eq!(remaining.len(), 2);
+        let entry = remaining.iter().find(|e| e.key.ends_with("-summary")).unwrap();
+        let summary: episodes::Episode = serde_json::from_str(&entry.value).unwrap();
+        assert_eq!(summary.summarizes, vec!["s0", "s1", "s2"]);
+        assert!(summary.summary.starts_with("3 sessions 2026-01-01..2026-01-03: "));
+        assert_eq!(summary.key_decisions, vec!["Pin the toolchain"]);
+        assert!(db.kv.get("memory:archive:episode:s1").await.is_ok());
+        assert!(db.kv.get("memory:episode:s3").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn session_start_repairs_search_index() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(
+            AgentFS::create(
+                AgentFSConfig::builder(dir.path().join("test.db"))
+                    .checkpoint_interval_secs(0)
+                    .build(),
+            )
+            .await
+            .unwrap(),
+        );
+        let manager = MemoryManager::from_config(MemoryConfig::default(), Arc::clone(&db))
+            .await
+            .unwrap();
+        manager.on_session_start("s1").await;
+        manager.remember(Category::Strategy, "Run the linter", "s1", None).await.unwrap();
+        db.writer()
+            .with_conn(|conn| {
+                conn.execute("DELETE FROM memory_fts", [])?;
+                conn.execute(
+                    "INSERT INTO memory_fts (key, provider, content) VALUES ('memory:playbook:gone', 'playbook', 'linter')",
+                    [],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert!(manager.search("linter", 5).await.unwrap().iter().all(|r| r.key.ends_with("gone")));
+
+        manager.on_session_start("s2").await;
+        let results = manager.search("linter", 5).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].key, "memory:playbook:str-00001");
+        assert_eq!(manager.repair_search_index().await.unwrap().total(), 0);
+    }
+
+    #[test]
+    fn outcomes_of_recalled_entries() {
+        let recalled = vec![
+            "memory:playbook:str-00001".to_string(),
+            "memory:playbook:str-00002".to_string(),
+            "memory:tool_pattern:bash".to_string(),
+        ];
+        let ok = serde_json::json!({ "type": "tool_result", "content": "ok" });
+        let err = serde_json::json!({ "type": "tool_result", "content": "boom", "is_error": true });
+
+        let (succeeded, failed) = turn_outcomes(&recalled, &[ok.clone(), ok.clone()], None);
+        assert_eq!(succeeded.len(), 3);
+        assert!(failed.is_empty());
+
+        let (succeeded, failed) = turn_outcomes(&recalled, &[ok.clone(), err.clone()], None);
+        assert!(succeeded.is_empty());
+        assert_eq!(failed.len(), 3);
+
+        // One error in three: only the votes count
+        let reflection = Reflection {
+            learnings: Vec::new(),
+            helpful_ids: vec!["str-00001".into()],
+            harmful_ids: vec!["str-00002".into()],
+            tool_observations: Vec::new(),
+            session_id: "s1".into(),
+            tool_calls: Vec::new(),
+            preferences: Vec::new(),
+        };
+        let (succeeded, failed) =
+            turn_outcomes(&recalled, &[ok.clone(), ok, err], Some(&reflection));
+        assert_eq!(succeeded, vec!["memory:playbook:str-00001"]);
+        assert_eq!(failed, vec!["memory:playbook:str-00002"]);
+
+        // No tool calls: nothing to go on
+        assert_eq!(turn_outcomes(&recalled, &[], None), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn citations() {
+        assert_eq!(citation("str-00003", "2025-12-01T10:00:00Z"), "[str-00003, 2025-12-01]");
+        assert_eq!(citation("str-00003", ""), "[str-00003]");
+        assert_eq!(Provenance::default().citation(), "");
+        let source = Provenance {
+            session_id: "1a2b3c4d-5e6f".into(),
+            recorded: "2025-12-01T10:00:00Z".into(),
+            tool_calls: vec!["toolu_01".into()],
+        };
+        assert_eq!(source.citation(), "[1a2b3c4d, 2025-12-01]");
+    }
+
+    #[test]
+    fn ttls() {
+        assert_eq!(parse_ttl("3d"), Ok(3.0));
+        assert_eq!(parse_ttl("2w"), Ok(14.0));
+        assert_eq!(parse_ttl("12h"), Ok(0.5));
+        assert_eq!(parse_ttl("5"), Ok(5.0));
+        assert!(parse_ttl("0d").is_err());
+        assert!(parse_ttl("soon").is_err());
+
+        assert!(parse_ttl("1e9d").is_err());
+        assert!(parse_ttl("5300w").is_err());
+
+        let now = chrono::Utc::now();
+        let expires = expiry(&now, 1.0);
+        assert!(!is_expired(&expires, &now));
+        let capped = expiry(&now, 1e12);
+        assert!(!is_expired(&capped, &(now + chrono::Duration::days(36_000))));
+        assert!(is_expired(&expires, &(now + chrono::Duration::days(2))));
+        assert!(!is_expired("someday", &now));
+    }
+
+    #[test]
+    fn since() {
+        assert_eq!(parse_since("2026-01-31"), Ok("2026-01-31".to_string()));
+        let week_ago = parse_since("7d").unwrap();
+        let expected = (chrono::Utc::now() - chrono::Duration::days(7)).format("%Y-%m-%d").to_string();
+        assert!(week_ago.starts_with(&expected));
+        assert!(parse_since("last tuesday").is_err());
+        assert!(parse_since("1000000000d").is_err());
+    }
+
+    #[test]
+    fn category_parse() {
+        assert_eq!("mistake".parse::<Category>(), Ok(Category::Mistake));
+        assert!("idea".parse::<Category>().is_err());
+    }
+
+    #[test]
+    fn category_display() {
+        assert_eq!(Category::Strategy.to_string(), "strategy");
+        assert_eq!(Category::Mistake.to_string(), "mistake");
+        assert_eq!(Category::Pattern.to_string(), "pattern");
+    }
+}
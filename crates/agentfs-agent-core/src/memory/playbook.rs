@@ -0,0 +1,689 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use agentfs_core::AgentFS;
+
+use crate::error::Result;
+use crate::memory::{
+    citation, expiry, is_expired, Category, MemoryEntry, MemoryProvider, PlaybookConfig,
+    Reflection,
+};
+
+use super::compaction::content_hash;
+use super::search::MemorySearchEngine;
+use super::tiers::{parse_age_days, TierConfig, TierManager};
+
+const KV_PREFIX: &str = "memory:playbook:";
+
+/// A single playbook entry — a learned strategy, mistake, or pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybookEntry {
+    pub id: String,
+    pub category: Category,
+    pub content: String,
+    pub helpful: i32,
+    pub harmful: i32,
+    pub source_session: String,
+    /// `tool_use` ids of the calls the entry was learned from.
+    #[serde(default)]
+    pub tool_calls: Vec<String>,
+    pub created: String,
+    pub updated: String,
+    /// When the entry stops being true (RFC 3339); compaction deletes it
+    /// after that.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+}
+
+impl PlaybookEntry {
+    /// Net score: helpful - harmful.
+    pub fn score(&self) -> i32 {
+        self.helpful - self.harmful
+    }
+
+    pub fn is_expired(&self, now: &chrono::DateTime<chrono::Utc>) -> bool {
+        self.expires.as_deref().is_some_and(|at| is_expired(at, now))
+    }
+
+    /// ` (expires 2026-01-31)` for entries with a TTL, else empty.
+    fn expiry_note(&self) -> String {
+        match self.expires.as_deref() {
+            Some(at) => format!(" (expires {})", at.get(..10).unwrap_or(at)),
+            None => String::new(),
+        }
+    }
+}
+
+/// ACE-style playbook memory provider.
+pub struct PlaybookProvider {
+    db: Arc<AgentFS>,
+    config: PlaybookConfig,
+    /// In-memory cache of entries, loaded at session start.
+    entries: RwLock<Vec<PlaybookEntry>>,
+    /// Optional tier manager for access tracking and metadata.
+    tier_manager: Option<Arc<TierManager>>,
+    /// Optional search engine for FTS indexing.
+    search_engine: Option<Arc<MemorySearchEngine>>,
+}
+
+impl PlaybookProvider {
+    pub fn new(db: Arc<AgentFS>, config: PlaybookConfig) -> Self {
+        Self {
+            db,
+            config,
+            entries: RwLock::new(Vec::new()),
+            tier_manager: None,
+            search_engine: None,
+        }
+    }
+
+    /// Attach a tier manager and search engine.
+    pub fn with_tier_and_search(
+        mut self,
+        tier_manager: Arc<TierManager>,
+        search_engine: Arc<MemorySearchEngine>,
+    ) -> Self {
+        self.tier_manager = Some(tier_manager);
+        self.search_engine = Some(search_engine);
+        self
+    }
+
+    /// Load all playbook entries from KV.
+    async fn load_entries(&self) -> Result<Vec<PlaybookEntry>> {
+        let kv_entries = self
+            .db
+            .kv
+            .list_prefix(KV_PREFIX)
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let mut entries = Vec::new();
+        for kv in kv_entries {
+            match serde_json::from_str::<PlaybookEntry>(&kv.value) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => {
+                    tracing::warn!("Failed to parse playbook entry {}: {e}", kv.key);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Save a single entry to KV, and update metadata + FTS index.
+    async fn save_entry(&self, entry: &PlaybookEntry) -> Result<()> {
+        let key = format!("{KV_PREFIX}{}", entry.id);
+        let value = serde_json::to_string(entry)
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        self.db
+            .kv
+            .set(&key, &value)
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        // Update tier metadata
+        if let Some(ref tm) = self.tier_manager {
+            let hash = content_hash(&value);
+            let _ = tm.ensure_metadata(&key, "playbook", Some(&hash), value.len() as i64).await;
+        }
+
+        // Index in FTS
+        if let Some(ref se) = self.search_engine {
+            let _ = se.index_entry(&key, "playbook", &entry.content).await;
+        }
+
+        Ok(())
+    }
+
+    /// Remove an entry from KV, metadata and FTS.
+    async fn forget(&self, id: &str) -> Result<()> {
+        let key = format!("{KV_PREFIX}{id}");
+        super::forget(&self.db.kv, self.tier_manager.as_deref(), self.search_engine.as_deref(), &key).await
+    }
+
+    /// Add an entry unless the playbook already has it, making room by
+    /// dropping the lowest-scoring entry when full. Returns `false` for a
+    /// duplicate.
+    #[allow(clippy::too_many_arguments)]
+    async fn insert(
+        &self,
+        entries: &mut Vec<PlaybookEntry>,
+        category: Category,
+        content: &str,
+        session_id: &str,
+        tool_calls: Vec<String>,
+        now: &str,
+        expires: Option<String>,
+    ) -> Result<bool> {
+        // Content-hash dedup check
+        if let Some(ref tm) = self.tier_manager {
+            let hash = content_hash(content);
+            if let Ok(Some(_existing)) = tm.has_content_hash(&hash).await {
+                tracing::debug!("Skipping duplicate playbook entry (content hash match)");
+                return Ok(false);
+            }
+        }
+
+        // Check for duplicate content (case-insensitive fallback)
+        let already_exists = entries
+            .iter()
+            .any(|e| e.content.to_lowercase() == content.to_lowercase());
+        if already_exists {
+            return Ok(false);
+        }
+
+        // Enforce max entries
+        if entries.len() >= self.config.max_entries {
+            // Remove lowest-scoring entry
+            if let Some(min_idx) = entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.score())
+                .map(|(i, _)| i)
+            {
+                let removed = entries.remove(min_idx);
+                let _ = self.forget(&removed.id).await;
+            }
+        }
+
+        let entry = PlaybookEntry {
+            id: Self::next_id(entries),
+            category,
+            content: content.to_string(),
+            helpful: 1,
+            harmful: 0,
+            source_session: session_id.to_string(),
+            tool_calls,
+            created: now.to_string(),
+            updated: now.to_string(),
+            expires,
+        };
+        self.save_entry(&entry).await?;
+        entries.push(entry);
+        Ok(true)
+    }
+
+    /// Generate the next entry ID.
+    fn next_id(entries: &[PlaybookEntry]) -> String {
+        let max_num = entries
+            .iter()
+            .filter_map(|e| e.id.strip_prefix("str-").and_then(|n| n.parse::<u32>().ok()))
+            .max()
+            .unwrap_or(0);
+        format!("str-{:05}", max_num + 1)
+    }
+
+    /// Format entries for system prompt injection, leaving out expired
+    /// ones compaction hasn't deleted yet.
+    pub(crate) fn format_for_prompt(entries: &[PlaybookEntry], budget: usize) -> String {
+        let now = chrono::Utc::now();
+        let mut sorted: Vec<&PlaybookEntry> = entries
+            .iter()
+            .filter(|e| e.score() > 0 && !e.is_expired(&now))
+            .collect();
+        sorted.sort_by_key(|e| std::cmp::Reverse(e.score()));
+
+        let mut sections: Vec<String> = Vec::new();
+        let mut total_len = 0;
+
+        // Group by category
+        for (label, cat) in [
+            ("STRATEGIES", Category::Strategy),
+            ("MISTAKES TO AVOID", Category::Mistake),
+            ("PATTERNS & CONVENTIONS", Category::Pattern),
+        ] {
+            let items: Vec<String> = sorted
+                .iter()
+                .filter(|e| e.category == cat)
+                .map(|e| {
+                    format!(
+                        "- {} [score: {}] {}{}",
+                        e.content,
+                        e.score(),
+                        citation(&e.id, &e.created),
+                        e.expiry_note(),
+                    )
+                })
+                .collect();
+
+            if !items.is_empty() {
+                let section = format!("### {label}\n{}", items.join("\n"));
+                if total_len + section.len() > budget {
+                    break;
+                }
+                total_len += section.len();
+                sections.push(section);
+            }
+        }
+
+        if sections.is_empty() {
+            return String::new();
+        }
+
+        format!("<playbook>\n{}\n</playbook>", sections.join("\n\n"))
+    }
+
+    /// Get entry count.
+    #[allow(dead_code)]
+    pub async fn entry_count(&self) -> usize {
+        self.entries.read().await.len()
+    }
+
+    /// Get top entries for display.
+    #[allow(dead_code)]
+    pub async fn top_entries(&self, limit: usize) -> Vec<PlaybookEntry> {
+        let entries = self.entries.read().await;
+        let mut sorted: Vec<PlaybookEntry> = entries.clone();
+        sorted.sort_by_key(|e| std::cmp::Reverse(e.score()));
+        sorted.truncate(limit);
+        sorted
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for PlaybookProvider {
+    fn name(&self) -> &str {
+        "playbook"
+    }
+
+    async fn context_for_prompt(&self, _query: &str) -> Result<Option<String>> {
+        let entries = self.entries.read().await;
+
+        // Record access for entries included in prompt
+        if let Some(ref tm) = self.tier_manager {
+            for entry in entries.iter().filter(|e| e.score() > 0) {
+                let key = format!("{KV_PREFIX}{}", entry.id);
+                let _ = tm.record_access(&key).await;
+            }
+        }
+
+        let formatted = Self::format_for_prompt(&entries, self.config.prompt_budget_chars);
+        if formatted.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(formatted))
+        }
+    }
+
+    async fn store(&self, entry: MemoryEntry) -> Result<()> {
+        let category = serde_json::from_value(
+            entry.metadata.get("category").cloned().unwrap_or_default(),
+        )
+        .unwrap_or(Category::Pattern);
+        let session_id = entry
+            .metadata
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let tool_calls = serde_json::from_value(
+            entry.metadata.get("tool_calls").cloned().unwrap_or_default(),
+        )
+        .unwrap_or_default();
+        let expires = entry
+            .metadata
+            .get("expires")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        let mut entries = self.entries.write().await;
+        if self
+            .insert(&mut entries, category, &entry.content, session_id, tool_calls, &entry.created, expires)
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(crate::error::AgentError::Memory(
+                "the playbook already has this entry".to_string(),
+            ))
+        }
+    }
+
+    async fn on_reflection(&self, reflection: &Reflection) -> Result<()> {
+        let mut entries = self.entries.write().await;
+        let reflected = chrono::Utc::now();
+        let now = reflected.to_rfc3339();
+
+        // Bump helpful scores
+        for id in &reflection.helpful_ids {
+            if let Some(entry) = entries.iter_mut().find(|e| &e.id == id) {
+                entry.helpful += 1;
+                entry.updated = now.clone();
+                let key = format!("{KV_PREFIX}{}", entry.id);
+                if let Ok(val) = serde_json::to_string(entry) {
+                    let _ = self.db.kv.set(&key, &val).await;
+                    // Update FTS
+                    if let Some(ref se) = self.search_engine {
+                        let _ = se.index_entry(&key, "playbook", &entry.content).await;
+                    }
+                }
+            }
+        }
+
+        // Bump harmful scores
+        for id in &reflection.harmful_ids {
+            if let Some(entry) = entries.iter_mut().find(|e| &e.id == id) {
+                entry.harmful += 1;
+                entry.updated = now.clone();
+                let key = format!("{KV_PREFIX}{}", entry.id);
+                if let Ok(val) = serde_json::to_string(entry) {
+                    let _ = self.db.kv.set(&key, &val).await;
+                }
+            }
+        }
+
+        // Store new learnings
+        let tool_calls = reflection.tool_call_ids();
+        for learning in &reflection.learnings {
+            if learning.confidence < 0.5 {
+                continue;
+            }
+
+            if let Err(e) = self
+                .insert(
+                    &mut entries,
+                    learning.category.clone(),
+                    &learning.content,
+                    &reflection.session_id,
+                    tool_calls.clone(),
+                    &now,
+                    learning.ttl_days.map(|days| expiry(&reflected, days)),
+                )
+                .await
+            {
+                tracing::warn!("Failed to save playbook entry: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn on_session_start(&self, _session_id: &str) -> Result<()> {
+        let loaded = self.load_entries().await?;
+
+        // Ensure metadata and FTS index for all loaded entries
+        if self.tier_manager.is_some() || self.search_engine.is_some() {
+            for entry in &loaded {
+                let key = format!("{KV_PREFIX}{}", entry.id);
+                if let Ok(val) = serde_json::to_string(entry) {
+                    if let Some(ref tm) = self.tier_manager {
+                        let hash = content_hash(&val);
+                        let _ = tm.ensure_metadata(&key, "playbook", Some(&hash), val.len() as i64).await;
+                    }
+                    if let Some(ref se) = self.search_engine {
+                        let _ = se.index_entry(&key, "playbook", &entry.content).await;
+                    }
+                }
+            }
+        }
+
+        *self.entries.write().await = loaded;
+        Ok(())
+    }
+
+    async fn on_session_end(&self, _session_id: &str) -> Result<()> {
+        // Entries are saved incrementally; nothing extra needed.
+        Ok(())
+    }
+
+    async fn decay(&self, rules: &TierConfig) -> Result<usize> {
+        let now = chrono::Utc::now();
+        let stamp = now.to_rfc3339();
+        let mut entries = self.entries.write().await;
+        let mut decayed = 0;
+        for entry in entries.iter_mut() {
+            let helpful = rules.decayed_helpful(entry.helpful, parse_age_days(&entry.updated, &now));
+            if helpful == entry.helpful {
+                continue;
+            }
+            // Restarting the clock means the next decay only counts the
+            // time from here.
+            entry.helpful = helpful;
+            entry.updated = stamp.clone();
+            self.save_entry(entry).await?;
+            decayed += 1;
+        }
+        Ok(decayed)
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, String)>> {
+        let entries = self.entries.read().await;
+        Ok(entries
+            .iter()
+            .map(|e| {
+                let summary = format!("{}: {} [{:+}]{}", e.category, e.content, e.score(), e.expiry_note());
+                (e.id.clone(), summary)
+            })
+            .collect())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<String>> {
+        let entries = self.entries.read().await;
+        entries
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| {
+                serde_json::to_string_pretty(e)
+                    .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn update(&self, id: &str, json: &str) -> Result<()> {
+        let mut edited: PlaybookEntry = serde_json::from_str(json)
+            .map_err(|e| crate::error::AgentError::Memory(format!("invalid entry: {e}")))?;
+        let mut entries = self.entries.write().await;
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        // The id is the storage key; keep it whatever the edit says.
+        edited.id = id.to_string();
+        edited.updated = chrono::Utc::now().to_rfc3339();
+        self.save_entry(&edited).await?;
+        *entry = edited;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let mut entries = self.entries.write().await;
+        let idx = entries
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        self.forget(id).await?;
+        entries.remove(idx);
+        Ok(())
+    }
+
+    fn owns(&self, key: &str) -> bool {
+        key.starts_with(KV_PREFIX)
+    }
+
+    async fn evict(&self, key: &str) {
+        if let Some(id) = key.strip_prefix(KV_PREFIX) {
+            self.entries.write().await.retain(|e| e.id != id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_score() {
+        let entry = PlaybookEntry {
+            id: "str-00001".into(),
+            category: Category::Strategy,
+            content: "test".into(),
+            helpful: 5,
+            harmful: 2,
+            source_session: "s1".into(),
+            tool_calls: Vec::new(),
+            created: String::new(),
+            updated: String::new(),
+            expires: None,
+        };
+        assert_eq!(entry.score(), 3);
+    }
+
+    #[test]
+    fn format_empty() {
+        let result = PlaybookProvider::format_for_prompt(&[], 2000);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn format_with_entries() {
+        let entries = vec![
+            PlaybookEntry {
+                id: "str-00001".into(),
+                category: Category::Strategy,
+                content: "Always check file exists".into(),
+                helpful: 5,
+                harmful: 0,
+                source_session: "s1".into(),
+                tool_calls: vec!["toolu_01".into()],
+                created: "2025-12-01T10:00:00Z".into(),
+                updated: String::new(),
+                expires: None,
+            },
+            PlaybookEntry {
+                id: "str-00002".into(),
+                category: Category::Mistake,
+                content: "Don't use rm -rf without checking".into(),
+                helpful: 3,
+                harmful: 0,
+                source_session: "s1".into(),
+                tool_calls: Vec::new(),
+                created: String::new(),
+                updated: String::new(),
+                expires: Some("2099-01-31T00:00:00Z".into()),
+            },
+        ];
+
+        let result = PlaybookProvider::format_for_prompt(&entries, 2000);
+        assert!(result.contains("<playbook>"));
+        assert!(result.contains("STRATEGIES"));
+        assert!(result.contains("MISTAKES TO AVOID"));
+        assert!(result.contains("- Always check file exists [score: 5] [str-00001, 2025-12-01]"));
+        assert!(result.contains("[str-00002] (expires 2099-01-31)"));
+    }
+
+    #[test]
+    fn format_skips_expired() {
+        let entries = vec![PlaybookEntry {
+            id: "str-00001".into(),
+            category: Category::Strategy,
+            content: "Use the backup staging server".into(),
+            helpful: 2,
+            harmful: 0,
+            source_session: "s1".into(),
+            tool_calls: Vec::new(),
+            created: String::new(),
+            updated: String::new(),
+            expires: Some("2020-01-01T00:00:00Z".into()),
+        }];
+        assert!(PlaybookProvider::format_for_prompt(&entries, 2000).is_empty());
+    }
+
+    #[tokio::test]
+    async fn store_dedups_and_evicts() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentFSConfig::builder(dir.path().join("test.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        let db = Arc::new(AgentFS::create(config).await.unwrap());
+        let provider = PlaybookProvider::new(
+            Arc::clone(&db),
+            PlaybookConfig {
+                max_entries: 2,
+                prompt_budget_chars: 2000,
+            },
+        );
+        let entry = |content: &str| MemoryEntry {
+            id: String::new(),
+            provider: "playbook".into(),
+            content: content.into(),
+            metadata: serde_json::json!({ "category": "strategy", "session_id": "s1" }),
+            created: "2026-01-01T00:00:00Z".into(),
+        };
+
+        provider.store(entry("Run clippy before committing")).await.unwrap();
+        assert!(provider.store(entry("run clippy before committing")).await.is_err());
+        provider.store(entry("Prefer small commits")).await.unwrap();
+        provider.store(entry("Read the tests first")).await.unwrap();
+
+        let entries = provider.load_entries().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.category == Category::Strategy));
+        assert!(entries.iter().any(|e| e.id == "str-00003" && e.content == "Read the tests first"));
+    }
+
+    #[tokio::test]
+    async fn decay_down_weights_stale_entries() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentFSConfig::builder(dir.path().join("test.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        let db = Arc::new(AgentFS::create(config).await.unwrap());
+        let provider = PlaybookProvider::new(Arc::clone(&db), PlaybookConfig::default());
+
+        let long_ago = (chrono::Utc::now() - chrono::Duration::days(42)).to_rfc3339();
+        let recent = chrono::Utc::now().to_rfc3339();
+        for (content, updated) in [("Old lesson", &long_ago), ("New lesson", &recent)] {
+            let entry = MemoryEntry {
+                id: String::new(),
+                provider: "playbook".into(),
+                content: content.into(),
+                metadata: serde_json::json!({ "category": "strategy" }),
+                created: updated.clone(),
+            };
+            provider.store(entry).await.unwrap();
+        }
+        for entry in provider.entries.write().await.iter_mut() {
+            entry.helpful = 8;
+            provider.save_entry(entry).await.unwrap();
+        }
+
+        assert_eq!(provider.decay(&TierConfig::default()).await.unwrap(), 1);
+        let entries = provider.load_entries().await.unwrap();
+        let old = entries.iter().find(|e| e.content == "Old lesson").unwrap();
+        let new = entries.iter().find(|e| e.content == "New lesson").unwrap();
+        assert_eq!(old.helpful, 1);
+        assert_ne!(old.updated, long_ago);
+        assert_eq!(new.helpful, 8);
+
+        // Just decayed, so nothing is stale any more
+        assert_eq!(provider.decay(&TierConfig::default()).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn next_id_from_empty() {
+        assert_eq!(PlaybookProvider::next_id(&[]), "str-00001");
+    }
+
+    #[test]
+    fn next_id_increments() {
+        let entries = vec![PlaybookEntry {
+            id: "str-00003".into(),
+            category: Category::Strategy,
+            content: "test".into(),
+            helpful: 1,
+            harmful: 0,
+            source_session: "s1".into(),
+            tool_calls: Vec::new(),
+            created: String::new(),
+            updated: String::new(),
+            expires: None,
+        }];
+        assert_eq!(PlaybookProvider::next_id(&entries), "str-00004");
+    }
+}
@@ -0,0 +1,436 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use agentfs_core::AgentFS;
+
+use crate::error::Result;
+use crate::memory::{
+    citation, MemoryEntry, MemoryProvider, PreferencesConfig, Provenance, Reflection,
+};
+
+use super::compaction::content_hash;
+use super::search::MemorySearchEngine;
+use super::tiers::TierManager;
+
+const KV_PREFIX: &str = "memory:preference:";
+
+/// A stable user preference, e.g. "Use pytest, not unittest".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preference {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub source: Provenance,
+    pub updated: String,
+}
+
+/// User preference memory provider. Preferences hold across tasks, so they
+/// are kept apart from playbook learnings, with their own budget, and
+/// injected ahead of other memory.
+pub struct PreferenceProvider {
+    db: Arc<AgentFS>,
+    config: PreferencesConfig,
+    /// In-memory cache of preferences, loaded at session start.
+    preferences: RwLock<Vec<Preference>>,
+    /// Optional tier manager for access tracking and metadata.
+    tier_manager: Option<Arc<TierManager>>,
+    /// Optional search engine for FTS indexing.
+    search_engine: Option<Arc<MemorySearchEngine>>,
+}
+
+impl PreferenceProvider {
+    pub fn new(db: Arc<AgentFS>, config: PreferencesConfig) -> Self {
+        Self {
+            db,
+            config,
+            preferences: RwLock::new(Vec::new()),
+            tier_manager: None,
+            search_engine: None,
+        }
+    }
+
+    /// Attach a tier manager and search engine.
+    pub fn with_tier_and_search(
+        mut self,
+        tier_manager: Arc<TierManager>,
+        search_engine: Arc<MemorySearchEngine>,
+    ) -> Self {
+        self.tier_manager = Some(tier_manager);
+        self.search_engine = Some(search_engine);
+        self
+    }
+
+    /// Load all preferences from KV.
+    async fn load_preferences(&self) -> Result<Vec<Preference>> {
+        let kv_entries = self
+            .db
+            .kv
+            .list_prefix(KV_PREFIX)
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let mut preferences = Vec::new();
+        for kv in kv_entries {
+            match serde_json::from_str::<Preference>(&kv.value) {
+                Ok(pref) => preferences.push(pref),
+                Err(e) => {
+                    tracing::warn!("Failed to parse preference {}: {e}", kv.key);
+                }
+            }
+        }
+        Ok(preferences)
+    }
+
+    /// Save a preference to KV, and update metadata + FTS index.
+    async fn save_preference(&self, pref: &Preference) -> Result<()> {
+        let key = format!("{KV_PREFIX}{}", pref.id);
+        let value = serde_json::to_string(pref)
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        self.db
+            .kv
+            .set(&key, &value)
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        if let Some(ref tm) = self.tier_manager {
+            let hash = content_hash(&value);
+            let _ = tm.ensure_metadata(&key, "preferences", Some(&hash), value.len() as i64).await;
+        }
+        if let Some(ref se) = self.search_engine {
+            let _ = se.index_entry(&key, "preferences", &pref.content).await;
+        }
+
+        Ok(())
+    }
+
+    /// Remove a preference from KV, metadata and FTS.
+    async fn forget(&self, id: &str) -> Result<()> {
+        let key = format!("{KV_PREFIX}{id}");
+        super::forget(&self.db.kv, self.tier_manager.as_deref(), self.search_engine.as_deref(), &key).await
+    }
+
+    /// Add a preference unless it's already known, dropping the least
+    /// recently confirmed one when full. Returns `false` for a duplicate.
+    async fn insert(
+        &self,
+        preferences: &mut Vec<Preference>,
+        content: &str,
+        source: Provenance,
+    ) -> Result<bool> {
+        let content = content.trim();
+        if content.is_empty() {
+            return Ok(false);
+        }
+        if let Some(existing) = preferences
+            .iter_mut()
+            .find(|p| p.content.to_lowercase() == content.to_lowercase())
+        {
+            // Seen again: it's still current.
+            existing.updated = source.recorded;
+            self.save_preference(existing).await?;
+            return Ok(false);
+        }
+
+        if preferences.len() >= self.config.max_entries {
+            if let Some(oldest) = preferences
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.updated.cmp(&b.updated))
+                .map(|(i, _)| i)
+            {
+                let removed = preferences.remove(oldest);
+                let _ = self.forget(&removed.id).await;
+            }
+        }
+
+        let pref = Preference {
+            id: Self::next_id(preferences),
+            content: content.to_string(),
+            updated: source.recorded.clone(),
+            source,
+        };
+        self.save_preference(&pref).await?;
+        preferences.push(pref);
+        Ok(true)
+    }
+
+    /// Generate the next preference ID.
+    fn next_id(preferences: &[Preference]) -> String {
+        let max_num = preferences
+            .iter()
+            .filter_map(|p| p.id.strip_prefix("pref-").and_then(|n| n.parse::<u32>().ok()))
+            .max()
+            .unwrap_or(0);
+        format!("pref-{:05}", max_num + 1)
+    }
+
+    /// Format preferences for system prompt, oldest first.
+    fn format_for_prompt(preferences: &[Preference], budget: usize) -> String {
+        let mut lines = Vec::new();
+        let mut total_len = 0;
+
+        for pref in preferences {
+            let line = format!("- {} {}", pref.content, citation(&pref.id, &pref.source.recorded));
+            if total_len + line.len() > budget {
+                break;
+            }
+            total_len += line.len();
+            lines.push(line);
+        }
+
+        if lines.is_empty() {
+            return String::new();
+        }
+
+        format!("<user_preferences>\n{}\n</user_preferences>", lines.join("\n"))
+    }
+}
+
+#[async_trait]
+impl MemoryProvider for PreferenceProvider {
+    fn name(&self) -> &str {
+        "preferences"
+    }
+
+    async fn context_for_prompt(&self, _query: &str) -> Result<Option<String>> {
+        let preferences = self.preferences.read().await;
+
+        if let Some(ref tm) = self.tier_manager {
+            for pref in preferences.iter() {
+                let key = format!("{KV_PREFIX}{}", pref.id);
+                let _ = tm.record_access(&key).await;
+            }
+        }
+
+        let formatted = Self::format_for_prompt(&preferences, self.config.prompt_budget_chars);
+        if formatted.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(formatted))
+        }
+    }
+
+    async fn store(&self, entry: MemoryEntry) -> Result<()> {
+        let session_id = entry
+            .metadata
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let source = Provenance {
+            session_id: session_id.to_string(),
+            recorded: entry.created,
+            tool_calls: Vec::new(),
+        };
+
+        let mut preferences = self.preferences.write().await;
+        if self.insert(&mut preferences, &entry.content, source).await? {
+            Ok(())
+        } else {
+            Err(crate::error::AgentError::Memory(
+                "that preference is already remembered".to_string(),
+            ))
+        }
+    }
+
+    async fn on_reflection(&self, reflection: &Reflection) -> Result<()> {
+        if reflection.preferences.is_empty() {
+            return Ok(());
+        }
+        let mut preferences = self.preferences.write().await;
+        for content in &reflection.preferences {
+            let source = Provenance::new(&reflection.session_id, reflection.tool_call_ids());
+            if let Err(e) = self.insert(&mut preferences, content, source).await {
+                tracing::warn!("Failed to save preference: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_session_start(&self, _session_id: &str) -> Result<()> {
+        let mut loaded = self.load_preferences().await?;
+        loaded.sort_by(|a, b| a.id.cmp(&b.id));
+
+        if self.tier_manager.is_some() || self.search_engine.is_some() {
+            for pref in &loaded {
+                let key = format!("{KV_PREFIX}{}", pref.id);
+                if let Ok(val) = serde_json::to_string(pref) {
+                    if let Some(ref tm) = self.tier_manager {
+                        let hash = content_hash(&val);
+                        let _ = tm.ensure_metadata(&key, "preferences", Some(&hash), val.len() as i64).await;
+                    }
+                    if let Some(ref se) = self.search_engine {
+                        let _ = se.index_entry(&key, "preferences", &pref.content).await;
+                    }
+                }
+            }
+        }
+
+        *self.preferences.write().await = loaded;
+        Ok(())
+    }
+
+    async fn on_session_end(&self, _session_id: &str) -> Result<()> {
+        // Preferences are saved as they're learned; nothing extra needed.
+        Ok(())
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, String)>> {
+        let preferences = self.preferences.read().await;
+        Ok(preferences
+            .iter()
+            .map(|p| (p.id.clone(), p.content.clone()))
+            .collect())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<String>> {
+        let preferences = self.preferences.read().await;
+        preferences
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| {
+                serde_json::to_string_pretty(p)
+                    .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn update(&self, id: &str, json: &str) -> Result<()> {
+        let mut edited: Preference = serde_json::from_str(json)
+            .map_err(|e| crate::error::AgentError::Memory(format!("invalid preference: {e}")))?;
+        let mut preferences = self.preferences.write().await;
+        let pref = preferences
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        // The id is the storage key; keep it whatever the edit says.
+        edited.id = id.to_string();
+        edited.updated = chrono::Utc::now().to_rfc3339();
+        self.save_preference(&edited).await?;
+        *pref = edited;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let mut preferences = self.preferences.write().await;
+        let idx = preferences
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        self.forget(id).await?;
+        preferences.remove(idx);
+        Ok(())
+    }
+
+    fn owns(&self, key: &str) -> bool {
+        key.starts_with(KV_PREFIX)
+    }
+
+    async fn evict(&self, key: &str) {
+        if let Some(id) = key.strip_prefix(KV_PREFIX) {
+            self.preferences.write().await.retain(|p| p.id != id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pref(id: &str, content: &str) -> Preference {
+        Preference {
+            id: id.into(),
+            content: content.into(),
+            source: Provenance {
+                session_id: "s1".into(),
+                recorded: "2026-03-01T09:00:00Z".into(),
+                tool_calls: Vec::new(),
+            },
+            updated: "2026-03-01T09:00:00Z".into(),
+        }
+    }
+
+    #[test]
+    fn format_with_budget() {
+        assert!(PreferenceProvider::format_for_prompt(&[], 800).is_empty());
+
+        let prefs = vec![
+            pref("pref-00001", "Never force-push"),
+            pref("pref-00002", "Use pytest for Python tests"),
+        ];
+        let result = PreferenceProvider::format_for_prompt(&prefs, 800);
+        assert!(result.starts_with("<user_preferences>"));
+        assert!(result.contains("- Never force-push [pref-00001, 2026-03-01]"));
+
+        let result = PreferenceProvider::format_for_prompt(&prefs, 50);
+        assert!(result.contains("Never force-push"));
+        assert!(!result.contains("pytest"));
+    }
+
+    #[test]
+    fn next_id_increments() {
+        assert_eq!(PreferenceProvider::next_id(&[]), "pref-00001");
+        assert_eq!(
+            PreferenceProvider::next_id(&[pref("pref-00004", "x")]),
+            "pref-00005"
+        );
+    }
+
+    #[tokio::test]
+    async fn reflection_adds_preferences_once() {
+        use agentfs_core::config::AgentFSConfig;
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentFSConfig::builder(dir.path().join("test.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        let db = Arc::new(AgentFS::create(config).await.unwrap());
+        let provider = PreferenceProvider::new(
+            Arc::clone(&db),
+            PreferencesConfig {
+                max_entries: 2,
+                prompt_budget_chars: 800,
+            },
+        );
+        let reflection = Reflection {
+            learnings: Vec::new(),
+            helpful_ids: Vec::new(),
+            harmful_ids: Vec::new(),
+            tool_observations: Vec::new(),
+            session_id: "s1".into(),
+            tool_calls: Vec::new(),
+            preferences: vec!["Never force-push".into(), "never force-push".into()],
+        };
+        provider.on_reflection(&reflection).await.unwrap();
+        assert_eq!(provider.load_preferences().await.unwrap().len(), 1);
+
+        provider
+            .store(MemoryEntry {
+                id: String::new(),
+                provider: "preferences".into(),
+                content: "Use tabs".into(),
+                metadata: serde_json::json!({ "session_id": "s2" }),
+                created: chrono::Utc::now().to_rfc3339(),
+            })
+            .await
+            .unwrap();
+        provider
+            .store(MemoryEntry {
+                id: String::new(),
+                provider: "preferences".into(),
+                content: "Answer in British English".into(),
+                metadata: serde_json::json!({ "session_id": "s3" }),
+                created: chrono::Utc::now().to_rfc3339(),
+            })
+            .await
+            .unwrap();
+
+        // Full: the least recently confirmed preference made room.
+        let loaded = provider.load_preferences().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert!(loaded.iter().all(|p| p.content != "Never force-push"));
+    }
+}
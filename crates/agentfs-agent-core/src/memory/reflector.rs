@@ -3,16 +3,17 @@ use serde_json::Value;
 use crate::api::Message;
 use crate::auth::AuthProvider;
 use crate::error::{AgentError, Result};
-use crate::memory::{Category, Learning, Reflection, ToolObs};
+use crate::memory::{Category, Learning, Reflection, ToolCallRef, ToolObs};
 
 const API_URL: &str = "https://api.anthropic.com/v1/messages";
 const API_VERSION: &str = "2023-06-01";
 
 const REFLECTION_PROMPT: &str = r#"Analyze this conversation turn and extract learnings. Return a JSON object with:
-- "learnings": array of {"category": "strategy"|"mistake"|"pattern", "content": "what was learned", "confidence": 0.0-1.0}
+- "learnings": array of {"category": "strategy"|"mistake"|"pattern", "content": "what was learned", "confidence": 0.0-1.0, "ttl_days": optional number of days it stays true, only for temporary facts like "the staging server is down"}
 - "helpful_ids": array of playbook entry IDs that were helpful (if any referenced in context)
 - "harmful_ids": array of playbook entry IDs that were wrong/misleading
 - "tool_observations": array of {"tool": "tool_name", "success": true/false, "pattern": "optional tip", "error": "optional error description"}
+- "preferences": array of stable user preferences stated or implied, e.g. "Use pytest for tests", "Never force-push" (not task-specific details)
 
 Focus on:
 1. What strategies worked or failed
@@ -23,6 +24,13 @@ Focus on:
 Be selective — only extract high-confidence learnings. Prefer 0-3 learnings per turn.
 Return ONLY the JSON object, no other text."#;
 
+/// Tokens one reflection call used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReflectionUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
 /// The reflector analyzes turns and extracts learnings.
 pub struct Reflector {
     model: String,
@@ -37,6 +45,11 @@ impl Reflector {
         }
     }
 
+    /// The model reflection calls use.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
     /// Check whether reflection should be triggered for this turn.
     pub fn should_reflect(&self, messages: &[Message], tool_results: &[Value]) -> bool {
         // Trigger 1: A tool call errored
@@ -72,14 +85,15 @@ impl Reflector {
         false
     }
 
-    /// Analyze a turn and produce a Reflection.
+    /// Analyze a turn and produce a Reflection, with the tokens the call
+    /// used.
     pub async fn reflect_on_turn(
         &self,
         auth: &mut AuthProvider,
         messages: &[Message],
         tool_results: &[Value],
         session_id: &str,
-    ) -> Result<Reflection> {
+    ) -> Result<(Reflection, ReflectionUsage)> {
         // Build a condensed version of the turn for analysis
         let turn_summary = self.summarize_turn(messages, tool_results);
 
@@ -92,8 +106,10 @@ impl Reflector {
             },
         ];
 
-        let response = self.call_api(auth, &reflection_messages).await?;
-        self.parse_reflection(&response, session_id)
+        let (response, usage) = self.call_api(auth, &reflection_messages).await?;
+        let mut reflection = self.parse_reflection(&response, session_id)?;
+        reflection.tool_calls = turn_tool_calls(messages, tool_results);
+        Ok((reflection, usage))
     }
 
     /// Make a non-streaming API call to the cheap model.
@@ -101,7 +117,7 @@ impl Reflector {
         &self,
         auth: &mut AuthProvider,
         messages: &[Message],
-    ) -> Result<String> {
+    ) -> Result<(String, ReflectionUsage)> {
         let auth_headers = auth.get_auth_headers().await?;
 
         let body = serde_json::json!({
@@ -158,8 +174,12 @@ impl Reflector {
             .and_then(|block| block["text"].as_str())
             .unwrap_or("")
             .to_string();
+        let usage = ReflectionUsage {
+            input_tokens: json["usage"]["input_tokens"].as_u64().unwrap_or(0),
+            output_tokens: json["usage"]["output_tokens"].as_u64().unwrap_or(0),
+        };
 
-        Ok(text)
+        Ok((text, usage))
     }
 
     /// Build a condensed summary of the turn for reflection.
@@ -278,10 +298,12 @@ impl Reflector {
                         };
                         let content = item["content"].as_str()?.to_string();
                         let confidence = item["confidence"].as_f64().unwrap_or(0.5) as f32;
+                        let ttl_days = item["ttl_days"].as_f64().filter(|d| *d > 0.0);
                         Some(Learning {
                             category,
                             content,
                             confidence,
+                            ttl_days,
                         })
                     })
                     .collect()
@@ -326,16 +348,50 @@ impl Reflector {
             })
             .unwrap_or_default();
 
+        let preferences = parsed["preferences"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Ok(Reflection {
             learnings,
             helpful_ids,
             harmful_ids,
             tool_observations,
             session_id: session_id.to_string(),
+            tool_calls: Vec::new(),
+            preferences,
         })
     }
 }
 
+/// The tool calls whose results are in `tool_results`, named from the
+/// `tool_use` blocks in `messages`.
+fn turn_tool_calls(messages: &[Message], tool_results: &[Value]) -> Vec<ToolCallRef> {
+    let ids: Vec<&str> = tool_results
+        .iter()
+        .filter_map(|r| r.get("tool_use_id").and_then(|v| v.as_str()))
+        .collect();
+    messages
+        .iter()
+        .filter_map(|m| m.content.as_array())
+        .flatten()
+        .filter(|block| block.get("type").and_then(|v| v.as_str()) == Some("tool_use"))
+        .filter_map(|block| {
+            let id = block.get("id")?.as_str()?;
+            let tool = block.get("name")?.as_str()?;
+            ids.contains(&id).then(|| ToolCallRef {
+                id: id.to_string(),
+                tool: tool.to_string(),
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -388,21 +444,50 @@ mod tests {
         let r = make_reflector();
         let json = r#"{
             "learnings": [
-                {"category": "strategy", "content": "Check file exists first", "confidence": 0.9}
+                {"category": "strategy", "content": "Check file exists first", "confidence": 0.9},
+                {"category": "pattern", "content": "Staging is down, use the backup", "confidence": 0.8, "ttl_days": 3}
             ],
             "helpful_ids": ["str-00001"],
             "harmful_ids": [],
             "tool_observations": [
                 {"tool": "bash", "success": true, "pattern": "Use absolute paths"}
-            ]
+            ],
+            "preferences": ["Never force-push"]
         }"#;
 
         let reflection = r.parse_reflection(json, "test-session").unwrap();
-        assert_eq!(reflection.learnings.len(), 1);
+        assert_eq!(reflection.learnings.len(), 2);
         assert_eq!(reflection.learnings[0].category, Category::Strategy);
+        assert_eq!(reflection.learnings[0].ttl_days, None);
+        assert_eq!(reflection.learnings[1].ttl_days, Some(3.0));
         assert_eq!(reflection.helpful_ids, vec!["str-00001"]);
         assert_eq!(reflection.tool_observations.len(), 1);
         assert_eq!(reflection.session_id, "test-session");
+        assert_eq!(reflection.preferences, vec!["Never force-push"]);
+    }
+
+    #[test]
+    fn tool_calls_of_turn() {
+        let messages = vec![
+            Message {
+                role: "assistant".into(),
+                content: serde_json::json!([
+                    {"type": "tool_use", "id": "toolu_old", "name": "bash", "input": {}}
+                ]),
+            },
+            Message {
+                role: "assistant".into(),
+                content: serde_json::json!([
+                    {"type": "text", "text": "Reading it"},
+                    {"type": "tool_use", "id": "toolu_new", "name": "read_file", "input": {}}
+                ]),
+            },
+        ];
+        let tool_results = vec![serde_json::json!({"tool_use_id": "toolu_new", "content": "ok"})];
+
+        let calls = turn_tool_calls(&messages, &tool_results);
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].id.as_str(), calls[0].tool.as_str()), ("toolu_new", "read_file"));
     }
 
     #[test]
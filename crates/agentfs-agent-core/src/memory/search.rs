@@ -3,6 +3,13 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 use agentfs_core::connection::pool::{ReaderPool, WriterHandle};
+use agentfs_core::memory_index::{self, MemoryIndexReport};
+
+/// Rows of each kind fixed per write transaction by [`MemorySearchEngine::repair_index`].
+const REPAIR_BATCH: i64 = 200;
+
+use super::tiers::MemoryTier;
+use super::Category;
 
 // ── Types ──────────────────────────────────────────────────────────
 
@@ -16,6 +23,18 @@ pub struct SearchResult {
     pub combined_score: f64,
 }
 
+/// Restricts which entries a search can return. Empty matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// Provider name the entry was indexed under, e.g. `playbook`.
+    pub provider: Option<String>,
+    pub tier: Option<MemoryTier>,
+    /// Only entries created at or after this `memory_metadata` timestamp.
+    pub since: Option<String>,
+    /// Playbook category (`strategy`, `mistake` or `pattern`).
+    pub category: Option<Category>,
+}
+
 // ── MemorySearchEngine ─────────────────────────────────────────────
 
 /// FTS5-backed BM25 search engine for memory entries.
@@ -72,12 +91,12 @@ impl MemorySearchEngine {
 
     /// Search using BM25 ranking.
     ///
-    /// Returns results ranked by `-bm25(memory_fts)` (higher is more relevant).
-    /// Optionally filter by provider.
+    /// Returns results ranked by `-bm25(memory_fts)` (higher is more relevant),
+    /// restricted to entries matching `filter`.
     pub async fn search_bm25(
         &self,
         query: &str,
-        provider_filter: Option<&str>,
+        filter: &SearchFilter,
         limit: usize,
     ) -> crate::error::Result<Vec<SearchResult>> {
         let reader = self.readers.acquire().await
@@ -88,35 +107,31 @@ impl MemorySearchEngine {
             return Ok(Vec::new());
         }
 
-        let (sql, params_vec): (String, Vec<Box<dyn rusqlite::types::ToSql>>) =
-            if let Some(provider) = provider_filter {
-                (
-                    "SELECT key, provider, snippet(memory_fts, 2, '»', '«', '…', 32) as snip,
-                            -bm25(memory_fts) as rank
-                     FROM memory_fts
-                     WHERE memory_fts MATCH ?1 AND provider = ?2
-                     ORDER BY rank DESC
-                     LIMIT ?3".to_string(),
-                    vec![
-                        Box::new(query) as Box<dyn rusqlite::types::ToSql>,
-                        Box::new(provider.to_string()) as Box<dyn rusqlite::types::ToSql>,
-                        Box::new(limit as i64) as Box<dyn rusqlite::types::ToSql>,
-                    ],
-                )
-            } else {
-                (
-                    "SELECT key, provider, snippet(memory_fts, 2, '»', '«', '…', 32) as snip,
-                            -bm25(memory_fts) as rank
-                     FROM memory_fts
-                     WHERE memory_fts MATCH ?1
-                     ORDER BY rank DESC
-                     LIMIT ?2".to_string(),
-                    vec![
-                        Box::new(query) as Box<dyn rusqlite::types::ToSql>,
-                        Box::new(limit as i64) as Box<dyn rusqlite::types::ToSql>,
-                    ],
-                )
-            };
+        let mut sql = "SELECT f.key, f.provider, snippet(memory_fts, 2, '»', '«', '…', 32) as snip,
+                    -bm25(memory_fts) as rank
+             FROM memory_fts f
+             LEFT JOIN memory_metadata m ON m.key = f.key
+             LEFT JOIN kv_store kv ON kv.key = f.key
+             WHERE memory_fts MATCH ?".to_string();
+        let mut params_vec: Vec<Box<dyn rusqlite::types::ToSql>> = vec![Box::new(query)];
+        if let Some(provider) = &filter.provider {
+            sql.push_str(" AND f.provider = ?");
+            params_vec.push(Box::new(provider.clone()));
+        }
+        if let Some(tier) = &filter.tier {
+            sql.push_str(" AND m.tier = ?");
+            params_vec.push(Box::new(tier.as_str()));
+        }
+        if let Some(since) = &filter.since {
+            sql.push_str(" AND m.created >= ?");
+            params_vec.push(Box::new(since.clone()));
+        }
+        if let Some(category) = &filter.category {
+            sql.push_str(" AND json_extract(kv.value, '$.category') = ?");
+            params_vec.push(Box::new(category.to_string()));
+        }
+        sql.push_str(" ORDER BY rank DESC LIMIT ?");
+        params_vec.push(Box::new(limit as i64));
 
         let params_refs: Vec<&dyn rusqlite::types::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
 
@@ -143,7 +158,6 @@ impl MemorySearchEngine {
     /// Search with combined scoring: bm25_score × (0.3 + 0.7 × normalized_memory_score).
     ///
     /// `memory_scores` maps key → normalized memory score in [0, 1].
-    #[allow(dead_code)]
     pub fn apply_combined_scoring(
         results: &mut [SearchResult],
         memory_scores: &std::collections::HashMap<String, f64>,
@@ -162,49 +176,23 @@ impl MemorySearchEngine {
         });
     }
 
-    /// Rebuild the entire FTS5 index from kv_store memory entries.
-    #[allow(dead_code)]
-    pub async fn rebuild_index(&self) -> crate::error::Result<usize> {
-        let reader = self.readers.acquire().await
-            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
-
-        // Collect all memory KV entries
-        let mut stmt = reader.conn().prepare(
-            "SELECT key, value FROM kv_store WHERE key LIKE 'memory:%' ORDER BY key",
-        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
-
-        let entries: Vec<(String, String)> = stmt
-            .query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })
-            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        drop(stmt);
-        drop(reader);
-
-        // Clear existing index
-        self.writer
-            .with_conn(|conn| {
-                conn.execute("DELETE FROM memory_fts", [])?;
-                Ok(())
-            })
-            .await
-            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
-
-        // Re-index each entry
-        let mut indexed = 0;
-        for (key, value) in &entries {
-            let provider = extract_provider(key);
-            let content = extract_searchable_content(value);
-            if !content.is_empty() {
-                self.index_entry(key, &provider, &content).await?;
-                indexed += 1;
+    /// Bring `memory_metadata` and the FTS5 index back in line with the
+    /// memory KV entries, a batch at a time so other writes can interleave.
+    /// Returns what was repaired.
+    pub async fn repair_index(&self) -> crate::error::Result<MemoryIndexReport> {
+        let mut report = MemoryIndexReport::default();
+        loop {
+            let step = self
+                .writer
+                .with_conn(|conn| memory_index::repair_step(conn, REPAIR_BATCH))
+                .await
+                .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+            if step.total() == 0 {
+                return Ok(report);
             }
+            report.merge(&step);
+            tokio::task::yield_now().await;
         }
-
-        Ok(indexed)
     }
 }
 
@@ -228,71 +216,6 @@ fn sanitize_fts_query(query: &str) -> String {
         .join(" ")
 }
 
-/// Extract the provider name from a KV key like "memory:playbook:str-00001".
-#[allow(dead_code)]
-fn extract_provider(key: &str) -> String {
-    if let Some(rest) = key.strip_prefix("memory:") {
-        if let Some(idx) = rest.find(':') {
-            return rest[..idx].to_string();
-        }
-    }
-    "unknown".to_string()
-}
-
-/// Extract searchable text content from a JSON value string.
-#[allow(dead_code)]
-fn extract_searchable_content(json_value: &str) -> String {
-    if let Ok(v) = serde_json::from_str::<serde_json::Value>(json_value) {
-        let mut parts = Vec::new();
-
-        // Extract common fields
-        if let Some(content) = v.get("content").and_then(|v| v.as_str()) {
-            parts.push(content.to_string());
-        }
-        if let Some(summary) = v.get("summary").and_then(|v| v.as_str()) {
-            parts.push(summary.to_string());
-        }
-        if let Some(outcome) = v.get("outcome").and_then(|v| v.as_str()) {
-            parts.push(outcome.to_string());
-        }
-        if let Some(category) = v.get("category").and_then(|v| v.as_str()) {
-            parts.push(category.to_string());
-        }
-
-        // Tool patterns: extract pattern text
-        if let Some(patterns) = v.get("patterns").and_then(|v| v.as_array()) {
-            for p in patterns {
-                if let Some(text) = p.get("pattern").and_then(|v| v.as_str()) {
-                    parts.push(text.to_string());
-                }
-            }
-        }
-
-        // Key decisions
-        if let Some(decisions) = v.get("key_decisions").and_then(|v| v.as_array()) {
-            for d in decisions {
-                if let Some(text) = d.as_str() {
-                    parts.push(text.to_string());
-                }
-            }
-        }
-
-        // Common errors
-        if let Some(errors) = v.get("common_errors").and_then(|v| v.as_array()) {
-            for e in errors {
-                if let Some(text) = e.get("error").and_then(|v| v.as_str()) {
-                    parts.push(text.to_string());
-                }
-            }
-        }
-
-        parts.join(" ")
-    } else {
-        // Not JSON, use raw text
-        json_value.to_string()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,38 +238,6 @@ mod tests {
         assert!(result.is_empty());
     }
 
-    #[test]
-    fn extract_provider_playbook() {
-        assert_eq!(extract_provider("memory:playbook:str-00001"), "playbook");
-    }
-
-    #[test]
-    fn extract_provider_episode() {
-        assert_eq!(extract_provider("memory:episode:abc-123"), "episode");
-    }
-
-    #[test]
-    fn extract_provider_tool() {
-        assert_eq!(extract_provider("memory:tool_pattern:bash"), "tool_pattern");
-    }
-
-    #[test]
-    fn extract_content_from_playbook_json() {
-        let json = r#"{"content":"Always check file exists","category":"strategy","helpful":5}"#;
-        let content = extract_searchable_content(json);
-        assert!(content.contains("Always check file exists"));
-        assert!(content.contains("strategy"));
-    }
-
-    #[test]
-    fn extract_content_from_episode_json() {
-        let json = r#"{"summary":"Built a REST API","outcome":"success","key_decisions":["chose tower"]}"#;
-        let content = extract_searchable_content(json);
-        assert!(content.contains("Built a REST API"));
-        assert!(content.contains("success"));
-        assert!(content.contains("chose tower"));
-    }
-
     #[test]
     fn combined_scoring() {
         let mut results = vec![
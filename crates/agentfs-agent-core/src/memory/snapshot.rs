@@ -0,0 +1,310 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use agentfs_core::error::AgentFSError;
+use agentfs_core::AgentFS;
+
+// ── Types ──────────────────────────────────────────────────────────
+
+/// A named point-in-time copy of memory, as listed by `memory snapshot list`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub created: String,
+    /// Memory KV entries captured.
+    pub entry_count: i64,
+}
+
+/// Everything memory keeps in the database: `memory:*` KV entries, their
+/// tier metadata and their search index rows.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MemoryState {
+    kv: Vec<KvRow>,
+    metadata: Vec<MetadataRow>,
+    fts: Vec<FtsRow>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct KvRow {
+    key: String,
+    value: String,
+    created: String,
+    updated: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MetadataRow {
+    key: String,
+    provider: String,
+    tier: String,
+    access_count: i64,
+    last_accessed: String,
+    content_hash: Option<String>,
+    byte_size: i64,
+    created: String,
+    successes: i64,
+    failures: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FtsRow {
+    key: String,
+    provider: String,
+    content: String,
+}
+
+// ── Operations ─────────────────────────────────────────────────────
+
+/// Capture the current memory state as snapshot `name`.
+pub async fn create(db: &AgentFS, name: &str) -> crate::error::Result<SnapshotInfo> {
+    let name = name.to_string();
+    db.writer()
+        .with_conn(move |conn| create_snapshot(conn, &name))
+        .await
+        .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+}
+
+/// All snapshots, newest first.
+pub async fn list(db: &AgentFS) -> crate::error::Result<Vec<SnapshotInfo>> {
+    let reader = db.readers().acquire().await
+        .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+    let mut stmt = reader.conn().prepare(
+        "SELECT name, created, entry_count FROM memory_snapshots ORDER BY created DESC, name",
+    ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+    let snapshots = stmt
+        .query_map([], |row| {
+            Ok(SnapshotInfo {
+                name: row.get(0)?,
+                created: row.get(1)?,
+                entry_count: row.get(2)?,
+            })
+        })
+        .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(snapshots)
+}
+
+/// Replace the current memory state with snapshot `name`. Memory loaded
+/// by a running session is stale afterwards.
+pub async fn restore(db: &AgentFS, name: &str) -> crate::error::Result<SnapshotInfo> {
+    let name = name.to_string();
+    db.writer()
+        .with_conn(move |conn| restore_snapshot(conn, &name))
+        .await
+        .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+}
+
+fn create_snapshot(conn: &Connection, name: &str) -> agentfs_core::error::Result<SnapshotInfo> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM memory_snapshots WHERE name = ?1",
+        [name],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Err(AgentFSError::Other(format!("snapshot {name} already exists")));
+    }
+
+    let state = read_state(conn)?;
+    let data = serde_json::to_string(&state).map_err(|e| AgentFSError::Other(e.to_string()))?;
+    conn.execute(
+        "INSERT INTO memory_snapshots (name, entry_count, data) VALUES (?1, ?2, ?3)",
+        rusqlite::params![name, state.kv.len() as i64, data],
+    )?;
+
+    Ok(conn.query_row(
+        "SELECT name, created, entry_count FROM memory_snapshots WHERE name = ?1",
+        [name],
+        |row| {
+            Ok(SnapshotInfo {
+                name: row.get(0)?,
+                created: row.get(1)?,
+                entry_count: row.get(2)?,
+            })
+        },
+    )?)
+}
+
+fn restore_snapshot(conn: &Connection, name: &str) -> agentfs_core::error::Result<SnapshotInfo> {
+    let found: Option<(String, i64, String)> = conn
+        .query_row(
+            "SELECT created, entry_count, data FROM memory_snapshots WHERE name = ?1",
+            [name],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((created, entry_count, data)) = found else {
+        return Err(AgentFSError::Other(format!("no snapshot named {name}")));
+    };
+    let state: MemoryState =
+        serde_json::from_str(&data).map_err(|e| AgentFSError::Other(e.to_string()))?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM kv_store WHERE key LIKE 'memory:%'", [])?;
+    tx.execute("DELETE FROM memory_metadata", [])?;
+    tx.execute("DELETE FROM memory_fts", [])?;
+    for row in &state.kv {
+        tx.execute(
+            "INSERT INTO kv_store (key, value, created, updated) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![row.key, row.value, row.created, row.updated],
+        )?;
+    }
+    for row in &state.metadata {
+        tx.execute(
+            "INSERT INTO memory_metadata
+               (key, provider, tier, access_count, last_accessed, content_hash, byte_size, created, successes, failures)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                row.key,
+                row.provider,
+                row.tier,
+                row.access_count,
+                row.last_accessed,
+                row.content_hash,
+                row.byte_size,
+                row.created,
+                row.successes,
+                row.failures,
+            ],
+        )?;
+    }
+    for row in &state.fts {
+        tx.execute(
+            "INSERT INTO memory_fts (key, provider, content) VALUES (?1, ?2, ?3)",
+            rusqlite::params![row.key, row.provider, row.content],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(SnapshotInfo {
+        name: name.to_string(),
+        created,
+        entry_count,
+    })
+}
+
+fn read_state(conn: &Connection) -> agentfs_core::error::Result<MemoryState> {
+    let mut stmt = conn.prepare(
+        "SELECT key, value, created, updated FROM kv_store WHERE key LIKE 'memory:%' ORDER BY key",
+    )?;
+    let kv = stmt
+        .query_map([], |row| {
+            Ok(KvRow {
+                key: row.get(0)?,
+                value: row.get(1)?,
+                created: row.get(2)?,
+                updated: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT key, provider, tier, access_count, last_accessed, content_hash, byte_size, created,
+                successes, failures
+         FROM memory_metadata ORDER BY key",
+    )?;
+    let metadata = stmt
+        .query_map([], |row| {
+            Ok(MetadataRow {
+                key: row.get(0)?,
+                provider: row.get(1)?,
+                tier: row.get(2)?,
+                access_count: row.get(3)?,
+                last_accessed: row.get(4)?,
+                content_hash: row.get(5)?,
+                byte_size: row.get(6)?,
+                created: row.get(7)?,
+                successes: row.get(8)?,
+                failures: row.get(9)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare("SELECT key, provider, content FROM memory_fts ORDER BY key")?;
+    let fts = stmt
+        .query_map([], |row| {
+            Ok(FtsRow {
+                key: row.get(0)?,
+                provider: row.get(1)?,
+                content: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(MemoryState { kv, metadata, fts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::config::AgentFSConfig;
+
+    #[tokio::test]
+    async fn create_list_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        db.kv.set("memory:playbook:str-00001", r#"{"content":"Run the linter"}"#).await.unwrap();
+        db.kv.set("session:notes", "not memory").await.unwrap();
+        db.writer()
+            .with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO memory_metadata (key, provider, tier, successes)
+                     VALUES ('memory:playbook:str-00001', 'playbook', 'hot', 3)",
+                    [],
+                )?;
+                conn.execute(
+                    "INSERT INTO memory_fts (key, provider, content)
+                     VALUES ('memory:playbook:str-00001', 'playbook', 'Run the linter')",
+                    [],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let info = create(&db, "before").await.unwrap();
+        assert_eq!(info.entry_count, 1);
+        assert!(create(&db, "before").await.is_err());
+
+        db.kv.delete("memory:playbook:str-00001").await.unwrap();
+        db.kv.set("memory:playbook:str-00002", r#"{"content":"Skip tests"}"#).await.unwrap();
+        db.kv.set("session:notes", "changed").await.unwrap();
+        db.writer()
+            .with_conn(|conn| {
+                conn.execute("DELETE FROM memory_metadata", [])?;
+                conn.execute("DELETE FROM memory_fts", [])?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(list(&db).await.unwrap().len(), 1);
+        restore(&db, "before").await.unwrap();
+        assert!(restore(&db, "missing").await.is_err());
+
+        assert!(db.kv.get("memory:playbook:str-00001").await.is_ok());
+        assert!(db.kv.get("memory:playbook:str-00002").await.is_err());
+        assert_eq!(db.kv.get("session:notes").await.unwrap().value, "changed");
+        let (tier, successes, indexed): (String, i64, i64) = db
+            .writer()
+            .with_conn(|conn| {
+                Ok(conn.query_row(
+                    "SELECT tier, successes, (SELECT COUNT(*) FROM memory_fts WHERE memory_fts MATCH 'linter')
+                     FROM memory_metadata",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )?)
+            })
+            .await
+            .unwrap();
+        assert_eq!((tier.as_str(), successes, indexed), ("hot", 3, 1));
+    }
+}
@@ -0,0 +1,746 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use agentfs_core::connection::pool::{ReaderPool, WriterHandle};
+
+// ── Types ──────────────────────────────────────────────────────────
+
+/// Memory tier — determines eviction priority and prompt inclusion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryTier {
+    Hot,
+    Warm,
+    Cold,
+}
+
+impl MemoryTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hot => "hot",
+            Self::Warm => "warm",
+            Self::Cold => "cold",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "hot" => Self::Hot,
+            "cold" => Self::Cold,
+            _ => Self::Warm,
+        }
+    }
+}
+
+impl std::fmt::Display for MemoryTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Memory pressure level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryPressure {
+    Low,
+    Medium,
+    High,
+}
+
+/// Configuration for the tier system.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TierConfig {
+    /// Max entries in the hot tier.
+    #[serde(default = "default_30")]
+    pub hot_budget: usize,
+    /// Max total entries across all tiers.
+    #[serde(default = "default_200")]
+    pub total_budget: usize,
+    /// Half-life for time decay in days.
+    #[serde(default = "default_14f64")]
+    pub half_life_days: f64,
+    /// Score threshold below which entries move to cold tier.
+    #[serde(default = "default_cold_threshold")]
+    pub cold_threshold: f64,
+    /// Score weight of how recently an entry was accessed.
+    #[serde(default = "default_recency_weight")]
+    pub recency_weight: f64,
+    /// Score weight of how often an entry was accessed (log-scaled).
+    #[serde(default = "default_frequency_weight")]
+    pub frequency_weight: f64,
+    /// Score weight of how the turns an entry was recalled into went
+    /// (see [`TierManager::usefulness`]).
+    #[serde(default = "default_outcome_weight")]
+    pub outcome_weight: f64,
+    /// Max hot entries per provider (e.g. `{"episodes": 5}`), within
+    /// `hot_budget`. Providers not listed are only bound by `hot_budget`.
+    #[serde(default)]
+    pub provider_budgets: HashMap<String, usize>,
+    /// Days without an update after which a decay pass down-weights an
+    /// entry's helpful count.
+    #[serde(default = "default_stale_after_days")]
+    pub stale_after_days: f64,
+}
+
+impl Default for TierConfig {
+    fn default() -> Self {
+        Self {
+            hot_budget: 30,
+            total_budget: 200,
+            half_life_days: 14.0,
+            cold_threshold: 0.1,
+            recency_weight: 0.3,
+            frequency_weight: 0.2,
+            outcome_weight: 1.0,
+            provider_budgets: HashMap::new(),
+            stale_after_days: 30.0,
+        }
+    }
+}
+
+impl TierConfig {
+    /// Helpful count after decay for an entry last updated `days_idle`
+    /// days ago: halved every `half_life_days` once the entry is stale,
+    /// unchanged before that.
+    pub fn decayed_helpful(&self, helpful: i32, days_idle: f64) -> i32 {
+        if helpful <= 0 || days_idle < self.stale_after_days {
+            return helpful;
+        }
+        (helpful as f64 * 0.5_f64.powf(days_idle / self.half_life_days)).round() as i32
+    }
+}
+
+fn default_30() -> usize { 30 }
+fn default_200() -> usize { 200 }
+fn default_14f64() -> f64 { 14.0 }
+fn default_cold_threshold() -> f64 { 0.1 }
+fn default_recency_weight() -> f64 { 0.3 }
+fn default_frequency_weight() -> f64 { 0.2 }
+fn default_outcome_weight() -> f64 { 1.0 }
+fn default_stale_after_days() -> f64 { 30.0 }
+
+/// Metadata row from memory_metadata table.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct MemoryMeta {
+    pub key: String,
+    pub provider: String,
+    pub tier: MemoryTier,
+    pub access_count: i64,
+    pub last_accessed: String,
+    pub content_hash: Option<String>,
+    pub byte_size: i64,
+    pub created: String,
+}
+
+/// What the tier system knows about one entry, for browsing memory.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryStats {
+    pub key: String,
+    pub provider: String,
+    pub tier: MemoryTier,
+    pub access_count: i64,
+    pub last_accessed: String,
+    pub created: String,
+    /// Turns the entry was recalled into that went well.
+    pub successes: i64,
+    /// Turns the entry was recalled into that went badly.
+    pub failures: i64,
+    /// [`TierManager::usefulness`] of the outcomes above.
+    pub usefulness: f64,
+}
+
+/// A scored entry used during rebalancing.
+#[derive(Debug, Clone)]
+pub struct ScoredEntry {
+    pub key: String,
+    pub provider: String,
+    pub score: f64,
+    pub tier: MemoryTier,
+}
+
+// ── TierManager ────────────────────────────────────────────────────
+
+/// Manages tiered memory storage with time-decay scoring.
+pub struct TierManager {
+    writer: Arc<WriterHandle>,
+    readers: Arc<ReaderPool>,
+    config: TierConfig,
+    /// Keys recalled since the last [`TierManager::take_recalled`].
+    recalled: Mutex<HashSet<String>>,
+}
+
+impl TierManager {
+    pub fn new(
+        writer: Arc<WriterHandle>,
+        readers: Arc<ReaderPool>,
+        config: TierConfig,
+    ) -> Self {
+        Self {
+            writer,
+            readers,
+            config,
+            recalled: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Compute the composite score for a memory entry.
+    ///
+    /// score = (helpful - harmful) × 0.5^(age_days / half_life)
+    ///       + recency_weight × 0.5^(days_since_access / half_life)
+    ///       + frequency_weight × ln(1 + access_count)
+    ///
+    /// The half-life and weights come from [`TierConfig`]. Rebalancing adds
+    /// `outcome_weight × usefulness` on top.
+    pub fn compute_score(
+        &self,
+        helpful: i32,
+        harmful: i32,
+        age_days: f64,
+        days_since_access: f64,
+        access_count: i64,
+    ) -> f64 {
+        let hl = self.config.half_life_days;
+        let base_score = (helpful - harmful) as f64;
+
+        let time_decay = 0.5_f64.powf(age_days / hl);
+        let relevance = base_score * time_decay;
+
+        let recency = self.config.recency_weight * 0.5_f64.powf(days_since_access / hl);
+
+        let frequency = self.config.frequency_weight * (1.0 + access_count as f64).ln();
+
+        relevance + recency + frequency
+    }
+
+    /// Ensure a metadata row exists for a memory entry.
+    pub async fn ensure_metadata(
+        &self,
+        key: &str,
+        provider: &str,
+        content_hash: Option<&str>,
+        byte_size: i64,
+    ) -> crate::error::Result<()> {
+        let key = key.to_string();
+        let provider = provider.to_string();
+        let content_hash = content_hash.map(|s| s.to_string());
+        self.writer
+            .with_conn(move |conn| {
+                conn.execute(
+                    "INSERT INTO memory_metadata (key, provider, content_hash, byte_size)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(key) DO UPDATE SET
+                       content_hash = excluded.content_hash,
+                       byte_size = excluded.byte_size",
+                    rusqlite::params![key, provider, content_hash, byte_size],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+    }
+
+    /// How the turns an entry was recalled into went, in (-1, 1): 0 with no
+    /// outcomes yet, approaching ±1 as consistent outcomes pile up.
+    pub fn usefulness(successes: i64, failures: i64) -> f64 {
+        (successes - failures) as f64 / (successes + failures + 2) as f64
+    }
+
+    /// Record an access to a memory entry — bumps access_count and last_accessed.
+    pub async fn record_access(&self, key: &str) -> crate::error::Result<()> {
+        self.recalled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_string());
+        let key = key.to_string();
+        self.writer
+            .with_conn(move |conn| {
+                conn.execute(
+                    "UPDATE memory_metadata
+                     SET access_count = access_count + 1,
+                         last_accessed = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+                     WHERE key = ?1",
+                    [&key],
+                )?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+    }
+
+    /// Keys recalled into prompt context since the last call.
+    pub fn take_recalled(&self) -> Vec<String> {
+        let mut recalled = self.recalled.lock().unwrap_or_else(|e| e.into_inner());
+        let mut keys: Vec<String> = recalled.drain().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Count a turn outcome against each recalled entry.
+    pub async fn record_outcomes(
+        &self,
+        succeeded: &[String],
+        failed: &[String],
+    ) -> crate::error::Result<()> {
+        let succeeded = succeeded.to_vec();
+        let failed = failed.to_vec();
+        self.writer
+            .with_conn(move |conn| {
+                for key in &succeeded {
+                    conn.execute(
+                        "UPDATE memory_metadata SET successes = successes + 1 WHERE key = ?1",
+                        [key],
+                    )?;
+                }
+                for key in &failed {
+                    conn.execute(
+                        "UPDATE memory_metadata SET failures = failures + 1 WHERE key = ?1",
+                        [key],
+                    )?;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+    }
+
+    /// Usefulness of every entry with outcomes, mapped to [0, 1] for
+    /// [`super::search::MemorySearchEngine::apply_combined_scoring`].
+    pub async fn usefulness_scores(&self) -> crate::error::Result<HashMap<String, f64>> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+        let mut stmt = reader.conn().prepare(
+            "SELECT key, successes, failures FROM memory_metadata
+             WHERE successes > 0 OR failures > 0",
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+        let scores = stmt
+            .query_map([], |row| {
+                let key: String = row.get(0)?;
+                let usefulness = Self::usefulness(row.get(1)?, row.get(2)?);
+                Ok((key, (usefulness + 1.0) / 2.0))
+            })
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(scores)
+    }
+
+    /// Tier, access and outcome stats of every entry with metadata.
+    pub async fn entry_stats(&self) -> crate::error::Result<Vec<EntryStats>> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+        let mut stmt = reader.conn().prepare(
+            "SELECT key, provider, tier, access_count, last_accessed, created, successes, failures
+             FROM memory_metadata ORDER BY key",
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+        let stats = stmt
+            .query_map([], |row| {
+                let tier: String = row.get(2)?;
+                let successes: i64 = row.get(6)?;
+                let failures: i64 = row.get(7)?;
+                Ok(EntryStats {
+                    key: row.get(0)?,
+                    provider: row.get(1)?,
+                    tier: MemoryTier::from_str(&tier),
+                    access_count: row.get(3)?,
+                    last_accessed: row.get(4)?,
+                    created: row.get(5)?,
+                    successes,
+                    failures,
+                    usefulness: Self::usefulness(successes, failures),
+                })
+            })
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(stats)
+    }
+
+    /// Remove metadata for a key.
+    pub async fn remove_metadata(&self, key: &str) -> crate::error::Result<()> {
+        let key = key.to_string();
+        self.writer
+            .with_conn(move |conn| {
+                conn.execute("DELETE FROM memory_metadata WHERE key = ?1", [&key])?;
+                Ok(())
+            })
+            .await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+    }
+
+    /// Rebalance tiers based on scores.
+    /// Returns the number of entries that changed tiers.
+    pub async fn rebalance(&self) -> crate::error::Result<usize> {
+        let now = chrono::Utc::now();
+        // Scoped so the reader and statement are gone before the awaits below
+        let mut scored = {
+            let reader = self.readers.acquire().await
+                .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+            let mut stmt = reader.conn().prepare(
+                "SELECT m.key, m.provider, m.tier, m.access_count, m.last_accessed, m.created,
+                        COALESCE(
+                            (SELECT CAST(json_extract(kv.value, '$.helpful') AS INTEGER) FROM kv_store kv WHERE kv.key = 'memory:playbook:' || SUBSTR(m.key, LENGTH('memory:playbook:') + 1)),
+                            0
+                        ) as helpful,
+                        COALESCE(
+                            (SELECT CAST(json_extract(kv.value, '$.harmful') AS INTEGER) FROM kv_store kv WHERE kv.key = 'memory:playbook:' || SUBSTR(m.key, LENGTH('memory:playbook:') + 1)),
+                            0
+                        ) as harmful,
+                        m.successes, m.failures
+                 FROM memory_metadata m
+                 ORDER BY m.key",
+            ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+            let mut scored: Vec<ScoredEntry> = Vec::new();
+
+            let rows = stmt.query_map([], |row| {
+                let key: String = row.get(0)?;
+                let provider: String = row.get(1)?;
+                let tier_str: String = row.get(2)?;
+                let access_count: i64 = row.get(3)?;
+                let last_accessed: String = row.get(4)?;
+                let created: String = row.get(5)?;
+                let helpful: i32 = row.get(6)?;
+                let harmful: i32 = row.get(7)?;
+                let outcomes: (i64, i64) = (row.get(8)?, row.get(9)?);
+                Ok((key, provider, tier_str, access_count, last_accessed, created, helpful, harmful, outcomes))
+            }).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+            for row in rows {
+                let (key, provider, _tier_str, access_count, last_accessed, created, helpful, harmful, outcomes) =
+                    row.map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+                let age_days = parse_age_days(&created, &now);
+                let access_days = parse_age_days(&last_accessed, &now);
+                let score = self.compute_score(helpful, harmful, age_days, access_days, access_count)
+                    + self.config.outcome_weight * Self::usefulness(outcomes.0, outcomes.1);
+
+                scored.push(ScoredEntry {
+                    key,
+                    provider,
+                    score,
+                    tier: MemoryTier::Warm, // Will be reassigned below
+                });
+            }
+
+            scored
+        };
+
+        // Sort by score descending
+        scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+        // Assign tiers: top hot_budget (within each provider's budget) → Hot,
+        // rest above cold_threshold → Warm, below → Cold
+        let mut changed = 0usize;
+        let mut hot = 0usize;
+        let mut hot_per_provider: HashMap<String, usize> = HashMap::new();
+        for entry in scored.iter_mut() {
+            let provider_hot = hot_per_provider.entry(entry.provider.clone()).or_default();
+            let provider_budget = self
+                .config
+                .provider_budgets
+                .get(&entry.provider)
+                .copied()
+                .unwrap_or(usize::MAX);
+            let new_tier = if hot < self.config.hot_budget && *provider_hot < provider_budget {
+                hot += 1;
+                *provider_hot += 1;
+                MemoryTier::Hot
+            } else if entry.score >= self.config.cold_threshold {
+                MemoryTier::Warm
+            } else {
+                MemoryTier::Cold
+            };
+            entry.tier = new_tier;
+        }
+
+        // Batch update tiers
+        for entry in &scored {
+            let key = entry.key.clone();
+            let tier = entry.tier.as_str().to_string();
+            let did_change = self.writer
+                .with_conn(move |conn| {
+                    let updated = conn.execute(
+                        "UPDATE memory_metadata SET tier = ?1 WHERE key = ?2 AND tier != ?1",
+                        rusqlite::params![tier, key],
+                    )?;
+                    Ok(updated)
+                })
+                .await
+                .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+            changed += did_change;
+        }
+
+        Ok(changed)
+    }
+
+    /// Get the current memory pressure level.
+    pub async fn memory_pressure(&self) -> crate::error::Result<MemoryPressure> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let total: i64 = reader.conn().query_row(
+            "SELECT COUNT(*) FROM memory_metadata",
+            [],
+            |r| r.get(0),
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let total = total as usize;
+        let budget = self.config.total_budget;
+
+        Ok(if total >= budget {
+            MemoryPressure::High
+        } else if total >= budget * 3 / 4 {
+            MemoryPressure::Medium
+        } else {
+            MemoryPressure::Low
+        })
+    }
+
+    /// Get tier distribution counts.
+    pub async fn tier_counts(&self) -> crate::error::Result<(usize, usize, usize)> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let hot: i64 = reader.conn().query_row(
+            "SELECT COUNT(*) FROM memory_metadata WHERE tier = 'hot'",
+            [],
+            |r| r.get(0),
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let warm: i64 = reader.conn().query_row(
+            "SELECT COUNT(*) FROM memory_metadata WHERE tier = 'warm'",
+            [],
+            |r| r.get(0),
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let cold: i64 = reader.conn().query_row(
+            "SELECT COUNT(*) FROM memory_metadata WHERE tier = 'cold'",
+            [],
+            |r| r.get(0),
+        ).map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        Ok((hot as usize, warm as usize, cold as usize))
+    }
+
+    /// Check if a content hash already exists.
+    pub async fn has_content_hash(&self, hash: &str) -> crate::error::Result<Option<String>> {
+        let reader = self.readers.acquire().await
+            .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+
+        let hash = hash.to_string();
+        let result: Option<String> = reader.conn().query_row(
+            "SELECT key FROM memory_metadata WHERE content_hash = ?1 LIMIT 1",
+            [&hash],
+            |r| r.get(0),
+        ).ok();
+
+        Ok(result)
+    }
+
+    /// Get the tier config.
+    #[allow(dead_code)]
+    pub fn config(&self) -> &TierConfig {
+        &self.config
+    }
+}
+
+/// Parse a datetime string into days-ago from `now`.
+pub(super) fn parse_age_days(datetime_str: &str, now: &chrono::DateTime<chrono::Utc>) -> f64 {
+    chrono::DateTime::parse_from_rfc3339(datetime_str)
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%dT%H:%M:%S%.f")
+            .map(|naive| naive.and_utc().fixed_offset()))
+        .map(|dt| {
+            let duration = *now - dt.with_timezone(&chrono::Utc);
+            duration.num_seconds() as f64 / 86400.0
+        })
+        .unwrap_or(0.0)
+        .max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tier_display() {
+        assert_eq!(MemoryTier::Hot.as_str(), "hot");
+        assert_eq!(MemoryTier::Warm.as_str(), "warm");
+        assert_eq!(MemoryTier::Cold.as_str(), "cold");
+    }
+
+    #[test]
+    fn tier_from_str() {
+        assert_eq!(MemoryTier::from_str("hot"), MemoryTier::Hot);
+        assert_eq!(MemoryTier::from_str("warm"), MemoryTier::Warm);
+        assert_eq!(MemoryTier::from_str("cold"), MemoryTier::Cold);
+        assert_eq!(MemoryTier::from_str("unknown"), MemoryTier::Warm);
+    }
+
+    #[test]
+    fn default_config() {
+        let cfg = TierConfig::default();
+        assert_eq!(cfg.hot_budget, 30);
+        assert_eq!(cfg.total_budget, 200);
+        assert_eq!(cfg.half_life_days, 14.0);
+        assert_eq!(cfg.cold_threshold, 0.1);
+        assert_eq!(cfg.recency_weight, 0.3);
+        assert_eq!(cfg.frequency_weight, 0.2);
+        assert_eq!(cfg.outcome_weight, 1.0);
+        assert!(cfg.provider_budgets.is_empty());
+    }
+
+    #[test]
+    fn decayed_helpful() {
+        let cfg = TierConfig::default();
+        // Not stale yet
+        assert_eq!(cfg.decayed_helpful(8, 10.0), 8);
+        // Two half-lives idle
+        assert_eq!(cfg.decayed_helpful(8, 28.0), 8);
+        assert_eq!(cfg.decayed_helpful(8, 42.0), 1);
+        assert_eq!(cfg.decayed_helpful(0, 90.0), 0);
+    }
+
+    #[tokio::test]
+    async fn rebalance_respects_provider_budgets() {
+        use agentfs_core::config::AgentFSConfig;
+        use agentfs_core::AgentFS;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        let config = TierConfig {
+            provider_budgets: HashMap::from([("episodes".to_string(), 1)]),
+            ..TierConfig::default()
+        };
+        let mgr = TierManager::new(db.writer().clone(), db.readers().clone(), config);
+        for i in 0..3 {
+            mgr.ensure_metadata(&format!("memory:episode:{i}"), "episodes", None, 10)
+                .await
+                .unwrap();
+        }
+        mgr.ensure_metadata("memory:tool_pattern:bash", "tool_patterns", None, 10)
+            .await
+            .unwrap();
+
+        mgr.rebalance().await.unwrap();
+        let (hot, warm, _cold) = mgr.tier_counts().await.unwrap();
+        assert_eq!(hot, 2);
+        assert_eq!(warm, 2);
+    }
+
+    #[test]
+    fn usefulness() {
+        assert_eq!(TierManager::usefulness(0, 0), 0.0);
+        assert!(TierManager::usefulness(8, 0) > TierManager::usefulness(1, 0));
+        assert!(TierManager::usefulness(0, 3) < 0.0);
+        assert!(TierManager::usefulness(100, 0) < 1.0);
+    }
+
+    #[tokio::test]
+    async fn outcomes_move_entries_between_tiers() {
+        use agentfs_core::config::AgentFSConfig;
+        use agentfs_core::AgentFS;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        let config = TierConfig {
+            hot_budget: 1,
+            ..TierConfig::default()
+        };
+        let mgr = TierManager::new(db.writer().clone(), db.readers().clone(), config);
+        for key in ["memory:episode:a", "memory:episode:b"] {
+            mgr.ensure_metadata(key, "episodes", None, 10).await.unwrap();
+            mgr.record_access(key).await.unwrap();
+        }
+        assert_eq!(mgr.take_recalled(), vec!["memory:episode:a", "memory:episode:b"]);
+        assert!(mgr.take_recalled().is_empty());
+
+        // b keeps showing up in turns that fail, a in turns that succeed
+        for _ in 0..3 {
+            mgr.record_outcomes(&["memory:episode:a".into()], &["memory:episode:b".into()])
+                .await
+                .unwrap();
+        }
+        mgr.rebalance().await.unwrap();
+
+        let scores = mgr.usefulness_scores().await.unwrap();
+        assert!(scores["memory:episode:a"] > 0.5);
+        assert!(scores["memory:episode:b"] < 0.5);
+        let stats = mgr.entry_stats().await.unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!((stats[0].key.as_str(), stats[0].tier), ("memory:episode:a", MemoryTier::Hot));
+        assert_eq!((stats[0].successes, stats[0].access_count), (3, 1));
+        assert_eq!(stats[1].failures, 3);
+        assert!(stats[1].usefulness < 0.0);
+        let reader = db.readers().acquire().await.unwrap();
+        let hot: String = reader
+            .conn()
+            .query_row("SELECT key FROM memory_metadata WHERE tier = 'hot'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(hot, "memory:episode:a");
+    }
+
+    #[test]
+    fn score_computation() {
+        let writer = test_writer();
+        let readers = test_readers();
+        let mgr = TierManager::new(writer, readers, TierConfig::default());
+
+        // Fresh, highly-helpful entry
+        let score = mgr.compute_score(5, 0, 0.0, 0.0, 0);
+        assert!(score > 4.0, "Fresh helpful entry should score high: {score}");
+
+        // Old entry decays
+        let score_old = mgr.compute_score(5, 0, 28.0, 28.0, 0);
+        assert!(score_old < score, "Old entry should score lower: {score_old} vs {score}");
+
+        // Frequently accessed entry gets a boost
+        let score_freq = mgr.compute_score(1, 0, 14.0, 1.0, 20);
+        let score_nofreq = mgr.compute_score(1, 0, 14.0, 1.0, 0);
+        assert!(score_freq > score_nofreq, "Frequent access should boost score");
+
+        // Harmful entries score lower
+        let score_harmful = mgr.compute_score(2, 5, 0.0, 0.0, 0);
+        assert!(score_harmful < 0.0, "Harmful entry should be negative: {score_harmful}");
+    }
+
+    #[test]
+    fn parse_age_days_works() {
+        let now = chrono::Utc::now();
+        let yesterday = (now - chrono::Duration::days(1)).to_rfc3339();
+        let age = parse_age_days(&yesterday, &now);
+        assert!((age - 1.0).abs() < 0.1, "Should be ~1 day: {age}");
+    }
+
+    // Helpers — these don't actually connect to a DB, just satisfy the type system
+    // for unit tests that only test compute_score.
+    fn test_writer() -> Arc<WriterHandle> {
+        use agentfs_core::config::AgentFSConfig;
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let cfg = AgentFSConfig::builder(tmp.path()).build();
+        Arc::new(WriterHandle::open(&cfg).unwrap())
+    }
+
+    fn test_readers() -> Arc<ReaderPool> {
+        use agentfs_core::config::AgentFSConfig;
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let cfg = AgentFSConfig::builder(tmp.path()).build();
+        Arc::new(ReaderPool::open(&cfg).unwrap())
+    }
+}
@@ -0,0 +1,137 @@
+use serde::Serialize;
+
+use agentfs_core::AgentFS;
+
+use super::episodes::Episode;
+
+// ── Types ──────────────────────────────────────────────────────────
+
+/// One episode on the timeline, with what the database remembers about
+/// its session.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEntry {
+    pub session_id: String,
+    pub created: String,
+    pub summary: String,
+    pub outcome: String,
+    pub key_decisions: Vec<String>,
+    pub tools_used: Vec<String>,
+    /// Sessions a summary episode stands in for (empty otherwise).
+    pub summarizes: Vec<String>,
+    pub agent_name: Option<String>,
+    /// Session status, when the session is still recorded.
+    pub status: Option<String>,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    /// Files the session's tool calls touched, in first-touched order.
+    pub files: Vec<String>,
+}
+
+// ── Building ───────────────────────────────────────────────────────
+
+/// The `limit` most recent episodes, oldest first.
+pub async fn build(db: &AgentFS, limit: usize) -> crate::error::Result<Vec<TimelineEntry>> {
+    let entries = db.kv.list_prefix("memory:episode:").await
+        .map_err(|e| crate::error::AgentError::Memory(e.to_string()))?;
+    let mut episodes: Vec<Episode> = entries
+        .iter()
+        .filter_map(|kv| serde_json::from_str(&kv.value).ok())
+        .collect();
+    episodes.sort_by(|a, b| a.created.cmp(&b.created));
+    let skip = episodes.len().saturating_sub(limit);
+
+    let mut timeline = Vec::new();
+    for episode in episodes.into_iter().skip(skip) {
+        let session = db.sessions.get(&episode.session_id).await.ok();
+
+        let mut files = Vec::new();
+        let sessions = std::iter::once(&episode.session_id).chain(episode.summarizes.iter());
+        for session_id in sessions {
+            let calls = db.tools.by_session(session_id).await.unwrap_or_default();
+            for call in &calls {
+                if let Some(path) = call.input.as_deref().and_then(input_path) {
+                    if !files.contains(&path) {
+                        files.push(path);
+                    }
+                }
+            }
+        }
+
+        timeline.push(TimelineEntry {
+            agent_name: session.as_ref().and_then(|s| s.agent_name.clone()),
+            status: session.as_ref().map(|s| s.status.clone()),
+            started_at: session.as_ref().map(|s| s.started_at.clone()),
+            ended_at: session.and_then(|s| s.ended_at),
+            session_id: episode.session_id,
+            created: episode.created,
+            summary: episode.summary,
+            outcome: episode.outcome,
+            key_decisions: episode.key_decisions,
+            tools_used: episode.tools_used,
+            summarizes: episode.summarizes,
+            files,
+        });
+    }
+
+    Ok(timeline)
+}
+
+/// The `path` argument of a recorded tool call's input, if it has one.
+fn input_path(input: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(input).ok()?;
+    value.get("path")?.as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::config::AgentFSConfig;
+
+    #[test]
+    fn path_from_input() {
+        assert_eq!(input_path(r#"{"path":"src/main.rs"}"#), Some("src/main.rs".into()));
+        assert_eq!(input_path(r#"{"command":"ls"}"#), None);
+        assert_eq!(input_path("not json"), None);
+    }
+
+    #[tokio::test]
+    async fn episodes_with_sessions_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        db.sessions.start("s1", Some("infinity"), None, None).await.unwrap();
+        db.tools.record(Some("s1"), "read_file", Some(r#"{"path":"Cargo.toml"}"#), Some("ok"), None).await.unwrap();
+        db.tools.record(Some("s1"), "edit_file", Some(r#"{"path":"src/lib.rs"}"#), Some("ok"), None).await.unwrap();
+        db.tools.record(Some("s1"), "read_file", Some(r#"{"path":"Cargo.toml"}"#), Some("ok"), None).await.unwrap();
+
+        for (id, created) in [("s1", "2026-01-02T00:00:00Z"), ("s0", "2026-01-01T00:00:00Z")] {
+            let episode = Episode {
+                session_id: id.into(),
+                summary: format!("{id} work"),
+                key_decisions: Vec::new(),
+                tools_used: vec!["read_file".into()],
+                tool_calls: Vec::new(),
+                outcome: "completed".into(),
+                created: created.into(),
+                summarizes: Vec::new(),
+            };
+            db.kv.set(&format!("memory:episode:{id}"), &serde_json::to_string(&episode).unwrap()).await.unwrap();
+        }
+
+        let timeline = build(&db, 10).await.unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].session_id, "s0");
+        assert!(timeline[0].status.is_none());
+        assert_eq!(timeline[1].agent_name.as_deref(), Some("infinity"));
+        assert_eq!(timeline[1].files, vec!["Cargo.toml", "src/lib.rs"]);
+
+        let latest = build(&db, 1).await.unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].session_id, "s1");
+    }
+}
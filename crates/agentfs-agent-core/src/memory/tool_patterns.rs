@@ -8,7 +8,7 @@ use tokio::sync::RwLock;
 use agentfs_core::AgentFS;
 
 use crate::error::Result;
-use crate::memory::{MemoryEntry, MemoryProvider, Reflection, ToolPatternsConfig};
+use crate::memory::{MemoryEntry, MemoryProvider, Provenance, Reflection, ToolPatternsConfig};
 
 use super::compaction::content_hash;
 use super::search::MemorySearchEngine;
@@ -21,6 +21,9 @@ const KV_PREFIX: &str = "memory:tool_pattern:";
 pub struct ToolPatternEntry {
     pub pattern: String,
     pub helpful: i32,
+    /// Where the pattern was first observed.
+    #[serde(default)]
+    pub source: Provenance,
 }
 
 /// A common error observed with a tool.
@@ -28,6 +31,9 @@ pub struct ToolPatternEntry {
 pub struct CommonError {
     pub error: String,
     pub frequency: i32,
+    /// Where the error was first observed.
+    #[serde(default)]
+    pub source: Provenance,
 }
 
 /// Per-tool learnings.
@@ -167,18 +173,18 @@ impl ToolPatternProvider {
 
                 // Top patterns by helpful score
                 let mut sorted_patterns: Vec<&ToolPatternEntry> = tp.patterns.iter().collect();
-                sorted_patterns.sort_by(|a, b| b.helpful.cmp(&a.helpful));
+                sorted_patterns.sort_by_key(|p| std::cmp::Reverse(p.helpful));
 
                 for p in sorted_patterns.iter().take(3) {
-                    tool_tips.push(format!("  tip: {}", p.pattern));
+                    tool_tips.push(format!("  tip: {} {}", p.pattern, p.source.citation()).trim_end().to_string());
                 }
 
                 // Top errors by frequency
                 let mut sorted_errors: Vec<&CommonError> = tp.common_errors.iter().collect();
-                sorted_errors.sort_by(|a, b| b.frequency.cmp(&a.frequency));
+                sorted_errors.sort_by_key(|e| std::cmp::Reverse(e.frequency));
 
                 for e in sorted_errors.iter().take(2) {
-                    tool_tips.push(format!("  watch: {}", e.error));
+                    tool_tips.push(format!("  watch: {} {}", e.error, e.source.citation()).trim_end().to_string());
                 }
 
                 if !tool_tips.is_empty() {
@@ -271,6 +277,10 @@ impl MemoryProvider for ToolPatternProvider {
                     tp.patterns.push(ToolPatternEntry {
                         pattern: pattern_text.clone(),
                         helpful: 1,
+                        source: Provenance::new(
+                            &reflection.session_id,
+                            reflection.tool_call_ids_for(&obs.tool),
+                        ),
                     });
                 }
             }
@@ -287,6 +297,10 @@ impl MemoryProvider for ToolPatternProvider {
                     tp.common_errors.push(CommonError {
                         error: error_text.clone(),
                         frequency: 1,
+                        source: Provenance::new(
+                            &reflection.session_id,
+                            reflection.tool_call_ids_for(&obs.tool),
+                        ),
                     });
                 }
             }
@@ -338,6 +352,69 @@ impl MemoryProvider for ToolPatternProvider {
         // Patterns are saved incrementally; nothing extra needed.
         Ok(())
     }
+
+    async fn entries(&self) -> Result<Vec<(String, String)>> {
+        let patterns = self.patterns.read().await;
+        let mut entries: Vec<(String, String)> = patterns
+            .values()
+            .map(|tp| {
+                (
+                    format!("tool-{}", tp.tool),
+                    format!("{} tips, {} common errors", tp.patterns.len(), tp.common_errors.len()),
+                )
+            })
+            .collect();
+        entries.sort();
+        Ok(entries)
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<String>> {
+        let patterns = self.patterns.read().await;
+        id.strip_prefix("tool-")
+            .and_then(|tool| patterns.get(tool))
+            .map(|tp| {
+                serde_json::to_string_pretty(tp)
+                    .map_err(|e| crate::error::AgentError::Memory(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn update(&self, id: &str, json: &str) -> Result<()> {
+        let mut edited: ToolPattern = serde_json::from_str(json)
+            .map_err(|e| crate::error::AgentError::Memory(format!("invalid tool pattern: {e}")))?;
+        let tool = id.strip_prefix("tool-").unwrap_or(id);
+        let mut patterns = self.patterns.write().await;
+        let pattern = patterns
+            .get_mut(tool)
+            .ok_or_else(|| crate::error::AgentError::Memory(format!("no memory entry {id}")))?;
+        // The tool name is the storage key; keep it whatever the edit says.
+        edited.tool = tool.to_string();
+        self.save_pattern(&edited).await?;
+        *pattern = edited;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let tool = id.strip_prefix("tool-").unwrap_or(id);
+        let mut patterns = self.patterns.write().await;
+        if !patterns.contains_key(tool) {
+            return Err(crate::error::AgentError::Memory(format!("no memory entry {id}")));
+        }
+        let key = format!("{KV_PREFIX}{tool}");
+        super::forget(&self.db.kv, self.tier_manager.as_deref(), self.search_engine.as_deref(), &key).await?;
+        patterns.remove(tool);
+        Ok(())
+    }
+
+    fn owns(&self, key: &str) -> bool {
+        key.starts_with(KV_PREFIX)
+    }
+
+    async fn evict(&self, key: &str) {
+        if let Some(tool) = key.strip_prefix(KV_PREFIX) {
+            self.patterns.write().await.remove(tool);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -365,15 +442,22 @@ mod tests {
                     ToolPatternEntry {
                         pattern: "Use timeout for long commands".into(),
                         helpful: 5,
+                        source: Provenance {
+                            session_id: "9f8e7d6c-0000".into(),
+                            recorded: "2026-02-19T12:00:00Z".into(),
+                            tool_calls: vec!["toolu_01".into()],
+                        },
                     },
                     ToolPatternEntry {
                         pattern: "Quote paths with spaces".into(),
                         helpful: 3,
+                        source: Provenance::default(),
                     },
                 ],
                 common_errors: vec![CommonError {
                     error: "Command not found".into(),
                     frequency: 2,
+                    source: Provenance::default(),
                 }],
             },
         );
@@ -384,7 +468,8 @@ mod tests {
         let result = ToolPatternProvider::format_for_prompt(&patterns, &session_tools, 500);
         assert!(result.contains("<tool_tips>"));
         assert!(result.contains("bash:"));
-        assert!(result.contains("Use timeout"));
+        assert!(result.contains("  tip: Use timeout for long commands [9f8e7d6c, 2026-02-19]\n"));
+        assert!(result.contains("  tip: Quote paths with spaces\n"));
         assert!(result.contains("Command not found"));
     }
 }
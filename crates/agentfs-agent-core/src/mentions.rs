@@ -0,0 +1,234 @@
+//! `@path` mentions in user input.
+//!
+//! `@/src/main.rs` names a workspace (AgentFS) file, falling back to the
+//! host if the workspace has no such file; `@~/notes.txt`, `@./x` and
+//! `@../x` name host files. Mentioned files are appended to the message
+//! in fenced blocks so the model doesn't need a tool call to read them.
+
+use std::path::Path;
+
+use agentfs_core::AgentFS;
+
+use crate::attachments::host_path;
+
+/// Largest file included whole; longer files are cut at this size.
+pub const MAX_FILE_BYTES: usize = 100 * 1024;
+
+/// Total size of all included files in one message.
+pub const MAX_TOTAL_BYTES: usize = 256 * 1024;
+
+/// Where a mentioned file was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Workspace,
+    Host,
+}
+
+/// A mention found in the input, and what became of it.
+#[derive(Debug)]
+pub struct Mention {
+    pub path: String,
+    /// `None` if the file couldn't be included.
+    pub source: Option<Source>,
+    /// Bytes included.
+    pub bytes: usize,
+    pub truncated: bool,
+    /// Why the file wasn't included.
+    pub error: Option<String>,
+}
+
+/// Expand the `@path` mentions in `input`. Returns the message to send
+/// (the input followed by the files' contents) and the mentions found.
+/// Input without mentions comes back unchanged.
+pub async fn expand(db: &AgentFS, input: &str) -> (String, Vec<Mention>) {
+    expand_from(db, input, &|_| true).await
+}
+
+/// Like [`expand`], but only workspace files are read: for input from
+/// clients that shouldn't see the host's files.
+pub async fn expand_workspace(db: &AgentFS, input: &str) -> (String, Vec<Mention>) {
+    expand_from(db, input, &|_| false).await
+}
+
+/// Like [`expand`], for `input` built from a template around text the user
+/// typed: only mentions in `typed` may read host files. A template from a
+/// project's `.infinity/commands` can't reach the host.
+pub async fn expand_typed(db: &AgentFS, input: &str, typed: &str) -> (String, Vec<Mention>) {
+    let typed = find(typed);
+    expand_from(db, input, &|path| typed.contains(&path)).await
+}
+
+async fn expand_from(db: &AgentFS, input: &str, host: &dyn Fn(&str) -> bool) -> (String, Vec<Mention>) {
+    let mut message = input.to_string();
+    let mut mentions = Vec::new();
+    let mut budget = MAX_TOTAL_BYTES;
+
+    for path in find(input) {
+        if mentions.iter().any(|m: &Mention| m.path == path) {
+            continue;
+        }
+        let mut mention = Mention {
+            path: path.to_string(),
+            source: None,
+            bytes: 0,
+            truncated: false,
+            error: None,
+        };
+        match read(db, path, host(path)).await {
+            Ok(_) if budget == 0 => mention.error = Some("message size limit reached".to_string()),
+            Ok((source, data)) if data.iter().take(8192).any(|&b| b == 0) => {
+                mention.source = Some(source);
+                mention.error = Some("binary file".to_string());
+            }
+            Ok((source, data)) => {
+                let cap = MAX_FILE_BYTES.min(budget);
+                let mut text = String::from_utf8_lossy(&data).into_owned();
+                if text.len() > cap {
+                    let mut cut = cap;
+                    while !text.is_char_boundary(cut) {
+                        cut -= 1;
+                    }
+                    text.truncate(cut);
+                    mention.truncated = true;
+                }
+                budget -= text.len();
+                mention.source = Some(source);
+                mention.bytes = text.len();
+                message.push_str(&render(path, source, &text, mention.truncated, data.len()));
+            }
+            Err(e) => mention.error = Some(e),
+        }
+        mentions.push(mention);
+    }
+    (message, mentions)
+}
+
+/// Paths mentioned in `input`: `@` at the start of a word, followed by a
+/// path starting with `/`, `~/`, `./` or `../`. Trailing punctuation is
+/// not part of the path.
+fn find(input: &str) -> Vec<&str> {
+    input
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|path| path.trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']))
+        .filter(|path| {
+            path.len() > 1 && ["/", "~/", "./", "../"].iter().any(|prefix| path.starts_with(prefix))
+        })
+        .collect()
+}
+
+/// Read a mentioned file from the workspace or, if `host`, the host.
+async fn read(db: &AgentFS, path: &str, host: bool) -> Result<(Source, Vec<u8>), String> {
+    if path.starts_with('/') {
+        if let Ok(stat) = db.fs.stat(path).await {
+            if stat.is_dir() {
+                return Err("is a directory".to_string());
+            }
+            return db
+                .fs
+                .read_file(path)
+                .await
+                .map(|data| (Source::Workspace, data))
+                .map_err(|e| e.to_string());
+        }
+    }
+    if !host {
+        return Err(if path.starts_with('/') {
+            "not found in the workspace".to_string()
+        } else {
+            "host files can't be mentioned here".to_string()
+        });
+    }
+    let host = host_path(path);
+    if host.is_dir() {
+        return Err("is a directory".to_string());
+    }
+    std::fs::read(&host).map(|data| (Source::Host, data)).map_err(|e| {
+        if path.starts_with('/') && e.kind() == std::io::ErrorKind::NotFound {
+            "not found in the workspace or on the host".to_string()
+        } else {
+            e.to_string()
+        }
+    })
+}
+
+fn render(path: &str, source: Source, text: &str, truncated: bool, size: usize) -> String {
+    let lang = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    let fence = fence(text);
+    let place = match source {
+        Source::Workspace => "workspace",
+        Source::Host => "host",
+    };
+    let mut out = format!("\n\n{path} ({place}):\n{fence}{lang}\n{text}");
+    if !text.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&fence);
+    if truncated {
+        out.push_str(&format!("\n(truncated: first {} of {size} bytes)", text.len()));
+    }
+    out
+}
+
+/// A code fence longer than any backtick run inside `text`.
+pub fn fence(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::config::AgentFSConfig;
+
+    #[test]
+    fn test_find() {
+        assert_eq!(
+            find("compare @/src/main.rs, @./notes.md and @~/x.txt. email me@example.com @foo @/"),
+            vec!["/src/main.rs", "./notes.md", "~/x.txt"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expand_workspace_and_host() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AgentFSConfig::builder(dir.path().join("test.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        let db = AgentFS::create(config).await.unwrap();
+        db.fs.write_file("/src/lib.rs", b"pub fn f() {}\n").await.unwrap();
+        let host = dir.path().join("log.txt");
+        std::fs::write(&host, "a ``` b").unwrap();
+
+        let input = format!("why? @/src/lib.rs @{} @/nope.rs", host.display());
+        let (message, mentions) = expand(&db, &input).await;
+        assert!(message.starts_with(&input));
+        assert!(message.contains("/src/lib.rs (workspace):\n```rs\npub fn f() {}\n```"));
+        assert!(message.contains("(host):\n````txt\na ``` b\n````"));
+        assert_eq!(mentions[0].source, Some(Source::Workspace));
+        assert_eq!(mentions[1].source, Some(Source::Host));
+        assert!(mentions[2].error.is_some());
+
+        let (message, mentions) = expand_workspace(&db, &input).await;
+        assert!(message.contains("/src/lib.rs (workspace)"));
+        assert!(!message.contains("(host)"));
+        assert_eq!(mentions[1].source, None);
+        assert!(mentions[1].error.is_some());
+
+        let mention = format!("@{}", host.display());
+        let template = format!("Summarize {mention}\n\n$ARGUMENTS");
+        let (message, mentions) = expand_typed(&db, &template.replace("$ARGUMENTS", ""), "").await;
+        assert!(!message.contains("(host)"));
+        assert!(mentions[0].error.is_some());
+        let (message, _) = expand_typed(&db, &template.replace("$ARGUMENTS", &mention), &mention).await;
+        assert!(message.contains("(host)"));
+        db.close().await.unwrap();
+    }
+}
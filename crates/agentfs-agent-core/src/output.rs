@@ -0,0 +1,87 @@
+//! Structured output for single-prompt runs (`chat -p --output-format`).
+//!
+//! `json` prints one result object when the run ends. `stream-json` prints
+//! newline-delimited events while the turn runs — text deltas, tool calls
+//! and tool results — and ends with the same result object.
+
+use std::io::Write;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::agent::TurnStats;
+
+/// Write one event to stdout as a line of JSON.
+pub fn emit(event: &Value) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{event}");
+    let _ = stdout.flush();
+}
+
+/// First `stream-json` event: what the run is working with.
+pub fn init_event(session_id: &str, provider: &str, model: &str, tools: &[&str]) -> Value {
+    json!({
+        "type": "system",
+        "subtype": "init",
+        "session_id": session_id,
+        "provider": provider,
+        "model": model,
+        "tools": tools,
+    })
+}
+
+/// Final event: the reply (or the error that ended the run) with usage and cost.
+pub fn result_event(
+    session_id: &str,
+    outcome: &std::result::Result<String, String>,
+    stats: TurnStats,
+    duration: Duration,
+) -> Value {
+    let (subtype, result, error) = match outcome {
+        Ok(text) => ("success", Some(text.as_str()), None),
+        Err(e) => ("error", None, Some(e.as_str())),
+    };
+    json!({
+        "type": "result",
+        "subtype": subtype,
+        "is_error": outcome.is_err(),
+        "result": result,
+        "error": error,
+        "session_id": session_id,
+        "num_steps": stats.steps,
+        "num_tool_calls": stats.tool_calls,
+        "duration_ms": duration.as_millis() as u64,
+        "usage": {
+            "input_tokens": stats.input_tokens,
+            "output_tokens": stats.output_tokens,
+        },
+        "total_cost_usd": stats.cost_microcents as f64 / 1e8,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_event() {
+        let stats = TurnStats {
+            steps: 2,
+            tool_calls: 1,
+            input_tokens: 1000,
+            output_tokens: 200,
+            cost_microcents: 600_000,
+        };
+        let ok = result_event("s1", &Ok("done".to_string()), stats, Duration::from_millis(1500));
+        assert_eq!(ok["subtype"], "success");
+        assert_eq!(ok["result"], "done");
+        assert!(ok["error"].is_null());
+        assert_eq!(ok["duration_ms"], 1500);
+        assert_eq!(ok["total_cost_usd"], 0.006);
+
+        let failed = result_event("s1", &Err("boom".to_string()), stats, Duration::ZERO);
+        assert_eq!(failed["is_error"], true);
+        assert_eq!(failed["error"], "boom");
+        assert!(failed["result"].is_null());
+    }
+}
@@ -0,0 +1,793 @@
+//! Tool permissions: whether a tool call runs, is refused, or needs the
+//! user's approval first.
+//!
+//! Configured by `~/.infinity/permissions.json`; both fields are optional:
+//!
+//! ```json
+//! { "tools": { "bash": "ask", "write_file": "allow", "github__*": "ask" },
+//!   "rules": [ { "tool": "bash", "pattern": "git status*", "policy": "allow" },
+//!              { "tool": "bash", "pattern": "rm -rf *", "policy": "deny" },
+//!              { "tool": "write_file", "pattern": "/secrets/*", "policy": "deny" } ] }
+//! ```
+//!
+//! Without a `tools` entry, `bash` and MCP tools ask and built-in tools are
+//! allowed. Rules match a tool name and, optionally, the call's subject: each
+//! command of a bash line (split on `;`, `&&`, `||`, `|`), the file path(s)
+//! of file tools, or the URL of `fetch_url`. In both, `*` matches anything
+//! (including `/` and spaces) and `?` one character. The first matching rule
+//! wins; a `deny` can't be overridden.
+//!
+//! Answering "always" at the prompt is remembered in the KV store: for bash
+//! the programs run (`cargo`, `git`), otherwise the whole tool. Remembered
+//! bash approvals never cover lines with `$(...)` or backticks. Runs without
+//! a terminal, like the dashboard chat, can ask elsewhere through an
+//! [`ApprovalRequest`] channel.
+//!
+//! A project's `.infinity/config.toml` can add policies of its own (see
+//! [`Permissions::with_project`]), but only to make calls stricter: a call
+//! gets the stricter of the user's policy and the project's.
+//!
+//! A run can be scoped further from the command line (see [`ToolScope`]):
+//! tools outside `--allowed-tools` or inside `--disallowed-tools` are
+//! hidden from the model and refused, and `--dangerously-skip-permissions`
+//! runs calls that would ask without asking.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+
+use agentfs_core::kvstore::KvStore;
+
+use crate::error::{AgentError, Result};
+
+/// KV prefix of remembered "always allow" answers. Under
+/// [`PERMISSION_PREFIX`](agentfs_core::kvstore::PERMISSION_PREFIX), so
+/// tools can't write them.
+pub const ALWAYS_KEY_PREFIX: &str = "permission:always:";
+
+/// What happens to a tool call. Ordered from least to most restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    Allow,
+    Ask,
+    Deny,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    /// Tool name glob.
+    pub tool: String,
+    /// Subject glob; `None` matches every call of the tool.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    pub policy: Policy,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PermissionConfig {
+    /// Policy per tool name glob, for calls no rule matches.
+    #[serde(default)]
+    pub tools: HashMap<String, Policy>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl PermissionConfig {
+    /// Policy for `tool` from the first rule matching `subject`, then the
+    /// `tools` table; `None` if neither says.
+    fn configured(&self, tool: &str, subject: Option<&str>) -> Option<Policy> {
+        if let Some(rule) = first_rule(&self.rules, tool, subject) {
+            return Some(rule.policy);
+        }
+        self.tools.get(tool).copied().or_else(|| {
+            self.tools
+                .iter()
+                .find(|(pattern, _)| glob_match(pattern, tool))
+                .map(|(_, policy)| *policy)
+        })
+    }
+}
+
+/// Load permission config from ~/.infinity/permissions.json (defaults if
+/// missing). An invalid file is an error, not the default policy.
+pub fn load_permission_config() -> Result<PermissionConfig> {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".infinity");
+    path.push("permissions.json");
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| AgentError::Config(format!("Invalid {}: {e}", path.display()))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PermissionConfig::default()),
+        Err(e) => Err(AgentError::Config(format!("Cannot read {}: {e}", path.display()))),
+    }
+}
+
+/// Limits on a run's tools from the command line, on top of the config.
+#[derive(Debug, Clone, Default)]
+pub struct ToolScope {
+    /// Tool name globs the run may use; empty allows all.
+    pub allowed: Vec<String>,
+    /// Tool name globs the run may not use.
+    pub disallowed: Vec<String>,
+    /// Allow calls whose policy is `ask`; `deny` still refuses.
+    pub skip_prompts: bool,
+}
+
+impl ToolScope {
+    /// Whether the run may use `tool` at all.
+    pub fn includes(&self, tool: &str) -> bool {
+        (self.allowed.is_empty() || self.allowed.iter().any(|g| glob_match(g, tool)))
+            && !self.disallowed.iter().any(|g| glob_match(g, tool))
+    }
+}
+
+/// Approvals remembered from earlier "always" answers.
+#[derive(Debug, Default)]
+struct Remembered {
+    tool: bool,
+    programs: HashSet<String>,
+}
+
+/// Outcome of evaluating a call against the config.
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    Allow,
+    Ask,
+    Deny(String),
+}
+
+/// The user's answer to an approval prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Once,
+    Always,
+    No,
+}
+
+/// A call waiting for approval from outside the terminal. Dropping `reply`
+/// declines it.
+#[derive(Debug)]
+pub struct ApprovalRequest {
+    pub tool: String,
+    /// The call, as the terminal prompt shows it.
+    pub summary: String,
+    /// What answering "always" approves, e.g. ``always allow `git` commands``.
+    pub always: String,
+    pub reply: oneshot::Sender<Answer>,
+}
+
+/// Decides, and when needed asks, whether tool calls may run.
+pub struct Permissions {
+    config: PermissionConfig,
+    /// The project's policies, which can only make calls stricter.
+    project: PermissionConfig,
+    /// Whether the user can be asked; otherwise `ask` means deny.
+    interactive: bool,
+    scope: ToolScope,
+    /// Where to ask instead of the terminal.
+    approver: Option<mpsc::UnboundedSender<ApprovalRequest>>,
+}
+
+impl Permissions {
+    pub fn new(config: PermissionConfig) -> Self {
+        Self {
+            config,
+            project: PermissionConfig::default(),
+            interactive: std::io::stdin().is_terminal(),
+            scope: ToolScope::default(),
+            approver: None,
+        }
+    }
+
+    /// Add a project's policies. Where they're looser than the user's (or
+    /// than the built-in defaults) they're ignored: an untrusted checkout
+    /// can deny or ask for more, never allow more.
+    pub fn with_project(mut self, project: PermissionConfig) -> Self {
+        self.project = project;
+        self
+    }
+
+    /// Limit the run to `scope`.
+    pub fn with_scope(mut self, scope: ToolScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Whether `tool` is within this run's scope, and so offered to the model.
+    pub fn in_scope(&self, tool: &str) -> bool {
+        self.scope.includes(tool)
+    }
+
+    /// Ask for approvals on `approver` instead of the terminal.
+    pub fn with_approver(mut self, approver: mpsc::UnboundedSender<ApprovalRequest>) -> Self {
+        self.interactive = true;
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Treat `ask` as deny, e.g. in single-prompt mode.
+    pub fn non_interactive(mut self) -> Self {
+        self.interactive = false;
+        self
+    }
+
+    /// Check a tool call, prompting the user if its policy is `ask`.
+    /// Returns [`AgentError::PermissionDenied`] if it must not run.
+    pub async fn authorize(&self, kv: &KvStore, tool: &str, input: &Value) -> Result<()> {
+        let subjects = subjects(tool, input);
+        let mut remembered = Remembered {
+            tool: kv.get(&tool_key(tool)).await.is_ok(),
+            ..Default::default()
+        };
+        if tool == "bash" {
+            for program in subjects.iter().filter_map(|s| program(s)) {
+                if kv.get(&program_key(program)).await.is_ok() {
+                    remembered.programs.insert(program.to_string());
+                }
+            }
+        }
+
+        match self.evaluate(tool, input, &subjects, &remembered) {
+            Verdict::Allow => Ok(()),
+            Verdict::Deny(reason) => Err(AgentError::PermissionDenied(reason)),
+            Verdict::Ask if !self.interactive => Err(AgentError::PermissionDenied(format!(
+                "{tool} needs approval, but there is no terminal to ask; \
+                 allow it in ~/.infinity/permissions.json"
+            ))),
+            Verdict::Ask => {
+                // Programs an "always" answer would approve.
+                let programs: Vec<&str> = if tool == "bash" {
+                    let mut seen = Vec::new();
+                    for p in subjects.iter().filter_map(|s| program(s)) {
+                        if !seen.contains(&p) {
+                            seen.push(p);
+                        }
+                    }
+                    seen
+                } else {
+                    Vec::new()
+                };
+                let always = if tool == "bash" {
+                    let names: Vec<String> = programs.iter().map(|p| format!("`{p}`")).collect();
+                    format!("always allow {} commands", names.join(", "))
+                } else {
+                    format!("always allow {tool}")
+                };
+
+                let summary = summary(tool, input);
+                let answer = match &self.approver {
+                    Some(approver) => ask_approver(approver, tool, summary, always).await,
+                    None => ask(tool, &summary, &always).await?,
+                };
+                match answer {
+                    Answer::Once => Ok(()),
+                    Answer::Always => {
+                        if tool == "bash" {
+                            for p in programs {
+                                kv.set(&program_key(p), "allow").await?;
+                            }
+                        } else {
+                            kv.set(&tool_key(tool), "allow").await?;
+                        }
+                        Ok(())
+                    }
+                    Answer::No => Err(AgentError::PermissionDenied(format!(
+                        "the user declined this {tool} call"
+                    ))),
+                }
+            }
+        }
+    }
+
+    fn evaluate(&self, tool: &str, input: &Value, subjects: &[String], remembered: &Remembered) -> Verdict {
+        if !self.scope.includes(tool) {
+            return Verdict::Deny(format!("{tool} is not enabled for this run"));
+        }
+        let default = self.tool_policy(tool);
+        if subjects.is_empty() {
+            let floor = self.project.configured(tool, None).unwrap_or(Policy::Allow);
+            return match self.rule_for(tool, None) {
+                Some(rule) if rule.policy == Policy::Deny => Verdict::Deny(rule_reason(tool, rule)),
+                _ if floor == Policy::Deny => Verdict::Deny(project_reason(tool, None)),
+                Some(rule) => self.verdict(tool, rule.policy.max(floor), remembered.tool),
+                None => self.verdict(tool, default.max(floor), remembered.tool),
+            };
+        }
+
+        let command = input.get("command").and_then(|c| c.as_str()).unwrap_or("");
+        let substitution = tool == "bash" && (command.contains("$(") || command.contains('`'));
+        let mut worst = Policy::Allow;
+        for subject in subjects {
+            let rule = self.rule_for(tool, Some(subject));
+            if let Some(rule) = rule.filter(|r| r.policy == Policy::Deny) {
+                return Verdict::Deny(rule_reason(tool, rule));
+            }
+            let floor = self.project.configured(tool, Some(subject)).unwrap_or(Policy::Allow);
+            if floor == Policy::Deny {
+                return Verdict::Deny(project_reason(tool, Some(subject)));
+            }
+            let approved = tool == "bash"
+                && !substitution
+                && program(subject).is_some_and(|p| remembered.programs.contains(p));
+            let policy = match rule {
+                _ if approved => Policy::Allow,
+                // Command substitution can hide anything inside an allowed prefix.
+                Some(rule) if rule.policy == Policy::Allow && substitution => default.max(floor),
+                Some(rule) => rule.policy.max(floor),
+                None => default.max(floor),
+            };
+            worst = worst.max(policy);
+        }
+        self.verdict(tool, worst, tool != "bash" && remembered.tool)
+    }
+
+    fn verdict(&self, tool: &str, policy: Policy, remembered: bool) -> Verdict {
+        match policy {
+            Policy::Allow => Verdict::Allow,
+            Policy::Ask if remembered || self.scope.skip_prompts => Verdict::Allow,
+            Policy::Ask => Verdict::Ask,
+            Policy::Deny => Verdict::Deny(format!(
+                "{tool} is disabled in ~/.infinity/permissions.json"
+            )),
+        }
+    }
+
+    /// Configured policy for `tool`, or the built-in default.
+    fn tool_policy(&self, tool: &str) -> Policy {
+        if let Some(policy) = self.config.tools.get(tool) {
+            return *policy;
+        }
+        let configured = self
+            .config
+            .tools
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, tool))
+            .map(|(_, policy)| *policy);
+        configured.unwrap_or(if tool == "bash" || tool.contains("__") {
+            Policy::Ask
+        } else {
+            Policy::Allow
+        })
+    }
+
+    /// First rule for `tool` matching `subject`.
+    fn rule_for(&self, tool: &str, subject: Option<&str>) -> Option<&Rule> {
+        first_rule(&self.config.rules, tool, subject)
+    }
+
+    /// Policies and remembered approvals, for the `/permissions` command.
+    pub async fn describe(&self, kv: &KvStore) -> Result<String> {
+        let mut out = String::from("Tool policies (unlisted: bash and MCP tools ask, others allow):\n");
+        let mut tools: Vec<_> = self.config.tools.iter().collect();
+        tools.sort();
+        for (tool, policy) in tools {
+            out.push_str(&format!("  {tool}: {policy:?}\n"));
+        }
+        if !self.config.rules.is_empty() {
+            out.push_str("Rules (first match wins):\n");
+            for rule in &self.config.rules {
+                out.push_str(&format!(
+                    "  {} {} -> {:?}\n",
+                    rule.tool,
+                    rule.pattern.as_deref().unwrap_or("*"),
+                    rule.policy
+                ));
+            }
+        }
+        if !self.project.tools.is_empty() || !self.project.rules.is_empty() {
+            out.push_str("Project policies (.infinity/config.toml; can only make calls stricter):\n");
+            let mut tools: Vec<_> = self.project.tools.iter().collect();
+            tools.sort();
+            for (tool, policy) in tools {
+                out.push_str(&format!("  {tool}: {policy:?}\n"));
+            }
+            for rule in &self.project.rules {
+                out.push_str(&format!(
+                    "  {} {} -> {:?}\n",
+                    rule.tool,
+                    rule.pattern.as_deref().unwrap_or("*"),
+                    rule.policy
+                ));
+            }
+        }
+        let always = kv.list_prefix(ALWAYS_KEY_PREFIX).await?;
+        if always.is_empty() {
+            out.push_str("No remembered approvals.\n");
+        } else {
+            out.push_str("Always allowed (clear with /permissions reset):\n");
+            for entry in always {
+                let what = &entry.key[ALWAYS_KEY_PREFIX.len()..];
+                match what.strip_prefix("bash:") {
+                    Some(program) => out.push_str(&format!("  bash: `{program}` commands\n")),
+                    None => out.push_str(&format!("  {}\n", what.trim_start_matches("tool:"))),
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn tool_key(tool: &str) -> String {
+    format!("{ALWAYS_KEY_PREFIX}tool:{tool}")
+}
+
+fn program_key(program: &str) -> String {
+    format!("{ALWAYS_KEY_PREFIX}bash:{program}")
+}
+
+/// First of `rules` for `tool` matching `subject`.
+fn first_rule<'a>(rules: &'a [Rule], tool: &str, subject: Option<&str>) -> Option<&'a Rule> {
+    rules.iter().find(|rule| {
+        glob_match(&rule.tool, tool)
+            && match (&rule.pattern, subject) {
+                (None, _) => true,
+                (Some(pattern), Some(subject)) => glob_match(pattern, subject),
+                (Some(_), None) => false,
+            }
+    })
+}
+
+fn project_reason(tool: &str, subject: Option<&str>) -> String {
+    match subject {
+        Some(subject) => format!("`{subject}` is denied by the project's .infinity/config.toml"),
+        None => format!("{tool} is denied by the project's .infinity/config.toml"),
+    }
+}
+
+fn rule_reason(tool: &str, rule: &Rule) -> String {
+    match &rule.pattern {
+        Some(pattern) => format!("{tool} matching `{pattern}` is denied by ~/.infinity/permissions.json"),
+        None => format!("{tool} is denied by ~/.infinity/permissions.json"),
+    }
+}
+
+/// What rules match against: each command of a bash line, the paths of
+/// file tools, or the URL of `fetch_url`. Empty for other tools.
+fn subjects(tool: &str, input: &Value) -> Vec<String> {
+    let str_field = |key: &str| input.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    match tool {
+        "bash" => split_commands(&str_field("command").unwrap_or_default()),
+        "multi_edit" => input
+            .get("edits")
+            .and_then(|e| e.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|e| e.get("path").and_then(|p| p.as_str()).map(str::to_string))
+            .collect(),
+        "fetch_url" => str_field("url").into_iter().collect(),
+        _ => str_field("path").into_iter().collect(),
+    }
+}
+
+/// Split a shell line into its commands at `;`, `&`, `|` and newlines.
+/// Quotes are respected; anything subtler only makes commands look longer,
+/// which errs towards asking.
+fn split_commands(line: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some(_), _) => {}
+            (None, '\\') => escaped = true,
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ';' | '&' | '|' | '\n') => {
+                commands.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    commands.push(current);
+    commands
+        .into_iter()
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// The program a command runs, skipping leading `VAR=value` assignments.
+fn program(command: &str) -> Option<&str> {
+    command
+        .split_whitespace()
+        .find(|word| !word.contains('='))
+}
+
+/// Glob match where `*` matches any run of characters and `?` one.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    // Position after the last `*` and the text index it was tried at.
+    let mut backtrack = None;
+    while ti < t.len() {
+        match p.get(pi) {
+            Some('*') => {
+                backtrack = Some((pi + 1, ti));
+                pi += 1;
+            }
+            Some(&c) if c == '?' || c == t[ti] => {
+                pi += 1;
+                ti += 1;
+            }
+            _ => match backtrack {
+                Some((star, start)) => {
+                    pi = star;
+                    ti = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// One-line description of a call for the approval prompt.
+fn summary(tool: &str, input: &Value) -> String {
+    let text = match tool {
+        "bash" => input.get("command").and_then(|c| c.as_str()).unwrap_or("").to_string(),
+        _ => subjects(tool, input).join(", "),
+    };
+    let text = text.replace('\n', " ⏎ ");
+    match text.char_indices().nth(200) {
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text,
+    }
+}
+
+/// Restores cooked mode even if the prompt is cancelled mid-read.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Ask the user to approve a call: y = once, a = always, n/Esc/Ctrl+C = no.
+async fn ask(tool: &str, summary: &str, always: &str) -> Result<Answer> {
+    crate::display::print_permission_prompt(tool, summary, always);
+    let answer = read_key(|code| match code {
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Some(Answer::Once),
+        KeyCode::Char('a') | KeyCode::Char('A') => Some(Answer::Always),
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(Answer::No),
+        _ => None,
+    }, Answer::No)
+    .await?;
+    println!(
+        "{}",
+        match answer {
+            Answer::Once => "yes",
+            Answer::Always => "always",
+            Answer::No => "no",
+        }
+    );
+    let _ = std::io::stdout().flush();
+    Ok(answer)
+}
+
+/// Send a call to `approver` and wait for the answer; no if nobody is
+/// listening.
+async fn ask_approver(
+    approver: &mpsc::UnboundedSender<ApprovalRequest>,
+    tool: &str,
+    summary: String,
+    always: String,
+) -> Answer {
+    let (reply, answer) = oneshot::channel();
+    let request = ApprovalRequest {
+        tool: tool.to_string(),
+        summary,
+        always,
+        reply,
+    };
+    if approver.send(request).is_err() {
+        return Answer::No;
+    }
+    answer.await.unwrap_or(Answer::No)
+}
+
+/// Wait for a key that `choose` maps to an answer; Ctrl+C gives `cancel`.
+///
+/// Keys are polled rather than read on a blocking thread, so dropping this
+/// future (Ctrl+C cancelling the turn) leaves nothing reading stdin.
+pub async fn read_key<T>(choose: impl Fn(KeyCode) -> Option<T>, cancel: T) -> Result<T> {
+    let _raw = RawMode::enable()?;
+    loop {
+        if !event::poll(Duration::ZERO)? {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(cancel);
+        }
+        if let Some(answer) = choose(key.code) {
+            return Ok(answer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn permissions(config: Value) -> Permissions {
+        Permissions {
+            config: serde_json::from_value(config).unwrap(),
+            project: PermissionConfig::default(),
+            interactive: false,
+            scope: ToolScope::default(),
+            approver: None,
+        }
+    }
+
+    fn verdict(p: &Permissions, tool: &str, input: Value, remembered: &Remembered) -> Verdict {
+        p.evaluate(tool, &input, &subjects(tool, &input), remembered)
+    }
+
+    #[test]
+    fn test_glob_and_split() {
+        assert!(glob_match("git *", "git status"));
+        assert!(glob_match("/src/*.rs", "/src/a/b.rs"));
+        assert!(glob_match("github__*", "github__create_issue"));
+        assert!(!glob_match("git *", "gitk"));
+        assert!(glob_match("a?c*", "abc"));
+
+        assert_eq!(
+            split_commands("cd /tmp && echo 'a;b' | grep a; ls"),
+            ["cd /tmp", "echo 'a;b'", "grep a", "ls"]
+        );
+        assert_eq!(program("RUST_LOG=debug cargo test"), Some("cargo"));
+    }
+
+    #[test]
+    fn test_evaluate_defaults_and_rules() {
+        let none = Remembered::default();
+        let p = permissions(json!({
+            "tools": { "write_file": "ask" },
+            "rules": [
+                { "tool": "bash", "pattern": "rm -rf *", "policy": "deny" },
+                { "tool": "bash", "pattern": "git status*", "policy": "allow" },
+                { "tool": "write_file", "pattern": "/notes/*", "policy": "allow" }
+            ]
+        }));
+
+        assert_eq!(verdict(&p, "read_file", json!({"path": "/a"}), &none), Verdict::Allow);
+        assert_eq!(verdict(&p, "bash", json!({"command": "ls"}), &none), Verdict::Ask);
+        assert_eq!(verdict(&p, "github__x", json!({}), &none), Verdict::Ask);
+        assert_eq!(verdict(&p, "bash", json!({"command": "git status"}), &none), Verdict::Allow);
+        // Every command of the line must be allowed, and denies always win.
+        assert_eq!(verdict(&p, "bash", json!({"command": "git status; ls"}), &none), Verdict::Ask);
+        assert!(matches!(
+            verdict(&p, "bash", json!({"command": "git status && rm -rf /"}), &none),
+            Verdict::Deny(_)
+        ));
+        assert_eq!(verdict(&p, "bash", json!({"command": "git status $(rm x)"}), &none), Verdict::Ask);
+        assert_eq!(verdict(&p, "write_file", json!({"path": "/notes/a.md"}), &none), Verdict::Allow);
+        assert_eq!(verdict(&p, "write_file", json!({"path": "/src/a.rs"}), &none), Verdict::Ask);
+    }
+
+    #[test]
+    fn test_evaluate_scope() {
+        let none = Remembered::default();
+        let p = permissions(json!({
+            "rules": [{ "tool": "bash", "pattern": "rm *", "policy": "deny" }]
+        }))
+        .with_scope(ToolScope {
+            allowed: vec!["read_file".to_string(), "bash".to_string(), "github__*".to_string()],
+            disallowed: vec!["github__delete*".to_string()],
+            skip_prompts: true,
+        });
+        assert!(p.in_scope("github__create_issue"));
+        assert!(!p.in_scope("github__delete_repo"));
+        assert!(matches!(verdict(&p, "write_file", json!({"path": "/a"}), &none), Verdict::Deny(_)));
+        assert_eq!(verdict(&p, "bash", json!({"command": "ls"}), &none), Verdict::Allow);
+        assert!(matches!(verdict(&p, "bash", json!({"command": "rm x"}), &none), Verdict::Deny(_)));
+    }
+
+    #[test]
+    fn test_evaluate_remembered() {
+        let p = permissions(json!({
+            "rules": [{ "tool": "bash", "pattern": "cargo publish*", "policy": "deny" }]
+        }));
+        let remembered = Remembered {
+            tool: true,
+            programs: ["cargo".to_string()].into(),
+        };
+        assert_eq!(verdict(&p, "bash", json!({"command": "cargo test"}), &remembered), Verdict::Allow);
+        assert_eq!(verdict(&p, "bash", json!({"command": "cargo test | tee x"}), &remembered), Verdict::Ask);
+        assert!(matches!(
+            verdict(&p, "bash", json!({"command": "cargo publish"}), &remembered),
+            Verdict::Deny(_)
+        ));
+        assert_eq!(verdict(&p, "github__x", json!({}), &remembered), Verdict::Allow);
+    }
+
+    #[test]
+    fn test_evaluate_project() {
+        let none = Remembered::default();
+        let p = permissions(json!({
+            "tools": { "bash": "deny", "fetch_url": "allow" },
+            "rules": [
+                { "tool": "bash", "pattern": "git *", "policy": "allow" },
+                { "tool": "write_file", "pattern": "/tmp/*", "policy": "allow" }
+            ]
+        }))
+        .with_project(
+            serde_json::from_value(json!({
+                "tools": { "bash": "allow", "write_file": "ask", "github__*": "allow" },
+                "rules": [
+                    { "tool": "bash", "pattern": "ls*", "policy": "allow" },
+                    { "tool": "bash", "pattern": "git push*", "policy": "deny" },
+                    { "tool": "fetch_url", "policy": "ask" }
+                ]
+            }))
+            .unwrap(),
+        );
+        // Looser project policies change nothing
+        assert!(matches!(verdict(&p, "bash", json!({"command": "ls"}), &none), Verdict::Deny(_)));
+        assert_eq!(verdict(&p, "github__x", json!({}), &none), Verdict::Ask);
+        assert_eq!(verdict(&p, "bash", json!({"command": "git status"}), &none), Verdict::Allow);
+        // Stricter ones apply
+        assert!(matches!(verdict(&p, "bash", json!({"command": "git push"}), &none), Verdict::Deny(_)));
+        assert_eq!(verdict(&p, "write_file", json!({"path": "/tmp/a"}), &none), Verdict::Ask);
+        assert_eq!(verdict(&p, "fetch_url", json!({"url": "https://x"}), &none), Verdict::Ask);
+        assert_eq!(verdict(&p, "read_file", json!({"path": "/a"}), &none), Verdict::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_approver() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = agentfs_core::config::AgentFSConfig::builder(dir.path().join("test.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        let db = agentfs_core::AgentFS::create(config).await.unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel::<ApprovalRequest>();
+        let p = permissions(json!({})).with_approver(tx);
+        tokio::spawn(async move {
+            let mut answers = [Answer::No, Answer::Always].into_iter();
+            while let Some(request) = rx.recv().await {
+                assert_eq!(request.tool, "bash");
+                let _ = request.reply.send(answers.next().unwrap_or(Answer::No));
+            }
+        });
+
+        let call = json!({"command": "git status"});
+        assert!(matches!(
+            p.authorize(&db.kv, "bash", &call).await,
+            Err(AgentError::PermissionDenied(_))
+        ));
+        p.authorize(&db.kv, "bash", &call).await.unwrap();
+        // Remembered: not asked again
+        p.authorize(&db.kv, "bash", &json!({"command": "git log"})).await.unwrap();
+        assert!(db.kv.get(&program_key("git")).await.is_ok());
+    }
+}
@@ -0,0 +1,116 @@
+//! System prompt profiles: named personas in `~/.infinity/prompts/<name>.md`.
+//!
+//! A profile is selected with `--profile <name>` or `/profile <name>` and
+//! added to the system prompt after the base prompt, ahead of the skills
+//! and project instructions sections. The file may start with frontmatter
+//! giving a `description` for `/profile` listings.
+
+use std::path::PathBuf;
+
+use crate::error::{AgentError, Result};
+use crate::skills::split_frontmatter;
+
+/// A loaded prompt profile.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub description: String,
+    pub body: String,
+}
+
+impl Profile {
+    /// Load `~/.infinity/prompts/<name>.md`.
+    pub fn load(name: &str) -> Result<Self> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(AgentError::Other(format!("Invalid profile name: {name}")));
+        }
+        let path = prompts_dir().join(format!("{name}.md"));
+        let content = std::fs::read_to_string(&path).map_err(|_| {
+            let available = list();
+            AgentError::Other(if available.is_empty() {
+                format!("No profile {name}: create {}", path.display())
+            } else {
+                let names: Vec<&str> = available.iter().map(|p| p.name.as_str()).collect();
+                format!("No profile {name} (available: {})", names.join(", "))
+            })
+        })?;
+        Ok(parse(name, &content))
+    }
+
+    /// The system prompt section for this profile.
+    pub fn section(&self) -> String {
+        format!("\n\n# Profile: {}\n\n{}", self.name, self.body)
+    }
+}
+
+fn parse(name: &str, content: &str) -> Profile {
+    let (description, body) = match split_frontmatter(content) {
+        Some((mut fields, body)) => (fields.remove("description").unwrap_or_default(), body),
+        None => (String::new(), content.trim().to_string()),
+    };
+    Profile {
+        name: name.to_string(),
+        description,
+        body,
+    }
+}
+
+/// Directory holding the profiles.
+pub fn prompts_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".infinity")
+        .join("prompts")
+}
+
+/// All profiles, sorted by name.
+pub fn list() -> Vec<Profile> {
+    let Ok(entries) = std::fs::read_dir(prompts_dir()) else {
+        return Vec::new();
+    };
+    let mut profiles: Vec<Profile> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "md"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            let content = std::fs::read_to_string(&path).ok()?;
+            Some(parse(&name, &content))
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+/// Join the system prompt: the base prompt, the profile, then the other
+/// sections (skills, project instructions).
+pub fn compose(base: &str, profile: Option<&Profile>, sections: &str) -> String {
+    let profile = profile.map(Profile::section).unwrap_or_default();
+    format!("{base}{profile}{sections}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_compose() {
+        let reviewer = parse(
+            "reviewer",
+            "---\ndescription: Strict code reviewer\n---\nReview every change for bugs first.\n",
+        );
+        assert_eq!(reviewer.description, "Strict code reviewer");
+        assert_eq!(reviewer.body, "Review every change for bugs first.");
+
+        let ops = parse("ops", "You are on call.\n");
+        assert_eq!(ops.description, "");
+        assert_eq!(ops.body, "You are on call.");
+
+        assert_eq!(
+            compose("Base.", Some(&reviewer), "\n\n## Skills"),
+            "Base.\n\n# Profile: reviewer\n\nReview every change for bugs first.\n\n## Skills"
+        );
+        assert_eq!(compose("Base.", None, ""), "Base.");
+        assert!(Profile::load("../secrets").is_err());
+    }
+}
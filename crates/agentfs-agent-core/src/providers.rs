@@ -0,0 +1,518 @@
+//! Fallback model providers.
+//!
+//! `~/.infinity/providers.json` lists the providers to try in order, e.g.
+//!
+//! ```json
+//! {
+//!   "chain": ["anthropic", "openrouter", "ollama"],
+//!   "providers": {
+//!     "openrouter": { "model": "anthropic/claude-sonnet-4" },
+//!     "ollama": { "model": "qwen2.5-coder:14b" }
+//!   }
+//! }
+//! ```
+//!
+//! `anthropic` is the built-in client (model from `--model`). Every other
+//! entry is an OpenAI-compatible chat completions API; `openrouter`,
+//! `ollama` and `openai` have default URLs and key variables, other names
+//! need a `url`. The conversation is kept in Anthropic's format and
+//! converted for each request, so switching mid-session keeps the history.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use crate::api::{self, AnthropicClient, Message, Provider};
+use crate::error::{AgentError, Result};
+use crate::streaming::{ContentBlockType, StreamEvent};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProvidersConfig {
+    /// Provider names in the order to try them.
+    #[serde(default)]
+    pub chain: Vec<String>,
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderConfig>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProviderConfig {
+    /// Base URL of the API, up to and including `/v1`.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Model id as the provider names it.
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Environment variable holding the API key.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+/// Load the provider chain from ~/.infinity/providers.json (none if
+/// missing). An invalid file is reported and ignored.
+pub fn load_providers_config() -> ProvidersConfig {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".infinity");
+    path.push("providers.json");
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid {}: {e}", path.display());
+            ProvidersConfig::default()
+        }),
+        Err(_) => ProvidersConfig::default(),
+    }
+}
+
+/// Default URL and API key variable for well-known providers.
+fn known_provider(name: &str) -> Option<(&'static str, Option<&'static str>)> {
+    match name {
+        "openrouter" => Some(("https://openrouter.ai/api/v1", Some("OPENROUTER_API_KEY"))),
+        "ollama" => Some(("http://localhost:11434/v1", None)),
+        "openai" => Some(("https://api.openai.com/v1", Some("OPENAI_API_KEY"))),
+        _ => None,
+    }
+}
+
+/// Build the configured chain. Empty when no chain is configured.
+pub fn build_chain(
+    config: &ProvidersConfig,
+    anthropic: &AnthropicClient,
+    max_tokens: u32,
+) -> Result<Vec<Provider>> {
+    config
+        .chain
+        .iter()
+        .map(|name| {
+            if name == "anthropic" {
+                return Ok(Provider::Anthropic(anthropic.clone()));
+            }
+            let entry = config.providers.get(name).cloned().unwrap_or_default();
+            let known = known_provider(name);
+            let url = entry
+                .url
+                .or_else(|| known.map(|(url, _)| url.to_string()))
+                .ok_or_else(|| AgentError::Config(format!("provider '{name}' needs a url")))?;
+            let model = entry
+                .model
+                .ok_or_else(|| AgentError::Config(format!("provider '{name}' needs a model")))?;
+            let key_env = entry
+                .api_key_env
+                .or_else(|| known.and_then(|(_, env)| env).map(str::to_string));
+            let api_key = entry
+                .api_key
+                .or_else(|| key_env.and_then(|env| std::env::var(env).ok()))
+                .filter(|k| !k.is_empty());
+            Ok(Provider::OpenAi(OpenAiClient::new(name, &url, api_key, model, max_tokens)))
+        })
+        .collect()
+}
+
+/// Client for an OpenAI-compatible chat completions API.
+#[derive(Clone)]
+pub struct OpenAiClient {
+    client: reqwest::Client,
+    name: String,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    max_tokens: u32,
+}
+
+impl OpenAiClient {
+    pub fn new(name: &str, url: &str, api_key: Option<String>, model: String, max_tokens: u32) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            name: name.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+            model,
+            max_tokens,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Send a streaming request and return a channel of Anthropic-style events.
+    pub async fn stream_message(
+        &self,
+        messages: &[Message],
+        tools: &[Value],
+        system: Option<&str>,
+    ) -> Result<mpsc::Receiver<StreamEvent>> {
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": self.max_tokens,
+            "stream": true,
+            "stream_options": { "include_usage": true },
+            "messages": to_openai_messages(messages, system),
+        });
+        if !tools.is_empty() {
+            body["tools"] = Value::Array(tools.iter().map(to_openai_tool).collect());
+        }
+
+        let url = format!("{}/chat/completions", self.url);
+        let resp = api::send_with_retries(false, async || {
+            let mut req = self.client.post(&url).json(&body);
+            if let Some(key) = &self.api_key {
+                req = req.bearer_auth(key);
+            }
+            Ok(req.send().await?)
+        })
+        .await?;
+        Ok(stream_events(resp))
+    }
+}
+
+// ── Conversion ──────────────────────────────────────────────────────
+
+fn to_openai_tool(tool: &Value) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": tool["name"],
+            "description": tool["description"],
+            "parameters": tool["input_schema"],
+        }
+    })
+}
+
+/// Convert Anthropic-format messages (plus system prompt) to chat
+/// completions messages: tool results become `tool` messages, tool uses
+/// become `tool_calls` and images become `image_url` data URLs.
+pub fn to_openai_messages(messages: &[Message], system: Option<&str>) -> Vec<Value> {
+    let mut out = Vec::new();
+    if let Some(system) = system {
+        out.push(json!({ "role": "system", "content": system }));
+    }
+    for message in messages {
+        let blocks = match &message.content {
+            Value::Array(blocks) => blocks.as_slice(),
+            content => {
+                out.push(json!({ "role": message.role, "content": content.as_str().unwrap_or("") }));
+                continue;
+            }
+        };
+        let text: Vec<&str> = blocks
+            .iter()
+            .filter(|b| b["type"] == "text")
+            .filter_map(|b| b["text"].as_str())
+            .collect();
+        if message.role == "assistant" {
+            let calls: Vec<Value> = blocks
+                .iter()
+                .filter(|b| b["type"] == "tool_use")
+                .map(|b| {
+                    json!({
+                        "id": b["id"],
+                        "type": "function",
+                        "function": { "name": b["name"], "arguments": b["input"].to_string() },
+                    })
+                })
+                .collect();
+            let mut msg = json!({ "role": "assistant", "content": text.join("") });
+            if !calls.is_empty() {
+                msg["tool_calls"] = Value::Array(calls);
+            }
+            out.push(msg);
+        } else {
+            for result in blocks.iter().filter(|b| b["type"] == "tool_result") {
+                out.push(json!({
+                    "role": "tool",
+                    "tool_call_id": result["tool_use_id"],
+                    "content": tool_result_text(&result["content"]),
+                }));
+            }
+            let images: Vec<Value> = blocks
+                .iter()
+                .filter(|b| b["type"] == "image")
+                .map(|b| {
+                    let source = &b["source"];
+                    let url = format!(
+                        "data:{};base64,{}",
+                        source["media_type"].as_str().unwrap_or("image/png"),
+                        source["data"].as_str().unwrap_or("")
+                    );
+                    json!({ "type": "image_url", "image_url": { "url": url } })
+                })
+                .collect();
+            if !images.is_empty() {
+                let mut parts = images;
+                parts.push(json!({ "type": "text", "text": text.join("\n") }));
+                out.push(json!({ "role": "user", "content": parts }));
+            } else if !text.is_empty() {
+                out.push(json!({ "role": "user", "content": text.join("\n") }));
+            }
+        }
+    }
+    out
+}
+
+fn tool_result_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Parse the SSE body of a chat completions stream into Anthropic-style events.
+fn stream_events(resp: reqwest::Response) -> mpsc::Receiver<StreamEvent> {
+    let (tx, rx) = mpsc::channel(64);
+
+    tokio::spawn(async move {
+        let mut stream = resp.bytes_stream();
+        let mut buffer = String::new();
+        let mut translator = Translator::default();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(c) => c,
+                Err(e) => {
+                    let message = format!("connection lost mid-response: {e}");
+                    let _ = tx.send(StreamEvent::Error { message, retryable: true }).await;
+                    return;
+                }
+            };
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(pos) = buffer.find('\n') {
+                let line = buffer[..pos].trim().to_string();
+                buffer = buffer[pos + 1..].to_string();
+                let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                let events = if data == "[DONE]" {
+                    translator.finish()
+                } else {
+                    match serde_json::from_str::<Value>(data) {
+                        Ok(chunk) => translator.push(&chunk),
+                        Err(_) => continue,
+                    }
+                };
+                for event in events {
+                    if tx.send(event).await.is_err() {
+                        return; // receiver dropped
+                    }
+                }
+            }
+        }
+
+        if !translator.done {
+            let message = "connection closed mid-response".to_string();
+            let _ = tx.send(StreamEvent::Error { message, retryable: true }).await;
+        }
+    });
+
+    rx
+}
+
+/// Turns chat completions chunks into the block-structured events the
+/// agent consumes. Usage arrives last, so `MessageStart` (which carries
+/// the input token count) is emitted at the end with `MessageDelta`.
+#[derive(Default)]
+struct Translator {
+    id: String,
+    /// Index of the open block, and whether it is text.
+    open: Option<(u32, bool)>,
+    next_index: u32,
+    /// Open tool call's index in the provider's `tool_calls` array.
+    tool_slot: Option<u64>,
+    stop_reason: Option<String>,
+    input_tokens: u64,
+    output_tokens: u64,
+    done: bool,
+}
+
+impl Translator {
+    fn push(&mut self, chunk: &Value) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        if let Some(error) = chunk.get("error") {
+            self.done = true;
+            let message = error["message"].as_str().unwrap_or("Unknown error").to_string();
+            events.push(StreamEvent::Error { message, retryable: true });
+            return events;
+        }
+        if let Some(id) = chunk["id"].as_str() {
+            self.id = id.to_string();
+        }
+        if let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) {
+            self.input_tokens = usage["prompt_tokens"].as_u64().unwrap_or(0);
+            self.output_tokens = usage["completion_tokens"].as_u64().unwrap_or(0);
+        }
+        let Some(choice) = chunk["choices"].get(0) else {
+            return events;
+        };
+        let delta = &choice["delta"];
+
+        if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) {
+            let index = match self.open {
+                Some((index, true)) => index,
+                _ => self.start(ContentBlockType::Text, &mut events),
+            };
+            events.push(StreamEvent::TextDelta { index, text: text.to_string() });
+        }
+
+        for call in delta["tool_calls"].as_array().map(Vec::as_slice).unwrap_or_default() {
+            let slot = call["index"].as_u64().unwrap_or(0);
+            if self.tool_slot != Some(slot) {
+                let block_type = ContentBlockType::ToolUse {
+                    id: call["id"].as_str().unwrap_or_default().to_string(),
+                    name: call["function"]["name"].as_str().unwrap_or_default().to_string(),
+                };
+                self.start(block_type, &mut events);
+                self.tool_slot = Some(slot);
+            }
+            if let (Some((index, false)), Some(args)) = (self.open, call["function"]["arguments"].as_str()) {
+                if !args.is_empty() {
+                    events.push(StreamEvent::InputJsonDelta { index, partial_json: args.to_string() });
+                }
+            }
+        }
+
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            self.stop_reason = Some(
+                match reason {
+                    "tool_calls" | "function_call" => "tool_use",
+                    "length" => "max_tokens",
+                    _ => "end_turn",
+                }
+                .to_string(),
+            );
+        }
+        events
+    }
+
+    /// Close the open block and start a new one; returns its index.
+    fn start(&mut self, block_type: ContentBlockType, events: &mut Vec<StreamEvent>) -> u32 {
+        self.close(events);
+        let index = self.next_index;
+        self.next_index += 1;
+        self.open = Some((index, matches!(block_type, ContentBlockType::Text)));
+        events.push(StreamEvent::ContentBlockStart { index, block_type });
+        index
+    }
+
+    fn close(&mut self, events: &mut Vec<StreamEvent>) {
+        if let Some((index, _)) = self.open.take() {
+            events.push(StreamEvent::ContentBlockStop { index });
+        }
+        self.tool_slot = None;
+    }
+
+    /// The stream ended (`[DONE]`).
+    fn finish(&mut self) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        if self.done {
+            return events;
+        }
+        self.done = true;
+        self.close(&mut events);
+        events.push(StreamEvent::MessageStart {
+            id: self.id.clone(),
+            input_tokens: self.input_tokens,
+        });
+        events.push(StreamEvent::MessageDelta {
+            stop_reason: self.stop_reason.take().unwrap_or_else(|| "end_turn".to_string()),
+            output_tokens: self.output_tokens,
+        });
+        events.push(StreamEvent::MessageStop);
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::ContentAccumulator;
+
+    #[test]
+    fn test_to_openai_messages() {
+        let messages = vec![
+            Message { role: "user".into(), content: json!("list files") },
+            Message {
+                role: "assistant".into(),
+                content: json!([
+                    { "type": "text", "text": "Listing." },
+                    { "type": "tool_use", "id": "t1", "name": "list_dir", "input": { "path": "/" } }
+                ]),
+            },
+            Message {
+                role: "user".into(),
+                content: json!([
+                    { "type": "tool_result", "tool_use_id": "t1", "content": "src/" },
+                    { "type": "text", "text": "and now?" }
+                ]),
+            },
+        ];
+        let out = to_openai_messages(&messages, Some("be brief"));
+        assert_eq!(out.len(), 5);
+        assert_eq!(out[0], json!({ "role": "system", "content": "be brief" }));
+        assert_eq!(out[2]["tool_calls"][0]["function"]["arguments"], r#"{"path":"/"}"#);
+        assert_eq!(out[3], json!({ "role": "tool", "tool_call_id": "t1", "content": "src/" }));
+        assert_eq!(out[4], json!({ "role": "user", "content": "and now?" }));
+
+        let with_image = vec![Message {
+            role: "user".into(),
+            content: json!([
+                { "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "iVBO" } },
+                { "type": "text", "text": "what's wrong here?" }
+            ]),
+        }];
+        let out = to_openai_messages(&with_image, None);
+        assert_eq!(out[0]["content"][0]["image_url"]["url"], "data:image/png;base64,iVBO");
+        assert_eq!(out[0]["content"][1]["text"], "what's wrong here?");
+    }
+
+    #[test]
+    fn test_translator_text_and_tool_calls() {
+        let chunks = [
+            json!({ "id": "c1", "choices": [{ "delta": { "content": "Let me " } }] }),
+            json!({ "choices": [{ "delta": { "content": "look." } }] }),
+            json!({ "choices": [{ "delta": { "tool_calls": [
+                { "index": 0, "id": "call_a", "function": { "name": "read_file", "arguments": "{\"pa" } }
+            ] } }] }),
+            json!({ "choices": [{ "delta": { "tool_calls": [
+                { "index": 0, "function": { "arguments": "th\":\"/a\"}" } }
+            ] } }] }),
+            json!({ "choices": [{ "delta": { "tool_calls": [
+                { "index": 1, "id": "call_b", "function": { "name": "tree", "arguments": "{}" } }
+            ] } }] }),
+            json!({ "choices": [{ "delta": {}, "finish_reason": "tool_calls" }] }),
+            json!({ "choices": [], "usage": { "prompt_tokens": 12, "completion_tokens": 7 } }),
+        ];
+        let mut translator = Translator::default();
+        let mut events: Vec<StreamEvent> = chunks.iter().flat_map(|c| translator.push(c)).collect();
+        events.extend(translator.finish());
+
+        let mut acc = ContentAccumulator::new();
+        for event in &events {
+            acc.process(event);
+        }
+        assert_eq!(acc.full_text(), "Let me look.");
+        assert_eq!(acc.tool_use_blocks.len(), 2);
+        assert_eq!(acc.tool_use_blocks[0].id, "call_a");
+        assert_eq!(acc.tool_use_blocks[0].input, json!({ "path": "/a" }));
+        assert_eq!(acc.tool_use_blocks[1].name, "tree");
+        assert!(events.iter().any(|e| matches!(
+            e,
+            StreamEvent::MessageDelta { stop_reason, output_tokens: 7 } if stop_reason == "tool_use"
+        )));
+        assert!(events.iter().any(|e| matches!(e, StreamEvent::MessageStart { input_tokens: 12, .. })));
+    }
+}
@@ -0,0 +1,347 @@
+//! Turn-by-turn reconstruction of a past session, for replaying it.
+//!
+//! The saved conversation (`session:messages:<id>`) has what was said and
+//! which tools were called; the `tool_calls` table adds when each call ran
+//! and `token_usage` what each model call cost. Messages don't carry ids
+//! from those tables, so they are lined up by order, counting back from the
+//! end: `/clear` and cancelled turns drop messages from the conversation
+//! but not rows from the tables.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use agentfs_core::analytics::TokenRecord;
+use agentfs_core::toolcalls::ToolCall;
+use agentfs_core::AgentFS;
+
+use crate::agent::MESSAGES_KEY_PREFIX;
+use crate::api::Message;
+use crate::error::{AgentError, Result};
+
+// ── Types ──────────────────────────────────────────────────────────
+
+/// A user message and everything the agent did in answer to it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayTurn {
+    pub user: String,
+    pub steps: Vec<ReplayStep>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_microcents: i64,
+}
+
+/// One model call: the text it streamed and the tools it asked for.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayStep {
+    pub text: String,
+    pub tool_calls: Vec<ReplayToolCall>,
+    /// What the call cost, when its usage record is still there.
+    pub usage: Option<StepUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepUsage {
+    pub model: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_microcents: i64,
+    pub recorded_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayToolCall {
+    /// The model's `tool_use` id.
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+    /// The result the model was given, if the conversation has it.
+    pub output: Option<String>,
+    pub is_error: bool,
+    /// Row in `tool_calls`, for calls that reached the executor.
+    pub call_id: Option<i64>,
+    pub started_at: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+// ── Building ───────────────────────────────────────────────────────
+
+/// Reconstruct session `session_id`. Sessions without a saved conversation
+/// replay as no turns.
+pub async fn build(db: &AgentFS, session_id: &str) -> Result<Vec<ReplayTurn>> {
+    let messages: Vec<Message> = match db.kv.get(&format!("{MESSAGES_KEY_PREFIX}{session_id}")).await {
+        Ok(entry) => serde_json::from_str(&entry.value)
+            .map_err(|e| AgentError::Other(format!("Failed to parse saved messages: {e}")))?,
+        Err(agentfs_core::error::AgentFSError::KeyNotFound { .. }) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let calls = db.tools.by_session(session_id).await?;
+    // Reflection and other side calls are recorded with a purpose
+    let usage: Vec<TokenRecord> = db
+        .analytics
+        .session_records(session_id)
+        .await?
+        .into_iter()
+        .filter(|r| r.purpose.is_none())
+        .collect();
+    Ok(assemble(&messages, &calls, &usage))
+}
+
+fn assemble(messages: &[Message], calls: &[ToolCall], usage: &[TokenRecord]) -> Vec<ReplayTurn> {
+    let mut turns: Vec<ReplayTurn> = Vec::new();
+    for message in messages {
+        match message.role.as_str() {
+            "user" => {
+                let results = tool_results(&message.content);
+                if results.is_empty() {
+                    turns.push(ReplayTurn {
+                        user: user_text(&message.content),
+                        ..Default::default()
+                    });
+                    continue;
+                }
+                let Some(step) = turns.last_mut().and_then(|t| t.steps.last_mut()) else {
+                    continue;
+                };
+                for call in &mut step.tool_calls {
+                    if let Some((output, is_error)) = results.iter().find(|r| r.0 == call.id).map(|r| (&r.1, r.2)) {
+                        call.output = Some(output.clone());
+                        call.is_error = is_error;
+                    }
+                }
+            }
+            "assistant" => {
+                if turns.is_empty() {
+                    turns.push(ReplayTurn::default());
+                }
+                let turn = turns.last_mut().expect("pushed above");
+                turn.steps.push(assistant_step(&message.content));
+            }
+            _ => {}
+        }
+    }
+
+    attach_usage(&mut turns, usage);
+    attach_calls(&mut turns, calls);
+    turns
+}
+
+/// Give the last `usage.len()` steps their records, in order.
+fn attach_usage(turns: &mut [ReplayTurn], usage: &[TokenRecord]) {
+    let mut records = usage.iter().rev();
+    for turn in turns.iter_mut().rev() {
+        for step in turn.steps.iter_mut().rev() {
+            let Some(r) = records.next() else { break };
+            step.usage = Some(StepUsage {
+                model: r.model.clone(),
+                input_tokens: r.input_tokens,
+                output_tokens: r.output_tokens,
+                cost_microcents: r.cost_microcents,
+                recorded_at: r.recorded_at.clone(),
+            });
+        }
+        for usage in turn.steps.iter().filter_map(|s| s.usage.as_ref()) {
+            turn.input_tokens += usage.input_tokens;
+            turn.output_tokens += usage.output_tokens;
+            turn.cost_microcents += usage.cost_microcents;
+        }
+    }
+}
+
+/// Match tool uses to `tool_calls` rows of the same tool, latest first.
+/// Calls that were refused never got a row and are skipped over.
+fn attach_calls(turns: &mut [ReplayTurn], calls: &[ToolCall]) {
+    let mut remaining = calls.len();
+    let uses = turns
+        .iter_mut()
+        .rev()
+        .flat_map(|t| t.steps.iter_mut().rev())
+        .flat_map(|s| s.tool_calls.iter_mut().rev());
+    for tool_use in uses {
+        let Some(found) = calls[..remaining].iter().rposition(|c| c.tool_name == tool_use.name) else {
+            continue;
+        };
+        let call = &calls[found];
+        tool_use.call_id = Some(call.id);
+        tool_use.started_at = Some(call.started_at.clone());
+        tool_use.duration_ms = call.ended_at.as_deref().and_then(|end| duration_ms(&call.started_at, end));
+        if tool_use.output.is_none() {
+            tool_use.output = call.output.clone().or_else(|| call.error_msg.clone());
+        }
+        remaining = found;
+    }
+}
+
+fn assistant_step(content: &Value) -> ReplayStep {
+    let mut step = ReplayStep::default();
+    match content {
+        Value::String(text) => step.text = text.clone(),
+        Value::Array(blocks) => {
+            for block in blocks {
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("text") => step.text.push_str(block.get("text").and_then(|t| t.as_str()).unwrap_or("")),
+                    Some("tool_use") => step.tool_calls.push(ReplayToolCall {
+                        id: str_field(block, "id"),
+                        name: str_field(block, "name"),
+                        input: block.get("input").cloned().unwrap_or(Value::Null),
+                        output: None,
+                        is_error: false,
+                        call_id: None,
+                        started_at: None,
+                        duration_ms: None,
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    step
+}
+
+/// The text of a user message; attachments show as placeholders.
+fn user_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .map(|block| match block.get("type").and_then(|t| t.as_str()) {
+                Some("text") => str_field(block, "text"),
+                Some(kind) => format!("[{kind}]"),
+                None => String::new(),
+            })
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// The `(tool_use_id, content, is_error)` of each tool result in a message.
+fn tool_results(content: &Value) -> Vec<(String, String, bool)> {
+    let Value::Array(blocks) = content else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+        .map(|b| {
+            let text = match b.get("content") {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            let is_error = b.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
+            (str_field(b, "tool_use_id"), text, is_error)
+        })
+        .collect()
+}
+
+fn str_field(value: &Value, field: &str) -> String {
+    value.get(field).and_then(|v| v.as_str()).unwrap_or("").to_string()
+}
+
+/// Milliseconds between two `tool_calls` timestamps.
+fn duration_ms(start: &str, end: &str) -> Option<i64> {
+    let parse = |s: &str| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok();
+    Some((parse(end)? - parse(start)?).num_milliseconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::config::AgentFSConfig;
+    use serde_json::json;
+
+    fn message(role: &str, content: Value) -> Message {
+        Message {
+            role: role.into(),
+            content,
+        }
+    }
+
+    fn usage(session_id: &str, cost: i64, purpose: Option<&str>) -> TokenRecord {
+        TokenRecord {
+            id: None,
+            session_id: Some(session_id.into()),
+            tool_call_id: None,
+            model: "claude-sonnet-4-6".into(),
+            input_tokens: 100,
+            output_tokens: 10,
+            cache_read_tokens: 0,
+            cache_write_tokens: 0,
+            cost_microcents: cost,
+            recorded_at: None,
+            purpose: purpose.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration_ms("2026-03-02T10:00:00.000", "2026-03-02T10:00:01.250"), Some(1250));
+        assert_eq!(duration_ms("2026-03-02T10:00:00.000", "garbage"), None);
+    }
+
+    #[tokio::test]
+    async fn turns_with_tools_and_costs() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+
+        db.sessions.start("s1", Some("infinity"), None, None).await.unwrap();
+        // A turn cleared from the conversation: its rows stay behind
+        db.tools.record(Some("s1"), "bash", Some("{}"), Some("old"), None).await.unwrap();
+        db.analytics.record_usage(usage("s1", 1, None)).await.unwrap();
+
+        let messages = vec![
+            message("user", json!("read the manifest")),
+            message(
+                "assistant",
+                json!([
+                    {"type": "text", "text": "Reading it."},
+                    {"type": "tool_use", "id": "tu1", "name": "read_file", "input": {"path": "/Cargo.toml"}}
+                ]),
+            ),
+            message(
+                "user",
+                json!([{"type": "tool_result", "tool_use_id": "tu1", "content": "[package]", "is_error": false}]),
+            ),
+            message("assistant", json!([{"type": "text", "text": "It's a package."}])),
+            message("user", json!([{"type": "text", "text": "thanks"}, {"type": "image"}])),
+            message("assistant", json!([{"type": "text", "text": "Welcome."}])),
+        ];
+        db.kv
+            .set("session:messages:s1", &serde_json::to_string(&messages).unwrap())
+            .await
+            .unwrap();
+        db.tools
+            .record(Some("s1"), "read_file", Some(r#"{"path":"/Cargo.toml"}"#), Some("[pack"), None)
+            .await
+            .unwrap();
+        for cost in [10, 20] {
+            db.analytics.record_usage(usage("s1", cost, None)).await.unwrap();
+        }
+        db.analytics.record_usage(usage("s1", 99, Some("reflection"))).await.unwrap();
+        db.analytics.record_usage(usage("s1", 30, None)).await.unwrap();
+
+        let turns = build(&db, "s1").await.unwrap();
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].user, "read the manifest");
+        assert_eq!(turns[0].steps.len(), 2);
+        assert_eq!(turns[0].cost_microcents, 30);
+        let call = &turns[0].steps[0].tool_calls[0];
+        assert_eq!(call.name, "read_file");
+        assert_eq!(call.output.as_deref(), Some("[package]"));
+        assert_eq!(call.call_id, Some(2));
+        assert!(call.duration_ms.is_some());
+        assert_eq!(turns[1].user, "thanks\n[image]");
+        assert_eq!(turns[1].steps[0].text, "Welcome.");
+        assert_eq!(turns[1].cost_microcents, 30);
+
+        assert!(build(&db, "missing").await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,425 @@
+//! A long-lived bash process on a pseudo-terminal, backing the `bash` tool.
+//!
+//! Each command is written to a script file and sourced into the shell, so
+//! `cd`, exported variables and activated virtualenvs carry over between
+//! calls, and quoting or heredocs in the command can't desynchronize the
+//! session. A marker printed after the script carries its exit status.
+//! Programs see a terminal on stdout/stderr (so they line-buffer and
+//! behave as they would for a user) while stdin is `/dev/null`, so nothing
+//! blocks waiting for input. stdout and stderr arrive interleaved.
+
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::io::unix::AsyncFd;
+use tokio::process::{Child, Command};
+use tokio::time::Instant;
+
+use crate::error::{AgentError, Result};
+
+/// Output kept per command; the rest is dropped from the middle.
+const MAX_OUTPUT: usize = 200 * 1024;
+
+/// How long to wait for the shell to settle after an interrupt or at startup.
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Terminal size reported to programs, wide enough to avoid most wrapping.
+const COLS: u16 = 200;
+const ROWS: u16 = 50;
+
+/// Result of one command.
+pub struct ShellOutput {
+    pub output: String,
+    /// `None` if the command timed out or the shell exited.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// The shell is gone (e.g. the command ran `exit`).
+    pub exited: bool,
+}
+
+pub struct Shell {
+    child: Child,
+    master: AsyncFd<OwnedFd>,
+    /// Holds the script file for the command being run. Only we can
+    /// enter it, so nobody else can swap the script.
+    dir: PathBuf,
+    /// Random per session, so a command can't print the next marker and
+    /// end its output early.
+    nonce: String,
+    /// Marker counter, so output from an abandoned command is never
+    /// mistaken for the current one's.
+    seq: u64,
+}
+
+impl Shell {
+    /// Start bash on a new pseudo-terminal in the current directory.
+    pub async fn spawn() -> Result<Self> {
+        let (master, slave) = open_pty()?;
+        let stdio = |fd: &OwnedFd| -> Result<Stdio> { Ok(Stdio::from(fd.try_clone()?)) };
+
+        let mut cmd = Command::new("bash");
+        cmd.args(["--noprofile", "--norc", "--noediting", "-i"])
+            .env("PS1", "")
+            .env("PS2", "")
+            .env("TERM", "dumb")
+            .env("HISTFILE", "/dev/null")
+            .stdin(stdio(&slave)?)
+            .stdout(stdio(&slave)?)
+            .stderr(stdio(&slave)?)
+            .kill_on_drop(true);
+        // SAFETY: only async-signal-safe calls between fork and exec.
+        unsafe {
+            cmd.pre_exec(|| {
+                // New session with the pty as controlling terminal, so Ctrl+C
+                // written to the master interrupts the running command only.
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = cmd
+            .spawn()
+            .map_err(|e| AgentError::Tool(format!("bash: failed to start shell: {e}")))?;
+        drop(slave);
+
+        let dir = std::env::temp_dir().join(format!("infinity-shell-{}", uuid::Uuid::new_v4()));
+        std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+        let mut shell = Self {
+            child,
+            master: AsyncFd::new(master)?,
+            dir,
+            nonce: uuid::Uuid::new_v4().simple().to_string(),
+            seq: 0,
+        };
+
+        shell.write(b"unset PROMPT_COMMAND; set +o history\n").await?;
+        if !shell.sync().await? {
+            return Err(AgentError::Tool("bash: shell did not start".to_string()));
+        }
+        Ok(shell)
+    }
+
+    /// Run `command` in the shell, interrupting it after `timeout`.
+    pub async fn run(&mut self, command: &str, timeout: Duration) -> Result<ShellOutput> {
+        let script = self.dir.join("command.sh");
+        std::fs::write(&script, command)?;
+        let marker = self.next_marker();
+        let line = format!(
+            ". {} < /dev/null; printf '\\n{marker}%d__\\n' \"$?\"\n",
+            shell_quote(&script.to_string_lossy())
+        );
+        self.write(line.as_bytes()).await?;
+
+        let mut collected = Output::default();
+        let deadline = Instant::now() + timeout;
+        let status = self.read_until(&marker, deadline, &mut collected).await?;
+
+        match status {
+            Read::Marker(code) => Ok(ShellOutput {
+                output: collected.finish(),
+                exit_code: Some(code),
+                timed_out: false,
+                exited: false,
+            }),
+            Read::Eof => Ok(ShellOutput {
+                output: collected.finish(),
+                exit_code: None,
+                timed_out: false,
+                exited: true,
+            }),
+            Read::TimedOut => {
+                // Interrupt the foreground job, then resynchronize.
+                self.write(b"\x03").await?;
+                let exited = !self.sync().await?;
+                Ok(ShellOutput {
+                    output: collected.finish(),
+                    exit_code: None,
+                    timed_out: true,
+                    exited,
+                })
+            }
+        }
+    }
+
+    /// Stop the shell and everything it started.
+    pub async fn kill(mut self) {
+        // The shell leads its own session; signal the whole process group.
+        if let Some(pid) = self.child.id() {
+            unsafe {
+                libc::kill(-(pid as i32), libc::SIGKILL);
+            }
+        }
+        let _ = self.child.kill().await;
+    }
+
+    fn next_marker(&mut self) -> String {
+        self.seq += 1;
+        format!("__INFINITY_DONE_{}_{}_", self.nonce, self.seq)
+    }
+
+    /// Print a fresh marker and discard output up to it. Returns false if
+    /// the shell didn't answer (or is gone).
+    async fn sync(&mut self) -> Result<bool> {
+        let marker = self.next_marker();
+        self.write(format!("\nprintf '\\n{marker}%d__\\n' 0\n").as_bytes())
+            .await?;
+        let mut discard = Output::default();
+        let status = self
+            .read_until(&marker, Instant::now() + SETTLE_TIMEOUT, &mut discard)
+            .await?;
+        Ok(matches!(status, Read::Marker(_)))
+    }
+
+    async fn read_until(&mut self, marker: &str, deadline: Instant, out: &mut Output) -> Result<Read> {
+        let mut buf = [0u8; 8192];
+        loop {
+            if let Some(code) = out.take_marker(marker) {
+                return Ok(Read::Marker(code));
+            }
+            let ready = tokio::time::timeout_at(deadline, self.master.readable()).await;
+            let Ok(guard) = ready else {
+                return Ok(Read::TimedOut);
+            };
+            let mut guard = guard?;
+            match guard.try_io(|fd| read_fd(fd.get_ref().as_raw_fd(), &mut buf)) {
+                Ok(Ok(0)) => return Ok(Read::Eof),
+                Ok(Ok(n)) => out.push(&buf[..n]),
+                // EIO: the shell closed the terminal.
+                Ok(Err(e)) if e.raw_os_error() == Some(libc::EIO) => return Ok(Read::Eof),
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    async fn write(&mut self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            let mut guard = self.master.writable().await?;
+            match guard.try_io(|fd| write_fd(fd.get_ref().as_raw_fd(), data)) {
+                Ok(Ok(n)) => data = &data[n..],
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_would_block) => continue,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+enum Read {
+    Marker(i32),
+    TimedOut,
+    Eof,
+}
+
+/// Terminal output collected for one command.
+#[derive(Default)]
+struct Output {
+    buf: Vec<u8>,
+    /// Bytes dropped from the middle to stay under `MAX_OUTPUT`.
+    dropped: usize,
+}
+
+impl Output {
+    fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+        // Keep the head and the most recent tail; the marker arrives last.
+        if self.buf.len() > MAX_OUTPUT * 2 {
+            let excess = self.buf.len() - MAX_OUTPUT;
+            let head = MAX_OUTPUT / 2;
+            self.buf.drain(head..head + excess);
+            self.dropped += excess;
+        }
+    }
+
+    /// If `marker<code>__` has arrived, cut it (and what follows) off and
+    /// return the exit code.
+    fn take_marker(&mut self, marker: &str) -> Option<i32> {
+        let mut from = 0;
+        while let Some(i) = find(&self.buf[from..], marker.as_bytes()) {
+            let at = from + i;
+            let rest = &self.buf[at + marker.len()..];
+            // Skip anything that isn't `<digits>__`, e.g. the command text.
+            if let Some(end) = find(rest, b"__") {
+                if let Some(code) = std::str::from_utf8(&rest[..end]).ok().and_then(|c| c.parse().ok()) {
+                    self.buf.truncate(at);
+                    return Some(code);
+                }
+            }
+            from = at + 1;
+        }
+        None
+    }
+
+    fn finish(self) -> String {
+        let text = String::from_utf8_lossy(&self.buf);
+        let mut text = strip_ansi(&text.replace("\r\n", "\n")).replace('\r', "");
+        // Drop trailing newlines, including the one printed before the marker.
+        text.truncate(text.trim_end_matches('\n').len());
+        if self.dropped > 0 {
+            let mut mid = (MAX_OUTPUT / 2).min(text.len());
+            while !text.is_char_boundary(mid) {
+                mid -= 1;
+            }
+            text.insert_str(mid, &format!("\n[... {} bytes of output omitted ...]\n", self.dropped));
+        }
+        text
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Remove terminal escape sequences (colors, cursor movement).
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters, then one final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: until BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Single-quote `s` for the shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Open a pseudo-terminal: (non-blocking master, slave).
+fn open_pty() -> Result<(OwnedFd, OwnedFd)> {
+    let mut master: RawFd = -1;
+    let mut slave: RawFd = -1;
+    let size = libc::winsize {
+        ws_row: ROWS,
+        ws_col: COLS,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: openpty writes two fds on success; both are owned from here on.
+    unsafe {
+        if libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), std::ptr::null(), &size) != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let (master, slave) = (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave));
+
+        // Don't echo commands back; the output should be the command's own.
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(slave.as_raw_fd(), &mut termios) == 0 {
+            termios.c_lflag &= !(libc::ECHO | libc::ECHONL);
+            libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios);
+        }
+        let flags = libc::fcntl(master.as_raw_fd(), libc::F_GETFL);
+        if flags == -1 || libc::fcntl(master.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        libc::fcntl(master.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC);
+        Ok((master, slave))
+    }
+}
+
+fn read_fd(fd: RawFd, buf: &mut [u8]) -> std::io::Result<usize> {
+    // SAFETY: buf is valid for writes of buf.len() bytes.
+    let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    if n < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+fn write_fd(fd: RawFd, data: &[u8]) -> std::io::Result<usize> {
+    // SAFETY: data is valid for reads of data.len() bytes.
+    let n = unsafe { libc::write(fd, data.as_ptr().cast(), data.len()) };
+    if n < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_state_persists_between_commands() {
+        let mut shell = Shell::spawn().await.unwrap();
+        let out = shell.run("cd /tmp && export FOO=bar", Duration::from_secs(10)).await.unwrap();
+        assert_eq!(out.exit_code, Some(0));
+
+        let out = shell.run("pwd; echo $FOO; echo err >&2", Duration::from_secs(10)).await.unwrap();
+        assert_eq!(out.output, "/tmp\nbar\nerr");
+
+        let out = shell.run("false", Duration::from_secs(10)).await.unwrap();
+        assert_eq!(out.exit_code, Some(1));
+        assert_eq!(out.output, "");
+        shell.kill().await;
+    }
+
+    #[tokio::test]
+    async fn test_timeout_interrupts_and_shell_survives() {
+        let mut shell = Shell::spawn().await.unwrap();
+        let out = shell.run("echo start; sleep 30", Duration::from_millis(500)).await.unwrap();
+        assert!(out.timed_out);
+        assert!(!out.exited);
+        assert_eq!(out.output, "start");
+
+        let out = shell.run("echo 'still here'", Duration::from_secs(10)).await.unwrap();
+        assert_eq!(out.output, "still here");
+
+        let out = shell.run("exit 3", Duration::from_secs(10)).await.unwrap();
+        assert!(out.exited);
+    }
+
+    #[tokio::test]
+    async fn test_command_cannot_forge_marker() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut shell = Shell::spawn().await.unwrap();
+        let mode = std::fs::metadata(&shell.dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let forged = format!("printf '\\n__INFINITY_DONE_{}_0__\\n'; echo after", shell.seq + 1);
+        let out = shell.run(&forged, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(out.exit_code, Some(0));
+        assert!(out.output.ends_with("after"), "{}", out.output);
+        shell.kill().await;
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[1;32mok\x1b[0m done\x1b]0;title\x07!"), "ok done!");
+    }
+}
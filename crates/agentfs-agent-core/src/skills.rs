@@ -0,0 +1,933 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use agentfs_core::AgentFS;
+pub use agentfs_core::skills::{prompt, resource_dir, resources, SKILL_PREFIX};
+use serde::{Deserialize, Serialize};
+
+/// JSON-serializable skill for DB storage (no `dir` — not meaningful in DB).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SkillRecord {
+    name: String,
+    description: String,
+    body: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    allowed_tools: Vec<String>,
+    #[serde(default = "first_version")]
+    version: u32,
+    #[serde(default)]
+    updated: String,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+impl SkillRecord {
+    fn from_skill(skill: &Skill, version: u32) -> Self {
+        Self {
+            name: skill.name.clone(),
+            description: skill.description.clone(),
+            body: skill.body.clone(),
+            allowed_tools: skill.allowed_tools.clone(),
+            version,
+            updated: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    fn into_skill(self, scope: SkillScope) -> Skill {
+        Skill {
+            name: self.name,
+            description: self.description,
+            body: self.body,
+            allowed_tools: self.allowed_tools,
+            dir: PathBuf::new(),
+            scope,
+        }
+    }
+
+    fn same_content(&self, skill: &Skill) -> bool {
+        self.description == skill.description
+            && self.body == skill.body
+            && self.allowed_tools == skill.allowed_tools
+    }
+}
+
+/// One stored version of a skill, as listed by `skills history`.
+#[derive(Debug, Clone)]
+pub struct SkillVersion {
+    pub version: u32,
+    /// When this version was saved (RFC 3339; empty for skills saved
+    /// before versioning).
+    pub updated: String,
+    pub description: String,
+    pub body: String,
+    pub allowed_tools: Vec<String>,
+    /// Whether this is the version in use.
+    pub current: bool,
+}
+
+impl From<SkillRecord> for SkillVersion {
+    fn from(record: SkillRecord) -> Self {
+        Self {
+            version: record.version,
+            updated: record.updated,
+            description: record.description,
+            body: record.body,
+            allowed_tools: record.allowed_tools,
+            current: false,
+        }
+    }
+}
+
+/// A loaded skill from a SKILL.md file.
+#[derive(Debug, Clone)]
+pub struct Skill {
+    pub name: String,
+    pub description: String,
+    pub body: String,
+    /// Tool name globs the skill's turn may use; empty allows all.
+    pub allowed_tools: Vec<String>,
+    /// Directory the SKILL.md was loaded from; empty for DB-only skills.
+    pub dir: PathBuf,
+    pub scope: SkillScope,
+}
+
+/// Where a skill is defined. Project skills shadow global ones of the same
+/// name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SkillScope {
+    /// `~/.infinity/skills/` and the global DB.
+    #[default]
+    Global,
+    /// `.infinity/skills/` and the project's DB.
+    Project,
+}
+
+impl SkillScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Project => "project",
+        }
+    }
+
+    /// The skills directory of this scope.
+    pub fn dir(self) -> Option<PathBuf> {
+        match self {
+            Self::Global => dirs::home_dir().map(|home| home.join(".infinity").join("skills")),
+            Self::Project => Some(PathBuf::from(".infinity").join("skills")),
+        }
+    }
+}
+
+/// Registry of available skills.
+pub struct SkillRegistry {
+    skills: HashMap<String, Skill>,
+    /// Skills read from a skills directory without a DB to bundle the
+    /// files next to their SKILL.md into.
+    unbundled: Vec<String>,
+}
+
+impl SkillRegistry {
+    /// Scan for skills in global and project-local directories.
+    /// Project-local skills (`.infinity/skills/`) take precedence over global (`~/.infinity/skills/`).
+    pub fn load() -> Self {
+        let mut skills = HashMap::new();
+        for scope in [SkillScope::Global, SkillScope::Project] {
+            if let Some(dir) = scope.dir() {
+                load_skills_from_dir(&dir, scope, &mut skills);
+            }
+        }
+        Self {
+            skills,
+            unbundled: Vec::new(),
+        }
+    }
+
+    /// Get a skill by name.
+    #[allow(dead_code)]
+    pub fn get(&self, name: &str) -> Option<&Skill> {
+        self.skills.get(name)
+    }
+
+    /// List all skills as (name, description) pairs, sorted by name.
+    pub fn list(&self) -> Vec<(&str, &str)> {
+        let mut items: Vec<(&str, &str)> = self
+            .skills
+            .values()
+            .map(|s| (s.name.as_str(), s.description.as_str()))
+            .collect();
+        items.sort_by_key(|(name, _)| *name);
+        items
+    }
+
+    /// Generate a section to append to the system prompt describing available skills.
+    pub fn system_prompt_section(&self) -> Option<String> {
+        if self.skills.is_empty() {
+            return None;
+        }
+
+        let mut section = String::from("\n\n## Available Skills\n");
+        section.push_str("The user can invoke these skills with /skillname [args]:\n");
+
+        let mut items: Vec<&Skill> = self.skills.values().collect();
+        items.sort_by_key(|s| &s.name);
+
+        for skill in items {
+            section.push_str(&format!("- /{} — {}\n", skill.name, skill.description));
+        }
+
+        Some(section)
+    }
+
+    /// Check if user input starts with a skill invocation.
+    /// Returns (skill, remaining_args) if matched.
+    pub fn matches_command<'a>(&self, input: &'a str) -> Option<(&Skill, &'a str)> {
+        if !input.starts_with('/') {
+            return None;
+        }
+
+        let without_slash = &input[1..];
+        let (cmd, args) = match without_slash.find(char::is_whitespace) {
+            Some(idx) => (&without_slash[..idx], without_slash[idx..].trim()),
+            None => (without_slash, ""),
+        };
+
+        self.skills.get(cmd).map(|skill| (skill, args))
+    }
+
+    /// All skills, sorted by name.
+    pub fn skills(&self) -> Vec<&Skill> {
+        let mut items: Vec<&Skill> = self.skills.values().collect();
+        items.sort_by_key(|s| &s.name);
+        items
+    }
+
+    /// Whether any skills are loaded.
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.skills.is_empty()
+    }
+
+    // ── DB-backed methods ────────────────────────────────────────────
+
+    /// Load global skills from the global DB, then project skills from the
+    /// project DB, shadowing global ones of the same name. A scope without
+    /// a DB (no global DB yet, or a session on the global DB) has its
+    /// skills read straight from its skills directory.
+    pub async fn load_from_db(global: Option<&AgentFS>, project: Option<&AgentFS>) -> Self {
+        let mut skills = HashMap::new();
+        let mut unbundled = Vec::new();
+        for (scope, db) in [(SkillScope::Global, global), (SkillScope::Project, project)] {
+            let mut found = HashMap::new();
+            match db {
+                Some(db) => {
+                    for skill in Self::list_from_db(db, scope).await {
+                        found.insert(skill.name.clone(), skill);
+                    }
+                }
+                None => {
+                    if let Some(dir) = scope.dir() {
+                        load_skills_from_dir(&dir, scope, &mut found);
+                    }
+                }
+            }
+            // A shadowed skill's files don't matter any more.
+            unbundled.retain(|name| !found.contains_key(name));
+            if db.is_none() {
+                unbundled.extend(
+                    found
+                        .values()
+                        .filter(|skill| !collect_resource_files(&skill.dir).is_empty())
+                        .map(|skill| skill.name.clone()),
+                );
+            }
+            skills.extend(found);
+        }
+        Self { skills, unbundled }
+    }
+
+    /// Skills with files next to their SKILL.md that their turns can't
+    /// read, as they were loaded without a DB to bundle them into.
+    pub fn unbundled(&self) -> Vec<&Skill> {
+        let mut items: Vec<&Skill> = self
+            .unbundled
+            .iter()
+            .filter_map(|name| self.skills.get(name))
+            .collect();
+        items.sort_by_key(|s| &s.name);
+        items
+    }
+
+    /// Persist a single skill to the DB and return its version. Saving
+    /// changed content keeps the previous version in the skill's history
+    /// (`config:skill_history:<name>:<version>`); saving unchanged content
+    /// is a no-op.
+    pub async fn save_to_db(db: &AgentFS, skill: &Skill) -> u32 {
+        let key = format!("{SKILL_PREFIX}{}", skill.name);
+        let current = Self::current_record(db, &skill.name).await;
+
+        let version = match current {
+            Some(current) if current.same_content(skill) => return current.version,
+            Some(current) => {
+                let history_key = format!("config:skill_history:{}:{}", skill.name, current.version);
+                if let Ok(json) = serde_json::to_string(&current) {
+                    let _ = db.kv.set(&history_key, &json).await;
+                }
+                current.version + 1
+            }
+            None => 1,
+        };
+
+        if let Ok(json) = serde_json::to_string(&SkillRecord::from_skill(skill, version)) {
+            let _ = db.kv.set(&key, &json).await;
+        }
+        version
+    }
+
+    /// Remove a skill, its history, its sync record and its bundled
+    /// resources from the DB by name.
+    pub async fn remove_from_db(db: &AgentFS, name: &str) -> bool {
+        let history = db.kv
+            .list_prefix(&format!("config:skill_history:{name}:"))
+            .await
+            .unwrap_or_default();
+        for entry in history {
+            let _ = db.kv.delete(&entry.key).await;
+        }
+        let _ = db.kv.delete(&format!("config:skill_synced:{name}")).await;
+        let _ = db.fs.remove_tree(&resource_dir(name)).await;
+        let key = format!("{SKILL_PREFIX}{name}");
+        db.kv.delete(&key).await.is_ok()
+    }
+
+    /// Every stored version of a skill, oldest first. Empty if the skill
+    /// is not in the DB.
+    pub async fn history(db: &AgentFS, name: &str) -> Vec<SkillVersion> {
+        let Some(current) = Self::current_record(db, name).await else {
+            return Vec::new();
+        };
+        let entries = db.kv
+            .list_prefix(&format!("config:skill_history:{name}:"))
+            .await
+            .unwrap_or_default();
+        let mut versions: Vec<SkillVersion> = entries
+            .into_iter()
+            .filter_map(|entry| serde_json::from_str::<SkillRecord>(&entry.value).ok())
+            .map(SkillVersion::from)
+            .collect();
+        versions.push(SkillVersion {
+            current: true,
+            ..current.into()
+        });
+        versions.sort_by_key(|v| v.version);
+        versions
+    }
+
+    /// Make an earlier version of a skill current again. The rollback is
+    /// saved as a new version, so it can itself be undone; returns it.
+    pub async fn rollback(db: &AgentFS, name: &str, version: u32) -> crate::error::Result<u32> {
+        let versions = Self::history(db, name).await;
+        if versions.is_empty() {
+            return Err(crate::error::AgentError::Other(format!("no skill named {name}")));
+        }
+        let target = versions
+            .into_iter()
+            .find(|v| v.version == version)
+            .ok_or_else(|| crate::error::AgentError::Other(format!("skill {name} has no version {version}")))?;
+        if target.current {
+            return Err(crate::error::AgentError::Other(format!("skill {name} is already at version {version}")));
+        }
+
+        let skill = Skill {
+            name: name.to_string(),
+            description: target.description,
+            body: target.body,
+            allowed_tools: target.allowed_tools,
+            dir: PathBuf::new(),
+            scope: SkillScope::default(),
+        };
+        Ok(Self::save_to_db(db, &skill).await)
+    }
+
+    async fn current_record(db: &AgentFS, name: &str) -> Option<SkillRecord> {
+        let entry = db.kv.get(&format!("{SKILL_PREFIX}{name}")).await.ok()?;
+        serde_json::from_str(&entry.value).ok()
+    }
+
+    /// List all skills stored in the DB, as skills of `scope`.
+    pub async fn list_from_db(db: &AgentFS, scope: SkillScope) -> Vec<Skill> {
+        let entries = db.kv.list_prefix(SKILL_PREFIX).await.unwrap_or_default();
+        let mut skills: Vec<Skill> = entries
+            .into_iter()
+            .filter_map(|entry| {
+                let record: SkillRecord = serde_json::from_str(&entry.value).ok()?;
+                Some(record.into_skill(scope))
+            })
+            .collect();
+        skills.sort_by(|a, b| a.name.cmp(&b.name));
+        skills
+    }
+}
+
+// ── Filesystem sync ──────────────────────────────────────────────
+
+/// Keeps the DB registries in step with the skills directories: the global
+/// directory with the global DB, the project directory with the project DB.
+/// Each `poll` compares file modification times and sizes against the last
+/// scan and, when anything changed, re-imports that scope's skills.
+#[derive(Default)]
+pub struct SkillWatcher {
+    global: DirSync,
+    project: DirSync,
+}
+
+/// Sync state of one skills directory.
+#[derive(Default)]
+struct DirSync {
+    stamps: HashMap<PathBuf, (SystemTime, u64)>,
+    /// Skills found on disk at the last sync, so deleted files can be
+    /// dropped from the DB without touching skills added with `skills add`.
+    on_disk: HashSet<String>,
+}
+
+impl SkillWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sync the skills directories into their DBs if they changed since the
+    /// last poll. Returns whether the skills to load may have changed.
+    /// A scope without a DB is not synced anywhere; its skills are read
+    /// from disk by [`SkillRegistry::load_from_db`].
+    ///
+    /// A skill file is only imported when its content differs from what was
+    /// last imported (`config:skill_synced:<name>`), so a rolled-back skill
+    /// stays rolled back until its file is edited again.
+    pub async fn poll(&mut self, global: Option<&AgentFS>, project: Option<&AgentFS>) -> bool {
+        let global_changed = self.global.poll(SkillScope::Global, global).await;
+        let project_changed = self.project.poll(SkillScope::Project, project).await;
+        global_changed || project_changed
+    }
+}
+
+impl DirSync {
+    async fn poll(&mut self, scope: SkillScope, db: Option<&AgentFS>) -> bool {
+        let Some(dir) = scope.dir() else {
+            return false;
+        };
+        self.sync(&dir, scope, db).await
+    }
+
+    async fn sync(&mut self, dir: &Path, scope: SkillScope, db: Option<&AgentFS>) -> bool {
+        let stamps = scan_stamps(&[dir.to_path_buf()]);
+        if stamps == self.stamps {
+            return false;
+        }
+        self.stamps = stamps;
+        let Some(db) = db else {
+            return true;
+        };
+
+        let mut skills = HashMap::new();
+        load_skills_from_dir(dir, scope, &mut skills);
+        for skill in skills.values() {
+            import_resources(db, skill).await;
+            let synced_key = format!("config:skill_synced:{}", skill.name);
+            let hash = source_hash(skill);
+            if db.kv.get(&synced_key).await.is_ok_and(|e| e.value == hash) {
+                continue;
+            }
+            SkillRegistry::save_to_db(db, skill).await;
+            let _ = db.kv.set(&synced_key, &hash).await;
+        }
+        for name in self.on_disk.iter().filter(|n| !skills.contains_key(*n)) {
+            SkillRegistry::remove_from_db(db, name).await;
+        }
+        self.on_disk = skills.into_keys().collect();
+        true
+    }
+}
+
+fn source_hash(skill: &Skill) -> String {
+    crate::memory::compaction::content_hash(&format!(
+        "{}\n{}\n{}",
+        skill.description,
+        skill.allowed_tools.join(","),
+        skill.body
+    ))
+}
+
+// ── Bundled resources ────────────────────────────────────────────
+
+/// Deepest directory level below a skill's directory that is bundled.
+const MAX_RESOURCE_DEPTH: usize = 4;
+/// Most files bundled with one skill.
+const MAX_RESOURCE_FILES: usize = 200;
+/// Most bytes bundled with one skill; files past it are left out.
+const MAX_RESOURCE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// The directory whose files come along with skill `name` added from
+/// `file`: only a SKILL.md in a directory named after the skill, so adding
+/// one from a project root doesn't bundle the whole tree.
+pub fn bundle_dir(file: &Path, name: &str) -> Option<PathBuf> {
+    if file.file_name()? != "SKILL.md" {
+        return None;
+    }
+    let parent = file.parent()?;
+    (parent.file_name()? == name).then(|| parent.to_path_buf())
+}
+
+/// Copy the files next to a skill's SKILL.md (templates, checklists,
+/// example configs, ...) into its AgentFS resource directory, replacing
+/// what was there. Returns the number of files copied.
+pub async fn import_resources(db: &AgentFS, skill: &Skill) -> usize {
+    let files = if skill.dir.as_os_str().is_empty() {
+        Vec::new()
+    } else {
+        collect_resource_files(&skill.dir)
+    };
+    let dir = resource_dir(&skill.name);
+    if files.is_empty() && !db.fs.exists(&dir).await.unwrap_or(false) {
+        return 0;
+    }
+
+    let _ = db.fs.remove_tree(&dir).await;
+    let mut copied = 0;
+    for (relative, path, _) in files {
+        let Ok(data) = std::fs::read(&path) else {
+            continue;
+        };
+        if db.fs.write_file(&format!("{dir}/{relative}"), &data).await.is_ok() {
+            copied += 1;
+        }
+    }
+    copied
+}
+
+/// Files in skill directory `root` other than its SKILL.md, as
+/// (`/`-separated relative path, host path, metadata), in name order.
+/// Symlinks are skipped, since they may lead out of the directory or loop,
+/// and the walk stops at [`MAX_RESOURCE_DEPTH`], [`MAX_RESOURCE_FILES`]
+/// and [`MAX_RESOURCE_BYTES`].
+fn collect_resource_files(root: &Path) -> Vec<(String, PathBuf, std::fs::Metadata)> {
+    let mut files = Vec::new();
+    let mut total = 0;
+    walk_resources(root, root, 0, &mut total, &mut files);
+    files
+}
+
+fn walk_resources(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    total: &mut u64,
+    files: &mut Vec<(String, PathBuf, std::fs::Metadata)>,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if files.len() >= MAX_RESOURCE_FILES {
+            return;
+        }
+        let Ok(meta) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_dir() {
+            if depth < MAX_RESOURCE_DEPTH {
+                walk_resources(root, &path, depth + 1, total, files);
+            }
+        } else if meta.is_file() && path != root.join("SKILL.md") {
+            if *total + meta.len() > MAX_RESOURCE_BYTES {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let relative: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+            *total += meta.len();
+            files.push((relative.join("/"), path, meta));
+        }
+    }
+}
+
+/// Modification time and size of every file in a skill directory (one
+/// with a SKILL.md) under `dirs`, so edits to bundled resources count too.
+fn scan_stamps(dirs: &[PathBuf]) -> HashMap<PathBuf, (SystemTime, u64)> {
+    let mut stamps = HashMap::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let skill_dir = entry.path();
+            if !skill_dir.join("SKILL.md").is_file() {
+                continue;
+            }
+            let skill_md = skill_dir.join("SKILL.md");
+            let skill_md = std::fs::metadata(&skill_md).map(|meta| (skill_md, meta));
+            let files = collect_resource_files(&skill_dir).into_iter().map(|(_, path, meta)| (path, meta));
+            for (path, meta) in skill_md.into_iter().chain(files) {
+                let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                stamps.insert(path, (modified, meta.len()));
+            }
+        }
+    }
+    stamps
+}
+
+/// Scan a directory for `*/SKILL.md` files and insert them into the map.
+fn load_skills_from_dir(dir: &Path, scope: SkillScope, skills: &mut HashMap<String, Skill>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let skill_file = entry.path().join("SKILL.md");
+        if skill_file.is_file() {
+            if let Some(skill) = parse_skill_md(&skill_file) {
+                skills.insert(skill.name.clone(), Skill { scope, ..skill });
+            }
+        }
+    }
+}
+
+/// Parse a SKILL.md file with frontmatter.
+///
+/// Format:
+/// ```markdown
+/// ---
+/// name: my-skill
+/// description: Does something useful
+/// allowed-tools: read_file, grep
+/// ---
+/// Body content here...
+/// ```
+fn parse_skill_md(path: &Path) -> Option<Skill> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let (mut fields, body) = split_frontmatter(&content)?;
+
+    let name = fields.remove("name")?;
+    let description = fields.remove("description").unwrap_or_default();
+    let allowed_tools = allowed_tools(&mut fields);
+    let dir = path.parent()?.to_path_buf();
+
+    Some(Skill {
+        name,
+        description,
+        body,
+        allowed_tools,
+        dir,
+        scope: SkillScope::default(),
+    })
+}
+
+/// Take the `allowed-tools` (or `allowed_tools`) frontmatter field: a
+/// comma-separated list of tool name globs. Empty when absent.
+pub fn allowed_tools(fields: &mut HashMap<String, String>) -> Vec<String> {
+    fields
+        .remove("allowed-tools")
+        .or_else(|| fields.remove("allowed_tools"))
+        .map(|tools| {
+            tools
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Split a markdown file's `---`-delimited frontmatter into its
+/// `key: value` lines and the body. `None` without frontmatter.
+pub fn split_frontmatter(content: &str) -> Option<(HashMap<String, String>, String)> {
+    let trimmed = content.trim();
+
+    // Must start with ---
+    if !trimmed.starts_with("---") {
+        return None;
+    }
+
+    // Find the second ---
+    let after_first = &trimmed[3..].trim_start_matches(['\r', '\n']);
+    let end_idx = after_first.find("\n---")?;
+    let frontmatter = &after_first[..end_idx];
+    let body_start = end_idx + 4; // skip "\n---"
+    let body = if body_start < after_first.len() {
+        after_first[body_start..].trim().to_string()
+    } else {
+        String::new()
+    };
+
+    let fields = frontmatter
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Some((fields, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_skill_md() {
+        let dir = std::env::temp_dir().join("test_skill_parse");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let skill_path = dir.join("SKILL.md");
+        fs::write(
+            &skill_path,
+            "---\nname: test-skill\ndescription: A test skill\n---\nThis is the body.\n",
+        )
+        .unwrap();
+
+        let skill = parse_skill_md(&skill_path).unwrap();
+        assert_eq!(skill.name, "test-skill");
+        assert_eq!(skill.description, "A test skill");
+        assert_eq!(skill.body, "This is the body.");
+        assert!(skill.allowed_tools.is_empty());
+        assert_eq!(skill.dir, dir);
+
+        fs::write(
+            &skill_path,
+            "---\nname: summarize\nallowed-tools: read_file, grep, list_*\n---\nSummarize.\n",
+        )
+        .unwrap();
+        let skill = parse_skill_md(&skill_path).unwrap();
+        assert_eq!(skill.allowed_tools, vec!["read_file", "grep", "list_*"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_skill_md_no_frontmatter() {
+        let dir = std::env::temp_dir().join("test_skill_nofm");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let skill_path = dir.join("SKILL.md");
+        fs::write(&skill_path, "No frontmatter here").unwrap();
+
+        assert!(parse_skill_md(&skill_path).is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_scan_stamps_detects_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        fs::create_dir_all(dir.join("review")).unwrap();
+        let dirs = vec![dir.clone()];
+
+        assert!(scan_stamps(&dirs).is_empty());
+
+        fs::write(dir.join("review").join("SKILL.md"), "---\nname: review\n---\nReview.\n").unwrap();
+        let first = scan_stamps(&dirs);
+        assert_eq!(first.len(), 1);
+        assert_eq!(scan_stamps(&dirs), first);
+
+        fs::write(dir.join("review").join("SKILL.md"), "---\nname: review\n---\nReview carefully.\n").unwrap();
+        assert_ne!(scan_stamps(&dirs), first);
+
+        fs::remove_dir_all(dir.join("review")).unwrap();
+        assert!(scan_stamps(&dirs).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_project_sync_stays_in_project_db() {
+        let tmp = tempfile::tempdir().unwrap();
+        let skills_dir = tmp.path().join("skills");
+        fs::create_dir_all(skills_dir.join("review")).unwrap();
+        fs::write(skills_dir.join("review").join("SKILL.md"), "---\nname: review\n---\nReview.\n").unwrap();
+        let open = |name: &str| {
+            AgentFS::create(
+                agentfs_core::config::AgentFSConfig::builder(tmp.path().join(name))
+                    .checkpoint_interval_secs(0)
+                    .build(),
+            )
+        };
+        let global = open("global.db").await.unwrap();
+        let project = open("project.db").await.unwrap();
+
+        let mut sync = DirSync::default();
+        assert!(sync.sync(&skills_dir, SkillScope::Project, None).await);
+        assert!(SkillRegistry::list_from_db(&global, SkillScope::Project).await.is_empty());
+
+        let mut sync = DirSync::default();
+        assert!(sync.sync(&skills_dir, SkillScope::Project, Some(&project)).await);
+        assert_eq!(SkillRegistry::list_from_db(&project, SkillScope::Project).await.len(), 1);
+        assert!(global.kv.list_prefix("config:skill").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_history_and_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            agentfs_core::config::AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        let mut skill = Skill {
+            name: "review".to_string(),
+            description: "Code review".to_string(),
+            body: "Review the diff.".to_string(),
+            allowed_tools: Vec::new(),
+            dir: PathBuf::new(),
+            scope: SkillScope::Global,
+        };
+
+        assert_eq!(SkillRegistry::save_to_db(&db, &skill).await, 1);
+        assert_eq!(SkillRegistry::save_to_db(&db, &skill).await, 1);
+        skill.body = "Approve everything.".to_string();
+        assert_eq!(SkillRegistry::save_to_db(&db, &skill).await, 2);
+
+        let history = SkillRegistry::history(&db, "review").await;
+        assert_eq!(history.iter().map(|v| v.version).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(history[1].current);
+        assert_eq!(history[0].body, "Review the diff.");
+
+        assert!(SkillRegistry::rollback(&db, "review", 2).await.is_err());
+        assert!(SkillRegistry::rollback(&db, "review", 9).await.is_err());
+        assert_eq!(SkillRegistry::rollback(&db, "review", 1).await.unwrap(), 3);
+        let skills = SkillRegistry::list_from_db(&db, SkillScope::Global).await;
+        assert_eq!(skills[0].body, "Review the diff.");
+        assert_eq!(SkillRegistry::history(&db, "review").await.len(), 3);
+
+        assert!(SkillRegistry::remove_from_db(&db, "review").await);
+        assert!(SkillRegistry::history(&db, "review").await.is_empty());
+        assert!(db.kv.list_prefix("config:skill_history:").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_resources() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            agentfs_core::config::AgentFSConfig::builder(tmp.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        let dir = tmp.path().join("release");
+        fs::create_dir_all(dir.join("templates")).unwrap();
+        fs::write(dir.join("SKILL.md"), "---\nname: release\n---\nFollow checklist.md.\n").unwrap();
+        fs::write(dir.join("checklist.md"), "- [ ] bump version\n").unwrap();
+        fs::write(dir.join("templates").join("notes.md"), "## Changes\n").unwrap();
+
+        let skill = parse_skill_md(&dir.join("SKILL.md")).unwrap();
+        assert_eq!(import_resources(&db, &skill).await, 2);
+        assert_eq!(
+            resources(&db, "release").await,
+            vec!["/skills/release/checklist.md", "/skills/release/templates/notes.md"]
+        );
+        assert_eq!(db.fs.read_file("/skills/release/checklist.md").await.unwrap(), b"- [ ] bump version\n");
+
+        fs::remove_dir_all(dir.join("templates")).unwrap();
+        assert_eq!(import_resources(&db, &skill).await, 1);
+        assert_eq!(resources(&db, "release").await, vec!["/skills/release/checklist.md"]);
+
+        assert!(resources(&db, "missing").await.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_resource_files_bounds() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("release");
+        fs::create_dir_all(dir.join("a/b/c/d/e")).unwrap();
+        fs::write(dir.join("SKILL.md"), "---\nname: release\n---\n").unwrap();
+        fs::write(dir.join("a/b/c/d/deep.md"), "ok").unwrap();
+        fs::write(dir.join("a/b/c/d/e/deeper.md"), "too deep").unwrap();
+        fs::write(dir.join("big.bin"), vec![0u8; MAX_RESOURCE_BYTES as usize + 1]).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", dir.join("host")).unwrap();
+
+        let files: Vec<_> = collect_resource_files(&dir).into_iter().map(|(rel, _, _)| rel).collect();
+        assert_eq!(files, vec!["a/b/c/d/deep.md"]);
+
+        assert_eq!(bundle_dir(&dir.join("SKILL.md"), "release"), Some(dir.clone()));
+        assert_eq!(bundle_dir(&dir.join("SKILL.md"), "other"), None);
+        assert_eq!(bundle_dir(Path::new("SKILL.md"), "release"), None);
+        assert_eq!(bundle_dir(&dir.join("notes.md"), "release"), None);
+    }
+
+    #[tokio::test]
+    async fn test_project_skills_shadow_global() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut dbs = Vec::new();
+        for name in ["global.db", "project.db"] {
+            dbs.push(
+                AgentFS::create(
+                    agentfs_core::config::AgentFSConfig::builder(tmp.path().join(name))
+                        .checkpoint_interval_secs(0)
+                        .build(),
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        let skill = |name: &str, body: &str| Skill {
+            name: name.to_string(),
+            description: String::new(),
+            body: body.to_string(),
+            allowed_tools: Vec::new(),
+            dir: PathBuf::new(),
+            scope: SkillScope::default(),
+        };
+        SkillRegistry::save_to_db(&dbs[0], &skill("review", "Global review")).await;
+        SkillRegistry::save_to_db(&dbs[0], &skill("deploy", "Deploy")).await;
+        SkillRegistry::save_to_db(&dbs[1], &skill("review", "Project review")).await;
+
+        let registry = SkillRegistry::load_from_db(Some(&dbs[0]), Some(&dbs[1])).await;
+        let skills = registry.skills();
+        assert_eq!(skills.len(), 2);
+        assert_eq!((skills[0].name.as_str(), skills[0].scope), ("deploy", SkillScope::Global));
+        assert_eq!((skills[1].body.as_str(), skills[1].scope), ("Project review", SkillScope::Project));
+        assert!(registry.unbundled().is_empty());
+    }
+
+    #[test]
+    fn test_matches_command() {
+        let mut skills = HashMap::new();
+        skills.insert(
+            "review".to_string(),
+            Skill {
+                name: "review".to_string(),
+                description: "Code review".to_string(),
+                body: "Review body".to_string(),
+                allowed_tools: Vec::new(),
+                dir: PathBuf::from("/tmp"),
+                scope: SkillScope::Global,
+            },
+        );
+
+        let registry = SkillRegistry {
+            skills,
+            unbundled: Vec::new(),
+        };
+
+        let (skill, args) = registry.matches_command("/review PR #123").unwrap();
+        assert_eq!(skill.name, "review");
+        assert_eq!(args, "PR #123");
+
+        let (skill, args) = registry.matches_command("/review").unwrap();
+        assert_eq!(skill.name, "review");
+        assert_eq!(args, "");
+
+        assert!(registry.matches_command("not a command").is_none());
+        assert!(registry.matches_command("/unknown").is_none());
+    }
+}
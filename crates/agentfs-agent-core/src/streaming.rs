@@ -31,6 +31,9 @@ pub enum StreamEvent {
     Ping,
     Error {
         message: String,
+        /// Overload, rate limit, server error or dropped connection: the
+        /// request can be sent again.
+        retryable: bool,
     },
 }
 
@@ -139,13 +142,17 @@ pub fn parse_sse_event(raw: &str) -> Option<StreamEvent> {
         "ping" => Some(StreamEvent::Ping),
         "error" => {
             let v: Value = serde_json::from_str(&data).ok()?;
-            let message = v
-                .get("error")
+            let error = v.get("error");
+            let message = error
                 .and_then(|e| e.get("message"))
                 .and_then(|m| m.as_str())
                 .unwrap_or("Unknown error")
                 .to_string();
-            Some(StreamEvent::Error { message })
+            let retryable = matches!(
+                error.and_then(|e| e.get("type")).and_then(|t| t.as_str()),
+                Some("overloaded_error" | "rate_limit_error" | "api_error" | "timeout_error")
+            );
+            Some(StreamEvent::Error { message, retryable })
         }
         _ => None,
     }
@@ -171,6 +178,12 @@ pub struct ToolUseBlock {
     pub input: Value,
 }
 
+impl Default for ContentAccumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ContentAccumulator {
     pub fn new() -> Self {
         Self {
@@ -0,0 +1,310 @@
+use serde_json::{json, Value};
+
+/// Merge built-in tools with extra tool definitions (e.g. from MCP servers).
+pub fn merge_tools(builtin: Vec<Value>, extra: Vec<Value>) -> Vec<Value> {
+    let mut all = builtin;
+    all.extend(extra);
+    all
+}
+
+/// Name of the tool that delegates work to a subagent.
+pub const TASK_TOOL: &str = "task";
+
+/// Built-in tools a subagent may use: read-only workspace and web access.
+/// No shell, no writes, no MCP servers and no further subagents.
+pub const TASK_TOOLS: &[&str] = &[
+    "read_file",
+    "list_dir",
+    "search",
+    "glob",
+    "grep",
+    "tree",
+    "kv_get",
+    "fetch_url",
+    "web_search",
+];
+
+/// Return all tool definitions for Claude.
+pub fn tool_definitions() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "read_file",
+            "description": "Read a file from the agent workspace filesystem. Returns the file contents as a string.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path to the file (e.g., /src/main.rs)"
+                    }
+                },
+                "required": ["path"]
+            }
+        }),
+        json!({
+            "name": "write_file",
+            "description": "Write or create a file in the agent workspace filesystem. Creates parent directories automatically.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path to the file (e.g., /src/main.rs)"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The content to write to the file"
+                    }
+                },
+                "required": ["path", "content"]
+            }
+        }),
+        json!({
+            "name": "multi_edit",
+            "description": "Apply an ordered list of exact search-and-replace edits to one or more files in the agent workspace, atomically: if any edit fails to match, no file is changed. Each edit sees the result of the previous ones. Read files first so old_string matches exactly, including whitespace.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "edits": {
+                        "type": "array",
+                        "description": "Edits to apply, in order",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "path": {
+                                    "type": "string",
+                                    "description": "Absolute path to the file (e.g., /src/main.rs)"
+                                },
+                                "old_string": {
+                                    "type": "string",
+                                    "description": "Exact text to replace; must be unique in the file unless replace_all is set"
+                                },
+                                "new_string": {
+                                    "type": "string",
+                                    "description": "Replacement text"
+                                },
+                                "replace_all": {
+                                    "type": "boolean",
+                                    "description": "Replace every occurrence (default: false)",
+                                    "default": false
+                                }
+                            },
+                            "required": ["path", "old_string", "new_string"]
+                        },
+                        "minItems": 1
+                    }
+                },
+                "required": ["edits"]
+            }
+        }),
+        json!({
+            "name": "list_dir",
+            "description": "List the contents of a directory in the agent workspace filesystem.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Absolute path to the directory (default: /)",
+                        "default": "/"
+                    }
+                },
+                "required": []
+            }
+        }),
+        json!({
+            "name": "search",
+            "description": "Search for files by name pattern in the agent workspace. Supports * (any chars) and ? (single char) wildcards.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Glob pattern to match file names (e.g., *.rs, test_*)"
+                    }
+                },
+                "required": ["pattern"]
+            }
+        }),
+        json!({
+            "name": "glob",
+            "description": "Find files in the agent workspace whose path matches a glob pattern, most recently modified first. * and ? match within one directory, ** spans directories, and a pattern without a leading / matches at any depth (e.g. src/**/*.test.ts, *.md). Use it to discover files before reading them.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Glob pattern to match file paths against"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Directory the pattern is relative to (default: whole workspace)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of paths to return (default: 100, max: 1000)",
+                        "default": 100
+                    }
+                },
+                "required": ["pattern"]
+            }
+        }),
+        json!({
+            "name": "grep",
+            "description": "Search file contents in the agent workspace with a regular expression. Returns matching lines with line numbers, grouped by file. Use this instead of bash grep, which cannot see the workspace filesystem. Binary files are skipped.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "pattern": {
+                        "type": "string",
+                        "description": "Regular expression to search for (Rust regex syntax, e.g. fn\\s+main, TODO|FIXME)"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Only search files whose path matches this glob (e.g. *.rs, /src/**/*.py). * and ? stay within one directory, ** spans directories"
+                    },
+                    "ignore_case": {
+                        "type": "boolean",
+                        "description": "Case-insensitive match (default: false)",
+                        "default": false
+                    },
+                    "context": {
+                        "type": "integer",
+                        "description": "Lines of context to show before and after each match (default: 0, max: 10)",
+                        "default": 0
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Maximum number of matching lines to return (default: 50, max: 500)",
+                        "default": 50
+                    }
+                },
+                "required": ["pattern"]
+            }
+        }),
+        json!({
+            "name": "tree",
+            "description": "Show a recursive directory tree of the agent workspace filesystem.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Root path for the tree (default: /)",
+                        "default": "/"
+                    }
+                },
+                "required": []
+            }
+        }),
+        json!({
+            "name": "bash",
+            "description": "Execute a shell command on the host system. Commands run in one persistent bash session, so the working directory, exported variables and activated virtualenvs carry over between calls. stdout and stderr are combined; stdin is empty, so don't run interactive programs. Commands are interrupted after `timeout` seconds (default 30). Use for running code, tests, git operations, or any system command.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The shell command to execute"
+                    },
+                    "timeout": {
+                        "type": "integer",
+                        "description": "Seconds before the command is interrupted (default 30, max 600)"
+                    }
+                },
+                "required": ["command"]
+            }
+        }),
+        json!({
+            "name": "restart_shell",
+            "description": "Kill the persistent bash session and everything running in it. The next bash call starts a fresh shell in the original directory with the original environment. Use when the shell is stuck or its state is broken.",
+            "input_schema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }),
+        json!({
+            "name": "fetch_url",
+            "description": "Fetch a web page or text document over HTTP(S) with GET. HTML is converted to markdown (headings, lists, links, code blocks); scripts, styles and markup are dropped. Use it to read documentation and API references. Binary content is rejected and long pages are truncated.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "Absolute http:// or https:// URL"
+                    }
+                },
+                "required": ["url"]
+            }
+        }),
+        json!({
+            "name": "web_search",
+            "description": "Search the web and get back titles, URLs and snippets. Use it to find documentation, error explanations or current information, then read promising pages with fetch_url.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Search query"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Number of results (default: 5, max: 20)"
+                    }
+                },
+                "required": ["query"]
+            }
+        }),
+        json!({
+            "name": "task",
+            "description": "Delegate a self-contained research or exploration task to a subagent. It starts with a fresh context, can read the workspace and the web (read_file, list_dir, search, glob, grep, tree, kv_get, fetch_url, web_search) but cannot write files or run commands, and returns only its final report. Use it for broad searches or reading many files, to keep that work out of this conversation. The subagent sees nothing of this conversation, so put everything it needs in the prompt.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "description": {
+                        "type": "string",
+                        "description": "Short (3-5 word) label for the task"
+                    },
+                    "prompt": {
+                        "type": "string",
+                        "description": "Full instructions for the subagent, including what to report back"
+                    }
+                },
+                "required": ["description", "prompt"]
+            }
+        }),
+        json!({
+            "name": "kv_get",
+            "description": "Read a value from the persistent key-value store.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "The key to look up"
+                    }
+                },
+                "required": ["key"]
+            }
+        }),
+        json!({
+            "name": "kv_set",
+            "description": "Write a value to the persistent key-value store. Creates or updates the key.",
+            "input_schema": {
+                "type": "object",
+                "properties": {
+                    "key": {
+                        "type": "string",
+                        "description": "The key to set"
+                    },
+                    "value": {
+                        "type": "string",
+                        "description": "The value to store"
+                    }
+                },
+                "required": ["key", "value"]
+            }
+        }),
+    ]
+}
@@ -0,0 +1,760 @@
+//! Built-in web access for the agent: `fetch_url` and `web_search`.
+//!
+//! Configured by `~/.infinity/web.json`; every field is optional:
+//!
+//! ```json
+//! { "fetch": { "allowed_domains": ["docs.rs", "python.org"], "max_bytes": 5242880,
+//!              "max_chars": 100000, "timeout_secs": 30 },
+//!   "search": { "provider": "brave", "api_key": "...", "max_results": 5 } }
+//! ```
+//!
+//! An empty `allowed_domains` allows any host. A listed domain also allows
+//! its subdomains, and redirects are checked against the list too.
+//! Whatever the list, `fetch_url` never connects to a loopback, private,
+//! link-local or otherwise non-public address: hosts are checked after DNS
+//! resolution, on every redirect hop. An invalid web.json is an error.
+//! `search.provider` is one of `brave`, `tavily` (both need an API key) or
+//! `searxng` (needs `search.url`); see [`search`].
+
+pub mod search;
+
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::error::{AgentError, Result};
+
+use self::search::{backend_from_config, render_hits, SearchBackend, SearchConfig, MAX_RESULTS};
+
+/// Redirects followed before giving up.
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebConfig {
+    #[serde(default)]
+    pub fetch: FetchConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FetchConfig {
+    /// Hosts the agent may fetch from (and their subdomains). Empty = any.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Largest response body downloaded, in bytes.
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+    /// Largest text returned to the model, in characters.
+    #[serde(default = "default_max_chars")]
+    pub max_chars: usize,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            allowed_domains: Vec::new(),
+            max_bytes: default_max_bytes(),
+            max_chars: default_max_chars(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+fn default_max_bytes() -> usize { 5 * 1024 * 1024 }
+fn default_max_chars() -> usize { 100_000 }
+fn default_timeout_secs() -> u64 { 30 }
+
+/// Load web config from ~/.infinity/web.json (defaults if missing). An
+/// invalid file is an error, not an empty allowlist.
+pub fn load_web_config() -> Result<WebConfig> {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".infinity");
+    path.push("web.json");
+    match std::fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| AgentError::Config(format!("Invalid {}: {e}", path.display()))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(WebConfig::default()),
+        Err(e) => Err(AgentError::Config(format!("Cannot read {}: {e}", path.display()))),
+    }
+}
+
+/// HTTP client for the web tools.
+pub struct WebClient {
+    /// Client for `fetch_url`: public addresses only.
+    http: reqwest::Client,
+    /// Client for the configured search backend, which may be a local
+    /// SearXNG.
+    search_http: reqwest::Client,
+    config: WebConfig,
+    /// Configured search backend, or why there isn't one.
+    search: std::result::Result<Box<dyn SearchBackend>, String>,
+}
+
+impl WebClient {
+    pub fn new(config: WebConfig) -> Self {
+        let allowed = Arc::new(config.fetch.allowed_domains.clone());
+        let policy = Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if let Err(e) = check_url(&allowed, attempt.url()) {
+                let msg = format!("redirect refused: {e}");
+                attempt.error(msg)
+            } else {
+                attempt.follow()
+            }
+        });
+        let timeout = Duration::from_secs(config.fetch.timeout_secs.max(1));
+        let user_agent = concat!("infinity-agent/", env!("CARGO_PKG_VERSION"));
+        let http = reqwest::Client::builder()
+            .user_agent(user_agent)
+            .timeout(timeout)
+            .redirect(policy)
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .unwrap_or_default();
+        let search_http = reqwest::Client::builder()
+            .user_agent(user_agent)
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        let search = backend_from_config(&config.search).map_err(|e| e.to_string());
+        Self { http, search_http, config, search }
+    }
+
+    /// Search the web with the configured provider; `count` defaults to
+    /// `search.max_results`.
+    pub async fn search(&self, query: &str, count: Option<usize>) -> Result<String> {
+        let backend = self.search.as_ref().map_err(|e| AgentError::Tool(e.clone()))?;
+        let count = count
+            .unwrap_or(self.config.search.max_results)
+            .clamp(1, MAX_RESULTS);
+        let hits = backend.search(&self.search_http, query, count).await?;
+        Ok(render_hits(backend.name(), query, &hits))
+    }
+
+    /// GET `url` and return its body as text, HTML converted to markdown.
+    pub async fn fetch(&self, url: &str) -> Result<String> {
+        let fetch = &self.config.fetch;
+        let url = Url::parse(url).map_err(|e| AgentError::Tool(format!("fetch_url: invalid URL {url}: {e}")))?;
+        check_url(&fetch.allowed_domains, &url).map_err(|e| AgentError::Tool(format!("fetch_url: {e}")))?;
+
+        let mut resp = self.http.get(url).send().await.map_err(|e| {
+            // The resolver's refusal is buried in the error's sources.
+            let mut reason = e.to_string();
+            let mut source = std::error::Error::source(&e);
+            while let Some(inner) = source {
+                reason = inner.to_string();
+                source = inner.source();
+            }
+            AgentError::Tool(format!("fetch_url: {reason}"))
+        })?;
+        let status = resp.status();
+        let final_url = resp.url().clone();
+        if !status.is_success() {
+            return Err(AgentError::Tool(format!("fetch_url: HTTP {status} from {final_url}")));
+        }
+        let content_type = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_string();
+        let html = mime.is_empty() || mime == "text/html" || mime == "application/xhtml+xml";
+        let textual = html
+            || mime.starts_with("text/")
+            || mime.ends_with("json")
+            || mime.ends_with("xml")
+            || mime.ends_with("javascript");
+        if !textual {
+            return Err(AgentError::Tool(format!(
+                "fetch_url: {final_url} is {mime}, not text"
+            )));
+        }
+
+        let mut body = Vec::new();
+        let mut cut = false;
+        while let Some(chunk) = resp.chunk().await? {
+            let room = fetch.max_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                cut = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let raw = String::from_utf8_lossy(&body);
+        let (title, text) = if html {
+            html_to_markdown(&raw, &final_url)
+        } else {
+            (None, raw.into_owned())
+        };
+
+        let mut output = format!("URL: {final_url}\nContent-Type: {mime}\n");
+        if let Some(title) = title {
+            output.push_str(&format!("Title: {title}\n"));
+        }
+        output.push('\n');
+        match text.char_indices().nth(fetch.max_chars) {
+            Some((end, _)) => {
+                output.push_str(&text[..end]);
+                output.push_str(&format!("\n\n[truncated at {} characters]\n", fetch.max_chars));
+            }
+            None => {
+                output.push_str(&text);
+                if cut {
+                    output.push_str(&format!("\n\n[download stopped at {} bytes]\n", fetch.max_bytes));
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Why `fetch_url` must not request `url`, if it mustn't: not http(s), a
+/// host outside `allowed`, or a non-public IP address. Hosts given by name
+/// are checked once resolved, by [`PublicResolver`].
+fn check_url(allowed: &[String], url: &Url) -> std::result::Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("unsupported scheme '{}' (use http or https)", url.scheme()));
+    }
+    if !host_allowed(allowed, url) {
+        return Err(format!(
+            "{} is not in allowed_domains ({})",
+            url.host_str().unwrap_or_default(),
+            allowed.join(", ")
+        ));
+    }
+    let host = url.host_str().unwrap_or_default();
+    let literal = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>();
+    match literal {
+        Ok(ip) if !is_public(ip) => Err(format!("{ip} is not a public address")),
+        _ => Ok(()),
+    }
+}
+
+/// Resolves hosts for `fetch_url`, failing for any host with a non-public
+/// address, so neither a URL nor a redirect can reach the local machine,
+/// the LAN or a cloud metadata endpoint. Checking what the connection will
+/// actually use also defeats DNS rebinding.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+                return Err(format!("{host} resolves to {}, which is not a public address", addr.ip()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `ip` is a globally routable address: not loopback, private,
+/// link-local (cloud metadata lives at 169.254.169.254), shared, unique
+/// local, multicast, reserved or unspecified.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (b == 18 || b == 19))
+                || a >= 240)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let segments = v6.segments();
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                // NAT64: the IPv4 address is in the last 32 bits.
+                let [.., hi, lo] = segments;
+                return is_public(IpAddr::V4(std::net::Ipv4Addr::from((u32::from(hi) << 16) | u32::from(lo))));
+            }
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Whether `url`'s host is `allowed` or a subdomain of an allowed domain.
+fn host_allowed(allowed: &[String], url: &Url) -> bool {
+    if allowed.is_empty() {
+        return true;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_ascii_lowercase();
+    allowed.iter().any(|domain| {
+        let domain = domain.trim_start_matches("*.").to_ascii_lowercase();
+        host == domain || host.strip_suffix(&domain).is_some_and(|sub| sub.ends_with('.'))
+    })
+}
+
+// ── HTML → markdown ──────────────────────────────────────────────────
+
+/// Elements whose content is never shown.
+const SKIPPED: &[&str] = &["script", "style", "noscript", "template", "svg", "iframe", "head"];
+
+/// Elements that start and end a paragraph.
+const BLOCKS: &[&str] = &[
+    "p", "div", "section", "article", "main", "header", "footer", "nav", "aside", "blockquote",
+    "table", "ul", "ol", "dl", "form", "figure", "details", "summary", "dd", "dt",
+];
+
+/// Convert an HTML page to markdown-ish text: headings, lists, links,
+/// emphasis and code blocks survive, scripts, styles and markup don't.
+/// Links are resolved against `base`. Returns the `<title>` separately.
+pub fn html_to_markdown(html: &str, base: &Url) -> (Option<String>, String) {
+    let mut md = Markdown {
+        out: String::new(),
+        base,
+        pre: 0,
+        lists: 0,
+        link: None,
+    };
+    let mut title = None;
+    let mut rest = html;
+
+    while let Some(lt) = rest.find('<') {
+        md.text(&rest[..lt]);
+        rest = &rest[lt..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(gt) = tag_end(rest) else {
+            md.text(rest);
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/').trim_end_matches('/');
+        let name_end = tag.find(|c: char| c.is_ascii_whitespace()).unwrap_or(tag.len());
+        let name = tag[..name_end].to_ascii_lowercase();
+        let attrs = &tag[name_end..];
+
+        if !closing && (name == "title" || SKIPPED.contains(&name.as_str())) {
+            let (inner, after) = skip_element(rest, &name);
+            if name == "title" {
+                let text = collapse_whitespace(&decode_entities(inner));
+                title = Some(text).filter(|t| !t.is_empty());
+            } else if name == "head" {
+                // The title lives in <head>; look for it before dropping the rest.
+                if let Some(start) = find_ci(inner, "<title") {
+                    let inner = &inner[start..];
+                    if let Some(gt) = inner.find('>') {
+                        let (text, _) = skip_element(&inner[gt + 1..], "title");
+                        let text = collapse_whitespace(&decode_entities(text));
+                        title = Some(text).filter(|t| !t.is_empty());
+                    }
+                }
+            }
+            rest = after;
+            continue;
+        }
+        md.tag(&name, attrs, closing);
+    }
+    md.text(rest);
+
+    (title, tidy(&md.out))
+}
+
+struct Markdown<'a> {
+    out: String,
+    base: &'a Url,
+    /// Nesting depth of `<pre>`; whitespace is kept inside.
+    pre: usize,
+    /// Nesting depth of lists, for indentation.
+    lists: usize,
+    /// Open link: resolved target and where its text starts in `out`.
+    link: Option<(Option<String>, usize)>,
+}
+
+impl Markdown<'_> {
+    fn text(&mut self, raw: &str) {
+        if raw.is_empty() {
+            return;
+        }
+        let text = decode_entities(raw);
+        if self.pre > 0 {
+            // A newline right after <pre> is not content.
+            let text = match self.out.ends_with("```\n") {
+                true => text.strip_prefix('\n').unwrap_or(&text),
+                false => &text,
+            };
+            self.out.push_str(text);
+            return;
+        }
+        let words = collapse_whitespace(&text);
+        let space = |c: char| c.is_ascii_whitespace();
+        if (text.starts_with(space) || words.is_empty()) && !self.out.ends_with(['\n', ' ']) && !self.out.is_empty() {
+            self.out.push(' ');
+        }
+        if !words.is_empty() {
+            self.out.push_str(&words);
+            if text.ends_with(space) {
+                self.out.push(' ');
+            }
+        }
+    }
+
+    fn tag(&mut self, name: &str, attrs: &str, closing: bool) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.block();
+                if !closing {
+                    let level = usize::from(name.as_bytes()[1] - b'0');
+                    self.out.push_str(&"#".repeat(level));
+                    self.out.push(' ');
+                }
+            }
+            "br" => self.out.push('\n'),
+            "hr" => {
+                self.block();
+                self.out.push_str("---");
+                self.block();
+            }
+            "li" if !closing => {
+                self.line();
+                self.out.push_str(&"  ".repeat(self.lists.saturating_sub(1)));
+                self.out.push_str("- ");
+            }
+            "ul" | "ol" if closing => {
+                self.lists = self.lists.saturating_sub(1);
+                if self.lists == 0 {
+                    self.block();
+                }
+            }
+            "ul" | "ol" => {
+                self.lists += 1;
+                if self.lists == 1 {
+                    self.block();
+                } else {
+                    self.line();
+                }
+            }
+            "tr" => self.line(),
+            "td" | "th" if !closing && !self.out.is_empty() && !self.out.ends_with('\n') => {
+                self.out.push_str(" | ");
+            }
+            "pre" => {
+                if closing {
+                    self.pre = self.pre.saturating_sub(1);
+                    self.line();
+                    self.out.push_str("```");
+                    self.block();
+                } else {
+                    self.block();
+                    self.out.push_str("```\n");
+                    self.pre += 1;
+                }
+            }
+            "code" if self.pre == 0 => self.out.push('`'),
+            "strong" | "b" => self.out.push_str("**"),
+            "em" | "i" => self.out.push('*'),
+            "a" => self.link(attrs, closing),
+            _ if BLOCKS.contains(&name) => self.block(),
+            _ => {}
+        }
+    }
+
+    fn link(&mut self, attrs: &str, closing: bool) {
+        if !closing {
+            let target = attr(attrs, "href")
+                .filter(|h| !h.starts_with('#') && !h.starts_with("javascript:"))
+                .and_then(|h| self.base.join(&decode_entities(&h)).ok())
+                .map(|u| u.to_string());
+            self.link = Some((target, self.out.len()));
+            return;
+        }
+        let Some((Some(target), start)) = self.link.take() else {
+            return;
+        };
+        if start > self.out.len() {
+            return;
+        }
+        let text = self.out[start..].trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        self.out.truncate(start);
+        self.out.push_str(&format!("[{text}]({target})"));
+    }
+
+    /// End the current line.
+    fn line(&mut self) {
+        let len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(len);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    /// End the current paragraph.
+    fn block(&mut self) {
+        self.trim_end();
+        if !self.out.is_empty() {
+            self.out.push_str("\n\n");
+        }
+    }
+
+    fn trim_end(&mut self) {
+        let len = self.out.trim_end().len();
+        self.out.truncate(len);
+    }
+}
+
+/// Index of the `>` closing the tag at the start of `s`, skipping quoted values.
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split `s` at the closing tag of `name`: (content, text after it).
+fn skip_element<'a>(s: &'a str, name: &str) -> (&'a str, &'a str) {
+    match find_ci(s, &format!("</{name}")) {
+        Some(end) => {
+            let after = &s[end..];
+            let after = after.find('>').map_or("", |gt| &after[gt + 1..]);
+            (&s[..end], after)
+        }
+        None => (s, ""),
+    }
+}
+
+/// Case-insensitive ASCII find.
+fn find_ci(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Value of attribute `name` in a tag's attribute text.
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs.trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        rest = rest[key_end..].trim_start();
+        let mut value = "";
+        if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            match after_eq.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let end = after_eq[1..].find(q).map_or(after_eq.len(), |e| e + 1);
+                    value = &after_eq[1..end];
+                    rest = after_eq.get(end + 1..).unwrap_or("");
+                }
+                _ => {
+                    let end = after_eq
+                        .find(|c: char| c.is_ascii_whitespace())
+                        .unwrap_or(after_eq.len());
+                    value = &after_eq[..end];
+                    rest = &after_eq[end..];
+                }
+            }
+        }
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value.to_string());
+        }
+        rest = rest.trim_start();
+    }
+    None
+}
+
+/// Decode the common named entities and numeric character references.
+pub(crate) fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..].find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end + 1];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "mdash" => Some('—'),
+                "ndash" => Some('–'),
+                "hellip" => Some('…'),
+                "copy" => Some('©'),
+                _ => {
+                    let code = if let Some(hex) = entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else {
+                        entity.strip_prefix('#').and_then(|d| d.parse().ok())
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            c.map(|c| (c, end + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Strip trailing spaces and squeeze runs of blank lines.
+fn tidy(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut blank = 0;
+    for line in s.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank += 1;
+            if blank > 1 {
+                continue;
+            }
+        } else {
+            blank = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_markdown() {
+        let base = Url::parse("https://example.com/docs/index.html").unwrap();
+        let html = r#"<!DOCTYPE html><html><head><title>The  Guide</title>
+            <style>body { color: red }</style></head>
+            <body><nav><a href="/">Home</a></nav>
+            <h1>Intro &amp; setup</h1>
+            <p>Run <code>cargo build</code>, then see <a href="api.html">the API</a>.</p>
+            <script>alert("x")</script>
+            <ul><li>one</li><li><b>two</b></li></ul>
+            <pre>fn main() {
+    println!("&lt;hi&gt;");
+}</pre></body></html>"#;
+
+        let (title, md) = html_to_markdown(html, &base);
+        assert_eq!(title.as_deref(), Some("The Guide"));
+        assert_eq!(
+            md,
+            "[Home](https://example.com/)\n\n\
+             # Intro & setup\n\n\
+             Run `cargo build`, then see [the API](https://example.com/docs/api.html).\n\n\
+             - one\n\
+             - **two**\n\n\
+             ```\nfn main() {\n    println!(\"<hi>\");\n}\n```"
+        );
+    }
+
+    #[test]
+    fn test_host_allowed() {
+        let allowed = vec!["docs.rs".to_string(), "*.python.org".to_string()];
+        let ok = |u: &str| host_allowed(&allowed, &Url::parse(u).unwrap());
+        assert!(ok("https://docs.rs/serde"));
+        assert!(ok("https://docs.python.org/3/"));
+        assert!(ok("https://python.org/"));
+        assert!(!ok("https://evildocs.rs/"));
+        assert!(!ok("https://example.com/"));
+        assert!(host_allowed(&[], &Url::parse("https://example.com/").unwrap()));
+    }
+
+    #[test]
+    fn test_check_url_blocks_non_public_addresses() {
+        let check = |u: &str| check_url(&[], &Url::parse(u).unwrap());
+        assert!(check("https://93.184.215.14/").is_ok());
+        assert!(check("https://[2606:4700::1111]/").is_ok());
+        assert!(check("https://example.com/").is_ok());
+        for url in [
+            "http://127.0.0.1:8080/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.5/",
+            "http://192.168.1.1/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[fd00::1]/",
+            "http://[64:ff9b::a9fe:a9fe]/",
+            "file:///etc/passwd",
+        ] {
+            assert!(check(url).is_err(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolver_refuses_local_names() {
+        let name: Name = "localhost".parse().unwrap();
+        let err = PublicResolver.resolve(name).await.err().unwrap();
+        assert!(err.to_string().contains("not a public address"));
+
+        let client = WebClient::new(WebConfig::default());
+        let err = client.fetch("http://localhost:9/").await.unwrap_err();
+        assert!(err.to_string().contains("not a public address"), "{err}");
+    }
+}
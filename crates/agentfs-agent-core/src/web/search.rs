@@ -0,0 +1,266 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::{AgentError, Result};
+
+use super::decode_entities;
+
+// ── Config ──────────────────────────────────────────────────────────
+
+/// Search API behind the `web_search` tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchProvider {
+    Brave,
+    Searxng,
+    Tavily,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchConfig {
+    /// `None` leaves `web_search` unconfigured.
+    #[serde(default)]
+    pub provider: Option<SearchProvider>,
+    /// API key for Brave or Tavily; falls back to `BRAVE_API_KEY` /
+    /// `TAVILY_API_KEY` from the environment.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Base URL of the SearXNG instance (its JSON format must be enabled).
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Results returned when the model doesn't ask for a number.
+    #[serde(default = "default_max_results")]
+    pub max_results: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            api_key: None,
+            url: None,
+            max_results: default_max_results(),
+        }
+    }
+}
+
+fn default_max_results() -> usize { 5 }
+
+/// Most results one call may return.
+pub const MAX_RESULTS: usize = 20;
+
+// ── Backends ────────────────────────────────────────────────────────
+
+/// One search hit.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// A web search API.
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Provider name shown in results (e.g. "brave").
+    fn name(&self) -> &str;
+
+    /// Run `query` and return up to `count` hits.
+    async fn search(&self, http: &reqwest::Client, query: &str, count: usize) -> Result<Vec<SearchHit>>;
+}
+
+/// Build the backend selected in `config`, or explain what's missing.
+pub fn backend_from_config(config: &SearchConfig) -> Result<Box<dyn SearchBackend>> {
+    let key = |env: &str| {
+        config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var(env).ok())
+            .filter(|k| !k.is_empty())
+            .ok_or_else(|| {
+                AgentError::Config(format!(
+                    "web_search: no API key; set search.api_key in ~/.infinity/web.json or {env}"
+                ))
+            })
+    };
+    match config.provider {
+        Some(SearchProvider::Brave) => Ok(Box::new(Brave { api_key: key("BRAVE_API_KEY")? })),
+        Some(SearchProvider::Tavily) => Ok(Box::new(Tavily { api_key: key("TAVILY_API_KEY")? })),
+        Some(SearchProvider::Searxng) => {
+            let url = config.url.clone().ok_or_else(|| {
+                AgentError::Config("web_search: searxng needs search.url in ~/.infinity/web.json".to_string())
+            })?;
+            Ok(Box::new(Searxng {
+                url: url.trim_end_matches('/').to_string(),
+            }))
+        }
+        None => Err(AgentError::Config(
+            "web_search: no search provider configured; set search.provider \
+             (brave, searxng or tavily) in ~/.infinity/web.json"
+                .to_string(),
+        )),
+    }
+}
+
+struct Brave {
+    api_key: String,
+}
+
+#[async_trait]
+impl SearchBackend for Brave {
+    fn name(&self) -> &str {
+        "brave"
+    }
+
+    async fn search(&self, http: &reqwest::Client, query: &str, count: usize) -> Result<Vec<SearchHit>> {
+        let resp = http
+            .get("https://api.search.brave.com/res/v1/web/search")
+            .query(&[("q", query), ("count", &count.to_string())])
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .send()
+            .await?;
+        let body = check(resp).await?;
+        Ok(hits(&body["web"]["results"], "description", count))
+    }
+}
+
+struct Searxng {
+    url: String,
+}
+
+#[async_trait]
+impl SearchBackend for Searxng {
+    fn name(&self) -> &str {
+        "searxng"
+    }
+
+    async fn search(&self, http: &reqwest::Client, query: &str, count: usize) -> Result<Vec<SearchHit>> {
+        let resp = http
+            .get(format!("{}/search", self.url))
+            .query(&[("q", query), ("format", "json")])
+            .send()
+            .await?;
+        let body = check(resp).await?;
+        Ok(hits(&body["results"], "content", count))
+    }
+}
+
+struct Tavily {
+    api_key: String,
+}
+
+#[async_trait]
+impl SearchBackend for Tavily {
+    fn name(&self) -> &str {
+        "tavily"
+    }
+
+    async fn search(&self, http: &reqwest::Client, query: &str, count: usize) -> Result<Vec<SearchHit>> {
+        let resp = http
+            .post("https://api.tavily.com/search")
+            .bearer_auth(&self.api_key)
+            .json(&json!({ "query": query, "max_results": count }))
+            .send()
+            .await?;
+        let body = check(resp).await?;
+        Ok(hits(&body["results"], "content", count))
+    }
+}
+
+/// Turn a non-success response into an API error, else parse its JSON.
+async fn check(resp: reqwest::Response) -> Result<Value> {
+    let status = resp.status();
+    if !status.is_success() {
+        let message = resp.text().await.unwrap_or_default();
+        return Err(AgentError::Api {
+            status: status.as_u16(),
+            message: message.chars().take(500).collect(),
+        });
+    }
+    Ok(resp.json().await?)
+}
+
+/// Read `{title, url, <snippet_key>}` objects from a results array.
+fn hits(results: &Value, snippet_key: &str, count: usize) -> Vec<SearchHit> {
+    results
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|r| {
+            Some(SearchHit {
+                title: plain(r.get("title")?.as_str()?),
+                url: r.get("url")?.as_str()?.to_string(),
+                snippet: plain(r.get(snippet_key).and_then(|s| s.as_str()).unwrap_or("")),
+            })
+        })
+        .take(count)
+        .collect()
+}
+
+/// Strip the highlighting markup some APIs put in titles and snippets.
+fn plain(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_tag = false;
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    decode_entities(&out).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Render hits as a numbered list for the model.
+pub fn render_hits(provider: &str, query: &str, hits: &[SearchHit]) -> String {
+    if hits.is_empty() {
+        return format!("(no results for \"{query}\" from {provider})\n");
+    }
+    let mut out = format!("Results for \"{query}\" from {provider}:\n");
+    for (i, hit) in hits.iter().enumerate() {
+        out.push_str(&format!("\n{}. {}\n   {}\n", i + 1, hit.title, hit.url));
+        if !hit.snippet.is_empty() {
+            out.push_str(&format!("   {}\n", hit.snippet));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hits_strip_markup() {
+        let body = json!({
+            "web": { "results": [
+                { "title": "The <strong>Rust</strong> Book", "url": "https://doc.rust-lang.org/book/",
+                  "description": "Learn <strong>Rust</strong> &amp; more" },
+                { "title": "missing url" },
+                { "title": "Second", "url": "https://example.com/" }
+            ]}
+        });
+        let hits = hits(&body["web"]["results"], "description", 5);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].title, "The Rust Book");
+        assert_eq!(hits[0].snippet, "Learn Rust & more");
+        assert_eq!(hits[1].snippet, "");
+
+        let out = render_hits("brave", "rust", &hits);
+        assert!(out.starts_with("Results for \"rust\" from brave:\n\n1. The Rust Book\n"));
+    }
+
+    #[test]
+    fn test_backend_from_config() {
+        let config: SearchConfig = serde_json::from_str(r#"{"provider": "searxng"}"#).unwrap();
+        assert!(backend_from_config(&config).is_err());
+        let config: SearchConfig =
+            serde_json::from_str(r#"{"provider": "searxng", "url": "http://localhost:8888/"}"#).unwrap();
+        assert_eq!(backend_from_config(&config).unwrap().name(), "searxng");
+        assert!(backend_from_config(&SearchConfig::default()).is_err());
+    }
+}
@@ -1,18 +1,23 @@
 use std::sync::Arc;
 
+use std::net::IpAddr;
+
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    extract::{ws::WebSocketUpgrade, Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
     routing::get,
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use tower_http::cors::CorsLayer;
+use uuid::Uuid;
 
 use agentfs_core::AgentFS;
 
-use crate::memory::MemoryManager;
+use agentfs_agent_core::memory::MemoryManager;
+
+use crate::webchat::{self, ChatConfig};
 
 // ── State ───────────────────────────────────────────────────────────
 
@@ -20,6 +25,19 @@ use crate::memory::MemoryManager;
 struct AppState {
     db: Arc<AgentFS>,
     memory: Arc<MemoryManager>,
+    chat: Arc<ChatConfig>,
+    access: Arc<Access>,
+}
+
+/// Who may use the server: whoever has the token made at launch, from a
+/// page served by this server.
+struct Access {
+    token: String,
+    /// Cookie the token is kept in once the browser has opened the link.
+    cookie: String,
+    /// `Host` headers naming this server; `None` accepts any, for a server
+    /// listening on every interface.
+    hosts: Option<Vec<String>>,
 }
 
 // ── Response types ──────────────────────────────────────────────────
@@ -28,6 +46,13 @@ struct AppState {
 struct TokensResponse {
     summary: agentfs_core::analytics::UsageSummary,
     by_model: Vec<agentfs_core::analytics::ModelBreakdown>,
+    by_purpose: Vec<agentfs_core::analytics::PurposeBreakdown>,
+}
+
+#[derive(Serialize)]
+struct UsageResponse {
+    buckets: Vec<agentfs_core::analytics::UsageBucket>,
+    sessions: Vec<agentfs_core::analytics::SessionCost>,
 }
 
 #[derive(Serialize)]
@@ -36,10 +61,38 @@ struct EventsResponse {
     by_type: Vec<(String, i64)>,
 }
 
+#[derive(Serialize)]
+struct EventPage {
+    events: Vec<EventView>,
+    /// Whether older matches remain before the last event.
+    has_more: bool,
+}
+
+/// An event with what the timeline links it to.
+#[derive(Serialize)]
+struct EventView {
+    #[serde(flatten)]
+    event: agentfs_core::events::Event,
+    severity: agentfs_core::events::Severity,
+    /// The `tool_calls` row a `tool:` / `tool_error:` event was logged for.
+    tool_call_id: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct FileResponse {
+    path: String,
+    size: usize,
+    /// Contents as text, cut at [`MAX_FILE_PREVIEW`] bytes; empty for
+    /// binary files.
+    content: String,
+    truncated: bool,
+    binary: bool,
+}
+
 #[derive(Serialize)]
 struct MemoryResponse {
     tiers: TierCounts,
-    pressure: crate::memory::tiers::MemoryPressure,
+    pressure: agentfs_agent_core::memory::tiers::MemoryPressure,
     providers: Vec<ProviderStats>,
 }
 
@@ -60,6 +113,79 @@ struct ProviderStats {
 struct SearchParams {
     q: Option<String>,
     limit: Option<usize>,
+    provider: Option<String>,
+    tier: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct EntryParams {
+    /// KV key of the entry, e.g. `memory:playbook:str-00003`.
+    key: String,
+}
+
+#[derive(Serialize)]
+struct MemoryEntryDetail {
+    key: String,
+    value: serde_json::Value,
+    stats: Option<agentfs_agent_core::memory::tiers::EntryStats>,
+}
+
+#[derive(serde::Deserialize)]
+struct UsageParams {
+    /// `hour`, `day` (default), `week` or `month`.
+    bucket: Option<String>,
+    /// Inclusive start, an ISO date or timestamp.
+    since: Option<String>,
+    /// Exclusive end, an ISO date or timestamp.
+    until: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct EventQueryParams {
+    /// Exact type, or a prefix ending in `*`.
+    #[serde(rename = "type")]
+    event_type: Option<String>,
+    session: Option<String>,
+    severity: Option<String>,
+    /// `2h`, `7d`, `2026-01-31`, ...
+    since: Option<String>,
+    until: Option<String>,
+    before_id: Option<i64>,
+    after_id: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(serde::Deserialize)]
+struct RangeParams {
+    /// Inclusive start, an ISO date or timestamp.
+    since: Option<String>,
+    /// Exclusive end, an ISO date or timestamp.
+    until: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct FileParams {
+    path: String,
+}
+
+#[derive(serde::Deserialize)]
+struct TreeParams {
+    /// Directory to list, `/` by default.
+    path: Option<String>,
+}
+
+/// Largest file the dashboard previews in full.
+const MAX_FILE_PREVIEW: usize = 64 * 1024;
+
+#[derive(serde::Deserialize)]
+struct ChatParams {
+    /// Session to continue; a new one by default.
+    session: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct LimitParams {
+    limit: Option<usize>,
 }
 
 // ── Session detail response types ──────────────────────────────────
@@ -144,8 +270,12 @@ async fn api_info(State(state): State<AppState>) -> impl IntoResponse {
     }
 }
 
-async fn api_sessions(State(state): State<AppState>) -> impl IntoResponse {
-    match state.db.sessions.list_recent(50).await {
+async fn api_sessions(
+    State(state): State<AppState>,
+    Query(params): Query<LimitParams>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(50).min(1000);
+    match state.db.sessions.list_recent(limit as i64).await {
         Ok(sessions) => Json(sessions).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
@@ -154,21 +284,128 @@ async fn api_sessions(State(state): State<AppState>) -> impl IntoResponse {
 async fn api_tokens(State(state): State<AppState>) -> impl IntoResponse {
     let summary = state.db.analytics.summary().await;
     let by_model = state.db.analytics.by_model().await;
+    let by_purpose = state.db.analytics.by_purpose().await;
 
-    match (summary, by_model) {
-        (Ok(s), Ok(m)) => Json(TokensResponse {
+    match (summary, by_model, by_purpose) {
+        (Ok(s), Ok(m), Ok(p)) => Json(TokensResponse {
             summary: s,
             by_model: m,
+            by_purpose: p,
         })
         .into_response(),
-        (Err(e), _) | (_, Err(e)) => {
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
             (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
         }
     }
 }
 
+async fn api_events_query(
+    State(state): State<AppState>,
+    Query(params): Query<EventQueryParams>,
+) -> impl IntoResponse {
+    use agentfs_core::events::{EventFilter, Severity, TimeBound};
+
+    let non_empty = |s: Option<String>| s.filter(|s| !s.trim().is_empty());
+    let time = |s: Option<String>| non_empty(s).map(|s| s.parse::<TimeBound>()).transpose();
+    let filter = (|| -> Result<EventFilter, String> {
+        Ok(EventFilter {
+            event_type: non_empty(params.event_type),
+            session_id: non_empty(params.session),
+            min_severity: non_empty(params.severity).map(|s| s.parse::<Severity>()).transpose()?,
+            since: time(params.since)?,
+            until: time(params.until)?,
+            after_id: params.after_id,
+            before_id: params.before_id,
+        })
+    })();
+    let filter = match filter {
+        Ok(f) => f,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let limit = params.limit.unwrap_or(100).clamp(1, 500);
+
+    // One extra tells whether there is another page
+    let mut events = match state.db.events.query(&filter, Some(limit + 1)).await {
+        Ok(e) => e,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let has_more = events.len() as i64 > limit;
+    if has_more {
+        events.remove(0);
+    }
+
+    let mut calls: std::collections::HashMap<String, Vec<agentfs_core::toolcalls::ToolCall>> =
+        std::collections::HashMap::new();
+    let mut views = Vec::with_capacity(events.len());
+    for event in events.into_iter().rev() {
+        let tool = event
+            .event_type
+            .strip_prefix("tool:")
+            .or_else(|| event.event_type.strip_prefix("tool_error:"));
+        let tool_call_id = match (tool, event.session_id.as_deref()) {
+            (Some(tool), Some(session_id)) => {
+                if !calls.contains_key(session_id) {
+                    let rows = state.db.tools.by_session(session_id).await.unwrap_or_default();
+                    calls.insert(session_id.to_string(), rows);
+                }
+                // The latest call of that tool started before the event
+                calls[session_id]
+                    .iter()
+                    .rev()
+                    .find(|c| c.tool_name == tool && c.started_at <= event.recorded_at)
+                    .map(|c| c.id)
+            }
+            _ => None,
+        };
+        views.push(EventView {
+            severity: event.severity(),
+            event,
+            tool_call_id,
+        });
+    }
+    Json(EventPage {
+        events: views,
+        has_more,
+    })
+    .into_response()
+}
+
+async fn api_fs_file(
+    State(state): State<AppState>,
+    Query(params): Query<FileParams>,
+) -> impl IntoResponse {
+    match state.db.fs.read_file(&params.path).await {
+        Ok(data) => {
+            let size = data.len();
+            let truncated = size > MAX_FILE_PREVIEW;
+            let shown = &data[..size.min(MAX_FILE_PREVIEW)];
+            let binary = shown.contains(&0);
+            Json(FileResponse {
+                path: params.path,
+                size,
+                content: if binary { String::new() } else { String::from_utf8_lossy(shown).into_owned() },
+                truncated,
+                binary,
+            })
+            .into_response()
+        }
+        Err(e) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    }
+}
+
+async fn api_fs_tree(
+    State(state): State<AppState>,
+    Query(params): Query<TreeParams>,
+) -> impl IntoResponse {
+    let path = params.path.filter(|p| !p.is_empty()).unwrap_or_else(|| "/".into());
+    match state.db.fs.tree(&path).await {
+        Ok(tree) => Json(tree).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    }
+}
+
 async fn api_tools(State(state): State<AppState>) -> impl IntoResponse {
-    match state.db.tools.stats().await {
+    match state.db.tools.stats(None).await {
         Ok(stats) => Json(stats).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
@@ -196,7 +433,7 @@ async fn api_memory(State(state): State<AppState>) -> impl IntoResponse {
         .memory
         .memory_pressure()
         .await
-        .unwrap_or(crate::memory::tiers::MemoryPressure::Low);
+        .unwrap_or(agentfs_agent_core::memory::tiers::MemoryPressure::Low);
 
     // Get provider entry counts from KV prefixes
     let playbook_count = state
@@ -251,11 +488,19 @@ async fn api_memory_search(
 ) -> impl IntoResponse {
     let query = params.q.unwrap_or_default();
     if query.is_empty() {
-        return Json(Vec::<crate::memory::search::SearchResult>::new()).into_response();
+        return Json(Vec::<agentfs_agent_core::memory::search::SearchResult>::new()).into_response();
     }
     let limit = params.limit.unwrap_or(10).min(50);
+    let filter = agentfs_agent_core::memory::search::SearchFilter {
+        provider: params.provider.filter(|p| !p.is_empty()),
+        tier: params
+            .tier
+            .filter(|t| !t.is_empty())
+            .map(|t| agentfs_agent_core::memory::tiers::MemoryTier::from_str(&t)),
+        ..Default::default()
+    };
 
-    match state.memory.search(&query, limit).await {
+    match state.memory.search_filtered(&query, &filter, limit).await {
         Ok(results) => Json(results).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
     }
@@ -352,6 +597,16 @@ async fn api_session_tools_detail(
     }
 }
 
+async fn api_session_replay(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match agentfs_agent_core::replay::build(&state.db, &id).await {
+        Ok(turns) => Json(turns).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 async fn api_session_learnings(
     State(state): State<AppState>,
     Path(id): Path<String>,
@@ -365,7 +620,7 @@ async fn api_session_learnings(
         .filter_map(|kv| serde_json::from_str::<DashPlaybookEntry>(&kv.value).ok())
         .filter(|entry| entry.source_session == id)
         .collect();
-    learnings.sort_by(|a, b| (b.helpful - b.harmful).cmp(&(a.helpful - a.harmful)));
+    learnings.sort_by_key(|e| std::cmp::Reverse(e.helpful - e.harmful));
     Json(learnings).into_response()
 }
 
@@ -380,7 +635,7 @@ async fn api_memory_playbook(State(state): State<AppState>) -> impl IntoResponse
         .into_iter()
         .filter_map(|kv| serde_json::from_str::<DashPlaybookEntry>(&kv.value).ok())
         .collect();
-    playbook.sort_by(|a, b| (b.helpful - b.harmful).cmp(&(a.helpful - a.harmful)));
+    playbook.sort_by_key(|e| std::cmp::Reverse(e.helpful - e.harmful));
     Json(playbook).into_response()
 }
 
@@ -397,6 +652,17 @@ async fn api_memory_episodes(State(state): State<AppState>) -> impl IntoResponse
     Json(episodes).into_response()
 }
 
+async fn api_memory_timeline(
+    State(state): State<AppState>,
+    Query(params): Query<LimitParams>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(50);
+    match agentfs_agent_core::memory::timeline::build(&state.db, limit).await {
+        Ok(timeline) => Json(timeline).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 async fn api_memory_tool_patterns(State(state): State<AppState>) -> impl IntoResponse {
     let entries = match state.db.kv.list_prefix("memory:tool_pattern:").await {
         Ok(e) => e,
@@ -409,6 +675,84 @@ async fn api_memory_tool_patterns(State(state): State<AppState>) -> impl IntoRes
     Json(patterns).into_response()
 }
 
+async fn api_memory_stats(State(state): State<AppState>) -> impl IntoResponse {
+    match state.memory.entry_stats().await {
+        Ok(stats) => Json(stats).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn api_memory_entry(
+    State(state): State<AppState>,
+    Query(params): Query<EntryParams>,
+) -> impl IntoResponse {
+    if !params.key.starts_with("memory:") {
+        return (StatusCode::BAD_REQUEST, format!("not a memory key: {}", params.key)).into_response();
+    }
+    let raw = match state.db.kv.get(&params.key).await {
+        Ok(entry) => entry.value,
+        Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+    let value = serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw));
+    let stats = state
+        .memory
+        .entry_stats()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|s| s.key == params.key);
+    Json(MemoryEntryDetail {
+        key: params.key,
+        value,
+        stats,
+    })
+    .into_response()
+}
+
+async fn api_memory_delete(
+    State(state): State<AppState>,
+    Query(params): Query<EntryParams>,
+) -> impl IntoResponse {
+    match state.memory.delete_key(&params.key).await {
+        Ok(()) => Json(serde_json::json!({ "deleted": params.key })).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    }
+}
+
+async fn api_tokens_usage(
+    State(state): State<AppState>,
+    Query(params): Query<UsageParams>,
+) -> impl IntoResponse {
+    let bucket = match params.bucket.as_deref() {
+        None => agentfs_core::analytics::Bucket::default(),
+        Some(b) => match agentfs_core::analytics::Bucket::parse(b) {
+            Some(bucket) => bucket,
+            None => return (StatusCode::BAD_REQUEST, format!("unknown bucket: {b}")).into_response(),
+        },
+    };
+    let since = params.since.as_deref().filter(|s| !s.is_empty());
+    let until = params.until.as_deref().filter(|s| !s.is_empty());
+
+    let buckets = state.db.analytics.usage_buckets(bucket, since, until).await;
+    let sessions = state.db.analytics.by_session_range(since, until).await;
+    match (buckets, sessions) {
+        (Ok(buckets), Ok(sessions)) => Json(UsageResponse { buckets, sessions }).into_response(),
+        (Err(e), _) | (_, Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn api_analytics_daily(
+    State(state): State<AppState>,
+    Query(params): Query<RangeParams>,
+) -> impl IntoResponse {
+    let since = params.since.as_deref().filter(|s| !s.is_empty());
+    let until = params.until.as_deref().filter(|s| !s.is_empty());
+    match state.db.analytics.daily(since, until).await {
+        Ok(days) => Json(days).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 async fn api_sessions_costs(State(state): State<AppState>) -> impl IntoResponse {
     match state.db.analytics.by_session().await {
         Ok(costs) => Json(costs).into_response(),
@@ -434,7 +778,7 @@ struct ConfigMcpEntry {
 }
 
 async fn api_config_skills(State(state): State<AppState>) -> impl IntoResponse {
-    let entries = state.db.kv.list_prefix("config:skill:").await.unwrap_or_default();
+    let entries = state.db.kv.list_prefix(agentfs_core::skills::SKILL_PREFIX).await.unwrap_or_default();
     let skills: Vec<ConfigSkillEntry> = entries
         .into_iter()
         .filter_map(|kv| {
@@ -455,7 +799,7 @@ async fn api_config_mcp(State(state): State<AppState>) -> impl IntoResponse {
         .into_iter()
         .filter_map(|kv| {
             let name = kv.key.strip_prefix("config:mcp:")?.to_string();
-            let entry: crate::mcp_client::McpServerEntry =
+            let entry: agentfs_agent_core::mcp_client::McpServerEntry =
                 serde_json::from_str(&kv.value).ok()?;
             Some(ConfigMcpEntry {
                 name,
@@ -468,26 +812,142 @@ async fn api_config_mcp(State(state): State<AppState>) -> impl IntoResponse {
     Json(servers)
 }
 
+// ── Access ──────────────────────────────────────────────────────────
+
+impl Access {
+    fn new(host: &str, port: u16) -> Self {
+        let unspecified = host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified());
+        let hosts = (!unspecified).then(|| {
+            let bracketed = if host.contains(':') { format!("[{host}]") } else { host.to_string() };
+            ["localhost", "127.0.0.1", "[::1]", &bracketed]
+                .iter()
+                .map(|h| format!("{h}:{port}"))
+                .collect()
+        });
+        Self {
+            token: Uuid::new_v4().simple().to_string(),
+            cookie: format!("infinity_dashboard_{port}"),
+            hosts,
+        }
+    }
+
+    /// Whether the request carries the token: in the cookie, an
+    /// `Authorization: Bearer` header or a `token` query parameter.
+    fn authorized(&self, headers: &HeaderMap, query: Option<&str>) -> bool {
+        let from_cookie = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .filter(|(name, _)| *name == self.cookie)
+            .map(|(_, value)| value);
+        let from_header = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        from_cookie
+            .chain(from_header)
+            .chain(query_token(query))
+            .any(|token| same(token.as_bytes(), self.token.as_bytes()))
+    }
+}
+
+fn query_token(query: Option<&str>) -> Option<&str> {
+    query?.split('&').find_map(|pair| pair.strip_prefix("token="))
+}
+
+/// Compare without stopping at the first difference, so response times
+/// don't give the token away.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Turn away requests for another host (DNS rebinding), from another
+/// site's pages (including WebSocket upgrades, which CORS doesn't cover)
+/// and without the token. Opening the launch link stores the token in a
+/// cookie and drops it from the address bar.
+async fn guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let access = &state.access;
+    let headers = request.headers();
+    let host = headers.get(header::HOST).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if let Some(hosts) = &access.hosts {
+        if !hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+            return (StatusCode::FORBIDDEN, "Unknown host").into_response();
+        }
+    }
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        let ours = format!("http://{host}");
+        if !origin.to_str().is_ok_and(|o| o.eq_ignore_ascii_case(&ours)) {
+            return (StatusCode::FORBIDDEN, "Cross-origin requests are not allowed").into_response();
+        }
+    }
+    if !access.authorized(headers, request.uri().query()) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "Missing or wrong token: open the link `infinity-agent dashboard` printed",
+        )
+            .into_response();
+    }
+    if request.uri().path() == "/" && query_token(request.uri().query()).is_some() {
+        let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Strict", access.cookie, access.token);
+        let mut response = (StatusCode::SEE_OTHER, [(header::LOCATION, "/")]).into_response();
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().insert(header::SET_COOKIE, value);
+        }
+        return response;
+    }
+    next.run(request).await
+}
+
+// ── Chat ────────────────────────────────────────────────────────────
+
+async fn api_chat(
+    State(state): State<AppState>,
+    Query(params): Query<ChatParams>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let session = params.session.filter(|s| !s.is_empty());
+    ws.on_upgrade(move |socket| webchat::serve(socket, state.chat, session))
+}
+
 // ── Server ──────────────────────────────────────────────────────────
 
 pub async fn run_dashboard(
     db: Arc<AgentFS>,
     memory: Arc<MemoryManager>,
+    chat: ChatConfig,
+    host: &str,
     port: u16,
 ) -> anyhow::Result<()> {
-    let state = AppState { db, memory };
+    let access = Access::new(host, port);
+    let token = access.token.clone();
+    let state = AppState {
+        db,
+        memory,
+        chat: Arc::new(chat),
+        access: Arc::new(access),
+    };
 
     let app = Router::new()
         .route("/", get(index))
         .route("/api/info", get(api_info))
         .route("/api/sessions", get(api_sessions))
         .route("/api/tokens", get(api_tokens))
+        .route("/api/tokens/usage", get(api_tokens_usage))
         .route("/api/tools", get(api_tools))
         .route("/api/events", get(api_events))
+        .route("/api/events/query", get(api_events_query))
+        .route("/api/fs/file", get(api_fs_file))
+        .route("/api/fs/tree", get(api_fs_tree))
+        .route("/api/analytics/daily", get(api_analytics_daily))
         .route("/api/memory", get(api_memory))
         .route("/api/memory/search", get(api_memory_search))
+        .route("/api/memory/stats", get(api_memory_stats))
+        .route("/api/memory/entry", get(api_memory_entry).delete(api_memory_delete))
         .route("/api/memory/playbook", get(api_memory_playbook))
         .route("/api/memory/episodes", get(api_memory_episodes))
+        .route("/api/memory/timeline", get(api_memory_timeline))
         .route("/api/memory/tool-patterns", get(api_memory_tool_patterns))
         .route("/api/sessions/costs", get(api_sessions_costs))
         .route("/api/config/skills", get(api_config_skills))
@@ -497,15 +957,24 @@ pub async fn run_dashboard(
         .route("/api/sessions/{id}/tokens", get(api_session_tokens))
         .route("/api/sessions/{id}/tools", get(api_session_tools_detail))
         .route("/api/sessions/{id}/learnings", get(api_session_learnings))
-        .layer(CorsLayer::permissive())
+        .route("/api/sessions/{id}/replay", get(api_session_replay))
+        .route("/api/chat", get(api_chat))
+        .layer(middleware::from_fn_with_state(state.clone(), guard))
         .with_state(state);
 
-    let addr = format!("0.0.0.0:{port}");
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    println!("Dashboard running at http://localhost:{port}");
+    let listener = tokio::net::TcpListener::bind((host, port)).await?;
+    let loopback = host.parse::<IpAddr>().map_or(host == "localhost", |ip| ip.is_loopback() || ip.is_unspecified());
+    let url = if loopback {
+        format!("http://localhost:{port}/?token={token}")
+    } else if host.contains(':') {
+        format!("http://[{host}]:{port}/?token={token}")
+    } else {
+        format!("http://{host}:{port}/?token={token}")
+    };
+    println!("Dashboard running at {url}");
+    println!("API token: {token}");
 
     // Open browser
-    let url = format!("http://localhost:{port}");
     if let Err(e) = open::that(&url) {
         eprintln!("Could not open browser: {e}");
         println!("Open manually: {url}");
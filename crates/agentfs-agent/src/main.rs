@@ -1,21 +1,13 @@
-mod agent;
-mod api;
-mod auth;
-mod config;
 mod dashboard;
-mod display;
-mod error;
-mod executor;
-mod mcp_client;
-mod memory;
-mod skills;
-mod streaming;
-mod tools;
+mod notify;
+mod piped;
+mod project;
+mod webchat;
 
 use std::collections::HashMap;
-use std::io::Write;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
 use clap::{Parser, Subcommand};
 use tokio::sync::Mutex;
@@ -24,17 +16,74 @@ use uuid::Uuid;
 use agentfs_core::config::AgentFSConfig;
 use agentfs_core::AgentFS;
 
-use crate::agent::Agent;
-use crate::api::AnthropicClient;
-use crate::auth::AuthProvider;
-use crate::config::AgentConfig;
-use crate::executor::ToolExecutor;
-use crate::mcp_client::McpManager;
-use crate::memory::{load_memory_config, MemoryManager};
-use crate::skills::SkillRegistry;
+use agentfs_agent_core::agent::AGENT_NAME;
+use agentfs_agent_core::api::{AnthropicClient, LlmClient, Provider};
+use agentfs_agent_core::attachments::Attachment;
+use agentfs_agent_core::auth::AuthProvider;
+use agentfs_agent_core::commands::CommandRegistry;
+use agentfs_agent_core::config::{AgentConfig, TurnLimits, DEFAULT_MAX_TOKENS, DEFAULT_MODEL};
+use agentfs_agent_core::hooks::{load_hooks_config, Hooks};
+use agentfs_agent_core::mcp_client::McpManager;
+use agentfs_agent_core::memory::{load_memory_config, Category, MemoryManager};
+use agentfs_agent_core::permissions::{load_permission_config, Permissions, ToolScope, ALWAYS_KEY_PREFIX};
+use agentfs_agent_core::profiles::Profile;
+use agentfs_agent_core::providers::build_chain;
+use agentfs_agent_core::skills::{SkillRegistry, SkillScope, SkillWatcher};
+use agentfs_agent_core::web::{load_web_config, WebClient};
+use agentfs_agent_core::AgentBuilder;
+use agentfs_agent_core::{attachments, display, highlight, mcp_client, memory, mentions, output, profiles, skills};
+
+use crate::notify::load_notify_config;
+
+/// What `chat -p` writes to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Rendered for a terminal.
+    #[default]
+    Text,
+    /// One JSON result object.
+    Json,
+    /// Newline-delimited JSON events, then the result object.
+    StreamJson,
+}
+
+/// What `memory search` and `memory timeline` write to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum ListFormat {
+    /// Rendered for a terminal.
+    #[default]
+    Text,
+    /// A JSON array.
+    Json,
+}
 
-/// Return the global default DB path: `~/.infinity/infinity.db`.
+impl OutputFormat {
+    fn is_structured(self) -> bool {
+        self != Self::Text
+    }
+}
+
+/// Return the default DB path: the project's database (the project
+/// config's `db`, else the nearest `.infinity/workspace.db`) if the user
+/// trusts it, else the global `~/.infinity/infinity.db`.
 fn default_db_path() -> PathBuf {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| match project::project_db() {
+        Some(db) if project::is_trusted(&db) => db,
+        Some(db) => {
+            eprintln!(
+                "Not using {}: run `infinity-agent trust` if you trust this project's database.",
+                db.display()
+            );
+            global_db_path()
+        }
+        None => global_db_path(),
+    })
+    .clone()
+}
+
+/// The global DB path: `~/.infinity/infinity.db`.
+fn global_db_path() -> PathBuf {
     dirs::home_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join(".infinity")
@@ -56,6 +105,15 @@ enum Commands {
     Logout,
     /// Show authentication status
     Status,
+    /// Create a project workspace (.infinity/workspace.db) in the current directory
+    Init,
+    /// Use the project's database (.infinity/workspace.db, or the db in
+    /// .infinity/config.toml) from now on, after showing what it would run
+    Trust {
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
     /// List past sessions
     Sessions {
         /// Path to the AgentFS database
@@ -80,13 +138,17 @@ enum Commands {
         /// Path to the AgentFS database
         #[arg(long, default_value_os_t = default_db_path())]
         db: PathBuf,
+        /// Use the global database (~/.infinity/infinity.db) for global skills
+        #[arg(long, conflicts_with = "db")]
+        global: bool,
     },
     /// Manage memory system
     Memory {
         #[command(subcommand)]
         action: MemoryAction,
     },
-    /// Launch web dashboard (read-only)
+    /// Launch web dashboard: browse sessions and memory, delete memory
+    /// entries, chat with the agent
     Dashboard {
         /// Path to the AgentFS database
         #[arg(long, default_value_os_t = default_db_path())]
@@ -94,27 +156,59 @@ enum Commands {
         /// Port to serve on
         #[arg(long, default_value = "3210")]
         port: u16,
+        /// Address to listen on; `0.0.0.0` serves other machines too
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
     },
     /// Start interactive agent (default)
     Chat {
         /// Path to the AgentFS database
         #[arg(long, default_value_os_t = default_db_path())]
         db: PathBuf,
-        /// Model to use
-        #[arg(long, default_value = "claude-sonnet-4-6")]
-        model: String,
-        /// Maximum output tokens
-        #[arg(long, default_value = "8192")]
-        max_tokens: u32,
+        /// Model to use [default: the project config's, else claude-sonnet-4-6]
+        #[arg(long)]
+        model: Option<String>,
+        /// Maximum output tokens [default: the project config's, else 8192]
+        #[arg(long)]
+        max_tokens: Option<u32>,
         /// System prompt
         #[arg(long)]
         system: Option<String>,
-        /// Single prompt (non-interactive mode)
+        /// Prompt profile from ~/.infinity/prompts/<name>.md, added to the system prompt
+        #[arg(long)]
+        profile: Option<String>,
+        /// Single prompt (non-interactive mode); input piped on stdin is appended
         #[arg(short = 'p', long)]
         prompt: Option<String>,
         /// Resume a previous session by ID (or "last" for the most recent)
         #[arg(short = 'r', long)]
         resume: Option<String>,
+        /// Model responses per turn before asking to continue (0 = no limit)
+        #[arg(long, default_value = "50")]
+        max_steps: u32,
+        /// Tool calls per turn before asking to continue (0 = no limit)
+        #[arg(long, default_value = "100")]
+        max_tool_calls: u32,
+        /// Estimated spend per turn, in dollars, before asking to continue (0 = no limit)
+        #[arg(long, default_value = "5")]
+        max_turn_cost: f64,
+        /// Estimated cost, in dollars, of one model request above which to ask before sending it; with -p such a request fails the run (0 = never ask)
+        #[arg(long, default_value = "1")]
+        confirm_call_cost: f64,
+        /// Output of a single-prompt run (-p or a prompt on stdin): text,
+        /// json (one result object) or stream-json (newline-delimited
+        /// events, then the result)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+        /// Only offer these tools (comma-separated names or globs, e.g. read_file,grep,github__*)
+        #[arg(long, value_delimiter = ',')]
+        allowed_tools: Vec<String>,
+        /// Never offer these tools (comma-separated names or globs)
+        #[arg(long, value_delimiter = ',')]
+        disallowed_tools: Vec<String>,
+        /// Run tool calls that would ask for approval without asking; deny rules still apply
+        #[arg(long)]
+        dangerously_skip_permissions: bool,
     },
 }
 
@@ -141,13 +235,13 @@ enum McpAction {
 
 #[derive(Subcommand)]
 enum SkillsAction {
-    /// List all skills in the database
+    /// List all skills, project ones shadowing global ones, with their scope
     List,
     /// Add a skill from a SKILL.md file
     Add {
         /// Skill name
         name: String,
-        /// Path to a SKILL.md file
+        /// Path to a SKILL.md file; files beside it are bundled if its directory is named after the skill
         file: PathBuf,
     },
     /// Remove a skill by name
@@ -155,12 +249,39 @@ enum SkillsAction {
         /// Skill name
         name: String,
     },
+    /// Show the stored versions of a skill
+    History {
+        /// Skill name
+        name: String,
+    },
+    /// Make an earlier version of a skill current again
+    Rollback {
+        /// Skill name
+        name: String,
+        /// Version to restore (see `skills history`)
+        version: u32,
+    },
+    /// Dry-run a skill: read-only tools run, the rest are only listed
+    Test {
+        /// Skill name
+        name: String,
+        /// Request to run the skill with, as after /<skill> in the REPL
+        #[arg(long, default_value = "")]
+        args: String,
+        /// Model to use [default: the project config's, else the built-in default]
+        #[arg(long)]
+        model: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
 enum MemoryAction {
     /// Show memory entries (playbook, episodes, tool patterns)
-    Show,
+    Show {
+        /// List entries with their ids, for `memory edit` and `memory delete`
+        #[arg(long)]
+        ids: bool,
+    },
     /// Show memory statistics with tier distribution
     Stats {
         /// Path to the AgentFS database
@@ -174,6 +295,63 @@ enum MemoryAction {
         /// Maximum results to return
         #[arg(short, long, default_value = "10")]
         limit: usize,
+        /// Only entries from this provider (playbook, episodes, tool_patterns, preferences, codebase)
+        #[arg(long)]
+        provider: Option<String>,
+        /// Only entries in this tier
+        #[arg(long, value_parser = ["hot", "warm", "cold"])]
+        tier: Option<String>,
+        /// Only entries created since a date (2026-01-31) or within an age (7d, 12h)
+        #[arg(long, value_parser = memory::parse_since)]
+        since: Option<String>,
+        /// Only playbook entries of this category: strategy, mistake or pattern
+        #[arg(long)]
+        category: Option<Category>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::Text)]
+        format: ListFormat,
+        /// Path to the AgentFS database
+        #[arg(long, default_value_os_t = default_db_path())]
+        db: PathBuf,
+    },
+    /// Show past sessions' episodes in order, with their outcomes and files
+    Timeline {
+        /// Number of most recent episodes to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::Text)]
+        format: ListFormat,
+        /// Path to the AgentFS database
+        #[arg(long, default_value_os_t = default_db_path())]
+        db: PathBuf,
+    },
+    /// Add an entry to the playbook
+    Add {
+        /// What to remember, e.g. "Always run cargo clippy before committing"
+        text: String,
+        /// Entry category: strategy, mistake or pattern
+        #[arg(long, default_value = "strategy")]
+        category: Category,
+        /// Delete the entry after this long, e.g. 12h, 3d or 2w
+        #[arg(long, value_parser = memory::parse_ttl)]
+        ttl: Option<f64>,
+        /// Path to the AgentFS database
+        #[arg(long, default_value_os_t = default_db_path())]
+        db: PathBuf,
+    },
+    /// Delete a memory entry
+    Delete {
+        /// Entry id from `memory show --ids` (a unique prefix will do)
+        id: String,
+        /// Path to the AgentFS database
+        #[arg(long, default_value_os_t = default_db_path())]
+        db: PathBuf,
+    },
+    /// Edit a memory entry's JSON in $EDITOR
+    Edit {
+        /// Entry id from `memory show --ids` (a unique prefix will do)
+        id: String,
         /// Path to the AgentFS database
         #[arg(long, default_value_os_t = default_db_path())]
         db: PathBuf,
@@ -184,12 +362,42 @@ enum MemoryAction {
         #[arg(long, default_value_os_t = default_db_path())]
         db: PathBuf,
     },
+    /// Down-weight stale playbook entries and rebalance tiers
+    Decay {
+        /// Path to the AgentFS database
+        #[arg(long, default_value_os_t = default_db_path())]
+        db: PathBuf,
+    },
     /// Clear all memory data
     Clear {
         /// Path to the AgentFS database
         #[arg(long, default_value_os_t = default_db_path())]
         db: PathBuf,
     },
+    /// Save or roll back to named copies of all memory
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+        /// Path to the AgentFS database
+        #[arg(long, default_value_os_t = default_db_path())]
+        db: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotAction {
+    /// Save the current memory (entries, tiers, search index) under a name
+    Create {
+        /// Snapshot name
+        name: String,
+    },
+    /// List snapshots, newest first
+    List,
+    /// Replace the current memory with a snapshot
+    Restore {
+        /// Snapshot name
+        name: String,
+    },
 }
 
 #[tokio::main]
@@ -208,29 +416,54 @@ async fn main() -> anyhow::Result<()> {
         Some(Commands::Login) => cmd_login().await?,
         Some(Commands::Logout) => cmd_logout()?,
         Some(Commands::Status) => cmd_status()?,
+        Some(Commands::Init) => cmd_init().await?,
+        Some(Commands::Trust { yes }) => cmd_trust(yes)?,
         Some(Commands::Sessions { db, limit }) => cmd_sessions(db, limit).await?,
-        Some(Commands::Dashboard { db, port }) => cmd_dashboard(db, port).await?,
+        Some(Commands::Dashboard { db, port, host }) => cmd_dashboard(db, &host, port).await?,
         Some(Commands::Mcp { action, db }) => cmd_mcp(action, &db).await?,
-        Some(Commands::Skills { action, db }) => cmd_skills(action, db).await?,
+        Some(Commands::Skills { action, db, global }) => {
+            let db = if global { global_db_path() } else { db };
+            cmd_skills(action, db).await?
+        }
         Some(Commands::Memory { action }) => cmd_memory(action).await?,
         Some(Commands::Chat {
             db,
             model,
             max_tokens,
             system,
+            profile,
             prompt,
             resume,
+            max_steps,
+            max_tool_calls,
+            max_turn_cost,
+            confirm_call_cost,
+            output_format,
+            allowed_tools,
+            disallowed_tools,
+            dangerously_skip_permissions,
         }) => {
-            cmd_chat(db, model, max_tokens, system, prompt, resume).await?;
+            let limits = TurnLimits::new(max_steps, max_tool_calls, max_turn_cost, confirm_call_cost);
+            let scope = ToolScope {
+                allowed: allowed_tools,
+                disallowed: disallowed_tools,
+                skip_prompts: dangerously_skip_permissions,
+            };
+            cmd_chat(db, model, max_tokens, system, profile, prompt, resume, limits, output_format, scope)
+                .await?;
         }
         None => {
             cmd_chat(
                 default_db_path(),
-                "claude-sonnet-4-6".to_string(),
-                8192,
                 None,
                 None,
                 None,
+                None,
+                None,
+                None,
+                TurnLimits::default(),
+                OutputFormat::Text,
+                ToolScope::default(),
             )
             .await?;
         }
@@ -259,6 +492,111 @@ fn cmd_status() -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn cmd_init() -> anyhow::Result<()> {
+    let db_path = std::env::current_dir()?.join(project::WORKSPACE_DB);
+    if db_path.exists() {
+        println!("Project workspace already exists: {}", db_path.display());
+        if !project::is_trusted(&db_path) {
+            println!("Run `infinity-agent trust` to use it.");
+        }
+        return Ok(());
+    }
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)?;
+        // The database stays out of version control; config.toml can be committed.
+        let gitignore = parent.join(".gitignore");
+        if !gitignore.exists() {
+            std::fs::write(gitignore, "workspace.db*\n")?;
+        }
+    }
+    let afs_config = AgentFSConfig::builder(&db_path)
+        .checkpoint_interval_secs(0)
+        .build();
+    AgentFS::create(afs_config).await?.close().await?;
+    project::trust(&db_path)?;
+    println!("Created project workspace: {}", db_path.display());
+    println!("Sessions started in this directory or below now use it instead of the global database.");
+    Ok(())
+}
+
+/// Trust the project's database, once the user has seen the MCP servers it
+/// starts and the approvals it remembers.
+fn cmd_trust(yes: bool) -> anyhow::Result<()> {
+    let Some(db) = project::project_db() else {
+        println!("No project database here; create one with `infinity-agent init`.");
+        return Ok(());
+    };
+    if project::is_trusted(&db) {
+        println!("Already trusted: {}", db.display());
+        return Ok(());
+    }
+    if db.is_file() {
+        let (servers, approvals) = trust_summary(&db)?;
+        println!("{}", db.display());
+        if !servers.is_empty() {
+            println!("  MCP servers it starts at launch:");
+            for server in &servers {
+                println!("    {server}");
+            }
+        }
+        if !approvals.is_empty() {
+            println!("  Approvals it remembers:");
+            for approval in &approvals {
+                println!("    {approval}");
+            }
+        }
+        if servers.is_empty() && approvals.is_empty() {
+            println!("  No MCP servers or remembered approvals.");
+        }
+        if !yes {
+            if !std::io::stdin().is_terminal() {
+                anyhow::bail!("not trusting {} without confirmation: pass --yes", db.display());
+            }
+            print!("Trust it? [y/N] ");
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                println!("Not trusted.");
+                return Ok(());
+            }
+        }
+    }
+    project::trust(&db)?;
+    println!("Trusted {}; sessions here now use it.", db.display());
+    Ok(())
+}
+
+/// The MCP server commands and remembered approvals in the database at
+/// `db`, read without opening it for writing.
+fn trust_summary(db: &Path) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let conn = rusqlite::Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare(
+        "SELECT key, value FROM kv_store WHERE key LIKE 'config:mcp:%' OR key LIKE ?1 ORDER BY key",
+    )?;
+    let rows = stmt
+        .query_map([format!("{ALWAYS_KEY_PREFIX}%")], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut servers = Vec::new();
+    let mut approvals = Vec::new();
+    for (key, value) in rows {
+        if let Some(name) = key.strip_prefix("config:mcp:") {
+            let entry: serde_json::Value = serde_json::from_str(&value).unwrap_or_default();
+            let mut command = entry["command"].as_str().unwrap_or("?").to_string();
+            for arg in entry["args"].as_array().into_iter().flatten() {
+                command.push(' ');
+                command.push_str(arg.as_str().unwrap_or("?"));
+            }
+            servers.push(format!("{name}: {command}"));
+        } else if let Some(what) = key.strip_prefix(ALWAYS_KEY_PREFIX) {
+            approvals.push(what.to_string());
+        }
+    }
+    Ok((servers, approvals))
+}
+
 async fn cmd_mcp(action: McpAction, db_path: &PathBuf) -> anyhow::Result<()> {
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent).ok();
@@ -313,18 +651,19 @@ async fn cmd_skills(action: SkillsAction, db_path: PathBuf) -> anyhow::Result<()
         AgentFS::create(afs_config).await?
     };
 
+    let on_global = is_global_db(&db_path);
+    let global_db = open_global_db(&db_path).await?;
+    let (global, project) = skill_dbs(global_db.as_ref(), &db, on_global);
+
     match action {
         SkillsAction::List => {
-            let skills = SkillRegistry::list_from_db(&db).await;
-            if skills.is_empty() {
+            let registry = SkillRegistry::load_from_db(global, project).await;
+            warn_unbundled(&registry);
+            if registry.is_empty() {
                 println!("No skills in database.");
-                println!("Add one with: infinity-agent skills add <name> <file.md> --db {}", db_path.display());
+                println!("Add one with: infinity-agent skills --db {} add <name> <file.md>", db_path.display());
             } else {
-                let pairs: Vec<(&str, &str)> = skills
-                    .iter()
-                    .map(|s| (s.name.as_str(), s.description.as_str()))
-                    .collect();
-                display::print_skills_list(&pairs);
+                display::print_skills_list(&registry.skills());
             }
         }
         SkillsAction::Add { name, file } => {
@@ -332,16 +671,28 @@ async fn cmd_skills(action: SkillsAction, db_path: PathBuf) -> anyhow::Result<()
                 .map_err(|e| anyhow::anyhow!("Cannot read {}: {e}", file.display()))?;
 
             // Try to parse as SKILL.md format; otherwise treat entire file as body
-            let (description, body) = parse_skill_content(&content);
+            let (description, allowed_tools, body) = parse_skill_content(&content);
 
+            // A SKILL.md in its own <name>/ directory brings the files next
+            // to it along as bundled resources
+            let dir = skills::bundle_dir(&file, &name).unwrap_or_default();
+            if dir.as_os_str().is_empty() && file.file_name().is_some_and(|f| f == "SKILL.md") {
+                println!("Not bundling files next to {}: put it in a directory named '{name}'.", file.display());
+            }
             let skill = skills::Skill {
                 name: name.clone(),
                 description,
                 body,
-                dir: PathBuf::new(),
+                allowed_tools,
+                dir,
+                scope: if project.is_some() { SkillScope::Project } else { SkillScope::Global },
             };
-            SkillRegistry::save_to_db(&db, &skill).await;
-            println!("Added skill '{name}' to DB.");
+            let version = SkillRegistry::save_to_db(&db, &skill).await;
+            println!("Saved skill '{name}' to DB (version {version}).");
+            let resources = skills::import_resources(&db, &skill).await;
+            if resources > 0 {
+                println!("Bundled {resources} resource file(s) under {}.", skills::resource_dir(&name));
+            }
         }
         SkillsAction::Remove { name } => {
             let removed = SkillRegistry::remove_from_db(&db, &name).await;
@@ -351,47 +702,251 @@ async fn cmd_skills(action: SkillsAction, db_path: PathBuf) -> anyhow::Result<()
                 println!("Skill '{name}' not found in DB.");
             }
         }
+        SkillsAction::History { name } => {
+            display::print_skill_history(&name, &SkillRegistry::history(&db, &name).await);
+        }
+        SkillsAction::Rollback { name, version } => {
+            let new_version = SkillRegistry::rollback(&db, &name, version).await?;
+            println!("Rolled back skill '{name}' to version {version} (saved as version {new_version}).");
+        }
+        SkillsAction::Test { name, args, model } => {
+            let registry = SkillRegistry::load_from_db(global, project).await;
+            match registry.get(&name) {
+                Some(skill) => cmd_skill_test(&db_path, skill, &args, model).await?,
+                None => println!("Skill '{name}' not found in DB."),
+            }
+        }
     }
 
+    if let Some(global_db) = global_db {
+        global_db.close().await?;
+    }
     db.close().await?;
     Ok(())
 }
 
-/// Parse SKILL.md content to extract description and body.
-/// If no frontmatter is found, use empty description and the entire content as body.
-fn parse_skill_content(content: &str) -> (String, String) {
-    let trimmed = content.trim();
-    if !trimmed.starts_with("---") {
-        return (String::new(), trimmed.to_string());
+/// Whether `db_path` is the global DB.
+fn is_global_db(db_path: &Path) -> bool {
+    let global = global_db_path();
+    match (db_path.canonicalize(), global.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => db_path == global,
     }
+}
 
-    let after_first = trimmed[3..].trim_start_matches(['\r', '\n']);
-    let end_idx = match after_first.find("\n---") {
-        Some(idx) => idx,
-        None => return (String::new(), trimmed.to_string()),
-    };
-    let frontmatter = &after_first[..end_idx];
-    let body_start = end_idx + 4;
-    let body = if body_start < after_first.len() {
-        after_first[body_start..].trim().to_string()
+/// The global DB, opened alongside a project's DB at `db_path` for global
+/// skills. It is only created to hold the skills of `~/.infinity/skills/`,
+/// so a session on another DB doesn't leave one behind. `None` when
+/// `db_path` is the global DB or there is no global DB to open.
+async fn open_global_db(db_path: &Path) -> anyhow::Result<Option<AgentFS>> {
+    let global = global_db_path();
+    if is_global_db(db_path) {
+        return Ok(None);
+    }
+    let config = AgentFSConfig::builder(&global).checkpoint_interval_secs(0).build();
+    if global.exists() {
+        return Ok(Some(AgentFS::open(config).await?));
+    }
+    if !SkillScope::Global.dir().is_some_and(|dir| dir.is_dir()) {
+        return Ok(None);
+    }
+    if let Some(parent) = global.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    Ok(Some(AgentFS::create(config).await?))
+}
+
+/// The (global, project) DBs skills live in, given the global DB from
+/// [`open_global_db`] and the session's DB, which is the global DB when
+/// `on_global`. A session on the global DB has no project DB.
+fn skill_dbs<'a>(
+    global_db: Option<&'a AgentFS>,
+    db: &'a AgentFS,
+    on_global: bool,
+) -> (Option<&'a AgentFS>, Option<&'a AgentFS>) {
+    if on_global {
+        (Some(db), None)
     } else {
-        String::new()
-    };
+        (global_db, Some(db))
+    }
+}
+
+/// Say which skills can't read the files next to their SKILL.md, because
+/// they were read from disk with no DB to bundle those into.
+fn warn_unbundled(registry: &SkillRegistry) {
+    for skill in registry.unbundled() {
+        let fix = match skill.scope {
+            SkillScope::Project => "run `infinity-agent init` to create a project database",
+            SkillScope::Global => "run `infinity-agent skills --global list` to create the global database",
+        };
+        eprintln!(
+            "The /{} skill can't read the files in {}: {fix}.",
+            skill.name,
+            skill.dir.display()
+        );
+    }
+}
+
+/// Default system prompt of chat sessions, before the profile and sections.
+const BASE_SYSTEM_PROMPT: &str = "You are Infinity Agent, an AI coding assistant.\n\n\
+     You have two separate environments:\n\n\
+     1. **Workspace (AgentFS)** — a persistent virtual filesystem stored in a database.\n\
+     Tools: read_file, write_file, multi_edit, list_dir, search, glob, grep, tree, kv_get, kv_set.\n\
+     Paths like /src/main.rs live ONLY in this virtual DB — they are NOT on the host disk.\n\n\
+     2. **Host shell** — the user's real machine.\n\
+     Tools: bash, restart_shell. bash runs real commands on the host OS in one persistent \
+     shell, so `cd` and exported variables carry over; restart_shell resets it.\n\
+     Tools: web_search, fetch_url. Find and read web pages and docs as markdown; prefer \
+     them over curl.\n\
+     Files on the host are at normal paths like /tmp/foo.py or ~/project/.\n\n\
+     IMPORTANT RULES:\n\
+     - If the user asks you to write and RUN code, use `bash` to write it to a temp \
+     location on the host (e.g. write via bash: echo '...' > /tmp/script.py) and then \
+     run it with bash. Do NOT write to AgentFS and then try to run it — the virtual \
+     filesystem is not mounted on the host.\n\
+     - Use AgentFS (write_file/read_file) for persistent notes, project files, or \
+     artifacts the user wants to keep across sessions.\n\
+     - Use bash for everything that needs to execute: running code, git, installs, etc.\n\
+     - To search file contents in AgentFS use `grep`, not bash grep — bash cannot see \
+     the virtual filesystem.\n\
+     - For broad exploration (searching many files, reading long docs) use `task` to hand \
+     the work to a subagent and get back only its report.\n\
+     - Keep responses concise. Show code, not explanations unless asked.";
+
+/// System prompt for `skills test`.
+const SKILL_TEST_SYSTEM: &str = "You are Infinity Agent, an AI coding assistant, running a \
+     skill as a dry run. Read-only tools (read_file, list_dir, search, glob, grep, tree, \
+     kv_get, fetch_url, web_search) run as usual. Every other tool call is recorded but not \
+     run, and reports success. Work through the skill as you normally would, then end with \
+     a short numbered plan of the changes you would make.";
+
+/// Run a skill as a dry run against the database and list the tool calls it
+/// would make. The workspace, the conversation and the tool call log are left
+/// alone; only token usage is recorded.
+async fn cmd_skill_test(
+    db_path: &Path,
+    skill: &skills::Skill,
+    args: &str,
+    model: Option<String>,
+) -> anyhow::Result<()> {
+    let project = project::project_config();
+    let model = model
+        .or_else(|| project.model.clone())
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    let max_tokens = project.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let mut config = AgentConfig::from_args(db_path.to_path_buf(), model.clone(), max_tokens, None)?;
+    if !config.auth.is_authenticated() {
+        eprintln!("Not authenticated. Run `infinity-agent login` or set ANTHROPIC_API_KEY.");
+        std::process::exit(1);
+    }
+
+    let anthropic = AnthropicClient::new(model, max_tokens);
+    let providers = project.provider_chain();
+    let chain = build_chain(&providers, &anthropic, max_tokens).unwrap_or_else(|e| {
+        eprintln!("Ignoring provider fallbacks: {e}");
+        Vec::new()
+    });
+    let client = LlmClient::new(Provider::Anthropic(anthropic)).with_chain(chain);
+
+    let db = AgentFS::open(AgentFSConfig::builder(db_path).checkpoint_interval_secs(0).build()).await?;
+    let mut agent = AgentBuilder::new(db)
+        .session_id(format!("skill-test-{}", Uuid::new_v4()))
+        .client(client)
+        .system(SKILL_TEST_SYSTEM)
+        .web(WebClient::new(load_web_config()?))
+        .permissions(
+            Permissions::new(load_permission_config()?)
+                .with_project(project.permissions.clone().unwrap_or_default())
+                .non_interactive(),
+        )
+        .non_interactive()
+        .dry_run()
+        .build();
+
+    agent.run_skill_turn(&mut config.auth, skill, args).await?;
+    display::print_planned_calls(&agent.executor().planned_calls());
+    Ok(())
+}
+
+/// Sections after the profile: skills, then project instructions from INFINITY.md.
+fn compose_sections(skills: &SkillRegistry) -> String {
+    let mut sections = String::new();
+    if let Some(section) = skills.system_prompt_section() {
+        sections.push_str(&section);
+    }
+    if let Some(section) = project::instructions_section() {
+        sections.push_str(&section);
+    }
+    sections
+}
+
+/// Parse SKILL.md content to extract description, allowed tools and body.
+/// If no frontmatter is found, use empty description and the entire content as body.
+fn parse_skill_content(content: &str) -> (String, Vec<String>, String) {
+    match skills::split_frontmatter(content) {
+        Some((mut fields, body)) => {
+            let allowed_tools = skills::allowed_tools(&mut fields);
+            (fields.remove("description").unwrap_or_default(), allowed_tools, body)
+        }
+        None => (String::new(), Vec::new(), content.trim().to_string()),
+    }
+}
 
-    let mut description = String::new();
-    for line in frontmatter.lines() {
-        let line = line.trim();
-        if let Some(val) = line.strip_prefix("description:") {
-            description = val.trim().to_string();
+/// Split a leading `--ttl <ttl>` off `/remember` text.
+fn split_ttl(text: &str) -> Result<(Option<f64>, &str), String> {
+    match text.trim_start().strip_prefix("--ttl ") {
+        Some(rest) => {
+            let rest = rest.trim_start();
+            let (ttl, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if text.trim().is_empty() {
+                return Err("usage: /remember --ttl <ttl> <text>".to_string());
+            }
+            Ok((Some(memory::parse_ttl(ttl)?), text))
         }
+        None => Ok((None, text)),
     }
+}
 
-    (description, body)
+/// Open the database at `db_path` and load memory, as `memory` subcommands
+/// that work on entries need.
+async fn open_memory(db_path: &Path, purpose: &str) -> anyhow::Result<MemoryManager> {
+    if !db_path.exists() {
+        eprintln!("Database not found: {}", db_path.display());
+        std::process::exit(1);
+    }
+
+    let afs_config = AgentFSConfig::builder(db_path)
+        .checkpoint_interval_secs(0)
+        .build();
+    let db = Arc::new(AgentFS::open(afs_config).await?);
+    let manager = MemoryManager::from_config(load_memory_config(), db)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    manager.on_session_start(purpose).await;
+    Ok(manager)
+}
+
+/// Open `path` in $VISUAL or $EDITOR (vi if neither is set) and wait for it.
+fn run_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run {editor}: {e}"))?;
+    if !status.success() {
+        anyhow::bail!("{editor} exited with {status}");
+    }
+    Ok(())
 }
 
 async fn cmd_memory(action: MemoryAction) -> anyhow::Result<()> {
     match action {
-        MemoryAction::Show => {
+        MemoryAction::Show { ids } => {
             let db_path = default_db_path();
             if !db_path.exists() {
                 eprintln!("Database not found: {}", db_path.display());
@@ -412,6 +967,12 @@ async fn cmd_memory(action: MemoryAction) -> anyhow::Result<()> {
             // Initialize providers to load data
             manager.on_session_start("__show__").await;
 
+            if ids {
+                let entries = manager.entry_ids().await.map_err(|e| anyhow::anyhow!("{e}"))?;
+                display::print_memory_ids(&entries);
+                return Ok(());
+            }
+
             // Show each provider's context
             let mut found_any = false;
             for provider in manager.providers() {
@@ -442,11 +1003,15 @@ async fn cmd_memory(action: MemoryAction) -> anyhow::Result<()> {
             let db_arc = Arc::new(db);
 
             // Count entries by prefix
+            let preference_entries = db_arc.kv.list_prefix("memory:preference:").await.unwrap_or_default();
             let playbook_entries = db_arc.kv.list_prefix("memory:playbook:").await.unwrap_or_default();
             let episode_entries = db_arc.kv.list_prefix("memory:episode:").await.unwrap_or_default();
             let tool_entries = db_arc.kv.list_prefix("memory:tool_pattern:").await.unwrap_or_default();
+            let has_brief = !db_arc.kv.list_prefix("memory:codebase:").await.unwrap_or_default().is_empty();
 
             let mut stats = vec![
+                ("preferences".to_string(), format!("{} preferences", preference_entries.len())),
+                ("codebase".to_string(), if has_brief { "project brief stored" } else { "no project brief" }.to_string()),
                 ("playbook".to_string(), format!("{} entries", playbook_entries.len())),
                 ("episodes".to_string(), format!("{} episodes", episode_entries.len())),
                 ("tool_patterns".to_string(), format!("{} tools tracked", tool_entries.len())),
@@ -472,7 +1037,7 @@ async fn cmd_memory(action: MemoryAction) -> anyhow::Result<()> {
             display::print_memory_stats(&stats);
             // db_arc is dropped here
         }
-        MemoryAction::Search { query, limit, db } => {
+        MemoryAction::Search { query, limit, provider, tier, since, category, format, db } => {
             if !db.exists() {
                 eprintln!("Database not found: {}", db.display());
                 std::process::exit(1);
@@ -492,15 +1057,91 @@ async fn cmd_memory(action: MemoryAction) -> anyhow::Result<()> {
             // Initialize to load and index data
             manager.on_session_start("__search__").await;
 
-            match manager.search(&query, limit).await {
-                Ok(results) => {
-                    display::print_search_results(&query, &results);
-                }
+            let filter = memory::search::SearchFilter {
+                provider,
+                tier: tier.as_deref().map(memory::tiers::MemoryTier::from_str),
+                since,
+                category,
+            };
+            match manager.search_filtered(&query, &filter, limit).await {
+                Ok(results) => match format {
+                    ListFormat::Text => display::print_search_results(&query, &results),
+                    ListFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+                },
                 Err(e) => {
                     eprintln!("Search error: {e}");
                 }
             }
         }
+        MemoryAction::Timeline { limit, format, db } => {
+            if !db.exists() {
+                eprintln!("Database not found: {}", db.display());
+                std::process::exit(1);
+            }
+
+            let afs_config = AgentFSConfig::builder(&db)
+                .checkpoint_interval_secs(0)
+                .build();
+            let db_inst = AgentFS::open(afs_config).await?;
+
+            let timeline = memory::timeline::build(&db_inst, limit)
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+            match format {
+                ListFormat::Text => display::print_timeline(&timeline),
+                ListFormat::Json => println!("{}", serde_json::to_string_pretty(&timeline)?),
+            }
+        }
+        MemoryAction::Add { text, category, ttl, db } => {
+            if !db.exists() {
+                eprintln!("Database not found: {}", db.display());
+                std::process::exit(1);
+            }
+
+            let afs_config = AgentFSConfig::builder(&db)
+                .checkpoint_interval_secs(0)
+                .build();
+            let db_inst = AgentFS::open(afs_config).await?;
+            let db_arc = Arc::new(db_inst);
+
+            let mem_config = load_memory_config();
+            let manager = MemoryManager::from_config(mem_config, Arc::clone(&db_arc))
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+            // Load existing entries so duplicates are caught
+            manager.on_session_start("__add__").await;
+
+            manager
+                .remember(category.clone(), &text, "cli", ttl)
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+            println!("Added {category} to the playbook.");
+        }
+        MemoryAction::Delete { id, db } => {
+            let manager = open_memory(&db, "__delete__").await?;
+            let id = manager.delete_entry(&id).await.map_err(|e| anyhow::anyhow!("{e}"))?;
+            println!("Deleted {id}.");
+        }
+        MemoryAction::Edit { id, db } => {
+            let manager = open_memory(&db, "__edit__").await?;
+            let (id, original) = manager.get_entry(&id).await.map_err(|e| anyhow::anyhow!("{e}"))?;
+
+            let file = tempfile::Builder::new()
+                .prefix("infinity-memory-")
+                .suffix(".json")
+                .tempfile()?;
+            std::fs::write(file.path(), &original)?;
+            run_editor(file.path())?;
+            let edited = std::fs::read_to_string(file.path())?;
+
+            if edited.trim() == original.trim() {
+                println!("No changes to {id}.");
+            } else {
+                manager.update_entry(&id, &edited).await.map_err(|e| anyhow::anyhow!("{e}"))?;
+                println!("Updated {id}.");
+            }
+        }
         MemoryAction::Compact { db } => {
             if !db.exists() {
                 eprintln!("Database not found: {}", db.display());
@@ -530,6 +1171,75 @@ async fn cmd_memory(action: MemoryAction) -> anyhow::Result<()> {
                 }
             }
         }
+        MemoryAction::Decay { db } => {
+            if !db.exists() {
+                eprintln!("Database not found: {}", db.display());
+                std::process::exit(1);
+            }
+
+            let afs_config = AgentFSConfig::builder(&db)
+                .checkpoint_interval_secs(0)
+                .build();
+            let db_inst = AgentFS::open(afs_config).await?;
+            let db_arc = Arc::new(db_inst);
+
+            let mem_config = load_memory_config();
+            let manager = MemoryManager::from_config(mem_config, Arc::clone(&db_arc))
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+            // Initialize to load data
+            manager.on_session_start("__decay__").await;
+
+            match manager.decay().await {
+                Ok((decayed, rebalanced)) => {
+                    println!("Decayed {decayed} stale entries; {rebalanced} tier changes.");
+                }
+                Err(e) => {
+                    eprintln!("Decay error: {e}");
+                }
+            }
+        }
+        MemoryAction::Snapshot { action, db } => {
+            if !db.exists() {
+                eprintln!("Database not found: {}", db.display());
+                std::process::exit(1);
+            }
+
+            let afs_config = AgentFSConfig::builder(&db)
+                .checkpoint_interval_secs(0)
+                .build();
+            let db_inst = AgentFS::open(afs_config).await?;
+
+            match action {
+                SnapshotAction::Create { name } => {
+                    let info = memory::snapshot::create(&db_inst, &name)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{e}"))?;
+                    println!("Saved snapshot {} ({} entries).", info.name, info.entry_count);
+                }
+                SnapshotAction::List => {
+                    let snapshots = memory::snapshot::list(&db_inst)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{e}"))?;
+                    if snapshots.is_empty() {
+                        println!("No memory snapshots.");
+                    }
+                    for s in &snapshots {
+                        println!("{}  {}  {} entries", s.created, s.name, s.entry_count);
+                    }
+                }
+                SnapshotAction::Restore { name } => {
+                    let info = memory::snapshot::restore(&db_inst, &name)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("{e}"))?;
+                    println!(
+                        "Restored snapshot {} from {} ({} entries).",
+                        info.name, info.created, info.entry_count
+                    );
+                }
+            }
+        }
         MemoryAction::Clear { db } => {
             if !db.exists() {
                 eprintln!("Database not found: {}", db.display());
@@ -543,7 +1253,7 @@ async fn cmd_memory(action: MemoryAction) -> anyhow::Result<()> {
 
             // Delete all memory keys
             let mut deleted = 0usize;
-            for prefix in &["memory:playbook:", "memory:episode:", "memory:tool_pattern:"] {
+            for prefix in &["memory:preference:", "memory:codebase:", "memory:playbook:", "memory:episode:", "memory:archive:", "memory:tool_pattern:"] {
                 let entries = db_inst.kv.list_prefix(prefix).await.unwrap_or_default();
                 for entry in &entries {
                     let _ = db_inst.kv.delete(&entry.key).await;
@@ -582,8 +1292,8 @@ async fn cmd_sessions(db_path: PathBuf, limit: i64) -> anyhow::Result<()> {
         println!("No sessions found.");
     } else {
         println!(
-            "{:<38} {:<10} {:<20} {}",
-            "SESSION ID", "STATUS", "STARTED", "AGENT"
+            "{:<38} {:<10} {:<20} AGENT",
+            "SESSION ID", "STATUS", "STARTED"
         );
         println!("{}", "-".repeat(80));
         for s in &sessions {
@@ -605,7 +1315,7 @@ async fn cmd_sessions(db_path: PathBuf, limit: i64) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn cmd_dashboard(db_path: PathBuf, port: u16) -> anyhow::Result<()> {
+async fn cmd_dashboard(db_path: PathBuf, host: &str, port: u16) -> anyhow::Result<()> {
     if !db_path.exists() {
         eprintln!("Database not found: {}", db_path.display());
         std::process::exit(1);
@@ -618,6 +1328,7 @@ async fn cmd_dashboard(db_path: PathBuf, port: u16) -> anyhow::Result<()> {
     let db_arc = Arc::new(db);
 
     let mem_config = load_memory_config();
+    let memory_enabled = mem_config.enabled;
     let memory = match MemoryManager::from_config(mem_config, Arc::clone(&db_arc)).await {
         Ok(m) => {
             m.on_session_start("__dashboard__").await;
@@ -626,7 +1337,7 @@ async fn cmd_dashboard(db_path: PathBuf, port: u16) -> anyhow::Result<()> {
         Err(e) => {
             eprintln!("Warning: memory system init failed: {e}");
             // Create a minimal memory manager with disabled config
-            let mut cfg = crate::memory::MemoryConfig::default();
+            let mut cfg = agentfs_agent_core::memory::MemoryConfig::default();
             cfg.providers.clear();
             Arc::new(
                 MemoryManager::from_config(cfg, Arc::clone(&db_arc))
@@ -636,11 +1347,20 @@ async fn cmd_dashboard(db_path: PathBuf, port: u16) -> anyhow::Result<()> {
         }
     };
 
-    dashboard::run_dashboard(db_arc, memory, port).await
+    let chat = webchat::ChatConfig {
+        db_path,
+        memory: memory_enabled.then(|| Arc::clone(&memory)),
+    };
+
+    dashboard::run_dashboard(db_arc, memory, chat, host, port).await
 }
 
 async fn resolve_last_session(db: &AgentFS) -> (String, bool) {
-    let recent = db.sessions.list_recent(1).await.unwrap_or_default();
+    let recent = db
+        .sessions
+        .list_recent_by_agent(AGENT_NAME, 1)
+        .await
+        .unwrap_or_default();
     match recent.first() {
         Some(s) => {
             println!("Resuming session: {}", s.session_id);
@@ -653,14 +1373,70 @@ async fn resolve_last_session(db: &AgentFS) -> (String, bool) {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn cmd_chat(
     db_path: PathBuf,
-    model: String,
-    max_tokens: u32,
+    model: Option<String>,
+    max_tokens: Option<u32>,
     system: Option<String>,
+    profile: Option<String>,
     prompt: Option<String>,
     resume: Option<String>,
+    limits: TurnLimits,
+    output_format: OutputFormat,
+    scope: ToolScope,
 ) -> anyhow::Result<()> {
+    // Input piped on stdin goes with -p, or is the prompt without it. With
+    // -p, a stdin that stays silent is left unread.
+    let piped = if prompt.is_some() && !piped::ready(piped::STDIN_WAIT) {
+        eprintln!(
+            "No input on stdin after {}s; running without it (redirect stdin from /dev/null to skip the wait)",
+            piped::STDIN_WAIT.as_secs()
+        );
+        Ok(None)
+    } else {
+        piped::read_stdin()
+    };
+    let piped = match piped {
+        Ok(piped) => piped,
+        Err(e) => {
+            eprintln!("Failed to read stdin: {e}");
+            std::process::exit(1);
+        }
+    };
+    let (prompt, piped) = match (prompt, piped) {
+        (None, Some(piped)) => {
+            if piped.truncated {
+                eprintln!(
+                    "Prompt on stdin cut to its last {} of {} bytes",
+                    piped.text.len(),
+                    piped.size
+                );
+            }
+            (Some(piped.text), None)
+        }
+        other => other,
+    };
+    if prompt.is_none() && output_format.is_structured() {
+        eprintln!("--output-format json and stream-json need a prompt: pass -p or pipe one on stdin.");
+        std::process::exit(1);
+    }
+
+    // Structured output owns stdout; the turn's terminal rendering is silenced.
+    display::set_quiet(output_format.is_structured());
+
+    // Flags win over the project's .infinity/config.toml, which wins over defaults.
+    let project = project::project_config();
+    let model = model
+        .or_else(|| project.model.clone())
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    let max_tokens = max_tokens.or(project.max_tokens).unwrap_or(DEFAULT_MAX_TOKENS);
+    if let Some(theme) = &project.theme {
+        if let Err(e) = highlight::set_theme(theme) {
+            eprintln!("Ignoring theme in {}: {e}", project::CONFIG_FILE);
+        }
+    }
+
     let mut config = AgentConfig::from_args(db_path.clone(), model.clone(), max_tokens, system)?;
 
     if !config.auth.is_authenticated() {
@@ -695,7 +1471,9 @@ async fn cmd_chat(
         Some(id) => {
             match db.sessions.get(id).await {
                 Ok(_) => {
-                    println!("Resuming session: {id}");
+                    if !output_format.is_structured() {
+                        println!("Resuming session: {id}");
+                    }
                     (id.clone(), true)
                 }
                 Err(_) => {
@@ -704,9 +1482,13 @@ async fn cmd_chat(
                 }
             }
         }
+        // Structured or piped runs can't ask, and start fresh unless told to resume.
+        None if output_format.is_structured() || !std::io::stdin().is_terminal() => {
+            (Uuid::new_v4().to_string(), false)
+        }
         None => {
             // Check if there's a previous session with saved messages
-            let recent = db.sessions.list_recent(1).await?;
+            let recent = db.sessions.list_recent_by_agent(AGENT_NAME, 1).await?;
             if let Some(s) = recent.first() {
                 let key = format!("session:messages:{}", s.session_id);
                 match db.kv.get(&key).await {
@@ -719,10 +1501,7 @@ async fn cmd_chat(
                         };
                         let started = &s.started_at[..19.min(s.started_at.len())];
                         println!(
-                            "\n  {}Previous session found:{} {}{}...{} ({})",
-                            "\x1b[1m", "\x1b[0m",
-                            "\x1b[36m", short_id, "\x1b[0m",
-                            started,
+                            "\n  \x1b[1mPrevious session found:\x1b[0m \x1b[36m{short_id}...\x1b[0m ({started})",
                         );
                         print!("  Resume previous session? [Y/n] ");
                         std::io::stdout().flush().ok();
@@ -745,10 +1524,19 @@ async fn cmd_chat(
         }
     };
 
+    // Model client: --model on Anthropic, then any configured fallbacks
+    let anthropic = AnthropicClient::new(model.clone(), max_tokens);
+    let providers = project.provider_chain();
+    let chain = build_chain(&providers, &anthropic, max_tokens).unwrap_or_else(|e| {
+        eprintln!("Ignoring provider fallbacks: {e}");
+        Vec::new()
+    });
+    let client = LlmClient::new(Provider::Anthropic(anthropic)).with_chain(chain);
+
     // Start or reopen session
     if !is_resume {
         db.sessions
-            .start(&session_id, Some("infinity-agent"), Some("anthropic"), None)
+            .start(&session_id, Some(AGENT_NAME), Some(client.provider().name()), None)
             .await?;
         db.events
             .log(Some(&session_id), "session_start", None, Some(&model))
@@ -765,8 +1553,20 @@ async fn cmd_chat(
 
     let mcp_arc = Arc::new(Mutex::new(mcp_manager));
 
-    // Load skills (from DB with filesystem fallback)
-    let skill_registry = SkillRegistry::load_from_db(&db).await;
+    // Sync the skills directories into the DB, then load skills from it
+    let on_global = is_global_db(&db_path);
+    let global_db = open_global_db(&db_path).await?;
+    let (global_skills, project_skills) = skill_dbs(global_db.as_ref(), &db, on_global);
+    let mut skill_watcher = SkillWatcher::new();
+    skill_watcher.poll(global_skills, project_skills).await;
+    let mut skill_registry = SkillRegistry::load_from_db(global_skills, project_skills).await;
+    warn_unbundled(&skill_registry);
+
+    // Load user-defined slash commands
+    let command_registry = CommandRegistry::load();
+
+    // Notifications for long turns
+    let notify_config = load_notify_config();
 
     // Load memory system
     let mem_config = load_memory_config();
@@ -796,61 +1596,105 @@ async fn cmd_chat(
         AgentFS::open(afs_config2).await?
     };
 
-    let client = AnthropicClient::new(model.clone(), max_tokens);
-    let executor = ToolExecutor::new(executor_db, session_id.clone()).with_mcp(Arc::clone(&mcp_arc));
-
-    let mut default_system = config.system_prompt.take().unwrap_or_else(|| {
-        "You are Infinity Agent, an AI coding assistant.\n\n\
-         You have two separate environments:\n\n\
-         1. **Workspace (AgentFS)** — a persistent virtual filesystem stored in a database.\n\
-         Tools: read_file, write_file, list_dir, search, tree, kv_get, kv_set.\n\
-         Paths like /src/main.rs live ONLY in this virtual DB — they are NOT on the host disk.\n\n\
-         2. **Host shell** — the user's real machine.\n\
-         Tool: bash. This runs real commands on the host OS.\n\
-         Files on the host are at normal paths like /tmp/foo.py or ~/project/.\n\n\
-         IMPORTANT RULES:\n\
-         - If the user asks you to write and RUN code, use `bash` to write it to a temp \
-         location on the host (e.g. write via bash: echo '...' > /tmp/script.py) and then \
-         run it with bash. Do NOT write to AgentFS and then try to run it — the virtual \
-         filesystem is not mounted on the host.\n\
-         - Use AgentFS (write_file/read_file) for persistent notes, project files, or \
-         artifacts the user wants to keep across sessions.\n\
-         - Use bash for everything that needs to execute: running code, git, installs, etc.\n\
-         - Keep responses concise. Show code, not explanations unless asked."
-            .to_string()
-    });
-
-    // Inject skills section into system prompt
-    if let Some(section) = skill_registry.system_prompt_section() {
-        default_system.push_str(&section);
+    let mut permissions = Permissions::new(load_permission_config()?)
+        .with_project(project.permissions.clone().unwrap_or_default())
+        .with_scope(scope);
+    if prompt.is_some() {
+        // Nobody is watching a single-prompt run; anything that would ask is refused.
+        permissions = permissions.non_interactive();
+    }
+    let base_system = config
+        .system_prompt
+        .take()
+        .unwrap_or_else(|| BASE_SYSTEM_PROMPT.to_string());
+
+    let mut system_sections = compose_sections(&skill_registry);
+    let mut profile = match profile.as_deref().map(Profile::load).transpose() {
+        Ok(profile) => profile,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let default_system = profiles::compose(&base_system, profile.as_ref(), &system_sections);
+
+    let agent_provider = client.provider().name().to_string();
+    let mut builder = AgentBuilder::new(executor_db)
+        .session_id(session_id.clone())
+        .client(client)
+        .system(default_system)
+        .mcp(Arc::clone(&mcp_arc), mcp_tools)
+        .web(WebClient::new(load_web_config()?))
+        .permissions(permissions)
+        .hooks(Hooks::new(load_hooks_config()?))
+        .limits(limits);
+    if prompt.is_some() {
+        builder = builder.non_interactive();
+    }
+    if output_format == OutputFormat::StreamJson {
+        builder = builder.stream_json();
     }
-
-    let mut agent = Agent::new(
-        client,
-        executor,
-        Some(default_system),
-        session_id.clone(),
-        model.clone(),
-        mcp_tools,
-    );
-
-    // Attach memory manager if available
     if let Some(ref mgr) = memory_manager {
-        agent = agent.with_memory(Arc::clone(mgr));
+        builder = builder.memory(Arc::clone(mgr));
     }
+    let mut agent = builder.build();
+    agent.executor().hooks.session_start(&session_id, &model, is_resume).await;
 
     // If resuming, load persisted messages
     if is_resume {
         let count = agent.load_messages().await?;
-        if count > 0 {
+        if count > 0 && !output_format.is_structured() {
             println!("Loaded {count} messages from previous session.");
         }
     }
 
     // Single-prompt mode
     if let Some(prompt) = prompt {
-        agent.run_turn(&mut config.auth, &prompt).await?;
-        println!();
+        let started = std::time::Instant::now();
+        if output_format == OutputFormat::StreamJson {
+            output::emit(&output::init_event(
+                &session_id,
+                &agent_provider,
+                agent.model(),
+                &agent.tool_names(),
+            ));
+        }
+        let (text, dropped) = attachments::detect_dropped(&prompt);
+        let text = if dropped.is_empty() { prompt } else { text };
+        let (text, mentions) = mentions::expand(&agent.executor().db, &text).await;
+        display::print_mentions(&mentions);
+        let text = match &piped {
+            Some(piped) => piped.append_to(&text),
+            None => text,
+        };
+        let outcome = if dropped.is_empty() {
+            agent.run_turn(&mut config.auth, &text).await
+        } else {
+            let prepared = async {
+                let images = dropped
+                    .iter()
+                    .map(|path| Attachment::load(path))
+                    .collect::<agentfs_agent_core::error::Result<Vec<_>>>()?;
+                attachments::prepare(&agent.executor().db, &session_id, &images, &text).await
+            };
+            match prepared.await {
+                Ok((text, blocks)) => agent.run_turn_with_images(&mut config.auth, &text, blocks).await,
+                Err(e) => Err(e),
+            }
+        };
+        let failed = outcome.is_err();
+        if output_format.is_structured() {
+            let outcome = outcome.map_err(|e| e.to_string());
+            output::emit(&output::result_event(
+                &session_id,
+                &outcome,
+                agent.last_turn(),
+                started.elapsed(),
+            ));
+        } else {
+            outcome?;
+            println!();
+        }
 
         // End memory session
         if let Some(ref mgr) = memory_manager {
@@ -858,13 +1702,23 @@ async fn cmd_chat(
         }
 
         let executor = agent.into_executor();
-        executor.db.sessions.end(&session_id, "completed").await?;
+        let status = if failed { "failed" } else { "completed" };
+        executor.hooks.session_end(&session_id, status).await;
+        executor.db.sessions.end(&session_id, status).await?;
         mcp_arc.lock().await.shutdown().await;
-        executor.db.close().await?;
+        if let Some(db) = Arc::into_inner(executor.db) {
+            db.close().await?;
+        }
         // Close memory DB
         if let Some(db) = Arc::into_inner(db_arc) {
             db.close().await?;
         }
+        if let Some(db) = global_db {
+            db.close().await?;
+        }
+        if failed {
+            std::process::exit(1);
+        }
         return Ok(());
     }
 
@@ -900,7 +1754,9 @@ async fn cmd_chat(
 
     // Built-in tool names
     let builtin_tools: Vec<&str> = vec![
-        "read_file", "write_file", "bash", "list_dir", "search", "tree", "kv_get", "kv_set",
+        "read_file", "write_file", "multi_edit", "bash", "restart_shell", "list_dir", "search", "glob", "grep",
+        "tree", "kv_get", "kv_set",
+        "fetch_url", "web_search", "task",
     ];
 
     // Count loaded messages for resume
@@ -913,13 +1769,23 @@ async fn cmd_chat(
         memory_info_string.as_deref(),
         &builtin_tools,
         &mcp_summary,
+        &project::files_in_use(),
     );
 
     let mut rl = rustyline::DefaultEditor::new()?;
     let prompt = display::prompt_string();
+    // Images queued by /attach for the next message
+    let mut pending: Vec<Attachment> = Vec::new();
 
     loop {
-        display::print_separator();
+        let (input_t, output_t) = agent.token_counts();
+        display::print_status_line(
+            agent.model(),
+            agent.provider_name(),
+            agent.context_usage(),
+            input_t + output_t,
+            agent.session_cost(),
+        );
         let line = match rl.readline(&prompt) {
             Ok(line) => line,
             Err(rustyline::error::ReadlineError::Interrupted) => {
@@ -942,6 +1808,106 @@ async fn cmd_chat(
             continue;
         }
 
+        // Pick up edited skill files before dispatching the input
+        let (global_skills, project_skills) = skill_dbs(global_db.as_ref(), &db_arc, on_global);
+        if skill_watcher.poll(global_skills, project_skills).await {
+            skill_registry = SkillRegistry::load_from_db(global_skills, project_skills).await;
+            system_sections = compose_sections(&skill_registry);
+            agent.set_system(profiles::compose(&base_system, profile.as_ref(), &system_sections));
+            display::print_skills_reloaded(skill_registry.list().len());
+        }
+
+        if let Some(arg) = input.strip_prefix("/attach").filter(|a| a.is_empty() || a.starts_with(' ')) {
+            let arg = arg.trim();
+            if arg.is_empty() {
+                display::print_attachments(&pending);
+                continue;
+            }
+            let (rest, paths) = attachments::detect_dropped(arg);
+            let mut loaded: Vec<_> = paths.iter().map(|p| Attachment::load(p)).collect();
+            if !rest.is_empty() {
+                loaded.push(Attachment::load(&attachments::host_path(&rest)));
+            }
+            for attachment in loaded {
+                match attachment {
+                    Ok(attachment) => pending.push(attachment),
+                    Err(e) => eprintln!("Error: {e}"),
+                }
+            }
+            display::print_attachments(&pending);
+            continue;
+        }
+
+        if let Some(arg) = input.strip_prefix("/diff").filter(|a| a.is_empty() || a.starts_with(' ')) {
+            let arg = arg.trim();
+            match agent.session_changes().await {
+                Ok(mut changes) => {
+                    if !arg.is_empty() {
+                        let dir = format!("{}/", arg.trim_end_matches('/'));
+                        changes.retain(|c| c.path == arg || c.path.starts_with(&dir));
+                    }
+                    display::print_diff(&changes);
+                }
+                Err(e) => eprintln!("Error: {e}"),
+            }
+            continue;
+        }
+
+        if let Some(text) = input.strip_prefix("/remember ") {
+            match memory_manager {
+                Some(ref mgr) => {
+                    let saved = match text.trim_start().strip_prefix("preference:") {
+                        Some(preference) => mgr
+                            .remember_preference(preference, &session_id)
+                            .await
+                            .map(|()| "Saved preference."),
+                        None => match split_ttl(text) {
+                            Ok((ttl, text)) => mgr
+                                .remember(Category::Strategy, text, &session_id, ttl)
+                                .await
+                                .map(|()| "Added strategy to the playbook."),
+                            Err(e) => Err(agentfs_agent_core::error::AgentError::Memory(e)),
+                        },
+                    };
+                    match saved {
+                        Ok(message) => println!("{message}"),
+                        Err(e) => eprintln!("Error: {e}"),
+                    }
+                }
+                None => println!("Memory system is not enabled."),
+            }
+            continue;
+        }
+
+        if let Some(name) = input.strip_prefix("/theme ") {
+            match highlight::set_theme(name.trim()) {
+                Ok(()) => println!("Code blocks now use the {} theme.", name.trim()),
+                Err(e) => eprintln!("Error: {e}"),
+            }
+            continue;
+        }
+
+        if let Some(name) = input.strip_prefix("/profile ") {
+            let name = name.trim();
+            let switched = if name == "none" {
+                Ok(None)
+            } else {
+                Profile::load(name).map(Some)
+            };
+            match switched {
+                Ok(switched) => {
+                    profile = switched;
+                    agent.set_system(profiles::compose(&base_system, profile.as_ref(), &system_sections));
+                    match &profile {
+                        Some(p) => println!("Switched to profile {}.", p.name),
+                        None => println!("Profile cleared."),
+                    }
+                }
+                Err(e) => eprintln!("Error: {e}"),
+            }
+            continue;
+        }
+
         match input {
             "/quit" | "/exit" => break,
             "/clear" => {
@@ -963,8 +1929,58 @@ async fn cmd_chat(
                 println!("Session ID: {session_id}");
                 continue;
             }
+            "/help" => {
+                display::print_help(&skill_registry.list(), &command_registry.list());
+                continue;
+            }
+            "/profile" => {
+                display::print_profiles(&profiles::list(), profile.as_ref().map(|p| p.name.as_str()));
+                continue;
+            }
+            "/theme" => {
+                display::print_themes(&highlight::theme_names(), &highlight::theme_name());
+                continue;
+            }
+            "/undo" => {
+                match agent.undo().await {
+                    Ok(Some(applied)) => display::print_checkpoint_applied("Undid", &applied),
+                    Ok(None) => println!("Nothing to undo."),
+                    Err(e) => eprintln!("Error: {e}"),
+                }
+                continue;
+            }
+            "/redo" => {
+                match agent.redo().await {
+                    Ok(Some(applied)) => display::print_checkpoint_applied("Redid", &applied),
+                    Ok(None) => println!("Nothing to redo."),
+                    Err(e) => eprintln!("Error: {e}"),
+                }
+                continue;
+            }
+            "/checkpoints" => {
+                match agent.checkpoints().await {
+                    Ok(history) => display::print_checkpoints(&history),
+                    Err(e) => eprintln!("Error: {e}"),
+                }
+                continue;
+            }
+            "/permissions" => {
+                let executor = agent.executor();
+                match executor.permissions.describe(&executor.db.kv).await {
+                    Ok(text) => print!("{text}"),
+                    Err(e) => eprintln!("Error: {e}"),
+                }
+                continue;
+            }
+            "/permissions reset" => {
+                match agent.executor().db.kv.delete_prefix(ALWAYS_KEY_PREFIX).await {
+                    Ok(n) => println!("Forgot {n} remembered approvals."),
+                    Err(e) => eprintln!("Error: {e}"),
+                }
+                continue;
+            }
             "/skills" => {
-                display::print_skills_list(&skill_registry.list());
+                display::print_skills_list(&skill_registry.skills());
                 continue;
             }
             "/mcp" => {
@@ -985,6 +2001,11 @@ async fn cmd_chat(
                 if let Some(ref mgr) = memory_manager {
                     // Get stats from each provider via KV prefix counts
                     let executor = agent.executor();
+                    let preference_count = executor.db.kv
+                        .list_prefix("memory:preference:")
+                        .await
+                        .map(|v| v.len())
+                        .unwrap_or(0);
                     let playbook_count = executor.db.kv
                         .list_prefix("memory:playbook:")
                         .await
@@ -1002,6 +2023,7 @@ async fn cmd_chat(
                         .unwrap_or(0);
 
                     let mut stats = vec![
+                        ("preferences".to_string(), format!("{preference_count} preferences")),
                         ("playbook".to_string(), format!("{playbook_count} entries")),
                         ("episodes".to_string(), format!("{episode_count} episodes")),
                         ("tool_patterns".to_string(), format!("{tool_count} tools tracked")),
@@ -1019,6 +2041,19 @@ async fn cmd_chat(
                         };
                         stats.push(("pressure".to_string(), pressure_str.to_string()));
                     }
+                    if mgr.has_reflector() {
+                        if let Ok(usage) = mgr.reflection_usage(&session_id).await {
+                            stats.push((
+                                "reflection".to_string(),
+                                format!(
+                                    "{} calls, {} tokens, {} this session",
+                                    usage.record_count,
+                                    usage.total_input_tokens + usage.total_output_tokens,
+                                    display::format_cost(usage.total_cost_microcents),
+                                ),
+                            ));
+                        }
+                    }
 
                     display::print_memory_stats(&stats);
                     println!(
@@ -1036,19 +2071,50 @@ async fn cmd_chat(
         // Check if input matches a skill invocation
         if let Some((skill, args)) = skill_registry.matches_command(input) {
             rl.add_history_entry(input)?;
-            let args_str = if args.is_empty() {
-                format!("Run the /{} skill", skill.name)
-            } else {
-                args.to_string()
+            let before = agent.message_count();
+            let started = std::time::Instant::now();
+            let result = tokio::select! {
+                r = agent.run_skill_turn(&mut config.auth, skill, args) => Some(r),
+                _ = tokio::signal::ctrl_c() => None,
             };
+            match result {
+                Some(Ok(_)) => notify::turn_finished(&notify_config, &agent.last_turn(), started.elapsed(), None),
+                Some(Err(e)) => {
+                    eprintln!("\nError: {e}");
+                    notify::turn_finished(&notify_config, &agent.last_turn(), started.elapsed(), Some(&e.to_string()));
+                }
+                None => {
+                    agent.rollback_to(before);
+                    display::print_cancelled();
+                }
+            }
+            continue;
+        }
+
+        // Check if input invokes a user-defined command
+        if let Some((command, args)) = command_registry.matches(input) {
+            rl.add_history_entry(input)?;
+            let (text, mentions) = mentions::expand_typed(&agent.executor().db, &command.expand(args), args).await;
+            display::print_mentions(&mentions);
             let before = agent.message_count();
+            let started = std::time::Instant::now();
+            let turn = async {
+                if command.allowed_tools.is_empty() {
+                    agent.run_turn(&mut config.auth, &text).await
+                } else {
+                    agent.run_turn_with_tools(&mut config.auth, &text, &command.allowed_tools).await
+                }
+            };
             let result = tokio::select! {
-                r = agent.run_skill_turn(&mut config.auth, &skill.body, &args_str) => Some(r),
+                r = turn => Some(r),
                 _ = tokio::signal::ctrl_c() => None,
             };
             match result {
-                Some(Ok(_)) => {}
-                Some(Err(e)) => eprintln!("\nError: {e}"),
+                Some(Ok(_)) => notify::turn_finished(&notify_config, &agent.last_turn(), started.elapsed(), None),
+                Some(Err(e)) => {
+                    eprintln!("\nError: {e}");
+                    notify::turn_finished(&notify_config, &agent.last_turn(), started.elapsed(), Some(&e.to_string()));
+                }
                 None => {
                     agent.rollback_to(before);
                     display::print_cancelled();
@@ -1059,14 +2125,48 @@ async fn cmd_chat(
 
         rl.add_history_entry(input)?;
 
+        // Image paths dropped into the prompt are attached too
+        let (text, dropped) = attachments::detect_dropped(input);
+        for path in &dropped {
+            match Attachment::load(path) {
+                Ok(attachment) => pending.push(attachment),
+                Err(e) => eprintln!("Error: {e}"),
+            }
+        }
+        let text = if dropped.is_empty() { input.to_string() } else { text };
+
+        // @path mentions are expanded into the message
+        let (text, mentions) = mentions::expand(&agent.executor().db, &text).await;
+        display::print_mentions(&mentions);
+
+        let (text, images) = if pending.is_empty() {
+            (text, Vec::new())
+        } else {
+            let images = std::mem::take(&mut pending);
+            match attachments::prepare(&agent.executor().db, &session_id, &images, &text).await {
+                Ok(prepared) => {
+                    display::print_attachments_sent(&images);
+                    prepared
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    continue;
+                }
+            }
+        };
+
         let before = agent.message_count();
+        let started = std::time::Instant::now();
         let result = tokio::select! {
-            r = agent.run_turn(&mut config.auth, input) => Some(r),
+            r = agent.run_turn_with_images(&mut config.auth, &text, images) => Some(r),
             _ = tokio::signal::ctrl_c() => None,
         };
         match result {
-            Some(Ok(_)) => {}
-            Some(Err(e)) => eprintln!("\nError: {e}"),
+            Some(Ok(_)) => notify::turn_finished(&notify_config, &agent.last_turn(), started.elapsed(), None),
+            Some(Err(e)) => {
+                eprintln!("\nError: {e}");
+                notify::turn_finished(&notify_config, &agent.last_turn(), started.elapsed(), Some(&e.to_string()));
+            }
             None => {
                 agent.rollback_to(before);
                 display::print_cancelled();
@@ -1085,6 +2185,7 @@ async fn cmd_chat(
     }
 
     let executor = agent.into_executor();
+    executor.hooks.session_end(&session_id, "completed").await;
     executor
         .db
         .sessions
@@ -1098,12 +2199,17 @@ async fn cmd_chat(
 
     // Shutdown MCP servers before closing DB
     mcp_arc.lock().await.shutdown().await;
-    executor.db.close().await?;
+    if let Some(db) = Arc::into_inner(executor.db) {
+        db.close().await?;
+    }
 
     // Close memory DB
     if let Some(db) = Arc::into_inner(db_arc) {
         db.close().await?;
     }
+    if let Some(db) = global_db {
+        db.close().await?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,146 @@
+//! Notifications when a long turn ends, for users who tab away while the
+//! agent works.
+//!
+//! Configured in `~/.infinity/notifications.toml`; every field is optional:
+//!
+//! ```toml
+//! after_secs = 30   # notify for turns at least this long (0 = every turn)
+//! desktop = true    # desktop notification
+//! bell = true       # terminal bell
+//! ```
+//!
+//! Terminals don't reliably report whether they have focus, so the turn's
+//! length is what decides.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use agentfs_agent_core::agent::{TurnStats, AGENT_NAME};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+    #[serde(default = "default_after_secs")]
+    pub after_secs: u64,
+    #[serde(default = "default_true")]
+    pub desktop: bool,
+    #[serde(default = "default_true")]
+    pub bell: bool,
+}
+
+fn default_after_secs() -> u64 {
+    30
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            after_secs: default_after_secs(),
+            desktop: true,
+            bell: true,
+        }
+    }
+}
+
+/// Load `~/.infinity/notifications.toml` (defaults if missing). An invalid
+/// file is reported and ignored.
+pub fn load_notify_config() -> NotifyConfig {
+    let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(".infinity");
+    path.push("notifications.toml");
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return NotifyConfig::default();
+    };
+    toml::from_str(&content).unwrap_or_else(|e| {
+        eprintln!("Ignoring invalid {}: {e}", path.display());
+        NotifyConfig::default()
+    })
+}
+
+/// Notify that a turn ended, if it ran long enough. `error` is set when
+/// the turn failed.
+pub fn turn_finished(config: &NotifyConfig, stats: &TurnStats, elapsed: Duration, error: Option<&str>) {
+    if elapsed.as_secs() < config.after_secs || !(config.desktop || config.bell) {
+        return;
+    }
+    if config.bell {
+        let mut stdout = std::io::stdout();
+        let _ = stdout.write_all(b"\x07");
+        let _ = stdout.flush();
+    }
+    if config.desktop {
+        let body = summary(stats, elapsed, error);
+        // Showing a notification can block on the desktop's notification
+        // service; don't hold up the prompt for it.
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = notify_rust::Notification::new()
+                .summary(AGENT_NAME)
+                .body(&body)
+                .show()
+            {
+                tracing::debug!("desktop notification failed: {e}");
+            }
+        });
+    }
+}
+
+/// One-line description of how the turn went.
+fn summary(stats: &TurnStats, elapsed: Duration, error: Option<&str>) -> String {
+    let elapsed = format_elapsed(elapsed);
+    match error {
+        Some(error) => {
+            let error: String = error.chars().take(120).collect();
+            format!("Turn failed after {elapsed}: {error}")
+        }
+        None => {
+            let tools = match stats.tool_calls {
+                0 => String::new(),
+                1 => " · 1 tool call".to_string(),
+                n => format!(" · {n} tool calls"),
+            };
+            format!("Turn finished in {elapsed}{tools}")
+        }
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{secs}s")
+    } else {
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_and_summary() {
+        let config: NotifyConfig = toml::from_str("after_secs = 0\ndesktop = false").unwrap();
+        assert_eq!(config.after_secs, 0);
+        assert!(!config.desktop && config.bell);
+        assert!(toml::from_str::<NotifyConfig>("after = 5").is_err());
+
+        let stats = TurnStats {
+            tool_calls: 5,
+            ..TurnStats::default()
+        };
+        assert_eq!(
+            summary(&stats, Duration::from_secs(72), None),
+            "Turn finished in 1m 12s · 5 tool calls"
+        );
+        assert_eq!(
+            summary(&TurnStats::default(), Duration::from_secs(45), Some("rate limited")),
+            "Turn failed after 45s: rate limited"
+        );
+    }
+}
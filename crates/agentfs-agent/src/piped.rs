@@ -0,0 +1,152 @@
+//! Input piped on stdin.
+//!
+//! `cat bug.log | infinity-agent chat -p "explain this failure"` appends the
+//! piped text to the prompt; without `-p` the piped text is the prompt.
+//! Long input keeps its end, where logs usually say what went wrong.
+//! With `-p`, input that hasn't started after [`STDIN_WAIT`] is skipped, so
+//! a run whose stdin is an open pipe nobody writes to doesn't hang.
+
+use std::io::{IsTerminal, Read};
+use std::os::fd::AsRawFd;
+use std::time::Duration;
+
+use agentfs_agent_core::mentions::fence;
+
+/// Most of the piped input sent to the model; earlier bytes are dropped.
+pub const MAX_PIPED_BYTES: usize = 256 * 1024;
+
+/// Text read from stdin.
+#[derive(Debug)]
+pub struct Piped {
+    pub text: String,
+    /// Bytes read in total.
+    pub size: usize,
+    pub truncated: bool,
+}
+
+impl Piped {
+    /// `prompt` followed by the piped text in a fenced block.
+    pub fn append_to(&self, prompt: &str) -> String {
+        let fence = fence(&self.text);
+        let mut out = format!("{prompt}\n\nInput piped on stdin:\n{fence}\n{}", self.text);
+        if !self.text.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&fence);
+        if self.truncated {
+            out.push_str(&format!(
+                "\n(truncated: last {} of {} bytes)",
+                self.text.len(),
+                self.size
+            ));
+        }
+        out
+    }
+}
+
+/// How long a `-p` run waits for piped input to start.
+pub const STDIN_WAIT: Duration = Duration::from_secs(3);
+
+/// Whether [`read_stdin`] won't block on a silent stdin: stdin is a
+/// terminal (which isn't read) or has input or end of file within `wait`.
+pub fn ready(wait: Duration) -> bool {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return true;
+    }
+    let mut fd = libc::pollfd {
+        fd: stdin.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = wait.as_millis().min(i32::MAX as u128) as i32;
+    // SAFETY: `fd` is one valid pollfd for the duration of the call.
+    unsafe { libc::poll(&mut fd, 1, timeout) > 0 }
+}
+
+/// Read stdin if it isn't a terminal. `None` when nothing was piped.
+pub fn read_stdin() -> Result<Option<Piped>, String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(None);
+    }
+    let mut stdin = stdin.lock();
+    let mut data = Vec::new();
+    let mut size = 0;
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        let n = match stdin.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+        size += n;
+        data.extend_from_slice(&chunk[..n]);
+        // Keep memory bounded however much is piped in.
+        if data.len() > 2 * MAX_PIPED_BYTES {
+            data.drain(..data.len() - MAX_PIPED_BYTES);
+        }
+    }
+    from_bytes(data, size)
+}
+
+fn from_bytes(mut data: Vec<u8>, size: usize) -> Result<Option<Piped>, String> {
+    if data.iter().take(8192).any(|&b| b == 0) {
+        return Err("stdin looks like a binary file".to_string());
+    }
+    let truncated = data.len() > MAX_PIPED_BYTES || data.len() < size;
+    if data.len() > MAX_PIPED_BYTES {
+        data.drain(..data.len() - MAX_PIPED_BYTES);
+    }
+    let mut text = String::from_utf8_lossy(&data).into_owned();
+    if truncated {
+        // Start on a whole line.
+        if let Some(newline) = text.find('\n') {
+            text.drain(..=newline);
+        }
+    }
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Piped {
+        text,
+        size,
+        truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bytes_keeps_the_end() {
+        let mut log = Vec::new();
+        let mut n = 0;
+        while log.len() <= MAX_PIPED_BYTES {
+            log.extend_from_slice(format!("line {n}\n").as_bytes());
+            n += 1;
+        }
+        log.extend_from_slice(b"panicked at src/main.rs:3\n");
+        let size = log.len();
+        let piped = from_bytes(log, size).unwrap().unwrap();
+        assert!(piped.truncated);
+        assert!(piped.text.starts_with("line "));
+        assert!(piped.text.ends_with("panicked at src/main.rs:3\n"));
+        assert!(piped.text.len() <= MAX_PIPED_BYTES);
+
+        let message = piped.append_to("explain this failure");
+        assert!(message.starts_with("explain this failure\n\nInput piped on stdin:\n```\nline "));
+        assert!(message.ends_with(&format!("```\n(truncated: last {} of {size} bytes)", piped.text.len())));
+    }
+
+    #[test]
+    fn test_from_bytes_empty_and_binary() {
+        assert!(from_bytes(b"  \n".to_vec(), 3).unwrap().is_none());
+        assert!(from_bytes(b"\x7fELF\0\0".to_vec(), 6).is_err());
+        let piped = from_bytes(b"hello".to_vec(), 5).unwrap().unwrap();
+        assert!(!piped.truncated);
+        assert_eq!(piped.append_to("hi"), "hi\n\nInput piped on stdin:\n```\nhello\n```");
+    }
+}
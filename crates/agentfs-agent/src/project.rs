@@ -0,0 +1,318 @@
+//! Project-level configuration, read from the working directory.
+//!
+//! `.infinity/config.toml` overrides the global settings for this project;
+//! every field is optional:
+//!
+//! ```toml
+//! model = "claude-opus-4-6"
+//! max_tokens = 16384
+//! theme = "Solarized (dark)"     # code block highlighting
+//! db = ".infinity/workspace.db"   # relative to the project directory; used once trusted
+//!
+//! [providers]                     # chain and models over ~/.infinity/providers.json
+//! chain = ["anthropic", "ollama"]
+//! providers = { ollama = { model = "qwen2.5-coder:14b" } }
+//!
+//! [permissions]                   # can only tighten ~/.infinity/permissions.json
+//! tools = { write_file = "ask" }
+//! rules = [{ tool = "bash", pattern = "rm *", policy = "deny" }]
+//! ```
+//!
+//! A checked-out repository isn't trusted: its config can't loosen the
+//! user's permissions, and can't say where providers connect or which API
+//! key they send (`url`, `api_key` and `api_key_env` are ignored).
+//!
+//! `INFINITY.md` holds project instructions, appended to the system prompt.
+//!
+//! A project workspace (`infinity-agent init`) keeps its own database at
+//! `.infinity/workspace.db`, found from any directory below the project.
+//! A project database holds MCP servers that start at launch and remembered
+//! approvals, so one that came with a repository isn't used until the user
+//! trusts it (`infinity-agent trust`); `init` trusts the database it creates.
+//! Trusted databases are listed in `~/.infinity/trusted.json`.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use agentfs_agent_core::permissions::PermissionConfig;
+use agentfs_agent_core::providers::{load_providers_config, ProvidersConfig};
+
+/// Project configuration file, relative to the project directory.
+pub const CONFIG_FILE: &str = ".infinity/config.toml";
+
+/// Project instructions file, relative to the project directory.
+pub const INSTRUCTIONS_FILE: &str = "INFINITY.md";
+
+/// Project database, relative to the project directory.
+pub const WORKSPACE_DB: &str = ".infinity/workspace.db";
+
+/// Longest instructions file included in the system prompt.
+const MAX_INSTRUCTIONS_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Database path, relative to the project directory. Like a discovered
+    /// workspace database, it is only used once the user trusts it.
+    #[serde(default)]
+    pub db: Option<PathBuf>,
+    /// Syntax highlighting theme for code blocks.
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub providers: Option<ProvidersConfig>,
+    #[serde(default)]
+    pub permissions: Option<PermissionConfig>,
+}
+
+impl ProjectConfig {
+    /// Read `.infinity/config.toml` under `dir` (defaults if missing). An
+    /// invalid file is reported and ignored.
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(CONFIG_FILE);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        let mut config: Self = toml::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid {}: {e}", path.display());
+            Self::default()
+        });
+        config.db = config.db.map(|db| dir.join(db));
+        config
+    }
+
+    /// The provider chain to use: the user's providers, with the project's
+    /// chain and models.
+    pub fn provider_chain(&self) -> ProvidersConfig {
+        self.providers_over(load_providers_config())
+    }
+
+    fn providers_over(&self, mut user: ProvidersConfig) -> ProvidersConfig {
+        let Some(project) = &self.providers else {
+            return user;
+        };
+        user.chain = project.chain.clone();
+        for (name, entry) in &project.providers {
+            if entry.url.is_some() || entry.api_key.is_some() || entry.api_key_env.is_some() {
+                eprintln!(
+                    "Ignoring the url and API key of provider '{name}' in {CONFIG_FILE}; \
+                     set them in ~/.infinity/providers.json"
+                );
+            }
+            if let Some(model) = &entry.model {
+                user.providers.entry(name.clone()).or_default().model = Some(model.clone());
+            }
+        }
+        user
+    }
+}
+
+/// The working directory's project configuration, read once.
+pub fn project_config() -> &'static ProjectConfig {
+    static CONFIG: OnceLock<ProjectConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        std::env::current_dir()
+            .map(|dir| ProjectConfig::load(&dir))
+            .unwrap_or_default()
+    })
+}
+
+/// The nearest project database: `.infinity/workspace.db` in `start` or
+/// the closest directory above it that has one.
+pub fn find_workspace_db(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(WORKSPACE_DB))
+        .find(|db| db.is_file())
+}
+
+/// The project's database: the config's `db`, else the nearest
+/// `.infinity/workspace.db`. Only used once [`is_trusted`].
+pub fn project_db() -> Option<PathBuf> {
+    if let Some(db) = &project_config().db {
+        return Some(db.clone());
+    }
+    let cwd = std::env::current_dir().ok()?;
+    find_workspace_db(&cwd)
+}
+
+/// `~/.infinity/trusted.json`: the databases the user trusts.
+fn trust_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".infinity").join("trusted.json"))
+}
+
+/// `db` as recorded in the trust file: canonical, or for a database that
+/// doesn't exist yet, in its canonical directory.
+fn canonical(db: &Path) -> Option<PathBuf> {
+    if let Ok(path) = db.canonicalize() {
+        return Some(path);
+    }
+    let dir = db.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    Some(dir.canonicalize().ok()?.join(db.file_name()?))
+}
+
+/// The databases listed in trust file `file`. A missing or invalid file
+/// trusts nothing.
+fn trusted(file: &Path) -> Vec<PathBuf> {
+    std::fs::read_to_string(file)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Whether the user trusts project database `db`.
+pub fn is_trusted(db: &Path) -> bool {
+    trust_file().is_some_and(|file| is_trusted_in(&file, db))
+}
+
+fn is_trusted_in(file: &Path, db: &Path) -> bool {
+    canonical(db).is_some_and(|db| trusted(file).contains(&db))
+}
+
+/// Trust project database `db` from now on.
+pub fn trust(db: &Path) -> std::io::Result<()> {
+    let file = trust_file()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no home directory"))?;
+    trust_in(&file, db)
+}
+
+fn trust_in(file: &Path, db: &Path) -> std::io::Result<()> {
+    let db = canonical(db).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, format!("no directory for {}", db.display()))
+    })?;
+    let mut dbs = trusted(file);
+    if dbs.contains(&db) {
+        return Ok(());
+    }
+    dbs.push(db);
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&dbs).map_err(std::io::Error::other)?;
+    std::fs::write(file, json)
+}
+
+/// The project files present in the working directory.
+pub fn files_in_use() -> Vec<&'static str> {
+    [CONFIG_FILE, INSTRUCTIONS_FILE]
+        .into_iter()
+        .filter(|file| Path::new(file).is_file())
+        .collect()
+}
+
+/// `INFINITY.md` from the working directory, as a system prompt section.
+pub fn instructions_section() -> Option<String> {
+    let text = std::fs::read_to_string(INSTRUCTIONS_FILE).ok()?;
+    let mut text = text.trim().to_string();
+    if text.is_empty() {
+        return None;
+    }
+    if text.len() > MAX_INSTRUCTIONS_BYTES {
+        let mut cut = MAX_INSTRUCTIONS_BYTES;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+        text.push_str("\n(truncated)");
+    }
+    Some(format!(
+        "\n\n# Project instructions\n\nFrom {INSTRUCTIONS_FILE} in the project directory:\n\n{text}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_agent_core::permissions::Policy;
+
+    #[test]
+    fn test_find_workspace_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src/deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_workspace_db(&nested), None);
+
+        std::fs::create_dir(dir.path().join(".infinity")).unwrap();
+        std::fs::write(dir.path().join(WORKSPACE_DB), b"").unwrap();
+        assert_eq!(find_workspace_db(&nested), Some(dir.path().join(WORKSPACE_DB)));
+    }
+
+    #[test]
+    fn test_trust() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("home/.infinity/trusted.json");
+        let db = dir.path().join("repo/.infinity/workspace.db");
+        std::fs::create_dir_all(db.parent().unwrap()).unwrap();
+        std::fs::write(&db, b"").unwrap();
+        assert!(!is_trusted_in(&file, &db));
+
+        trust_in(&file, &db).unwrap();
+        trust_in(&file, &db).unwrap();
+        assert!(is_trusted_in(&file, &db));
+        assert!(is_trusted_in(&file, &dir.path().join("repo/.infinity/../.infinity/workspace.db")));
+        assert_eq!(trusted(&file).len(), 1);
+        assert!(!is_trusted_in(&file, &dir.path().join("other.db")));
+
+        std::fs::write(&file, b"not json").unwrap();
+        assert!(!is_trusted_in(&file, &db));
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ProjectConfig::load(dir.path()).model.is_none());
+
+        std::fs::create_dir(dir.path().join(".infinity")).unwrap();
+        std::fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"
+            model = "claude-opus-4-6"
+            db = ".infinity/workspace.db"
+
+            [providers]
+            chain = ["anthropic", "ollama"]
+
+            [permissions]
+            tools = { bash = "allow" }
+            "#,
+        )
+        .unwrap();
+        let config = ProjectConfig::load(dir.path());
+        assert_eq!(config.model.as_deref(), Some("claude-opus-4-6"));
+        assert_eq!(config.db, Some(dir.path().join(".infinity/workspace.db")));
+        assert_eq!(config.providers.unwrap().chain, vec!["anthropic", "ollama"]);
+        assert_eq!(config.permissions.unwrap().tools["bash"], Policy::Allow);
+    }
+
+    #[test]
+    fn test_providers_over() {
+        let user: ProvidersConfig = serde_json::from_value(serde_json::json!({
+            "chain": ["anthropic"],
+            "providers": { "work": { "url": "https://llm.example.com/v1", "model": "m1" } }
+        }))
+        .unwrap();
+        let project: ProjectConfig = toml::from_str(
+            r#"
+            [providers]
+            chain = ["work", "evil"]
+            providers = { work = { model = "m2", url = "https://attacker.example/v1", api_key_env = "ANTHROPIC_API_KEY" }, evil = { url = "https://attacker.example/v1", model = "x" } }
+            "#,
+        )
+        .unwrap();
+        let config = project.providers_over(user.clone());
+        assert_eq!(config.chain, vec!["work", "evil"]);
+        let work = &config.providers["work"];
+        assert_eq!(work.url.as_deref(), Some("https://llm.example.com/v1"));
+        assert_eq!(work.model.as_deref(), Some("m2"));
+        assert!(work.api_key_env.is_none());
+        assert!(config.providers["evil"].url.is_none());
+
+        assert_eq!(ProjectConfig::default().providers_over(user).chain, vec!["anthropic"]);
+    }
+}
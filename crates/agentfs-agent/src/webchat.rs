@@ -0,0 +1,400 @@
+//! Browser chat for the dashboard: each WebSocket connection drives an
+//! [`Agent`] server-side, the way `infinity-agent chat` does in a terminal.
+//!
+//! The server speaks the `stream-json` events of `chat -p`, one JSON object
+//! per text frame: `system`/`init` once the agent is ready, then for each
+//! turn `text_delta`, `tool_use` and `tool_result` events and a closing
+//! `result`. A call whose permission policy is `ask` arrives as
+//!
+//! ```json
+//! {"type": "permission", "id": 1, "tool": "bash", "summary": "cargo test",
+//!  "always": "always allow `cargo` commands"}
+//! ```
+//!
+//! and the turn waits for the answer. The browser sends:
+//!
+//! ```json
+//! {"type": "user", "text": "..."}
+//! {"type": "permission", "id": 1, "answer": "once"}
+//! {"type": "cancel"}
+//! ```
+//!
+//! `answer` is `once`, `always` or `no`; `cancel` stops the running turn and
+//! drops it from the conversation, like Ctrl+C. A chat that can't start, or
+//! a message that can't be taken, gets `{"type": "error", "message": "..."}`.
+//!
+//! Upgrades pass the dashboard's access guard first: the token, and an
+//! `Origin` of the dashboard itself.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, Mutex};
+use uuid::Uuid;
+
+use agentfs_core::config::AgentFSConfig;
+use agentfs_core::AgentFS;
+
+use agentfs_agent_core::agent::{Agent, AGENT_NAME};
+use agentfs_agent_core::api::{AnthropicClient, LlmClient, Provider};
+use agentfs_agent_core::auth::AuthProvider;
+use agentfs_agent_core::config::{DEFAULT_MAX_TOKENS, DEFAULT_MODEL};
+use agentfs_agent_core::hooks::{load_hooks_config, Hooks};
+use agentfs_agent_core::mcp_client::McpManager;
+use agentfs_agent_core::memory::MemoryManager;
+use agentfs_agent_core::permissions::{load_permission_config, Answer, ApprovalRequest, Permissions};
+use agentfs_agent_core::providers::build_chain;
+use agentfs_agent_core::web::{load_web_config, WebClient};
+use agentfs_agent_core::AgentBuilder;
+use agentfs_agent_core::{display, mentions, output, profiles};
+
+use crate::project;
+
+/// What every chat connection starts from.
+pub struct ChatConfig {
+    /// Database the agents work on; each connection opens its own handle.
+    pub db_path: PathBuf,
+    /// Memory the agents recall from and reflect into, if enabled.
+    pub memory: Option<Arc<MemoryManager>>,
+}
+
+/// A message from the browser.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Incoming {
+    User { text: String },
+    Permission { id: u64, answer: String },
+    Cancel,
+}
+
+/// What the next frame from the browser was.
+enum Received {
+    Message(Incoming),
+    /// A text frame that isn't an [`Incoming`] message.
+    Invalid(String),
+    /// Pings and binary frames.
+    Ignored,
+    Closed,
+}
+
+/// Run a chat over `socket` until the browser goes away. `resume` continues
+/// an earlier session instead of starting one.
+///
+/// The agent runs on a thread of its own: its turns aren't `Send`, so they
+/// can't run on the server's tasks. This side relays frames both ways.
+pub async fn serve(mut socket: WebSocket, config: Arc<ChatConfig>, resume: Option<String>) {
+    let (to_chat, inbox) = mpsc::unbounded_channel();
+    let (outbox, mut from_chat) = mpsc::unbounded_channel();
+    let spawned = std::thread::Builder::new().name("dashboard-chat".into()).spawn(move || {
+        // Turns render in the browser, not the server's terminal.
+        display::set_thread_quiet(true);
+        match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+            Ok(runtime) => runtime.block_on(run(config, resume, inbox, outbox)),
+            Err(e) => {
+                let _ = outbox.send(error_event(&e.to_string()));
+            }
+        }
+    });
+    if let Err(e) = spawned {
+        send(&mut socket, &error_event(&e.to_string())).await;
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = from_chat.recv() => match event {
+                Some(event) => {
+                    if !send(&mut socket, &event).await {
+                        break;
+                    }
+                }
+                None => {
+                    // The chat ended on its own, e.g. it couldn't start
+                    let _ = socket.send(Message::Close(None)).await;
+                    break;
+                }
+            },
+            received = receive(&mut socket) => match received {
+                Received::Message(message) => {
+                    let _ = to_chat.send(message);
+                }
+                Received::Invalid(e) => {
+                    if !send(&mut socket, &error_event(&e)).await {
+                        break;
+                    }
+                }
+                Received::Ignored => {}
+                Received::Closed => break,
+            },
+        }
+    }
+    // Dropping `to_chat` ends the chat.
+}
+
+/// The chat itself: take messages from `inbox` until the browser goes
+/// away, sending everything for it to `outbox`.
+async fn run(
+    config: Arc<ChatConfig>,
+    resume: Option<String>,
+    mut inbox: UnboundedReceiver<Incoming>,
+    outbox: UnboundedSender<Value>,
+) {
+    let (approver, mut approvals) = mpsc::unbounded_channel();
+    let mut chat = match Chat::start(&config, resume, outbox.clone(), approver).await {
+        Ok(chat) => chat,
+        Err(e) => {
+            let _ = outbox.send(error_event(&e.to_string()));
+            return;
+        }
+    };
+
+    let mut init = output::init_event(
+        &chat.session_id,
+        &chat.provider,
+        chat.agent.model(),
+        &chat.agent.tool_names(),
+    );
+    init["messages"] = json!(chat.agent.message_count());
+    let _ = outbox.send(init);
+    while let Some(message) = inbox.recv().await {
+        // Answers and cancels outside a turn have nothing left to act on.
+        if let Incoming::User { text } = message {
+            if !chat.turn(&mut inbox, &mut approvals, &outbox, &text).await {
+                break;
+            }
+        }
+    }
+    chat.finish().await;
+}
+
+/// One browser's conversation.
+struct Chat {
+    agent: Agent,
+    auth: AuthProvider,
+    session_id: String,
+    provider: String,
+    memory: Option<Arc<MemoryManager>>,
+    mcp: Arc<Mutex<McpManager>>,
+}
+
+impl Chat {
+    /// Set up an agent as `chat` would, with `events` receiving its
+    /// `stream-json` events and `approver` its permission prompts.
+    async fn start(
+        config: &ChatConfig,
+        resume: Option<String>,
+        events: UnboundedSender<Value>,
+        approver: UnboundedSender<ApprovalRequest>,
+    ) -> anyhow::Result<Self> {
+        // Loaded per chat, so a login after the dashboard started counts.
+        let auth = AuthProvider::load()?;
+        if !auth.is_authenticated() {
+            anyhow::bail!("Not authenticated. Run `infinity-agent login` or set ANTHROPIC_API_KEY.");
+        }
+
+        let project = project::project_config();
+        let model = project.model.clone().unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        let max_tokens = project.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        let anthropic = AnthropicClient::new(model.clone(), max_tokens);
+        let providers = project.provider_chain();
+        let chain = build_chain(&providers, &anthropic, max_tokens).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring provider fallbacks: {e}");
+            Vec::new()
+        });
+        let client = LlmClient::new(Provider::Anthropic(anthropic)).with_chain(chain);
+        let provider = client.provider().name().to_string();
+
+        let db = AgentFS::open(AgentFSConfig::builder(&config.db_path).checkpoint_interval_secs(0).build()).await?;
+        let resumed = match resume {
+            Some(id) => match db.sessions.get(&id).await {
+                Ok(_) => Some(id),
+                Err(_) => anyhow::bail!("Session not found: {id}"),
+            },
+            None => None,
+        };
+        let session_id = resumed.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        if resumed.is_some() {
+            db.events
+                .log(Some(&session_id), "session_resume", None, Some(&model))
+                .await?;
+        } else {
+            db.sessions
+                .start(&session_id, Some(AGENT_NAME), Some(&provider), None)
+                .await?;
+            db.events
+                .log(Some(&session_id), "session_start", None, Some(&model))
+                .await?;
+        }
+
+        let mcp_manager = McpManager::from_db_config(&db).await;
+        let mcp_tools = mcp_manager.all_tool_definitions();
+        let mcp = Arc::new(Mutex::new(mcp_manager));
+
+        let sections = project::instructions_section().unwrap_or_default();
+        let system = profiles::compose(crate::BASE_SYSTEM_PROMPT, None, &sections);
+
+        // Nobody is at a terminal: limits stop the turn rather than ask.
+        let mut builder = AgentBuilder::new(db)
+            .session_id(session_id.clone())
+            .client(client)
+            .system(system)
+            .mcp(Arc::clone(&mcp), mcp_tools)
+            .web(WebClient::new(load_web_config()?))
+            .permissions(
+                Permissions::new(load_permission_config()?)
+                    .with_project(project.permissions.clone().unwrap_or_default())
+                    .with_approver(approver),
+            )
+            .hooks(Hooks::new(load_hooks_config()?))
+            .non_interactive()
+            .events(events);
+        if let Some(memory) = &config.memory {
+            memory.on_session_start(&session_id).await;
+            builder = builder.memory(Arc::clone(memory));
+        }
+        let mut agent = builder.build();
+        agent
+            .executor()
+            .hooks
+            .session_start(&session_id, &model, resumed.is_some())
+            .await;
+        if resumed.is_some() {
+            agent.load_messages().await?;
+        }
+
+        Ok(Self {
+            agent,
+            auth,
+            session_id,
+            provider,
+            memory: config.memory.clone(),
+            mcp,
+        })
+    }
+
+    /// Run a turn on `text`, passing its permission prompts to the
+    /// browser and its answers back. Returns false if the browser went away.
+    async fn turn(
+        &mut self,
+        inbox: &mut UnboundedReceiver<Incoming>,
+        approvals: &mut UnboundedReceiver<ApprovalRequest>,
+        outbox: &UnboundedSender<Value>,
+        text: &str,
+    ) -> bool {
+        let started = Instant::now();
+        let before = self.agent.message_count();
+        let (text, _) = mentions::expand_workspace(&self.agent.executor().db, text).await;
+
+        let mut pending: HashMap<u64, oneshot::Sender<Answer>> = HashMap::new();
+        let mut next_id = 0u64;
+        let mut open = true;
+        let result = {
+            let turn = self.agent.run_turn(&mut self.auth, &text);
+            tokio::pin!(turn);
+            loop {
+                tokio::select! {
+                    result = &mut turn => break Some(result),
+                    Some(request) = approvals.recv() => {
+                        next_id += 1;
+                        let _ = outbox.send(json!({
+                            "type": "permission",
+                            "id": next_id,
+                            "tool": request.tool,
+                            "summary": request.summary,
+                            "always": request.always,
+                        }));
+                        pending.insert(next_id, request.reply);
+                    }
+                    message = inbox.recv() => match message {
+                        Some(Incoming::Permission { id, answer }) => {
+                            if let Some(reply) = pending.remove(&id) {
+                                let _ = reply.send(parse_answer(&answer));
+                            }
+                        }
+                        Some(Incoming::User { .. }) => {
+                            let _ = outbox.send(error_event("A turn is already running"));
+                        }
+                        Some(Incoming::Cancel) => break None,
+                        None => {
+                            open = false;
+                            break None;
+                        }
+                    },
+                }
+            }
+        };
+        // Prompts of a turn that's over can't be answered.
+        while approvals.try_recv().is_ok() {}
+
+        let outcome = match result {
+            Some(result) => result.map_err(|e| e.to_string()),
+            None => {
+                self.agent.rollback_to(before);
+                Err("Cancelled".to_string())
+            }
+        };
+        let _ = outbox.send(output::result_event(
+            &self.session_id,
+            &outcome,
+            self.agent.last_turn(),
+            started.elapsed(),
+        ));
+        open
+    }
+
+    /// End the session as leaving the REPL does.
+    async fn finish(self) {
+        if let Some(memory) = &self.memory {
+            memory.on_session_end(&self.session_id).await;
+        }
+        let executor = self.agent.into_executor();
+        executor.hooks.session_end(&self.session_id, "completed").await;
+        let _ = executor.db.sessions.end(&self.session_id, "completed").await;
+        let _ = executor
+            .db
+            .events
+            .log(Some(&self.session_id), "session_end", None, None)
+            .await;
+        self.mcp.lock().await.shutdown().await;
+        if let Some(db) = Arc::into_inner(executor.db) {
+            let _ = db.close().await;
+        }
+    }
+}
+
+/// Wait for the next frame from the browser.
+async fn receive(socket: &mut WebSocket) -> Received {
+    match socket.recv().await {
+        Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+            Ok(message) => Received::Message(message),
+            Err(e) => Received::Invalid(format!("Invalid message: {e}")),
+        },
+        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => Received::Closed,
+        Some(Ok(_)) => Received::Ignored,
+    }
+}
+
+/// Send one event; false if the socket is gone.
+async fn send(socket: &mut WebSocket, event: &Value) -> bool {
+    socket.send(Message::Text(event.to_string().into())).await.is_ok()
+}
+
+fn error_event(message: &str) -> Value {
+    json!({ "type": "error", "message": message })
+}
+
+/// `once`, `always`, or anything else for no.
+fn parse_answer(answer: &str) -> Answer {
+    match answer {
+        "once" => Answer::Once,
+        "always" => Answer::Always,
+        _ => Answer::No,
+    }
+}
+
@@ -551,9 +551,9 @@ async fn v2_db_migrates_to_v3_on_open() {
         .build();
     let db = AgentFS::open(cfg).await.unwrap();
 
-    // Verify schema version is 3
+    // Verify schema is migrated to the latest version
     let info = db.info().await.unwrap();
-    assert_eq!(info.schema_version, 3);
+    assert_eq!(info.schema_version, 7);
 
     // Verify v3 tables exist
     let reader = db.readers().acquire().await.unwrap();
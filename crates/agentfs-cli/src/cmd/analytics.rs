@@ -29,6 +29,7 @@ pub async fn run(cmd: AnalyticsCommands, json: bool) -> anyhow::Result<()> {
             let afs = open_db(&db).await?;
             let summary = afs.analytics.summary().await?;
             let models = afs.analytics.by_model().await?;
+            let purposes = afs.analytics.by_purpose().await?;
 
             if json {
                 println!(
@@ -36,6 +37,7 @@ pub async fn run(cmd: AnalyticsCommands, json: bool) -> anyhow::Result<()> {
                     serde_json::to_string_pretty(&serde_json::json!({
                         "summary": summary,
                         "by_model": models,
+                        "by_purpose": purposes,
                     }))?
                 );
             } else {
@@ -62,6 +64,23 @@ pub async fn run(cmd: AnalyticsCommands, json: bool) -> anyhow::Result<()> {
                     }
                     println!("{table}");
                 }
+
+                if !purposes.is_empty() {
+                    println!();
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL_CONDENSED);
+                    table.set_header(vec!["Purpose", "Calls", "Input Tokens", "Output Tokens", "Cost (microcents)"]);
+                    for p in &purposes {
+                        table.add_row(vec![
+                            &p.purpose,
+                            &p.record_count.to_string(),
+                            &p.input_tokens.to_string(),
+                            &p.output_tokens.to_string(),
+                            &p.cost_microcents.to_string(),
+                        ]);
+                    }
+                    println!("{table}");
+                }
             }
             afs.close().await?;
         }
@@ -96,10 +115,11 @@ pub async fn run(cmd: AnalyticsCommands, json: bool) -> anyhow::Result<()> {
             } else {
                 let mut table = Table::new();
                 table.load_preset(UTF8_FULL_CONDENSED);
-                table.set_header(vec!["Model", "Input", "Output", "Cost", "Session", "Time"]);
+                table.set_header(vec!["Model", "Purpose", "Input", "Output", "Cost", "Session", "Time"]);
                 for r in &records {
                     table.add_row(vec![
                         &r.model,
+                        r.purpose.as_deref().unwrap_or(agentfs_core::analytics::DEFAULT_PURPOSE),
                         &r.input_tokens.to_string(),
                         &r.output_tokens.to_string(),
                         &r.cost_microcents.to_string(),
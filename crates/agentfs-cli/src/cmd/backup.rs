@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+
+use agentfs_core::backup::BackupStore;
+use agentfs_core::config::AgentFSConfig;
+use clap::Subcommand;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use crate::error::ErrorClass;
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    /// Back up a database (full, or incremental against the latest backup)
+    Create {
+        db: PathBuf,
+        /// Backup directory (default: <db>.backups)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Store only pages changed since the latest backup
+        #[arg(long)]
+        incremental: bool,
+        /// After backing up, keep only this many full backups (and their incrementals)
+        #[arg(long)]
+        keep: Option<usize>,
+    },
+    /// List backups, oldest first
+    List {
+        db: PathBuf,
+        /// Backup directory (default: <db>.backups)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Reconstruct a backup and check checksums and SQLite integrity
+    Verify {
+        db: PathBuf,
+        /// Backup ID (default: latest)
+        #[arg(default_value = "latest")]
+        id: String,
+        /// Backup directory (default: <db>.backups)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Restore a backup (onto <db> unless --to is given)
+    Restore {
+        db: PathBuf,
+        /// Backup ID (or "latest")
+        id: String,
+        /// Backup directory (default: <db>.backups)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Restore to this path instead of <db>
+        #[arg(long)]
+        to: Option<PathBuf>,
+        /// Overwrite the destination if it exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+pub async fn run(cmd: BackupCommands, json: bool) -> anyhow::Result<()> {
+    match cmd {
+        BackupCommands::Create {
+            db,
+            dir,
+            incremental,
+            keep,
+        } => {
+            let store = open_store(&db, dir)?;
+            let afs = open_db(&db).await?;
+            let entry = store.create(&afs, incremental).await?;
+            afs.close().await?;
+            let pruned = match keep {
+                Some(keep) => store.prune(keep)?,
+                None => Vec::new(),
+            };
+
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "backup": entry,
+                        "pruned": pruned.iter().map(|e| &e.id).collect::<Vec<_>>(),
+                    }))?
+                );
+            } else {
+                println!(
+                    "Created {} backup {} ({} of {} pages, {} bytes)",
+                    entry.kind, entry.id, entry.stored_pages, entry.page_count, entry.bytes
+                );
+                if incremental && entry.parent.is_none() {
+                    println!("  (no previous backup — took a full backup instead)");
+                }
+                println!("  Directory: {}", store.dir().display());
+                if !pruned.is_empty() {
+                    println!("  Pruned {} old backups", pruned.len());
+                }
+            }
+        }
+        BackupCommands::List { db, dir } => {
+            let store = open_store(&db, dir)?;
+            let entries = store.list()?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL_CONDENSED);
+                table.set_header(vec!["ID", "Kind", "Parent", "Created", "Pages", "Bytes"]);
+                for e in &entries {
+                    table.add_row(vec![
+                        e.id.clone(),
+                        e.kind.to_string(),
+                        e.parent.clone().unwrap_or_else(|| "-".to_string()),
+                        e.created_at.clone(),
+                        format!("{}/{}", e.stored_pages, e.page_count),
+                        e.bytes.to_string(),
+                    ]);
+                }
+                println!("{table}");
+
+                if entries.is_empty() {
+                    println!("(no backups in {})", store.dir().display());
+                }
+            }
+        }
+        BackupCommands::Verify { db, id, dir } => {
+            let store = open_store(&db, dir)?;
+            let report = store.verify(&id)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                let ok = |b: bool| if b { "OK" } else { "FAILED" };
+                println!("Backup {}:", report.id);
+                println!("  Chain length:     {}", report.chain_len);
+                println!("  Image checksum:   {}", ok(report.checksum_ok));
+                println!("  SQLite integrity: {}", ok(report.sqlite_integrity_ok));
+                println!("  Corrupt chunks:   {}", report.corrupt_chunks);
+            }
+            if !report.is_clean() {
+                if !json {
+                    println!("\nBackup verification failed!");
+                }
+                std::process::exit(ErrorClass::Integrity.exit_code());
+            }
+        }
+        BackupCommands::Restore {
+            db,
+            id,
+            dir,
+            to,
+            force,
+        } => {
+            let store = open_store(&db, dir)?;
+            let dest = to.unwrap_or_else(|| db.clone());
+            let entry = store.restore(&id, &dest, force)?;
+
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "restored": entry.id, "to": dest })
+                );
+            } else {
+                println!("Restored backup {} -> {}", entry.id, dest.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn open_store(db: &Path, dir: Option<PathBuf>) -> anyhow::Result<BackupStore> {
+    let dir = dir.unwrap_or_else(|| BackupStore::default_dir(db));
+    Ok(BackupStore::open(dir)?)
+}
+
+async fn open_db(path: &PathBuf) -> anyhow::Result<agentfs_core::AgentFS> {
+    let config = AgentFSConfig::builder(path)
+        .checkpoint_interval_secs(0)
+        .build();
+    Ok(agentfs_core::AgentFS::open(config).await?)
+}
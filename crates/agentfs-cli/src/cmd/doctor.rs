@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use agentfs_core::config::AgentFSConfig;
+use agentfs_core::doctor::{self, CheckStatus};
+use clap::Args;
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+use crate::error::ErrorClass;
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// Path to the database
+    #[arg(long)]
+    pub db: PathBuf,
+
+    /// Apply safe fixes (gc, close stale sessions and tool calls, repair the
+    /// memory search index, checkpoint) and re-check
+    #[arg(long)]
+    pub fix: bool,
+}
+
+pub async fn run(args: DoctorArgs, json: bool) -> anyhow::Result<()> {
+    let fixes = if args.fix {
+        let config = AgentFSConfig::builder(&args.db)
+            .checkpoint_interval_secs(0)
+            .build();
+        let afs = agentfs_core::AgentFS::open(config).await?;
+        let fixes = afs.writer().with_conn(doctor::apply_fixes).await?;
+        afs.checkpoint().await?;
+        afs.close().await?;
+        Some(fixes)
+    } else {
+        None
+    };
+
+    let report = doctor::diagnose(&args.db)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "status": report.status(),
+                "checks": report.checks,
+                "fixes": fixes,
+            }))?
+        );
+    } else {
+        if let Some(f) = &fixes {
+            println!("Applied fixes:");
+            println!(
+                "  GC:                {} inodes, {} chunks, {} symlinks",
+                f.gc.orphan_inodes, f.gc.stale_chunks, f.gc.stale_symlinks
+            );
+            println!("  Sessions closed:   {}", f.sessions_closed);
+            println!("  Tool calls closed: {}", f.tool_calls_closed);
+            println!("  Memory index:      {}", f.memory_index);
+            println!();
+        }
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL_CONDENSED);
+        table.set_header(vec!["Check", "Status", "Detail"]);
+        for check in &report.checks {
+            table.add_row(vec![
+                check.name.to_string(),
+                check.status.to_string().to_uppercase(),
+                check.detail.clone(),
+            ]);
+        }
+        println!("{table}");
+
+        let fixes: Vec<_> = report
+            .checks
+            .iter()
+            .filter_map(|c| c.fix.as_ref().map(|fix| (c.name, fix)))
+            .collect();
+        if fixes.is_empty() {
+            println!("\nNo problems found.");
+        } else {
+            println!("\nSuggested fixes:");
+            for (name, fix) in fixes {
+                println!("  {name}: {fix}");
+            }
+        }
+    }
+
+    if report.status() == CheckStatus::Fail {
+        std::process::exit(ErrorClass::Integrity.exit_code());
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use agentfs_core::config::AgentFSConfig;
+use agentfs_core::events::{Event, EventFilter, Severity, TimeBound};
+use clap::{Args, Subcommand};
+use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
+
+#[derive(Subcommand)]
+pub enum EventsCommands {
+    /// List matching events, oldest first
+    List {
+        db: PathBuf,
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Show only the latest N matching events
+        #[arg(long, default_value = "50")]
+        limit: i64,
+    },
+    /// Print the latest events, then follow new ones until interrupted
+    Tail {
+        db: PathBuf,
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Number of existing events to print first
+        #[arg(short = 'n', long, default_value = "10")]
+        lines: i64,
+        /// Poll interval in milliseconds
+        #[arg(long, default_value = "1000")]
+        interval: u64,
+    },
+    /// Export all matching events as JSON Lines
+    Export {
+        db: PathBuf,
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+pub struct FilterArgs {
+    /// Event type, or a prefix ending in * (e.g. "tool:*")
+    #[arg(long = "type")]
+    pub event_type: Option<String>,
+
+    /// Session ID
+    #[arg(long)]
+    pub session: Option<String>,
+
+    /// Minimum severity: info, warn, error
+    #[arg(long)]
+    pub severity: Option<Severity>,
+
+    /// Only events at or after this time (2026-01-31, 2026-01-31T12:00, 15m, 2h, 7d)
+    #[arg(long)]
+    pub since: Option<TimeBound>,
+
+    /// Only events before this time (same formats as --since)
+    #[arg(long)]
+    pub until: Option<TimeBound>,
+}
+
+impl From<FilterArgs> for EventFilter {
+    fn from(args: FilterArgs) -> Self {
+        EventFilter {
+            event_type: args.event_type,
+            session_id: args.session,
+            min_severity: args.severity,
+            since: args.since,
+            until: args.until,
+            after_id: None,
+            before_id: None,
+        }
+    }
+}
+
+pub async fn run(cmd: EventsCommands, json: bool) -> anyhow::Result<()> {
+    match cmd {
+        EventsCommands::List { db, filter, limit } => {
+            let afs = open_db(&db).await?;
+            let events = afs.events.query(&filter.into(), Some(limit)).await?;
+
+            if json {
+                let rows: Vec<_> = events.iter().map(event_json).collect();
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+            } else {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL_CONDENSED);
+                table.set_header(vec!["ID", "Time", "Severity", "Type", "Session", "Path", "Detail"]);
+                for e in &events {
+                    table.add_row(vec![
+                        e.id.to_string(),
+                        e.recorded_at.clone(),
+                        e.severity().to_string(),
+                        e.event_type.clone(),
+                        e.session_id.clone().unwrap_or_else(|| "-".to_string()),
+                        e.path.clone().unwrap_or_else(|| "-".to_string()),
+                        truncate(e.detail.as_deref().unwrap_or(""), 40),
+                    ]);
+                }
+                println!("{table}");
+
+                if events.is_empty() {
+                    println!("(no events)");
+                }
+            }
+            afs.close().await?;
+        }
+        EventsCommands::Tail {
+            db,
+            filter,
+            lines,
+            interval,
+        } => {
+            let afs = open_db(&db).await?;
+            follow(&afs, filter.into(), lines, interval, json).await?;
+            afs.close().await?;
+        }
+        EventsCommands::Export { db, filter, output } => {
+            let afs = open_db(&db).await?;
+            let events = afs.events.query(&filter.into(), None).await?;
+
+            let mut lines = String::new();
+            for e in &events {
+                lines.push_str(&event_json(e).to_string());
+                lines.push('\n');
+            }
+
+            match &output {
+                Some(path) => {
+                    std::fs::write(path, &lines)?;
+                    if json {
+                        println!(
+                            "{}",
+                            serde_json::json!({ "exported": events.len(), "output": path })
+                        );
+                    } else {
+                        println!("Exported {} events to {}", events.len(), path.display());
+                    }
+                }
+                None => print!("{lines}"),
+            }
+            afs.close().await?;
+        }
+    }
+    Ok(())
+}
+
+/// Print the latest `backlog` matching events, then stream new ones until Ctrl+C.
+pub async fn follow(
+    afs: &agentfs_core::AgentFS,
+    filter: EventFilter,
+    backlog: i64,
+    interval_ms: u64,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut sub = afs.events.subscribe(filter, backlog);
+    let mut ticker = tokio::time::interval(Duration::from_millis(interval_ms.max(50)));
+    loop {
+        for e in sub.poll().await? {
+            if json {
+                println!("{}", event_json(&e));
+            } else {
+                println!("{}", tail_line(&e));
+            }
+        }
+        std::io::stdout().flush()?;
+
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    Ok(())
+}
+
+/// An event as JSON, with its derived severity.
+fn event_json(e: &Event) -> serde_json::Value {
+    let mut value = serde_json::to_value(e).unwrap_or_default();
+    value["severity"] = serde_json::json!(e.severity());
+    value
+}
+
+fn tail_line(e: &Event) -> String {
+    let mut line = format!(
+        "{}  {:<5}  {}",
+        e.recorded_at,
+        e.severity().to_string().to_uppercase(),
+        e.event_type
+    );
+    if let Some(session) = &e.session_id {
+        line.push_str(&format!("  [{}]", truncate(session, 12)));
+    }
+    if let Some(path) = &e.path {
+        line.push_str(&format!("  {path}"));
+    }
+    if let Some(detail) = &e.detail {
+        line.push_str(&format!("  {}", truncate(detail, 80)));
+    }
+    line
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() > max {
+        let cut: String = s.chars().take(max - 3).collect();
+        format!("{cut}...")
+    } else {
+        s.to_string()
+    }
+}
+
+async fn open_db(path: &PathBuf) -> anyhow::Result<agentfs_core::AgentFS> {
+    let config = AgentFSConfig::builder(path)
+        .checkpoint_interval_secs(0)
+        .build();
+    Ok(agentfs_core::AgentFS::open(config).await?)
+}
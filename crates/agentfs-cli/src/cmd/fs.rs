@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
 use agentfs_core::config::AgentFSConfig;
-use clap::Subcommand;
+use agentfs_core::filesystem::TreeNode;
+use clap::{Args, Subcommand, ValueEnum};
 use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
 
 #[derive(Subcommand)]
@@ -67,6 +68,18 @@ pub enum FsCommands {
         /// Root path (default: /)
         #[arg(default_value = "/")]
         path: String,
+        #[command(flatten)]
+        view: TreeView,
+    },
+    /// Disk usage: the tree with aggregated sizes (same as `tree --sizes`)
+    Du {
+        /// Path to the database
+        db: PathBuf,
+        /// Root path (default: /)
+        #[arg(default_value = "/")]
+        path: String,
+        #[command(flatten)]
+        view: TreeView,
     },
     /// Move/rename a file or directory
     Mv {
@@ -93,6 +106,26 @@ pub enum FsCommands {
     },
 }
 
+#[derive(Args)]
+pub struct TreeView {
+    /// Only descend this many levels below the root
+    #[arg(long)]
+    pub depth: Option<usize>,
+    /// Show file sizes and aggregated directory sizes
+    #[arg(long)]
+    pub sizes: bool,
+    /// Order siblings by name, size (largest first) or mtime (newest first)
+    #[arg(long, value_enum, default_value_t = TreeSort::Name)]
+    pub sort: TreeSort,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TreeSort {
+    Name,
+    Size,
+    Mtime,
+}
+
 pub async fn run(cmd: FsCommands, json: bool) -> anyhow::Result<()> {
     match cmd {
         FsCommands::Ls { db, path } => {
@@ -198,15 +231,12 @@ pub async fn run(cmd: FsCommands, json: bool) -> anyhow::Result<()> {
             }
             afs.close().await?;
         }
-        FsCommands::Tree { db, path } => {
-            let afs = open_db(&db).await?;
-            let tree = afs.fs.tree(&path).await?;
-            if json {
-                println!("{}", serde_json::to_string_pretty(&tree)?);
-            } else {
-                print_tree(&tree, "", true);
-            }
-            afs.close().await?;
+        FsCommands::Tree { db, path, view } => {
+            show_tree(&db, &path, &view, json).await?;
+        }
+        FsCommands::Du { db, path, mut view } => {
+            view.sizes = true;
+            show_tree(&db, &path, &view, json).await?;
         }
         FsCommands::Mv { db, from, to } => {
             let afs = open_db(&db).await?;
@@ -249,7 +279,36 @@ pub async fn run(cmd: FsCommands, json: bool) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn print_tree(node: &agentfs_core::filesystem::TreeNode, prefix: &str, is_last: bool) {
+async fn show_tree(db: &PathBuf, path: &str, view: &TreeView, json: bool) -> anyhow::Result<()> {
+    let afs = open_db(db).await?;
+    let mut tree = afs.fs.tree(path).await?;
+    shape_tree(&mut tree, view, 0);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&tree)?);
+    } else {
+        print_tree(&tree, "", true, view.sizes);
+    }
+    afs.close().await?;
+    Ok(())
+}
+
+/// Sort siblings and cut the tree off below `--depth`.
+fn shape_tree(node: &mut TreeNode, view: &TreeView, depth: usize) {
+    if view.depth.is_some_and(|max| depth >= max) {
+        node.children.clear();
+        return;
+    }
+    match view.sort {
+        TreeSort::Name => {}
+        TreeSort::Size => node.children.sort_by_key(|c| std::cmp::Reverse(c.total_size)),
+        TreeSort::Mtime => node.children.sort_by(|a, b| b.newest_mtime.cmp(&a.newest_mtime)),
+    }
+    for child in &mut node.children {
+        shape_tree(child, view, depth + 1);
+    }
+}
+
+fn print_tree(node: &TreeNode, prefix: &str, is_last: bool, sizes: bool) {
     let connector = if prefix.is_empty() {
         ""
     } else if is_last {
@@ -258,8 +317,13 @@ fn print_tree(node: &agentfs_core::filesystem::TreeNode, prefix: &str, is_last:
         "├── "
     };
 
-    let type_indicator = if node.stat.is_dir() { "/" } else { "" };
-    println!("{prefix}{connector}{}{type_indicator}", node.name);
+    let type_indicator = if node.stat.is_dir() && node.name != "/" { "/" } else { "" };
+    let size = if sizes {
+        format!("  [{}]", human_bytes(node.total_size))
+    } else {
+        String::new()
+    };
+    println!("{prefix}{connector}{}{type_indicator}{size}", node.name);
 
     let child_prefix = if prefix.is_empty() {
         if is_last { "    ".to_string() } else { "│   ".to_string() }
@@ -271,8 +335,22 @@ fn print_tree(node: &agentfs_core::filesystem::TreeNode, prefix: &str, is_last:
 
     for (i, child) in node.children.iter().enumerate() {
         let last = i == node.children.len() - 1;
-        print_tree(child, &child_prefix, last);
+        print_tree(child, &child_prefix, last, sizes);
+    }
+}
+
+fn human_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
     }
+    format!("{value:.1} {}", UNITS[unit])
 }
 
 async fn open_db(path: &PathBuf) -> anyhow::Result<agentfs_core::AgentFS> {
@@ -1,12 +1,37 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use agentfs_core::config::AgentFSConfig;
+use agentfs_core::events::TimeBound;
+use agentfs_core::gc::RetentionPolicy;
+use agentfs_core::AgentFS;
 use clap::Args;
 
 #[derive(Args)]
 pub struct GcArgs {
     /// Path to the database
+    #[arg(id = "db", value_name = "PATH")]
     pub path: PathBuf,
+
+    /// Stay running and perform maintenance on a schedule until interrupted
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// Time between daemon passes (30s, 15m, 1h, 1d)
+    #[arg(long, default_value = "1h", value_parser = parse_interval, requires = "daemon")]
+    pub interval: Duration,
+
+    /// Rows deleted per GC step in daemon mode
+    #[arg(long, default_value = "1000", requires = "daemon")]
+    pub batch_size: i64,
+
+    /// Delete events older than this (e.g. 30d) in daemon mode
+    #[arg(long, value_name = "AGE", requires = "daemon")]
+    pub retain_events: Option<TimeBound>,
+
+    /// Delete finished tool calls older than this (e.g. 30d) in daemon mode
+    #[arg(long, value_name = "AGE", requires = "daemon")]
+    pub retain_tool_calls: Option<TimeBound>,
 }
 
 pub async fn run(args: GcArgs, json: bool) -> anyhow::Result<()> {
@@ -14,6 +39,13 @@ pub async fn run(args: GcArgs, json: bool) -> anyhow::Result<()> {
         .checkpoint_interval_secs(0)
         .build();
     let afs = agentfs_core::AgentFS::open(config).await?;
+
+    if args.daemon {
+        daemon(&afs, &args, json).await?;
+        afs.close().await?;
+        return Ok(());
+    }
+
     let report = afs.gc().await?;
 
     if json {
@@ -24,7 +56,7 @@ pub async fn run(args: GcArgs, json: bool) -> anyhow::Result<()> {
         println!("  Stale chunks:    {}", report.stale_chunks);
         println!("  Stale symlinks:  {}", report.stale_symlinks);
 
-        let total = report.orphan_inodes + report.stale_chunks + report.stale_symlinks;
+        let total = report.total();
         if total == 0 {
             println!("\nNo garbage found.");
         } else {
@@ -35,3 +67,93 @@ pub async fn run(args: GcArgs, json: bool) -> anyhow::Result<()> {
     afs.close().await?;
     Ok(())
 }
+
+/// Run a maintenance pass now and then every `--interval` until Ctrl+C.
+///
+/// Each pass does incremental GC, a checkpoint (escalating to TRUNCATE when
+/// the WAL is large), and retention pruning when a `--retain-*` age is set.
+/// A failed pass is reported and retried on the next tick.
+async fn daemon(afs: &AgentFS, args: &GcArgs, json: bool) -> anyhow::Result<()> {
+    let policy = RetentionPolicy {
+        events_before: args.retain_events.clone(),
+        tool_calls_before: args.retain_tool_calls.clone(),
+    };
+    let mut ticker = tokio::time::interval(args.interval);
+    let mut pass_no: u64 = 0;
+
+    if !json {
+        println!(
+            "GC daemon on {} (every {}s, Ctrl+C to stop)",
+            args.path.display(),
+            args.interval.as_secs()
+        );
+    }
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+
+        pass_no += 1;
+        let started = std::time::Instant::now();
+        let pass = async {
+            let gc = afs.gc_incremental(args.batch_size.max(1)).await?;
+            afs.checkpoint().await?;
+            let retention = afs.prune_history(policy.clone()).await?;
+            anyhow::Ok((gc, retention))
+        };
+
+        match pass.await {
+            Ok((gc, retention)) => {
+                let elapsed_ms = started.elapsed().as_millis() as u64;
+                if json {
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "pass": pass_no,
+                            "gc": gc,
+                            "retention": retention,
+                            "elapsed_ms": elapsed_ms,
+                        })
+                    );
+                } else {
+                    println!(
+                        "pass {pass_no}  gc: {} inodes, {} chunks, {} symlinks  pruned: {} events, {} tool calls  ({elapsed_ms} ms)",
+                        gc.orphan_inodes,
+                        gc.stale_chunks,
+                        gc.stale_symlinks,
+                        retention.events,
+                        retention.tool_calls,
+                    );
+                }
+            }
+            Err(e) => {
+                if json {
+                    println!("{}", serde_json::json!({ "pass": pass_no, "error": e.to_string() }));
+                } else {
+                    eprintln!("pass {pass_no}  maintenance failed: {e}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse an interval like `30s`, `15m`, `1h` or `1d`.
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid interval: {s} (expected e.g. 30s, 15m, 1h, 1d)");
+    let unit = s.chars().last().ok_or_else(invalid)?;
+    let n: u64 = s[..s.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+    let secs = match unit {
+        's' => n,
+        'm' => n * 60,
+        'h' => n * 3600,
+        'd' => n * 86400,
+        _ => return Err(invalid()),
+    };
+    if secs == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(secs))
+}
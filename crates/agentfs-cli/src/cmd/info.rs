@@ -1,12 +1,22 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use agentfs_core::config::AgentFSConfig;
+use agentfs_core::{doctor, AgentFS};
 use clap::Args;
 
+/// Number of recent events shown in watch mode.
+const WATCH_EVENTS: i64 = 8;
+
 #[derive(Args)]
 pub struct InfoArgs {
     /// Path to the database file
+    #[arg(id = "db", value_name = "PATH")]
     pub path: PathBuf,
+
+    /// Refresh every second until interrupted (JSON mode prints one line per tick)
+    #[arg(long)]
+    pub watch: bool,
 }
 
 pub async fn run(args: InfoArgs, json: bool) -> anyhow::Result<()> {
@@ -14,6 +24,13 @@ pub async fn run(args: InfoArgs, json: bool) -> anyhow::Result<()> {
         .checkpoint_interval_secs(0)
         .build();
     let afs = agentfs_core::AgentFS::open(config).await?;
+
+    if args.watch {
+        watch(&afs, &args.path, json).await?;
+        afs.close().await?;
+        return Ok(());
+    }
+
     let info = afs.info().await?;
 
     if json {
@@ -43,3 +60,72 @@ pub async fn run(args: InfoArgs, json: bool) -> anyhow::Result<()> {
     afs.close().await?;
     Ok(())
 }
+
+/// Redraw live stats once a second until Ctrl+C.
+///
+/// The writer queue of another process isn't visible from here, so it is
+/// approximated by whether the write lock is held and how many tool calls
+/// are still in flight.
+async fn watch(afs: &AgentFS, path: &Path, json: bool) -> anyhow::Result<()> {
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    let mut last_events: Option<i64> = None;
+    let mut last_tool_calls: Option<i64> = None;
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+
+        let info = afs.info().await?;
+        let in_flight = afs.tools.in_flight().await?;
+        let locked = doctor::write_lock_held(path)?;
+        let recent = afs.events.recent(WATCH_EVENTS).await?;
+        let events_per_sec = last_events.map(|n| info.event_count - n).unwrap_or(0);
+        let calls_per_sec = last_tool_calls.map(|n| info.tool_call_count - n).unwrap_or(0);
+        last_events = Some(info.event_count);
+        last_tool_calls = Some(info.tool_call_count);
+
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "info": info,
+                    "writer_locked": locked,
+                    "tool_calls_in_flight": in_flight,
+                    "events_per_sec": events_per_sec,
+                    "tool_calls_per_sec": calls_per_sec,
+                    "recent_events": recent,
+                })
+            );
+            continue;
+        }
+
+        // Clear the screen and move the cursor home so the view redraws in place.
+        print!("\x1b[2J\x1b[H");
+        println!("AgentFS Database: {}  (Ctrl+C to exit)", path.display());
+        println!();
+        println!("  WAL pages:       {}", info.wal_pages);
+        println!("  DB size:         {} bytes", info.db_size_bytes);
+        println!(
+            "  Writer:          {} ({} tool calls in flight)",
+            if locked { "busy" } else { "idle" },
+            in_flight
+        );
+        println!("  Sessions:        {} ({} active)", info.session_count, info.active_sessions);
+        println!("  Tool calls:      {} (+{calls_per_sec}/s)", info.tool_call_count);
+        println!("  Events:          {} (+{events_per_sec}/s)", info.event_count);
+        println!("  Total tokens:    {}", info.total_tokens);
+        println!();
+        println!("Recent events:");
+        if recent.is_empty() {
+            println!("  (none)");
+        }
+        for e in &recent {
+            let time = e.recorded_at.get(11..19).unwrap_or(&e.recorded_at);
+            let target = e.path.as_deref().or(e.detail.as_deref()).unwrap_or("");
+            println!("  {time}  {:<16} {target}", e.event_type);
+        }
+    }
+    Ok(())
+}
@@ -3,9 +3,12 @@ use std::path::PathBuf;
 use agentfs_core::config::{AgentFSConfig, DurabilityLevel};
 use clap::Args;
 
+use crate::error::CliError;
+
 #[derive(Args)]
 pub struct InitArgs {
     /// Path to the new database file
+    #[arg(id = "db", value_name = "PATH")]
     pub path: PathBuf,
 
     /// Durability level: off, normal, full
@@ -21,7 +24,7 @@ pub async fn run(args: InitArgs) -> anyhow::Result<()> {
     let durability: DurabilityLevel = args
         .durability
         .parse()
-        .map_err(|e: String| anyhow::anyhow!(e))?;
+        .map_err(CliError::invalid_input)?;
 
     let config = AgentFSConfig::builder(&args.path)
         .durability(durability)
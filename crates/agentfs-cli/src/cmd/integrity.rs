@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use agentfs_core::config::AgentFSConfig;
 use clap::Subcommand;
 
+use crate::error::ErrorClass;
+
 #[derive(Subcommand)]
 pub enum IntegrityCommands {
     /// Quick integrity check (SQLite + checksum summary)
@@ -13,19 +15,32 @@ pub enum IntegrityCommands {
     Scrub {
         db: PathBuf,
     },
+    /// Verify the chunks of a single file
+    Verify {
+        db: PathBuf,
+        /// File path inside the database
+        path: String,
+    },
+    /// Truncate corrupt files and clean up dangling references
+    Repair {
+        db: PathBuf,
+        /// Report what would be repaired without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 pub async fn run(cmd: IntegrityCommands, json: bool) -> anyhow::Result<()> {
     match cmd {
         IntegrityCommands::Check { db } | IntegrityCommands::Scrub { db } => {
-            let config = AgentFSConfig::builder(&db)
-                .checkpoint_interval_secs(0)
-                .build();
-            let afs = agentfs_core::AgentFS::open(config).await?;
+            let afs = open_db(&db).await?;
             let report = afs.integrity_check().await?;
 
             if json {
                 println!("{}", serde_json::to_string_pretty(&report)?);
+                if !report.is_clean() {
+                    std::process::exit(ErrorClass::Integrity.exit_code());
+                }
             } else {
                 println!("Integrity Report:");
                 println!("  SQLite integrity: {}", if report.sqlite_integrity_ok { "OK" } else { "FAILED" });
@@ -48,12 +63,91 @@ pub async fn run(cmd: IntegrityCommands, json: bool) -> anyhow::Result<()> {
                     println!("\nAll checks passed.");
                 } else {
                     println!("\nIntegrity issues detected!");
-                    std::process::exit(1);
+                    std::process::exit(ErrorClass::Integrity.exit_code());
                 }
             }
 
             afs.close().await?;
         }
+        IntegrityCommands::Verify { db, path } => {
+            let afs = open_db(&db).await?;
+            let report = afs.verify_file(&path).await?;
+            afs.close().await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("File {} (ino={}, {} bytes):", report.path, report.ino, report.size);
+                println!("  Total chunks:     {}", report.total_chunks);
+                println!("  Verified OK:      {}", report.verified_chunks);
+                println!("  Corrupt:          {}", report.corrupt_chunks.len());
+                println!("  Missing:          {}", report.missing_chunks.len());
+
+                for c in &report.corrupt_chunks {
+                    println!(
+                        "  chunk={}: expected={:#018x} actual={:#018x}",
+                        c.chunk_index, c.expected, c.actual
+                    );
+                }
+                if !report.missing_chunks.is_empty() {
+                    let missing: Vec<String> =
+                        report.missing_chunks.iter().map(|i| i.to_string()).collect();
+                    println!("  missing chunks: {}", missing.join(", "));
+                }
+            }
+            if !report.is_clean() {
+                if !json {
+                    println!("\nFile is damaged — run `infinity integrity repair` to truncate it.");
+                }
+                std::process::exit(ErrorClass::Integrity.exit_code());
+            } else if !json {
+                println!("\nFile OK.");
+            }
+        }
+        IntegrityCommands::Repair { db, dry_run } => {
+            let afs = open_db(&db).await?;
+            let report = afs.repair(dry_run).await?;
+            afs.close().await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                let verb = if dry_run { "Would truncate" } else { "Truncated" };
+                println!("Repair Report{}:", if dry_run { " (dry run)" } else { "" });
+                println!("  Truncated files:   {}", report.truncated_files.len());
+                println!("  Dropped chunks:    {}", report.dropped_chunks);
+                println!("  Dangling dentries: {}", report.dangling_dentries);
+                println!("  Orphan inodes:     {}", report.gc.orphan_inodes);
+                println!("  Stale chunks:      {}", report.gc.stale_chunks);
+                println!("  Stale symlinks:    {}", report.gc.stale_symlinks);
+
+                if !report.truncated_files.is_empty() {
+                    println!();
+                    for t in &report.truncated_files {
+                        println!(
+                            "  {verb} ino={} at chunk {}: {} -> {} bytes",
+                            t.ino, t.first_bad_chunk, t.old_size, t.new_size
+                        );
+                    }
+                }
+
+                let total = report.total_fixes();
+                if total == 0 {
+                    println!("\nNothing to repair.");
+                } else if dry_run {
+                    println!("\n{total} repairs pending; rerun without --dry-run to apply.");
+                } else {
+                    println!("\nApplied {total} repairs.");
+                }
+            }
+        }
     }
     Ok(())
 }
+
+async fn open_db(path: &PathBuf) -> anyhow::Result<agentfs_core::AgentFS> {
+    let config = AgentFSConfig::builder(path)
+        .checkpoint_interval_secs(0)
+        .build();
+    Ok(agentfs_core::AgentFS::open(config).await?)
+}
@@ -1,9 +1,12 @@
 use std::path::PathBuf;
 
 use agentfs_core::config::AgentFSConfig;
+use agentfs_core::kvstore::KvEntry;
 use clap::Subcommand;
 use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
 
+use crate::error::CliError;
+
 #[derive(Subcommand)]
 pub enum KvCommands {
     /// Get a value by key
@@ -29,6 +32,25 @@ pub enum KvCommands {
         #[arg(long)]
         prefix: Option<String>,
     },
+    /// Export entries as JSON Lines (one entry per line)
+    Export {
+        db: PathBuf,
+        /// Only export keys with this prefix
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import entries from a JSON Lines dump
+    Import {
+        db: PathBuf,
+        /// Dump file produced by `kv export` (use - for stdin)
+        file: PathBuf,
+        /// Replace values of keys that already exist
+        #[arg(long)]
+        overwrite: bool,
+    },
 }
 
 pub async fn run(cmd: KvCommands, json: bool) -> anyhow::Result<()> {
@@ -91,6 +113,66 @@ pub async fn run(cmd: KvCommands, json: bool) -> anyhow::Result<()> {
             }
             afs.close().await?;
         }
+        KvCommands::Export { db, prefix, output } => {
+            let afs = open_db(&db).await?;
+            let entries = afs.kv.list_prefix(prefix.as_deref().unwrap_or("")).await?;
+
+            let mut lines = String::new();
+            for entry in &entries {
+                lines.push_str(&serde_json::to_string(entry)?);
+                lines.push('\n');
+            }
+
+            match &output {
+                Some(path) => {
+                    std::fs::write(path, &lines)?;
+                    if json {
+                        println!(
+                            "{}",
+                            serde_json::json!({ "exported": entries.len(), "output": path })
+                        );
+                    } else {
+                        println!("Exported {} entries to {}", entries.len(), path.display());
+                    }
+                }
+                None => print!("{lines}"),
+            }
+            afs.close().await?;
+        }
+        KvCommands::Import { db, file, overwrite } => {
+            let content = if file.as_os_str() == "-" {
+                use std::io::Read;
+                let mut buf = String::new();
+                std::io::stdin().read_to_string(&mut buf)?;
+                buf
+            } else {
+                std::fs::read_to_string(&file)?
+            };
+
+            let mut entries = Vec::new();
+            for (i, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry: KvEntry = serde_json::from_str(line)
+                    .map_err(|e| CliError::invalid_input(format!("line {}: {e}", i + 1)))?;
+                entries.push(entry);
+            }
+
+            let afs = open_db(&db).await?;
+            let total = entries.len();
+            let written = afs.kv.import(entries, overwrite).await?;
+            let skipped = total - written;
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "imported": written, "skipped": skipped })
+                );
+            } else {
+                println!("Imported {written} entries ({skipped} skipped)");
+            }
+            afs.close().await?;
+        }
     }
     Ok(())
 }
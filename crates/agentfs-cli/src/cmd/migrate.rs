@@ -6,6 +6,7 @@ use clap::Args;
 #[derive(Args)]
 pub struct MigrateArgs {
     /// Path to the database
+    #[arg(id = "db", value_name = "PATH")]
     pub path: PathBuf,
 }
 
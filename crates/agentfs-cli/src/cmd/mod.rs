@@ -1,5 +1,8 @@
 pub mod analytics;
+pub mod backup;
 pub mod checkpoint;
+pub mod doctor;
+pub mod events;
 pub mod fs;
 pub mod gc;
 pub mod info;
@@ -8,6 +11,5 @@ pub mod integrity;
 pub mod kv;
 pub mod migrate;
 pub mod sessions;
-pub mod snapshot;
 pub mod timeline;
 pub mod tools;
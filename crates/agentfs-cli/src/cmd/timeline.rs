@@ -1,25 +1,35 @@
 use std::path::PathBuf;
 
 use agentfs_core::config::AgentFSConfig;
+use agentfs_core::events::{EventFilter, TimeBound};
 use clap::Args;
 use comfy_table::{Table, presets::UTF8_FULL_CONDENSED};
 
 #[derive(Args)]
 pub struct TimelineArgs {
     /// Path to the database
+    #[arg(id = "db", value_name = "PATH")]
     pub path: PathBuf,
 
     /// Number of recent events to show
     #[arg(long, default_value = "50")]
     pub limit: i64,
 
-    /// Filter by event type
+    /// Filter by event type, or a prefix ending in * (e.g. "tool:*")
     #[arg(long, name = "type")]
     pub event_type: Option<String>,
 
     /// Filter by session ID
     #[arg(long)]
     pub session: Option<String>,
+
+    /// Only events at or after this time (2026-01-31, 2026-01-31T12:00, 15m, 2h, 7d)
+    #[arg(long)]
+    pub since: Option<TimeBound>,
+
+    /// Keep streaming new events as they are logged, until interrupted
+    #[arg(short, long)]
+    pub follow: bool,
 }
 
 pub async fn run(args: TimelineArgs, json: bool) -> anyhow::Result<()> {
@@ -28,14 +38,21 @@ pub async fn run(args: TimelineArgs, json: bool) -> anyhow::Result<()> {
         .build();
     let afs = agentfs_core::AgentFS::open(config).await?;
 
-    let events = if let Some(ref event_type) = args.event_type {
-        afs.events.by_type(event_type, args.limit).await?
-    } else if let Some(ref session_id) = args.session {
-        afs.events.by_session(session_id, args.limit).await?
-    } else {
-        afs.events.recent(args.limit).await?
+    let filter = EventFilter {
+        event_type: args.event_type,
+        session_id: args.session,
+        since: args.since,
+        ..Default::default()
     };
 
+    if args.follow {
+        super::events::follow(&afs, filter, args.limit, 1000, json).await?;
+        afs.close().await?;
+        return Ok(());
+    }
+
+    let events = afs.events.query(&filter, Some(args.limit)).await?;
+
     if json {
         println!("{}", serde_json::to_string_pretty(&events)?);
     } else {
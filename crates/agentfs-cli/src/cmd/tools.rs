@@ -13,9 +13,12 @@ pub enum ToolsCommands {
         #[arg(long, default_value = "20")]
         limit: i64,
     },
-    /// Show tool call statistics
+    /// Show per-tool call counts, error rates, and latency percentiles
     Stats {
         db: PathBuf,
+        /// Only include calls from this session
+        #[arg(long)]
+        session: Option<String>,
     },
 }
 
@@ -30,13 +33,14 @@ pub async fn run(cmd: ToolsCommands, json: bool) -> anyhow::Result<()> {
             } else {
                 let mut table = Table::new();
                 table.load_preset(UTF8_FULL_CONDENSED);
-                table.set_header(vec!["ID", "Tool", "Status", "Started", "Ended"]);
+                table.set_header(vec!["ID", "Tool", "Status", "Session", "Started", "Ended"]);
 
                 for call in &calls {
                     table.add_row(vec![
                         &call.id.to_string(),
                         &call.tool_name,
                         &call.status,
+                        call.session_id.as_deref().unwrap_or("-"),
                         &call.started_at,
                         call.ended_at.as_deref().unwrap_or("-"),
                     ]);
@@ -46,28 +50,39 @@ pub async fn run(cmd: ToolsCommands, json: bool) -> anyhow::Result<()> {
             }
             afs.close().await?;
         }
-        ToolsCommands::Stats { db } => {
+        ToolsCommands::Stats { db, session } => {
             let afs = open_db(&db).await?;
-            let stats = afs.tools.stats().await?;
+            let stats = afs.tools.stats(session.as_deref()).await?;
 
             if json {
                 println!("{}", serde_json::to_string_pretty(&stats)?);
             } else {
                 let mut table = Table::new();
                 table.load_preset(UTF8_FULL_CONDENSED);
-                table.set_header(vec!["Tool", "Total", "Success", "Error", "In Progress"]);
+                table.set_header(vec![
+                    "Tool", "Total", "Success", "Error", "In Progress", "Error %", "p50", "p95",
+                    "Max",
+                ]);
 
                 for s in &stats {
                     table.add_row(vec![
-                        &s.tool_name,
-                        &s.total.to_string(),
-                        &s.successes.to_string(),
-                        &s.errors.to_string(),
-                        &s.in_progress.to_string(),
+                        s.tool_name.clone(),
+                        s.total.to_string(),
+                        s.successes.to_string(),
+                        s.errors.to_string(),
+                        s.in_progress.to_string(),
+                        format!("{:.1}", s.error_rate * 100.0),
+                        format_ms(s.p50_ms),
+                        format_ms(s.p95_ms),
+                        format_ms(s.max_ms),
                     ]);
                 }
 
                 println!("{table}");
+
+                if stats.is_empty() {
+                    println!("(no tool calls)");
+                }
             }
             afs.close().await?;
         }
@@ -75,6 +90,14 @@ pub async fn run(cmd: ToolsCommands, json: bool) -> anyhow::Result<()> {
     Ok(())
 }
 
+fn format_ms(ms: Option<i64>) -> String {
+    match ms {
+        Some(ms) if ms >= 1000 => format!("{:.1}s", ms as f64 / 1000.0),
+        Some(ms) => format!("{ms}ms"),
+        None => "-".to_string(),
+    }
+}
+
 async fn open_db(path: &PathBuf) -> anyhow::Result<agentfs_core::AgentFS> {
     let config = AgentFSConfig::builder(path)
         .checkpoint_interval_secs(0)
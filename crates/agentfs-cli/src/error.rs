@@ -0,0 +1,231 @@
+use agentfs_core::error::AgentFSError;
+use serde_json::{json, Value};
+
+/// Exit code table, shown in `infinity --help`.
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  success
+  1  internal error
+  2  usage error (bad arguments)
+  3  not found (database, file, key, backup)
+  4  already exists
+  5  invalid input (bad path, wrong file type, malformed data)
+  6  integrity problem (checksum mismatch, failed check/verify)
+  7  schema version mismatch
+  8  I/O or SQLite error
+  9  database busy (locked by another process)
+
+With --json, errors are printed to stdout as {\"code\", \"message\", \"detail\"}.";
+
+/// Error classes, each with a stable string code and exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    Internal,
+    Usage,
+    NotFound,
+    AlreadyExists,
+    InvalidInput,
+    Integrity,
+    Schema,
+    Io,
+    Busy,
+}
+
+impl ErrorClass {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Internal => "internal",
+            Self::Usage => "usage",
+            Self::NotFound => "not_found",
+            Self::AlreadyExists => "already_exists",
+            Self::InvalidInput => "invalid_input",
+            Self::Integrity => "integrity",
+            Self::Schema => "schema_mismatch",
+            Self::Io => "io",
+            Self::Busy => "busy",
+        }
+    }
+
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Internal => 1,
+            Self::Usage => 2,
+            Self::NotFound => 3,
+            Self::AlreadyExists => 4,
+            Self::InvalidInput => 5,
+            Self::Integrity => 6,
+            Self::Schema => 7,
+            Self::Io => 8,
+            Self::Busy => 9,
+        }
+    }
+}
+
+/// An error raised by the CLI itself rather than by agentfs-core.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct CliError {
+    pub class: ErrorClass,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self {
+            class: ErrorClass::InvalidInput,
+            message: message.into(),
+        }
+    }
+}
+
+/// Classify an error by the first recognizable cause in its chain.
+pub fn classify(err: &anyhow::Error) -> ErrorClass {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<CliError>() {
+            return e.class;
+        }
+        if let Some(e) = cause.downcast_ref::<AgentFSError>() {
+            return classify_core(e);
+        }
+        if let Some(e) = cause.downcast_ref::<rusqlite::Error>() {
+            return classify_sqlite(e);
+        }
+        if cause.is::<serde_json::Error>() {
+            return ErrorClass::InvalidInput;
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return match e.kind() {
+                std::io::ErrorKind::NotFound => ErrorClass::NotFound,
+                std::io::ErrorKind::AlreadyExists => ErrorClass::AlreadyExists,
+                _ => ErrorClass::Io,
+            };
+        }
+    }
+    ErrorClass::Internal
+}
+
+fn classify_core(e: &AgentFSError) -> ErrorClass {
+    match e {
+        AgentFSError::Sqlite(e) => classify_sqlite(e),
+        AgentFSError::Io(_) => ErrorClass::Io,
+        AgentFSError::Json(_) => ErrorClass::InvalidInput,
+        AgentFSError::DatabaseNotFound { .. }
+        | AgentFSError::FileNotFound { .. }
+        | AgentFSError::KeyNotFound { .. }
+        | AgentFSError::BackupNotFound { .. } => ErrorClass::NotFound,
+        AgentFSError::DatabaseExists { .. } | AgentFSError::AlreadyExists { .. } => {
+            ErrorClass::AlreadyExists
+        }
+        AgentFSError::NotADirectory { .. }
+        | AgentFSError::NotAFile { .. }
+        | AgentFSError::DirectoryNotEmpty { .. }
+        | AgentFSError::InvalidPath { .. }
+        | AgentFSError::InvalidPattern { .. }
+        | AgentFSError::EditFailed { .. } => ErrorClass::InvalidInput,
+        AgentFSError::ChecksumMismatch { .. } => ErrorClass::Integrity,
+        AgentFSError::SchemaMismatch { .. } => ErrorClass::Schema,
+        AgentFSError::PoolShutDown | AgentFSError::Other(_) => ErrorClass::Internal,
+    }
+}
+
+fn classify_sqlite(e: &rusqlite::Error) -> ErrorClass {
+    match e.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::DatabaseBusy)
+        | Some(rusqlite::ErrorCode::DatabaseLocked) => ErrorClass::Busy,
+        Some(rusqlite::ErrorCode::DatabaseCorrupt)
+        | Some(rusqlite::ErrorCode::NotADatabase) => ErrorClass::Integrity,
+        _ => ErrorClass::Io,
+    }
+}
+
+/// Structured detail for errors that carry more than a message.
+fn detail(err: &anyhow::Error) -> Value {
+    if let Some(e) = err.chain().find_map(|c| c.downcast_ref::<AgentFSError>()) {
+        let fields = match e {
+            AgentFSError::DatabaseNotFound { path } | AgentFSError::DatabaseExists { path } => {
+                json!({ "path": path })
+            }
+            AgentFSError::FileNotFound { path }
+            | AgentFSError::NotADirectory { path }
+            | AgentFSError::NotAFile { path }
+            | AgentFSError::DirectoryNotEmpty { path }
+            | AgentFSError::AlreadyExists { path }
+            | AgentFSError::InvalidPath { path } => json!({ "path": path }),
+            AgentFSError::InvalidPattern { pattern, reason } => {
+                json!({ "pattern": pattern, "reason": reason })
+            }
+            AgentFSError::EditFailed { path, reason } => json!({ "path": path, "reason": reason }),
+            AgentFSError::KeyNotFound { key } => json!({ "key": key }),
+            AgentFSError::BackupNotFound { id } => json!({ "id": id }),
+            AgentFSError::SchemaMismatch { expected, found } => {
+                json!({ "expected": expected, "found": found })
+            }
+            AgentFSError::ChecksumMismatch {
+                ino,
+                chunk_index,
+                expected,
+                actual,
+            } => json!({
+                "ino": ino,
+                "chunk_index": chunk_index,
+                "expected": format!("{expected:#018x}"),
+                "actual": format!("{actual:#018x}"),
+            }),
+            _ => Value::Null,
+        };
+        if !fields.is_null() {
+            return fields;
+        }
+    }
+
+    let causes: Vec<String> = err.chain().skip(1).map(|c| c.to_string()).collect();
+    if causes.is_empty() {
+        Value::Null
+    } else {
+        json!({ "causes": causes })
+    }
+}
+
+/// Print an error in the requested format and return its exit code.
+pub fn report(err: &anyhow::Error, json: bool) -> i32 {
+    let class = classify(err);
+    if json {
+        println!(
+            "{}",
+            json!({
+                "code": class.code(),
+                "message": err.to_string(),
+                "detail": detail(err),
+            })
+        );
+    } else {
+        eprintln!("error: {err:#}");
+    }
+    class.exit_code()
+}
+
+/// Report a clap parse failure. Help and version output pass through untouched.
+pub fn report_usage(err: clap::Error, json: bool) -> i32 {
+    use clap::error::ErrorKind;
+    if !json
+        || matches!(
+            err.kind(),
+            ErrorKind::DisplayHelp
+                | ErrorKind::DisplayVersion
+                | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+        )
+    {
+        err.exit();
+    }
+    let message = err.kind().as_str().unwrap_or("invalid arguments").to_string();
+    println!(
+        "{}",
+        json!({
+            "code": ErrorClass::Usage.code(),
+            "message": message,
+            "detail": { "usage": err.render().to_string() },
+        })
+    );
+    ErrorClass::Usage.exit_code()
+}
+
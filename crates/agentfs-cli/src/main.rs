@@ -1,14 +1,24 @@
 mod cmd;
+mod error;
+mod profile;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 
 #[derive(Parser)]
-#[command(name = "infinity", about = "SQLite-backed agent filesystem with proper durability")]
+#[command(
+    name = "infinity",
+    about = "SQLite-backed agent filesystem with proper durability",
+    after_help = error::EXIT_CODES_HELP
+)]
 struct Cli {
     /// Output as JSON instead of human-readable tables
     #[arg(long, global = true)]
     json: bool,
 
+    /// Use a named profile from ~/.infinity/cli.toml
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -30,17 +40,23 @@ enum Commands {
     Tools(cmd::tools::ToolsCommands),
     /// Unified audit timeline
     Timeline(cmd::timeline::TimelineArgs),
-    /// Integrity checking
+    /// Query, follow and export the event log
+    #[command(subcommand)]
+    Events(cmd::events::EventsCommands),
+    /// Integrity checking and repair
     #[command(subcommand)]
     Integrity(cmd::integrity::IntegrityCommands),
     /// Garbage collection
     Gc(cmd::gc::GcArgs),
-    /// Create a snapshot using SQLite backup API
-    Snapshot(cmd::snapshot::SnapshotArgs),
+    /// Full and incremental backups with verification and restore
+    #[command(subcommand)]
+    Backup(cmd::backup::BackupCommands),
     /// Force a WAL checkpoint
     Checkpoint(cmd::checkpoint::CheckpointArgs),
     /// Run schema migration
     Migrate(cmd::migrate::MigrateArgs),
+    /// Diagnose database health and suggest fixes
+    Doctor(cmd::doctor::DoctorArgs),
     /// Session management
     #[command(subcommand)]
     Sessions(cmd::sessions::SessionsCommands),
@@ -50,30 +66,63 @@ enum Commands {
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let raw_json = args.iter().any(|a| a == "--json");
+
+    let active = profile::CliConfig::load().and_then(|config| {
+        config.profile(profile::scan_option(&args, "profile").as_deref())
+    });
+    let active = match active {
+        Ok(active) => active,
+        Err(e) => std::process::exit(error::report(&e, raw_json)),
+    };
+
+    let mut command = Cli::command();
+    if let Some(color) = active.color {
+        command = command.color(color.into());
+    }
+    command.build();
+    if let Some(db) = &active.db {
+        profile::insert_default_db(&command, &mut args, db);
+    }
+
+    let cli = match command
+        .try_get_matches_from(args)
+        .and_then(|m| Cli::from_arg_matches(&m))
+    {
+        Ok(cli) => cli,
+        Err(e) => std::process::exit(error::report_usage(e, raw_json || active.json)),
+    };
+    let json = cli.json || active.json;
+
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
         )
+        .with_ansi(!matches!(active.color, Some(profile::ColorSetting::Never)))
         .init();
 
-    let cli = Cli::parse();
-    let json = cli.json;
-
-    match cli.command {
+    let result = match cli.command {
         Commands::Init(args) => cmd::init::run(args).await,
         Commands::Info(args) => cmd::info::run(args, json).await,
         Commands::Fs(sub) => cmd::fs::run(sub, json).await,
         Commands::Kv(sub) => cmd::kv::run(sub, json).await,
         Commands::Tools(sub) => cmd::tools::run(sub, json).await,
         Commands::Timeline(args) => cmd::timeline::run(args, json).await,
+        Commands::Events(sub) => cmd::events::run(sub, json).await,
         Commands::Integrity(sub) => cmd::integrity::run(sub, json).await,
         Commands::Gc(args) => cmd::gc::run(args, json).await,
-        Commands::Snapshot(args) => cmd::snapshot::run(args).await,
+        Commands::Backup(sub) => cmd::backup::run(sub, json).await,
         Commands::Checkpoint(args) => cmd::checkpoint::run(args).await,
         Commands::Migrate(args) => cmd::migrate::run(args).await,
+        Commands::Doctor(args) => cmd::doctor::run(args, json).await,
         Commands::Sessions(sub) => cmd::sessions::run(sub, json).await,
         Commands::Analytics(sub) => cmd::analytics::run(sub, json).await,
+    };
+
+    if let Err(e) = result {
+        std::process::exit(error::report(&e, json));
     }
 }
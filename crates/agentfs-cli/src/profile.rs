@@ -0,0 +1,301 @@
+//! `~/.infinity/cli.toml` — named profiles with per-profile defaults.
+//!
+//! ```toml
+//! default_profile = "work"
+//!
+//! [profiles.work]
+//! db = "~/agents/work.db"
+//! json = false
+//! color = "auto"
+//! ```
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::{CliError, ErrorClass};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CliConfig {
+    /// Profile used when `--profile` is not given (falls back to `default`).
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Database used when a command's database argument is omitted.
+    pub db: Option<PathBuf>,
+    /// Output JSON without passing `--json`.
+    #[serde(default)]
+    pub json: bool,
+    /// Color for help and diagnostics: auto, always, never.
+    pub color: Option<ColorSetting>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSetting {
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<ColorSetting> for clap::ColorChoice {
+    fn from(c: ColorSetting) -> Self {
+        match c {
+            ColorSetting::Auto => clap::ColorChoice::Auto,
+            ColorSetting::Always => clap::ColorChoice::Always,
+            ColorSetting::Never => clap::ColorChoice::Never,
+        }
+    }
+}
+
+/// Location of the CLI config file.
+pub fn config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".infinity").join("cli.toml"))
+}
+
+impl CliConfig {
+    /// Load the config file; a missing file yields an empty config.
+    pub fn load() -> anyhow::Result<Self> {
+        match config_path() {
+            Some(path) if path.exists() => Self::load_from(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Load `path`, expanding a leading `~/` in each profile's `db`.
+    pub fn load_from(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let mut config: Self = toml::from_str(&text).map_err(|e| {
+            CliError::invalid_input(format!("{}: {}", path.display(), e.message()))
+        })?;
+        for profile in config.profiles.values_mut() {
+            if let Some(db) = &profile.db {
+                profile.db = Some(expand_home(db));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Resolve the active profile. An explicitly requested profile must
+    /// exist; otherwise `default_profile`, then `default`, then none.
+    pub fn profile(&self, requested: Option<&str>) -> anyhow::Result<Profile> {
+        if let Some(name) = requested {
+            return self.profiles.get(name).cloned().ok_or_else(|| {
+                CliError {
+                    class: ErrorClass::NotFound,
+                    message: format!("profile not found: {name}"),
+                }
+                .into()
+            });
+        }
+        let name = self.default_profile.as_deref().unwrap_or("default");
+        Ok(self.profiles.get(name).cloned().unwrap_or_default())
+    }
+}
+
+/// `path` with a leading `~/` replaced by the home directory.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Find the value of a global option (`--name value` or `--name=value`)
+/// before clap has parsed the command line.
+pub fn scan_option(args: &[OsString], name: &str) -> Option<String> {
+    let flag = format!("--{name}");
+    let prefix = format!("--{name}=");
+    let mut iter = args.iter().skip(1).map(|a| a.to_string_lossy());
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == flag {
+            return iter.next().map(|v| v.into_owned());
+        }
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Insert the profile's database into `args` when the command omits it.
+///
+/// Every command takes its database as the argument with id `db`: a
+/// positional, or `--db` for `doctor`. The positional counts as given when
+/// the command's other positionals can't account for all the values, as
+/// omitted when too few values were given for it to be there, and
+/// otherwise as given only if the value in its place is an existing file.
+pub fn insert_default_db(cmd: &clap::Command, args: &mut Vec<OsString>, db: &Path) {
+    let mut cmd = cmd;
+    let mut insert_at = None;
+    let mut i = 1;
+
+    // Walk down the subcommand path, skipping global options.
+    while i < args.len() {
+        let arg = args[i].to_string_lossy().into_owned();
+        if arg.starts_with('-') {
+            i += 1 + usize::from(takes_separate_value(cmd, &arg));
+            continue;
+        }
+        match cmd.find_subcommand(&arg) {
+            Some(sub) => {
+                cmd = sub;
+                i += 1;
+                insert_at = Some(i);
+                if !cmd.has_subcommands() {
+                    break;
+                }
+            }
+            None => return,
+        }
+    }
+    let Some(insert_at) = insert_at else { return };
+    if cmd.has_subcommands() {
+        return;
+    }
+    let Some(db_arg) = cmd.get_arguments().find(|a| a.get_id() == "db") else {
+        return;
+    };
+
+    let db = db.as_os_str().to_owned();
+    if !db_arg.is_positional() {
+        let Some(long) = db_arg.get_long() else { return };
+        let flag = format!("--{long}");
+        let given = args[insert_at..].iter().any(|a| {
+            let a = a.to_string_lossy();
+            a == flag.as_str() || a.strip_prefix(flag.as_str()).is_some_and(|v| v.starts_with('='))
+        });
+        if !given {
+            args.splice(insert_at..insert_at, [OsString::from(flag), db]);
+        }
+        return;
+    }
+
+    // Indices in `args` of the positional values given.
+    let mut given = Vec::new();
+    let mut j = insert_at;
+    while j < args.len() {
+        let arg = args[j].to_string_lossy().into_owned();
+        if arg == "--" {
+            given.extend(j + 1..args.len());
+            break;
+        }
+        if arg.starts_with('-') && arg.len() > 1 {
+            j += 1 + usize::from(takes_separate_value(cmd, &arg));
+            continue;
+        }
+        given.push(j);
+        j += 1;
+    }
+
+    let positionals: Vec<_> = cmd.get_positionals().collect();
+    let Some(db_index) = positionals.iter().position(|a| a.get_id() == "db") else {
+        return;
+    };
+    let required = positionals.iter().filter(|a| a.is_required_set()).count();
+    let omitted = if given.len() >= positionals.len() {
+        false
+    } else if given.len() < required {
+        true
+    } else {
+        given.get(db_index).is_none_or(|&at| !Path::new(&args[at]).is_file())
+    };
+    if omitted {
+        let at = given.get(db_index).copied().unwrap_or(args.len());
+        args.insert(at, db);
+    }
+}
+
+/// Whether `arg` is an option whose value is the next argument.
+fn takes_separate_value(cmd: &clap::Command, arg: &str) -> bool {
+    let found = if let Some(long) = arg.strip_prefix("--") {
+        if long.contains('=') {
+            return false;
+        }
+        cmd.get_arguments().find(|a| a.get_long() == Some(long))
+    } else {
+        let mut chars = arg.chars().skip(1);
+        match (chars.next(), chars.next()) {
+            (Some(short), None) => cmd.get_arguments().find(|a| a.get_short() == Some(short)),
+            _ => None,
+        }
+    };
+    found.is_some_and(|a| a.get_action().takes_values())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    /// `line` after inserting `db` the way `main` does.
+    fn with_db(line: &str, db: &Path) -> Vec<String> {
+        let mut command = crate::Cli::command();
+        command.build();
+        let mut args: Vec<OsString> = line.split_whitespace().map(OsString::from).collect();
+        insert_default_db(&command, &mut args, db);
+        args.into_iter().map(|a| a.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn test_load_expands_home_in_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cli.toml");
+        std::fs::write(
+            &path,
+            "[profiles.work]\ndb = \"~/agents/work.db\"\n[profiles.abs]\ndb = \"/srv/a.db\"\n[profiles.rel]\ndb = \"~user/b.db\"\n",
+        )
+        .unwrap();
+        let config = CliConfig::load_from(&path).unwrap();
+        let db = |name: &str| config.profile(Some(name)).unwrap().db.unwrap();
+        assert_eq!(db("work"), dirs::home_dir().unwrap().join("agents/work.db"));
+        assert_eq!(db("abs"), PathBuf::from("/srv/a.db"));
+        assert_eq!(db("rel"), PathBuf::from("~user/b.db"));
+    }
+
+    #[test]
+    fn test_insert_default_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = dir.path().join("profile.db");
+        let other = dir.path().join("other.db");
+        std::fs::write(&other, b"").unwrap();
+        let p = profile.to_string_lossy().into_owned();
+        let o = other.to_string_lossy().into_owned();
+        let line = |parts: &[&str]| parts.join(" ");
+        let words = |parts: &[&str]| parts.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // Omitted: inserted where the database goes.
+        assert_eq!(with_db("infinity fs ls", &profile), words(&["infinity", "fs", "ls", &p]));
+        assert_eq!(with_db("infinity fs ls /docs", &profile), words(&["infinity", "fs", "ls", &p, "/docs"]));
+        assert_eq!(with_db("infinity fs cat /a.md", &profile), words(&["infinity", "fs", "cat", &p, "/a.md"]));
+        assert_eq!(with_db("infinity --json kv set k v", &profile), words(&["infinity", "--json", "kv", "set", &p, "k", "v"]));
+        assert_eq!(with_db("infinity info", &profile), words(&["infinity", "info", &p]));
+        assert_eq!(with_db("infinity doctor --fix", &profile), words(&["infinity", "doctor", "--db", &p, "--fix"]));
+
+        // Given explicitly: left alone, even where an optional positional
+        // could have taken the value.
+        for given in [
+            line(&["infinity", "fs", "ls", &o]),
+            line(&["infinity", "fs", "ls", &o, "/docs"]),
+            line(&["infinity", "fs", "cat", "missing.db", "/a.md"]),
+            line(&["infinity", "kv", "set", &o, "k", "v"]),
+            line(&["infinity", "info", "new.db"]),
+            line(&["infinity", "doctor", "--db", &o]),
+            line(&["infinity", "doctor", &format!("--db={o}")]),
+            "infinity fs".to_string(),
+        ] {
+            assert_eq!(with_db(&given, &profile), given.split_whitespace().collect::<Vec<_>>(), "{given}");
+        }
+    }
+}
@@ -16,8 +16,15 @@ pub struct TokenRecord {
     pub cache_write_tokens: i64,
     pub cost_microcents: i64,
     pub recorded_at: Option<String>,
+    /// What the tokens were spent on, e.g. `reflection`; `None` for agent
+    /// turns.
+    #[serde(default)]
+    pub purpose: Option<String>,
 }
 
+/// Purpose of tokens recorded without one.
+pub const DEFAULT_PURPOSE: &str = "agent";
+
 /// Aggregated usage summary.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct UsageSummary {
@@ -38,6 +45,16 @@ pub struct ModelBreakdown {
     pub cost_microcents: i64,
 }
 
+/// Per-purpose breakdown.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PurposeBreakdown {
+    pub purpose: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_microcents: i64,
+    pub record_count: i64,
+}
+
 /// Per-session cost.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct SessionCost {
@@ -47,6 +64,81 @@ pub struct SessionCost {
     pub cost_microcents: i64,
 }
 
+/// Width of the time buckets usage is grouped into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Bucket {
+    Hour,
+    #[default]
+    Day,
+    /// Weeks starting on Monday.
+    Week,
+    Month,
+}
+
+impl Bucket {
+    /// Parse `hour`, `day`, `week` or `month`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "hour" => Some(Self::Hour),
+            "day" => Some(Self::Day),
+            "week" => Some(Self::Week),
+            "month" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    /// SQL expression labelling a `recorded_at` timestamp with its bucket.
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Hour => "strftime('%Y-%m-%dT%H:00', recorded_at)",
+            Self::Day => "date(recorded_at)",
+            Self::Week => "date(recorded_at, '-6 days', 'weekday 1')",
+            Self::Month => "strftime('%Y-%m', recorded_at)",
+        }
+    }
+}
+
+/// Usage of one model within one time bucket.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UsageBucket {
+    /// Start of the bucket: `2026-03-04T15:00`, `2026-03-04` or `2026-03`.
+    pub bucket: String,
+    pub model: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_microcents: i64,
+    pub record_count: i64,
+}
+
+/// Usage across all models on one day.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DailyUsage {
+    /// `2026-03-04`.
+    pub day: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cache_write_tokens: i64,
+    pub cost_microcents: i64,
+    /// Sessions that recorded usage that day.
+    pub sessions: i64,
+    pub record_count: i64,
+}
+
+/// Every total of one bucket, which [`UsageBucket`] and [`DailyUsage`]
+/// are cut from.
+struct BucketTotals {
+    bucket: String,
+    model: String,
+    input_tokens: i64,
+    output_tokens: i64,
+    cache_read_tokens: i64,
+    cache_write_tokens: i64,
+    cost_microcents: i64,
+    sessions: i64,
+    record_count: i64,
+}
+
 /// Token usage analytics.
 pub struct Analytics {
     writer: Arc<WriterHandle>,
@@ -65,8 +157,8 @@ impl Analytics {
                 conn.execute(
                     "INSERT INTO token_usage \
                      (session_id, tool_call_id, model, input_tokens, output_tokens, \
-                      cache_read_tokens, cache_write_tokens, cost_microcents) \
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                      cache_read_tokens, cache_write_tokens, cost_microcents, purpose) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
                     rusqlite::params![
                         record.session_id,
                         record.tool_call_id,
@@ -76,6 +168,7 @@ impl Analytics {
                         record.cache_read_tokens,
                         record.cache_write_tokens,
                         record.cost_microcents,
+                        record.purpose,
                     ],
                 )?;
                 Ok(conn.last_insert_rowid())
@@ -159,8 +252,150 @@ impl Analytics {
         Ok(rows)
     }
 
+    /// Get usage grouped by purpose, agent turns included.
+    pub async fn by_purpose(&self) -> Result<Vec<PurposeBreakdown>> {
+        let reader = self.readers.acquire().await?;
+        let mut stmt = reader.conn().prepare(
+            "SELECT COALESCE(purpose, ?1) as p, \
+                    SUM(input_tokens) as inp, \
+                    SUM(output_tokens) as outp, \
+                    SUM(cost_microcents) as cost, \
+                    COUNT(*) \
+             FROM token_usage GROUP BY p ORDER BY cost DESC",
+        )?;
+        let rows = stmt
+            .query_map([DEFAULT_PURPOSE], |row| {
+                Ok(PurposeBreakdown {
+                    purpose: row.get(0)?,
+                    input_tokens: row.get(1)?,
+                    output_tokens: row.get(2)?,
+                    cost_microcents: row.get(3)?,
+                    record_count: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Get a session's usage for one purpose.
+    pub async fn session_usage(&self, session_id: &str, purpose: &str) -> Result<UsageSummary> {
+        let reader = self.readers.acquire().await?;
+        reader.conn().query_row(
+            "SELECT \
+                COALESCE(SUM(input_tokens), 0), \
+                COALESCE(SUM(output_tokens), 0), \
+                COALESCE(SUM(cache_read_tokens), 0), \
+                COALESCE(SUM(cache_write_tokens), 0), \
+                COALESCE(SUM(cost_microcents), 0), \
+                COUNT(*) \
+             FROM token_usage WHERE session_id = ?1 AND COALESCE(purpose, ?3) = ?2",
+            rusqlite::params![session_id, purpose, DEFAULT_PURPOSE],
+            |row| {
+                Ok(UsageSummary {
+                    total_input_tokens: row.get(0)?,
+                    total_output_tokens: row.get(1)?,
+                    total_cache_read: row.get(2)?,
+                    total_cache_write: row.get(3)?,
+                    total_cost_microcents: row.get(4)?,
+                    record_count: row.get(5)?,
+                })
+            },
+        ).map_err(Into::into)
+    }
+
+    /// Get usage grouped by time bucket and model, oldest bucket first.
+    /// `since` is inclusive and `until` exclusive; both are ISO timestamps
+    /// or dates.
+    pub async fn usage_buckets(
+        &self,
+        bucket: Bucket,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<Vec<UsageBucket>> {
+        let rows = self.totals(bucket, true, since, until).await?;
+        Ok(rows
+            .into_iter()
+            .map(|t| UsageBucket {
+                bucket: t.bucket,
+                model: t.model,
+                input_tokens: t.input_tokens,
+                output_tokens: t.output_tokens,
+                cost_microcents: t.cost_microcents,
+                record_count: t.record_count,
+            })
+            .collect())
+    }
+
+    /// Get usage totals per day, oldest first. `since` is inclusive and
+    /// `until` exclusive, as for [`Analytics::usage_buckets`].
+    pub async fn daily(&self, since: Option<&str>, until: Option<&str>) -> Result<Vec<DailyUsage>> {
+        let rows = self.totals(Bucket::Day, false, since, until).await?;
+        Ok(rows
+            .into_iter()
+            .map(|t| DailyUsage {
+                day: t.bucket,
+                input_tokens: t.input_tokens,
+                output_tokens: t.output_tokens,
+                cache_read_tokens: t.cache_read_tokens,
+                cache_write_tokens: t.cache_write_tokens,
+                cost_microcents: t.cost_microcents,
+                sessions: t.sessions,
+                record_count: t.record_count,
+            })
+            .collect())
+    }
+
+    /// Usage totals per bucket, and per model too if `by_model`, recorded
+    /// in `[since, until)`. Without `by_model` the model is empty.
+    async fn totals(
+        &self,
+        bucket: Bucket,
+        by_model: bool,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<Vec<BucketTotals>> {
+        let (model, group) = if by_model { ("model", "b, model") } else { ("''", "b") };
+        let reader = self.readers.acquire().await?;
+        let mut stmt = reader.conn().prepare(&format!(
+            "SELECT {} as b, {model}, \
+                    SUM(input_tokens), \
+                    SUM(output_tokens), \
+                    SUM(cache_read_tokens), \
+                    SUM(cache_write_tokens), \
+                    SUM(cost_microcents), \
+                    COUNT(DISTINCT session_id), \
+                    COUNT(*) \
+             FROM token_usage \
+             WHERE (?1 IS NULL OR recorded_at >= ?1) AND (?2 IS NULL OR recorded_at < ?2) \
+             GROUP BY {group} ORDER BY {group}",
+            bucket.sql()
+        ))?;
+        let rows = stmt
+            .query_map(rusqlite::params![since, until], |row| {
+                Ok(BucketTotals {
+                    bucket: row.get(0)?,
+                    model: row.get(1)?,
+                    input_tokens: row.get(2)?,
+                    output_tokens: row.get(3)?,
+                    cache_read_tokens: row.get(4)?,
+                    cache_write_tokens: row.get(5)?,
+                    cost_microcents: row.get(6)?,
+                    sessions: row.get(7)?,
+                    record_count: row.get(8)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
     /// Get cost grouped by session.
     pub async fn by_session(&self) -> Result<Vec<SessionCost>> {
+        self.by_session_range(None, None).await
+    }
+
+    /// Get cost grouped by session, counting only usage recorded in
+    /// `[since, until)`.
+    pub async fn by_session_range(&self, since: Option<&str>, until: Option<&str>) -> Result<Vec<SessionCost>> {
         let reader = self.readers.acquire().await?;
         let mut stmt = reader.conn().prepare(
             "SELECT t.session_id, s.agent_name, \
@@ -169,10 +404,11 @@ impl Analytics {
              FROM token_usage t \
              LEFT JOIN sessions s ON t.session_id = s.session_id \
              WHERE t.session_id IS NOT NULL \
+               AND (?1 IS NULL OR t.recorded_at >= ?1) AND (?2 IS NULL OR t.recorded_at < ?2) \
              GROUP BY t.session_id ORDER BY cost DESC",
         )?;
         let rows = stmt
-            .query_map([], |row| {
+            .query_map(rusqlite::params![since, until], |row| {
                 Ok(SessionCost {
                     session_id: row.get(0)?,
                     agent_name: row.get(1)?,
@@ -184,34 +420,52 @@ impl Analytics {
         Ok(rows)
     }
 
+    /// Get a session's token usage records, oldest first.
+    pub async fn session_records(&self, session_id: &str) -> Result<Vec<TokenRecord>> {
+        let reader = self.readers.acquire().await?;
+        let mut stmt = reader.conn().prepare(
+            "SELECT id, session_id, tool_call_id, model, input_tokens, output_tokens, \
+                    cache_read_tokens, cache_write_tokens, cost_microcents, recorded_at, purpose \
+             FROM token_usage WHERE session_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map([session_id], row_to_record)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
     /// Get recent token usage records.
     pub async fn recent_usage(&self, limit: i64) -> Result<Vec<TokenRecord>> {
         let reader = self.readers.acquire().await?;
         let mut stmt = reader.conn().prepare(
             "SELECT id, session_id, tool_call_id, model, input_tokens, output_tokens, \
-                    cache_read_tokens, cache_write_tokens, cost_microcents, recorded_at \
+                    cache_read_tokens, cache_write_tokens, cost_microcents, recorded_at, purpose \
              FROM token_usage ORDER BY id DESC LIMIT ?1",
         )?;
         let rows = stmt
-            .query_map([limit], |row| {
-                Ok(TokenRecord {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    tool_call_id: row.get(2)?,
-                    model: row.get(3)?,
-                    input_tokens: row.get(4)?,
-                    output_tokens: row.get(5)?,
-                    cache_read_tokens: row.get(6)?,
-                    cache_write_tokens: row.get(7)?,
-                    cost_microcents: row.get(8)?,
-                    recorded_at: row.get(9)?,
-                })
-            })?
+            .query_map([limit], row_to_record)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(rows)
     }
 }
 
+/// Map a `token_usage` row selected in column order to a record.
+fn row_to_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<TokenRecord> {
+    Ok(TokenRecord {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        tool_call_id: row.get(2)?,
+        model: row.get(3)?,
+        input_tokens: row.get(4)?,
+        output_tokens: row.get(5)?,
+        cache_read_tokens: row.get(6)?,
+        cache_write_tokens: row.get(7)?,
+        cost_microcents: row.get(8)?,
+        recorded_at: row.get(9)?,
+        purpose: row.get(10)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,6 +503,7 @@ mod tests {
             cache_write_tokens: 0,
             cost_microcents: cost,
             recorded_at: None,
+            purpose: None,
         }
     }
 
@@ -288,5 +543,94 @@ mod tests {
         let recent = analytics.recent_usage(10).await.unwrap();
         assert_eq!(recent.len(), 2);
         assert_eq!(recent[0].model, "sonnet"); // most recent first
+
+        analytics
+            .writer
+            .with_conn(|conn| {
+                conn.execute("INSERT INTO sessions (session_id) VALUES ('s1')", [])?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        analytics
+            .record_usage(TokenRecord {
+                session_id: Some("s1".into()),
+                ..test_record("haiku", 10, 5, 5)
+            })
+            .await
+            .unwrap();
+        let session = analytics.session_records("s1").await.unwrap();
+        assert_eq!(session.len(), 1);
+        assert_eq!(session[0].model, "haiku");
+    }
+
+    #[tokio::test]
+    async fn by_purpose() {
+        let (analytics, _tmp) = setup().await;
+
+        analytics.record_usage(test_record("opus", 100, 50, 500)).await.unwrap();
+        for _ in 0..2 {
+            analytics
+                .record_usage(TokenRecord {
+                    purpose: Some("reflection".into()),
+                    ..test_record("haiku", 40, 10, 20)
+                })
+                .await
+                .unwrap();
+        }
+
+        let purposes = analytics.by_purpose().await.unwrap();
+        assert_eq!(purposes.len(), 2);
+        assert_eq!(purposes[0].purpose, DEFAULT_PURPOSE);
+        assert_eq!(purposes[1].purpose, "reflection");
+        assert_eq!(purposes[1].cost_microcents, 40);
+        assert_eq!(purposes[1].record_count, 2);
+        assert_eq!(analytics.recent_usage(1).await.unwrap()[0].purpose.as_deref(), Some("reflection"));
+    }
+
+    #[tokio::test]
+    async fn usage_buckets() {
+        let (analytics, _tmp) = setup().await;
+
+        analytics.record_usage(test_record("opus", 100, 50, 500)).await.unwrap();
+        analytics.record_usage(test_record("opus", 200, 100, 1000)).await.unwrap();
+        analytics.record_usage(test_record("haiku", 10, 5, 5)).await.unwrap();
+        analytics
+            .writer
+            .with_conn(|conn| {
+                conn.execute(
+                    "UPDATE token_usage SET recorded_at = '2026-03-02T10:00:00.000' WHERE id = 1",
+                    [],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let days = analytics.usage_buckets(Bucket::Day, None, None).await.unwrap();
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0].bucket, "2026-03-02");
+        assert_eq!(days[0].cost_microcents, 500);
+
+        let weeks = analytics
+            .usage_buckets(Bucket::Week, Some("2026-03-01"), Some("2026-03-09"))
+            .await
+            .unwrap();
+        assert_eq!(weeks.len(), 1);
+        assert_eq!(weeks[0].bucket, "2026-03-02");
+
+        let recent = analytics.usage_buckets(Bucket::Month, Some("2026-04-01"), None).await.unwrap();
+        assert_eq!(recent.iter().map(|b| b.record_count).sum::<i64>(), 2);
+        assert_eq!(Bucket::parse("week"), Some(Bucket::Week));
+        assert_eq!(Bucket::parse("year"), None);
+
+        let daily = analytics.daily(None, None).await.unwrap();
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].day, "2026-03-02");
+        assert_eq!(daily[1].input_tokens, 210);
+        assert_eq!(daily[1].cost_microcents, 1005);
+        assert_eq!(daily[1].record_count, 2);
+        assert_eq!(daily[1].sessions, 0);
+        assert!(analytics.daily(Some("2026-03-03"), Some("2026-03-04")).await.unwrap().is_empty());
     }
 }
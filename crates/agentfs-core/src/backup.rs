@@ -0,0 +1,463 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::error::{AgentFSError, Result};
+use crate::AgentFS;
+
+/// Manifest file listing every backup in a backup directory, oldest first.
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Magic header for incremental delta files.
+const DELTA_MAGIC: &[u8; 8] = b"AFSDELTA";
+
+/// Whether a backup is a complete database image or a page delta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupKind {
+    /// Complete database image produced by the SQLite backup API.
+    Full,
+    /// Pages that changed since the parent backup.
+    Incremental,
+}
+
+impl std::fmt::Display for BackupKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Full => write!(f, "full"),
+            Self::Incremental => write!(f, "incremental"),
+        }
+    }
+}
+
+/// A single backup recorded in the manifest.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BackupEntry {
+    pub id: String,
+    pub kind: BackupKind,
+    /// Backup this one was diffed against (incremental only).
+    pub parent: Option<String>,
+    /// File name inside the backup directory.
+    pub file: String,
+    pub created_at: String,
+    pub page_size: u32,
+    pub page_count: u32,
+    /// Pages stored in this backup (all pages for a full backup).
+    pub stored_pages: u32,
+    /// Size of the backup file on disk.
+    pub bytes: u64,
+    /// XXH3_64 of the reconstructed database image.
+    pub checksum: u64,
+}
+
+/// Result of verifying a backup chain.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BackupVerifyReport {
+    pub id: String,
+    /// Number of backup files needed to reconstruct this backup.
+    pub chain_len: usize,
+    pub checksum_ok: bool,
+    pub sqlite_integrity_ok: bool,
+    pub corrupt_chunks: usize,
+}
+
+impl BackupVerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.checksum_ok && self.sqlite_integrity_ok && self.corrupt_chunks == 0
+    }
+}
+
+/// A directory of full and incremental backups for one database.
+///
+/// Incremental backups store only the pages that differ from their parent,
+/// so restoring one replays the chain back to the nearest full backup.
+pub struct BackupStore {
+    dir: PathBuf,
+}
+
+impl BackupStore {
+    /// Open (creating if needed) a backup directory.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Default backup directory for a database: `<db>.backups` alongside it.
+    pub fn default_dir(db_path: &Path) -> PathBuf {
+        let mut name = db_path.as_os_str().to_os_string();
+        name.push(".backups");
+        PathBuf::from(name)
+    }
+
+    /// The backup directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// List all backups, oldest first.
+    pub fn list(&self) -> Result<Vec<BackupEntry>> {
+        let path = self.dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Resolve a backup ID (or `latest`) to its manifest entry.
+    pub fn get(&self, id: &str) -> Result<BackupEntry> {
+        let entries = self.list()?;
+        let found = if id == "latest" {
+            entries.last().cloned()
+        } else {
+            entries.into_iter().find(|e| e.id == id)
+        };
+        found.ok_or_else(|| AgentFSError::BackupNotFound { id: id.to_string() })
+    }
+
+    /// Back up `afs` into this directory.
+    ///
+    /// An incremental backup is diffed against the most recent backup; if
+    /// there is none yet, a full backup is taken instead.
+    pub async fn create(&self, afs: &AgentFS, incremental: bool) -> Result<BackupEntry> {
+        let mut entries = self.list()?;
+
+        let (stamp, created_at): (String, String) = {
+            let reader = afs.readers().acquire().await?;
+            reader.conn().query_row(
+                "SELECT strftime('%Y%m%d-%H%M%S', 'now'), strftime('%Y-%m-%dT%H:%M:%f', 'now')",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )?
+        };
+        let mut id = stamp.clone();
+        let mut n = 2;
+        while entries.iter().any(|e| e.id == id) {
+            id = format!("{stamp}-{n}");
+            n += 1;
+        }
+
+        let tmp = self.dir.join(format!(".{id}.tmp"));
+        afs.snapshot(&tmp).await?;
+        let image = std::fs::read(&tmp)?;
+        let page_size = page_size_of(&image)?;
+        let page_count = (image.len() / page_size as usize) as u32;
+        let checksum = xxh3_64(&image);
+
+        let parent = if incremental { entries.last().cloned() } else { None };
+        let entry = match parent {
+            Some(parent) if parent.page_size == page_size => {
+                let base = self.reconstruct(&parent)?;
+                let file = format!("{id}.delta");
+                let stored_pages = write_delta(&self.dir.join(&file), &base, &image, page_size)?;
+                std::fs::remove_file(&tmp)?;
+                BackupEntry {
+                    id,
+                    kind: BackupKind::Incremental,
+                    parent: Some(parent.id),
+                    bytes: std::fs::metadata(self.dir.join(&file))?.len(),
+                    file,
+                    created_at,
+                    page_size,
+                    page_count,
+                    stored_pages,
+                    checksum,
+                }
+            }
+            _ => {
+                let file = format!("{id}.db");
+                std::fs::rename(&tmp, self.dir.join(&file))?;
+                BackupEntry {
+                    id,
+                    kind: BackupKind::Full,
+                    parent: None,
+                    file,
+                    created_at,
+                    page_size,
+                    page_count,
+                    stored_pages: page_count,
+                    bytes: image.len() as u64,
+                    checksum,
+                }
+            }
+        };
+
+        entries.push(entry.clone());
+        self.save(&entries)?;
+        Ok(entry)
+    }
+
+    /// Reconstruct a backup and check its checksum, SQLite integrity, and
+    /// chunk checksums.
+    pub fn verify(&self, id: &str) -> Result<BackupVerifyReport> {
+        let entry = self.get(id)?;
+        let chain_len = self.chain(&entry)?.len();
+        let image = self.reconstruct(&entry)?;
+        let checksum_ok = xxh3_64(&image) == entry.checksum;
+
+        let tmp = self.dir.join(format!(".{}.verify.tmp", entry.id));
+        std::fs::write(&tmp, &image)?;
+        let scrubbed = Connection::open(&tmp)
+            .map_err(AgentFSError::from)
+            .and_then(|conn| crate::integrity::scrub(&conn));
+        std::fs::remove_file(&tmp).ok();
+
+        let (sqlite_integrity_ok, corrupt_chunks) = match scrubbed {
+            Ok(report) => (report.sqlite_integrity_ok, report.corrupt_chunks.len()),
+            Err(_) => (false, 0),
+        };
+
+        Ok(BackupVerifyReport {
+            id: entry.id,
+            chain_len,
+            checksum_ok,
+            sqlite_integrity_ok,
+            corrupt_chunks,
+        })
+    }
+
+    /// Restore a backup to `dest`. Fails if `dest` exists unless `overwrite`
+    /// is set, in which case any stale `-wal`/`-shm` files are removed too.
+    pub fn restore(&self, id: &str, dest: &Path, overwrite: bool) -> Result<BackupEntry> {
+        if dest.exists() && !overwrite {
+            return Err(AgentFSError::DatabaseExists {
+                path: dest.to_path_buf(),
+            });
+        }
+
+        let entry = self.get(id)?;
+        let image = self.reconstruct(&entry)?;
+        if xxh3_64(&image) != entry.checksum {
+            return Err(AgentFSError::Other(format!(
+                "backup {} failed checksum verification",
+                entry.id
+            )));
+        }
+
+        for suffix in ["-wal", "-shm"] {
+            let mut side = dest.as_os_str().to_os_string();
+            side.push(suffix);
+            let side = PathBuf::from(side);
+            if side.exists() {
+                std::fs::remove_file(side)?;
+            }
+        }
+        std::fs::write(dest, image)?;
+        Ok(entry)
+    }
+
+    /// Keep the `keep` most recent full backups (with their incrementals)
+    /// and delete everything older. Returns the removed entries.
+    pub fn prune(&self, keep: usize) -> Result<Vec<BackupEntry>> {
+        let entries = self.list()?;
+        let full_ids: Vec<&str> = entries
+            .iter()
+            .filter(|e| e.kind == BackupKind::Full)
+            .map(|e| e.id.as_str())
+            .collect();
+        if full_ids.len() <= keep {
+            return Ok(Vec::new());
+        }
+
+        // Everything before the oldest retained full backup goes.
+        let cutoff = if keep == 0 {
+            entries.len()
+        } else {
+            let oldest_kept = full_ids[full_ids.len() - keep];
+            entries.iter().position(|e| e.id == oldest_kept).unwrap_or(0)
+        };
+
+        let (removed, kept) = entries.split_at(cutoff);
+        for entry in removed {
+            let path = self.dir.join(&entry.file);
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        self.save(kept)?;
+        Ok(removed.to_vec())
+    }
+
+    /// Entries from the nearest full backup up to and including `entry`.
+    fn chain(&self, entry: &BackupEntry) -> Result<Vec<BackupEntry>> {
+        let entries = self.list()?;
+        let mut chain = vec![entry.clone()];
+        let mut current = entry.clone();
+        while let Some(parent_id) = current.parent.clone() {
+            current = entries
+                .iter()
+                .find(|e| e.id == parent_id)
+                .cloned()
+                .ok_or(AgentFSError::BackupNotFound { id: parent_id })?;
+            chain.push(current.clone());
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// Rebuild the full database image for `entry`.
+    fn reconstruct(&self, entry: &BackupEntry) -> Result<Vec<u8>> {
+        let mut image = Vec::new();
+        for link in self.chain(entry)? {
+            let path = self.dir.join(&link.file);
+            match link.kind {
+                BackupKind::Full => image = std::fs::read(path)?,
+                BackupKind::Incremental => apply_delta(&path, &mut image)?,
+            }
+        }
+        Ok(image)
+    }
+
+    /// Atomically rewrite the manifest.
+    fn save(&self, entries: &[BackupEntry]) -> Result<()> {
+        let tmp = self.dir.join(format!(".{MANIFEST_FILE}.tmp"));
+        std::fs::write(&tmp, serde_json::to_string_pretty(entries)?)?;
+        std::fs::rename(tmp, self.dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+}
+
+/// Read the page size from a SQLite database header.
+fn page_size_of(image: &[u8]) -> Result<u32> {
+    if image.len() < 100 || &image[..16] != b"SQLite format 3\0" {
+        return Err(AgentFSError::Other("not a SQLite database image".to_string()));
+    }
+    let raw = u16::from_be_bytes([image[16], image[17]]) as u32;
+    // A stored value of 1 means 65536.
+    Ok(if raw == 1 { 65536 } else { raw })
+}
+
+/// Write the pages of `image` that differ from `base`. Returns the page count written.
+///
+/// Layout: magic, page_size (u32 BE), page_count (u32 BE), then
+/// `(page_no u32 BE, page bytes)` for each changed page.
+fn write_delta(path: &Path, base: &[u8], image: &[u8], page_size: u32) -> Result<u32> {
+    let ps = page_size as usize;
+    let page_count = (image.len() / ps) as u32;
+
+    let mut out = Vec::with_capacity(16);
+    out.extend_from_slice(DELTA_MAGIC);
+    out.extend_from_slice(&page_size.to_be_bytes());
+    out.extend_from_slice(&page_count.to_be_bytes());
+
+    let mut written = 0;
+    for (page_no, page) in image.chunks(ps).enumerate() {
+        let start = page_no * ps;
+        if base.get(start..start + ps) != Some(page) {
+            out.extend_from_slice(&(page_no as u32).to_be_bytes());
+            out.extend_from_slice(page);
+            written += 1;
+        }
+    }
+
+    std::fs::write(path, out)?;
+    Ok(written)
+}
+
+/// Apply a delta file on top of `image` in place.
+fn apply_delta(path: &Path, image: &mut Vec<u8>) -> Result<()> {
+    let data = std::fs::read(path)?;
+    let corrupt = || AgentFSError::Other(format!("corrupt delta file: {}", path.display()));
+    if data.len() < 16 || &data[..8] != DELTA_MAGIC {
+        return Err(corrupt());
+    }
+    let page_size = u32::from_be_bytes(data[8..12].try_into().unwrap()) as usize;
+    let page_count = u32::from_be_bytes(data[12..16].try_into().unwrap()) as usize;
+    if page_size == 0 {
+        return Err(corrupt());
+    }
+
+    image.resize(page_count * page_size, 0);
+    let mut pos = 16;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 4).ok_or_else(corrupt)?;
+        let page_no = u32::from_be_bytes(header.try_into().unwrap()) as usize;
+        let page = data.get(pos + 4..pos + 4 + page_size).ok_or_else(corrupt)?;
+        let start = page_no * page_size;
+        image
+            .get_mut(start..start + page_size)
+            .ok_or_else(corrupt)?
+            .copy_from_slice(page);
+        pos += 4 + page_size;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentFSConfig;
+    use tempfile::TempDir;
+
+    async fn setup(dir: &TempDir) -> AgentFS {
+        let cfg = AgentFSConfig::builder(dir.path().join("src.db"))
+            .checkpoint_interval_secs(0)
+            .build();
+        AgentFS::create(cfg).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn full_and_incremental_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let afs = setup(&dir).await;
+        let store = BackupStore::open(dir.path().join("backups")).unwrap();
+
+        afs.fs.write_file("/a.txt", b"first").await.unwrap();
+        let full = store.create(&afs, true).await.unwrap();
+        assert_eq!(full.kind, BackupKind::Full);
+
+        afs.fs.write_file("/b.txt", b"second").await.unwrap();
+        let incr = store.create(&afs, true).await.unwrap();
+        assert_eq!(incr.kind, BackupKind::Incremental);
+        assert_eq!(incr.parent.as_deref(), Some(full.id.as_str()));
+        assert!(incr.stored_pages < incr.page_count);
+
+        let report = store.verify("latest").unwrap();
+        assert!(report.is_clean());
+        assert_eq!(report.chain_len, 2);
+        afs.close().await.unwrap();
+
+        let dest = dir.path().join("restored.db");
+        store.restore(&incr.id, &dest, false).unwrap();
+        let cfg = AgentFSConfig::builder(&dest)
+            .checkpoint_interval_secs(0)
+            .build();
+        let restored = AgentFS::open(cfg).await.unwrap();
+        assert_eq!(restored.fs.read_file("/a.txt").await.unwrap(), b"first");
+        assert_eq!(restored.fs.read_file("/b.txt").await.unwrap(), b"second");
+        restored.close().await.unwrap();
+
+        let err = store.restore(&full.id, &dest, false).unwrap_err();
+        assert!(matches!(err, AgentFSError::DatabaseExists { .. }));
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_recent_chains() {
+        let dir = TempDir::new().unwrap();
+        let afs = setup(&dir).await;
+        let store = BackupStore::open(dir.path().join("backups")).unwrap();
+
+        let old = store.create(&afs, false).await.unwrap();
+        store.create(&afs, true).await.unwrap();
+        let recent = store.create(&afs, false).await.unwrap();
+
+        let removed = store.prune(1).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(!store.dir().join(&old.file).exists());
+
+        let remaining = store.list().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, recent.id);
+        afs.close().await.unwrap();
+    }
+
+    #[test]
+    fn missing_backup() {
+        let dir = TempDir::new().unwrap();
+        let store = BackupStore::open(dir.path()).unwrap();
+        let err = store.get("latest").unwrap_err();
+        assert!(matches!(err, AgentFSError::BackupNotFound { .. }));
+    }
+}
@@ -1,22 +1,17 @@
 use std::path::{Path, PathBuf};
 
 /// Controls SQLite `PRAGMA synchronous` level.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum DurabilityLevel {
     /// `synchronous = OFF` — no crash safety. Benchmark only.
     Off,
     /// `synchronous = NORMAL` — safe against process crash. **Default.**
+    #[default]
     Normal,
     /// `synchronous = FULL` — safe against process crash + power loss.
     Full,
 }
 
-impl Default for DurabilityLevel {
-    fn default() -> Self {
-        Self::Normal
-    }
-}
-
 impl std::fmt::Display for DurabilityLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
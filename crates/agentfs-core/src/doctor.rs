@@ -0,0 +1,421 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::error::{AgentFSError, Result};
+use crate::schema::{self, SCHEMA_VERSION};
+
+/// Sessions still `active` with no activity for this long are considered stale.
+pub const STALE_SESSION_HOURS: i64 = 24;
+
+/// Tool calls stuck in `started` for this long are considered dangling.
+pub const DANGLING_TOOL_CALL_MINUTES: i64 = 60;
+
+/// WAL size (in pages) above which a checkpoint is recommended.
+pub const WAL_WARN_PAGES: u64 = 4000;
+
+/// Rows of each kind repaired per transaction when fixing the memory index.
+const MEMORY_INDEX_REPAIR_BATCH: i64 = 500;
+
+/// Outcome of a single health check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl std::fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::Warn => write!(f, "warn"),
+            Self::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+/// A single health check result, with a suggested fix when not OK.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DoctorCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Health report for a database.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DoctorReport {
+    pub db_path: PathBuf,
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// The worst status across all checks.
+    pub fn status(&self) -> CheckStatus {
+        self.checks
+            .iter()
+            .map(|c| c.status)
+            .max()
+            .unwrap_or(CheckStatus::Ok)
+    }
+}
+
+/// What `apply_fixes` changed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DoctorFixes {
+    pub gc: crate::gc::GcReport,
+    pub sessions_closed: u64,
+    pub tool_calls_closed: u64,
+    pub memory_index: crate::memory_index::MemoryIndexReport,
+}
+
+/// Run all health checks against the database at `db_path`.
+///
+/// Opens its own connection without migrating, so an outdated schema is
+/// reported rather than silently upgraded.
+pub fn diagnose(db_path: &Path) -> Result<DoctorReport> {
+    if !db_path.exists() {
+        return Err(AgentFSError::DatabaseNotFound {
+            path: db_path.to_path_buf(),
+        });
+    }
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let checks = vec![
+        check_schema(&conn),
+        check_wal(&conn, db_path),
+        check_lock(db_path),
+        check_orphans(&conn),
+        check_fts(&conn),
+        check_stale_sessions(&conn),
+        check_dangling_tool_calls(&conn),
+    ];
+
+    Ok(DoctorReport {
+        db_path: db_path.to_path_buf(),
+        checks,
+    })
+}
+
+/// Apply the fixes that have no dedicated command: garbage collection,
+/// closing stale sessions and dangling tool calls, and repairing drift
+/// between memory entries, their metadata and the search index.
+pub fn apply_fixes(conn: &Connection) -> Result<DoctorFixes> {
+    let gc = crate::gc::collect_garbage(conn)?;
+
+    let tx = conn.unchecked_transaction()?;
+    let sessions_closed = tx.execute(
+        &format!(
+            "UPDATE sessions SET status = 'abandoned', \
+             ended_at = strftime('%Y-%m-%dT%H:%M:%f', 'now') \
+             WHERE {}",
+            stale_session_filter()
+        ),
+        [],
+    )? as u64;
+    let tool_calls_closed = tx.execute(
+        &format!(
+            "UPDATE tool_calls SET status = 'error', error_msg = 'abandoned (closed by doctor)', \
+             ended_at = strftime('%Y-%m-%dT%H:%M:%f', 'now') \
+             WHERE {}",
+            dangling_tool_call_filter()
+        ),
+        [],
+    )? as u64;
+    tx.commit()?;
+
+    let memory_index = crate::memory_index::repair(conn, MEMORY_INDEX_REPAIR_BATCH)?;
+
+    Ok(DoctorFixes {
+        gc,
+        sessions_closed,
+        tool_calls_closed,
+        memory_index,
+    })
+}
+
+fn stale_session_filter() -> String {
+    format!(
+        "status = 'active' \
+         AND julianday('now') - julianday(started_at) > {hours} / 24.0 \
+         AND NOT EXISTS (SELECT 1 FROM events e WHERE e.session_id = sessions.session_id \
+                         AND julianday('now') - julianday(e.recorded_at) <= {hours} / 24.0)",
+        hours = STALE_SESSION_HOURS
+    )
+}
+
+fn dangling_tool_call_filter() -> String {
+    format!(
+        "status = 'started' AND julianday('now') - julianday(started_at) > {} / 1440.0",
+        DANGLING_TOOL_CALL_MINUTES
+    )
+}
+
+fn count(conn: &Connection, sql: &str) -> Result<i64> {
+    Ok(conn.query_row(sql, [], |r| r.get(0))?)
+}
+
+/// Turn a query error into a failed check instead of aborting the report.
+fn checked(name: &'static str, f: impl FnOnce() -> Result<DoctorCheck>) -> DoctorCheck {
+    f().unwrap_or_else(|e| {
+        DoctorCheck::fail(
+            name,
+            format!("check could not run: {e}"),
+            "run `infinity integrity check` to look for corruption, then `integrity repair`",
+        )
+    })
+}
+
+fn check_schema(conn: &Connection) -> DoctorCheck {
+    checked("schema", || {
+        let version = schema::get_schema_version(conn)?;
+        Ok(match version.cmp(&SCHEMA_VERSION) {
+            std::cmp::Ordering::Equal => DoctorCheck::ok("schema", format!("v{version}")),
+            std::cmp::Ordering::Less => DoctorCheck::warn(
+                "schema",
+                format!("v{version}, latest is v{SCHEMA_VERSION}"),
+                "run `infinity migrate <db>`",
+            ),
+            std::cmp::Ordering::Greater => DoctorCheck::fail(
+                "schema",
+                format!("v{version} is newer than this build supports (v{SCHEMA_VERSION})"),
+                "upgrade the infinity binaries",
+            ),
+        })
+    })
+}
+
+fn check_wal(conn: &Connection, db_path: &Path) -> DoctorCheck {
+    checked("wal", || {
+        let mut wal_path = db_path.as_os_str().to_os_string();
+        wal_path.push("-wal");
+        let wal_bytes = std::fs::metadata(PathBuf::from(wal_path))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let page_size: i64 = conn.pragma_query_value(None, "page_size", |r| r.get(0))?;
+        let pages = wal_bytes / page_size.max(1) as u64;
+        let detail = format!("{pages} pages ({wal_bytes} bytes)");
+        Ok(if pages > WAL_WARN_PAGES {
+            DoctorCheck::warn("wal", detail, "run `infinity checkpoint <db>`")
+        } else {
+            DoctorCheck::ok("wal", detail)
+        })
+    })
+}
+
+/// Whether another connection currently holds the database's write lock.
+///
+/// Probes with `BEGIN IMMEDIATE` and no busy timeout, so it never waits.
+pub fn write_lock_held(db_path: &Path) -> Result<bool> {
+    let conn = Connection::open(db_path)?;
+    conn.busy_timeout(std::time::Duration::ZERO)?;
+    match conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK;") {
+        Ok(()) => Ok(false),
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::DatabaseBusy
+                || e.code == rusqlite::ErrorCode::DatabaseLocked =>
+        {
+            Ok(true)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn check_lock(db_path: &Path) -> DoctorCheck {
+    checked("lock", || {
+        Ok(if write_lock_held(db_path)? {
+            DoctorCheck::warn(
+                "lock",
+                "write lock is held by another connection",
+                "wait for the running agent to finish, or stop it before maintenance",
+            )
+        } else {
+            DoctorCheck::ok("lock", "no writer holds the database")
+        })
+    })
+}
+
+fn check_orphans(conn: &Connection) -> DoctorCheck {
+    checked("orphans", || {
+        let inodes = count(
+            conn,
+            "SELECT COUNT(*) FROM fs_inode WHERE ino != 1 AND nlink <= 0 \
+             AND ino NOT IN (SELECT DISTINCT ino FROM fs_dentry)",
+        )?;
+        let chunks = count(
+            conn,
+            "SELECT COUNT(*) FROM fs_data WHERE ino NOT IN (SELECT ino FROM fs_inode)",
+        )?;
+        let symlinks = count(
+            conn,
+            "SELECT COUNT(*) FROM fs_symlink WHERE ino NOT IN (SELECT ino FROM fs_inode)",
+        )?;
+        let detail = format!("{inodes} inodes, {chunks} chunks, {symlinks} symlinks");
+        Ok(if inodes + chunks + symlinks > 0 {
+            DoctorCheck::warn("orphans", detail, "run `infinity gc <db>`")
+        } else {
+            DoctorCheck::ok("orphans", detail)
+        })
+    })
+}
+
+fn check_fts(conn: &Connection) -> DoctorCheck {
+    checked("fts", || {
+        let drift = crate::memory_index::check(conn)?;
+        Ok(if drift.total() > 0 {
+            DoctorCheck::warn(
+                "fts",
+                drift.to_string(),
+                "run `infinity doctor --db <db> --fix` to repair the memory index",
+            )
+        } else {
+            DoctorCheck::ok("fts", "memory entries, metadata and search index agree")
+        })
+    })
+}
+
+fn check_stale_sessions(conn: &Connection) -> DoctorCheck {
+    checked("sessions", || {
+        let stale = count(
+            conn,
+            &format!("SELECT COUNT(*) FROM sessions WHERE {}", stale_session_filter()),
+        )?;
+        Ok(if stale > 0 {
+            DoctorCheck::warn(
+                "sessions",
+                format!("{stale} sessions active with no activity for {STALE_SESSION_HOURS}h"),
+                "run `infinity doctor --db <db> --fix` to mark them abandoned",
+            )
+        } else {
+            DoctorCheck::ok("sessions", "no stale active sessions")
+        })
+    })
+}
+
+fn check_dangling_tool_calls(conn: &Connection) -> DoctorCheck {
+    checked("tool_calls", || {
+        let dangling = count(
+            conn,
+            &format!("SELECT COUNT(*) FROM tool_calls WHERE {}", dangling_tool_call_filter()),
+        )?;
+        Ok(if dangling > 0 {
+            DoctorCheck::warn(
+                "tool_calls",
+                format!(
+                    "{dangling} calls started over {DANGLING_TOOL_CALL_MINUTES}m ago never finished"
+                ),
+                "run `infinity doctor --db <db> --fix` to close them as errors",
+            )
+        } else {
+            DoctorCheck::ok("tool_calls", "no dangling calls")
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::init_schema;
+    use tempfile::TempDir;
+
+    fn setup(dir: &TempDir) -> (PathBuf, Connection) {
+        let path = dir.path().join("doctor.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        init_schema(&conn, 65536).unwrap();
+        (path, conn)
+    }
+
+    fn find<'a>(report: &'a DoctorReport, name: &str) -> &'a DoctorCheck {
+        report.checks.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn healthy_database() {
+        let dir = TempDir::new().unwrap();
+        let (path, _conn) = setup(&dir);
+        let report = diagnose(&path).unwrap();
+        assert_eq!(report.status(), CheckStatus::Ok, "{report:?}");
+    }
+
+    #[test]
+    fn detects_and_fixes_stale_rows() {
+        let dir = TempDir::new().unwrap();
+        let (path, conn) = setup(&dir);
+        conn.execute(
+            "INSERT INTO sessions (session_id, started_at) VALUES ('old', '2000-01-01T00:00:00')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO tool_calls (tool_name, started_at) VALUES ('bash', '2000-01-01T00:00:00')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO memory_fts (key, provider, content) VALUES ('memory:gone', 'p', 'x')",
+            [],
+        )
+        .unwrap();
+
+        let report = diagnose(&path).unwrap();
+        assert_eq!(find(&report, "sessions").status, CheckStatus::Warn);
+        assert_eq!(find(&report, "tool_calls").status, CheckStatus::Warn);
+        assert_eq!(find(&report, "fts").status, CheckStatus::Warn);
+
+        let fixes = apply_fixes(&conn).unwrap();
+        assert_eq!(fixes.sessions_closed, 1);
+        assert_eq!(fixes.tool_calls_closed, 1);
+        assert_eq!(fixes.memory_index.stale_fts, 1);
+
+        let report = diagnose(&path).unwrap();
+        assert_eq!(report.status(), CheckStatus::Ok, "{report:?}");
+    }
+
+    #[test]
+    fn outdated_schema_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let (path, conn) = setup(&dir);
+        conn.execute(
+            "UPDATE agentfs_meta SET value = '3' WHERE key = 'schema_version'",
+            [],
+        )
+        .unwrap();
+        let report = diagnose(&path).unwrap();
+        let schema = find(&report, "schema");
+        assert_eq!(schema.status, CheckStatus::Warn);
+        assert!(schema.fix.as_deref().unwrap().contains("migrate"));
+    }
+}
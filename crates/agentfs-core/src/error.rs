@@ -39,6 +39,12 @@ pub enum AgentFSError {
     #[error("invalid path: {path}")]
     InvalidPath { path: String },
 
+    #[error("edit failed for {path}: {reason}")]
+    EditFailed { path: String, reason: String },
+
+    #[error("invalid pattern {pattern:?}: {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+
     #[error("checksum mismatch at ino={ino} chunk={chunk_index}: expected {expected:#018x}, got {actual:#018x}")]
     ChecksumMismatch {
         ino: i64,
@@ -53,6 +59,9 @@ pub enum AgentFSError {
     #[error("key not found: {key}")]
     KeyNotFound { key: String },
 
+    #[error("backup not found: {id}")]
+    BackupNotFound { id: String },
+
     #[error("{0}")]
     Other(String),
 }
@@ -1,5 +1,9 @@
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
 
+use rusqlite::types::Value;
+
 use crate::connection::pool::{ReaderPool, WriterHandle};
 use crate::error::Result;
 
@@ -14,6 +18,161 @@ pub struct Event {
     pub recorded_at: String,
 }
 
+impl Event {
+    pub fn severity(&self) -> Severity {
+        Severity::of(&self.event_type)
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            event_type: row.get(2)?,
+            path: row.get(3)?,
+            detail: row.get(4)?,
+            recorded_at: row.get(5)?,
+        })
+    }
+}
+
+/// Event severity, derived from the event type (events carry no level of
+/// their own): types mentioning "error" or "fail" are errors, "warn" or
+/// "abandon" are warnings, everything else is info.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+/// SQL equivalent of [`Severity::of`] as a rank (0 = info, 1 = warn, 2 = error).
+const SEVERITY_RANK_SQL: &str = "CASE \
+     WHEN event_type LIKE '%error%' OR event_type LIKE '%fail%' THEN 2 \
+     WHEN event_type LIKE '%warn%' OR event_type LIKE '%abandon%' THEN 1 \
+     ELSE 0 END";
+
+impl Severity {
+    pub fn of(event_type: &str) -> Self {
+        let t = event_type.to_ascii_lowercase();
+        if t.contains("error") || t.contains("fail") {
+            Self::Error
+        } else if t.contains("warn") || t.contains("abandon") {
+            Self::Warn
+        } else {
+            Self::Info
+        }
+    }
+
+    fn rank(self) -> i64 {
+        self as i64
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        })
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(Self::Info),
+            "warn" | "warning" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            other => Err(format!("unknown severity: {other} (expected info, warn, error)")),
+        }
+    }
+}
+
+/// A point in time for filtering events: an absolute timestamp
+/// (`2026-01-31`, `2026-01-31T12:00`) or a duration ago (`30s`, `15m`, `2h`,
+/// `7d`, `1w`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeBound {
+    /// A timestamp in the events table's format (UTC, `T` separator).
+    At(String),
+    /// A SQLite date modifier relative to now, e.g. `-2 hours`.
+    Ago(String),
+}
+
+impl TimeBound {
+    /// SQL expression for this bound, with its parameter.
+    pub(crate) fn sql(&self) -> (&'static str, Value) {
+        match self {
+            Self::At(ts) => ("?", Value::Text(ts.clone())),
+            Self::Ago(modifier) => (
+                "strftime('%Y-%m-%dT%H:%M:%f', 'now', ?)",
+                Value::Text(modifier.clone()),
+            ),
+        }
+    }
+}
+
+impl FromStr for TimeBound {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            format!("invalid time: {s} (expected e.g. 2026-01-31, 2026-01-31T12:00, 15m, 2h, 7d)")
+        };
+
+        if let Some(unit) = s.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+            let n: u64 = s[..s.len() - 1].parse().map_err(|_| invalid())?;
+            let modifier = match unit {
+                's' => format!("-{n} seconds"),
+                'm' => format!("-{n} minutes"),
+                'h' => format!("-{n} hours"),
+                'd' => format!("-{n} days"),
+                'w' => format!("-{} days", n.checked_mul(7).ok_or_else(invalid)?),
+                _ => return Err(invalid()),
+            };
+            return Ok(Self::Ago(modifier));
+        }
+
+        let ts = s.replacen(' ', "T", 1);
+        let b = ts.as_bytes();
+        let date_ok = b.len() >= 10
+            && b[..10]
+                .iter()
+                .enumerate()
+                .all(|(i, c)| if i == 4 || i == 7 { *c == b'-' } else { c.is_ascii_digit() });
+        let time_ok = b.len() == 10
+            || (b.len() > 10
+                && b[10] == b'T'
+                && b[11..].iter().all(|c| c.is_ascii_digit() || *c == b':' || *c == b'.'));
+        if date_ok && time_ok {
+            Ok(Self::At(ts))
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+/// Filters for [`Events::query`]. All fields are optional and combined with AND.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Exact event type, or a prefix when it ends with `*` (e.g. `tool:*`).
+    pub event_type: Option<String>,
+    pub session_id: Option<String>,
+    /// Minimum severity.
+    pub min_severity: Option<Severity>,
+    pub since: Option<TimeBound>,
+    pub until: Option<TimeBound>,
+    /// Only events with a larger ID — used to follow the log.
+    pub after_id: Option<i64>,
+    /// Only events with a smaller ID — used to page back through the log.
+    pub before_id: Option<i64>,
+}
+
 /// Unified event logging.
 pub struct Events {
     writer: Arc<WriterHandle>,
@@ -58,16 +217,7 @@ impl Events {
              FROM events ORDER BY id DESC LIMIT ?1",
         )?;
         let events = stmt
-            .query_map([limit], |row| {
-                Ok(Event {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    event_type: row.get(2)?,
-                    path: row.get(3)?,
-                    detail: row.get(4)?,
-                    recorded_at: row.get(5)?,
-                })
-            })?
+            .query_map([limit], Event::from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(events)
     }
@@ -81,16 +231,7 @@ impl Events {
              FROM events WHERE event_type = ?1 ORDER BY id DESC LIMIT ?2",
         )?;
         let events = stmt
-            .query_map(rusqlite::params![event_type, limit], |row| {
-                Ok(Event {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    event_type: row.get(2)?,
-                    path: row.get(3)?,
-                    detail: row.get(4)?,
-                    recorded_at: row.get(5)?,
-                })
-            })?
+            .query_map(rusqlite::params![event_type, limit], Event::from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(events)
     }
@@ -104,20 +245,99 @@ impl Events {
              FROM events WHERE session_id = ?1 ORDER BY id DESC LIMIT ?2",
         )?;
         let events = stmt
-            .query_map(rusqlite::params![session_id, limit], |row| {
-                Ok(Event {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    event_type: row.get(2)?,
-                    path: row.get(3)?,
-                    detail: row.get(4)?,
-                    recorded_at: row.get(5)?,
-                })
-            })?
+            .query_map(rusqlite::params![session_id, limit], Event::from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(events)
+    }
+
+    /// Query events matching `filter`, oldest first.
+    ///
+    /// With a `limit`, returns the *latest* `limit` matches (still oldest
+    /// first). Poll with `after_id` set to the last seen ID to follow new
+    /// events as they are logged.
+    pub async fn query(&self, filter: &EventFilter, limit: Option<i64>) -> Result<Vec<Event>> {
+        let mut sql = String::from(
+            "SELECT id, session_id, event_type, path, detail, recorded_at FROM events WHERE 1 = 1",
+        );
+        let mut params: Vec<Value> = Vec::new();
+
+        if let Some(t) = &filter.event_type {
+            match t.strip_suffix('*') {
+                Some(prefix) => {
+                    sql.push_str(" AND substr(event_type, 1, length(?)) = ?");
+                    params.push(Value::Text(prefix.to_string()));
+                    params.push(Value::Text(prefix.to_string()));
+                }
+                None => {
+                    sql.push_str(" AND event_type = ?");
+                    params.push(Value::Text(t.clone()));
+                }
+            }
+        }
+        if let Some(session_id) = &filter.session_id {
+            sql.push_str(" AND session_id = ?");
+            params.push(Value::Text(session_id.clone()));
+        }
+        if let Some(severity) = filter.min_severity {
+            sql.push_str(&format!(" AND ({SEVERITY_RANK_SQL}) >= ?"));
+            params.push(Value::Integer(severity.rank()));
+        }
+        if let Some(since) = &filter.since {
+            let (expr, value) = since.sql();
+            sql.push_str(&format!(" AND recorded_at >= {expr}"));
+            params.push(value);
+        }
+        if let Some(until) = &filter.until {
+            let (expr, value) = until.sql();
+            sql.push_str(&format!(" AND recorded_at < {expr}"));
+            params.push(value);
+        }
+        if let Some(after_id) = filter.after_id {
+            sql.push_str(" AND id > ?");
+            params.push(Value::Integer(after_id));
+        }
+        if let Some(before_id) = filter.before_id {
+            sql.push_str(" AND id < ?");
+            params.push(Value::Integer(before_id));
+        }
+        match limit {
+            Some(limit) => {
+                sql = format!("SELECT * FROM ({sql} ORDER BY id DESC LIMIT ?) ORDER BY id");
+                params.push(Value::Integer(limit));
+            }
+            None => sql.push_str(" ORDER BY id"),
+        }
+
+        let reader = self.readers.acquire().await?;
+        let mut stmt = reader.conn().prepare(&sql)?;
+        let events = stmt
+            .query_map(rusqlite::params_from_iter(params), Event::from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(events)
     }
 
+    /// Subscribe to events matching `filter`.
+    ///
+    /// The first [`EventSubscription::poll`] returns up to `backlog` of the
+    /// latest existing matches; each later poll returns only matches logged
+    /// since the previous one.
+    pub fn subscribe(&self, filter: EventFilter, backlog: i64) -> EventSubscription<'_> {
+        EventSubscription {
+            events: self,
+            filter,
+            backlog: Some(backlog),
+        }
+    }
+
+    /// Highest event ID logged so far (0 when the log is empty).
+    async fn max_id(&self) -> Result<i64> {
+        let reader = self.readers.acquire().await?;
+        let id = reader
+            .conn()
+            .query_row("SELECT COALESCE(MAX(id), 0) FROM events", [], |r| r.get(0))?;
+        Ok(id)
+    }
+
     /// Get event counts grouped by type.
     pub async fn count_by_type(&self) -> Result<Vec<(String, i64)>> {
         let reader = self.readers.acquire().await?;
@@ -131,6 +351,34 @@ impl Events {
     }
 }
 
+/// A cursor over new events matching a filter. See [`Events::subscribe`].
+pub struct EventSubscription<'a> {
+    events: &'a Events,
+    filter: EventFilter,
+    backlog: Option<i64>,
+}
+
+impl EventSubscription<'_> {
+    /// Fetch matching events logged since the last poll, oldest first.
+    pub async fn poll(&mut self) -> Result<Vec<Event>> {
+        let batch = match self.backlog.take() {
+            Some(backlog) => {
+                // Read the high-water mark first so nothing logged during the
+                // backlog query is skipped when the backlog comes back empty.
+                let start = self.events.max_id().await?;
+                let batch = self.events.query(&self.filter, Some(backlog)).await?;
+                self.filter.after_id = Some(self.filter.after_id.unwrap_or(0).max(start));
+                batch
+            }
+            None => self.events.query(&self.filter, None).await?,
+        };
+        if let Some(last) = batch.last() {
+            self.filter.after_id = Some(self.filter.after_id.unwrap_or(0).max(last.id));
+        }
+        Ok(batch)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,4 +453,101 @@ mod tests {
         assert_eq!(counts[0], ("fs_write".to_string(), 2));
         assert_eq!(counts[1], ("fs_read".to_string(), 1));
     }
+
+    #[tokio::test]
+    async fn query_with_filters() {
+        let (events, _tmp) = setup().await;
+
+        events.log(Some("s1"), "tool:read_file", Some("/a"), None).await.unwrap();
+        events.log(Some("s1"), "tool_error:bash", None, Some("exit 1")).await.unwrap();
+        events.log(Some("s2"), "tool:bash", None, None).await.unwrap();
+        events.log(Some("s2"), "session_end", None, None).await.unwrap();
+
+        let all = events.query(&EventFilter::default(), None).await.unwrap();
+        assert_eq!(all.len(), 4);
+        assert!(all[0].id < all[3].id); // oldest first
+
+        let tools = EventFilter {
+            event_type: Some("tool:*".into()),
+            ..Default::default()
+        };
+        assert_eq!(events.query(&tools, None).await.unwrap().len(), 2);
+
+        let errors = EventFilter {
+            min_severity: Some(Severity::Error),
+            ..Default::default()
+        };
+        let errors = events.query(&errors, None).await.unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].severity(), Severity::Error);
+
+        let s2_latest = EventFilter {
+            session_id: Some("s2".into()),
+            ..Default::default()
+        };
+        let latest = events.query(&s2_latest, Some(1)).await.unwrap();
+        assert_eq!(latest[0].event_type, "session_end");
+
+        let after = EventFilter {
+            after_id: Some(all[2].id),
+            since: Some("1h".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(events.query(&after, None).await.unwrap().len(), 1);
+
+        let page = EventFilter {
+            before_id: Some(all[3].id),
+            ..Default::default()
+        };
+        let page = events.query(&page, Some(2)).await.unwrap();
+        assert_eq!(page.iter().map(|e| e.id).collect::<Vec<_>>(), vec![all[1].id, all[2].id]);
+
+        let future = EventFilter {
+            since: Some("2999-01-01".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(events.query(&future, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn subscription_returns_backlog_then_new_events() {
+        let (events, _tmp) = setup().await;
+
+        events.log(Some("s1"), "fs_write", None, None).await.unwrap();
+        events.log(Some("s2"), "fs_write", None, None).await.unwrap();
+        events.log(Some("s1"), "fs_read", None, None).await.unwrap();
+
+        let filter = EventFilter {
+            session_id: Some("s1".into()),
+            ..Default::default()
+        };
+        let mut sub = events.subscribe(filter, 1);
+        let backlog = sub.poll().await.unwrap();
+        assert_eq!(backlog.len(), 1);
+        assert_eq!(backlog[0].event_type, "fs_read");
+        assert!(sub.poll().await.unwrap().is_empty());
+
+        events.log(Some("s2"), "fs_read", None, None).await.unwrap();
+        events.log(Some("s1"), "session_end", None, None).await.unwrap();
+        let new = sub.poll().await.unwrap();
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].event_type, "session_end");
+
+        let mut empty = events.subscribe(EventFilter::default(), 0);
+        assert!(empty.poll().await.unwrap().is_empty());
+        assert!(empty.poll().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_time_bounds() {
+        assert_eq!("15m".parse(), Ok(TimeBound::Ago("-15 minutes".into())));
+        assert_eq!("1w".parse(), Ok(TimeBound::Ago("-7 days".into())));
+        assert_eq!(
+            "2026-01-31 12:00".parse(),
+            Ok(TimeBound::At("2026-01-31T12:00".into()))
+        );
+        assert!("yesterday".parse::<TimeBound>().is_err());
+        assert!("5y".parse::<TimeBound>().is_err());
+        assert!(format!("{}w", u64::MAX).parse::<TimeBound>().is_err());
+    }
 }
@@ -6,8 +6,11 @@ use crate::config::AgentFSConfig;
 use crate::connection::pool::{ReaderPool, WriterHandle};
 use crate::error::{AgentFSError, Result};
 use crate::filesystem::cache::DentryCache;
-use crate::filesystem::file_handle::{read_file_data, write_file_data};
-use crate::filesystem::{DirEntry, SearchResult, Stat, TreeNode};
+use crate::filesystem::file_handle::{read_file_data, read_file_range, write_file_data};
+use crate::filesystem::{
+    DirEntry, EditSummary, EntryKind, FileEdit, GrepMatch, GrepOptions, GrepResult, SearchFilter,
+    SearchResult, Stat, TreeNode,
+};
 use crate::schema::get_chunk_size;
 
 /// Root inode number.
@@ -16,6 +19,13 @@ const ROOT_INO: i64 = 1;
 /// POSIX mode bits.
 const S_IFDIR: i64 = 0o040000;
 const S_IFREG: i64 = 0o100000;
+const S_IFMT: i64 = 0o170000;
+
+/// Files with a NUL byte in their first this-many bytes are treated as binary.
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Longer matching lines are cut to this many characters in grep results.
+const MAX_GREP_LINE: usize = 500;
 
 /// SQLite-backed filesystem implementation.
 pub struct AgentFSFileSystem {
@@ -158,6 +168,43 @@ impl AgentFSFileSystem {
         Ok(entries)
     }
 
+    /// List up to `limit` directory entries in name order, starting after
+    /// the entry named `after`. Pass the last name of one page to get the next.
+    pub async fn readdir_page(
+        &self,
+        path: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<DirEntry>> {
+        let cache = self.cache.clone();
+        let reader = self.readers.acquire().await?;
+        let ino = Self::resolve_path(reader.conn(), path, &cache)?;
+
+        let st = Self::stat_ino(reader.conn(), ino)?;
+        if !st.is_dir() {
+            return Err(AgentFSError::NotADirectory {
+                path: path.to_string(),
+            });
+        }
+
+        let mut stmt = reader.conn().prepare_cached(
+            "SELECT d.name, d.ino, i.mode FROM fs_dentry d JOIN fs_inode i ON d.ino = i.ino \
+             WHERE d.parent_ino = ?1 AND (?2 IS NULL OR d.name > ?2) ORDER BY d.name LIMIT ?3",
+        )?;
+
+        let entries = stmt
+            .query_map(rusqlite::params![ino, after, limit as i64], |row| {
+                Ok(DirEntry {
+                    name: row.get(0)?,
+                    ino: row.get(1)?,
+                    mode: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
     /// Read file contents.
     pub async fn read_file(&self, path: &str) -> Result<Vec<u8>> {
         let cache = self.cache.clone();
@@ -175,6 +222,36 @@ impl AgentFSFileSystem {
         read_file_data(reader.conn(), ino, verify)
     }
 
+    /// Read up to `len` bytes starting at `offset`, without loading the rest
+    /// of the file. Returns the bytes and the file's total size.
+    pub async fn read_range(&self, path: &str, offset: u64, len: u64) -> Result<(Vec<u8>, u64)> {
+        let cache = self.cache.clone();
+        let reader = self.readers.acquire().await?;
+        let ino = Self::resolve_path(reader.conn(), path, &cache)?;
+
+        let st = Self::stat_ino(reader.conn(), ino)?;
+        if !st.is_file() {
+            return Err(AgentFSError::NotAFile {
+                path: path.to_string(),
+            });
+        }
+
+        let size = st.size as u64;
+        if offset >= size {
+            return Ok((Vec::new(), size));
+        }
+        let len = len.min(size - offset);
+        let data = read_file_range(
+            reader.conn(),
+            ino,
+            offset,
+            len,
+            self.chunk_size,
+            self.verify_checksums,
+        )?;
+        Ok((data, size))
+    }
+
     /// Write file contents. Creates parent directories and file if needed.
     pub async fn write_file(&self, path: &str, data: &[u8]) -> Result<()> {
         let cache = self.cache.clone();
@@ -223,6 +300,88 @@ impl AgentFSFileSystem {
             .await
     }
 
+    /// Apply search-and-replace edits, in order, to one or more files in a
+    /// single transaction.
+    ///
+    /// Each edit sees the result of the ones before it. If any edit fails to
+    /// match (or matches more than once without `replace_all`), nothing is
+    /// written. Returns one summary per file, in order of first edit.
+    pub async fn edit_files(&self, edits: Vec<FileEdit>) -> Result<Vec<EditSummary>> {
+        let cache = self.cache.clone();
+        let chunk_size = self.chunk_size;
+        let verify = self.verify_checksums;
+
+        self.writer
+            .with_conn(move |conn| {
+                let tx = conn.unchecked_transaction()?;
+
+                // (ino, contents, summary) per file, in first-edit order.
+                let mut files: Vec<(i64, String, EditSummary)> = Vec::new();
+                for (i, edit) in edits.iter().enumerate() {
+                    let fail = |reason: String| AgentFSError::EditFailed {
+                        path: edit.path.clone(),
+                        reason: format!("edit {}: {reason}", i + 1),
+                    };
+                    if edit.old_string.is_empty() {
+                        return Err(fail("old_string is empty".to_string()));
+                    }
+                    if edit.old_string == edit.new_string {
+                        return Err(fail("old_string and new_string are the same".to_string()));
+                    }
+
+                    let idx = match files.iter().position(|f| f.2.path == edit.path) {
+                        Some(idx) => idx,
+                        None => {
+                            let ino = Self::resolve_path(&tx, &edit.path, &cache)?;
+                            if !Self::stat_ino(&tx, ino)?.is_file() {
+                                return Err(AgentFSError::NotAFile {
+                                    path: edit.path.clone(),
+                                });
+                            }
+                            let data = read_file_data(&tx, ino, verify)?;
+                            let text = String::from_utf8(data)
+                                .map_err(|_| fail("file is not valid UTF-8".to_string()))?;
+                            let summary = EditSummary {
+                                path: edit.path.clone(),
+                                edits: 0,
+                                replacements: 0,
+                                size: 0,
+                            };
+                            files.push((ino, text, summary));
+                            files.len() - 1
+                        }
+                    };
+
+                    let (_, text, summary) = &mut files[idx];
+                    let count = text.matches(edit.old_string.as_str()).count();
+                    match count {
+                        0 => return Err(fail("old_string not found".to_string())),
+                        1 => *text = text.replacen(&edit.old_string, &edit.new_string, 1),
+                        _ if edit.replace_all => {
+                            *text = text.replace(&edit.old_string, &edit.new_string)
+                        }
+                        n => {
+                            return Err(fail(format!(
+                                "old_string matches {n} times; add context or set replace_all"
+                            )))
+                        }
+                    }
+                    summary.edits += 1;
+                    summary.replacements += count;
+                }
+
+                let mut summaries = Vec::with_capacity(files.len());
+                for (ino, text, mut summary) in files {
+                    write_file_data(&tx, ino, text.as_bytes(), chunk_size)?;
+                    summary.size = text.len();
+                    summaries.push(summary);
+                }
+                tx.commit()?;
+                Ok(summaries)
+            })
+            .await
+    }
+
     /// Create a directory (and intermediate parents).
     pub async fn mkdir(&self, path: &str) -> Result<()> {
         let cache = self.cache.clone();
@@ -588,12 +747,12 @@ impl AgentFSFileSystem {
         let sql_pattern = glob_to_sql(pattern);
 
         let mut stmt = reader.conn().prepare(
-            "SELECT d.ino, d.name, d.parent_ino, i.mode, i.size \
+            "SELECT d.ino, d.name, d.parent_ino, i.mode, i.size, i.mtime \
              FROM fs_dentry d JOIN fs_inode i ON d.ino = i.ino \
-             WHERE d.name LIKE ?1",
+             WHERE d.name LIKE ?1 ESCAPE '\\'",
         )?;
 
-        let rows: Vec<(i64, String, i64, i64, i64)> = stmt
+        let rows: Vec<(i64, String, i64, i64, i64, String)> = stmt
             .query_map([&sql_pattern], |row| {
                 Ok((
                     row.get(0)?,
@@ -601,12 +760,13 @@ impl AgentFSFileSystem {
                     row.get(2)?,
                     row.get(3)?,
                     row.get(4)?,
+                    row.get(5)?,
                 ))
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         let mut results = Vec::new();
-        for (ino, _name, parent_ino, mode, size) in rows {
+        for (ino, _name, parent_ino, mode, size, mtime) in rows {
             let path = reconstruct_path(reader.conn(), ino, parent_ino)?;
             let is_dir = (mode & 0o170000) == 0o040000;
             results.push(SearchResult {
@@ -614,11 +774,169 @@ impl AgentFSFileSystem {
                 ino,
                 is_dir,
                 size,
+                mtime,
             });
         }
 
         Ok(results)
     }
+
+    /// Find entries matching every criterion in `filter`, sorted by path.
+    pub async fn find(&self, filter: &SearchFilter) -> Result<Vec<SearchResult>> {
+        let path_re = filter.path.as_deref().map(path_glob_regex).transpose()?;
+        let reader = self.readers.acquire().await?;
+        find_entries(reader.conn(), filter, path_re.as_ref())
+    }
+
+    /// Search file contents line by line, like `grep -rn`.
+    ///
+    /// Binary files (a NUL byte near the start) are skipped and invalid
+    /// UTF-8 is replaced, so results are always text.
+    pub async fn grep(&self, opts: &GrepOptions) -> Result<GrepResult> {
+        let source = if opts.fixed_strings {
+            regex::escape(&opts.pattern)
+        } else {
+            opts.pattern.clone()
+        };
+        let re = regex::RegexBuilder::new(&source)
+            .case_insensitive(opts.ignore_case)
+            .build()
+            .map_err(|e| AgentFSError::InvalidPattern {
+                pattern: opts.pattern.clone(),
+                reason: e.to_string(),
+            })?;
+        let filter = SearchFilter {
+            path: opts.path.clone(),
+            kind: Some(EntryKind::File),
+            ..Default::default()
+        };
+        let path_re = filter.path.as_deref().map(path_glob_regex).transpose()?;
+
+        let reader = self.readers.acquire().await?;
+        let files = find_entries(reader.conn(), &filter, path_re.as_ref())?;
+
+        let mut result = GrepResult {
+            matches: Vec::new(),
+            files_searched: 0,
+            truncated: false,
+        };
+        'files: for file in files {
+            let data = read_file_data(reader.conn(), file.ino, self.verify_checksums)?;
+            if data[..data.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+                continue;
+            }
+            result.files_searched += 1;
+
+            let text = String::from_utf8_lossy(&data);
+            let lines: Vec<&str> = text.lines().collect();
+            for (i, line) in lines.iter().enumerate() {
+                if !re.is_match(line) {
+                    continue;
+                }
+                if opts.max_matches > 0 && result.matches.len() == opts.max_matches {
+                    result.truncated = true;
+                    break 'files;
+                }
+                let before = &lines[i.saturating_sub(opts.context)..i];
+                let after = &lines[i + 1..(i + 1 + opts.context).min(lines.len())];
+                result.matches.push(GrepMatch {
+                    path: file.path.clone(),
+                    line_number: i + 1,
+                    line: clip_line(line),
+                    before: before.iter().map(|l| clip_line(l)).collect(),
+                    after: after.iter().map(|l| clip_line(l)).collect(),
+                });
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// Cut a grep output line to `MAX_GREP_LINE` characters.
+fn clip_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_GREP_LINE) {
+        Some((cut, _)) => format!("{}...", &line[..cut]),
+        None => line.to_string(),
+    }
+}
+
+/// Query entries by name glob, kind and size, then filter by path regex.
+fn find_entries(
+    conn: &Connection,
+    filter: &SearchFilter,
+    path_re: Option<&regex::Regex>,
+) -> Result<Vec<SearchResult>> {
+    let name = filter.name.as_deref().map(glob_to_sql);
+    let kind = filter.kind.map(|k| match k {
+        EntryKind::File => S_IFREG,
+        EntryKind::Dir => S_IFDIR,
+    });
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT d.ino, d.parent_ino, i.mode, i.size, i.mtime \
+         FROM fs_dentry d JOIN fs_inode i ON d.ino = i.ino \
+         WHERE (?1 IS NULL OR d.name LIKE ?1 ESCAPE '\\') \
+           AND (?2 IS NULL OR (i.mode & ?5) = ?2) \
+           AND (?3 IS NULL OR i.size >= ?3) \
+           AND (?4 IS NULL OR i.size <= ?4)",
+    )?;
+    let rows: Vec<(i64, i64, i64, i64, String)> = stmt
+        .query_map(
+            rusqlite::params![name, kind, filter.min_size, filter.max_size, S_IFMT],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut results = Vec::new();
+    for (ino, parent_ino, mode, size, mtime) in rows {
+        let path = reconstruct_path(conn, ino, parent_ino)?;
+        if path_re.is_some_and(|re| !re.is_match(&path)) {
+            continue;
+        }
+        results.push(SearchResult {
+            path,
+            ino,
+            is_dir: (mode & S_IFMT) == S_IFDIR,
+            size,
+            mtime,
+        });
+    }
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(results)
+}
+
+/// Compile a path glob into an anchored regex.
+///
+/// `*` and `?` don't cross `/`, `**` does. Without a leading `/` the
+/// pattern may match at any depth, so `*.rs` finds every `.rs` file.
+fn path_glob_regex(pattern: &str) -> Result<regex::Regex> {
+    let mut re = String::from("^");
+    if !pattern.starts_with('/') {
+        re.push_str("(?:.*/)?");
+    }
+    let mut chars = pattern.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            _ => re.push_str(&regex::escape(ch.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    re.push('$');
+    regex::Regex::new(&re).map_err(|e| AgentFSError::InvalidPattern {
+        pattern: pattern.to_string(),
+        reason: e.to_string(),
+    })
 }
 
 /// Recursively build a tree from a directory inode.
@@ -654,9 +972,22 @@ fn build_tree(conn: &Connection, name: String, ino: i64, st: &Stat) -> Result<Tr
         }
     }
 
+    let total_size = if st.is_dir() {
+        children.iter().map(|c| c.total_size).sum()
+    } else {
+        st.size
+    };
+    let newest_mtime = children
+        .iter()
+        .map(|c| &c.newest_mtime)
+        .fold(&st.mtime, |a, b| a.max(b))
+        .clone();
+
     Ok(TreeNode {
         name,
         stat: st.clone(),
+        total_size,
+        newest_mtime,
         children,
     })
 }
@@ -901,6 +1232,9 @@ mod tests {
         let tree = fs.tree("/").await.unwrap();
         assert_eq!(tree.name, "/");
         assert_eq!(tree.children.len(), 2);
+        assert_eq!(tree.total_size, 3);
+        assert_eq!(tree.children[0].total_size, 2);
+        assert!(tree.newest_mtime >= tree.children[1].stat.mtime);
     }
 
     #[tokio::test]
@@ -976,4 +1310,194 @@ mod tests {
         assert!(paths.contains(&"/readme.md"));
         assert!(paths.contains(&"/docs/guide.md"));
     }
+
+    #[tokio::test]
+    async fn edit_files_is_all_or_nothing() {
+        let (fs, _tmp) = setup().await;
+        fs.write_file("/a.rs", b"fn old() {}\nold();\nold();\n").await.unwrap();
+        fs.write_file("/b.rs", b"use a::old;\n").await.unwrap();
+
+        let edit = |path: &str, old: &str, new: &str, all: bool| FileEdit {
+            path: path.to_string(),
+            old_string: old.to_string(),
+            new_string: new.to_string(),
+            replace_all: all,
+        };
+
+        // Second edit is ambiguous: nothing may be written.
+        let err = fs
+            .edit_files(vec![
+                edit("/b.rs", "old", "new", false),
+                edit("/a.rs", "old();", "new();", false),
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AgentFSError::EditFailed { ref path, .. } if path == "/a.rs"));
+        assert_eq!(fs.read_file("/b.rs").await.unwrap(), b"use a::old;\n");
+
+        let summaries = fs
+            .edit_files(vec![
+                edit("/a.rs", "fn old", "fn new", false),
+                edit("/b.rs", "old", "new", false),
+                edit("/a.rs", "old();", "new();", true),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].path, "/a.rs");
+        assert_eq!(summaries[0].edits, 2);
+        assert_eq!(summaries[0].replacements, 3);
+        assert_eq!(
+            fs.read_file("/a.rs").await.unwrap(),
+            b"fn new() {}\nnew();\nnew();\n"
+        );
+        assert_eq!(fs.read_file("/b.rs").await.unwrap(), b"use a::new;\n");
+
+        assert!(matches!(
+            fs.edit_files(vec![edit("/missing.rs", "x", "y", false)]).await,
+            Err(AgentFSError::FileNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn readdir_pages() {
+        let (fs, _tmp) = setup().await;
+        for name in ["e", "a", "d", "b", "c"] {
+            fs.write_file(&format!("/dir/{name}"), b"x").await.unwrap();
+        }
+
+        let names = |entries: Vec<DirEntry>| entries.into_iter().map(|e| e.name).collect::<Vec<_>>();
+        assert_eq!(names(fs.readdir_page("/dir", None, 2).await.unwrap()), ["a", "b"]);
+        assert_eq!(names(fs.readdir_page("/dir", Some("b"), 2).await.unwrap()), ["c", "d"]);
+        assert_eq!(names(fs.readdir_page("/dir", Some("d"), 2).await.unwrap()), ["e"]);
+        assert!(fs.readdir_page("/dir", Some("e"), 2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_range_across_chunks() {
+        let (fs, _tmp) = setup().await;
+        let data: Vec<u8> = (0..200u8).collect();
+        fs.write_file("/big.bin", &data).await.unwrap();
+
+        // Spans chunks 0..=2 with a chunk size of 64.
+        let (part, size) = fs.read_range("/big.bin", 60, 80).await.unwrap();
+        assert_eq!(size, 200);
+        assert_eq!(part, &data[60..140]);
+
+        let (tail, _) = fs.read_range("/big.bin", 190, 100).await.unwrap();
+        assert_eq!(tail, &data[190..]);
+
+        let (past_end, _) = fs.read_range("/big.bin", 500, 10).await.unwrap();
+        assert!(past_end.is_empty());
+
+        fs.mkdir("/d").await.unwrap();
+        assert!(matches!(
+            fs.read_range("/d", 0, 1).await,
+            Err(AgentFSError::NotAFile { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn find_with_path_kind_and_size() {
+        let (fs, _tmp) = setup().await;
+        fs.write_file("/src/main.rs", b"fn main() {}").await.unwrap();
+        fs.write_file("/src/util/mod.rs", b"").await.unwrap();
+        fs.write_file("/tests/it.rs", &[b'x'; 100]).await.unwrap();
+        fs.write_file("/my_notes.md", b"n").await.unwrap();
+
+        let paths = |r: Vec<SearchResult>| r.into_iter().map(|e| e.path).collect::<Vec<_>>();
+
+        let filter = SearchFilter {
+            path: Some("/src/*.rs".into()),
+            ..Default::default()
+        };
+        assert_eq!(paths(fs.find(&filter).await.unwrap()), ["/src/main.rs"]);
+
+        let filter = SearchFilter {
+            path: Some("/src/**".into()),
+            kind: Some(EntryKind::File),
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(fs.find(&filter).await.unwrap()),
+            ["/src/main.rs", "/src/util/mod.rs"]
+        );
+
+        let filter = SearchFilter {
+            path: Some("*.rs".into()),
+            min_size: Some(1),
+            max_size: Some(50),
+            ..Default::default()
+        };
+        assert_eq!(paths(fs.find(&filter).await.unwrap()), ["/src/main.rs"]);
+
+        let filter = SearchFilter {
+            kind: Some(EntryKind::Dir),
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(fs.find(&filter).await.unwrap()),
+            ["/src", "/src/util", "/tests"]
+        );
+
+        // `_` is literal, not a LIKE wildcard.
+        let filter = SearchFilter {
+            name: Some("my_*".into()),
+            ..Default::default()
+        };
+        assert_eq!(paths(fs.find(&filter).await.unwrap()), ["/my_notes.md"]);
+    }
+
+    #[tokio::test]
+    async fn grep_contents() {
+        let (fs, _tmp) = setup().await;
+        fs.write_file("/a.rs", b"fn main() {\n    todo!()\n}\n").await.unwrap();
+        fs.write_file("/b.md", b"TODO: docs\nnothing here\n").await.unwrap();
+        fs.write_file("/bin.dat", b"todo\0binary").await.unwrap();
+
+        let mut opts = GrepOptions {
+            pattern: "todo".into(),
+            ..Default::default()
+        };
+        let result = fs.grep(&opts).await.unwrap();
+        assert_eq!(result.files_searched, 2);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].path, "/a.rs");
+        assert_eq!(result.matches[0].line_number, 2);
+        assert_eq!(result.matches[0].line, "    todo!()");
+        assert!(result.matches[0].before.is_empty());
+
+        opts.context = 2;
+        let result = fs.grep(&opts).await.unwrap();
+        assert_eq!(result.matches[0].before, vec!["fn main() {"]);
+        assert_eq!(result.matches[0].after, vec!["}"]);
+        opts.context = 0;
+
+        opts.ignore_case = true;
+        assert_eq!(fs.grep(&opts).await.unwrap().matches.len(), 2);
+
+        opts.path = Some("*.md".into());
+        let result = fs.grep(&opts).await.unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!(result.matches[0].path, "/b.md");
+
+        let opts = GrepOptions {
+            pattern: "(".into(),
+            fixed_strings: true,
+            max_matches: 1,
+            ..Default::default()
+        };
+        let result = fs.grep(&opts).await.unwrap();
+        assert_eq!(result.matches.len(), 1);
+        assert!(result.truncated);
+
+        let opts = GrepOptions {
+            pattern: "(".into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            fs.grep(&opts).await,
+            Err(AgentFSError::InvalidPattern { .. })
+        ));
+    }
 }
@@ -73,6 +73,52 @@ pub fn read_file_data(conn: &Connection, ino: i64, verify: bool) -> Result<Vec<u
     Ok(result)
 }
 
+/// Read up to `len` bytes starting at byte `offset`, loading only the chunks
+/// that overlap the range.
+///
+/// Relies on every chunk but the last being exactly `chunk_size` bytes, which
+/// [`write_file_data`] guarantees. Does not touch atime.
+pub fn read_file_range(
+    conn: &Connection,
+    ino: i64,
+    offset: u64,
+    len: u64,
+    chunk_size: usize,
+    verify: bool,
+) -> Result<Vec<u8>> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    let chunk_size = chunk_size as u64;
+    let first = offset / chunk_size;
+    let last = (offset + len - 1) / chunk_size;
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT chunk_index, data, checksum FROM fs_data \
+         WHERE ino = ?1 AND chunk_index BETWEEN ?2 AND ?3 ORDER BY chunk_index",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![ino, first as i64, last as i64], |row| {
+        let chunk_index: i64 = row.get(0)?;
+        let data: Vec<u8> = row.get(1)?;
+        let checksum: i64 = row.get(2)?;
+        Ok((chunk_index, data, checksum as u64))
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        let (chunk_index, data, checksum) = row?;
+        if verify {
+            verify_checksum(&data, checksum, ino, chunk_index)?;
+        }
+        result.extend_from_slice(&data);
+    }
+
+    let skip = ((offset - first * chunk_size) as usize).min(result.len());
+    result.drain(..skip);
+    result.truncate(len as usize);
+    Ok(result)
+}
+
 /// Perform fsync semantics based on durability level.
 ///
 /// - `Full`: every commit already fsyncs; this is a no-op.
@@ -2,7 +2,7 @@ pub mod agentfs_fs;
 pub mod cache;
 pub mod file_handle;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Metadata for an inode.
 #[derive(Debug, Clone, Serialize)]
@@ -65,6 +65,10 @@ pub struct DirEntry {
 pub struct TreeNode {
     pub name: String,
     pub stat: Stat,
+    /// Bytes in this file, or in every file under this directory.
+    pub total_size: i64,
+    /// Latest mtime of this node or anything under it.
+    pub newest_mtime: String,
     pub children: Vec<TreeNode>,
 }
 
@@ -75,6 +79,89 @@ pub struct SearchResult {
     pub ino: i64,
     pub is_dir: bool,
     pub size: i64,
+    pub mtime: String,
+}
+
+/// Kind of entry to match in a [`SearchFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+}
+
+/// Criteria for [`AgentFSFileSystem::find`]. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// Glob on the entry name (`*`, `?`).
+    pub name: Option<String>,
+    /// Glob on the full path. `*` and `?` stay within one component, `**`
+    /// spans directories; a pattern without a leading `/` may match at any
+    /// depth.
+    pub path: Option<String>,
+    pub kind: Option<EntryKind>,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+}
+
+/// Options for [`AgentFSFileSystem::grep`].
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// Regular expression, or a literal string if `fixed_strings` is set.
+    pub pattern: String,
+    /// Only search files whose path matches this glob (see [`SearchFilter::path`]).
+    pub path: Option<String>,
+    pub ignore_case: bool,
+    pub fixed_strings: bool,
+    /// Stop after this many matching lines (0 = unlimited).
+    pub max_matches: usize,
+    /// Lines of context to return before and after each match.
+    pub context: usize,
+}
+
+/// A line matching a grep pattern.
+#[derive(Debug, Clone, Serialize)]
+pub struct GrepMatch {
+    pub path: String,
+    /// 1-based line number.
+    pub line_number: usize,
+    pub line: String,
+    /// Up to `context` lines preceding the match, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    /// Up to `context` lines following the match, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+/// Result of a content search.
+#[derive(Debug, Clone, Serialize)]
+pub struct GrepResult {
+    pub matches: Vec<GrepMatch>,
+    /// Text files scanned; binary files are skipped.
+    pub files_searched: u64,
+    /// True if `max_matches` cut the search short.
+    pub truncated: bool,
+}
+
+/// One search-and-replace for [`AgentFSFileSystem::edit_files`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileEdit {
+    pub path: String,
+    /// Exact text to find; must occur once unless `replace_all` is set.
+    pub old_string: String,
+    pub new_string: String,
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// What [`AgentFSFileSystem::edit_files`] changed in one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct EditSummary {
+    pub path: String,
+    pub edits: usize,
+    pub replacements: usize,
+    /// Size after editing.
+    pub size: usize,
 }
 
 pub use agentfs_fs::AgentFSFileSystem;
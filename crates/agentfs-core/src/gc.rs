@@ -1,9 +1,10 @@
 use rusqlite::Connection;
 
 use crate::error::Result;
+use crate::events::TimeBound;
 
 /// Report from a garbage collection run.
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, Default, serde::Serialize)]
 pub struct GcReport {
     /// Number of orphan inodes deleted (nlink=0, no dentry refs).
     pub orphan_inodes: u64,
@@ -13,6 +14,20 @@ pub struct GcReport {
     pub stale_symlinks: u64,
 }
 
+impl GcReport {
+    /// Total number of rows deleted.
+    pub fn total(&self) -> u64 {
+        self.orphan_inodes + self.stale_chunks + self.stale_symlinks
+    }
+
+    /// Add another report's counts to this one.
+    pub fn merge(&mut self, other: &GcReport) {
+        self.orphan_inodes += other.orphan_inodes;
+        self.stale_chunks += other.stale_chunks;
+        self.stale_symlinks += other.stale_symlinks;
+    }
+}
+
 /// Run garbage collection in a single transaction.
 ///
 /// Cleans up:
@@ -21,26 +36,64 @@ pub struct GcReport {
 /// 3. Stale symlinks: ino references a non-existent inode
 pub fn collect_garbage(conn: &Connection) -> Result<GcReport> {
     let tx = conn.unchecked_transaction()?;
+    let report = delete_garbage(&tx)?;
+    tx.commit()?;
+    Ok(report)
+}
 
+/// The deletes behind [`collect_garbage`], for callers already in a transaction.
+pub(crate) fn delete_garbage(conn: &Connection) -> Result<GcReport> {
     // 1. Find and delete orphan inodes (nlink <= 0 and no dentry refs, excluding root)
-    let orphan_inodes = tx.execute(
+    let orphan_inodes = conn.execute(
         "DELETE FROM fs_inode WHERE ino != 1 AND nlink <= 0 \
          AND ino NOT IN (SELECT DISTINCT ino FROM fs_dentry)",
         [],
     )? as u64;
 
     // 2. Delete data chunks whose inode no longer exists
-    let stale_chunks = tx.execute(
+    let stale_chunks = conn.execute(
         "DELETE FROM fs_data WHERE ino NOT IN (SELECT ino FROM fs_inode)",
         [],
     )? as u64;
 
     // 3. Delete symlinks whose inode no longer exists
-    let stale_symlinks = tx.execute(
+    let stale_symlinks = conn.execute(
         "DELETE FROM fs_symlink WHERE ino NOT IN (SELECT ino FROM fs_inode)",
         [],
     )? as u64;
 
+    Ok(GcReport {
+        orphan_inodes,
+        stale_chunks,
+        stale_symlinks,
+    })
+}
+
+/// Run one bounded step of garbage collection in a single transaction.
+///
+/// Deletes at most `batch` rows of each kind, so the write lock is held only
+/// briefly. Repeat until a step returns an empty report.
+pub fn collect_garbage_step(conn: &Connection, batch: i64) -> Result<GcReport> {
+    let tx = conn.unchecked_transaction()?;
+
+    let orphan_inodes = tx.execute(
+        "DELETE FROM fs_inode WHERE ino IN (SELECT ino FROM fs_inode WHERE ino != 1 AND nlink <= 0 \
+         AND ino NOT IN (SELECT DISTINCT ino FROM fs_dentry) LIMIT ?1)",
+        [batch],
+    )? as u64;
+
+    let stale_chunks = tx.execute(
+        "DELETE FROM fs_data WHERE rowid IN (SELECT rowid FROM fs_data \
+         WHERE ino NOT IN (SELECT ino FROM fs_inode) LIMIT ?1)",
+        [batch],
+    )? as u64;
+
+    let stale_symlinks = tx.execute(
+        "DELETE FROM fs_symlink WHERE ino IN (SELECT ino FROM fs_symlink \
+         WHERE ino NOT IN (SELECT ino FROM fs_inode) LIMIT ?1)",
+        [batch],
+    )? as u64;
+
     tx.commit()?;
 
     Ok(GcReport {
@@ -50,6 +103,52 @@ pub fn collect_garbage(conn: &Connection) -> Result<GcReport> {
     })
 }
 
+/// How long to keep audit history. `None` keeps everything.
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    /// Delete events recorded before this time.
+    pub events_before: Option<TimeBound>,
+    /// Delete finished tool calls started before this time.
+    pub tool_calls_before: Option<TimeBound>,
+}
+
+/// Report from a retention pruning run.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct RetentionReport {
+    pub events: u64,
+    pub tool_calls: u64,
+}
+
+/// Delete audit history older than `policy` allows, in a single transaction.
+///
+/// Tool calls still `started` or referenced by token usage are kept.
+pub fn prune_history(conn: &Connection, policy: &RetentionPolicy) -> Result<RetentionReport> {
+    let tx = conn.unchecked_transaction()?;
+    let mut report = RetentionReport::default();
+
+    if let Some(bound) = &policy.events_before {
+        let (expr, value) = bound.sql();
+        report.events = tx.execute(
+            &format!("DELETE FROM events WHERE recorded_at < {expr}"),
+            [value],
+        )? as u64;
+    }
+
+    if let Some(bound) = &policy.tool_calls_before {
+        let (expr, value) = bound.sql();
+        report.tool_calls = tx.execute(
+            &format!(
+                "DELETE FROM tool_calls WHERE started_at < {expr} AND status != 'started' \
+                 AND id NOT IN (SELECT tool_call_id FROM token_usage WHERE tool_call_id IS NOT NULL)"
+            ),
+            [value],
+        )? as u64;
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +212,59 @@ mod tests {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn gc_step_is_bounded() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn, 65536).unwrap();
+        for _ in 0..3 {
+            conn.execute("INSERT INTO fs_inode (mode, nlink) VALUES (?1, 0)", [0o100644i64])
+                .unwrap();
+        }
+
+        let mut total = GcReport::default();
+        let mut steps = 0;
+        loop {
+            let step = collect_garbage_step(&conn, 2).unwrap();
+            if step.total() == 0 {
+                break;
+            }
+            assert!(step.orphan_inodes <= 2);
+            total.merge(&step);
+            steps += 1;
+        }
+        assert_eq!(steps, 2);
+        assert_eq!(total.orphan_inodes, 3);
+    }
+
+    #[test]
+    fn prune_history_respects_policy() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn, 65536).unwrap();
+        conn.execute_batch(
+            "INSERT INTO events (event_type, recorded_at) VALUES ('old', '2000-01-01T00:00:00.000');
+             INSERT INTO events (event_type) VALUES ('new');
+             INSERT INTO tool_calls (tool_name, status, started_at) VALUES ('a', 'success', '2000-01-01T00:00:00.000');
+             INSERT INTO tool_calls (tool_name, status, started_at) VALUES ('b', 'started', '2000-01-01T00:00:00.000');",
+        )
+        .unwrap();
+
+        let report = prune_history(&conn, &RetentionPolicy::default()).unwrap();
+        assert_eq!((report.events, report.tool_calls), (0, 0));
+
+        let policy = RetentionPolicy {
+            events_before: Some("30d".parse().unwrap()),
+            tool_calls_before: Some("30d".parse().unwrap()),
+        };
+        let report = prune_history(&conn, &policy).unwrap();
+        assert_eq!(report.events, 1);
+        assert_eq!(report.tool_calls, 1);
+
+        let left: String = conn
+            .query_row("SELECT tool_name FROM tool_calls", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(left, "b");
+    }
+
     #[test]
     fn gc_noop_on_clean_db() {
         let conn = Connection::open_in_memory().unwrap();
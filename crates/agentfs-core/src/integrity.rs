@@ -2,6 +2,7 @@ use rusqlite::Connection;
 use xxhash_rust::xxh3::xxh3_64;
 
 use crate::error::{AgentFSError, Result};
+use crate::gc::{self, GcReport};
 
 /// Compute an XXH3_64 checksum of a data chunk.
 pub fn compute_checksum(data: &[u8]) -> u64 {
@@ -89,6 +90,175 @@ pub fn scrub(conn: &Connection) -> Result<IntegrityReport> {
     })
 }
 
+/// Result of verifying a single file's chunks.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileIntegrityReport {
+    pub path: String,
+    pub ino: i64,
+    pub size: i64,
+    pub total_chunks: u64,
+    pub verified_chunks: u64,
+    pub corrupt_chunks: Vec<CorruptChunk>,
+    /// Chunk indices implied by the file size but absent from `fs_data`.
+    pub missing_chunks: Vec<i64>,
+}
+
+impl FileIntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.corrupt_chunks.is_empty() && self.missing_chunks.is_empty()
+    }
+}
+
+/// Verify every chunk of one file inode against its checksum and size.
+pub fn verify_inode(conn: &Connection, path: &str, ino: i64) -> Result<FileIntegrityReport> {
+    let size: i64 = conn.query_row("SELECT size FROM fs_inode WHERE ino = ?1", [ino], |r| r.get(0))?;
+    let chunk_size = crate::schema::get_chunk_size(conn)? as i64;
+    let expected_chunks = (size + chunk_size - 1) / chunk_size;
+
+    let mut stmt = conn.prepare("SELECT chunk_index, data, checksum FROM fs_data WHERE ino = ?1 ORDER BY chunk_index")?;
+    let rows = stmt.query_map([ino], |row| {
+        let chunk_index: i64 = row.get(0)?;
+        let data: Vec<u8> = row.get(1)?;
+        let checksum: i64 = row.get(2)?;
+        Ok((chunk_index, data, checksum as u64))
+    })?;
+
+    let mut total: u64 = 0;
+    let mut verified: u64 = 0;
+    let mut corrupt = Vec::new();
+    let mut present = Vec::new();
+    for row in rows {
+        let (chunk_index, data, expected) = row?;
+        total += 1;
+        present.push(chunk_index);
+        let actual = compute_checksum(&data);
+        if actual == expected {
+            verified += 1;
+        } else {
+            corrupt.push(CorruptChunk {
+                ino,
+                chunk_index,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    let missing_chunks = (0..expected_chunks)
+        .filter(|i| present.binary_search(i).is_err())
+        .collect();
+
+    Ok(FileIntegrityReport {
+        path: path.to_string(),
+        ino,
+        size,
+        total_chunks: total,
+        verified_chunks: verified,
+        corrupt_chunks: corrupt,
+        missing_chunks,
+    })
+}
+
+/// A file cut back to its last intact byte by [`repair`].
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TruncatedFile {
+    pub ino: i64,
+    /// First corrupt chunk; it and every later chunk were dropped.
+    pub first_bad_chunk: i64,
+    pub old_size: i64,
+    pub new_size: i64,
+}
+
+/// Result of a repair run.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RepairReport {
+    pub dry_run: bool,
+    pub truncated_files: Vec<TruncatedFile>,
+    /// Chunks deleted from truncated files (corrupt ones and those after them).
+    pub dropped_chunks: u64,
+    /// Directory entries whose inode or parent no longer exists.
+    pub dangling_dentries: u64,
+    pub gc: GcReport,
+}
+
+impl RepairReport {
+    pub fn total_fixes(&self) -> u64 {
+        self.truncated_files.len() as u64
+            + self.dangling_dentries
+            + self.gc.orphan_inodes
+            + self.gc.stale_chunks
+            + self.gc.stale_symlinks
+    }
+}
+
+/// Repair corruption in a single transaction.
+///
+/// Checksums can detect but not reconstruct damaged data, so repair is lossy:
+/// 1. Files with corrupt chunks are truncated before the first bad chunk
+/// 2. Dangling directory entries are removed
+/// 3. Garbage collection runs on whatever those steps orphaned
+///
+/// With `dry_run`, the same work is done and reported, then rolled back.
+pub fn repair(conn: &Connection, dry_run: bool) -> Result<RepairReport> {
+    let tx = conn.unchecked_transaction()?;
+
+    let report = scrub(&tx)?;
+    let mut first_bad: Vec<(i64, i64)> = Vec::new();
+    for c in &report.corrupt_chunks {
+        match first_bad.last_mut() {
+            Some((ino, idx)) if *ino == c.ino => *idx = (*idx).min(c.chunk_index),
+            _ => first_bad.push((c.ino, c.chunk_index)),
+        }
+    }
+
+    let mut truncated_files = Vec::new();
+    let mut dropped_chunks: u64 = 0;
+    for (ino, first_bad_chunk) in first_bad {
+        let old_size: i64 = tx.query_row("SELECT size FROM fs_inode WHERE ino = ?1", [ino], |r| r.get(0))?;
+        dropped_chunks += tx.execute(
+            "DELETE FROM fs_data WHERE ino = ?1 AND chunk_index >= ?2",
+            rusqlite::params![ino, first_bad_chunk],
+        )? as u64;
+        let new_size: i64 = tx.query_row(
+            "SELECT COALESCE(SUM(LENGTH(data)), 0) FROM fs_data WHERE ino = ?1",
+            [ino],
+            |r| r.get(0),
+        )?;
+        tx.execute(
+            "UPDATE fs_inode SET size = ?1, mtime = strftime('%Y-%m-%dT%H:%M:%f', 'now') WHERE ino = ?2",
+            rusqlite::params![new_size, ino],
+        )?;
+        truncated_files.push(TruncatedFile {
+            ino,
+            first_bad_chunk,
+            old_size,
+            new_size,
+        });
+    }
+
+    let dangling_dentries = tx.execute(
+        "DELETE FROM fs_dentry WHERE ino NOT IN (SELECT ino FROM fs_inode) \
+         OR parent_ino NOT IN (SELECT ino FROM fs_inode)",
+        [],
+    )? as u64;
+
+    let gc = gc::delete_garbage(&tx)?;
+
+    if dry_run {
+        tx.rollback()?;
+    } else {
+        tx.commit()?;
+    }
+
+    Ok(RepairReport {
+        dry_run,
+        truncated_files,
+        dropped_chunks,
+        dangling_dentries,
+        gc,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,4 +277,60 @@ mod tests {
         let err = verify_checksum(data, 0xDEADBEEF, 1, 0).unwrap_err();
         assert!(matches!(err, AgentFSError::ChecksumMismatch { .. }));
     }
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::schema::init_schema(&conn, 4).unwrap();
+        conn.execute("INSERT INTO fs_inode (mode, nlink) VALUES (?1, 1)", [0o100644i64])
+            .unwrap();
+        let ino = conn.last_insert_rowid();
+        conn.execute(
+            "INSERT INTO fs_dentry (parent_ino, name, ino) VALUES (1, 'f.txt', ?1)",
+            [ino],
+        )
+        .unwrap();
+        crate::filesystem::file_handle::write_file_data(&conn, ino, b"aaaabbbbcccc", 4).unwrap();
+        conn
+    }
+
+    fn corrupt(conn: &Connection, chunk_index: i64) {
+        conn.execute(
+            "UPDATE fs_data SET data = X'00' WHERE ino = 2 AND chunk_index = ?1",
+            [chunk_index],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn verify_inode_reports_corrupt_and_missing() {
+        let conn = setup();
+        assert!(verify_inode(&conn, "/f.txt", 2).unwrap().is_clean());
+
+        corrupt(&conn, 0);
+        conn.execute("DELETE FROM fs_data WHERE ino = 2 AND chunk_index = 2", [])
+            .unwrap();
+        let report = verify_inode(&conn, "/f.txt", 2).unwrap();
+        assert_eq!(report.total_chunks, 2);
+        assert_eq!(report.verified_chunks, 1);
+        assert_eq!(report.corrupt_chunks.len(), 1);
+        assert_eq!(report.missing_chunks, vec![2]);
+    }
+
+    #[test]
+    fn repair_truncates_at_first_bad_chunk() {
+        let conn = setup();
+        corrupt(&conn, 1);
+
+        let dry = repair(&conn, true).unwrap();
+        assert!(dry.dry_run);
+        assert_eq!(dry.truncated_files.len(), 1);
+        assert_eq!(dry.dropped_chunks, 2);
+        assert!(!scrub(&conn).unwrap().is_clean());
+
+        let report = repair(&conn, false).unwrap();
+        assert_eq!(report.truncated_files[0].old_size, 12);
+        assert_eq!(report.truncated_files[0].new_size, 4);
+        assert!(scrub(&conn).unwrap().is_clean());
+        assert!(verify_inode(&conn, "/f.txt", 2).unwrap().is_clean());
+    }
 }
@@ -3,6 +3,31 @@ use std::sync::Arc;
 use crate::connection::pool::{ReaderPool, WriterHandle};
 use crate::error::{AgentFSError, Result};
 
+/// Prefix of keys holding the user's tool approvals. Tools a model drives
+/// must not write under it, or it could approve its own calls.
+pub const PERMISSION_PREFIX: &str = "permission:";
+
+/// Prefix of keys holding the agent's configuration: MCP servers it
+/// starts (`config:mcp:`), skills (`config:skill:`) and the like. A tool
+/// that could write one could have its command run at the next launch.
+pub const CONFIG_PREFIX: &str = "config:";
+
+/// Prefixes model-driven tools must not write or delete under.
+const PROTECTED_PREFIXES: [&str; 2] = [PERMISSION_PREFIX, CONFIG_PREFIX];
+
+/// Whether model-driven tools (the agent's `kv_set`, MCP's `agentfs_kv_*`)
+/// must refuse to write or delete `key`.
+pub fn is_protected(key: &str) -> bool {
+    PROTECTED_PREFIXES.iter().any(|p| key.starts_with(p))
+}
+
+/// Whether deleting every key under `prefix` would reach a protected key.
+pub fn reaches_protected(prefix: &str) -> bool {
+    PROTECTED_PREFIXES
+        .iter()
+        .any(|p| p.starts_with(prefix) || prefix.starts_with(p))
+}
+
 /// Key-value entry.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct KvEntry {
@@ -104,6 +129,83 @@ impl KvStore {
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(entries)
     }
+
+    /// List up to `limit` entries whose key starts with `prefix` (matched
+    /// literally), in key order, starting after the key `after`.
+    ///
+    /// Pass the last key of one page as `after` to get the next.
+    pub async fn list_page(
+        &self,
+        prefix: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<KvEntry>> {
+        let reader = self.readers.acquire().await?;
+        let mut stmt = reader.conn().prepare_cached(
+            "SELECT key, value, created, updated FROM kv_store \
+             WHERE substr(key, 1, length(?1)) = ?1 AND (?2 IS NULL OR key > ?2) \
+             ORDER BY key LIMIT ?3",
+        )?;
+        let entries = stmt
+            .query_map(rusqlite::params![prefix, after, limit as i64], |row| {
+                Ok(KvEntry {
+                    key: row.get(0)?,
+                    value: row.get(1)?,
+                    created: row.get(2)?,
+                    updated: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Delete every key starting with `prefix` (matched literally).
+    /// Returns the number of keys deleted.
+    pub async fn delete_prefix(&self, prefix: &str) -> Result<usize> {
+        let prefix = prefix.to_string();
+        self.writer
+            .with_conn(move |conn| {
+                let deleted = conn.execute(
+                    "DELETE FROM kv_store WHERE substr(key, 1, length(?1)) = ?1",
+                    [&prefix],
+                )?;
+                Ok(deleted)
+            })
+            .await
+    }
+
+    /// Import entries in a single transaction, preserving their timestamps.
+    /// Existing keys are left untouched unless `overwrite` is set.
+    /// Returns the number of entries written.
+    pub async fn import(&self, entries: Vec<KvEntry>, overwrite: bool) -> Result<usize> {
+        self.writer
+            .with_conn(move |conn| {
+                let tx = conn.unchecked_transaction()?;
+                let sql = if overwrite {
+                    "INSERT INTO kv_store (key, value, created, updated) VALUES (?1, ?2, ?3, ?4) \
+                     ON CONFLICT(key) DO UPDATE SET value = excluded.value, \
+                     created = excluded.created, updated = excluded.updated"
+                } else {
+                    "INSERT OR IGNORE INTO kv_store (key, value, created, updated) \
+                     VALUES (?1, ?2, ?3, ?4)"
+                };
+                let mut written = 0;
+                {
+                    let mut stmt = tx.prepare(sql)?;
+                    for entry in &entries {
+                        written += stmt.execute(rusqlite::params![
+                            entry.key,
+                            entry.value,
+                            entry.created,
+                            entry.updated
+                        ])?;
+                    }
+                }
+                tx.commit()?;
+                Ok(written)
+            })
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -131,6 +233,17 @@ mod tests {
         (kv, tmp)
     }
 
+    #[test]
+    fn protected_keys_and_prefixes() {
+        assert!(is_protected("permission:always:bash"));
+        assert!(is_protected("config:mcp:git"));
+        assert!(!is_protected("notes:config:x"));
+
+        assert!(reaches_protected("config:skill:"));
+        assert!(reaches_protected("perm"));
+        assert!(!reaches_protected("notes:"));
+    }
+
     #[tokio::test]
     async fn set_get_delete() {
         let (kv, _tmp) = setup().await;
@@ -166,4 +279,51 @@ mod tests {
         let agents = kv.list_prefix("agent:").await.unwrap();
         assert_eq!(agents.len(), 2);
     }
+
+    #[tokio::test]
+    async fn paging_and_delete_prefix() {
+        let (kv, _tmp) = setup().await;
+        for key in ["job:1", "job:2", "job:3", "job_x", "JOB:4"] {
+            kv.set(key, "v").await.unwrap();
+        }
+
+        let page = kv.list_page("job:", None, 2).await.unwrap();
+        let keys: Vec<&str> = page.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["job:1", "job:2"]);
+        let page = kv.list_page("job:", Some("job:2"), 2).await.unwrap();
+        let keys: Vec<&str> = page.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["job:3"]);
+
+        // Prefix is literal and case-sensitive: `_` and `JOB:` don't match.
+        assert_eq!(kv.delete_prefix("job:").await.unwrap(), 3);
+        assert_eq!(kv.keys().await.unwrap(), ["JOB:4", "job_x"]);
+        assert_eq!(kv.delete_prefix("job:").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn import_respects_overwrite() {
+        let (kv, _tmp) = setup().await;
+        kv.set("a", "old").await.unwrap();
+
+        let entry = |key: &str, value: &str| KvEntry {
+            key: key.to_string(),
+            value: value.to_string(),
+            created: "2024-01-01T00:00:00.000".to_string(),
+            updated: "2024-01-02T00:00:00.000".to_string(),
+        };
+
+        let written = kv
+            .import(vec![entry("a", "new"), entry("b", "fresh")], false)
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(kv.get("a").await.unwrap().value, "old");
+        let b = kv.get("b").await.unwrap();
+        assert_eq!(b.value, "fresh");
+        assert_eq!(b.updated, "2024-01-02T00:00:00.000");
+
+        let written = kv.import(vec![entry("a", "new")], true).await.unwrap();
+        assert_eq!(written, 1);
+        assert_eq!(kv.get("a").await.unwrap().value, "new");
+    }
 }
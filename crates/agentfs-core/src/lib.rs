@@ -1,14 +1,18 @@
 pub mod analytics;
+pub mod backup;
 pub mod config;
 pub mod connection;
+pub mod doctor;
 pub mod error;
 pub mod events;
 pub mod filesystem;
 pub mod gc;
 pub mod integrity;
 pub mod kvstore;
+pub mod memory_index;
 pub mod schema;
 pub mod sessions;
+pub mod skills;
 pub mod toolcalls;
 
 use std::path::Path;
@@ -166,7 +170,31 @@ impl AgentFS {
     /// Run garbage collection.
     pub async fn gc(&self) -> Result<gc::GcReport> {
         self.writer
-            .with_conn(|conn| gc::collect_garbage(conn))
+            .with_conn(gc::collect_garbage)
+            .await
+    }
+
+    /// Run garbage collection in steps of at most `batch` rows per kind,
+    /// releasing the writer between steps so other writes can interleave.
+    pub async fn gc_incremental(&self, batch: i64) -> Result<gc::GcReport> {
+        let mut report = gc::GcReport::default();
+        loop {
+            let step = self
+                .writer
+                .with_conn(move |conn| gc::collect_garbage_step(conn, batch))
+                .await?;
+            if step.total() == 0 {
+                return Ok(report);
+            }
+            report.merge(&step);
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Delete audit history older than the retention policy allows.
+    pub async fn prune_history(&self, policy: gc::RetentionPolicy) -> Result<gc::RetentionReport> {
+        self.writer
+            .with_conn(move |conn| gc::prune_history(conn, &policy))
             .await
     }
 
@@ -176,6 +204,25 @@ impl AgentFS {
         integrity::scrub(reader.conn())
     }
 
+    /// Verify the chunks of a single file.
+    pub async fn verify_file(&self, path: &str) -> Result<integrity::FileIntegrityReport> {
+        let st = self.fs.stat(path).await?;
+        if !st.is_file() {
+            return Err(AgentFSError::NotAFile {
+                path: path.to_string(),
+            });
+        }
+        let reader = self.readers.acquire().await?;
+        integrity::verify_inode(reader.conn(), path, st.ino)
+    }
+
+    /// Repair corrupt files and dangling references (see [`integrity::repair`]).
+    pub async fn repair(&self, dry_run: bool) -> Result<integrity::RepairReport> {
+        self.writer
+            .with_conn(move |conn| integrity::repair(conn, dry_run))
+            .await
+    }
+
     /// Create a snapshot using SQLite's backup API.
     pub async fn snapshot(&self, dest: &Path) -> Result<()> {
         let dest = dest.to_path_buf();
@@ -339,7 +386,7 @@ mod tests {
 
         // Info
         let info = afs.info().await.unwrap();
-        assert_eq!(info.schema_version, 3);
+        assert_eq!(info.schema_version, 7);
         assert_eq!(info.file_count, 1);
 
         // Close
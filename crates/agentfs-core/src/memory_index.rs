@@ -0,0 +1,332 @@
+use rusqlite::Connection;
+
+use crate::error::Result;
+
+/// Memory KV entries: `memory:*` keys, except episodes compaction archived,
+/// which are deliberately neither tracked nor searchable.
+const LIVE_KEYS: &str =
+    "SELECT key FROM kv_store WHERE key LIKE 'memory:%' AND key NOT LIKE 'memory:archive:%'";
+
+/// Drift between memory KV entries, `memory_metadata` and `memory_fts`.
+///
+/// From [`check`] these are problems found; from [`repair_step`] they are
+/// rows fixed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct MemoryIndexReport {
+    /// Entries with no `memory_metadata` row (invisible to tiers and compaction).
+    pub untracked: u64,
+    /// `memory_metadata` rows whose entry no longer exists.
+    pub orphaned_metadata: u64,
+    /// `memory_fts` rows whose entry no longer exists.
+    pub stale_fts: u64,
+    /// Entries with no `memory_fts` row (never returned by search).
+    pub unindexed: u64,
+    /// Extra `memory_fts` rows for an already indexed entry.
+    pub duplicate_fts: u64,
+}
+
+impl MemoryIndexReport {
+    /// Total number of rows.
+    pub fn total(&self) -> u64 {
+        self.untracked + self.orphaned_metadata + self.stale_fts + self.unindexed + self.duplicate_fts
+    }
+
+    /// Add another report's counts to this one.
+    pub fn merge(&mut self, other: &MemoryIndexReport) {
+        self.untracked += other.untracked;
+        self.orphaned_metadata += other.orphaned_metadata;
+        self.stale_fts += other.stale_fts;
+        self.unindexed += other.unindexed;
+        self.duplicate_fts += other.duplicate_fts;
+    }
+}
+
+impl std::fmt::Display for MemoryIndexReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} untracked, {} orphaned metadata, {} stale rows, {} unindexed, {} duplicate rows",
+            self.untracked, self.orphaned_metadata, self.stale_fts, self.unindexed, self.duplicate_fts
+        )
+    }
+}
+
+/// Count drift without changing anything.
+pub fn check(conn: &Connection) -> Result<MemoryIndexReport> {
+    let count = |sql: String| -> Result<u64> {
+        Ok(conn.query_row(&sql, [], |r| r.get::<_, i64>(0))? as u64)
+    };
+    Ok(MemoryIndexReport {
+        untracked: count(format!(
+            "SELECT COUNT(*) FROM ({LIVE_KEYS}) WHERE key NOT IN (SELECT key FROM memory_metadata)"
+        ))?,
+        orphaned_metadata: count(format!(
+            "SELECT COUNT(*) FROM memory_metadata WHERE key NOT IN ({LIVE_KEYS})"
+        ))?,
+        stale_fts: count(format!("SELECT COUNT(*) FROM memory_fts WHERE key NOT IN ({LIVE_KEYS})"))?,
+        unindexed: count(format!(
+            "SELECT COUNT(*) FROM ({LIVE_KEYS}) WHERE key NOT IN (SELECT key FROM memory_fts)"
+        ))?,
+        duplicate_fts: count(
+            "SELECT COUNT(*) - COUNT(DISTINCT key) FROM memory_fts".to_string(),
+        )?,
+    })
+}
+
+/// Repair at most `batch` rows of each kind in a single transaction.
+///
+/// Untracked entries get default metadata, orphaned and stale rows are
+/// deleted, unindexed entries are indexed from their JSON, and duplicate
+/// index rows are dropped. Repeat until a step returns an empty report.
+pub fn repair_step(conn: &Connection, batch: i64) -> Result<MemoryIndexReport> {
+    let tx = conn.unchecked_transaction()?;
+
+    let mut stmt = tx.prepare(&format!(
+        "SELECT key, length(value) FROM kv_store
+         WHERE key IN ({LIVE_KEYS}) AND key NOT IN (SELECT key FROM memory_metadata)
+         LIMIT ?1"
+    ))?;
+    let untracked_entries: Vec<(String, i64)> = stmt
+        .query_map([batch], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    drop(stmt);
+    for (key, byte_size) in &untracked_entries {
+        tx.execute(
+            "INSERT INTO memory_metadata (key, provider, byte_size) VALUES (?1, ?2, ?3)",
+            rusqlite::params![key, provider_for_key(key), byte_size],
+        )?;
+    }
+
+    let orphaned_metadata = tx.execute(
+        &format!(
+            "DELETE FROM memory_metadata WHERE key IN
+             (SELECT key FROM memory_metadata WHERE key NOT IN ({LIVE_KEYS}) LIMIT ?1)"
+        ),
+        [batch],
+    )? as u64;
+
+    let stale_fts = tx.execute(
+        &format!(
+            "DELETE FROM memory_fts WHERE rowid IN
+             (SELECT rowid FROM memory_fts WHERE key NOT IN ({LIVE_KEYS}) LIMIT ?1)"
+        ),
+        [batch],
+    )? as u64;
+
+    let duplicate_fts = tx.execute(
+        "DELETE FROM memory_fts WHERE rowid IN
+         (SELECT rowid FROM memory_fts
+          WHERE rowid NOT IN (SELECT MIN(rowid) FROM memory_fts GROUP BY key) LIMIT ?1)",
+        [batch],
+    )? as u64;
+
+    let mut stmt = tx.prepare(&format!(
+        "SELECT key, value FROM kv_store
+         WHERE key IN ({LIVE_KEYS}) AND key NOT IN (SELECT key FROM memory_fts)
+         LIMIT ?1"
+    ))?;
+    let unindexed_entries: Vec<(String, String)> = stmt
+        .query_map([batch], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    drop(stmt);
+    for (key, value) in &unindexed_entries {
+        let provider: String = tx
+            .query_row("SELECT provider FROM memory_metadata WHERE key = ?1", [key], |r| r.get(0))
+            .unwrap_or_else(|_| provider_for_key(key).to_string());
+        tx.execute(
+            "INSERT INTO memory_fts (key, provider, content) VALUES (?1, ?2, ?3)",
+            rusqlite::params![key, provider, searchable_content(value)],
+        )?;
+    }
+
+    tx.commit()?;
+
+    Ok(MemoryIndexReport {
+        untracked: untracked_entries.len() as u64,
+        orphaned_metadata,
+        stale_fts,
+        unindexed: unindexed_entries.len() as u64,
+        duplicate_fts,
+    })
+}
+
+/// Repair all drift, one [`repair_step`] of `batch` rows at a time.
+pub fn repair(conn: &Connection, batch: i64) -> Result<MemoryIndexReport> {
+    let mut report = MemoryIndexReport::default();
+    loop {
+        let step = repair_step(conn, batch)?;
+        if step.total() == 0 {
+            return Ok(report);
+        }
+        report.merge(&step);
+    }
+}
+
+/// The provider that owns a memory key, as recorded in `memory_metadata`
+/// and `memory_fts` (e.g. `memory:episode:…` belongs to `episodes`).
+pub fn provider_for_key(key: &str) -> &'static str {
+    let kind = key
+        .strip_prefix("memory:")
+        .and_then(|rest| rest.split(':').next())
+        .unwrap_or("");
+    match kind {
+        "playbook" => "playbook",
+        "episode" => "episodes",
+        "tool_pattern" => "tool_patterns",
+        "preference" => "preferences",
+        "codebase" => "codebase",
+        _ => "unknown",
+    }
+}
+
+/// The searchable text of a memory entry's JSON value: its content,
+/// summary, outcome, category, patterns, decisions and errors.
+pub fn searchable_content(json_value: &str) -> String {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(json_value) else {
+        return json_value.to_string();
+    };
+    let mut parts = Vec::new();
+
+    for field in ["content", "summary", "outcome", "category"] {
+        if let Some(text) = v.get(field).and_then(|v| v.as_str()) {
+            parts.push(text.to_string());
+        }
+    }
+
+    // Tool patterns: extract pattern text
+    if let Some(patterns) = v.get("patterns").and_then(|v| v.as_array()) {
+        for p in patterns {
+            if let Some(text) = p.get("pattern").and_then(|v| v.as_str()) {
+                parts.push(text.to_string());
+            }
+        }
+    }
+
+    // Key decisions
+    if let Some(decisions) = v.get("key_decisions").and_then(|v| v.as_array()) {
+        for d in decisions {
+            if let Some(text) = d.as_str() {
+                parts.push(text.to_string());
+            }
+        }
+    }
+
+    // Common errors
+    if let Some(errors) = v.get("common_errors").and_then(|v| v.as_array()) {
+        for e in errors {
+            if let Some(text) = e.get("error").and_then(|v| v.as_str()) {
+                parts.push(text.to_string());
+            }
+        }
+    }
+
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::init_schema;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn, 65536).unwrap();
+        conn
+    }
+
+    fn kv(conn: &Connection, key: &str, value: &str) {
+        conn.execute("INSERT INTO kv_store (key, value) VALUES (?1, ?2)", [key, value])
+            .unwrap();
+    }
+
+    #[test]
+    fn consistent_memory() {
+        let conn = setup();
+        kv(&conn, "memory:playbook:str-00001", r#"{"content":"Run the linter"}"#);
+        kv(&conn, "memory:archive:episode:s1", r#"{"summary":"old"}"#);
+        conn.execute(
+            "INSERT INTO memory_metadata (key, provider) VALUES ('memory:playbook:str-00001', 'playbook')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO memory_fts (key, provider, content) VALUES ('memory:playbook:str-00001', 'playbook', 'Run the linter')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(check(&conn).unwrap(), MemoryIndexReport::default());
+    }
+
+    #[test]
+    fn detects_and_repairs_drift() {
+        let conn = setup();
+        // Untracked and unindexed
+        kv(&conn, "memory:episode:s1", r#"{"summary":"Fixed the flaky test","outcome":"completed"}"#);
+        kv(&conn, "memory:tool_pattern:bash", r#"{"tool":"bash","patterns":[{"pattern":"quote paths"}]}"#);
+        // Orphaned metadata and a stale index row
+        conn.execute(
+            "INSERT INTO memory_metadata (key, provider) VALUES ('memory:playbook:gone', 'playbook')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO memory_fts (key, provider, content) VALUES ('memory:playbook:gone', 'playbook', 'x')",
+            [],
+        )
+        .unwrap();
+        // An indexed entry with a duplicate row
+        kv(&conn, "memory:preference:p1", r#"{"content":"Use tabs"}"#);
+        conn.execute(
+            "INSERT INTO memory_metadata (key, provider) VALUES ('memory:preference:p1', 'preferences')",
+            [],
+        )
+        .unwrap();
+        for _ in 0..2 {
+            conn.execute(
+                "INSERT INTO memory_fts (key, provider, content) VALUES ('memory:preference:p1', 'preferences', 'Use tabs')",
+                [],
+            )
+            .unwrap();
+        }
+
+        let found = check(&conn).unwrap();
+        assert_eq!(
+            found,
+            MemoryIndexReport { untracked: 2, orphaned_metadata: 1, stale_fts: 1, unindexed: 2, duplicate_fts: 1 }
+        );
+
+        let first = repair_step(&conn, 1).unwrap();
+        assert_eq!(first.untracked, 1);
+        let rest = repair(&conn, 1).unwrap();
+        let mut fixed = first;
+        fixed.merge(&rest);
+        assert_eq!(fixed, found);
+        assert_eq!(check(&conn).unwrap(), MemoryIndexReport::default());
+
+        let provider: String = conn
+            .query_row("SELECT provider FROM memory_metadata WHERE key = 'memory:episode:s1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(provider, "episodes");
+        let hits: i64 = conn
+            .query_row("SELECT COUNT(*) FROM memory_fts WHERE memory_fts MATCH 'flaky'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[test]
+    fn providers() {
+        assert_eq!(provider_for_key("memory:episode:abc"), "episodes");
+        assert_eq!(provider_for_key("memory:tool_pattern:bash"), "tool_patterns");
+        assert_eq!(provider_for_key("memory:codebase:brief"), "codebase");
+        assert_eq!(provider_for_key("session:x"), "unknown");
+    }
+
+    #[test]
+    fn content_from_json() {
+        let playbook = r#"{"content":"Always check file exists","category":"strategy","helpful":5}"#;
+        assert_eq!(searchable_content(playbook), "Always check file exists strategy");
+        let episode = r#"{"summary":"Built a REST API","outcome":"success","key_decisions":["chose tower"]}"#;
+        assert_eq!(searchable_content(episode), "Built a REST API success chose tower");
+        assert_eq!(searchable_content("plain text"), "plain text");
+    }
+}
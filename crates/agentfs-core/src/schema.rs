@@ -4,7 +4,7 @@ use tracing::info;
 use crate::error::{AgentFSError, Result};
 
 /// Current schema version.
-pub const SCHEMA_VERSION: u32 = 3;
+pub const SCHEMA_VERSION: u32 = 7;
 
 /// Default chunk size in bytes (64 KiB).
 pub const DEFAULT_CHUNK_SIZE: usize = 65536;
@@ -149,6 +149,28 @@ CREATE VIRTUAL TABLE IF NOT EXISTS memory_fts USING fts5(
 );
 "#;
 
+/// DDL for schema v4 additions (per-session tool call lookups).
+/// Applied after `tool_calls.session_id` is guaranteed to exist.
+const SCHEMA_V4_ADDITIONS: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_tool_calls_session ON tool_calls(session_id);
+"#;
+
+/// DDL for schema v5 additions (token usage by purpose).
+/// Applied after `token_usage.purpose` is guaranteed to exist.
+const SCHEMA_V5_ADDITIONS: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_token_purpose ON token_usage(purpose);
+"#;
+
+/// DDL for schema v7 additions (named copies of memory state).
+const SCHEMA_V7_ADDITIONS: &str = r#"
+CREATE TABLE IF NOT EXISTS memory_snapshots (
+    name        TEXT PRIMARY KEY,
+    created     TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+    entry_count INTEGER NOT NULL DEFAULT 0,
+    data        TEXT NOT NULL
+);
+"#;
+
 /// Initialize the schema on a freshly opened connection.
 /// Returns `true` if the schema was newly created, `false` if it already existed.
 pub fn init_schema(conn: &Connection, chunk_size: usize) -> Result<bool> {
@@ -172,10 +194,16 @@ pub fn init_schema(conn: &Connection, chunk_size: usize) -> Result<bool> {
         });
     }
 
-    // Create schema (v1 base + v2 + v3 additions)
+    // Create schema (v1 base + v2 + v3 + v4 + v5 + v6 + v7 additions)
     conn.execute_batch(SCHEMA_V1)?;
     conn.execute_batch(SCHEMA_V2_ADDITIONS)?;
     conn.execute_batch(SCHEMA_V3_ADDITIONS)?;
+    ensure_tool_calls_session_column(conn)?;
+    conn.execute_batch(SCHEMA_V4_ADDITIONS)?;
+    ensure_token_usage_purpose_column(conn)?;
+    conn.execute_batch(SCHEMA_V5_ADDITIONS)?;
+    ensure_memory_outcome_columns(conn)?;
+    conn.execute_batch(SCHEMA_V7_ADDITIONS)?;
 
     // Insert metadata
     conn.execute(
@@ -252,6 +280,26 @@ pub fn migrate(conn: &Connection, chunk_size: usize) -> Result<()> {
 
     if version == 2 {
         migrate_v2_to_v3(conn)?;
+        version = 3;
+    }
+
+    if version == 3 {
+        migrate_v3_to_v4(conn)?;
+        version = 4;
+    }
+
+    if version == 4 {
+        migrate_v4_to_v5(conn)?;
+        version = 5;
+    }
+
+    if version == 5 {
+        migrate_v5_to_v6(conn)?;
+        version = 6;
+    }
+
+    if version == 6 {
+        migrate_v6_to_v7(conn)?;
         return Ok(());
     }
 
@@ -301,6 +349,120 @@ fn migrate_v2_to_v3(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+/// Migrate from schema v3 to v4: make sure `tool_calls.session_id` exists
+/// (databases created fresh at v2/v3 never got it) and index it.
+fn migrate_v3_to_v4(conn: &Connection) -> Result<()> {
+    info!("migrating schema v3 → v4");
+
+    ensure_tool_calls_session_column(conn)?;
+    conn.execute_batch(SCHEMA_V4_ADDITIONS)?;
+
+    // Update schema version
+    conn.execute(
+        "UPDATE agentfs_meta SET value = ?1 WHERE key = 'schema_version'",
+        [SCHEMA_VERSION.to_string()],
+    )?;
+
+    info!("schema migrated to v{SCHEMA_VERSION}");
+    Ok(())
+}
+
+/// Migrate from schema v4 to v5: tag token usage with what it was spent
+/// on (`NULL` for agent turns, e.g. `reflection` for memory upkeep).
+fn migrate_v4_to_v5(conn: &Connection) -> Result<()> {
+    info!("migrating schema v4 → v5");
+
+    ensure_token_usage_purpose_column(conn)?;
+    conn.execute_batch(SCHEMA_V5_ADDITIONS)?;
+
+    // Update schema version
+    conn.execute(
+        "UPDATE agentfs_meta SET value = ?1 WHERE key = 'schema_version'",
+        [SCHEMA_VERSION.to_string()],
+    )?;
+
+    info!("schema migrated to v{SCHEMA_VERSION}");
+    Ok(())
+}
+
+/// Migrate from schema v5 to v6: count the turns each memory entry was
+/// recalled into that went well or badly.
+fn migrate_v5_to_v6(conn: &Connection) -> Result<()> {
+    info!("migrating schema v5 → v6");
+
+    ensure_memory_outcome_columns(conn)?;
+
+    // Update schema version
+    conn.execute(
+        "UPDATE agentfs_meta SET value = ?1 WHERE key = 'schema_version'",
+        [SCHEMA_VERSION.to_string()],
+    )?;
+
+    info!("schema migrated to v{SCHEMA_VERSION}");
+    Ok(())
+}
+
+/// Migrate from schema v6 to v7: add the memory snapshots table.
+fn migrate_v6_to_v7(conn: &Connection) -> Result<()> {
+    info!("migrating schema v6 → v7");
+
+    conn.execute_batch(SCHEMA_V7_ADDITIONS)?;
+
+    // Update schema version
+    conn.execute(
+        "UPDATE agentfs_meta SET value = ?1 WHERE key = 'schema_version'",
+        [SCHEMA_VERSION.to_string()],
+    )?;
+
+    info!("schema migrated to v{SCHEMA_VERSION}");
+    Ok(())
+}
+
+/// Add `memory_metadata.successes` and `.failures` if they are missing.
+fn ensure_memory_outcome_columns(conn: &Connection) -> Result<()> {
+    for column in ["successes", "failures"] {
+        let has_column: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('memory_metadata') WHERE name = ?1",
+            [column],
+            |row| row.get(0),
+        )?;
+        if !has_column {
+            conn.execute_batch(&format!(
+                "ALTER TABLE memory_metadata ADD COLUMN {column} INTEGER NOT NULL DEFAULT 0;"
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// Add `token_usage.purpose` if the column is missing.
+fn ensure_token_usage_purpose_column(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('token_usage') WHERE name = 'purpose'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_column {
+        conn.execute_batch("ALTER TABLE token_usage ADD COLUMN purpose TEXT;")?;
+    }
+    Ok(())
+}
+
+/// Add `tool_calls.session_id` if the column is missing.
+fn ensure_tool_calls_session_column(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('tool_calls') WHERE name = 'session_id'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_column {
+        conn.execute_batch(
+            "ALTER TABLE tool_calls ADD COLUMN session_id TEXT REFERENCES sessions(session_id);",
+        )?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -312,7 +474,7 @@ mod tests {
         assert!(created);
 
         let version = get_schema_version(&conn).unwrap();
-        assert_eq!(version, 3);
+        assert_eq!(version, 7);
 
         let chunk_size = get_chunk_size(&conn).unwrap();
         assert_eq!(chunk_size, 65536);
@@ -388,11 +550,11 @@ mod tests {
         .unwrap();
 
         let err = init_schema(&conn, 65536).unwrap_err();
-        assert!(matches!(err, AgentFSError::SchemaMismatch { expected: 3, found: 999 }));
+        assert!(matches!(err, AgentFSError::SchemaMismatch { expected: 7, found: 999 }));
     }
 
     #[test]
-    fn migrate_v1_to_v3() {
+    fn migrate_v1_to_latest() {
         let conn = Connection::open_in_memory().unwrap();
 
         // Create a v1 schema manually
@@ -421,10 +583,10 @@ mod tests {
 
         assert_eq!(get_schema_version(&conn).unwrap(), 1);
 
-        // Run migration (v1 → v2 → v3)
+        // Run migration (v1 → v2 → v3 → v4 → v5 → v6 → v7)
         migrate(&conn, 65536).unwrap();
 
-        assert_eq!(get_schema_version(&conn).unwrap(), 3);
+        assert_eq!(get_schema_version(&conn).unwrap(), 7);
 
         // Verify v2 tables exist
         let sessions_exists: bool = conn
@@ -467,7 +629,7 @@ mod tests {
     }
 
     #[test]
-    fn migrate_v2_to_v3() {
+    fn migrate_v2_to_latest() {
         let conn = Connection::open_in_memory().unwrap();
 
         // Create a v2 schema manually
@@ -491,10 +653,10 @@ mod tests {
 
         assert_eq!(get_schema_version(&conn).unwrap(), 2);
 
-        // Run migration (v2 → v3)
+        // Run migration (v2 → v3 → v4 → v5 → v6 → v7)
         migrate(&conn, 65536).unwrap();
 
-        assert_eq!(get_schema_version(&conn).unwrap(), 3);
+        assert_eq!(get_schema_version(&conn).unwrap(), 7);
 
         // Verify v3 tables exist
         let metadata_exists: bool = conn
@@ -506,4 +668,125 @@ mod tests {
             .unwrap();
         assert!(metadata_exists);
     }
+
+    #[test]
+    fn migrate_v3_to_v4_adds_session_column() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        // A fresh v3 database never had tool_calls.session_id
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute_batch(SCHEMA_V2_ADDITIONS).unwrap();
+        conn.execute_batch(SCHEMA_V3_ADDITIONS).unwrap();
+        conn.execute(
+            "INSERT INTO agentfs_meta (key, value) VALUES ('schema_version', '3')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn, 65536).unwrap();
+
+        assert_eq!(get_schema_version(&conn).unwrap(), 7);
+        let has_session_id: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('tool_calls') WHERE name='session_id'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!(has_session_id);
+    }
+
+    #[test]
+    fn migrate_v4_to_v5_adds_purpose_column() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute_batch(SCHEMA_V2_ADDITIONS).unwrap();
+        conn.execute_batch(SCHEMA_V3_ADDITIONS).unwrap();
+        ensure_tool_calls_session_column(&conn).unwrap();
+        conn.execute_batch(SCHEMA_V4_ADDITIONS).unwrap();
+        conn.execute(
+            "INSERT INTO agentfs_meta (key, value) VALUES ('schema_version', '4')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn, 65536).unwrap();
+
+        assert_eq!(get_schema_version(&conn).unwrap(), 7);
+        let has_purpose: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('token_usage') WHERE name='purpose'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!(has_purpose);
+    }
+
+    #[test]
+    fn migrate_v5_to_v6_adds_outcome_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute_batch(SCHEMA_V2_ADDITIONS).unwrap();
+        conn.execute_batch(SCHEMA_V3_ADDITIONS).unwrap();
+        ensure_tool_calls_session_column(&conn).unwrap();
+        conn.execute_batch(SCHEMA_V4_ADDITIONS).unwrap();
+        ensure_token_usage_purpose_column(&conn).unwrap();
+        conn.execute_batch(SCHEMA_V5_ADDITIONS).unwrap();
+        conn.execute(
+            "INSERT INTO agentfs_meta (key, value) VALUES ('schema_version', '5')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO memory_metadata (key, provider) VALUES ('memory:playbook:str-00001', 'playbook')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn, 65536).unwrap();
+
+        assert_eq!(get_schema_version(&conn).unwrap(), 7);
+        let (successes, failures): (i64, i64) = conn
+            .query_row(
+                "SELECT successes, failures FROM memory_metadata",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((successes, failures), (0, 0));
+    }
+
+    #[test]
+    fn migrate_v6_to_v7_adds_snapshots_table() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute_batch(SCHEMA_V2_ADDITIONS).unwrap();
+        conn.execute_batch(SCHEMA_V3_ADDITIONS).unwrap();
+        ensure_tool_calls_session_column(&conn).unwrap();
+        conn.execute_batch(SCHEMA_V4_ADDITIONS).unwrap();
+        ensure_token_usage_purpose_column(&conn).unwrap();
+        conn.execute_batch(SCHEMA_V5_ADDITIONS).unwrap();
+        ensure_memory_outcome_columns(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO agentfs_meta (key, value) VALUES ('schema_version', '6')",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn, 65536).unwrap();
+
+        assert_eq!(get_schema_version(&conn).unwrap(), 7);
+        let snapshots_exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='memory_snapshots'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!(snapshots_exists);
+    }
 }
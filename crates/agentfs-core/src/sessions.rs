@@ -159,6 +159,30 @@ impl Sessions {
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(sessions)
     }
+
+    /// List recent sessions started by `agent_name` (any status).
+    pub async fn list_recent_by_agent(&self, agent_name: &str, limit: i64) -> Result<Vec<Session>> {
+        let reader = self.readers.acquire().await?;
+        let mut stmt = reader.conn().prepare(
+            "SELECT id, session_id, agent_name, provider, status, metadata, started_at, ended_at \
+             FROM sessions WHERE agent_name = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let sessions = stmt
+            .query_map(rusqlite::params![agent_name, limit], |row| {
+                Ok(Session {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    agent_name: row.get(2)?,
+                    provider: row.get(3)?,
+                    status: row.get(4)?,
+                    metadata: row.get(5)?,
+                    started_at: row.get(6)?,
+                    ended_at: row.get(7)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
 }
 
 #[cfg(test)]
@@ -229,4 +253,16 @@ mod tests {
         let recent = sessions.list_recent(10).await.unwrap();
         assert_eq!(recent.len(), 2);
     }
+
+    #[tokio::test]
+    async fn list_recent_by_agent() {
+        let (sessions, _tmp) = setup().await;
+        sessions.start("a", Some("coder"), None, None).await.unwrap();
+        sessions.start("b", Some("coder:task"), None, None).await.unwrap();
+        sessions.start("c", None, None, None).await.unwrap();
+
+        let recent = sessions.list_recent_by_agent("coder", 10).await.unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].session_id, "a");
+    }
 }
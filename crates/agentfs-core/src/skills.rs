@@ -0,0 +1,64 @@
+use crate::filesystem::{EntryKind, SearchFilter};
+use crate::AgentFS;
+
+/// KV prefix skills are stored under, one JSON record per skill at
+/// `config:skill:<name>`.
+pub const SKILL_PREFIX: &str = "config:skill:";
+
+/// AgentFS directory holding the files bundled with skill `name`.
+pub fn resource_dir(name: &str) -> String {
+    format!("/skills/{name}")
+}
+
+/// Paths of the files bundled with skill `name`, sorted.
+pub async fn resources(db: &AgentFS, name: &str) -> Vec<String> {
+    let filter = SearchFilter {
+        path: Some(format!("{}/**", resource_dir(name))),
+        kind: Some(EntryKind::File),
+        ..Default::default()
+    };
+    db.fs
+        .find(&filter)
+        .await
+        .map(|found| found.into_iter().map(|r| r.path).collect())
+        .unwrap_or_default()
+}
+
+/// The user message a skill runs as: its body, the files bundled with it
+/// (readable with `read_tool`), then the request. An empty request asks
+/// for the skill to be run as it is.
+pub fn prompt(name: &str, body: &str, resources: &[String], read_tool: &str, request: &str) -> String {
+    let mut prompt = format!("<skill>\n{body}\n</skill>\n\n");
+    if !resources.is_empty() {
+        prompt.push_str(&format!(
+            "<skill_resources>\nFiles bundled with this skill (read them with {read_tool}):\n"
+        ));
+        for path in resources {
+            prompt.push_str(&format!("- {path}\n"));
+        }
+        prompt.push_str("</skill_resources>\n\n");
+    }
+    let request = request.trim();
+    if request.is_empty() {
+        prompt.push_str(&format!("User request: Run the /{name} skill"));
+    } else {
+        prompt.push_str(&format!("User request: {request}"));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_lists_resources_and_defaults_the_request() {
+        let text = prompt("review", "Review it.", &[], "read_file", "  ");
+        assert_eq!(text, "<skill>\nReview it.\n</skill>\n\nUser request: Run the /review skill");
+
+        let files = vec!["/skills/review/checklist.md".to_string()];
+        let text = prompt("review", "Review it.", &files, "agentfs_read_file", "the parser");
+        assert!(text.contains("(read them with agentfs_read_file):\n- /skills/review/checklist.md\n"));
+        assert!(text.ends_with("User request: the parser"));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use crate::connection::pool::{ReaderPool, WriterHandle};
@@ -7,6 +8,7 @@ use crate::error::Result;
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ToolCall {
     pub id: i64,
+    pub session_id: Option<String>,
     pub tool_name: String,
     pub status: String,
     pub input: Option<String>,
@@ -17,6 +19,9 @@ pub struct ToolCall {
 }
 
 /// Tool call statistics.
+///
+/// Latency percentiles are computed over finished calls only and are
+/// `None` when a tool has no finished calls.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct ToolStats {
     pub tool_name: String,
@@ -24,6 +29,12 @@ pub struct ToolStats {
     pub successes: i64,
     pub errors: i64,
     pub in_progress: i64,
+    /// Errors as a fraction of finished calls (0.0–1.0).
+    pub error_rate: f64,
+    pub avg_ms: Option<i64>,
+    pub p50_ms: Option<i64>,
+    pub p95_ms: Option<i64>,
+    pub max_ms: Option<i64>,
 }
 
 /// Tool call audit trail backed by SQLite.
@@ -38,14 +49,21 @@ impl ToolCalls {
     }
 
     /// Record the start of a tool call. Returns the new record ID.
-    pub async fn start(&self, tool_name: &str, input: Option<&str>) -> Result<i64> {
+    pub async fn start(
+        &self,
+        session_id: Option<&str>,
+        tool_name: &str,
+        input: Option<&str>,
+    ) -> Result<i64> {
+        let session_id = session_id.map(|s| s.to_string());
         let tool_name = tool_name.to_string();
         let input = input.map(|s| s.to_string());
         self.writer
             .with_conn(move |conn| {
                 conn.execute(
-                    "INSERT INTO tool_calls (tool_name, status, input) VALUES (?1, 'started', ?2)",
-                    rusqlite::params![tool_name, input],
+                    "INSERT INTO tool_calls (session_id, tool_name, status, input) \
+                     VALUES (?1, ?2, 'started', ?3)",
+                    rusqlite::params![session_id, tool_name, input],
                 )?;
                 Ok(conn.last_insert_rowid())
             })
@@ -85,11 +103,13 @@ impl ToolCalls {
     /// Record a complete tool call in one shot.
     pub async fn record(
         &self,
+        session_id: Option<&str>,
         tool_name: &str,
         input: Option<&str>,
         output: Option<&str>,
         error_msg: Option<&str>,
     ) -> Result<i64> {
+        let session_id = session_id.map(|s| s.to_string());
         let tool_name = tool_name.to_string();
         let input = input.map(|s| s.to_string());
         let output = output.map(|s| s.to_string());
@@ -99,9 +119,10 @@ impl ToolCalls {
         self.writer
             .with_conn(move |conn| {
                 conn.execute(
-                    "INSERT INTO tool_calls (tool_name, status, input, output, error_msg, ended_at) \
-                     VALUES (?1, ?2, ?3, ?4, ?5, strftime('%Y-%m-%dT%H:%M:%f', 'now'))",
-                    rusqlite::params![tool_name, status, input, output, error_msg],
+                    "INSERT INTO tool_calls \
+                     (session_id, tool_name, status, input, output, error_msg, ended_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, strftime('%Y-%m-%dT%H:%M:%f', 'now'))",
+                    rusqlite::params![session_id, tool_name, status, input, output, error_msg],
                 )?;
                 Ok(conn.last_insert_rowid())
             })
@@ -112,52 +133,139 @@ impl ToolCalls {
     pub async fn recent(&self, limit: i64) -> Result<Vec<ToolCall>> {
         let reader = self.readers.acquire().await?;
         let mut stmt = reader.conn().prepare(
-            "SELECT id, tool_name, status, input, output, error_msg, started_at, ended_at \
+            "SELECT id, session_id, tool_name, status, input, output, error_msg, started_at, ended_at \
              FROM tool_calls ORDER BY id DESC LIMIT ?1",
         )?;
         let calls = stmt
             .query_map([limit], |row| {
                 Ok(ToolCall {
                     id: row.get(0)?,
-                    tool_name: row.get(1)?,
-                    status: row.get(2)?,
-                    input: row.get(3)?,
-                    output: row.get(4)?,
-                    error_msg: row.get(5)?,
-                    started_at: row.get(6)?,
-                    ended_at: row.get(7)?,
+                    session_id: row.get(1)?,
+                    tool_name: row.get(2)?,
+                    status: row.get(3)?,
+                    input: row.get(4)?,
+                    output: row.get(5)?,
+                    error_msg: row.get(6)?,
+                    started_at: row.get(7)?,
+                    ended_at: row.get(8)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(calls)
     }
 
-    /// Get statistics grouped by tool name.
-    pub async fn stats(&self) -> Result<Vec<ToolStats>> {
+    /// Get a session's tool calls, oldest first.
+    pub async fn by_session(&self, session_id: &str) -> Result<Vec<ToolCall>> {
         let reader = self.readers.acquire().await?;
         let mut stmt = reader.conn().prepare(
-            "SELECT tool_name, \
-                    COUNT(*) as total, \
-                    SUM(CASE WHEN status = 'success' THEN 1 ELSE 0 END) as successes, \
-                    SUM(CASE WHEN status = 'error' THEN 1 ELSE 0 END) as errors, \
-                    SUM(CASE WHEN status = 'started' THEN 1 ELSE 0 END) as in_progress \
-             FROM tool_calls GROUP BY tool_name ORDER BY total DESC",
+            "SELECT id, session_id, tool_name, status, input, output, error_msg, started_at, ended_at \
+             FROM tool_calls WHERE session_id = ?1 ORDER BY id",
         )?;
-        let stats = stmt
-            .query_map([], |row| {
-                Ok(ToolStats {
-                    tool_name: row.get(0)?,
-                    total: row.get(1)?,
-                    successes: row.get(2)?,
-                    errors: row.get(3)?,
-                    in_progress: row.get(4)?,
+        let calls = stmt
+            .query_map([session_id], |row| {
+                Ok(ToolCall {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    tool_name: row.get(2)?,
+                    status: row.get(3)?,
+                    input: row.get(4)?,
+                    output: row.get(5)?,
+                    error_msg: row.get(6)?,
+                    started_at: row.get(7)?,
+                    ended_at: row.get(8)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(calls)
+    }
+
+    /// Count tool calls that have started but not yet finished.
+    pub async fn in_flight(&self) -> Result<i64> {
+        let reader = self.readers.acquire().await?;
+        Ok(reader.conn().query_row(
+            "SELECT COUNT(*) FROM tool_calls WHERE status = 'started'",
+            [],
+            |r| r.get(0),
+        )?)
+    }
+
+    /// Get statistics grouped by tool name, optionally limited to one session.
+    pub async fn stats(&self, session_id: Option<&str>) -> Result<Vec<ToolStats>> {
+        let reader = self.readers.acquire().await?;
+        let mut stmt = reader.conn().prepare(
+            "SELECT tool_name, status, \
+                    CAST(ROUND((julianday(ended_at) - julianday(started_at)) * 86400000) AS INTEGER) \
+             FROM tool_calls WHERE ?1 IS NULL OR session_id = ?1",
+        )?;
+        let rows = stmt
+            .query_map([session_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut by_tool: BTreeMap<String, (ToolStats, Vec<i64>)> = BTreeMap::new();
+        for (tool_name, status, duration_ms) in rows {
+            let (stats, durations) = by_tool.entry(tool_name.clone()).or_insert_with(|| {
+                (
+                    ToolStats {
+                        tool_name,
+                        total: 0,
+                        successes: 0,
+                        errors: 0,
+                        in_progress: 0,
+                        error_rate: 0.0,
+                        avg_ms: None,
+                        p50_ms: None,
+                        p95_ms: None,
+                        max_ms: None,
+                    },
+                    Vec::new(),
+                )
+            });
+            stats.total += 1;
+            match status.as_str() {
+                "success" => stats.successes += 1,
+                "error" => stats.errors += 1,
+                _ => stats.in_progress += 1,
+            }
+            if let Some(ms) = duration_ms {
+                durations.push(ms.max(0));
+            }
+        }
+
+        let mut stats: Vec<ToolStats> = by_tool
+            .into_values()
+            .map(|(mut stats, mut durations)| {
+                let finished = stats.successes + stats.errors;
+                if finished > 0 {
+                    stats.error_rate = stats.errors as f64 / finished as f64;
+                }
+                if !durations.is_empty() {
+                    durations.sort_unstable();
+                    let sum: i64 = durations.iter().sum();
+                    stats.avg_ms = Some(sum / durations.len() as i64);
+                    stats.p50_ms = Some(percentile(&durations, 50));
+                    stats.p95_ms = Some(percentile(&durations, 95));
+                    stats.max_ms = durations.last().copied();
+                }
+                stats
+            })
+            .collect();
+        stats.sort_by_key(|s| std::cmp::Reverse(s.total));
         Ok(stats)
     }
 }
 
+/// Nearest-rank percentile over an ascending, non-empty slice.
+fn percentile(sorted: &[i64], pct: usize) -> i64 {
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,23 +291,41 @@ mod tests {
         (tc, tmp)
     }
 
+    async fn add_session(tc: &ToolCalls, session_id: &str) {
+        let session_id = session_id.to_string();
+        tc.writer
+            .with_conn(move |conn| {
+                conn.execute("INSERT INTO sessions (session_id) VALUES (?1)", [session_id])?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn start_success_flow() {
         let (tc, _tmp) = setup().await;
+        add_session(&tc, "s1").await;
 
-        let id = tc.start("read_file", Some(r#"{"path":"/foo"}"#)).await.unwrap();
+        let id = tc
+            .start(Some("s1"), "read_file", Some(r#"{"path":"/foo"}"#))
+            .await
+            .unwrap();
+        assert_eq!(tc.in_flight().await.unwrap(), 1);
         tc.success(id, Some(r#"{"content":"hello"}"#)).await.unwrap();
+        assert_eq!(tc.in_flight().await.unwrap(), 0);
 
         let recent = tc.recent(10).await.unwrap();
         assert_eq!(recent.len(), 1);
         assert_eq!(recent[0].status, "success");
+        assert_eq!(recent[0].session_id.as_deref(), Some("s1"));
     }
 
     #[tokio::test]
     async fn start_error_flow() {
         let (tc, _tmp) = setup().await;
 
-        let id = tc.start("write_file", None).await.unwrap();
+        let id = tc.start(None, "write_file", None).await.unwrap();
         tc.error(id, "permission denied").await.unwrap();
 
         let recent = tc.recent(10).await.unwrap();
@@ -210,10 +336,55 @@ mod tests {
     #[tokio::test]
     async fn record_one_shot() {
         let (tc, _tmp) = setup().await;
-        tc.record("ls", None, Some("file.txt"), None).await.unwrap();
-        tc.record("rm", None, None, Some("not found")).await.unwrap();
+        tc.record(None, "ls", None, Some("file.txt"), None).await.unwrap();
+        tc.record(None, "rm", None, None, Some("not found")).await.unwrap();
 
-        let stats = tc.stats().await.unwrap();
+        let stats = tc.stats(None).await.unwrap();
         assert_eq!(stats.len(), 2);
     }
+
+    #[tokio::test]
+    async fn calls_by_session() {
+        let (tc, _tmp) = setup().await;
+        add_session(&tc, "s1").await;
+        add_session(&tc, "s2").await;
+        tc.record(Some("s1"), "bash", Some("cargo test"), Some("ok"), None).await.unwrap();
+        tc.record(Some("s2"), "bash", None, Some("ok"), None).await.unwrap();
+        tc.record(Some("s1"), "read_file", None, None, Some("not found")).await.unwrap();
+
+        let calls = tc.by_session("s1").await.unwrap();
+        let names: Vec<&str> = calls.iter().map(|c| c.tool_name.as_str()).collect();
+        assert_eq!(names, vec!["bash", "read_file"]);
+        assert_eq!(calls[0].input.as_deref(), Some("cargo test"));
+        assert!(tc.by_session("s3").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stats_by_session_with_error_rate() {
+        let (tc, _tmp) = setup().await;
+        add_session(&tc, "s1").await;
+        add_session(&tc, "s2").await;
+        tc.record(Some("s1"), "bash", None, Some("ok"), None).await.unwrap();
+        tc.record(Some("s1"), "bash", None, None, Some("exit 1")).await.unwrap();
+        tc.record(Some("s2"), "bash", None, Some("ok"), None).await.unwrap();
+        tc.start(Some("s1"), "bash", None).await.unwrap();
+
+        let stats = tc.stats(Some("s1")).await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].total, 3);
+        assert_eq!(stats[0].in_progress, 1);
+        assert!((stats[0].error_rate - 0.5).abs() < f64::EPSILON);
+        assert!(stats[0].p50_ms.is_some());
+
+        let all = tc.stats(None).await.unwrap();
+        assert_eq!(all[0].total, 4);
+    }
+
+    #[test]
+    fn nearest_rank_percentile() {
+        let durations: Vec<i64> = (1..=20).collect();
+        assert_eq!(percentile(&durations, 50), 10);
+        assert_eq!(percentile(&durations, 95), 19);
+        assert_eq!(percentile(&[7], 95), 7);
+    }
 }
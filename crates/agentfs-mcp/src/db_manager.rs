@@ -1,40 +1,127 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use agentfs_core::config::AgentFSConfig;
 use agentfs_core::AgentFS;
+use serde_json::{json, Value};
+use tracing::info;
+
+/// Default cap on simultaneously open databases.
+pub const DEFAULT_MAX_OPEN: usize = 16;
+
+/// Default cap on the size of one tool result.
+pub const DEFAULT_MAX_RESULT_BYTES: usize = 256 * 1024;
+
+/// Default time a database may sit unused before it is closed.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+struct OpenDb {
+    afs: AgentFS,
+    last_used: Instant,
+    /// Opened at startup; never evicted, so its resources stay listed.
+    pinned: bool,
+}
 
 /// Manages database connections — one per database path.
+///
+/// Databases opened by tool calls are closed again when idle for longer
+/// than the idle timeout, or least-recently-used first when opening
+/// another would exceed the cap. Each open database holds a writer lock
+/// and a WAL file, so a long-running server shouldn't keep them forever.
 pub struct DbManager {
-    dbs: HashMap<PathBuf, AgentFS>,
+    dbs: HashMap<PathBuf, OpenDb>,
+    /// Database used by tool calls that omit `db`.
+    default_db: Option<String>,
+    /// Whether maintenance tools (snapshot, gc, integrity) may be called.
+    allow_admin: bool,
+    max_open: usize,
+    /// `None` keeps databases open until evicted by the cap.
+    idle_timeout: Option<Duration>,
+    /// Largest tool result text sent to a client.
+    max_result_bytes: usize,
 }
 
 impl DbManager {
-    pub fn new() -> Self {
+    pub fn new(default_db: Option<String>, allow_admin: bool) -> Self {
         Self {
             dbs: HashMap::new(),
+            default_db,
+            allow_admin,
+            max_open: DEFAULT_MAX_OPEN,
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            max_result_bytes: DEFAULT_MAX_RESULT_BYTES,
         }
     }
 
+    /// Set the open-database cap (at least 1) and idle timeout.
+    pub fn with_limits(mut self, max_open: usize, idle_timeout: Option<Duration>) -> Self {
+        self.max_open = max_open.max(1);
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Cap tool results at `bytes` (at least 4 KiB).
+    pub fn with_max_result_bytes(mut self, bytes: usize) -> Self {
+        self.max_result_bytes = bytes.max(4096);
+        self
+    }
+
+    /// Largest tool result text sent to a client.
+    pub fn max_result_bytes(&self) -> usize {
+        self.max_result_bytes
+    }
+
+    /// The database used when a tool call has no `db` argument.
+    pub fn default_db(&self) -> Option<&str> {
+        self.default_db.as_deref()
+    }
+
+    /// Whether admin tools are enabled for this server.
+    pub fn allow_admin(&self) -> bool {
+        self.allow_admin
+    }
+
     /// Get or open a database at the given path.
     pub async fn get_or_open(&mut self, path: &str) -> Result<&AgentFS, String> {
+        self.open(path, false).await
+    }
+
+    /// Open a database that stays open for the life of the server.
+    pub async fn open_pinned(&mut self, path: &str) -> Result<&AgentFS, String> {
+        self.open(path, true).await
+    }
+
+    async fn open(&mut self, path: &str, pin: bool) -> Result<&AgentFS, String> {
         let canonical = std::fs::canonicalize(path).map_err(|e| format!("invalid path {path}: {e}"))?;
 
         if !self.dbs.contains_key(&canonical) {
+            self.make_room().await?;
             let config = AgentFSConfig::builder(&canonical)
                 .checkpoint_interval_secs(0)
                 .build();
             let afs = AgentFS::open(config)
                 .await
                 .map_err(|e| format!("failed to open {path}: {e}"))?;
-            self.dbs.insert(canonical.clone(), afs);
+            self.dbs.insert(
+                canonical.clone(),
+                OpenDb {
+                    afs,
+                    last_used: Instant::now(),
+                    pinned: false,
+                },
+            );
         }
 
-        Ok(self.dbs.get(&canonical).unwrap())
+        let db = self.dbs.get_mut(&canonical).unwrap();
+        db.last_used = Instant::now();
+        db.pinned |= pin;
+        Ok(&db.afs)
     }
 
     /// Create a new database at the given path.
     pub async fn create(&mut self, path: &str) -> Result<&AgentFS, String> {
+        self.make_room().await?;
         let path_buf = PathBuf::from(path);
         let config = AgentFSConfig::builder(&path_buf)
             .checkpoint_interval_secs(0)
@@ -44,14 +131,104 @@ impl DbManager {
             .map_err(|e| format!("failed to create {path}: {e}"))?;
 
         let canonical = std::fs::canonicalize(path).map_err(|e| format!("canonicalize failed: {e}"))?;
-        self.dbs.insert(canonical.clone(), afs);
-        Ok(self.dbs.get(&canonical).unwrap())
+        let db = self.dbs.entry(canonical).or_insert(OpenDb {
+            afs,
+            last_used: Instant::now(),
+            pinned: false,
+        });
+        Ok(&db.afs)
+    }
+
+    /// Close the least recently used unpinned database if at the cap.
+    async fn make_room(&mut self) -> Result<(), String> {
+        if self.dbs.len() < self.max_open {
+            return Ok(());
+        }
+        let lru = self
+            .dbs
+            .iter()
+            .filter(|(_, db)| !db.pinned)
+            .min_by_key(|(_, db)| db.last_used)
+            .map(|(path, _)| path.clone())
+            .ok_or_else(|| {
+                format!(
+                    "too many open databases ({}), and all are pinned",
+                    self.dbs.len()
+                )
+            })?;
+        info!(path = %lru.display(), "closing least recently used database");
+        self.close(&lru).await;
+        Ok(())
+    }
+
+    /// Close unpinned databases unused for longer than the idle timeout.
+    /// Returns how many were closed.
+    pub async fn close_idle(&mut self) -> usize {
+        let Some(timeout) = self.idle_timeout else {
+            return 0;
+        };
+        let idle: Vec<PathBuf> = self
+            .dbs
+            .iter()
+            .filter(|(_, db)| !db.pinned && db.last_used.elapsed() >= timeout)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &idle {
+            info!(path = %path.display(), "closing idle database");
+            self.close(path).await;
+        }
+        idle.len()
+    }
+
+    /// How often `close_idle` should run to honor the timeout reasonably.
+    pub fn sweep_interval(&self) -> Option<Duration> {
+        self.idle_timeout
+            .map(|t| (t / 4).clamp(Duration::from_secs(1), Duration::from_secs(60)))
+    }
+
+    async fn close(&mut self, path: &PathBuf) {
+        if let Some(db) = self.dbs.remove(path) {
+            let _ = db.afs.close().await;
+        }
+    }
+
+    /// All open databases, ordered by path.
+    pub fn open_dbs(&self) -> Vec<(&PathBuf, &AgentFS)> {
+        let mut dbs: Vec<_> = self.dbs.iter().map(|(p, db)| (p, &db.afs)).collect();
+        dbs.sort_by(|a, b| a.0.cmp(b.0));
+        dbs
+    }
+
+    /// Open databases and limits, for the `agentfs_databases_list` tool.
+    pub fn describe(&self) -> Value {
+        let default = self
+            .default_db
+            .as_deref()
+            .and_then(|p| std::fs::canonicalize(p).ok());
+        let mut dbs: Vec<_> = self.dbs.iter().collect();
+        dbs.sort_by(|a, b| a.0.cmp(b.0));
+        let databases: Vec<Value> = dbs
+            .into_iter()
+            .map(|(path, db)| {
+                json!({
+                    "path": path.display().to_string(),
+                    "pinned": db.pinned,
+                    "default": default.as_ref() == Some(path),
+                    "idle_secs": db.last_used.elapsed().as_secs(),
+                })
+            })
+            .collect();
+        json!({
+            "databases": databases,
+            "max_open": self.max_open,
+            "idle_timeout_secs": self.idle_timeout.map(|t| t.as_secs()),
+        })
     }
 
     /// Gracefully close all database connections.
     pub async fn close_all(self) {
-        for (_, afs) in self.dbs {
-            let _ = afs.close().await;
+        for (_, db) in self.dbs {
+            let _ = db.afs.close().await;
         }
     }
 }
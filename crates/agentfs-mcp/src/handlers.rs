@@ -1,7 +1,14 @@
 use agentfs_core::analytics::TokenRecord;
+use agentfs_core::backup::BackupStore;
+use agentfs_core::events::{EventFilter, Severity, TimeBound};
+use agentfs_core::filesystem::{EntryKind, GrepOptions, SearchFilter, TreeNode};
 use agentfs_core::AgentFS;
+use serde::Serialize;
 use serde_json::{json, Value};
 
+/// Bytes of each result reserved for the fields around a paged list.
+const ENVELOPE_BYTES: usize = 1024;
+
 /// Extract a required string parameter.
 fn get_str(args: &Value, key: &str) -> Result<String, String> {
     args.get(key)
@@ -20,13 +27,113 @@ fn get_opt_i64(args: &Value, key: &str) -> Option<i64> {
     args.get(key).and_then(|v| v.as_i64())
 }
 
+/// Extract an optional boolean parameter, defaulting to false.
+fn get_bool(args: &Value, key: &str) -> bool {
+    args.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// How many leading `items` fit in `budget` bytes of pretty-printed tool
+/// output. Always at least one, so paging makes progress.
+fn fit_to_budget<T: Serialize>(items: &[T], budget: usize) -> usize {
+    let budget = budget.saturating_sub(ENVELOPE_BYTES);
+    let mut used = 0;
+    for (i, item) in items.iter().enumerate() {
+        // Each item ends up nested two levels deep in the pretty output.
+        let text = serde_json::to_string_pretty(item).unwrap_or_default();
+        used += text.len() + 4 * text.lines().count() + 2;
+        if used > budget && i > 0 {
+            return i;
+        }
+    }
+    items.len()
+}
+
 // ── Filesystem handlers ────────────────────────────────────────────
 
-pub async fn handle_read_file(db: &AgentFS, args: &Value) -> Result<Value, String> {
+/// Default and maximum page size for `agentfs_read_file`, in bytes.
+const READ_DEFAULT_LIMIT: i64 = 64 * 1024;
+const READ_MAX_LIMIT: i64 = 1024 * 1024;
+
+pub async fn handle_read_file(db: &AgentFS, args: &Value, budget: usize) -> Result<Value, String> {
     let path = get_str(args, "path")?;
-    let data = db.fs.read_file(&path).await.map_err(|e| e.to_string())?;
+    let offset = get_opt_i64(args, "offset").unwrap_or(0).max(0) as u64;
+    let limit = get_opt_i64(args, "limit")
+        .unwrap_or(READ_DEFAULT_LIMIT)
+        .clamp(1, READ_MAX_LIMIT) as u64;
+    let limit = limit.min(budget.saturating_sub(ENVELOPE_BYTES).max(1) as u64);
+
+    let (mut data, total_size) = db
+        .fs
+        .read_range(&path, offset, limit)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if offset + (data.len() as u64) < total_size {
+        // Don't split a UTF-8 sequence across pages; the next page picks it up.
+        if let Err(e) = std::str::from_utf8(&data) {
+            if e.error_len().is_none() && e.valid_up_to() > 0 {
+                data.truncate(e.valid_up_to());
+            }
+        }
+    }
+
+    // The budget is for the pretty-printed result, where the text is
+    // escaped: fit the escaped text into what the other fields leave.
+    let skeleton = json!({
+        "content": "",
+        "offset": offset,
+        "length": total_size,
+        "total_size": total_size,
+        "truncated": true,
+        "next_offset": total_size,
+    });
+    let fields = serde_json::to_string_pretty(&skeleton).map_or(ENVELOPE_BYTES, |s| s.len());
+    data.truncate(escaped_fit(&data, budget.saturating_sub(fields)));
+    let end = offset + data.len() as u64;
+    let truncated = end < total_size;
+
     let text = String::from_utf8_lossy(&data);
-    Ok(json!({ "content": text }))
+    let mut result = json!({
+        "content": text,
+        "offset": offset,
+        "length": data.len(),
+        "total_size": total_size,
+        "truncated": truncated,
+    });
+    if truncated {
+        result["next_offset"] = json!(end);
+    }
+    Ok(result)
+}
+
+/// Length of the longest prefix of `data` whose lossy UTF-8 text, escaped
+/// as a JSON string, takes at most `budget` bytes.
+fn escaped_fit(data: &[u8], budget: usize) -> usize {
+    let mut used = 0;
+    let mut kept = 0;
+    for chunk in data.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            let cost = match c {
+                '"' | '\\' | '\n' | '\r' | '\t' | '\u{8}' | '\u{c}' => 2,
+                c if u32::from(c) < 0x20 => 6,
+                c => c.len_utf8(),
+            };
+            if used + cost > budget {
+                return kept;
+            }
+            used += cost;
+            kept += c.len_utf8();
+        }
+        if !chunk.invalid().is_empty() {
+            // Shown as one U+FFFD.
+            if used + 3 > budget {
+                return kept;
+            }
+            used += 3;
+            kept += chunk.invalid().len();
+        }
+    }
+    kept
 }
 
 pub async fn handle_write_file(db: &AgentFS, args: &Value) -> Result<Value, String> {
@@ -55,10 +162,31 @@ pub async fn handle_delete_file(db: &AgentFS, args: &Value) -> Result<Value, Str
     Ok(json!({ "deleted": path }))
 }
 
-pub async fn handle_list_dir(db: &AgentFS, args: &Value) -> Result<Value, String> {
+/// Default and maximum page size for `agentfs_list_dir` and `agentfs_tree`.
+const LIST_DEFAULT_LIMIT: i64 = 500;
+const LIST_MAX_LIMIT: i64 = 5000;
+
+fn list_limit(args: &Value) -> usize {
+    get_opt_i64(args, "limit")
+        .unwrap_or(LIST_DEFAULT_LIMIT)
+        .clamp(1, LIST_MAX_LIMIT) as usize
+}
+
+pub async fn handle_list_dir(db: &AgentFS, args: &Value, budget: usize) -> Result<Value, String> {
     let path = get_opt_str(args, "path").unwrap_or_else(|| "/".to_string());
-    let entries = db.fs.readdir(&path).await.map_err(|e| e.to_string())?;
-    let items: Vec<Value> = entries
+    let cursor = get_opt_str(args, "cursor");
+    let limit = list_limit(args);
+
+    // Fetch one extra entry to learn whether another page exists.
+    let mut entries = db
+        .fs
+        .readdir_page(&path, cursor.as_deref(), limit + 1)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut more = entries.len() > limit;
+    entries.truncate(limit);
+
+    let mut items: Vec<Value> = entries
         .iter()
         .map(|e| {
             let ftype = if (e.mode & 0o170000) == 0o040000 {
@@ -69,7 +197,17 @@ pub async fn handle_list_dir(db: &AgentFS, args: &Value) -> Result<Value, String
             json!({ "name": e.name, "ino": e.ino, "type": ftype })
         })
         .collect();
-    Ok(json!({ "entries": items }))
+    let fit = fit_to_budget(&items, budget);
+    if fit < items.len() {
+        items.truncate(fit);
+        entries.truncate(fit);
+        more = true;
+    }
+    let mut result = json!({ "entries": items });
+    if more {
+        result["next_cursor"] = json!(entries.last().map(|e| &e.name));
+    }
+    Ok(result)
 }
 
 pub async fn handle_mkdir(db: &AgentFS, args: &Value) -> Result<Value, String> {
@@ -84,10 +222,64 @@ pub async fn handle_stat(db: &AgentFS, args: &Value) -> Result<Value, String> {
     Ok(serde_json::to_value(&st).unwrap())
 }
 
-pub async fn handle_tree(db: &AgentFS, args: &Value) -> Result<Value, String> {
+pub async fn handle_tree(db: &AgentFS, args: &Value, budget: usize) -> Result<Value, String> {
     let path = get_opt_str(args, "path").unwrap_or_else(|| "/".to_string());
+    let max_depth = get_opt_i64(args, "max_depth").map(|d| d.max(1) as usize);
+    let offset: usize = match get_opt_str(args, "cursor") {
+        Some(c) => c.parse().map_err(|_| format!("invalid cursor: {c}"))?,
+        None => 0,
+    };
+    let limit = list_limit(args);
+
     let tree = db.fs.tree(&path).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::to_value(&tree).unwrap())
+    let root = path.trim_end_matches('/');
+    let mut entries = Vec::new();
+    for child in &tree.children {
+        flatten_tree(child, root, 1, max_depth, &mut entries);
+    }
+
+    let total = entries.len();
+    let mut page: Vec<Value> = entries.into_iter().skip(offset).take(limit).collect();
+    page.truncate(fit_to_budget(&page, budget));
+    let next = offset + page.len();
+    let mut result = json!({
+        "path": path,
+        "size": tree.total_size,
+        "total": total,
+        "entries": page,
+    });
+    if next < total {
+        result["next_cursor"] = json!(next.to_string());
+    }
+    Ok(result)
+}
+
+/// Append `node` and its descendants, depth first, as flat entries.
+fn flatten_tree(
+    node: &TreeNode,
+    parent: &str,
+    depth: usize,
+    max_depth: Option<usize>,
+    out: &mut Vec<Value>,
+) {
+    let path = format!("{parent}/{}", node.name);
+    let is_dir = node.stat.is_dir();
+    let mut entry = json!({
+        "path": path,
+        "type": if is_dir { "dir" } else { "file" },
+        "size": node.total_size,
+        "depth": depth,
+    });
+    let descend = max_depth.is_none_or(|max| depth < max);
+    if is_dir && !descend && !node.children.is_empty() {
+        entry["hidden_children"] = json!(node.children.len());
+    }
+    out.push(entry);
+    if descend {
+        for child in &node.children {
+            flatten_tree(child, &path, depth + 1, max_depth, out);
+        }
+    }
 }
 
 pub async fn handle_rename(db: &AgentFS, args: &Value) -> Result<Value, String> {
@@ -103,10 +295,58 @@ pub async fn handle_remove_tree(db: &AgentFS, args: &Value) -> Result<Value, Str
     Ok(json!({ "removed": path }))
 }
 
-pub async fn handle_search(db: &AgentFS, args: &Value) -> Result<Value, String> {
-    let pattern = get_str(args, "pattern")?;
-    let results = db.fs.search(&pattern).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::to_value(&results).unwrap())
+pub async fn handle_search(db: &AgentFS, args: &Value, budget: usize) -> Result<Value, String> {
+    let kind = match get_opt_str(args, "type").as_deref() {
+        None => None,
+        Some("file") => Some(EntryKind::File),
+        Some("dir") => Some(EntryKind::Dir),
+        Some(other) => return Err(format!("invalid type: {other} (expected file or dir)")),
+    };
+    let filter = SearchFilter {
+        name: get_opt_str(args, "pattern"),
+        path: get_opt_str(args, "path"),
+        kind,
+        min_size: get_opt_i64(args, "min_size"),
+        max_size: get_opt_i64(args, "max_size"),
+    };
+    let offset: usize = match get_opt_str(args, "cursor") {
+        Some(c) => c.parse().map_err(|_| format!("invalid cursor: {c}"))?,
+        None => 0,
+    };
+    let limit = get_opt_i64(args, "limit").unwrap_or(200).max(1) as usize;
+
+    let results = db.fs.find(&filter).await.map_err(|e| e.to_string())?;
+    let total = results.len();
+    let mut page: Vec<_> = results.into_iter().skip(offset).take(limit).collect();
+    page.truncate(fit_to_budget(&page, budget));
+    let next = offset + page.len();
+    let mut result = json!({
+        "results": page,
+        "total": total,
+        "truncated": next < total,
+    });
+    if next < total {
+        result["next_cursor"] = json!(next.to_string());
+    }
+    Ok(result)
+}
+
+pub async fn handle_grep(db: &AgentFS, args: &Value, budget: usize) -> Result<Value, String> {
+    let opts = GrepOptions {
+        pattern: get_str(args, "pattern")?,
+        path: get_opt_str(args, "path"),
+        ignore_case: get_bool(args, "ignore_case"),
+        fixed_strings: get_bool(args, "fixed_strings"),
+        max_matches: get_opt_i64(args, "max_results").unwrap_or(100).max(1) as usize,
+        ..Default::default()
+    };
+    let mut result = db.fs.grep(&opts).await.map_err(|e| e.to_string())?;
+    let fit = fit_to_budget(&result.matches, budget);
+    if fit < result.matches.len() {
+        result.matches.truncate(fit);
+        result.truncated = true;
+    }
+    Ok(serde_json::to_value(&result).unwrap())
 }
 
 // ── Key-Value handlers ─────────────────────────────────────────────
@@ -120,20 +360,75 @@ pub async fn handle_kv_get(db: &AgentFS, args: &Value) -> Result<Value, String>
 pub async fn handle_kv_set(db: &AgentFS, args: &Value) -> Result<Value, String> {
     let key = get_str(args, "key")?;
     let value = get_str(args, "value")?;
+    if agentfs_core::kvstore::is_protected(&key) {
+        return Err(format!("'{key}' holds the agent's tool approvals or configuration and can't be set over MCP"));
+    }
     db.kv.set(&key, &value).await.map_err(|e| e.to_string())?;
     Ok(json!({ "set": key }))
 }
 
 pub async fn handle_kv_delete(db: &AgentFS, args: &Value) -> Result<Value, String> {
     let key = get_str(args, "key")?;
+    if agentfs_core::kvstore::is_protected(&key) {
+        return Err(format!("'{key}' holds the agent's tool approvals or configuration and can't be deleted over MCP"));
+    }
     db.kv.delete(&key).await.map_err(|e| e.to_string())?;
     Ok(json!({ "deleted": key }))
 }
 
-pub async fn handle_kv_list(db: &AgentFS, args: &Value) -> Result<Value, String> {
+pub async fn handle_kv_list(db: &AgentFS, args: &Value, budget: usize) -> Result<Value, String> {
     let prefix = get_opt_str(args, "prefix").unwrap_or_default();
-    let entries = db.kv.list_prefix(&prefix).await.map_err(|e| e.to_string())?;
-    Ok(serde_json::to_value(&entries).unwrap())
+    let cursor = get_opt_str(args, "cursor");
+    let limit = get_opt_i64(args, "limit").unwrap_or(100).clamp(1, 1000) as usize;
+
+    // Fetch one extra row to learn whether another page exists.
+    let mut entries = db
+        .kv
+        .list_page(&prefix, cursor.as_deref(), limit + 1)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut more = entries.len() > limit;
+    entries.truncate(limit);
+    let keys_only = get_bool(args, "keys_only");
+    let fit = if keys_only {
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        fit_to_budget(&keys, budget)
+    } else {
+        fit_to_budget(&entries, budget)
+    };
+    if fit < entries.len() {
+        entries.truncate(fit);
+        more = true;
+    }
+
+    let mut result = if keys_only {
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        json!({ "keys": keys })
+    } else {
+        json!({ "entries": entries })
+    };
+    if more {
+        result["next_cursor"] = json!(entries.last().map(|e| &e.key));
+    }
+    Ok(result)
+}
+
+pub async fn handle_kv_delete_prefix(db: &AgentFS, args: &Value) -> Result<Value, String> {
+    let prefix = get_str(args, "prefix")?;
+    if prefix.is_empty() {
+        return Err("prefix must not be empty".to_string());
+    }
+    if agentfs_core::kvstore::reaches_protected(&prefix) {
+        return Err(format!(
+            "'{prefix}' reaches keys holding the agent's tool approvals or configuration, which can't be deleted over MCP"
+        ));
+    }
+    let deleted = db
+        .kv
+        .delete_prefix(&prefix)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(json!({ "prefix": prefix, "deleted": deleted }))
 }
 
 // ── Platform handlers ──────────────────────────────────────────────
@@ -155,6 +450,7 @@ pub async fn handle_record_usage(db: &AgentFS, args: &Value) -> Result<Value, St
         cache_write_tokens: args.get("cache_write_tokens").and_then(|v| v.as_i64()).unwrap_or(0),
         cost_microcents: args.get("cost_microcents").and_then(|v| v.as_i64()).unwrap_or(0),
         recorded_at: None,
+        purpose: get_opt_str(args, "purpose"),
     };
     let id = db.analytics.record_usage(record).await.map_err(|e| e.to_string())?;
     Ok(json!({ "recorded_id": id }))
@@ -188,28 +484,247 @@ pub async fn handle_session_end(db: &AgentFS, args: &Value) -> Result<Value, Str
     Ok(json!({ "ended": session_id, "status": status }))
 }
 
+pub async fn handle_sessions_list(db: &AgentFS, args: &Value) -> Result<Value, String> {
+    let limit = get_opt_i64(args, "limit").unwrap_or(20);
+    let sessions = match get_opt_str(args, "status") {
+        Some(status) if status == "active" => {
+            let mut active = db.sessions.list_active().await.map_err(|e| e.to_string())?;
+            active.truncate(limit.max(0) as usize);
+            active
+        }
+        Some(status) => {
+            // Filter a wider window so older finished sessions still show up.
+            let mut recent = db
+                .sessions
+                .list_recent(limit.saturating_mul(10).max(100))
+                .await
+                .map_err(|e| e.to_string())?;
+            recent.retain(|s| s.status == status);
+            recent.truncate(limit.max(0) as usize);
+            recent
+        }
+        None => db.sessions.list_recent(limit).await.map_err(|e| e.to_string())?,
+    };
+    Ok(json!({ "sessions": sessions }))
+}
+
+pub async fn handle_sessions_get(db: &AgentFS, args: &Value) -> Result<Value, String> {
+    let session_id = get_str(args, "session_id")?;
+    let session = db.sessions.get(&session_id).await.map_err(|e| e.to_string())?;
+    let cost = db
+        .analytics
+        .by_session()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|c| c.session_id == session_id);
+    Ok(json!({ "session": session, "usage": cost }))
+}
+
+pub async fn handle_analytics_summary(db: &AgentFS, args: &Value) -> Result<Value, String> {
+    let summary = match get_opt_str(args, "since") {
+        Some(since) => db.analytics.summary_since(&since).await,
+        None => db.analytics.summary().await,
+    }
+    .map_err(|e| e.to_string())?;
+    let by_model = db.analytics.by_model().await.map_err(|e| e.to_string())?;
+    let by_purpose = db.analytics.by_purpose().await.map_err(|e| e.to_string())?;
+    Ok(json!({ "summary": summary, "by_model": by_model, "by_purpose": by_purpose }))
+}
+
+pub async fn handle_events_query(db: &AgentFS, args: &Value) -> Result<Value, String> {
+    let time = |key: &str| -> Result<Option<TimeBound>, String> {
+        get_opt_str(args, key).map(|s| s.parse()).transpose()
+    };
+    let filter = EventFilter {
+        event_type: get_opt_str(args, "type"),
+        session_id: get_opt_str(args, "session_id"),
+        min_severity: get_opt_str(args, "severity")
+            .map(|s| s.parse::<Severity>())
+            .transpose()?,
+        since: time("since")?,
+        until: time("until")?,
+        after_id: get_opt_i64(args, "after_id"),
+        before_id: None,
+    };
+    let limit = get_opt_i64(args, "limit").unwrap_or(50);
+    let events = db
+        .events
+        .query(&filter, Some(limit))
+        .await
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<Value> = events
+        .iter()
+        .map(|e| {
+            let mut row = serde_json::to_value(e).unwrap();
+            row["severity"] = json!(e.severity());
+            row
+        })
+        .collect();
+    Ok(json!({ "events": rows }))
+}
+
+// ── Admin handlers ─────────────────────────────────────────────────
+
+pub async fn handle_snapshot(db: &AgentFS, args: &Value) -> Result<Value, String> {
+    let store = BackupStore::open(BackupStore::default_dir(&db.config().db_path))
+        .map_err(|e| e.to_string())?;
+    let entry = store
+        .create(db, get_bool(args, "incremental"))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(json!({
+        "backup": entry,
+        "dir": store.dir().display().to_string(),
+    }))
+}
+
+pub async fn handle_gc(db: &AgentFS, _args: &Value) -> Result<Value, String> {
+    let report = db.gc().await.map_err(|e| e.to_string())?;
+    db.checkpoint().await.map_err(|e| e.to_string())?;
+    let total = report.total();
+    let mut result = serde_json::to_value(&report).unwrap();
+    result["total"] = json!(total);
+    Ok(result)
+}
+
+pub async fn handle_integrity_check(db: &AgentFS, args: &Value) -> Result<Value, String> {
+    let (mut result, clean) = match get_opt_str(args, "path") {
+        Some(path) => {
+            let report = db.verify_file(&path).await.map_err(|e| e.to_string())?;
+            (serde_json::to_value(&report).unwrap(), report.is_clean())
+        }
+        None => {
+            let report = db.integrity_check().await.map_err(|e| e.to_string())?;
+            (serde_json::to_value(&report).unwrap(), report.is_clean())
+        }
+    };
+    result["clean"] = json!(clean);
+    Ok(result)
+}
+
 /// Dispatch a tool call to the appropriate handler.
-pub async fn dispatch(tool_name: &str, db: &AgentFS, args: &Value) -> Result<Value, String> {
+///
+/// Handlers that return lists or file contents page them to fit in
+/// `budget` bytes of output.
+pub async fn dispatch(
+    tool_name: &str,
+    db: &AgentFS,
+    args: &Value,
+    budget: usize,
+) -> Result<Value, String> {
     match tool_name {
-        "agentfs_read_file" => handle_read_file(db, args).await,
+        "agentfs_read_file" => handle_read_file(db, args, budget).await,
         "agentfs_write_file" => handle_write_file(db, args).await,
         "agentfs_append_file" => handle_append_file(db, args).await,
         "agentfs_delete_file" => handle_delete_file(db, args).await,
-        "agentfs_list_dir" => handle_list_dir(db, args).await,
+        "agentfs_list_dir" => handle_list_dir(db, args, budget).await,
         "agentfs_mkdir" => handle_mkdir(db, args).await,
         "agentfs_stat" => handle_stat(db, args).await,
-        "agentfs_tree" => handle_tree(db, args).await,
+        "agentfs_tree" => handle_tree(db, args, budget).await,
         "agentfs_rename" => handle_rename(db, args).await,
         "agentfs_remove_tree" => handle_remove_tree(db, args).await,
-        "agentfs_search" => handle_search(db, args).await,
+        "agentfs_search" => handle_search(db, args, budget).await,
+        "agentfs_grep" => handle_grep(db, args, budget).await,
         "agentfs_kv_get" => handle_kv_get(db, args).await,
         "agentfs_kv_set" => handle_kv_set(db, args).await,
         "agentfs_kv_delete" => handle_kv_delete(db, args).await,
-        "agentfs_kv_list" => handle_kv_list(db, args).await,
+        "agentfs_kv_list" => handle_kv_list(db, args, budget).await,
+        "agentfs_kv_delete_prefix" => handle_kv_delete_prefix(db, args).await,
         "agentfs_info" => handle_info(db, args).await,
         "agentfs_record_usage" => handle_record_usage(db, args).await,
         "agentfs_session_start" => handle_session_start(db, args).await,
         "agentfs_session_end" => handle_session_end(db, args).await,
+        "agentfs_sessions_list" => handle_sessions_list(db, args).await,
+        "agentfs_sessions_get" => handle_sessions_get(db, args).await,
+        "agentfs_analytics_summary" => handle_analytics_summary(db, args).await,
+        "agentfs_events_query" => handle_events_query(db, args).await,
+        "agentfs_snapshot" => handle_snapshot(db, args).await,
+        "agentfs_gc" => handle_gc(db, args).await,
+        "agentfs_integrity_check" => handle_integrity_check(db, args).await,
         _ => Err(format!("unknown tool: {tool_name}")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::config::AgentFSConfig;
+
+    #[test]
+    fn test_escaped_fit() {
+        assert_eq!(escaped_fit(b"abc", 10), 3);
+        assert_eq!(escaped_fit(b"a\"b", 3), 2);
+        assert_eq!(escaped_fit(b"\x01x", 6), 1);
+        assert_eq!(escaped_fit("é".as_bytes(), 1), 0);
+        assert_eq!(escaped_fit(b"\xffa", 4), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_file_pages_fit_encoded_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        // Text that grows when escaped: quotes, newlines, control bytes,
+        // multi-byte characters.
+        let content: Vec<u8> = (0..4000)
+            .flat_map(|i| match i % 4 {
+                0 => b"\"q\"\n".to_vec(),
+                1 => b"\x01\x02".to_vec(),
+                2 => "\u{e9}\u{1F600}".as_bytes().to_vec(),
+                _ => b"\\x".to_vec(),
+            })
+            .collect();
+        db.fs.write_file("/f.txt", &content).await.unwrap();
+
+        let budget = 4096;
+        let mut offset = 0u64;
+        let mut read = Vec::new();
+        loop {
+            let args = json!({ "path": "/f.txt", "offset": offset });
+            let page = handle_read_file(&db, &args, budget).await.unwrap();
+            assert!(serde_json::to_string_pretty(&page).unwrap().len() <= budget);
+            let length = page["length"].as_u64().unwrap();
+            assert!(length > 0);
+            read.extend_from_slice(page["content"].as_str().unwrap().as_bytes());
+            if !page["truncated"].as_bool().unwrap() {
+                break;
+            }
+            assert_eq!(page["next_offset"].as_u64().unwrap(), offset + length);
+            offset += length;
+        }
+        assert_eq!(read, content);
+    }
+
+    #[tokio::test]
+    async fn test_kv_refuses_protected_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = AgentFS::create(
+            AgentFSConfig::builder(dir.path().join("test.db"))
+                .checkpoint_interval_secs(0)
+                .build(),
+        )
+        .await
+        .unwrap();
+        db.kv.set("config:mcp:git", "{}").await.unwrap();
+        db.kv.set("permission:always:bash", "true").await.unwrap();
+        db.kv.set("notes:a", "1").await.unwrap();
+
+        assert!(handle_kv_set(&db, &json!({ "key": "config:mcp:evil", "value": "{}" })).await.is_err());
+        assert!(handle_kv_delete(&db, &json!({ "key": "config:mcp:git" })).await.is_err());
+        assert!(handle_kv_delete(&db, &json!({ "key": "permission:always:bash" })).await.is_err());
+        for prefix in ["config:", "config:mcp:", "perm", "c"] {
+            assert!(handle_kv_delete_prefix(&db, &json!({ "prefix": prefix })).await.is_err());
+        }
+        assert!(db.kv.get("config:mcp:git").await.is_ok());
+        assert!(db.kv.get("permission:always:bash").await.is_ok());
+
+        handle_kv_delete_prefix(&db, &json!({ "prefix": "notes:" })).await.unwrap();
+        assert!(db.kv.get("notes:a").await.is_err());
+    }
+}
@@ -0,0 +1,349 @@
+//! Streamable HTTP transport.
+//!
+//! Clients POST JSON-RPC messages (single or batched) to `/mcp` and get the
+//! responses back as `application/json`. `initialize` starts a session whose
+//! ID is returned in the `Mcp-Session-Id` header; every later request must
+//! carry it, and `DELETE /mcp` ends it. A session unused for 30 minutes
+//! expires, and `initialize` is refused with 503 while 1024 sessions are
+//! live. The server never initiates messages, so the optional GET event
+//! stream is answered with 405. A request naming an unsupported revision
+//! in `MCP-Protocol-Version` is rejected with 400.
+//!
+//! Every request must carry `Authorization: Bearer <token>`. Requests from
+//! a browser page are only served when its origin is on localhost, and on a
+//! loopback address only for a localhost `Host` (DNS rebinding).
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Bytes,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::info;
+
+use crate::db_manager::DbManager;
+use crate::protocol::{
+    JsonRpcRequest, JsonRpcResponse, Session, INVALID_REQUEST, PARSE_ERROR, PROTOCOL_VERSIONS,
+};
+
+const MCP_PATH: &str = "/mcp";
+const SESSION_HEADER: &str = "mcp-session-id";
+const VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// Most sessions live at once.
+const MAX_SESSIONS: usize = 1024;
+/// How long a session may go unused before it expires.
+const SESSION_IDLE: Duration = Duration::from_secs(30 * 60);
+
+struct HttpState {
+    db_manager: Mutex<DbManager>,
+    sessions: Mutex<Sessions>,
+    /// Bearer token every request must present.
+    token: String,
+    /// Only answer requests naming a localhost `Host`. Set when bound to
+    /// loopback, where any other name means a rebound DNS entry.
+    local_hosts_only: bool,
+}
+
+/// Serve MCP over HTTP on `addr` until Ctrl+C, to clients presenting `token`.
+pub async fn serve(addr: SocketAddr, db_manager: DbManager, token: String) -> std::io::Result<()> {
+    let state = Arc::new(HttpState {
+        db_manager: Mutex::new(db_manager),
+        sessions: Mutex::new(Sessions::new(MAX_SESSIONS, SESSION_IDLE)),
+        token,
+        local_hosts_only: addr.ip().is_loopback(),
+    });
+
+    if let Some(every) = state.db_manager.lock().await.sweep_interval() {
+        let state = Arc::downgrade(&state);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                let Some(state) = state.upgrade() else { break };
+                state.db_manager.lock().await.close_idle().await;
+            }
+        });
+    }
+
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(|origin, _| {
+            origin.to_str().is_ok_and(local_origin)
+        }))
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static(SESSION_HEADER),
+            HeaderName::from_static(VERSION_HEADER),
+        ])
+        .expose_headers([HeaderName::from_static(SESSION_HEADER)]);
+
+    let app = Router::new()
+        .route(MCP_PATH, post(handle_post).get(handle_get).delete(handle_delete))
+        .layer(middleware::from_fn_with_state(state.clone(), guard))
+        .layer(cors)
+        .with_state(state.clone());
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!(%addr, "MCP HTTP transport listening");
+    eprintln!("agentfs-mcp listening on http://{addr}{MCP_PATH}");
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    if let Ok(state) = Arc::try_unwrap(state) {
+        state.db_manager.into_inner().close_all().await;
+    }
+    Ok(())
+}
+
+async fn handle_post(State(state): State<Arc<HttpState>>, headers: HeaderMap, body: Bytes) -> Response {
+    if let Some(version) = headers.get(VERSION_HEADER) {
+        let supported = version
+            .to_str()
+            .is_ok_and(|v| PROTOCOL_VERSIONS.contains(&v));
+        if !supported {
+            return (StatusCode::BAD_REQUEST, "unsupported MCP-Protocol-Version").into_response();
+        }
+    }
+
+    let parsed: Value = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(e) => {
+            let resp = JsonRpcResponse::error(None, PARSE_ERROR, format!("parse error: {e}"));
+            return (StatusCode::BAD_REQUEST, json_body(&resp)).into_response();
+        }
+    };
+    let batch = parsed.is_array();
+    let messages = match parsed {
+        Value::Array(items) => items,
+        single => vec![single],
+    };
+
+    let initializing = messages
+        .iter()
+        .any(|m| m.get("method").and_then(|v| v.as_str()) == Some("initialize"));
+    let mut sessions = state.sessions.lock().await;
+    let mut fresh = Session::default();
+    let (new_session, session) = if initializing {
+        if messages.len() > 1 {
+            let resp = JsonRpcResponse::error(None, INVALID_REQUEST, "initialize must not be batched");
+            return (StatusCode::BAD_REQUEST, json_body(&resp)).into_response();
+        }
+        if !sessions.has_room() {
+            return (StatusCode::SERVICE_UNAVAILABLE, "too many sessions").into_response();
+        }
+        (Some(uuid::Uuid::new_v4().to_string()), &mut fresh)
+    } else {
+        let Some(id) = session_id(&headers) else {
+            return (StatusCode::BAD_REQUEST, "missing Mcp-Session-Id header").into_response();
+        };
+        match sessions.get_mut(id) {
+            Some(session) => (None, session),
+            None => return (StatusCode::NOT_FOUND, "unknown or expired session").into_response(),
+        }
+    };
+
+    let mut responses = Vec::new();
+    {
+        let mut db_manager = state.db_manager.lock().await;
+        for message in messages {
+            // Responses to server-initiated requests: we never send any.
+            if message.get("method").is_none() {
+                continue;
+            }
+            let id = message.get("id").cloned();
+            match serde_json::from_value::<JsonRpcRequest>(message) {
+                Ok(request) => {
+                    if let Some(resp) = crate::handle_message(request, session, &mut db_manager).await {
+                        responses.push(resp);
+                    }
+                }
+                Err(e) => responses.push(JsonRpcResponse::error(
+                    id,
+                    INVALID_REQUEST,
+                    format!("invalid request: {e}"),
+                )),
+            }
+        }
+    }
+
+    // Only notifications: acknowledge without a body.
+    if responses.is_empty() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let body = if batch {
+        serde_json::to_string(&responses)
+    } else {
+        serde_json::to_string(&responses[0])
+    }
+    .unwrap_or_default();
+    let mut resp = ([(header::CONTENT_TYPE, "application/json")], body).into_response();
+
+    if let Some(id) = new_session {
+        // Only keep sessions whose initialize succeeded.
+        if responses[0].error.is_none() {
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                resp.headers_mut().insert(SESSION_HEADER, value);
+            }
+            sessions.insert(id, fresh);
+        }
+    }
+    resp
+}
+
+async fn handle_get() -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, "POST, DELETE")]).into_response()
+}
+
+async fn handle_delete(State(state): State<Arc<HttpState>>, headers: HeaderMap) -> Response {
+    match session_id(&headers) {
+        None => (StatusCode::BAD_REQUEST, "missing Mcp-Session-Id header").into_response(),
+        Some(id) if state.sessions.lock().await.remove(id) => StatusCode::OK.into_response(),
+        Some(_) => (StatusCode::NOT_FOUND, "unknown or expired session").into_response(),
+    }
+}
+
+/// Live sessions by ID, each with when it was last used.
+struct Sessions {
+    live: HashMap<String, (Session, Instant)>,
+    max: usize,
+    idle: Duration,
+}
+
+impl Sessions {
+    fn new(max: usize, idle: Duration) -> Self {
+        Self { live: HashMap::new(), max, idle }
+    }
+
+    /// Session `id`, marked as used; `None` if unknown or expired.
+    fn get_mut(&mut self, id: &str) -> Option<&mut Session> {
+        self.expire();
+        let (session, last_used) = self.live.get_mut(id)?;
+        *last_used = Instant::now();
+        Some(session)
+    }
+
+    /// Whether another session may start.
+    fn has_room(&mut self) -> bool {
+        self.expire();
+        self.live.len() < self.max
+    }
+
+    fn insert(&mut self, id: String, session: Session) {
+        self.live.insert(id, (session, Instant::now()));
+    }
+
+    /// End session `id`. Returns whether it was live.
+    fn remove(&mut self, id: &str) -> bool {
+        self.expire();
+        self.live.remove(id).is_some()
+    }
+
+    fn expire(&mut self) {
+        let idle = self.idle;
+        self.live.retain(|_, (_, last_used)| last_used.elapsed() < idle);
+    }
+}
+
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SESSION_HEADER).and_then(|v| v.to_str().ok())
+}
+
+/// Turn away requests from other sites' pages, for other hosts when on
+/// loopback, and without the bearer token.
+async fn guard(State(state): State<Arc<HttpState>>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    if state.local_hosts_only {
+        let host = headers.get(header::HOST).and_then(|v| v.to_str().ok()).unwrap_or("");
+        if !local_host(host) {
+            return (StatusCode::FORBIDDEN, "host not allowed").into_response();
+        }
+    }
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        if !origin.to_str().is_ok_and(local_origin) {
+            return (StatusCode::FORBIDDEN, "origin not allowed").into_response();
+        }
+    }
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+    if !same(presented.as_bytes(), state.token.as_bytes()) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "missing or wrong bearer token",
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Constant-time comparison, so the token can't be guessed byte by byte.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Whether an `Origin` header names a page served from this machine.
+fn local_origin(origin: &str) -> bool {
+    origin
+        .split_once("://")
+        .is_some_and(|(scheme, host)| matches!(scheme, "http" | "https") && local_host(host))
+}
+
+/// Whether a `host[:port]` names this machine.
+fn local_host(host: &str) -> bool {
+    let host = match host.rsplit_once(':') {
+        Some((h, port)) if port.chars().all(|c| c.is_ascii_digit()) => h,
+        _ => host,
+    };
+    host.eq_ignore_ascii_case("localhost") || matches!(host, "127.0.0.1" | "[::1]")
+}
+
+fn json_body(resp: &JsonRpcResponse) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::to_string(resp).unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_are_capped_and_expire() {
+        let mut sessions = Sessions::new(2, Duration::from_secs(60));
+        sessions.insert("a".into(), Session::default());
+        assert!(sessions.has_room());
+        sessions.insert("b".into(), Session::default());
+        assert!(!sessions.has_room());
+        assert!(sessions.remove("a"));
+        assert!(!sessions.remove("a"));
+        assert!(sessions.get_mut("a").is_none());
+        assert!(sessions.has_room());
+
+        let mut sessions = Sessions::new(2, Duration::ZERO);
+        sessions.insert("a".into(), Session::default());
+        assert!(sessions.get_mut("a").is_none());
+        assert!(sessions.has_room());
+    }
+}
@@ -1,50 +1,155 @@
 mod db_manager;
 mod handlers;
+mod http;
+mod prompts;
 mod protocol;
+mod resources;
 mod tools;
 
-use std::io::BufRead;
+use std::net::SocketAddr;
+use std::time::Duration;
 
+use clap::Parser;
 use serde_json::{json, Value};
+use tokio::io::AsyncBufReadExt;
 use tracing::debug;
 
 use db_manager::DbManager;
-use protocol::{JsonRpcRequest, JsonRpcResponse, INTERNAL_ERROR, METHOD_NOT_FOUND, PARSE_ERROR};
+use protocol::{
+    negotiate_version, JsonRpcRequest, JsonRpcResponse, Session, INVALID_PARAMS, METHOD_NOT_FOUND,
+    PARSE_ERROR, STRUCTURED_OUTPUT_SINCE,
+};
 
-const PROTOCOL_VERSION: &str = "2024-11-05";
 const SERVER_NAME: &str = "agentfs-mcp";
 const SERVER_VERSION: &str = "0.1.0";
 
-fn handle_initialize(id: Option<Value>) -> JsonRpcResponse {
+/// Launch options.
+#[derive(Parser)]
+#[command(name = "agentfs-mcp", about = "MCP server for AgentFS (stdio by default)")]
+struct Args {
+    /// Serve the streamable HTTP transport on this address instead of stdio
+    #[arg(long, value_name = "ADDR")]
+    http: Option<SocketAddr>,
+
+    /// Bearer token HTTP clients must send; a random one is printed if unset
+    #[arg(long, env = "AGENTFS_MCP_TOKEN", value_name = "TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Default database for tool calls that omit `db`
+    #[arg(long, env = "AGENTFS_DB", value_name = "PATH")]
+    db: Option<String>,
+
+    /// Enable admin tools: snapshot, gc, integrity check and database listing
+    #[arg(long, env = "AGENTFS_MCP_ALLOW_ADMIN")]
+    allow_admin: bool,
+
+    /// Most databases kept open at once; the least recently used is closed first
+    #[arg(long, default_value_t = db_manager::DEFAULT_MAX_OPEN, value_name = "N")]
+    max_open_dbs: usize,
+
+    /// Close databases unused for this many seconds (0 = never)
+    #[arg(long, default_value_t = db_manager::DEFAULT_IDLE_TIMEOUT.as_secs(), value_name = "SECS")]
+    idle_timeout: u64,
+
+    /// Largest tool result in bytes; bigger ones are paged or truncated
+    #[arg(long, default_value_t = db_manager::DEFAULT_MAX_RESULT_BYTES, value_name = "BYTES")]
+    max_result_bytes: usize,
+
+    /// Databases to keep open, exposing their files as resources
+    #[arg(value_name = "DB")]
+    dbs: Vec<String>,
+}
+
+fn handle_initialize(id: Option<Value>, params: &Value, session: &mut Session) -> JsonRpcResponse {
+    let requested = params.get("protocolVersion").and_then(|v| v.as_str());
+    session.protocol_version = negotiate_version(requested);
+
+    let mut server_info = json!({
+        "name": SERVER_NAME,
+        "version": SERVER_VERSION
+    });
+    if session.supports(STRUCTURED_OUTPUT_SINCE) {
+        server_info["title"] = json!("AgentFS");
+    }
     JsonRpcResponse::success(
         id,
         json!({
-            "protocolVersion": PROTOCOL_VERSION,
+            "protocolVersion": session.protocol_version,
             "capabilities": {
-                "tools": {}
+                "tools": { "listChanged": false },
+                "resources": { "subscribe": false, "listChanged": false },
+                "prompts": { "listChanged": false }
             },
-            "serverInfo": {
-                "name": SERVER_NAME,
-                "version": SERVER_VERSION
-            }
+            "serverInfo": server_info
         }),
     )
 }
 
-fn handle_tools_list(id: Option<Value>) -> JsonRpcResponse {
-    JsonRpcResponse::success(id, json!({ "tools": tools::tool_definitions() }))
+fn handle_tools_list(id: Option<Value>, session: &Session, db_manager: &DbManager) -> JsonRpcResponse {
+    let mut tools = tools::tool_definitions(db_manager.default_db(), db_manager.allow_admin());
+    tools::shape_for(&mut tools, session);
+    JsonRpcResponse::success(id, json!({ "tools": tools }))
+}
+
+async fn handle_resources_list(
+    id: Option<Value>,
+    params: &Value,
+    db_manager: &DbManager,
+) -> JsonRpcResponse {
+    let cursor = params.get("cursor").and_then(|v| v.as_str());
+    match resources::list(db_manager, cursor).await {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err((code, message)) => JsonRpcResponse::error(id, code, message),
+    }
+}
+
+async fn handle_resources_read(
+    id: Option<Value>,
+    params: &Value,
+    db_manager: &DbManager,
+) -> JsonRpcResponse {
+    let uri = match params.get("uri").and_then(|v| v.as_str()) {
+        Some(uri) => uri,
+        None => return JsonRpcResponse::error(id, INVALID_PARAMS, "missing resource uri"),
+    };
+    match resources::read(db_manager, uri).await {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err((code, message)) => JsonRpcResponse::error(id, code, message),
+    }
+}
+
+async fn handle_prompts_list(id: Option<Value>, db_manager: &DbManager) -> JsonRpcResponse {
+    match prompts::list(db_manager).await {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err((code, message)) => JsonRpcResponse::error(id, code, message),
+    }
+}
+
+async fn handle_prompts_get(
+    id: Option<Value>,
+    params: &Value,
+    db_manager: &DbManager,
+) -> JsonRpcResponse {
+    let name = match params.get("name").and_then(|v| v.as_str()) {
+        Some(name) => name,
+        None => return JsonRpcResponse::error(id, INVALID_PARAMS, "missing prompt name"),
+    };
+    let args = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+    match prompts::get(db_manager, name, &args).await {
+        Ok(result) => JsonRpcResponse::success(id, result),
+        Err((code, message)) => JsonRpcResponse::error(id, code, message),
+    }
 }
 
 async fn handle_tools_call(
     id: Option<Value>,
     params: &Value,
+    session: &Session,
     db_manager: &mut DbManager,
 ) -> JsonRpcResponse {
     let tool_name = match params.get("name").and_then(|v| v.as_str()) {
-        Some(name) => name.to_string(),
-        None => {
-            return JsonRpcResponse::error(id, INTERNAL_ERROR, "missing tool name");
-        }
+        Some(name) => name,
+        None => return JsonRpcResponse::error(id, INVALID_PARAMS, "missing tool name"),
     };
 
     let args = params
@@ -52,59 +157,102 @@ async fn handle_tools_call(
         .cloned()
         .unwrap_or_else(|| json!({}));
 
+    let result = call_tool(tool_name, &args, db_manager).await;
+    tool_result(
+        id,
+        result,
+        session.supports(STRUCTURED_OUTPUT_SINCE),
+        db_manager.max_result_bytes(),
+    )
+}
+
+async fn call_tool(tool_name: &str, args: &Value, db_manager: &mut DbManager) -> Result<Value, String> {
     // Special case: agentfs_init creates a new database
     if tool_name == "agentfs_init" {
-        let path = match args.get("path").and_then(|v| v.as_str()) {
-            Some(p) => p.to_string(),
-            None => {
-                return tool_result(id, Err("missing required parameter: path".to_string()));
-            }
-        };
-        match db_manager.create(&path).await {
-            Ok(_) => {
-                return tool_result(id, Ok(json!({ "created": path })));
-            }
-            Err(e) => {
-                return tool_result(id, Err(e));
-            }
-        }
+        let path = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or("missing required parameter: path")?;
+        db_manager.create(path).await?;
+        return Ok(json!({ "created": path }));
     }
 
-    // All other tools need a 'db' parameter
-    let db_path = match args.get("db").and_then(|v| v.as_str()) {
-        Some(p) => p.to_string(),
-        None => {
-            return tool_result(id, Err("missing required parameter: db".to_string()));
-        }
-    };
+    if tools::is_admin(tool_name) && !db_manager.allow_admin() {
+        return Err(format!(
+            "{tool_name} is disabled; start agentfs-mcp with --allow-admin to enable it"
+        ));
+    }
 
-    let db = match db_manager.get_or_open(&db_path).await {
-        Ok(db) => db,
-        Err(e) => {
-            return tool_result(id, Err(e));
-        }
-    };
+    if tool_name == "agentfs_databases_list" {
+        return Ok(db_manager.describe());
+    }
 
-    let result = handlers::dispatch(&tool_name, db, &args).await;
-    tool_result(id, result)
+    // All other tools need a 'db' parameter, unless a default was configured
+    let db_path = args
+        .get("db")
+        .and_then(|v| v.as_str())
+        .or(db_manager.default_db())
+        .ok_or("missing required parameter: db")?
+        .to_string();
+
+    let budget = db_manager.max_result_bytes();
+    let db = db_manager.get_or_open(&db_path).await?;
+    handlers::dispatch(tool_name, db, args, budget).await
 }
 
 /// Wrap a tool result in an MCP-style response (content array, isError flag).
-fn tool_result(id: Option<Value>, result: Result<Value, String>) -> JsonRpcResponse {
+///
+/// With `structured`, object results are also returned as `structuredContent`
+/// alongside the text block, which older clients still read. Text longer
+/// than `max_bytes` is cut, and `_meta` records the full and returned
+/// sizes. A continuation cursor the handler returned is dropped then: it
+/// points past what was cut.
+fn tool_result(
+    id: Option<Value>,
+    result: Result<Value, String>,
+    structured: bool,
+    max_bytes: usize,
+) -> JsonRpcResponse {
     match result {
         Ok(value) => {
-            let text = if value.is_string() {
+            let mut text = if value.is_string() {
                 value.as_str().unwrap().to_string()
             } else {
                 serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string())
             };
-            JsonRpcResponse::success(
-                id,
-                json!({
-                    "content": [{ "type": "text", "text": text }],
-                    "isError": false
-                }),
-            )
+
+            if text.len() > max_bytes {
+                let total = text.len();
+                let mut cut = max_bytes;
+                while !text.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                text.truncate(cut);
+                let meta = json!({ "totalBytes": total, "returnedBytes": cut });
+                return JsonRpcResponse::success(
+                    id,
+                    json!({
+                        "content": [
+                            { "type": "text", "text": text },
+                            {
+                                "type": "text",
+                                "text": format!("[output truncated to {cut} of {total} bytes; narrow the request or lower limit]")
+                            }
+                        ],
+                        "isError": false,
+                        "_meta": { "agentfs/truncated": meta }
+                    }),
+                );
+            }
+
+            let mut body = json!({
+                "content": [{ "type": "text", "text": text }],
+                "isError": false
+            });
+            if structured && value.is_object() {
+                body["structuredContent"] = value;
+            }
+            JsonRpcResponse::success(id, body)
         }
         Err(e) => JsonRpcResponse::success(
             id,
@@ -116,8 +264,40 @@ fn tool_result(id: Option<Value>, result: Result<Value, String>) -> JsonRpcRespo
     }
 }
 
+/// Handle one JSON-RPC message. Returns `None` for notifications, which get
+/// no response.
+pub async fn handle_message(
+    request: JsonRpcRequest,
+    session: &mut Session,
+    db_manager: &mut DbManager,
+) -> Option<JsonRpcResponse> {
+    debug!(method = %request.method, "received request");
+
+    // Notifications (no id) — silently acknowledge
+    request.id.as_ref()?;
+
+    let response = match request.method.as_str() {
+        "initialize" => handle_initialize(request.id, &request.params, session),
+        "ping" => JsonRpcResponse::success(request.id, json!({})),
+        "tools/list" => handle_tools_list(request.id, session, db_manager),
+        "tools/call" => handle_tools_call(request.id, &request.params, session, db_manager).await,
+        "resources/list" => handle_resources_list(request.id, &request.params, db_manager).await,
+        "resources/read" => handle_resources_read(request.id, &request.params, db_manager).await,
+        "prompts/list" => handle_prompts_list(request.id, db_manager).await,
+        "prompts/get" => handle_prompts_get(request.id, &request.params, db_manager).await,
+        _ => JsonRpcResponse::error(
+            request.id,
+            METHOD_NOT_FOUND,
+            format!("method not found: {}", request.method),
+        ),
+    };
+    Some(response)
+}
+
 #[tokio::main]
 async fn main() {
+    let args = Args::parse();
+
     // Tracing to stderr only — stdout is the protocol channel
     tracing_subscriber::fmt()
         .with_writer(std::io::stderr)
@@ -127,15 +307,51 @@ async fn main() {
         )
         .init();
 
-    let mut db_manager = DbManager::new();
+    // Databases given on the command line are opened up front and pinned,
+    // so their files stay listed as resources. A default database that
+    // doesn't exist yet can still be made with agentfs_init.
+    let idle_timeout = (args.idle_timeout > 0).then(|| Duration::from_secs(args.idle_timeout));
+    let mut db_manager = DbManager::new(args.db.clone(), args.allow_admin)
+        .with_limits(args.max_open_dbs, idle_timeout)
+        .with_max_result_bytes(args.max_result_bytes);
+    let default_db = args.db.iter().filter(|p| std::path::Path::new(p).exists());
+    for path in args.dbs.iter().chain(default_db) {
+        if let Err(e) = db_manager.open_pinned(path).await {
+            eprintln!("agentfs-mcp: {e}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(addr) = args.http {
+        let token = match args.token.filter(|t| !t.is_empty()) {
+            Some(token) => token,
+            None => {
+                let token = uuid::Uuid::new_v4().simple().to_string();
+                eprintln!("agentfs-mcp HTTP token: {token}");
+                token
+            }
+        };
+        if let Err(e) = http::serve(addr, db_manager, token).await {
+            eprintln!("agentfs-mcp: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let stdin = std::io::stdin();
-    let reader = stdin.lock();
+    let mut session = Session::default();
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    let mut sweep = db_manager.sweep_interval().map(tokio::time::interval);
 
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => break, // stdin closed
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(l)) => l,
+                _ => break, // stdin closed
+            },
+            _ = tick(&mut sweep) => {
+                db_manager.close_idle().await;
+                continue;
+            }
         };
 
         let line = line.trim().to_string();
@@ -152,33 +368,47 @@ async fn main() {
             }
         };
 
-        debug!(method = %request.method, "received request");
-
-        // Notifications (no id) — silently acknowledge
-        if request.id.is_none() {
-            continue;
+        if let Some(response) = handle_message(request, &mut session, &mut db_manager).await {
+            send_response(&response);
         }
-
-        let response = match request.method.as_str() {
-            "initialize" => handle_initialize(request.id),
-            "tools/list" => handle_tools_list(request.id),
-            "tools/call" => handle_tools_call(request.id, &request.params, &mut db_manager).await,
-            _ => JsonRpcResponse::error(
-                request.id,
-                METHOD_NOT_FOUND,
-                format!("method not found: {}", request.method),
-            ),
-        };
-
-        send_response(&response);
     }
 
     // Graceful shutdown
     db_manager.close_all().await;
 }
 
+/// Wait for the next idle sweep, or forever if idle closing is off.
+async fn tick(sweep: &mut Option<tokio::time::Interval>) {
+    match sweep {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
 fn send_response(response: &JsonRpcResponse) {
     if let Ok(json) = serde_json::to_string(response) {
         println!("{json}");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_result_cuts_without_cursor() {
+        let value = json!({ "entries": vec!["x".repeat(100); 100], "next_cursor": "/z" });
+        let resp = tool_result(Some(json!(1)), Ok(value), true, 4096);
+        let result = resp.result.unwrap();
+        assert_eq!(result["content"][0]["text"].as_str().unwrap().len(), 4096);
+        let meta = &result["_meta"]["agentfs/truncated"];
+        assert_eq!(meta["returnedBytes"], 4096);
+        assert!(meta.get("next_cursor").is_none());
+        assert!(result.get("structuredContent").is_none());
+
+        let small = tool_result(None, Ok(json!({ "ok": true })), true, 4096).result.unwrap();
+        assert_eq!(small["structuredContent"]["ok"], true);
+    }
+}
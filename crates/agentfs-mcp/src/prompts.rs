@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+
+use agentfs_core::skills::{self, SKILL_PREFIX};
+use agentfs_core::AgentFS;
+use serde_json::{json, Value};
+
+use crate::db_manager::DbManager;
+use crate::protocol::{INTERNAL_ERROR, INVALID_PARAMS};
+
+/// The one argument every skill prompt takes: the request the skill is
+/// run with, as after `/<skill>` in the REPL.
+fn arguments() -> Value {
+    json!([{
+        "name": "args",
+        "description": "What to ask of the skill",
+        "required": false
+    }])
+}
+
+/// Skills stored in the open databases, as (database, skill record) pairs.
+/// A skill name in more than one database is taken from the first.
+async fn skills(db_manager: &DbManager) -> Result<Vec<(&AgentFS, Value)>, (i32, String)> {
+    let mut seen = HashSet::new();
+    let mut skills = Vec::new();
+    for (_, db) in db_manager.open_dbs() {
+        let entries = db
+            .kv
+            .list_prefix(SKILL_PREFIX)
+            .await
+            .map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+        for entry in entries {
+            let Ok(record) = serde_json::from_str::<Value>(&entry.value) else {
+                continue;
+            };
+            let Some(name) = record.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if seen.insert(name.to_string()) {
+                skills.push((db, record));
+            }
+        }
+    }
+    skills.sort_by(|(_, a), (_, b)| a["name"].as_str().cmp(&b["name"].as_str()));
+    Ok(skills)
+}
+
+/// List every skill of the open databases as a prompt.
+pub async fn list(db_manager: &DbManager) -> Result<Value, (i32, String)> {
+    let prompts: Vec<Value> = skills(db_manager)
+        .await?
+        .into_iter()
+        .map(|(_, record)| {
+            json!({
+                "name": record["name"],
+                "description": record.get("description").and_then(|v| v.as_str()).unwrap_or(""),
+                "arguments": arguments(),
+            })
+        })
+        .collect();
+    Ok(json!({ "prompts": prompts }))
+}
+
+/// Render skill `name` as a user message, the way infinity-agent runs it:
+/// the skill body, the files bundled under `/skills/<name>/`, then the
+/// request.
+pub async fn get(db_manager: &DbManager, name: &str, args: &Value) -> Result<Value, (i32, String)> {
+    let (db, record) = skills(db_manager)
+        .await?
+        .into_iter()
+        .find(|(_, record)| record["name"].as_str() == Some(name))
+        .ok_or_else(|| (INVALID_PARAMS, format!("unknown prompt: {name}")))?;
+
+    let body = record.get("body").and_then(|v| v.as_str()).unwrap_or("");
+    let resources = skills::resources(db, name).await;
+    let request = args.get("args").and_then(|v| v.as_str()).unwrap_or("");
+    let text = skills::prompt(name, body, &resources, "agentfs_read_file", request);
+
+    let description = record.get("description").and_then(|v| v.as_str()).unwrap_or("");
+    Ok(json!({
+        "description": description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": text }
+        }]
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentfs_core::config::AgentFSConfig;
+
+    #[tokio::test]
+    async fn test_skill_prompts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let db = AgentFS::create(AgentFSConfig::builder(&path).checkpoint_interval_secs(0).build())
+            .await
+            .unwrap();
+        let record = json!({ "name": "review", "description": "Code review", "body": "Review the diff." });
+        db.kv.set(&format!("{SKILL_PREFIX}review"), &record.to_string()).await.unwrap();
+        db.fs.write_file("/skills/review/checklist.md", b"- tests").await.unwrap();
+        db.close().await.unwrap();
+
+        let mut db_manager = DbManager::new(None, false);
+        db_manager.get_or_open(path.to_str().unwrap()).await.unwrap();
+
+        let listed = list(&db_manager).await.unwrap();
+        assert_eq!(listed["prompts"][0]["name"], "review");
+        assert_eq!(listed["prompts"][0]["description"], "Code review");
+
+        let got = get(&db_manager, "review", &json!({})).await.unwrap();
+        let text = got["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.starts_with("<skill>\nReview the diff.\n</skill>"));
+        assert!(text.contains("- /skills/review/checklist.md\n"));
+        assert!(text.ends_with("User request: Run the /review skill"));
+
+        let got = get(&db_manager, "review", &json!({ "args": "the parser" })).await.unwrap();
+        let text = got["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.ends_with("User request: the parser"));
+
+        let err = get(&db_manager, "deploy", &json!({})).await.unwrap_err();
+        assert_eq!(err.0, INVALID_PARAMS);
+    }
+}
@@ -33,15 +33,54 @@ pub struct JsonRpcError {
     pub data: Option<Value>,
 }
 
+/// MCP revisions this server speaks, newest first.
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// First revision with tool annotations.
+pub const ANNOTATIONS_SINCE: &str = "2025-03-26";
+
+/// First revision with structured tool output and `title` fields.
+pub const STRUCTURED_OUTPUT_SINCE: &str = "2025-06-18";
+
+/// Pick the revision to speak: the client's if we support it, else our newest.
+pub fn negotiate_version(requested: Option<&str>) -> &'static str {
+    requested
+        .and_then(|r| PROTOCOL_VERSIONS.iter().copied().find(|v| *v == r))
+        .unwrap_or(PROTOCOL_VERSIONS[0])
+}
+
+/// Per-connection protocol state.
+pub struct Session {
+    pub protocol_version: &'static str,
+}
+
+impl Default for Session {
+    /// Until `initialize` says otherwise, assume the oldest revision.
+    fn default() -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSIONS[PROTOCOL_VERSIONS.len() - 1],
+        }
+    }
+}
+
+impl Session {
+    /// Whether the negotiated revision is `since` or newer. Revisions are
+    /// dates, so they order as strings.
+    pub fn supports(&self, since: &str) -> bool {
+        self.protocol_version >= since
+    }
+}
+
 // Standard error codes
 pub const PARSE_ERROR: i32 = -32700;
-#[allow(dead_code)]
 pub const INVALID_REQUEST: i32 = -32600;
 pub const METHOD_NOT_FOUND: i32 = -32601;
-#[allow(dead_code)]
 pub const INVALID_PARAMS: i32 = -32602;
 pub const INTERNAL_ERROR: i32 = -32603;
 
+// MCP-specific error codes
+pub const RESOURCE_NOT_FOUND: i32 = -32002;
+
 impl JsonRpcResponse {
     pub fn success(id: Option<Value>, result: Value) -> Self {
         Self {
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use agentfs_core::filesystem::{EntryKind, SearchFilter};
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::db_manager::DbManager;
+use crate::protocol::{INTERNAL_ERROR, INVALID_PARAMS, RESOURCE_NOT_FOUND};
+
+const URI_SCHEME: &str = "agentfs://";
+
+/// Resources returned per `resources/list` page.
+const PAGE_SIZE: usize = 100;
+
+/// Name used as the URI authority for a database: its file name.
+fn db_name(db_path: &Path) -> String {
+    db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| db_path.display().to_string())
+}
+
+/// `agentfs://<db>/<path>` for a file in an open database.
+fn resource_uri(db: &str, path: &str) -> String {
+    format!("{URI_SCHEME}{db}{path}")
+}
+
+/// Split a resource URI into (database name, absolute file path).
+fn parse_uri(uri: &str) -> Option<(&str, String)> {
+    let rest = uri.strip_prefix(URI_SCHEME)?;
+    let (db, path) = rest.split_once('/')?;
+    if db.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((db, format!("/{path}")))
+}
+
+/// List files of every open database as resources, `PAGE_SIZE` at a time.
+///
+/// The cursor is the offset of the next page, as a string.
+pub async fn list(db_manager: &DbManager, cursor: Option<&str>) -> Result<Value, (i32, String)> {
+    let offset: usize = match cursor {
+        Some(c) => c
+            .parse()
+            .map_err(|_| (INVALID_PARAMS, format!("invalid cursor: {c}")))?,
+        None => 0,
+    };
+
+    let mut resources = Vec::new();
+    for (db_path, db) in db_manager.open_dbs() {
+        let name = db_name(db_path);
+        let files_only = SearchFilter {
+            kind: Some(EntryKind::File),
+            ..Default::default()
+        };
+        let files = db
+            .fs
+            .find(&files_only)
+            .await
+            .map_err(|e| (INTERNAL_ERROR, e.to_string()))?;
+
+        for f in files {
+            resources.push(json!({
+                "uri": resource_uri(&name, &f.path),
+                "name": f.path,
+                "description": format!("File in {name}"),
+                "mimeType": mime_type(&f.path),
+                "size": f.size,
+            }));
+        }
+    }
+
+    let next = offset + PAGE_SIZE;
+    let page: Vec<Value> = resources.iter().skip(offset).take(PAGE_SIZE).cloned().collect();
+    let mut result = json!({ "resources": page });
+    if next < resources.len() {
+        result["nextCursor"] = json!(next.to_string());
+    }
+    Ok(result)
+}
+
+/// Read one resource. Text files are returned as `text`, anything else as
+/// base64 `blob`.
+pub async fn read(db_manager: &DbManager, uri: &str) -> Result<Value, (i32, String)> {
+    let (name, path) =
+        parse_uri(uri).ok_or_else(|| (INVALID_PARAMS, format!("invalid resource URI: {uri}")))?;
+    let db = db_manager
+        .open_dbs()
+        .into_iter()
+        .find(|(db_path, _)| db_name(db_path) == name)
+        .map(|(_, db)| db)
+        .ok_or_else(|| (RESOURCE_NOT_FOUND, format!("unknown database: {name}")))?;
+
+    let data = db
+        .fs
+        .read_file(&path)
+        .await
+        .map_err(|e| (RESOURCE_NOT_FOUND, e.to_string()))?;
+
+    let mime = mime_type(&path);
+    let textual = mime.starts_with("text/") || mime == "application/json";
+    let content = match String::from_utf8(data) {
+        Ok(text) if textual => json!({ "uri": uri, "mimeType": mime, "text": text }),
+        Ok(text) => blob(uri, mime, text.as_bytes()),
+        // A guessed text type was wrong; don't label raw bytes as text.
+        Err(e) if textual => blob(uri, "application/octet-stream", e.as_bytes()),
+        Err(e) => blob(uri, mime, e.as_bytes()),
+    };
+    Ok(json!({ "contents": [content] }))
+}
+
+fn blob(uri: &str, mime: &str, data: &[u8]) -> Value {
+    json!({
+        "uri": uri,
+        "mimeType": mime,
+        "blob": base64::engine::general_purpose::STANDARD.encode(data),
+    })
+}
+
+/// Guess a MIME type from the file extension. Unknown extensions are
+/// treated as plain text, since agent workspaces are mostly source and notes.
+fn mime_type(path: &str) -> &'static str {
+    let ext = path
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("md" | "markdown") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("csv") => "text/csv",
+        Some("js" | "mjs") => "text/javascript",
+        Some("json" | "jsonl") => "application/json",
+        Some("xml") => "text/xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        Some("gz") => "application/gzip",
+        Some("wasm") => "application/wasm",
+        Some("db" | "sqlite" | "bin" | "exe" | "so" | "dylib") => "application/octet-stream",
+        _ => "text/plain",
+    }
+}
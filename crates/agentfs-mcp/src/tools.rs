@@ -1,8 +1,26 @@
 use serde_json::{json, Value};
 
+use crate::protocol::{Session, ANNOTATIONS_SINCE, STRUCTURED_OUTPUT_SINCE};
+
+/// Maintenance tools only offered when the server runs with `--allow-admin`.
+const ADMIN_TOOLS: &[&str] = &[
+    "agentfs_snapshot",
+    "agentfs_gc",
+    "agentfs_integrity_check",
+    "agentfs_databases_list",
+];
+
+/// Whether `name` is an admin-gated tool.
+pub fn is_admin(name: &str) -> bool {
+    ADMIN_TOOLS.contains(&name)
+}
+
 /// Return the list of all tool definitions for tools/list.
-pub fn tool_definitions() -> Vec<Value> {
-    vec![
+///
+/// With a `default_db`, the `db` parameter becomes optional everywhere.
+/// Admin tools are left out unless `allow_admin` is set.
+pub fn tool_definitions(default_db: Option<&str>, allow_admin: bool) -> Vec<Value> {
+    let mut tools = vec![
         tool("agentfs_init", "Create a new AgentFS database. Creates the SQLite file and initializes the schema.", json!({
             "type": "object",
             "properties": {
@@ -10,11 +28,13 @@ pub fn tool_definitions() -> Vec<Value> {
             },
             "required": ["path"]
         })),
-        tool("agentfs_read_file", "Read the contents of a file. Returns the file content as UTF-8 text.", json!({
+        tool("agentfs_read_file", "Read a file as UTF-8 text, a page at a time. If truncated is true, call again with offset set to next_offset to continue.", json!({
             "type": "object",
             "properties": {
                 "db": { "type": "string", "description": "Path to the database file" },
-                "path": { "type": "string", "description": "File path within the filesystem (e.g., /docs/readme.md)" }
+                "path": { "type": "string", "description": "File path within the filesystem (e.g., /docs/readme.md)" },
+                "offset": { "type": "integer", "description": "Byte offset to start reading at (default: 0)", "default": 0 },
+                "limit": { "type": "integer", "description": "Maximum bytes to return (default: 65536, max: 1048576)", "default": 65536 }
             },
             "required": ["db", "path"]
         })),
@@ -44,11 +64,13 @@ pub fn tool_definitions() -> Vec<Value> {
             },
             "required": ["db", "path"]
         })),
-        tool("agentfs_list_dir", "List directory contents. Returns entries with name, inode, and type.", json!({
+        tool("agentfs_list_dir", "List directory contents in name order. Returns entries with name, inode, and type. Paginated: pass next_cursor back as cursor for the next page.", json!({
             "type": "object",
             "properties": {
                 "db": { "type": "string", "description": "Path to the database file" },
-                "path": { "type": "string", "description": "Directory path (default: /)", "default": "/" }
+                "path": { "type": "string", "description": "Directory path (default: /)", "default": "/" },
+                "cursor": { "type": "string", "description": "next_cursor from the previous page" },
+                "limit": { "type": "integer", "description": "Entries per page (default: 500, max: 5000)", "default": 500 }
             },
             "required": ["db"]
         })),
@@ -68,11 +90,14 @@ pub fn tool_definitions() -> Vec<Value> {
             },
             "required": ["db", "path"]
         })),
-        tool("agentfs_tree", "Get a recursive tree listing of the filesystem.", json!({
+        tool("agentfs_tree", "Get a recursive listing as a flat, depth-first list of entries with path, type, size (total bytes for directories) and depth. Paginated: pass next_cursor back as cursor for the next page.", json!({
             "type": "object",
             "properties": {
                 "db": { "type": "string", "description": "Path to the database file" },
-                "path": { "type": "string", "description": "Root path for the tree (default: /)", "default": "/" }
+                "path": { "type": "string", "description": "Root path for the tree (default: /)", "default": "/" },
+                "max_depth": { "type": "integer", "description": "Levels below the root to descend (default: unlimited); directories cut off here report hidden_children" },
+                "cursor": { "type": "string", "description": "next_cursor from the previous page" },
+                "limit": { "type": "integer", "description": "Entries per page (default: 500, max: 5000)", "default": 500 }
             },
             "required": ["db"]
         })),
@@ -93,11 +118,29 @@ pub fn tool_definitions() -> Vec<Value> {
             },
             "required": ["db", "path"]
         })),
-        tool("agentfs_search", "Search for files/directories matching a glob pattern (* and ? supported).", json!({
+        tool("agentfs_search", "Find files/directories by name or path glob, type and size. All filters are optional and combined; results are sorted by path. Paginated: pass next_cursor back as cursor for the next page.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "pattern": { "type": "string", "description": "Glob on the file name (e.g., *.rs, config*)" },
+                "path": { "type": "string", "description": "Glob on the full path; * stays within a directory, ** spans directories (e.g., /src/**/*.rs, docs/*.md)" },
+                "type": { "type": "string", "enum": ["file", "dir"], "description": "Only files or only directories" },
+                "min_size": { "type": "integer", "description": "Minimum size in bytes" },
+                "max_size": { "type": "integer", "description": "Maximum size in bytes" },
+                "cursor": { "type": "string", "description": "next_cursor from the previous page" },
+                "limit": { "type": "integer", "description": "Results per page (default: 200)", "default": 200 }
+            },
+            "required": ["db"]
+        })),
+        tool("agentfs_grep", "Search file contents for a regex, returning matching lines with paths and line numbers. Binary files are skipped.", json!({
             "type": "object",
             "properties": {
                 "db": { "type": "string", "description": "Path to the database file" },
-                "pattern": { "type": "string", "description": "Glob pattern to match (e.g., *.rs, config*)" }
+                "pattern": { "type": "string", "description": "Regular expression (Rust regex syntax), or a literal string with fixed_strings" },
+                "path": { "type": "string", "description": "Only search files whose path matches this glob (e.g., *.md, /src/**)" },
+                "ignore_case": { "type": "boolean", "description": "Case-insensitive match (default: false)", "default": false },
+                "fixed_strings": { "type": "boolean", "description": "Treat pattern as a literal string (default: false)", "default": false },
+                "max_results": { "type": "integer", "description": "Maximum matching lines to return (default: 100)", "default": 100 }
             },
             "required": ["db", "pattern"]
         })),
@@ -126,14 +169,25 @@ pub fn tool_definitions() -> Vec<Value> {
             },
             "required": ["db", "key"]
         })),
-        tool("agentfs_kv_list", "List key-value pairs with an optional prefix filter.", json!({
+        tool("agentfs_kv_list", "List key-value pairs in key order, optionally under a prefix. Paginated: pass next_cursor back as cursor for the next page.", json!({
             "type": "object",
             "properties": {
                 "db": { "type": "string", "description": "Path to the database file" },
-                "prefix": { "type": "string", "description": "Optional key prefix to filter by", "default": "" }
+                "prefix": { "type": "string", "description": "Optional key prefix to filter by (matched literally)", "default": "" },
+                "cursor": { "type": "string", "description": "next_cursor from the previous page" },
+                "limit": { "type": "integer", "description": "Entries per page (default: 100, max: 1000)", "default": 100 },
+                "keys_only": { "type": "boolean", "description": "Return only keys, not values (default: false)", "default": false }
             },
             "required": ["db"]
         })),
+        tool("agentfs_kv_delete_prefix", "Delete every key starting with a prefix. Returns the number of keys deleted.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "prefix": { "type": "string", "description": "Non-empty key prefix (matched literally, e.g., scratch:)" }
+            },
+            "required": ["db", "prefix"]
+        })),
         tool("agentfs_info", "Get database stats: schema version, file counts, sizes, token usage, session counts.", json!({
             "type": "object",
             "properties": {
@@ -151,7 +205,8 @@ pub fn tool_definitions() -> Vec<Value> {
                 "session_id": { "type": "string", "description": "Optional session ID" },
                 "cache_read_tokens": { "type": "integer", "description": "Cache read tokens (default: 0)", "default": 0 },
                 "cache_write_tokens": { "type": "integer", "description": "Cache write tokens (default: 0)", "default": 0 },
-                "cost_microcents": { "type": "integer", "description": "Cost in microcents (default: 0)", "default": 0 }
+                "cost_microcents": { "type": "integer", "description": "Cost in microcents (default: 0)", "default": 0 },
+                "purpose": { "type": "string", "description": "What the tokens were spent on, e.g. reflection (default: agent turns)" }
             },
             "required": ["db", "model", "input_tokens", "output_tokens"]
         })),
@@ -175,7 +230,179 @@ pub fn tool_definitions() -> Vec<Value> {
             },
             "required": ["db", "session_id"]
         })),
-    ]
+        tool("agentfs_sessions_list", "List recent agent sessions, newest first.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "status": { "type": "string", "description": "Only sessions with this status (e.g., active, completed, failed)" },
+                "limit": { "type": "integer", "description": "Maximum number of sessions (default: 20)", "default": 20 }
+            },
+            "required": ["db"]
+        })),
+        tool("agentfs_sessions_get", "Get one session with its token usage and cost.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "session_id": { "type": "string", "description": "Session ID to look up" }
+            },
+            "required": ["db", "session_id"]
+        })),
+        tool("agentfs_analytics_summary", "Get total token usage and cost, with a per-model breakdown.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "since": { "type": "string", "description": "Only usage recorded at or after this ISO timestamp (e.g., 2026-01-31T00:00)" }
+            },
+            "required": ["db"]
+        })),
+        tool("agentfs_events_query", "Query the event log, oldest first. All filters are optional and combined.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "type": { "type": "string", "description": "Event type, or a prefix ending in * (e.g., tool:*)" },
+                "session_id": { "type": "string", "description": "Only events from this session" },
+                "severity": { "type": "string", "description": "Minimum severity: info, warn, or error" },
+                "since": { "type": "string", "description": "Only events at or after this time (2026-01-31, 2026-01-31T12:00, 15m, 2h, 7d)" },
+                "until": { "type": "string", "description": "Only events before this time (same formats as since)" },
+                "after_id": { "type": "integer", "description": "Only events with a larger ID, to page forward" },
+                "limit": { "type": "integer", "description": "Return the latest N matches (default: 50)", "default": 50 }
+            },
+            "required": ["db"]
+        })),
+        tool("agentfs_snapshot", "Back up the database into its backup directory (<db>.backups), the same store the infinity backup command uses.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "incremental": { "type": "boolean", "description": "Store only pages changed since the latest backup (default: false)", "default": false }
+            },
+            "required": ["db"]
+        })),
+        tool("agentfs_gc", "Delete orphaned inodes, chunks and symlinks, then checkpoint the WAL.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" }
+            },
+            "required": ["db"]
+        })),
+        tool("agentfs_integrity_check", "Verify chunk checksums and SQLite integrity for the whole database, or for one file.", json!({
+            "type": "object",
+            "properties": {
+                "db": { "type": "string", "description": "Path to the database file" },
+                "path": { "type": "string", "description": "Only verify this file" }
+            },
+            "required": ["db"]
+        })),
+        tool("agentfs_databases_list", "List the databases this server has open, with idle time and whether each is pinned or the default, plus the open-database limits.", json!({
+            "type": "object",
+            "properties": {}
+        })),
+    ];
+
+    if !allow_admin {
+        tools.retain(|t| !t["name"].as_str().is_some_and(is_admin));
+    }
+
+    for t in &mut tools {
+        let (title, effect) = describe(t["name"].as_str().unwrap_or_default());
+        t["title"] = json!(title);
+        t["annotations"] = effect.annotations(title);
+    }
+
+    if let Some(default_db) = default_db {
+        for t in &mut tools {
+            let schema = &mut t["inputSchema"];
+            if let Some(db) = schema["properties"].get_mut("db") {
+                db["description"] = json!(format!("Path to the database file (default: {default_db})"));
+            }
+            if let Some(required) = schema["required"].as_array_mut() {
+                required.retain(|r| r != "db");
+            }
+        }
+    }
+    tools
+}
+
+/// How a tool affects state, reported to hosts as annotation hints.
+#[derive(Clone, Copy)]
+enum Effect {
+    ReadOnly,
+    /// Changes state without overwriting or removing data a client can see.
+    NonDestructive { idempotent: bool },
+    /// May overwrite or delete existing data.
+    Destructive { idempotent: bool },
+}
+
+impl Effect {
+    fn annotations(self, title: &str) -> Value {
+        let (read_only, destructive, idempotent) = match self {
+            Effect::ReadOnly => (true, false, true),
+            Effect::NonDestructive { idempotent } => (false, false, idempotent),
+            Effect::Destructive { idempotent } => (false, true, idempotent),
+        };
+        json!({
+            "title": title,
+            "readOnlyHint": read_only,
+            "destructiveHint": destructive,
+            "idempotentHint": idempotent,
+            // Everything stays inside the local database.
+            "openWorldHint": false
+        })
+    }
+}
+
+/// Display title and effect of each tool.
+fn describe(name: &str) -> (&'static str, Effect) {
+    use Effect::*;
+    match name {
+        "agentfs_init" => ("Create database", NonDestructive { idempotent: false }),
+        "agentfs_read_file" => ("Read file", ReadOnly),
+        "agentfs_write_file" => ("Write file", Destructive { idempotent: true }),
+        "agentfs_append_file" => ("Append to file", NonDestructive { idempotent: false }),
+        "agentfs_delete_file" => ("Delete file", Destructive { idempotent: true }),
+        "agentfs_list_dir" => ("List directory", ReadOnly),
+        "agentfs_mkdir" => ("Create directory", NonDestructive { idempotent: true }),
+        "agentfs_stat" => ("Stat path", ReadOnly),
+        "agentfs_tree" => ("Directory tree", ReadOnly),
+        "agentfs_rename" => ("Rename or move", Destructive { idempotent: false }),
+        "agentfs_remove_tree" => ("Remove directory tree", Destructive { idempotent: true }),
+        "agentfs_search" => ("Find files", ReadOnly),
+        "agentfs_grep" => ("Search file contents", ReadOnly),
+        "agentfs_kv_get" => ("Get key", ReadOnly),
+        "agentfs_kv_set" => ("Set key", Destructive { idempotent: true }),
+        "agentfs_kv_delete" => ("Delete key", Destructive { idempotent: true }),
+        "agentfs_kv_list" => ("List keys", ReadOnly),
+        "agentfs_kv_delete_prefix" => ("Delete keys by prefix", Destructive { idempotent: true }),
+        "agentfs_info" => ("Database info", ReadOnly),
+        "agentfs_record_usage" => ("Record token usage", NonDestructive { idempotent: false }),
+        "agentfs_session_start" => ("Start session", NonDestructive { idempotent: false }),
+        "agentfs_session_end" => ("End session", NonDestructive { idempotent: true }),
+        "agentfs_sessions_list" => ("List sessions", ReadOnly),
+        "agentfs_sessions_get" => ("Get session", ReadOnly),
+        "agentfs_analytics_summary" => ("Usage summary", ReadOnly),
+        "agentfs_events_query" => ("Query events", ReadOnly),
+        "agentfs_snapshot" => ("Snapshot database", NonDestructive { idempotent: false }),
+        // Only removes unreachable data.
+        "agentfs_gc" => ("Collect garbage", NonDestructive { idempotent: true }),
+        "agentfs_integrity_check" => ("Check integrity", ReadOnly),
+        "agentfs_databases_list" => ("List open databases", ReadOnly),
+        // Unknown tools get the most cautious hints.
+        _ => ("AgentFS tool", Destructive { idempotent: false }),
+    }
+}
+
+/// Drop tool fields the session's protocol revision doesn't define, so
+/// older clients see the shape they expect.
+pub fn shape_for(tools: &mut [Value], session: &Session) {
+    for t in tools {
+        let Some(t) = t.as_object_mut() else { continue };
+        if !session.supports(ANNOTATIONS_SINCE) {
+            t.remove("annotations");
+        }
+        if !session.supports(STRUCTURED_OUTPUT_SINCE) {
+            t.remove("title");
+            t.remove("outputSchema");
+        }
+    }
 }
 
 fn tool(name: &str, description: &str, input_schema: Value) -> Value {